@@ -0,0 +1,10 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="files" tilewidth="32" tileheight="32" tilecount="1" columns="0">
+ <properties>
+  <property name="sheet" type="file" value="tilesheet.png"/>
+ </properties>
+ <grid orientation="orthogonal" width="1" height="1"/>
+ <tile id="0">
+  <image source="tilesheet.png" width="448" height="192"/>
+ </tile>
+</tileset>
@@ -0,0 +1,17 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="probability" tilewidth="32" tileheight="32" tilecount="4" columns="4">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="0" probability="0"/>
+ <tile id="1" probability="3"/>
+ <tile id="3" probability="0"/>
+ <wangsets>
+  <wangset name="terrain" type="corner" tile="-1">
+   <wangcolor name="grass" color="#00ff00" tile="-1" probability="1"/>
+   <wangcolor name="sand" color="#ffff00" tile="-1" probability="0.5"/>
+   <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+   <wangtile tileid="1" wangid="0,1,0,1,0,1,0,1"/>
+   <wangtile tileid="2" wangid="0,1,0,2,0,1,0,1"/>
+   <wangtile tileid="3" wangid="0,2,0,2,0,2,0,2"/>
+  </wangset>
+ </wangsets>
+</tileset>
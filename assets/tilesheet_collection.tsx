@@ -0,0 +1,19 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="collection" tilewidth="4" tileheight="3" tilecount="3" columns="0">
+ <grid orientation="orthogonal" width="1" height="1"/>
+ <tile id="0">
+  <image format="png" width="4" height="2">
+   <data encoding="base64">iVBORw0KGgoAAAANSUhEUgAAAAQAAAACCAYAAAB/qH1jAAAAEklEQVR4nGP4z8DwHxkzoAsAAA8hD/EEN8afAAAAAElFTkSuQmCC</data>
+  </image>
+ </tile>
+ <tile id="2">
+  <image format="png" width="2" height="3">
+   <data encoding="base64">iVBORw0KGgoAAAANSUhEUgAAAAIAAAADCAYAAAC56t6BAAAADklEQVR4nGNg+A+FGAwAm38L9SgUsAQAAAAASUVORK5CYII=</data>
+  </image>
+ </tile>
+ <tile id="5">
+  <image format="png" width="3" height="3">
+   <data encoding="base64">iVBORw0KGgoAAAANSUhEUgAAAAMAAAADCAYAAABWKLW/AAAAEElEQVR4nGNgYPj/H4FxcQBL6RHvAs1oSAAAAABJRU5ErkJggg==</data>
+  </image>
+ </tile>
+</tileset>
@@ -0,0 +1,5 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="transformations" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <transformations hflip="1" vflip="1" rotate="0" preferuntransformed="1"/>
+</tileset>
@@ -19,7 +19,8 @@ impl MapHandler {
         let mut tileset_image_cache = HashMap::new();
         for ts in map.tilesets().iter() {
             if let Some(image) = &ts.image {
-                let img = graphics::Image::from_path(ctx, &image.source)?;
+                let source = image.source.as_path().expect("tileset image is a file");
+                let img = graphics::Image::from_path(ctx, source)?;
 
                 tileset_image_cache.insert(ts.name.clone(), img);
             }
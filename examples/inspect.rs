@@ -0,0 +1,196 @@
+//! Loads a map in lenient mode and prints its structure, statistics, validation findings and the
+//! features used by its files that the crate doesn't support. Validation findings come from
+//! [`Map::validate`], from the problems recovered from while loading and from checking that the
+//! images the map uses exist, and unsupported features are the unknown elements and attributes
+//! reported as [warnings](tiled::ParseWarningKind::UnknownElement) while parsing.
+//!
+//! Usage: `cargo run --example tiled-inspect --features inspect -- path/to/map.tmx`
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use tiled::{
+    Image, Layer, LayerTile, LayerType, Loader, LoaderOptions, Map, MapVisitor, Object,
+    ParseWarningKind, TileLayer, Tileset,
+};
+
+#[derive(Default)]
+struct Inspector {
+    depth: usize,
+    layers: BTreeMap<&'static str, usize>,
+    tiles: usize,
+    tiles_per_tileset: BTreeMap<String, usize>,
+    objects: usize,
+    findings: Vec<String>,
+}
+
+impl Inspector {
+    fn indent(&self) -> String {
+        "  ".repeat(self.depth + 1)
+    }
+}
+
+impl<'map> MapVisitor<'map> for Inspector {
+    fn visit_map(&mut self, map: &'map Map) {
+        println!(
+            "Map: {}x{} tiles of {}x{} pixels, {:?}{}",
+            map.width,
+            map.height,
+            map.tile_width,
+            map.tile_height,
+            map.orientation,
+            if map.infinite() { ", infinite" } else { "" }
+        );
+    }
+
+    fn visit_tileset(&mut self, index: usize, tileset: &'map Tileset) {
+        println!(
+            "  Tileset #{} \"{}\": {} tiles of {}x{} pixels",
+            index, tileset.name, tileset.tilecount, tileset.tile_width, tileset.tile_height
+        );
+        for (_, tile) in tileset.tiles() {
+            if let Some(image) = &tile.image {
+                check_image(image, &mut self.findings);
+            }
+        }
+        if let Some(image) = &tileset.image {
+            check_image(image, &mut self.findings);
+        }
+    }
+
+    fn visit_layer(&mut self, layer: Layer<'map>) {
+        let kind = match layer.layer_type() {
+            LayerType::Tiles(TileLayer::Finite(_)) => "finite tile layer",
+            LayerType::Tiles(TileLayer::Infinite(_)) => "infinite tile layer",
+            LayerType::Objects(_) => "object layer",
+            LayerType::Image(_) => "image layer",
+            LayerType::Group(_) => "group layer",
+        };
+        *self.layers.entry(kind).or_default() += 1;
+        println!(
+            "{}Layer #{} \"{}\" ({}){}",
+            self.indent(),
+            layer.id(),
+            layer.name,
+            kind,
+            if layer.visible { "" } else { ", hidden" }
+        );
+
+        if let LayerType::Image(image_layer) = layer.layer_type() {
+            if let Some(image) = &image_layer.image {
+                check_image(image, &mut self.findings);
+            }
+        }
+    }
+
+    fn enter_group(&mut self, _layer: Layer<'map>) {
+        self.depth += 1;
+    }
+
+    fn leave_group(&mut self, _layer: Layer<'map>) {
+        self.depth -= 1;
+    }
+
+    fn visit_tile(&mut self, _layer: Layer<'map>, _x: i32, _y: i32, tile: LayerTile<'map>) {
+        self.tiles += 1;
+        *self
+            .tiles_per_tileset
+            .entry(tile.get_tileset().name.clone())
+            .or_default() += 1;
+    }
+
+    fn visit_object(&mut self, _layer: Layer<'map>, _object: Object<'map>) {
+        self.objects += 1;
+    }
+}
+
+fn check_image(image: &Image, findings: &mut Vec<String>) {
+    let Some(source) = image.source.as_path() else {
+        return;
+    };
+    if !source.exists() {
+        let finding = format!("image \"{}\" does not exist", source.display());
+        if !findings.contains(&finding) {
+            findings.push(finding);
+        }
+    }
+}
+
+fn main() {
+    let path = std::env::args_os().nth(1).map_or_else(
+        || {
+            PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap())
+                .join("assets/tiled_group_layers.tmx")
+        },
+        PathBuf::from,
+    );
+
+    let mut loader = Loader::new();
+    loader.set_options(LoaderOptions {
+        lenient: true,
+        ..Default::default()
+    });
+    let map = match loader.load_tmx_map(&path) {
+        Ok(map) => map,
+        Err(err) => {
+            eprintln!("Could not load \"{}\": {}", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+
+    println!("== Structure ==");
+    let mut inspector = Inspector::default();
+    map.accept(&mut inspector);
+
+    println!("\n== Statistics ==");
+    println!("Tilesets: {}", map.tilesets().len());
+    for (kind, count) in &inspector.layers {
+        println!("{}s: {}", kind, count);
+    }
+    println!("Tiles: {}", inspector.tiles);
+    for (tileset, count) in &inspector.tiles_per_tileset {
+        println!("  from \"{}\": {}", tileset, count);
+    }
+    println!("Objects: {}", inspector.objects);
+
+    println!("\n== Validation ==");
+    let mut findings: Vec<String> = map.validate().iter().map(ToString::to_string).collect();
+    findings.extend(
+        map.diagnostics()
+            .iter()
+            .map(|diagnostic| format!("{}: {}", diagnostic.path.display(), diagnostic.message)),
+    );
+    findings.append(&mut inspector.findings);
+    if findings.is_empty() {
+        println!("No issues found");
+    }
+    for finding in &findings {
+        println!("- {}", finding);
+    }
+
+    println!("\n== Unsupported features ==");
+    let mut unsupported = Vec::new();
+    for warning in loader.take_warnings() {
+        let feature = match &warning.kind {
+            ParseWarningKind::UnknownElement { name, parent } if parent.is_empty() => {
+                format!("root element <{}>", name)
+            }
+            ParseWarningKind::UnknownElement { name, parent } => {
+                format!("element <{}> in <{}>", name, parent)
+            }
+            ParseWarningKind::UnknownAttribute { element, name } => {
+                format!("attribute '{}' of <{}>", name, element)
+            }
+            _ => continue,
+        };
+        let feature = format!("{} ({})", feature, warning.path.display());
+        if !unsupported.contains(&feature) {
+            unsupported.push(feature);
+        }
+    }
+    if unsupported.is_empty() {
+        println!("None found");
+    }
+    for feature in &unsupported {
+        println!("- {}", feature);
+    }
+}
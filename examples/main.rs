@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use tiled::Loader;
+use tiled::{ImageSource, Loader};
 
 fn main() {
     let mut loader = Loader::new();
@@ -34,8 +34,12 @@ fn main() {
                 println!(
                     "Image layer with {}",
                     match &layer.image {
-                        Some(img) =>
-                            format!("an image with source = {}", img.source.to_string_lossy()),
+                        Some(img) => match &img.source {
+                            ImageSource::Path(source) =>
+                                format!("an image with source = {}", source.to_string_lossy()),
+                            ImageSource::Data(data, _) =>
+                                format!("an embedded image of {} bytes", data.len()),
+                        },
                         None => "no image".to_owned(),
                     }
                 )
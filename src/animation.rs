@@ -1,5 +1,7 @@
 //! Structures related to tile animations.
 
+use std::time::Duration;
+
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
@@ -13,6 +15,7 @@ use crate::{
 /// [frame]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-frame
 /// [TMX tile animation]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#animation
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frame {
     /// The local ID of a tile within the parent tileset.
     pub tile_id: u32,
@@ -43,3 +46,116 @@ pub(crate) async fn parse_animation<R: Reader>(parser: &mut Parser<R>) -> Result
     });
     Ok(animation)
 }
+
+/// Plays back a tile animation, keeping track of which frame should be displayed as time passes.
+///
+/// Animations loop, and each frame is displayed for its own [`duration`](Frame::duration).
+/// Frames with a duration of zero are skipped.
+///
+/// ## Example
+/// ```
+/// use std::time::Duration;
+/// use tiled::{AnimationState, Frame};
+///
+/// let mut state = AnimationState::new(vec![
+///     Frame { tile_id: 4, duration: 100 },
+///     Frame { tile_id: 5, duration: 300 },
+/// ]);
+/// assert_eq!(state.current_tile_id(), Some(4));
+///
+/// state.update(Duration::from_millis(150));
+/// assert_eq!(state.current_tile_id(), Some(5));
+///
+/// // 400ms later, the animation has looped back to the same point
+/// state.update(Duration::from_millis(400));
+/// assert_eq!(state.current_tile_id(), Some(5));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnimationState {
+    frames: Vec<Frame>,
+    index: usize,
+    elapsed: Duration,
+}
+
+impl AnimationState {
+    /// Creates a state that starts at the beginning of the first frame of the animation given,
+    /// which is usually a tile's [`animation`](crate::TileData::animation).
+    pub fn new(frames: Vec<Frame>) -> Self {
+        let mut state = Self {
+            frames,
+            index: 0,
+            elapsed: Duration::ZERO,
+        };
+        state.skip_empty_frames();
+        state
+    }
+
+    /// Returns the frames of the animation.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Advances the animation by the time given.
+    pub fn update(&mut self, delta: Duration) {
+        let total = self.total_duration();
+        if total.is_zero() {
+            return;
+        }
+        self.elapsed += delta;
+        // Skip whole loops at once so that large deltas don't step through every frame.
+        let remaining = self.frame_duration(self.index);
+        if self.elapsed >= remaining + total {
+            let loops = (self.elapsed - remaining).as_nanos() / total.as_nanos();
+            self.elapsed -= Duration::from_nanos((loops * total.as_nanos()) as u64);
+        }
+        while self.elapsed >= self.frame_duration(self.index) {
+            self.elapsed -= self.frame_duration(self.index);
+            self.index = (self.index + 1) % self.frames.len();
+        }
+    }
+
+    /// Goes back to the beginning of the first frame.
+    pub fn reset(&mut self) {
+        self.index = 0;
+        self.elapsed = Duration::ZERO;
+        self.skip_empty_frames();
+    }
+
+    /// Returns the index of the frame currently displayed within [`frames()`](Self::frames).
+    pub fn frame_index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the frame currently displayed, or [`None`] if the animation has no frames.
+    pub fn current_frame(&self) -> Option<&Frame> {
+        self.frames.get(self.index)
+    }
+
+    /// Returns the local ID of the tile currently displayed, or [`None`] if the animation has no
+    /// frames.
+    pub fn current_tile_id(&self) -> Option<u32> {
+        self.current_frame().map(|frame| frame.tile_id)
+    }
+
+    /// Returns how long the current frame has been displayed for.
+    pub fn time_in_frame(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns how long a whole loop of the animation lasts.
+    pub fn total_duration(&self) -> Duration {
+        Duration::from_millis(self.frames.iter().map(|frame| frame.duration as u64).sum())
+    }
+
+    fn frame_duration(&self, index: usize) -> Duration {
+        Duration::from_millis(self.frames[index].duration as u64)
+    }
+
+    fn skip_empty_frames(&mut self) {
+        if !self.total_duration().is_zero() {
+            while self.frames[self.index].duration == 0 {
+                self.index += 1;
+            }
+        }
+    }
+}
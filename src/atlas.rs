@@ -0,0 +1,149 @@
+//! Structures related to packing the tile images of image collection tilesets into a single image,
+//! available under the `image` feature.
+
+use std::collections::HashMap;
+
+use image::{imageops, RgbaImage};
+use rayon::prelude::*;
+
+use crate::{Rect, ResourceReader, Result, TileId, Tileset, UvRect};
+
+/// The images of the tiles of an image collection tileset, packed into a single image so that the
+/// tileset can be drawn from a single texture. Created by [`Tileset::pack_atlas()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilesetAtlas {
+    /// The image every tile image was packed into. Pixels not covered by any tile are transparent.
+    pub image: RgbaImage,
+    /// The rectangle each tile image occupies within [`TilesetAtlas::image`], by tile ID.
+    pub rects: HashMap<TileId, Rect>,
+}
+
+impl TilesetAtlas {
+    /// Returns the rectangle the image of the tile with the specified ID occupies within the
+    /// atlas, or [`None`] if the tile has no image.
+    #[inline]
+    pub fn tile_rect(&self, id: TileId) -> Option<Rect> {
+        self.rects.get(&id).copied()
+    }
+
+    /// Returns the texture coordinates of the tile with the specified ID within the atlas.
+    /// Coordinates are normalized between 0 and 1, with the origin at the top left of the image.
+    ///
+    /// Returns [`None`] in the same cases as [`TilesetAtlas::tile_rect()`].
+    pub fn tile_uv(&self, id: TileId) -> Option<UvRect> {
+        let rect = self.tile_rect(id)?;
+        let (width, height) = (self.image.width() as f32, self.image.height() as f32);
+        Some(UvRect {
+            u_min: rect.x as f32 / width,
+            v_min: rect.y as f32 / height,
+            u_max: (rect.x + rect.width) as f32 / width,
+            v_max: (rect.y + rect.height) as f32 / height,
+        })
+    }
+}
+
+impl Tileset {
+    /// Reads and decodes the images of the tiles of an image collection tileset, then packs them
+    /// into a single [atlas](TilesetAtlas), with `padding` transparent pixels between images.
+    /// Returns `None` for tilesets that have an image of their own, which already are atlases;
+    /// See [`Tileset::load_image()`].
+    ///
+    /// Images are read sequentially through the [`ResourceReader`] given, and then decoded
+    /// concurrently with [rayon](https://docs.rs/rayon). They are packed in rows, from the tallest
+    /// to the shortest, into an image about as wide as it is tall.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, Rect};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let tileset = loader
+    ///     .load_tsx_tileset("assets/tilesheet_collection.tsx")
+    ///     .unwrap();
+    ///
+    /// let atlas = tileset.pack_atlas(loader.reader_mut(), 0).unwrap().unwrap();
+    /// assert_eq!(atlas.image.dimensions(), (5, 5));
+    /// assert_eq!(
+    ///     atlas.tile_rect(0),
+    ///     Some(Rect { x: 0, y: 3, width: 4, height: 2 })
+    /// );
+    /// # }
+    /// ```
+    pub fn pack_atlas(
+        &self,
+        reader: &mut impl ResourceReader,
+        padding: u32,
+    ) -> Result<Option<TilesetAtlas>> {
+        if self.image.is_some() {
+            return Ok(None);
+        }
+
+        let mut encoded = Vec::new();
+        for (&id, tile) in &self.tiles {
+            if let Some(image) = &tile.image {
+                encoded.push((id, image, image.read(reader)?));
+            }
+        }
+        let mut images = encoded
+            .into_par_iter()
+            .map(|(id, image, bytes)| Ok((id, image.decode(bytes)?)))
+            .collect::<Result<Vec<_>>>()?;
+        images.sort_by_key(|(id, image)| (std::cmp::Reverse(image.height()), *id));
+
+        let rects = pack_rows(&images, padding);
+        let width = rects
+            .iter()
+            .map(|rect| rect.x + rect.width)
+            .max()
+            .unwrap_or(0);
+        let height = rects
+            .iter()
+            .map(|rect| rect.y + rect.height)
+            .max()
+            .unwrap_or(0);
+        let mut atlas = RgbaImage::new(width, height);
+        for ((_, image), rect) in images.iter().zip(&rects) {
+            imageops::replace(&mut atlas, image, rect.x.into(), rect.y.into());
+        }
+
+        Ok(Some(TilesetAtlas {
+            image: atlas,
+            rects: images.iter().map(|(id, _)| *id).zip(rects).collect(),
+        }))
+    }
+}
+
+/// Places the images given left to right in rows, starting a new row below the previous one
+/// whenever an image doesn't fit anymore, and returns where each image was placed.
+fn pack_rows(images: &[(TileId, RgbaImage)], padding: u32) -> Vec<Rect> {
+    let padded_area: u64 = images
+        .iter()
+        .map(|(_, image)| u64::from(image.width() + padding) * u64::from(image.height() + padding))
+        .sum();
+    let widest = images
+        .iter()
+        .map(|(_, image)| image.width())
+        .max()
+        .unwrap_or(0);
+    let row_width = widest.max((padded_area as f64).sqrt().ceil() as u32);
+
+    let mut rects = Vec::with_capacity(images.len());
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for (_, image) in images {
+        if x > 0 && x + image.width() > row_width {
+            x = 0;
+            y += row_height + padding;
+            row_height = 0;
+        }
+        rects.push(Rect {
+            x,
+            y,
+            width: image.width(),
+            height: image.height(),
+        });
+        x += image.width() + padding;
+        row_height = row_height.max(image.height());
+    }
+    rects
+}
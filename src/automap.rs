@@ -0,0 +1,855 @@
+//! Structures related to applying Tiled automapping rules to maps.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{
+    parse::xml::{ReadFrom, Reader},
+    ChunkData, Error, FiniteTileLayerData, InfiniteTileLayerData, LayerData, LayerDataType,
+    LayerTileData, LoaderOptions, Map, PropertyValue, ResourceCache, Result, TileId, TileLayerData,
+    Tileset,
+};
+
+/// A tile used within a rule map, relative to the rule map's tilesets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct RuleTile {
+    tileset_index: usize,
+    id: TileId,
+    flip_h: bool,
+    flip_v: bool,
+    flip_d: bool,
+    rotate_hex_120: bool,
+}
+
+impl From<&LayerTileData> for RuleTile {
+    fn from(data: &LayerTileData) -> Self {
+        Self {
+            tileset_index: data.tileset_index(),
+            id: data.id(),
+            flip_h: data.flip_h,
+            flip_v: data.flip_v,
+            flip_d: data.flip_d,
+            rotate_hex_120: data.rotate_hex_120,
+        }
+    }
+}
+
+/// A condition placed on a cell of an input layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Condition {
+    /// The target cell must contain this specific tile.
+    Tile(RuleTile),
+    /// The target cell must be empty.
+    Empty,
+    /// Anything matches.
+    Ignore,
+    /// The target cell must contain any tile.
+    NonEmpty,
+    /// The target cell must contain a tile not used in the rule's input for the same layer.
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct InputLayer {
+    index: String,
+    /// The target layer, by index in [`RuleMap::targets`].
+    target: usize,
+    negated: bool,
+    cells: HashMap<(i32, i32), Condition>,
+}
+
+#[derive(Debug, Clone)]
+struct OutputLayer {
+    index: String,
+    /// The target layer, by index in [`RuleMap::targets`].
+    target: usize,
+    probability: f32,
+    cells: HashMap<(i32, i32), RuleTile>,
+}
+
+/// The input layers with the same index and target layer, by index in [`RuleMap::inputs`].
+#[derive(Debug, Clone)]
+struct InputTarget {
+    target: usize,
+    allowed: Vec<usize>,
+    forbidden: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    input_cells: Vec<(i32, i32)>,
+    output_cells: Vec<(i32, i32)>,
+    /// The tiles used as input for each target layer, by index in [`RuleMap::targets`], used by
+    /// [`Condition::Other`].
+    used_tiles: Vec<HashSet<RuleTile>>,
+}
+
+/// Options of a [`RuleMap`], set through the custom properties of the rule map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RuleMapOptions {
+    /// Whether the output regions of the target layers are cleared before placing the output of
+    /// a matching rule. Corresponds to the `DeleteTiles` map property.
+    pub delete_tiles: bool,
+    /// Whether rules may match partially outside of finite maps, treating the outside as empty.
+    /// Corresponds to the `MatchOutsideMap` map property.
+    pub match_outside_map: bool,
+    /// Whether the output of a rule is prevented from overlapping a previous output of the same
+    /// rule. Corresponds to the `NoOverlappingOutput` (or legacy `NoOverlappingRules`) map
+    /// property.
+    pub no_overlapping_output: bool,
+}
+
+/// A single automapping rule map, describing a set of rules through its `input_*`,
+/// `inputnot_*`, `output_*` and `regions*` tile layers.
+///
+/// The following matching semantics are supported:
+/// - Input layers with the same index are combined, and different indices are alternatives:
+///   a rule matches if the conditions of any of its input indices are met.
+/// - Within the input region, a cell with tiles in `input_<name>` layers requires the target
+///   cell to contain one of them; a cell with tiles only in `inputnot_<name>` layers requires the
+///   target cell to contain none of them; an empty cell requires the target cell to be empty.
+/// - The special `MatchType` tile property (`Empty`, `Ignore`, `NonEmpty` and `Other`).
+/// - Output layers with the same index are combined, and different indices are alternatives
+///   chosen with the weights given by their `Probability` layer property. The choice is
+///   pseudo-random but deterministic for a given rule and position.
+///
+/// Only tile layers are supported: rule maps with object input or output layers fail to load
+/// with [`Error::InvalidAutomappingRules`].
+///
+/// Also see the [Tiled docs](https://doc.mapeditor.org/en/stable/manual/automapping/).
+#[derive(Debug, Clone)]
+pub struct RuleMap {
+    /// The options of this rule map.
+    pub options: RuleMapOptions,
+    tilesets: Vec<Arc<Tileset>>,
+    /// The names of the layers the rules read from and write to.
+    targets: Vec<String>,
+    inputs: Vec<InputLayer>,
+    outputs: Vec<OutputLayer>,
+    /// The input layers grouped by index, then by target layer. Rules match if all the target
+    /// layers of any of the groups match.
+    input_groups: Vec<Vec<InputTarget>>,
+    /// The output layers grouped by index, by index in [`RuleMap::outputs`].
+    output_groups: Vec<Vec<usize>>,
+    rules: Vec<Rule>,
+}
+
+impl RuleMap {
+    /// Builds the rules described by a map loaded from a rule map file.
+    ///
+    /// Returns an error if the map has no input or output layers, or if some of them are object
+    /// layers.
+    pub fn from_map(map: &Map) -> Result<RuleMap> {
+        let invalid = |description: &str| Error::InvalidAutomappingRules {
+            description: description.to_owned(),
+        };
+        let bool_property = |name: &str| {
+            matches!(
+                map.properties.get(name),
+                Some(PropertyValue::BoolValue(true))
+            )
+        };
+        let options = RuleMapOptions {
+            delete_tiles: bool_property("DeleteTiles"),
+            match_outside_map: bool_property("MatchOutsideMap"),
+            no_overlapping_output: bool_property("NoOverlappingOutput")
+                || bool_property("NoOverlappingRules"),
+        };
+
+        if let Some(name) = object_rule_layer(&map.layers) {
+            return Err(invalid(&format!(
+                "object layer \"{}\" can't be used as input or output",
+                name
+            )));
+        }
+
+        let mut targets = Vec::new();
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        let mut regions = None;
+        let mut input_regions = None;
+        let mut output_regions = None;
+
+        for layer in tile_layers(&map.layers) {
+            let LayerDataType::Tiles(tiles) = &layer.layer_type else {
+                continue;
+            };
+            let name = &*layer.name;
+            match name {
+                "regions" => regions = Some(occupied_cells(tiles)),
+                "regions_input" => input_regions = Some(occupied_cells(tiles)),
+                "regions_output" => output_regions = Some(occupied_cells(tiles)),
+                _ => {
+                    if let Some((index, target)) = split_layer_name(name, "inputnot") {
+                        inputs.push(InputLayer {
+                            index,
+                            target: target_index(&mut targets, target),
+                            negated: true,
+                            cells: input_cells(map, tiles),
+                        });
+                    } else if let Some((index, target)) = split_layer_name(name, "input") {
+                        inputs.push(InputLayer {
+                            index,
+                            target: target_index(&mut targets, target),
+                            negated: false,
+                            cells: input_cells(map, tiles),
+                        });
+                    } else if let Some((index, target)) = split_layer_name(name, "output") {
+                        let probability = match layer.properties.get("Probability") {
+                            Some(PropertyValue::FloatValue(p)) => *p,
+                            Some(PropertyValue::IntValue(p)) => *p as f32,
+                            _ => 1.0,
+                        };
+                        outputs.push(OutputLayer {
+                            index,
+                            target: target_index(&mut targets, target),
+                            probability,
+                            cells: occupied_cells(tiles)
+                                .into_iter()
+                                .filter_map(|pos| {
+                                    tiles.get_tile_data(pos.0, pos.1).map(|t| (pos, t.into()))
+                                })
+                                .collect(),
+                        });
+                    }
+                }
+            }
+        }
+
+        if inputs.is_empty() {
+            return Err(invalid("rule map has no input layers"));
+        }
+        if outputs.is_empty() {
+            return Err(invalid("rule map has no output layers"));
+        }
+
+        let mut input_groups: BTreeMap<&str, Vec<InputTarget>> = BTreeMap::new();
+        for (input_index, layer) in inputs.iter().enumerate() {
+            let group = input_groups.entry(&layer.index).or_default();
+            let position = match group.iter().position(|t| t.target == layer.target) {
+                Some(position) => position,
+                None => {
+                    group.push(InputTarget {
+                        target: layer.target,
+                        allowed: Vec::new(),
+                        forbidden: Vec::new(),
+                    });
+                    group.len() - 1
+                }
+            };
+            if layer.negated {
+                group[position].forbidden.push(input_index);
+            } else {
+                group[position].allowed.push(input_index);
+            }
+        }
+        let input_groups: Vec<_> = input_groups.into_values().collect();
+
+        let mut output_groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (output_index, layer) in outputs.iter().enumerate() {
+            output_groups
+                .entry(&layer.index)
+                .or_default()
+                .push(output_index);
+        }
+        let output_groups: Vec<_> = output_groups.into_values().collect();
+
+        let input_region: HashSet<(i32, i32)> = input_regions
+            .or_else(|| regions.clone())
+            .unwrap_or_else(|| {
+                inputs
+                    .iter()
+                    .flat_map(|l| l.cells.keys().copied())
+                    .collect()
+            });
+        let output_region: HashSet<(i32, i32)> = output_regions.or(regions).unwrap_or_else(|| {
+            outputs
+                .iter()
+                .flat_map(|l| l.cells.keys().copied())
+                .collect()
+        });
+
+        let rules = connected_regions(input_region.union(&output_region).copied().collect())
+            .into_iter()
+            .map(|cells| {
+                let mut input_cells: Vec<_> = cells
+                    .iter()
+                    .copied()
+                    .filter(|c| input_region.contains(c))
+                    .collect();
+                let mut output_cells: Vec<_> = cells
+                    .iter()
+                    .copied()
+                    .filter(|c| output_region.contains(c))
+                    .collect();
+                input_cells.sort_by_key(|&(x, y)| (y, x));
+                output_cells.sort_by_key(|&(x, y)| (y, x));
+
+                let mut used_tiles = vec![HashSet::new(); targets.len()];
+                for layer in &inputs {
+                    let used = &mut used_tiles[layer.target];
+                    for cell in &input_cells {
+                        if let Some(Condition::Tile(tile)) = layer.cells.get(cell) {
+                            used.insert(*tile);
+                        }
+                    }
+                }
+
+                Rule {
+                    input_cells,
+                    output_cells,
+                    used_tiles,
+                }
+            })
+            .filter(|rule| !rule.input_cells.is_empty())
+            .collect();
+
+        Ok(RuleMap {
+            options,
+            tilesets: map.tilesets().to_vec(),
+            targets,
+            inputs,
+            outputs,
+            input_groups,
+            output_groups,
+            rules,
+        })
+    }
+
+    /// The amount of rules defined by this rule map.
+    pub fn rule_count(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Applies all the rules of this rule map to the map given, in order. Returns the amount of
+    /// times a rule matched and its output was placed.
+    ///
+    /// Output layers that don't exist in the target map are created as new top-level tile
+    /// layers, and tilesets used by the output that the map doesn't have are added to it.
+    pub fn apply(&self, map: &mut Map) -> usize {
+        let mut applier = Applier::new(self, map);
+        let mut applied = 0;
+        for (rule_index, rule) in self.rules.iter().enumerate() {
+            applied += applier.apply_rule(rule_index, rule);
+        }
+        applied
+    }
+}
+
+/// An ordered collection of [`RuleMap`]s, usually loaded from a `rules.txt` file with
+/// [`Loader::load_automapping_rules()`](crate::Loader::load_automapping_rules).
+#[derive(Debug, Clone, Default)]
+pub struct AutomappingRules {
+    /// The rule maps, in the order they are applied.
+    pub rule_maps: Vec<RuleMap>,
+}
+
+impl AutomappingRules {
+    /// Applies every rule map to the map given, in order. Returns the amount of times a rule
+    /// matched and its output was placed.
+    pub fn apply(&self, map: &mut Map) -> usize {
+        self.rule_maps.iter().map(|rules| rules.apply(map)).sum()
+    }
+
+    pub(crate) async fn parse(
+        path: &Path,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
+        options: &LoaderOptions,
+    ) -> Result<AutomappingRules> {
+        let mut rules = AutomappingRules::default();
+        Self::parse_into(&mut rules, path, read_from, cache, options).await?;
+        Ok(rules)
+    }
+
+    async fn parse_into(
+        &mut self,
+        path: &Path,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
+        options: &LoaderOptions,
+    ) -> Result<()> {
+        let is_rules_file = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("txt"));
+        if !is_rules_file {
+            let map = crate::parse::xml::parse_map(path, read_from, cache, options).await?;
+            self.rule_maps.push(RuleMap::from_map(&map)?);
+            return Ok(());
+        }
+
+        let load_error = |err: std::io::Error| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        };
+        let mut reader =
+            read_from
+                .read_from(path)
+                .await
+                .map_err(|err| Error::ResourceLoadingError {
+                    path: path.to_owned(),
+                    err: Box::new(err),
+                })?;
+        let mut contents = Vec::new();
+        reader
+            .read_to_end(&mut contents)
+            .await
+            .map_err(load_error)?;
+        let contents = String::from_utf8(contents)
+            .map_err(|err| load_error(std::io::Error::new(std::io::ErrorKind::InvalidData, err)))?;
+
+        let root = path.parent().ok_or(Error::PathIsNotFile)?;
+        for line in contents.lines().map(str::trim) {
+            // `[*]` is the only map name filter that can be honored, as maps don't know their
+            // file name.
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") || line == "[*]" {
+                continue;
+            }
+            if line.starts_with('[') {
+                return Err(Error::InvalidAutomappingRules {
+                    description: format!("map name filter `{}` is not supported", line),
+                });
+            }
+            // add indirection because the returned async state machine is a recursive data
+            // structure (rules files may include other rules files)
+            Box::pin(self.parse_into(&root.join(line), read_from, cache, options)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the index of a target layer in `targets`, adding it if needed.
+fn target_index(targets: &mut Vec<String>, target: String) -> usize {
+    match targets.iter().position(|t| *t == target) {
+        Some(index) => index,
+        None => {
+            targets.push(target);
+            targets.len() - 1
+        }
+    }
+}
+
+/// Returns the name of the first object layer meant as an input or output layer, including
+/// nested ones.
+fn object_rule_layer(layers: &[LayerData]) -> Option<&str> {
+    layers.iter().find_map(|layer| match &layer.layer_type {
+        LayerDataType::Objects(_)
+            if split_layer_name(&layer.name, "input").is_some()
+                || split_layer_name(&layer.name, "output").is_some() =>
+        {
+            Some(&*layer.name)
+        }
+        LayerDataType::Group(group) => object_rule_layer(group.layer_data()),
+        _ => None,
+    })
+}
+
+/// Splits a layer name like `input2_Ground` into its index (`2`) and target layer (`Ground`).
+fn split_layer_name(name: &str, prefix: &str) -> Option<(String, String)> {
+    let rest = name.strip_prefix(prefix)?;
+    let (index, target) = rest.split_once('_')?;
+    if index.contains(|c: char| !c.is_ascii_alphanumeric()) || target.is_empty() {
+        return None;
+    }
+    Some((index.to_owned(), target.to_owned()))
+}
+
+/// Collects all tile layers, including nested ones, in display order.
+fn tile_layers(layers: &[LayerData]) -> Vec<&LayerData> {
+    let mut out = Vec::new();
+    for layer in layers {
+        match &layer.layer_type {
+            LayerDataType::Tiles(_) => out.push(layer),
+            LayerDataType::Group(group) => out.extend(tile_layers(group.layer_data())),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn occupied_cells(tiles: &TileLayerData) -> HashSet<(i32, i32)> {
+    let mut cells = HashSet::new();
+    match tiles {
+        TileLayerData::Finite(finite) => {
+            for y in 0..finite.height() as i32 {
+                for x in 0..finite.width() as i32 {
+                    if finite.get_tile_data(x, y).is_some() {
+                        cells.insert((x, y));
+                    }
+                }
+            }
+        }
+        TileLayerData::Infinite(infinite) => {
+            for ((chunk_x, chunk_y), chunk) in infinite.chunk_data() {
+                for y in 0..ChunkData::HEIGHT as i32 {
+                    for x in 0..ChunkData::WIDTH as i32 {
+                        if chunk.get_tile_data(x, y).is_some() {
+                            cells.insert((
+                                chunk_x * ChunkData::WIDTH as i32 + x,
+                                chunk_y * ChunkData::HEIGHT as i32 + y,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    cells
+}
+
+fn input_cells(map: &Map, tiles: &TileLayerData) -> HashMap<(i32, i32), Condition> {
+    occupied_cells(tiles)
+        .into_iter()
+        .filter_map(|(x, y)| {
+            let data = tiles.get_tile_data(x, y)?;
+            let match_type = map.tilesets()[data.tileset_index()]
+                .get_tile(data.id())
+                .and_then(|tile| match tile.properties.get("MatchType") {
+                    Some(PropertyValue::StringValue(s)) => Some(s.clone()),
+                    _ => None,
+                });
+            let condition = match match_type.as_deref() {
+                Some("Empty") => Condition::Empty,
+                Some("Ignore") => Condition::Ignore,
+                Some("NonEmpty") => Condition::NonEmpty,
+                Some("Other") => Condition::Other,
+                _ => Condition::Tile(data.into()),
+            };
+            Some(((x, y), condition))
+        })
+        .collect()
+}
+
+/// Splits a set of cells into 4-connected regions, ordered by their top-left-most cell.
+fn connected_regions(mut cells: HashSet<(i32, i32)>) -> Vec<Vec<(i32, i32)>> {
+    let mut starts: Vec<_> = cells.iter().copied().collect();
+    starts.sort_by_key(|&(x, y)| (y, x));
+
+    let mut regions = Vec::new();
+    for start in starts {
+        if !cells.remove(&start) {
+            continue;
+        }
+        let mut region = vec![start];
+        let mut pending = vec![start];
+        while let Some((x, y)) = pending.pop() {
+            for neighbor in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+                if cells.remove(&neighbor) {
+                    region.push(neighbor);
+                    pending.push(neighbor);
+                }
+            }
+        }
+        regions.push(region);
+    }
+    regions
+}
+
+/// The state of applying a [`RuleMap`] to a target map.
+struct Applier<'a> {
+    rules: &'a RuleMap,
+    map: &'a mut Map,
+    /// The target tileset index of each rule map tileset, if present in the target map.
+    tileset_indices: Vec<Option<usize>>,
+    /// The location of each of the rules' target layers in the map, if present, as given by
+    /// [`tile_layer_path()`].
+    layer_paths: Vec<Option<Vec<usize>>>,
+}
+
+impl<'a> Applier<'a> {
+    fn new(rules: &'a RuleMap, map: &'a mut Map) -> Self {
+        let tileset_indices = rules
+            .tilesets
+            .iter()
+            .map(|tileset| {
+                map.tilesets
+                    .iter()
+                    .position(|t| Arc::ptr_eq(t, tileset) || **t == **tileset)
+            })
+            .collect();
+        let layer_paths = rules
+            .targets
+            .iter()
+            .map(|name| tile_layer_path(&map.layers, name))
+            .collect();
+        Self {
+            rules,
+            map,
+            tileset_indices,
+            layer_paths,
+        }
+    }
+
+    /// Returns the bounds of the area rules are checked against, as (min x, min y, max x, max y)
+    /// with exclusive maximums.
+    fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        if !self.map.infinite() {
+            return Some((0, 0, self.map.width as i32, self.map.height as i32));
+        }
+        let mut bounds: Option<(i32, i32, i32, i32)> = None;
+        for layer in tile_layers(&self.map.layers) {
+            if let LayerDataType::Tiles(TileLayerData::Infinite(infinite)) = &layer.layer_type {
+                for ((x, y), _) in infinite.chunk_data() {
+                    let (x0, y0) = (x * ChunkData::WIDTH as i32, y * ChunkData::HEIGHT as i32);
+                    let (x1, y1) = (x0 + ChunkData::WIDTH as i32, y0 + ChunkData::HEIGHT as i32);
+                    bounds = Some(match bounds {
+                        Some((a, b, c, d)) => (a.min(x0), b.min(y0), c.max(x1), d.max(y1)),
+                        None => (x0, y0, x1, y1),
+                    });
+                }
+            }
+        }
+        bounds
+    }
+
+    fn apply_rule(&mut self, rule_index: usize, rule: &Rule) -> usize {
+        let Some((bx0, by0, bx1, by1)) = self.bounds() else {
+            return 0;
+        };
+        let min_x = rule.input_cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let max_x = rule.input_cells.iter().map(|c| c.0).max().unwrap_or(0);
+        let min_y = rule.input_cells.iter().map(|c| c.1).min().unwrap_or(0);
+        let max_y = rule.input_cells.iter().map(|c| c.1).max().unwrap_or(0);
+
+        let (x_range, y_range) = if self.rules.options.match_outside_map || self.map.infinite() {
+            (bx0 - max_x..bx1 - min_x, by0 - max_y..by1 - min_y)
+        } else {
+            (bx0 - min_x..bx1 - max_x, by0 - min_y..by1 - max_y)
+        };
+
+        let mut written = HashSet::new();
+        let mut applied = 0;
+        for dy in y_range {
+            for dx in x_range.clone() {
+                if self.matches(rule, dx, dy)
+                    && self.place_output(rule_index, rule, dx, dy, &mut written)
+                {
+                    applied += 1;
+                }
+            }
+        }
+        applied
+    }
+
+    fn target_layer(&self, target: usize) -> Option<&TileLayerData> {
+        tile_layer_at(&self.map.layers, self.layer_paths[target].as_deref()?)
+    }
+
+    fn target_tile(&self, layer: Option<&TileLayerData>, x: i32, y: i32) -> Option<RuleTile> {
+        let data = layer?.get_tile_data(x, y)?;
+        // Translate the tileset index to the rule map's tilesets so tiles can be compared.
+        let tileset_index = self
+            .tileset_indices
+            .iter()
+            .position(|index| *index == Some(data.tileset_index()))
+            .unwrap_or(usize::MAX);
+        Some(RuleTile {
+            tileset_index,
+            ..data.into()
+        })
+    }
+
+    fn matches(&self, rule: &Rule, dx: i32, dy: i32) -> bool {
+        let inputs = &self.rules.inputs;
+        self.rules.input_groups.iter().any(|group| {
+            group.iter().all(|input| {
+                let layer = self.target_layer(input.target);
+                let used = &rule.used_tiles[input.target];
+                rule.input_cells.iter().all(|&(x, y)| {
+                    let tile = self.target_tile(layer, x + dx, y + dy);
+                    let check = |condition: &Condition| match condition {
+                        Condition::Tile(expected) => tile == Some(*expected),
+                        Condition::Empty => tile.is_none(),
+                        Condition::Ignore => true,
+                        Condition::NonEmpty => tile.is_some(),
+                        Condition::Other => tile.is_some_and(|t| !used.contains(&t)),
+                    };
+
+                    let mut allowed = input
+                        .allowed
+                        .iter()
+                        .filter_map(|&i| inputs[i].cells.get(&(x, y)))
+                        .peekable();
+                    let mut forbidden = input
+                        .forbidden
+                        .iter()
+                        .filter_map(|&i| inputs[i].cells.get(&(x, y)))
+                        .peekable();
+
+                    match (allowed.peek().is_some(), forbidden.peek().is_some()) {
+                        (true, _) => allowed.any(&check) && !forbidden.any(&check),
+                        (false, true) => !forbidden.any(&check),
+                        (false, false) => tile.is_none(),
+                    }
+                })
+            })
+        })
+    }
+
+    /// Places the output of a rule matched at the given offset. Returns whether it was placed.
+    fn place_output(
+        &mut self,
+        rule_index: usize,
+        rule: &Rule,
+        dx: i32,
+        dy: i32,
+        written: &mut HashSet<(usize, i32, i32)>,
+    ) -> bool {
+        let outputs = &self.rules.outputs;
+        let groups = &self.rules.output_groups;
+        let total: f32 = groups.iter().map(|g| outputs[g[0]].probability).sum();
+        let mut roll = pseudo_random(rule_index, dx, dy) * total;
+        let chosen = groups
+            .iter()
+            .find(|g| {
+                roll -= outputs[g[0]].probability;
+                roll < 0.0
+            })
+            .unwrap_or(&groups[groups.len() - 1]);
+
+        let mut placements = Vec::new();
+        for layer in chosen.iter().map(|&i| &outputs[i]) {
+            for &(x, y) in &rule.output_cells {
+                if let Some(tile) = layer.cells.get(&(x, y)) {
+                    placements.push((layer.target, x + dx, y + dy, *tile));
+                }
+            }
+        }
+
+        if self.rules.options.no_overlapping_output
+            && placements
+                .iter()
+                .any(|&(target, x, y, _)| written.contains(&(target, x, y)))
+        {
+            return false;
+        }
+
+        if self.rules.options.delete_tiles {
+            let targets: HashSet<usize> = outputs.iter().map(|l| l.target).collect();
+            for target in targets {
+                let Some(path) = self.layer_paths[target].as_deref() else {
+                    continue;
+                };
+                if let Some(layer) = tile_layer_at_mut(&mut self.map.layers, path) {
+                    for &(x, y) in &rule.output_cells {
+                        layer.set_tile_data(x + dx, y + dy, None);
+                    }
+                }
+            }
+        }
+
+        for (target, x, y, tile) in placements {
+            let data = self.target_tile_data(tile);
+            self.target_layer_mut(target)
+                .set_tile_data(x, y, Some(data));
+            written.insert((target, x, y));
+        }
+        true
+    }
+
+    fn target_tile_data(&mut self, tile: RuleTile) -> LayerTileData {
+        let tileset_index = match self.tileset_indices[tile.tileset_index] {
+            Some(index) => index,
+            None => {
+                self.map
+                    .tilesets
+                    .push(self.rules.tilesets[tile.tileset_index].clone());
+                self.map.gid_table.invalidate();
+                let index = self.map.tilesets.len() - 1;
+                self.tileset_indices[tile.tileset_index] = Some(index);
+                index
+            }
+        };
+        let mut data = LayerTileData::new(
+            tileset_index,
+            tile.id,
+            tile.flip_h,
+            tile.flip_v,
+            tile.flip_d,
+        );
+        data.rotate_hex_120 = tile.rotate_hex_120;
+        data
+    }
+
+    fn target_layer_mut(&mut self, target: usize) -> &mut TileLayerData {
+        if self.layer_paths[target].is_none() {
+            let id = max_layer_id(&self.map.layers) + 1;
+            let tiles = if self.map.infinite() {
+                TileLayerData::Infinite(InfiniteTileLayerData::empty())
+            } else {
+                TileLayerData::Finite(FiniteTileLayerData::empty(self.map.width, self.map.height))
+            };
+            let name = self.rules.targets[target].as_str().into();
+            self.map
+                .layers
+                .push(LayerData::new_tile_layer(id, name, tiles));
+            self.map.id_index.invalidate();
+            self.layer_paths[target] = Some(vec![self.map.layers.len() - 1]);
+        }
+        let path = self.layer_paths[target].as_deref().unwrap_or_default();
+        tile_layer_at_mut(&mut self.map.layers, path).expect("layer paths are kept up to date")
+    }
+}
+
+/// Returns the location of the first tile layer with the name given, including nested ones, in
+/// display order. Locations are the indices of the groups containing the layer, from the
+/// outermost one, followed by its own index.
+fn tile_layer_path(layers: &[LayerData], name: &str) -> Option<Vec<usize>> {
+    layers.iter().enumerate().find_map(|(index, layer)| {
+        let mut path = match &layer.layer_type {
+            LayerDataType::Tiles(_) if &*layer.name == name => Vec::new(),
+            LayerDataType::Group(group) => tile_layer_path(group.layer_data(), name)?,
+            _ => return None,
+        };
+        path.insert(0, index);
+        Some(path)
+    })
+}
+
+fn tile_layer_at<'a>(layers: &'a [LayerData], path: &[usize]) -> Option<&'a TileLayerData> {
+    let (&index, rest) = path.split_first()?;
+    match &layers.get(index)?.layer_type {
+        LayerDataType::Tiles(tiles) if rest.is_empty() => Some(tiles),
+        LayerDataType::Group(group) => tile_layer_at(group.layer_data(), rest),
+        _ => None,
+    }
+}
+
+fn tile_layer_at_mut<'a>(
+    layers: &'a mut [LayerData],
+    path: &[usize],
+) -> Option<&'a mut TileLayerData> {
+    let (&index, rest) = path.split_first()?;
+    match &mut layers.get_mut(index)?.layer_type {
+        LayerDataType::Tiles(tiles) if rest.is_empty() => Some(tiles),
+        LayerDataType::Group(group) => tile_layer_at_mut(group.layer_data_mut(), rest),
+        _ => None,
+    }
+}
+
+fn max_layer_id(layers: &[LayerData]) -> u32 {
+    layers
+        .iter()
+        .map(|layer| match &layer.layer_type {
+            LayerDataType::Group(group) => layer.id().max(max_layer_id(group.layer_data())),
+            _ => layer.id(),
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns a deterministic pseudo-random number in the `[0, 1)` range for a rule and position.
+fn pseudo_random(rule_index: usize, x: i32, y: i32) -> f32 {
+    let mut h = (rule_index as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        .wrapping_add((x as u32 as u64) << 32 | y as u32 as u64);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    h ^= h >> 33;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
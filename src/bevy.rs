@@ -0,0 +1,235 @@
+//! Integration with the asset system of [Bevy](https://bevyengine.org), available under the `bevy`
+//! feature.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    io::Cursor,
+    ops::Deref,
+    path::{Component, Path, PathBuf},
+};
+
+use bevy_app::{App, Plugin};
+use bevy_asset::{
+    io::{AssetSourceId, Reader},
+    Asset, AssetApp, AssetLoader, AssetPath, Handle, LoadContext, ReadAssetBytesError,
+    UntypedAssetId, VisitAssetDependencies,
+};
+use bevy_image::Image as BevyImage;
+use bevy_reflect::TypePath;
+
+use crate::{
+    dependencies::map_image_sources, AsyncResourceReader, Error, Loader, Map, Result, Tileset,
+};
+
+/// A Bevy plugin that registers [`TiledMap`] and [`Tileset`] as assets, along with the
+/// [`TmxAssetLoader`] for `.tmx` files.
+///
+/// Images are loaded as Bevy [images](bevy_image::Image), so their loader must be registered as
+/// well, which Bevy's default plugins do.
+///
+/// ## Example
+/// ```no_run
+/// use bevy_app::App;
+/// use tiled::TiledPlugin;
+///
+/// # fn main() {
+/// App::new().add_plugins(TiledPlugin);
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TiledPlugin;
+
+impl Plugin for TiledPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TiledMap>()
+            .init_asset::<Tileset>()
+            .register_asset_loader(TmxAssetLoader);
+    }
+}
+
+/// A TMX map loaded by the [`TmxAssetLoader`], along with handles to the assets it depends on.
+///
+/// Dereferences to the [`Map`] itself.
+#[derive(Debug)]
+pub struct TiledMap {
+    /// The map.
+    pub map: Map,
+    /// Handles to the tilesets of the map, in the same order as [`Map::tilesets()`]. Each tileset
+    /// is a labeled asset of the map; See [`TiledMap::tileset_label()`].
+    pub tilesets: Vec<Handle<Tileset>>,
+    /// Handles to every image referenced by the map, keyed by their
+    /// [`Image::source`](crate::Image::source) path. Images embedded in the files aren't included.
+    pub images: HashMap<PathBuf, Handle<BevyImage>>,
+}
+
+impl TiledMap {
+    /// Returns the label of the asset of the tileset with the index given, within the map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::TiledMap;
+    ///
+    /// assert_eq!(TiledMap::tileset_label(1), "Tileset1");
+    /// ```
+    pub fn tileset_label(index: usize) -> String {
+        format!("Tileset{}", index)
+    }
+}
+
+impl Deref for TiledMap {
+    type Target = Map;
+
+    fn deref(&self) -> &Map {
+        &self.map
+    }
+}
+
+impl TypePath for TiledMap {
+    fn type_path() -> &'static str {
+        "tiled::TiledMap"
+    }
+
+    fn short_type_path() -> &'static str {
+        "TiledMap"
+    }
+}
+
+impl VisitAssetDependencies for TiledMap {
+    fn visit_dependencies(&self, visit: &mut impl FnMut(UntypedAssetId)) {
+        for tileset in &self.tilesets {
+            visit(tileset.id().untyped());
+        }
+        for image in self.images.values() {
+            visit(image.id().untyped());
+        }
+    }
+}
+
+impl Asset for TiledMap {}
+
+impl TypePath for Tileset {
+    fn type_path() -> &'static str {
+        "tiled::Tileset"
+    }
+
+    fn short_type_path() -> &'static str {
+        "Tileset"
+    }
+}
+
+impl VisitAssetDependencies for Tileset {
+    fn visit_dependencies(&self, _visit: &mut impl FnMut(UntypedAssetId)) {}
+}
+
+impl Asset for Tileset {}
+
+/// A Bevy [`AssetLoader`] for `.tmx` files, which loads them as [`TiledMap`] assets.
+///
+/// The external tilesets and templates of maps are read through the same asset source as the map
+/// with a [`BevyResourceReader`], so that Bevy reloads the map when they change. Tilesets are
+/// added as labeled assets of the map, and images are loaded as dependencies of the map, so that
+/// its recursive dependency load state tracks them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TmxAssetLoader;
+
+impl AssetLoader for TmxAssetLoader {
+    type Asset = TiledMap;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<TiledMap> {
+        let path = load_context.path().to_owned();
+        let mut contents = Vec::new();
+        reader
+            .read_to_end(&mut contents)
+            .await
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.clone(),
+                err: Box::new(err),
+            })?;
+        let map = Loader::with_reader(BevyResourceReader::new(load_context))
+            .load_tmx_map_from_async(Cursor::new(contents), &path)
+            .await?;
+
+        let mut tilesets = Vec::with_capacity(map.tilesets().len());
+        for (index, tileset) in map.tilesets().iter().enumerate() {
+            let label = TiledMap::tileset_label(index);
+            tilesets.push(load_context.add_labeled_asset(label, Tileset::clone(tileset)));
+        }
+        let source = load_context.asset_path().source().clone_owned();
+        let mut images = HashMap::new();
+        for image in map_image_sources(&map) {
+            let handle = load_context.load(asset_path(&source, &image));
+            images.insert(image, handle);
+        }
+
+        Ok(TiledMap {
+            map,
+            tilesets,
+            images,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+}
+
+/// An [`AsyncResourceReader`] that reads files through the asset source of a Bevy
+/// [`LoadContext`], which records them as dependencies of the asset being loaded. Available under
+/// the `bevy` feature.
+///
+/// Paths are relative to the root of the asset source the asset being loaded comes from. This is
+/// the reader the [`TmxAssetLoader`] uses, and can be used by other asset loaders to load maps and
+/// tilesets through the asynchronous loading functions of [`Loader`].
+pub struct BevyResourceReader<'r, 'ctx> {
+    load_context: &'r mut LoadContext<'ctx>,
+}
+
+impl<'r, 'ctx> BevyResourceReader<'r, 'ctx> {
+    /// Creates a reader that reads files through the load context given.
+    pub fn new(load_context: &'r mut LoadContext<'ctx>) -> Self {
+        Self { load_context }
+    }
+}
+
+impl fmt::Debug for BevyResourceReader<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BevyResourceReader")
+            .field("asset_path", self.load_context.asset_path())
+            .finish()
+    }
+}
+
+impl AsyncResourceReader for BevyResourceReader<'_, '_> {
+    type Resource = Cursor<Vec<u8>>;
+    type Error = ReadAssetBytesError;
+
+    async fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        let path = asset_path(self.load_context.asset_path().source(), path);
+        let bytes = self.load_context.read_asset_bytes(path).await?;
+        Ok(Cursor::new(bytes))
+    }
+}
+
+/// Returns the path of an asset in the asset source given, with `.` and `..` components resolved
+/// so that every path to a file refers to the same asset.
+fn asset_path(source: &AssetSourceId, path: &Path) -> AssetPath<'static> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    AssetPath::from(normalized).with_source(source.clone_owned())
+}
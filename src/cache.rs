@@ -1,10 +1,17 @@
 use std::{
     collections::HashMap,
+    mem::size_of,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
 };
 
-use crate::{Template, Tileset};
+use crate::{
+    Frame, ImageSource, ObjectData, Properties, PropertyValue, Template, TileData, TileId, Tileset,
+    WangColor, WangSet, WangTile,
+};
 
 /// A reference type that is used to refer to a resource. For the owned variant, see [`ResourcePathBuf`].
 pub type ResourcePath = Path;
@@ -47,6 +54,108 @@ pub trait ResourceCache {
     fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>>;
     /// Insert a new template into the cache.
     fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>);
+
+    /// Inserts a tileset into the cache unless one is already cached under the same path,
+    /// returning the cached tileset.
+    ///
+    /// Loaders use this to cache the tilesets they parse, so that maps loaded at the same time
+    /// from several threads end up sharing their tilesets. The default implementation looks the
+    /// path up and then inserts, which caches shared between threads should do atomically.
+    fn get_or_insert_tileset(
+        &mut self,
+        path: impl AsRef<ResourcePath>,
+        tileset: Arc<Tileset>,
+    ) -> Arc<Tileset> {
+        let path = path.as_ref();
+        if let Some(cached) = self.get_tileset(path) {
+            return cached;
+        }
+        self.insert_tileset(path, tileset.clone());
+        tileset
+    }
+
+    /// Inserts a template into the cache unless one is already cached under the same path,
+    /// returning the cached template.
+    ///
+    /// See [`Self::get_or_insert_tileset()`] for details.
+    fn get_or_insert_template(
+        &mut self,
+        path: impl AsRef<ResourcePath>,
+        template: Arc<Template>,
+    ) -> Arc<Template> {
+        let path = path.as_ref();
+        if let Some(cached) = self.get_template(path) {
+            return cached;
+        }
+        self.insert_template(path, template.clone());
+        template
+    }
+
+    /// Removes a tileset from the cache, returning it if it was cached.
+    ///
+    /// The default implementation does nothing, which suits caches that don't store resources.
+    fn remove_tileset(&mut self, _path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        None
+    }
+    /// Removes a template from the cache, returning it if it was cached.
+    ///
+    /// The default implementation does nothing, which suits caches that don't store resources.
+    fn remove_template(&mut self, _path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        None
+    }
+    /// Only keeps the resources for which `keep` returns `true`, removing the other ones.
+    ///
+    /// The default implementation does nothing, which suits caches that don't store resources.
+    fn retain(&mut self, _keep: impl FnMut(&ResourcePath, CachedResource<'_>) -> bool) {}
+    /// Removes every resource from the cache.
+    fn clear(&mut self) {
+        self.retain(|_, _| false)
+    }
+    /// Removes the resources that are only referenced by the cache, i.e. the tilesets that no
+    /// loaded map or template uses anymore.
+    ///
+    /// Since objects copy the data of their templates, templates are always removed.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, ResourceCache};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let map = loader
+    ///     .load_tmx_map("assets/tiled_base64_external.tmx")
+    ///     .unwrap();
+    ///
+    /// loader.cache_mut().evict_unused();
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_some());
+    ///
+    /// drop(map);
+    /// loader.cache_mut().evict_unused();
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+    /// # }
+    /// ```
+    fn evict_unused(&mut self) {
+        self.retain(|_, resource| resource.strong_count() > 1)
+    }
+}
+
+/// A resource stored in a [`ResourceCache`], passed to [`ResourceCache::retain()`].
+#[derive(Debug, Clone, Copy)]
+pub enum CachedResource<'a> {
+    /// A cached tileset.
+    Tileset(&'a Arc<Tileset>),
+    /// A cached template.
+    Template(&'a Arc<Template>),
+}
+
+impl CachedResource<'_> {
+    /// Returns the number of strong references to the resource, including the cache's own.
+    pub fn strong_count(&self) -> usize {
+        match self {
+            CachedResource::Tileset(tileset) => Arc::strong_count(tileset),
+            CachedResource::Template(template) => Arc::strong_count(template),
+        }
+    }
 }
 
 /// A cache that identifies resources by their path, storing them in a [`HashMap`].
@@ -84,4 +193,428 @@ impl ResourceCache for DefaultResourceCache {
     fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>) {
         self.templates.insert(path.as_ref().to_path_buf(), tileset);
     }
+
+    fn remove_tileset(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        self.tilesets.remove(path.as_ref())
+    }
+
+    fn remove_template(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        self.templates.remove(path.as_ref())
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&ResourcePath, CachedResource<'_>) -> bool) {
+        self.tilesets
+            .retain(|path, tileset| keep(path, CachedResource::Tileset(tileset)));
+        self.templates
+            .retain(|path, template| keep(path, CachedResource::Template(template)));
+    }
+}
+
+/// A cache that can be shared between threads, e.g. by several [`Loader`](crate::Loader)s that
+/// load maps in parallel, so that the tilesets and templates they have in common are only kept
+/// once.
+///
+/// Clones of a [`SharedResourceCache`] share the same cache, and so do clones of a loader that
+/// uses one. The cache is guarded by a [`RwLock`], so lookups from different threads don't block
+/// each other. Two threads that miss the same resource at the same time will both parse it, but
+/// only the copy cached first is kept and returned to both, so that their maps share it.
+///
+/// By default it wraps a [`DefaultResourceCache`], but any [`ResourceCache`] can be used, such as
+/// a [`LruResourceCache`].
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, ResourceCache};
+///
+/// # fn main() {
+/// let loader = Loader::new_shared();
+/// std::thread::scope(|scope| {
+///     for path in ["assets/tiled_base64_external.tmx", "assets/tiled_flipped.tmx"] {
+///         let mut loader = loader.clone();
+///         scope.spawn(move || loader.load_tmx_map(path).unwrap());
+///     }
+/// });
+///
+/// // Both maps use the same tileset, which is cached once
+/// assert_eq!(loader.cache().read().tilesets.len(), 1);
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct SharedResourceCache<C = DefaultResourceCache> {
+    cache: Arc<RwLock<C>>,
+}
+
+impl SharedResourceCache {
+    /// Creates an empty [`SharedResourceCache`] backed by a [`DefaultResourceCache`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<C> SharedResourceCache<C> {
+    /// Creates a [`SharedResourceCache`] backed by the cache given.
+    pub fn with_cache(cache: C) -> Self {
+        Self {
+            cache: Arc::new(RwLock::new(cache)),
+        }
+    }
+
+    /// Locks the underlying cache for reading, blocking until no other thread is writing to it.
+    pub fn read(&self) -> RwLockReadGuard<'_, C> {
+        // Caches are left in a consistent state if a thread panics while inserting into them
+        self.cache.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the underlying cache for writing, blocking until no other thread is using it.
+    pub fn write(&self) -> RwLockWriteGuard<'_, C> {
+        self.cache.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<C> Clone for SharedResourceCache<C> {
+    fn clone(&self) -> Self {
+        Self {
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<C: ResourceCache> ResourceCache for SharedResourceCache<C> {
+    fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        self.read().get_tileset(path)
+    }
+
+    fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>) {
+        self.write().insert_tileset(path, tileset)
+    }
+
+    fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        self.read().get_template(path)
+    }
+
+    fn insert_template(&mut self, path: impl AsRef<ResourcePath>, template: Arc<Template>) {
+        self.write().insert_template(path, template)
+    }
+
+    fn get_or_insert_tileset(
+        &mut self,
+        path: impl AsRef<ResourcePath>,
+        tileset: Arc<Tileset>,
+    ) -> Arc<Tileset> {
+        self.write().get_or_insert_tileset(path, tileset)
+    }
+
+    fn get_or_insert_template(
+        &mut self,
+        path: impl AsRef<ResourcePath>,
+        template: Arc<Template>,
+    ) -> Arc<Template> {
+        self.write().get_or_insert_template(path, template)
+    }
+
+    fn remove_tileset(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        self.write().remove_tileset(path)
+    }
+
+    fn remove_template(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        self.write().remove_template(path)
+    }
+
+    fn retain(&mut self, keep: impl FnMut(&ResourcePath, CachedResource<'_>) -> bool) {
+        self.write().retain(keep)
+    }
+
+    fn clear(&mut self) {
+        self.write().clear()
+    }
+
+    fn evict_unused(&mut self) {
+        self.write().evict_unused()
+    }
+}
+
+/// How many resources a [`LruResourceCache`] can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheLimit {
+    /// The maximum number of tilesets and templates, combined.
+    Entries(usize),
+    /// The maximum estimated memory usage of the cached resources, in bytes.
+    ///
+    /// The estimate covers the data of tilesets and templates, such as their tiles, properties
+    /// and strings, but not the images they reference, which aren't loaded by this crate.
+    Bytes(usize),
+}
+
+/// A cache that identifies resources by their path and holds a limited number of them, evicting
+/// the least recently used ones first once its [limit](CacheLimit) is reached.
+///
+/// The most recently inserted resource is never evicted, even if it exceeds the limit on its own.
+/// Evicting a resource only drops the cache's reference to it, so maps that use it are unaffected.
+///
+/// ## Example
+/// ```
+/// use tiled::{CacheLimit, FilesystemResourceReader, Loader, LruResourceCache, ResourceCache};
+///
+/// # fn main() {
+/// let mut loader = Loader::with_cache_and_reader(
+///     LruResourceCache::new(CacheLimit::Entries(1)),
+///     FilesystemResourceReader::new(),
+/// );
+/// // The map uses two external tilesets, but only the last one loaded is kept
+/// loader
+///     .load_tmx_map("assets/tiled_multiple_tilesets.tmx")
+///     .unwrap();
+/// assert_eq!(loader.cache().len(), 1);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LruResourceCache {
+    limit: CacheLimit,
+    clock: AtomicU64,
+    size: usize,
+    tilesets: HashMap<ResourcePathBuf, LruEntry<Tileset>>,
+    templates: HashMap<ResourcePathBuf, LruEntry<Template>>,
+}
+
+#[derive(Debug)]
+struct LruEntry<T> {
+    resource: Arc<T>,
+    size: usize,
+    last_used: AtomicU64,
+}
+
+impl LruResourceCache {
+    /// Creates an empty [`LruResourceCache`] with the limit given.
+    pub fn new(limit: CacheLimit) -> Self {
+        Self {
+            limit,
+            clock: AtomicU64::new(0),
+            size: 0,
+            tilesets: HashMap::new(),
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Returns the limit of the cache.
+    pub fn limit(&self) -> CacheLimit {
+        self.limit
+    }
+
+    /// Changes the limit of the cache, evicting the least recently used resources if they don't
+    /// fit in it anymore.
+    pub fn set_limit(&mut self, limit: CacheLimit) {
+        self.limit = limit;
+        self.evict(None);
+    }
+
+    /// Returns the number of tilesets and templates in the cache.
+    pub fn len(&self) -> usize {
+        self.tilesets.len() + self.templates.len()
+    }
+
+    /// Returns `true` if the cache doesn't contain any resource.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the estimated memory usage of the cached resources, in bytes. See
+    /// [`CacheLimit::Bytes`].
+    pub fn estimated_size(&self) -> usize {
+        self.size
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn is_full(&self) -> bool {
+        match self.limit {
+            CacheLimit::Entries(entries) => self.len() > entries,
+            CacheLimit::Bytes(bytes) => self.size > bytes,
+        }
+    }
+
+    /// Evicts the least recently used resources until the cache fits in its limit, sparing the
+    /// resource at the path given.
+    fn evict(&mut self, spared: Option<&ResourcePath>) {
+        while self.is_full() {
+            let oldest_tileset = self
+                .tilesets
+                .iter()
+                .filter(|(path, _)| Some(path.as_path()) != spared)
+                .map(|(path, entry)| (entry.last_used.load(Ordering::Relaxed), path))
+                .min();
+            let oldest_template = self
+                .templates
+                .iter()
+                .filter(|(path, _)| Some(path.as_path()) != spared)
+                .map(|(path, entry)| (entry.last_used.load(Ordering::Relaxed), path))
+                .min();
+            let removed = match (oldest_tileset, oldest_template) {
+                (Some(tileset), Some(template)) if template < tileset => {
+                    let path = template.1.clone();
+                    self.templates.remove(&path).map(|entry| entry.size)
+                }
+                (Some((_, path)), _) => {
+                    let path = path.clone();
+                    self.tilesets.remove(&path).map(|entry| entry.size)
+                }
+                (None, Some((_, path))) => {
+                    let path = path.clone();
+                    self.templates.remove(&path).map(|entry| entry.size)
+                }
+                (None, None) => None,
+            };
+            match removed {
+                Some(size) => self.size -= size,
+                None => break,
+            }
+        }
+    }
+}
+
+impl<T> LruEntry<T> {
+    fn new(resource: Arc<T>, size: usize, last_used: u64) -> Self {
+        Self {
+            resource,
+            size,
+            last_used: AtomicU64::new(last_used),
+        }
+    }
+
+    fn touch(&self, tick: u64) -> Arc<T> {
+        self.last_used.store(tick, Ordering::Relaxed);
+        self.resource.clone()
+    }
+}
+
+impl ResourceCache for LruResourceCache {
+    fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        let entry = self.tilesets.get(path.as_ref())?;
+        Some(entry.touch(self.tick()))
+    }
+
+    fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>) {
+        let size = tileset_size(&tileset);
+        let entry = LruEntry::new(tileset, size, self.tick());
+        self.size += size;
+        if let Some(previous) = self.tilesets.insert(path.as_ref().to_path_buf(), entry) {
+            self.size -= previous.size;
+        }
+        self.evict(Some(path.as_ref()));
+    }
+
+    fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        let entry = self.templates.get(path.as_ref())?;
+        Some(entry.touch(self.tick()))
+    }
+
+    fn insert_template(&mut self, path: impl AsRef<ResourcePath>, template: Arc<Template>) {
+        let size = size_of::<Template>() + object_size(&template.object);
+        let entry = LruEntry::new(template, size, self.tick());
+        self.size += size;
+        if let Some(previous) = self.templates.insert(path.as_ref().to_path_buf(), entry) {
+            self.size -= previous.size;
+        }
+        self.evict(Some(path.as_ref()));
+    }
+
+    fn remove_tileset(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        let entry = self.tilesets.remove(path.as_ref())?;
+        self.size -= entry.size;
+        Some(entry.resource)
+    }
+
+    fn remove_template(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        let entry = self.templates.remove(path.as_ref())?;
+        self.size -= entry.size;
+        Some(entry.resource)
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&ResourcePath, CachedResource<'_>) -> bool) {
+        self.tilesets
+            .retain(|path, entry| keep(path, CachedResource::Tileset(&entry.resource)));
+        self.templates
+            .retain(|path, entry| keep(path, CachedResource::Template(&entry.resource)));
+        self.size = self
+            .tilesets
+            .values()
+            .map(|entry| entry.size)
+            .sum::<usize>()
+            + self
+                .templates
+                .values()
+                .map(|entry| entry.size)
+                .sum::<usize>();
+    }
+}
+
+/// Estimates the memory used by a tileset, not counting the images it references.
+fn tileset_size(tileset: &Tileset) -> usize {
+    let tiles: usize = tileset
+        .tiles
+        .values()
+        .map(|tile| {
+            size_of::<(TileId, TileData)>()
+                + properties_size(&tile.properties)
+                + tile
+                    .animation
+                    .as_ref()
+                    .map_or(0, |frames| frames.len() * size_of::<Frame>())
+                + tile.collision.as_ref().map_or(0, |collision| {
+                    collision.object_data().iter().map(object_size).sum()
+                })
+                + tile.image.as_ref().map_or(0, |image| match &image.source {
+                    ImageSource::Path(source) => source.as_os_str().len(),
+                    ImageSource::Data(data, format) => data.len() + format.len(),
+                })
+        })
+        .sum();
+    let wang_sets: usize = tileset
+        .wang_sets
+        .iter()
+        .map(|set| {
+            size_of::<WangSet>()
+                + set.name.len()
+                + properties_size(&set.properties)
+                + set.wang_colors.len() * size_of::<WangColor>()
+                + set.wang_tiles.len() * size_of::<(TileId, WangTile)>()
+        })
+        .sum();
+    size_of::<Tileset>()
+        + tileset.name.len()
+        + properties_size(&tileset.properties)
+        + tiles
+        + wang_sets
+}
+
+/// Estimates the memory used by an object.
+fn object_size(object: &ObjectData) -> usize {
+    size_of::<ObjectData>()
+        + object.name.len()
+        + object.user_type.len()
+        + properties_size(&object.properties)
+}
+
+/// Estimates the memory used by properties, including the ones of nested class properties.
+fn properties_size(properties: &Properties) -> usize {
+    properties
+        .iter()
+        .map(|(name, value)| {
+            size_of::<(String, PropertyValue)>()
+                + name.len()
+                + match value {
+                    PropertyValue::StringValue(s) => s.len(),
+                    PropertyValue::FileValue(path) => path.as_os_str().len(),
+                    PropertyValue::ClassValue {
+                        property_type,
+                        properties,
+                    } => property_type.len() + properties_size(properties),
+                    PropertyValue::Enum { name, value, flags } => {
+                        name.len() + value.len() + flags.iter().map(String::len).sum::<usize>()
+                    }
+                    _ => 0,
+                }
+        })
+        .sum()
 }
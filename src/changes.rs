@@ -0,0 +1,291 @@
+//! Structures related to detecting changes between two versions of the same map.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{ChunkData, LayerData, LayerDataType, Map, ObjectData, TileLayerData};
+
+/// A rectangular region of a tile layer, in tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileRegion {
+    /// The ID of the tile layer this region belongs to.
+    pub layer_id: u32,
+    /// The X coordinate of the top-left tile of the region.
+    pub x: i32,
+    /// The Y coordinate of the top-left tile of the region.
+    pub y: i32,
+    /// The width of the region, in tiles.
+    pub width: u32,
+    /// The height of the region, in tiles.
+    pub height: u32,
+}
+
+/// The differences between two versions of the same map, obtained via [`Map::changes_since()`].
+///
+/// Layers and objects are identified by their IDs, which are unique within a map. As such, maps
+/// saved by versions of Tiled that didn't assign layer IDs (older than 1.2) can't be compared
+/// reliably.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChangeSet {
+    /// Whether any of the map's own attributes changed, such as its size, tilesets or properties.
+    pub map_modified: bool,
+    /// The IDs of the layers that are present in the new map but not in the old one.
+    pub added_layers: Vec<u32>,
+    /// The IDs of the layers that were present in the old map but not in the new one.
+    pub removed_layers: Vec<u32>,
+    /// The IDs of the layers present in both maps whose attributes (name, visibility, offset,
+    /// properties...) changed. Changes to the layer contents are tracked separately.
+    pub modified_layers: Vec<u32>,
+    /// The IDs of the objects that are present in the new map but not in the old one.
+    pub added_objects: Vec<u32>,
+    /// The IDs of the objects that were present in the old map but not in the new one.
+    pub removed_objects: Vec<u32>,
+    /// The IDs of the objects present in both maps whose data changed, including objects that
+    /// were moved to a different layer.
+    pub modified_objects: Vec<u32>,
+    /// The regions of tile layers present in both maps whose tiles changed.
+    ///
+    /// Regions are aligned to blocks of [`ChunkData::WIDTH`] by [`ChunkData::HEIGHT`] tiles, and
+    /// may therefore contain unchanged tiles as well.
+    pub tile_regions: Vec<TileRegion>,
+}
+
+impl ChangeSet {
+    /// Returns `true` if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Map {
+    /// Computes the differences between this map and an older version of it.
+    ///
+    /// This is mainly useful for hot reloading, where only the entities affected by the changes
+    /// need to be updated instead of respawning the whole level.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let old = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    /// let new = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    ///
+    /// assert!(new.changes_since(&old).is_empty());
+    /// # }
+    /// ```
+    pub fn changes_since(&self, old: &Map) -> ChangeSet {
+        let mut changes = ChangeSet {
+            map_modified: !map_attributes_eq(self, old),
+            ..Default::default()
+        };
+
+        let new_layers = collect_layers(&self.layers);
+        let old_layers = collect_layers(&old.layers);
+
+        let ids: BTreeSet<u32> = new_layers
+            .keys()
+            .chain(old_layers.keys())
+            .copied()
+            .collect();
+        for id in ids {
+            match (old_layers.get(&id), new_layers.get(&id)) {
+                (None, Some(_)) => changes.added_layers.push(id),
+                (Some(_), None) => changes.removed_layers.push(id),
+                (Some(old_layer), Some(new_layer)) => {
+                    if !layer_attributes_eq(old_layer, new_layer) {
+                        changes.modified_layers.push(id);
+                    }
+                    if let (LayerDataType::Tiles(old_tiles), LayerDataType::Tiles(new_tiles)) =
+                        (&old_layer.layer_type, &new_layer.layer_type)
+                    {
+                        diff_tiles(id, old_tiles, new_tiles, &mut changes.tile_regions);
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+
+        let new_objects = collect_objects(&new_layers);
+        let old_objects = collect_objects(&old_layers);
+
+        let ids: BTreeSet<u32> = new_objects
+            .keys()
+            .chain(old_objects.keys())
+            .copied()
+            .collect();
+        for id in ids {
+            match (old_objects.get(&id), new_objects.get(&id)) {
+                (None, Some(_)) => changes.added_objects.push(id),
+                (Some(_), None) => changes.removed_objects.push(id),
+                (Some(old_object), Some(new_object)) => {
+                    if old_object != new_object {
+                        changes.modified_objects.push(id);
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+
+        changes
+    }
+}
+
+fn map_attributes_eq(a: &Map, b: &Map) -> bool {
+    map_settings_eq(a, b) && a.tilesets() == b.tilesets() && a.properties == b.properties
+}
+
+/// Compares the attributes of two maps, except for their tilesets and properties.
+pub(crate) fn map_settings_eq(a: &Map, b: &Map) -> bool {
+    a.version() == b.version()
+        && a.orientation == b.orientation
+        && a.width == b.width
+        && a.height == b.height
+        && a.tile_width == b.tile_width
+        && a.tile_height == b.tile_height
+        && a.stagger_axis == b.stagger_axis
+        && a.stagger_index == b.stagger_index
+        && a.hex_side_length == b.hex_side_length
+        && a.parallax_origin_x == b.parallax_origin_x
+        && a.parallax_origin_y == b.parallax_origin_y
+        && a.background_color == b.background_color
+        && a.editor_settings == b.editor_settings
+        && a.infinite() == b.infinite()
+        && a.user_type == b.user_type
+}
+
+fn layer_attributes_eq(a: &LayerData, b: &LayerData) -> bool {
+    layer_settings_eq(a, b) && a.properties == b.properties
+}
+
+/// Compares the attributes of two layers, except for their properties and contents.
+pub(crate) fn layer_settings_eq(a: &LayerData, b: &LayerData) -> bool {
+    let same_kind = matches!(
+        (&a.layer_type, &b.layer_type),
+        (LayerDataType::Tiles(_), LayerDataType::Tiles(_))
+            | (LayerDataType::Objects(_), LayerDataType::Objects(_))
+            | (LayerDataType::Image(_), LayerDataType::Image(_))
+            | (LayerDataType::Group(_), LayerDataType::Group(_))
+    );
+    let same_contents = match (&a.layer_type, &b.layer_type) {
+        (LayerDataType::Objects(a), LayerDataType::Objects(b)) => a.colour == b.colour,
+        (LayerDataType::Image(a), LayerDataType::Image(b)) => a == b,
+        _ => true,
+    };
+    same_kind
+        && same_contents
+        && a.name == b.name
+        && a.visible == b.visible
+        && a.offset_x == b.offset_x
+        && a.offset_y == b.offset_y
+        && a.parallax_x == b.parallax_x
+        && a.parallax_y == b.parallax_y
+        && a.opacity == b.opacity
+        && a.tint_color == b.tint_color
+        && a.user_type == b.user_type
+}
+
+/// Collects all layers, including nested ones, indexed by their ID.
+pub(crate) fn collect_layers(layers: &[LayerData]) -> HashMap<u32, &LayerData> {
+    fn collect<'a>(layers: &'a [LayerData], out: &mut HashMap<u32, &'a LayerData>) {
+        for layer in layers {
+            out.insert(layer.id(), layer);
+            if let LayerDataType::Group(group) = &layer.layer_type {
+                collect(group.layer_data(), out);
+            }
+        }
+    }
+
+    let mut out = HashMap::new();
+    collect(layers, &mut out);
+    out
+}
+
+/// Collects all objects, indexed by their ID, along with the ID of their layer.
+pub(crate) fn collect_objects<'a>(
+    layers: &HashMap<u32, &'a LayerData>,
+) -> HashMap<u32, (u32, &'a ObjectData)> {
+    layers
+        .iter()
+        .filter_map(|(id, layer)| match &layer.layer_type {
+            LayerDataType::Objects(objects) => Some((*id, objects)),
+            _ => None,
+        })
+        .flat_map(|(layer_id, objects)| {
+            objects
+                .object_data()
+                .iter()
+                .map(move |object| (object.id(), (layer_id, object)))
+        })
+        .collect()
+}
+
+/// Returns the positions of the blocks of [`ChunkData::WIDTH`] by [`ChunkData::HEIGHT`] tiles that
+/// may contain tiles in the layer given.
+fn blocks(layer: &TileLayerData) -> Vec<(i32, i32)> {
+    match layer {
+        TileLayerData::Finite(finite) => {
+            let columns = finite.width().div_ceil(ChunkData::WIDTH);
+            let rows = finite.height().div_ceil(ChunkData::HEIGHT);
+            (0..rows as i32)
+                .flat_map(|y| (0..columns as i32).map(move |x| (x, y)))
+                .collect()
+        }
+        TileLayerData::Infinite(infinite) => infinite.chunk_data().map(|(pos, _)| pos).collect(),
+    }
+}
+
+fn diff_tiles(
+    layer_id: u32,
+    old: &TileLayerData,
+    new: &TileLayerData,
+    regions: &mut Vec<TileRegion>,
+) {
+    for_each_block(old, new, |x, y, width, height| {
+        let changed = (y..y + height)
+            .flat_map(|ty| (x..x + width).map(move |tx| (tx, ty)))
+            .any(|(tx, ty)| old.get_tile_data(tx, ty) != new.get_tile_data(tx, ty));
+        if changed {
+            regions.push(TileRegion {
+                layer_id,
+                x,
+                y,
+                width: width as u32,
+                height: height as u32,
+            });
+        }
+    });
+}
+
+/// Calls `f` with the position and size of each block of [`ChunkData::WIDTH`] by
+/// [`ChunkData::HEIGHT`] tiles that may contain tiles in either layer.
+pub(crate) fn for_each_block(
+    old: &TileLayerData,
+    new: &TileLayerData,
+    mut f: impl FnMut(i32, i32, i32, i32),
+) {
+    let block_positions: BTreeSet<(i32, i32)> =
+        blocks(old).into_iter().chain(blocks(new)).collect();
+
+    // Finite layers don't extend past their size, so avoid reporting regions outside of them.
+    let bounds = match (old, new) {
+        (TileLayerData::Finite(a), TileLayerData::Finite(b)) => Some((
+            a.width().max(b.width()) as i32,
+            a.height().max(b.height()) as i32,
+        )),
+        _ => None,
+    };
+
+    for (block_x, block_y) in block_positions {
+        let x = block_x * ChunkData::WIDTH as i32;
+        let y = block_y * ChunkData::HEIGHT as i32;
+        let (width, height) = match bounds {
+            Some((w, h)) => (
+                (ChunkData::WIDTH as i32).min(w - x),
+                (ChunkData::HEIGHT as i32).min(h - y),
+            ),
+            None => (ChunkData::WIDTH as i32, ChunkData::HEIGHT as i32),
+        };
+        f(x, y, width, height);
+    }
+}
@@ -0,0 +1,418 @@
+//! Conversions between tile coordinates and world pixel coordinates, and the neighbours of tiles.
+
+use crate::{Map, Orientation, StaggerAxis, StaggerIndex};
+
+/// The dimensions staggered and hexagonal maps are laid out with, as computed by Tiled.
+pub(crate) struct StaggerParams {
+    pub tile_width: f32,
+    pub tile_height: f32,
+    pub stagger_x: bool,
+    pub stagger_even: bool,
+    pub side_length_x: f32,
+    pub side_length_y: f32,
+    pub side_offset_x: f32,
+    pub side_offset_y: f32,
+    pub column_width: f32,
+    pub row_height: f32,
+}
+
+impl StaggerParams {
+    pub(crate) fn new(map: &Map) -> Self {
+        // Tiled ignores the last pixel of odd tile sizes when laying out staggered maps
+        let tile_width = (map.tile_width & !1) as f32;
+        let tile_height = (map.tile_height & !1) as f32;
+        let stagger_x = map.stagger_axis == StaggerAxis::X;
+        let side_length = match map.orientation {
+            Orientation::Hexagonal => map.hex_side_length as f32,
+            _ => 0.0,
+        };
+        let (side_length_x, side_length_y) = if stagger_x {
+            (side_length, 0.0)
+        } else {
+            (0.0, side_length)
+        };
+        let side_offset_x = ((tile_width - side_length_x) / 2.0).floor();
+        let side_offset_y = ((tile_height - side_length_y) / 2.0).floor();
+        Self {
+            tile_width,
+            tile_height,
+            stagger_x,
+            stagger_even: map.stagger_index == StaggerIndex::Even,
+            side_length_x,
+            side_length_y,
+            side_offset_x,
+            side_offset_y,
+            column_width: side_offset_x + side_length_x,
+            row_height: side_offset_y + side_length_y,
+        }
+    }
+
+    /// Whether tiles are hexagons, which have edges along the stagger axis, rather than diamonds.
+    fn is_hexagonal(&self) -> bool {
+        self.side_length_x > 0.0 || self.side_length_y > 0.0
+    }
+
+    /// Whether the row or column with the index given along the stagger axis is shifted.
+    fn is_staggered(&self, index: i32) -> bool {
+        (index.rem_euclid(2) == 1) != self.stagger_even
+    }
+
+    fn top_left(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x, y - 1),
+            (false, false) => (x - 1, y - 1),
+            (true, true) => (x - 1, y),
+            (true, false) => (x - 1, y - 1),
+        }
+    }
+
+    fn top_right(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x + 1, y - 1),
+            (false, false) => (x, y - 1),
+            (true, true) => (x + 1, y),
+            (true, false) => (x + 1, y - 1),
+        }
+    }
+
+    fn bottom_left(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x, y + 1),
+            (false, false) => (x - 1, y + 1),
+            (true, true) => (x - 1, y + 1),
+            (true, false) => (x - 1, y),
+        }
+    }
+
+    fn bottom_right(&self, x: i32, y: i32) -> (i32, i32) {
+        match (
+            self.stagger_x,
+            self.is_staggered(if self.stagger_x { x } else { y }),
+        ) {
+            (false, true) => (x + 1, y + 1),
+            (false, false) => (x, y + 1),
+            (true, true) => (x + 1, y + 1),
+            (true, false) => (x + 1, y),
+        }
+    }
+
+    /// Converts a tile position to axial coordinates, where moving to any neighbour along the
+    /// rows or columns staggered changes only the first coordinate, or both coordinates by
+    /// opposite amounts. For maps staggered along the X axis, the roles of X and Y are swapped.
+    fn axial(&self, x: i32, y: i32) -> (i32, i32) {
+        let (along, across) = if self.stagger_x { (y, x) } else { (x, y) };
+        // Shifted rows or columns are shifted forward, so half of them are subtracted
+        let shifted = (across + self.stagger_even as i32).div_euclid(2);
+        (along - shifted, across)
+    }
+}
+
+impl Map {
+    /// Returns the top-left corner, in world pixels, of the bounding box of the tile cell at the
+    /// given tile position, taking the map's orientation into account.
+    ///
+    /// Layer offsets and parallax aren't taken into account; Also see
+    /// [`Layer::to_affine()`](crate::Layer::to_affine). The layout matches the one of Tiled,
+    /// including the stagger axis and index of staggered and hexagonal maps and the
+    /// [side length](Self::hex_side_length) of hexagonal tiles.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Map, Orientation};
+    ///
+    /// # fn main() {
+    /// let mut map = Map::new(Orientation::Hexagonal, 10, 10, 32, 28, false);
+    /// map.hex_side_length = 14;
+    ///
+    /// // Every other row is shifted by half a tile, and rows overlap by the slanted edges
+    /// assert_eq!(map.tile_to_world(0, 0), (0.0, 0.0));
+    /// assert_eq!(map.tile_to_world(0, 1), (16.0, 21.0));
+    /// assert_eq!(map.tile_to_world(1, 2), (32.0, 42.0));
+    /// # }
+    /// ```
+    pub fn tile_to_world(&self, x: i32, y: i32) -> (f32, f32) {
+        match self.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => {
+                self.projection().apply(x as f32, y as f32)
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let p = StaggerParams::new(self);
+                if p.stagger_x {
+                    let shift = if p.is_staggered(x) { p.row_height } else { 0.0 };
+                    (
+                        x as f32 * p.column_width,
+                        y as f32 * (p.tile_height + p.side_length_y) + shift,
+                    )
+                } else {
+                    let shift = if p.is_staggered(y) {
+                        p.column_width
+                    } else {
+                        0.0
+                    };
+                    (
+                        x as f32 * (p.tile_width + p.side_length_x) + shift,
+                        y as f32 * p.row_height,
+                    )
+                }
+            }
+        }
+    }
+
+    /// Returns the position of the tile cell containing the given point in world pixels, taking
+    /// the map's orientation and the shape of its tiles into account.
+    ///
+    /// This is the inverse of [`Map::tile_to_world()`]: Points inside of the diamond or hexagon of
+    /// a tile map to it, even where its bounding box overlaps with the ones of its neighbours. The
+    /// position returned may be outside of the map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Map, Orientation};
+    ///
+    /// # fn main() {
+    /// let map = Map::new(Orientation::Isometric, 10, 10, 64, 32, false);
+    ///
+    /// // The top corner of the map, which is in the middle of its top edge
+    /// assert_eq!(map.world_to_tile(320.0, 1.0), (0, 0));
+    /// // The center of the tile below and to the left of it
+    /// assert_eq!(map.world_to_tile(288.0, 32.0), (0, 1));
+    /// # }
+    /// ```
+    pub fn world_to_tile(&self, x: f32, y: f32) -> (i32, i32) {
+        let (tw, th) = (self.tile_width as f32, self.tile_height as f32);
+        match self.orientation {
+            Orientation::Orthogonal => ((x / tw).floor() as i32, (y / th).floor() as i32),
+            Orientation::Isometric => {
+                let x = x - self.height as f32 * tw / 2.0;
+                (
+                    (y / th + x / tw).floor() as i32,
+                    (y / th - x / tw).floor() as i32,
+                )
+            }
+            Orientation::Staggered => staggered_world_to_tile(&StaggerParams::new(self), x, y),
+            Orientation::Hexagonal => hexagonal_world_to_tile(&StaggerParams::new(self), x, y),
+        }
+    }
+
+    /// Returns the positions of the tiles sharing an edge with the tile at the given position, in
+    /// clockwise order starting from the top. Positions outside of the map are included.
+    ///
+    /// Orthogonal and isometric tiles have 4 neighbours, as do the diamonds of staggered maps,
+    /// while hexagonal tiles have 6 of them, following the stagger axis and index of the map.
+    /// Hexagonal maps whose [side length](Self::hex_side_length) is 0 are laid out like staggered
+    /// ones, so their tiles have 4 neighbours as well.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Map, Orientation};
+    ///
+    /// # fn main() {
+    /// let mut map = Map::new(Orientation::Hexagonal, 10, 10, 32, 28, false);
+    /// map.hex_side_length = 14;
+    ///
+    /// // Odd rows are shifted to the right, so they neighbour the tiles to the right above them
+    /// assert_eq!(
+    ///     map.neighbors(2, 1),
+    ///     [(3, 0), (3, 1), (3, 2), (2, 2), (1, 1), (2, 0)]
+    /// );
+    /// # }
+    /// ```
+    pub fn neighbors(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        match self.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => {
+                vec![(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let p = StaggerParams::new(self);
+                let corners = [
+                    p.top_right(x, y),
+                    p.bottom_right(x, y),
+                    p.bottom_left(x, y),
+                    p.top_left(x, y),
+                ];
+                if !p.is_hexagonal() {
+                    corners.to_vec()
+                } else if p.stagger_x {
+                    vec![
+                        (x, y - 1),
+                        corners[0],
+                        corners[1],
+                        (x, y + 1),
+                        corners[2],
+                        corners[3],
+                    ]
+                } else {
+                    vec![
+                        corners[0],
+                        (x + 1, y),
+                        corners[1],
+                        corners[2],
+                        (x - 1, y),
+                        corners[3],
+                    ]
+                }
+            }
+        }
+    }
+
+    /// Returns the amount of steps between neighbouring tiles it takes to go from one tile
+    /// position to another, following the [neighbours](Map::neighbors) of the map's orientation.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Map, Orientation};
+    ///
+    /// # fn main() {
+    /// let mut map = Map::new(Orientation::Hexagonal, 10, 10, 32, 28, false);
+    /// map.hex_side_length = 14;
+    /// assert_eq!(map.tile_distance((0, 0), (3, 4)), 5);
+    ///
+    /// // Diagonal moves aren't possible on orthogonal maps
+    /// let map = Map::new(Orientation::Orthogonal, 10, 10, 32, 32, false);
+    /// assert_eq!(map.tile_distance((0, 0), (3, 4)), 7);
+    /// # }
+    /// ```
+    pub fn tile_distance(&self, a: (i32, i32), b: (i32, i32)) -> u32 {
+        match self.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => {
+                a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let p = StaggerParams::new(self);
+                let (a, b) = (p.axial(a.0, a.1), p.axial(b.0, b.1));
+                let (dq, dr) = (b.0 - a.0, b.1 - a.1);
+                if p.is_hexagonal() {
+                    (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+                } else {
+                    dq.unsigned_abs() + (dq + dr).unsigned_abs()
+                }
+            }
+        }
+    }
+}
+
+/// Finds the diamond containing a point in a staggered map, from the square grid that every
+/// other row or column of diamonds is aligned to.
+fn staggered_world_to_tile(p: &StaggerParams, mut x: f32, mut y: f32) -> (i32, i32) {
+    if p.stagger_even {
+        if p.stagger_x {
+            x -= p.side_offset_x;
+        } else {
+            y -= p.side_offset_y;
+        }
+    }
+
+    let mut reference = (
+        (x / p.tile_width).floor() as i32,
+        (y / p.tile_height).floor() as i32,
+    );
+    let rel = (
+        x - reference.0 as f32 * p.tile_width,
+        y - reference.1 as f32 * p.tile_height,
+    );
+    let stagger_index = if p.stagger_x {
+        &mut reference.0
+    } else {
+        &mut reference.1
+    };
+    *stagger_index = *stagger_index * 2 + p.stagger_even as i32;
+
+    // Check whether the point is in any of the corners of the square, which belong to the
+    // neighbouring tiles
+    let (x, y) = reference;
+    let y_pos = rel.0 * (p.tile_height / p.tile_width);
+    if p.side_offset_y - y_pos > rel.1 {
+        p.top_left(x, y)
+    } else if -p.side_offset_y + y_pos > rel.1 {
+        p.top_right(x, y)
+    } else if p.side_offset_y + y_pos < rel.1 {
+        p.bottom_left(x, y)
+    } else if p.side_offset_y * 3.0 - y_pos < rel.1 {
+        p.bottom_right(x, y)
+    } else {
+        reference
+    }
+}
+
+/// Finds the hexagon containing a point in a hexagonal map, by finding the nearest tile center
+/// around a grid-aligned reference tile.
+fn hexagonal_world_to_tile(p: &StaggerParams, mut x: f32, mut y: f32) -> (i32, i32) {
+    if p.stagger_x {
+        x -= if p.stagger_even {
+            p.tile_width
+        } else {
+            p.side_offset_x
+        };
+    } else {
+        y -= if p.stagger_even {
+            p.tile_height
+        } else {
+            p.side_offset_y
+        };
+    }
+
+    let mut reference = (
+        (x / (p.column_width * 2.0)).floor() as i32,
+        (y / (p.row_height * 2.0)).floor() as i32,
+    );
+    let rel = (
+        x - reference.0 as f32 * p.column_width * 2.0,
+        y - reference.1 as f32 * p.row_height * 2.0,
+    );
+    let stagger_index = if p.stagger_x {
+        &mut reference.0
+    } else {
+        &mut reference.1
+    };
+    *stagger_index = *stagger_index * 2 + p.stagger_even as i32;
+
+    let (centers, offsets) = if p.stagger_x {
+        let left = (p.side_length_x / 2.0).floor();
+        let center_x = left + p.column_width;
+        let center_y = p.tile_height / 2.0;
+        (
+            [
+                (left, center_y),
+                (center_x, center_y - p.row_height),
+                (center_x, center_y + p.row_height),
+                (center_x + p.column_width, center_y),
+            ],
+            [(0, 0), (1, -1), (1, 0), (2, 0)],
+        )
+    } else {
+        let top = (p.side_length_y / 2.0).floor();
+        let center_x = p.tile_width / 2.0;
+        let center_y = top + p.row_height;
+        (
+            [
+                (center_x, top),
+                (center_x - p.column_width, center_y),
+                (center_x + p.column_width, center_y),
+                (center_x, center_y + p.row_height),
+            ],
+            [(0, 0), (-1, 1), (0, 1), (0, 2)],
+        )
+    };
+
+    let distance = |(cx, cy): (f32, f32)| (rel.0 - cx).powi(2) + (rel.1 - cy).powi(2);
+    let nearest = (1..centers.len()).fold(0, |nearest, i| {
+        if distance(centers[i]) < distance(centers[nearest]) {
+            i
+        } else {
+            nearest
+        }
+    });
+    (
+        reference.0 + offsets[nearest].0,
+        reference.1 + offsets[nearest].1,
+    )
+}
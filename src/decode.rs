@@ -0,0 +1,218 @@
+//! Structures related to decoding the images referenced by maps, available under the `image`
+//! feature.
+
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use image::{DynamicImage, ImageFormat, ImageReader, RgbaImage};
+use rayon::prelude::*;
+
+use crate::{
+    dependencies::map_images, Error, Image, ImageSource, Loader, Map, ResourceCache,
+    ResourceReader, Result, Tile, Tileset,
+};
+
+/// Decoded images, keyed by their [`Image::source`](crate::Image::source) path. Images embedded in
+/// the file aren't included; Use [`Image::load()`] for these.
+pub type DecodedImages = HashMap<PathBuf, DynamicImage>;
+
+impl<Reader: ResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
+    /// Reads and decodes every image referenced by a map: tileset images, images of tiles in
+    /// image collection tilesets (including the ones of tilesets used by templates) and image
+    /// layer images. Images embedded in the files aren't included, since they can be decoded
+    /// directly with [`Image::load()`].
+    ///
+    /// Files are read sequentially through the loader's [`ResourceReader`], and then decoded
+    /// concurrently with [rayon](https://docs.rs/rayon), since decoding usually dominates the
+    /// loading time of art-heavy maps. Each image is only decoded once, even if it is referenced
+    /// several times, and pixels of its [transparent color](Image::transparent_colour) are made
+    /// transparent as in [`Image::load()`]. Images referenced with different transparent colors
+    /// use the one of the first reference.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let map = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    /// let images = loader.load_map_images(&map).unwrap();
+    ///
+    /// let sheet = &images[Path::new("assets/tilesheet.png")];
+    /// assert_eq!((sheet.width(), sheet.height()), (448, 192));
+    /// # }
+    /// ```
+    pub fn load_map_images(&mut self, map: &Map) -> Result<DecodedImages> {
+        let mut encoded = Vec::new();
+        for image in map_images(map) {
+            let Some(path) = image.source.as_path() else {
+                continue;
+            };
+            let bytes = read_image(path, self.reader_mut())?;
+            encoded.push((image, path, bytes));
+        }
+
+        encoded
+            .into_par_iter()
+            .map(|(image, path, bytes)| {
+                let format = ImageFormat::from_path(path).ok();
+                let decoded = decode_image(path, bytes, format)?;
+                Ok((path.to_owned(), image.apply_transparent_colour(decoded)))
+            })
+            .collect()
+    }
+}
+
+impl Image {
+    /// Reads the image through the [`ResourceReader`] given and decodes it into an RGBA buffer.
+    ///
+    /// The image is read from its [`source`](Image::source), which is already relative to the
+    /// working directory, so the same reader that loaded the map or tileset can be used. Embedded
+    /// images are decoded from their data without using the reader. Pixels of
+    /// the image's [transparent color](Image::transparent_colour), if any, are made transparent.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{FilesystemResourceReader, Loader};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_image_layers.tmx").unwrap();
+    /// let layer = map.get_layer(1).unwrap().as_image_layer().unwrap();
+    ///
+    /// let image = layer
+    ///     .image
+    ///     .as_ref()
+    ///     .unwrap()
+    ///     .load(&mut FilesystemResourceReader::new())
+    ///     .unwrap();
+    /// assert_eq!(image.dimensions(), (448, 192));
+    /// # }
+    /// ```
+    pub fn load(&self, reader: &mut impl ResourceReader) -> Result<RgbaImage> {
+        let bytes = self.read(reader)?;
+        self.decode(bytes)
+    }
+
+    /// Reads the encoded contents of the image, which are only read through the reader if the
+    /// image isn't embedded.
+    pub(crate) fn read(&self, reader: &mut impl ResourceReader) -> Result<Vec<u8>> {
+        match &self.source {
+            ImageSource::Path(source) => read_image(source, reader),
+            ImageSource::Data(data, _) => Ok(data.clone()),
+        }
+    }
+
+    /// Decodes the contents of the image, as returned by [`Image::read()`], into an RGBA buffer.
+    pub(crate) fn decode(&self, bytes: Vec<u8>) -> Result<RgbaImage> {
+        let image = match &self.source {
+            ImageSource::Path(source) => {
+                decode_image(source, bytes, ImageFormat::from_path(source).ok())?
+            }
+            ImageSource::Data(_, format) => {
+                decode_image(Path::new(""), bytes, ImageFormat::from_extension(format))?
+            }
+        };
+        Ok(self.apply_transparent_colour(image).into_rgba8())
+    }
+
+    /// Makes the pixels of the image's [transparent color](Image::transparent_colour) transparent,
+    /// converting the image to RGBA if it has one.
+    fn apply_transparent_colour(&self, image: DynamicImage) -> DynamicImage {
+        let Some(color) = self.transparent_colour else {
+            return image;
+        };
+        let mut image = image.into_rgba8();
+        for pixel in image.pixels_mut() {
+            if pixel.0[..3] == [color.red, color.green, color.blue] {
+                pixel.0[3] = 0;
+            }
+        }
+        DynamicImage::ImageRgba8(image)
+    }
+}
+
+impl Tileset {
+    /// Reads and decodes the image of the tileset into an RGBA buffer. Returns `None` for image
+    /// collection tilesets, whose images belong to their tiles; See [`Tile::load_image()`].
+    ///
+    /// Also see [`Image::load()`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    ///
+    /// let image = tileset.load_image(loader.reader_mut()).unwrap().unwrap();
+    /// assert_eq!(image.dimensions(), (448, 192));
+    /// # }
+    /// ```
+    pub fn load_image(&self, reader: &mut impl ResourceReader) -> Result<Option<RgbaImage>> {
+        self.image
+            .as_ref()
+            .map(|image| image.load(reader))
+            .transpose()
+    }
+}
+
+impl Tile<'_> {
+    /// Reads and decodes the image of the tile into an RGBA buffer. Returns `None` for tiles that
+    /// don't have their own image, i.e. the ones not in image collection tilesets; See
+    /// [`Tileset::load_image()`].
+    ///
+    /// Also see [`Image::load()`].
+    pub fn load_image(&self, reader: &mut impl ResourceReader) -> Result<Option<RgbaImage>> {
+        self.image
+            .as_ref()
+            .map(|image| image.load(reader))
+            .transpose()
+    }
+}
+
+/// Reads the whole image file at the path given.
+fn read_image(path: &Path, reader: &mut impl ResourceReader) -> Result<Vec<u8>> {
+    let load_error = |err: Box<dyn std::error::Error + Send + Sync>| Error::ResourceLoadingError {
+        path: path.to_owned(),
+        err,
+    };
+    let mut bytes = Vec::new();
+    reader
+        .read_from(path)
+        .map_err(|err| load_error(Box::new(err)))?
+        .read_to_end(&mut bytes)
+        .map_err(|err| load_error(Box::new(err)))?;
+    Ok(bytes)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "decode_image", level = "debug", skip_all, fields(path = %path.display()))
+)]
+fn decode_image(
+    path: &Path,
+    bytes: Vec<u8>,
+    fallback_format: Option<ImageFormat>,
+) -> Result<DynamicImage> {
+    let decoding_error =
+        |err: Box<dyn std::error::Error + Send + Sync>| Error::ImageDecodingError {
+            path: path.to_owned(),
+            err,
+        };
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|err| decoding_error(Box::new(err)))?;
+    if reader.format().is_none() {
+        // Fall back to the file extension or format hint when the format can't be guessed from
+        // the contents.
+        if let Some(format) = fallback_format {
+            reader.set_format(format);
+        }
+    }
+    reader.decode().map_err(|err| decoding_error(Box::new(err)))
+}
@@ -0,0 +1,185 @@
+//! Structures related to listing the external resources a map depends on.
+
+use std::{cell::RefCell, path::PathBuf, sync::Arc};
+
+use crate::{
+    Image, LayerData, LayerDataType, Map, ResourceCache, ResourcePath, Template, Tileset,
+    TilesetLocation,
+};
+
+/// The external resources a map pulled in while being loaded, obtained via
+/// [`Loader::load_tmx_map_with_dependencies()`](crate::Loader::load_tmx_map_with_dependencies).
+///
+/// Each list is in dependency order, without duplicates: Templates come after the tilesets they
+/// use, and images are listed last since every other resource may reference them. Resources that
+/// were already in the loader's cache are listed as well.
+#[derive(Debug, Clone, Default)]
+pub struct MapDependencies {
+    /// The external tilesets used by the map or by its templates, along with the tilesets
+    /// embedded in templates.
+    pub tilesets: Vec<Arc<Tileset>>,
+    /// The templates used by the map's objects.
+    pub templates: Vec<Arc<Template>>,
+    /// The source paths of every image referenced by the map, its tilesets and its templates.
+    pub images: Vec<PathBuf>,
+}
+
+impl MapDependencies {
+    fn push_tileset(&mut self, tileset: Arc<Tileset>) {
+        if !self.tilesets.iter().any(|t| Arc::ptr_eq(t, &tileset)) {
+            self.tilesets.push(tileset);
+        }
+    }
+
+    fn push_template(&mut self, template: Arc<Template>) {
+        if let Some(tileset) = &template.tileset {
+            self.push_tileset(tileset.clone());
+        }
+        if !self.templates.iter().any(|t| Arc::ptr_eq(t, &template)) {
+            self.templates.push(template);
+        }
+    }
+}
+
+/// A [`ResourceCache`] that records every resource that goes through it, be it a cache hit or a
+/// newly parsed resource.
+pub(crate) struct RecordingCache<'c, C> {
+    cache: &'c mut C,
+    recorded: RefCell<MapDependencies>,
+}
+
+impl<'c, C: ResourceCache> RecordingCache<'c, C> {
+    pub(crate) fn new(cache: &'c mut C) -> Self {
+        Self {
+            cache,
+            recorded: RefCell::new(MapDependencies::default()),
+        }
+    }
+
+    /// Returns the recorded dependencies, along with the images used by the map given.
+    pub(crate) fn into_dependencies(self, map: &Map) -> MapDependencies {
+        let mut dependencies = self.recorded.into_inner();
+        let mut images = map_images(map);
+        for tileset in &dependencies.tilesets {
+            tileset_images(tileset, &mut images);
+        }
+        dependencies.images = image_sources(images);
+        dependencies
+    }
+}
+
+impl<C: ResourceCache> ResourceCache for RecordingCache<'_, C> {
+    fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        let tileset = self.cache.get_tileset(path)?;
+        self.recorded.borrow_mut().push_tileset(tileset.clone());
+        Some(tileset)
+    }
+
+    fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>) {
+        self.recorded.get_mut().push_tileset(tileset.clone());
+        self.cache.insert_tileset(path, tileset);
+    }
+
+    fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        let template = self.cache.get_template(path)?;
+        self.recorded.borrow_mut().push_template(template.clone());
+        Some(template)
+    }
+
+    fn insert_template(&mut self, path: impl AsRef<ResourcePath>, template: Arc<Template>) {
+        self.recorded.get_mut().push_template(template.clone());
+        self.cache.insert_template(path, template);
+    }
+
+    fn get_or_insert_tileset(
+        &mut self,
+        path: impl AsRef<ResourcePath>,
+        tileset: Arc<Tileset>,
+    ) -> Arc<Tileset> {
+        let tileset = self.cache.get_or_insert_tileset(path, tileset);
+        self.recorded.get_mut().push_tileset(tileset.clone());
+        tileset
+    }
+
+    fn get_or_insert_template(
+        &mut self,
+        path: impl AsRef<ResourcePath>,
+        template: Arc<Template>,
+    ) -> Arc<Template> {
+        let template = self.cache.get_or_insert_template(path, template);
+        self.recorded.get_mut().push_template(template.clone());
+        template
+    }
+}
+
+/// Returns the images referenced by a map that aren't embedded in its files, one per source
+/// path, in the order they are found.
+pub(crate) fn map_images(map: &Map) -> Vec<&Image> {
+    let mut images = Vec::new();
+    for tileset in map.tilesets() {
+        tileset_images(tileset, &mut images);
+    }
+    layer_images(&map.layers, &mut images);
+    images
+}
+
+/// Returns the unique sources of all the images referenced by a map, in the order they are found.
+pub(crate) fn map_image_sources(map: &Map) -> Vec<PathBuf> {
+    image_sources(map_images(map))
+}
+
+fn image_sources(images: Vec<&Image>) -> Vec<PathBuf> {
+    images
+        .into_iter()
+        .filter_map(|image| image.source.as_path())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Records an image, unless its data is embedded in the file itself or an image with the same
+/// source was already recorded.
+fn push_image<'a>(image: &'a Image, images: &mut Vec<&'a Image>) {
+    let Some(source) = image.source.as_path() else {
+        return;
+    };
+    if !images
+        .iter()
+        .any(|recorded| recorded.source.as_path() == Some(source))
+    {
+        images.push(image);
+    }
+}
+
+fn tileset_images<'a>(tileset: &'a Tileset, images: &mut Vec<&'a Image>) {
+    if let Some(image) = &tileset.image {
+        push_image(image, images);
+    }
+    for (_, tile) in tileset.tiles() {
+        if let Some(image) = &tile.data.image {
+            push_image(image, images);
+        }
+    }
+}
+
+fn layer_images<'a>(layers: &'a [LayerData], images: &mut Vec<&'a Image>) {
+    for layer in layers {
+        match &layer.layer_type {
+            LayerDataType::Image(image_layer) => {
+                if let Some(image) = &image_layer.image {
+                    push_image(image, images);
+                }
+            }
+            LayerDataType::Objects(objects) => {
+                for object in objects.object_data() {
+                    if let Some(TilesetLocation::Template(tileset)) =
+                        object.tile.as_ref().map(|tile| &tile.tileset_location)
+                    {
+                        tileset_images(tileset, images);
+                    }
+                }
+            }
+            LayerDataType::Group(group) => layer_images(group.layer_data(), images),
+            LayerDataType::Tiles(_) => {}
+        }
+    }
+}
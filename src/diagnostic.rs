@@ -0,0 +1,181 @@
+//! Structures related to problems found while loading files, either recovered from in lenient
+//! mode or reported as warnings.
+
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use crate::Error;
+
+/// A problem that was found while loading a map in
+/// [lenient mode](crate::LoaderOptions::lenient), and which was recovered from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    /// The path to the file the problem was found in.
+    pub path: PathBuf,
+    /// What the problem affected, and how it was recovered from.
+    pub kind: DiagnosticKind,
+    /// A description of the error that caused the problem.
+    pub message: String,
+}
+
+/// What a [`Diagnostic`] affected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A layer failed to parse, and was replaced by an empty layer of the same kind.
+    ReplacedLayer {
+        /// The ID of the layer, or 0 if it couldn't be read.
+        id: u32,
+        /// The name of the layer, or an empty string if it couldn't be read.
+        name: String,
+    },
+    /// A tileset failed to load, and was replaced by an empty tileset so that the GIDs of the
+    /// following tilesets are still valid.
+    ReplacedTileset {
+        /// The index of the tileset within the map.
+        index: usize,
+    },
+}
+
+/// A problem that was found while parsing a TMX, TSX or TX file, but which didn't prevent it from
+/// loading. Warnings are passed to the
+/// [`LoaderOptions::warning_handler`](crate::LoaderOptions::warning_handler), and collected by
+/// [`Loader::take_warnings()`](crate::Loader::take_warnings).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseWarning {
+    /// The path to the file the problem was found in.
+    pub path: PathBuf,
+    /// The byte offset within the file of the element the problem was found in.
+    pub position: u64,
+    /// What the problem is.
+    pub kind: ParseWarningKind,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (byte {}): ", self.path.display(), self.position)?;
+        match &self.kind {
+            ParseWarningKind::DeprecatedAttribute {
+                element,
+                name,
+                replacement,
+            } => write!(
+                f,
+                "attribute '{}' of <{}> is deprecated, use '{}' instead",
+                name, element, replacement
+            ),
+            ParseWarningKind::MissingAttribute {
+                element,
+                name,
+                default,
+            } => write!(
+                f,
+                "<{}> has no '{}' attribute, assuming {}",
+                element, name, default
+            ),
+            ParseWarningKind::UnknownElement { name, parent } if parent.is_empty() => {
+                write!(f, "unknown root element <{}> was skipped", name)
+            }
+            ParseWarningKind::UnknownElement { name, parent } => {
+                write!(f, "unknown element <{}> in <{}> was skipped", name, parent)
+            }
+            ParseWarningKind::UnknownAttribute { element, name } => {
+                write!(
+                    f,
+                    "unknown attribute '{}' of <{}> was skipped",
+                    name, element
+                )
+            }
+        }
+    }
+}
+
+/// What a [`ParseWarning`] is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ParseWarningKind {
+    /// An attribute that was renamed by a later version of the format was used, such as the
+    /// `class` attribute of objects and tiles, which Tiled 1.10 renamed back to `type`.
+    DeprecatedAttribute {
+        /// The name of the element the attribute is in.
+        element: String,
+        /// The name of the attribute.
+        name: String,
+        /// The name of the attribute that should be used instead.
+        replacement: String,
+    },
+    /// An attribute that recent versions of Tiled always write was missing, and a default was
+    /// used in its place.
+    MissingAttribute {
+        /// The name of the element the attribute is missing from.
+        element: String,
+        /// The name of the attribute.
+        name: String,
+        /// A description of the value that was used instead.
+        default: String,
+    },
+    /// An element that the file format doesn't define was skipped, in
+    /// [lenient mode](crate::LoaderOptions::lenient). The elements inside of it aren't reported.
+    UnknownElement {
+        /// The name of the element.
+        name: String,
+        /// The name of the element containing it, or an empty string for the root element.
+        parent: String,
+    },
+    /// An attribute that the file format doesn't define was skipped, in
+    /// [lenient mode](crate::LoaderOptions::lenient).
+    UnknownAttribute {
+        /// The name of the element the attribute is in.
+        element: String,
+        /// The name of the attribute.
+        name: String,
+    },
+}
+
+/// A function that receives the [warnings](ParseWarning) found while parsing files, set through
+/// [`LoaderOptions::warning_handler`](crate::LoaderOptions::warning_handler).
+///
+/// Two handlers are only equal if they are clones of each other.
+#[derive(Clone)]
+pub struct WarningHandler(Arc<dyn Fn(&ParseWarning) + Send + Sync>);
+
+impl WarningHandler {
+    /// Creates a handler from the function given.
+    pub fn new(handle: impl Fn(&ParseWarning) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handle))
+    }
+
+    /// Passes a warning to the handler.
+    pub fn handle(&self, warning: &ParseWarning) {
+        (self.0)(warning)
+    }
+}
+
+impl fmt::Debug for WarningHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WarningHandler").finish()
+    }
+}
+
+impl PartialEq for WarningHandler {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for WarningHandler {}
+
+impl Error {
+    /// Whether the parser can skip the element this error occurred in and continue parsing the
+    /// rest of the file. Errors caused by malformed XML can't be recovered from, and cancellation
+    /// stops loading altogether.
+    pub(crate) fn is_recoverable(&self) -> bool {
+        !matches!(
+            self,
+            Error::XmlDecodingError(_) | Error::PrematureEnd(_) | Error::LoadingCancelled
+        )
+    }
+}
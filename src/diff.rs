@@ -0,0 +1,433 @@
+//! Structures related to describing the differences between two maps in detail.
+
+use std::{collections::BTreeSet, fmt, sync::Arc};
+
+use crate::{
+    changes::{
+        collect_layers, collect_objects, for_each_block, layer_settings_eq, map_settings_eq,
+    },
+    LayerDataType, LayerTileData, Map, ObjectData, ObjectShape, Properties, PropertyValue, TileId,
+    TileLayerData, TilesetLocation,
+};
+
+/// What a [`MapChange::PropertyChanged`] change applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyOwner {
+    /// The map itself.
+    Map,
+    /// The layer with the ID given.
+    Layer(u32),
+    /// The object with the ID given.
+    Object(u32),
+}
+
+/// A single difference between two maps, as found by [`Map::diff()`].
+///
+/// Layers and objects are identified by their IDs, and tilesets by their names.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum MapChange {
+    /// An attribute of the map other than its tilesets and properties changed, such as its size
+    /// or orientation.
+    MapModified,
+    /// A tileset is only referenced by the new map.
+    TilesetAdded {
+        /// The name of the tileset.
+        name: String,
+    },
+    /// A tileset is only referenced by the old map.
+    TilesetRemoved {
+        /// The name of the tileset.
+        name: String,
+    },
+    /// A tileset referenced by both maps has different contents.
+    TilesetModified {
+        /// The name of the tileset.
+        name: String,
+    },
+    /// A layer is only present in the new map.
+    LayerAdded {
+        /// The ID of the layer.
+        layer_id: u32,
+    },
+    /// A layer is only present in the old map.
+    LayerRemoved {
+        /// The ID of the layer.
+        layer_id: u32,
+    },
+    /// An attribute of a layer present in both maps changed, such as its name, visibility or
+    /// offset. Changes to its properties and contents are reported separately.
+    LayerModified {
+        /// The ID of the layer.
+        layer_id: u32,
+    },
+    /// A tile of a tile layer present in both maps changed.
+    TileChanged {
+        /// The ID of the tile layer.
+        layer_id: u32,
+        /// The X coordinate of the tile within the layer.
+        x: i32,
+        /// The Y coordinate of the tile within the layer.
+        y: i32,
+        /// The tile in the old map, whose tileset index refers to the tilesets of the old map.
+        before: Option<LayerTileData>,
+        /// The tile in the new map, whose tileset index refers to the tilesets of the new map.
+        after: Option<LayerTileData>,
+    },
+    /// An object is only present in the new map.
+    ObjectAdded {
+        /// The ID of the object layer the object is in.
+        layer_id: u32,
+        /// The ID of the object.
+        object_id: u32,
+    },
+    /// An object is only present in the old map.
+    ObjectRemoved {
+        /// The ID of the object layer the object was in.
+        layer_id: u32,
+        /// The ID of the object.
+        object_id: u32,
+    },
+    /// An object present in both maps changed position.
+    ObjectMoved {
+        /// The ID of the object.
+        object_id: u32,
+        /// The position of the object in the old map, in pixels.
+        from: (f32, f32),
+        /// The position of the object in the new map, in pixels.
+        to: (f32, f32),
+    },
+    /// An attribute of an object present in both maps other than its position and properties
+    /// changed, such as its name, shape or tile, or the object moved to another layer.
+    ObjectModified {
+        /// The ID of the object layer the object is in in the new map.
+        layer_id: u32,
+        /// The ID of the object.
+        object_id: u32,
+    },
+    /// A custom property of the map, or of a layer or object present in both maps, was added,
+    /// removed or changed.
+    PropertyChanged {
+        /// What the property belongs to.
+        owner: PropertyOwner,
+        /// The name of the property.
+        name: Arc<str>,
+        /// The value of the property in the old map.
+        before: Option<PropertyValue>,
+        /// The value of the property in the new map.
+        after: Option<PropertyValue>,
+    },
+}
+
+impl fmt::Display for PropertyOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyOwner::Map => write!(f, "map"),
+            PropertyOwner::Layer(id) => write!(f, "layer {}", id),
+            PropertyOwner::Object(id) => write!(f, "object {}", id),
+        }
+    }
+}
+
+impl fmt::Display for MapChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapChange::MapModified => write!(f, "map attributes changed"),
+            MapChange::TilesetAdded { name } => write!(f, "tileset \"{}\" added", name),
+            MapChange::TilesetRemoved { name } => write!(f, "tileset \"{}\" removed", name),
+            MapChange::TilesetModified { name } => write!(f, "tileset \"{}\" changed", name),
+            MapChange::LayerAdded { layer_id } => write!(f, "layer {} added", layer_id),
+            MapChange::LayerRemoved { layer_id } => write!(f, "layer {} removed", layer_id),
+            MapChange::LayerModified { layer_id } => {
+                write!(f, "layer {} attributes changed", layer_id)
+            }
+            MapChange::TileChanged {
+                layer_id,
+                x,
+                y,
+                before,
+                after,
+            } => write!(
+                f,
+                "tile ({}, {}) of layer {} changed from {:?} to {:?}",
+                x, y, layer_id, before, after
+            ),
+            MapChange::ObjectAdded {
+                layer_id,
+                object_id,
+            } => write!(f, "object {} added to layer {}", object_id, layer_id),
+            MapChange::ObjectRemoved {
+                layer_id,
+                object_id,
+            } => write!(f, "object {} removed from layer {}", object_id, layer_id),
+            MapChange::ObjectMoved {
+                object_id,
+                from,
+                to,
+            } => write!(
+                f,
+                "object {} moved from ({}, {}) to ({}, {})",
+                object_id, from.0, from.1, to.0, to.1
+            ),
+            MapChange::ObjectModified {
+                layer_id,
+                object_id,
+            } => write!(f, "object {} of layer {} changed", object_id, layer_id),
+            MapChange::PropertyChanged {
+                owner,
+                name,
+                before,
+                after,
+            } => write!(
+                f,
+                "property \"{}\" of {} changed from {:?} to {:?}",
+                name, owner, before, after
+            ),
+        }
+    }
+}
+
+/// The detailed differences between two maps, as returned by [`Map::diff()`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MapDiff {
+    /// The changes found, ordered by what they apply to: The map itself, then its tilesets, its
+    /// layers by ID with their tiles in row-major order, and finally its objects by ID.
+    pub changes: Vec<MapChange>,
+}
+
+impl MapDiff {
+    /// Returns `true` if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Map {
+    /// Lists the changes that turn this map into `other`, such as the tiles that changed in each
+    /// tile layer, the objects that were added, removed or moved, the properties that changed
+    /// and the tilesets that were added or removed. Useful to review level changes semantically
+    /// instead of comparing files as text.
+    ///
+    /// Tiles are compared by the name of their tileset rather than its index, so that reordering
+    /// tilesets doesn't change every tile. Unlike [`Map::changes_since()`], which is meant for
+    /// hot reloading, every changed tile is reported individually.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, MapChange, PropertyOwner};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let before = loader.load_tmx_map("assets/tiled_diff_before.tmx").unwrap();
+    /// let after = loader.load_tmx_map("assets/tiled_diff_after.tmx").unwrap();
+    ///
+    /// let diff = before.diff(&after);
+    /// assert!(diff.changes.iter().any(|change| matches!(
+    ///     change,
+    ///     MapChange::PropertyChanged { owner: PropertyOwner::Map, .. }
+    /// )));
+    /// for change in &diff.changes {
+    ///     println!("{}", change);
+    /// }
+    /// # }
+    /// ```
+    pub fn diff(&self, other: &Map) -> MapDiff {
+        let mut differ = Differ {
+            old: self,
+            new: other,
+            changes: Vec::new(),
+        };
+        differ.diff_map();
+        MapDiff {
+            changes: differ.changes,
+        }
+    }
+}
+
+struct Differ<'a> {
+    old: &'a Map,
+    new: &'a Map,
+    changes: Vec<MapChange>,
+}
+
+impl Differ<'_> {
+    fn diff_map(&mut self) {
+        if !map_settings_eq(self.old, self.new) {
+            self.changes.push(MapChange::MapModified);
+        }
+        self.diff_properties(
+            PropertyOwner::Map,
+            &self.old.properties,
+            &self.new.properties,
+        );
+
+        for tileset in self.old.tilesets() {
+            let name = tileset.name.clone();
+            match self.new.tilesets().iter().find(|t| t.name == name) {
+                None => self.changes.push(MapChange::TilesetRemoved { name }),
+                Some(new) if new != tileset => {
+                    self.changes.push(MapChange::TilesetModified { name })
+                }
+                Some(_) => {}
+            }
+        }
+        for tileset in self.new.tilesets() {
+            if !self.old.tilesets().iter().any(|t| t.name == tileset.name) {
+                self.changes.push(MapChange::TilesetAdded {
+                    name: tileset.name.clone(),
+                });
+            }
+        }
+
+        let old_layers = collect_layers(&self.old.layers);
+        let new_layers = collect_layers(&self.new.layers);
+        let ids: BTreeSet<u32> = old_layers
+            .keys()
+            .chain(new_layers.keys())
+            .copied()
+            .collect();
+        for layer_id in ids {
+            match (old_layers.get(&layer_id), new_layers.get(&layer_id)) {
+                (None, Some(_)) => self.changes.push(MapChange::LayerAdded { layer_id }),
+                (Some(_), None) => self.changes.push(MapChange::LayerRemoved { layer_id }),
+                (Some(old), Some(new)) => {
+                    if !layer_settings_eq(old, new) {
+                        self.changes.push(MapChange::LayerModified { layer_id });
+                    }
+                    self.diff_properties(
+                        PropertyOwner::Layer(layer_id),
+                        &old.properties,
+                        &new.properties,
+                    );
+                    if let (LayerDataType::Tiles(old), LayerDataType::Tiles(new)) =
+                        (&old.layer_type, &new.layer_type)
+                    {
+                        self.diff_tiles(layer_id, old, new);
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+
+        let old_objects = collect_objects(&old_layers);
+        let new_objects = collect_objects(&new_layers);
+        let ids: BTreeSet<u32> = old_objects
+            .keys()
+            .chain(new_objects.keys())
+            .copied()
+            .collect();
+        for object_id in ids {
+            match (old_objects.get(&object_id), new_objects.get(&object_id)) {
+                (None, Some(&(layer_id, _))) => self.changes.push(MapChange::ObjectAdded {
+                    layer_id,
+                    object_id,
+                }),
+                (Some(&(layer_id, _)), None) => self.changes.push(MapChange::ObjectRemoved {
+                    layer_id,
+                    object_id,
+                }),
+                (Some(&(old_layer_id, old)), Some(&(layer_id, new))) => {
+                    if (old.x, old.y) != (new.x, new.y) {
+                        self.changes.push(MapChange::ObjectMoved {
+                            object_id,
+                            from: (old.x, old.y),
+                            to: (new.x, new.y),
+                        });
+                    }
+                    if old_layer_id != layer_id || !self.object_settings_eq(old, new) {
+                        self.changes.push(MapChange::ObjectModified {
+                            layer_id,
+                            object_id,
+                        });
+                    }
+                    self.diff_properties(
+                        PropertyOwner::Object(object_id),
+                        &old.properties,
+                        &new.properties,
+                    );
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+
+    fn diff_properties(&mut self, owner: PropertyOwner, old: &Properties, new: &Properties) {
+        let names: BTreeSet<&Arc<str>> = old.keys().chain(new.keys()).collect();
+        for name in names {
+            let (before, after) = (old.get(name), new.get(name));
+            if before != after {
+                self.changes.push(MapChange::PropertyChanged {
+                    owner,
+                    name: name.clone(),
+                    before: before.cloned(),
+                    after: after.cloned(),
+                });
+            }
+        }
+    }
+
+    fn diff_tiles(&mut self, layer_id: u32, old: &TileLayerData, new: &TileLayerData) {
+        let mut tiles = Vec::new();
+        for_each_block(old, new, |x, y, width, height| {
+            for ty in y..y + height {
+                for tx in x..x + width {
+                    let (before, after) = (old.get_tile_data(tx, ty), new.get_tile_data(tx, ty));
+                    let old_key = before.map(|tile| layer_tile_key(self.old, tile));
+                    let new_key = after.map(|tile| layer_tile_key(self.new, tile));
+                    if old_key != new_key {
+                        tiles.push(MapChange::TileChanged {
+                            layer_id,
+                            x: tx,
+                            y: ty,
+                            before: before.copied(),
+                            after: after.copied(),
+                        });
+                    }
+                }
+            }
+        });
+        tiles.sort_by_key(|change| match change {
+            MapChange::TileChanged { x, y, .. } => (*y, *x),
+            _ => unreachable!(),
+        });
+        self.changes.append(&mut tiles);
+    }
+
+    fn object_settings_eq(&self, old: &ObjectData, new: &ObjectData) -> bool {
+        // Points hold the position of their object, which is reported separately.
+        let same_shape = matches!(
+            (&old.shape, &new.shape),
+            (ObjectShape::Point(..), ObjectShape::Point(..))
+        ) || old.shape == new.shape;
+        same_shape
+            && old.name == new.name
+            && old.user_type == new.user_type
+            && old.rotation == new.rotation
+            && old.visible == new.visible
+            && object_tile_key(self.old, old) == object_tile_key(self.new, new)
+    }
+}
+
+/// Identifies a tile regardless of the index of its tileset in its map.
+type TileKey<'a> = (&'a str, TileId, [bool; 4]);
+
+fn layer_tile_key<'a>(map: &'a Map, tile: &LayerTileData) -> TileKey<'a> {
+    (
+        &map.tilesets()[tile.tileset_index()].name,
+        tile.id(),
+        [tile.flip_h, tile.flip_v, tile.flip_d, tile.rotate_hex_120],
+    )
+}
+
+fn object_tile_key<'a>(map: &'a Map, object: &'a ObjectData) -> Option<TileKey<'a>> {
+    let tile = object.tile.as_ref()?;
+    let tileset = match &tile.tileset_location {
+        TilesetLocation::Map(index) => &map.tilesets()[*index],
+        TilesetLocation::Template(tileset) => tileset,
+    };
+    Some((
+        &tileset.name,
+        tile.id(),
+        [tile.flip_h, tile.flip_v, tile.flip_d, false],
+    ))
+}
@@ -57,6 +57,11 @@ pub enum Error {
     CsvDecodingError(CsvDecodingError),
     /// An error occurred when parsing an XML file, such as a TMX or TSX file.
     XmlDecodingError(quick_xml::Error),
+    /// An error occurred when writing an XML file, such as a TMX or TSX file.
+    XmlEncodingError(quick_xml::Error),
+    /// An error occurred when parsing a JSON file, such as a TMJ or TSJ file.
+    #[cfg(feature = "json")]
+    JsonDecodingError(serde_json::Error),
     /// The XML stream ended before the document was fully parsed.
     PrematureEnd(String),
     /// The path given is invalid because it isn't contained in any folder.
@@ -78,6 +83,14 @@ pub enum Error {
         /// The `compression` attribute of the tile layer data, if any.
         compression: Option<String>,
     },
+    /// Tile layer data was compressed with a format whose support is disabled, because a cargo
+    /// feature of this crate isn't enabled.
+    CompressionFeatureDisabled {
+        /// The `compression` attribute of the tile layer data.
+        compression: String,
+        /// The feature that needs to be enabled to decompress the data.
+        feature: &'static str,
+    },
     /// There was an error parsing the value of a [`PropertyValue`].
     ///
     /// [`PropertyValue`]: crate::PropertyValue
@@ -107,6 +120,65 @@ pub enum Error {
     },
     /// There was an invalid tileset in the map parsed.
     InvalidTileset(InvalidTilesetError),
+    /// Group layers were nested deeper than allowed by
+    /// [`LoaderOptions::max_group_depth`](crate::LoaderOptions::max_group_depth).
+    GroupNestingTooDeep {
+        /// The maximum depth that was exceeded.
+        max_depth: usize,
+    },
+    /// An error occurred when decoding an image referenced by a map.
+    ImageDecodingError {
+        /// The path to the image that was unable to be decoded, which is empty for images embedded
+        /// in the file.
+        path: PathBuf,
+        /// The error that occurred when trying to decode the image.
+        err: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+    /// An automapping rule map or rules file was not valid.
+    InvalidAutomappingRules {
+        /// A description of the error that occurred.
+        description: String,
+    },
+    /// A layer that doesn't fit the map was added to it through
+    /// [`Map::add_layer()`](crate::Map::add_layer), or a tile that doesn't fit its layer was set
+    /// through [`TileLayerMut::set_tile()`](crate::TileLayerMut::set_tile).
+    InvalidLayer {
+        /// A description of the error that occurred.
+        description: String,
+    },
+    /// Loading a map was cancelled through the
+    /// [`LoaderOptions::cancellation_token`](crate::LoaderOptions::cancellation_token).
+    LoadingCancelled,
+    /// A global tile ID passed to [`Map::remap_gids()`](crate::Map::remap_gids) doesn't belong to
+    /// any of the map's tilesets.
+    InvalidGid(u32),
+    /// An element that the file format doesn't define was found while parsing in
+    /// [strict mode](crate::LoaderOptions::strict).
+    UnknownElement {
+        /// The name of the unknown element.
+        name: String,
+        /// The name of the element it was found in, which is empty for the root element.
+        parent: String,
+    },
+    /// An attribute that the file format doesn't define for the element it is in was found while
+    /// parsing in [strict mode](crate::LoaderOptions::strict).
+    UnknownAttribute {
+        /// The name of the element the attribute is in.
+        element: String,
+        /// The name of the unknown attribute.
+        name: String,
+    },
+    /// A precompiled map couldn't be read, because it is corrupted or was written by another
+    /// version of the crate. Only returned with the `precompiled` feature.
+    #[cfg(feature = "precompiled")]
+    InvalidPrecompiledMap {
+        /// A description of the error that occurred.
+        description: String,
+    },
+    /// An error occurred when watching the files of a map through a
+    /// [`MapWatcher`](crate::MapWatcher). Only returned with the `watch` feature.
+    #[cfg(feature = "watch")]
+    WatchError(notify::Error),
 }
 
 /// A result with an error variant of [`crate::Error`].
@@ -120,6 +192,9 @@ impl fmt::Display for Error {
             Error::Base64DecodingError(e) => write!(fmt, "{}", e),
             Error::CsvDecodingError(e) => write!(fmt, "{}", e),
             Error::XmlDecodingError(e) => write!(fmt, "{}", e),
+            Error::XmlEncodingError(e) => write!(fmt, "{}", e),
+            #[cfg(feature = "json")]
+            Error::JsonDecodingError(e) => write!(fmt, "{}", e),
             Error::PrematureEnd(e) => write!(fmt, "{}", e),
             Error::PathIsNotFile => {
                 write!(
@@ -148,6 +223,12 @@ impl fmt::Display for Error {
                     encoding.as_deref().unwrap_or("no"),
                     compression.as_deref().unwrap_or("no")
                 ),
+            Error::CompressionFeatureDisabled { compression, feature } =>
+                write!(
+                    fmt,
+                    "Tile layer data is compressed with {}, which requires the `{}` feature of the tiled crate to be enabled",
+                    compression, feature
+                ),
             Error::InvalidPropertyValue{description} =>
                 write!(fmt, "Invalid property value: {}", description),
             Error::UnknownPropertyType { type_name } =>
@@ -158,6 +239,34 @@ impl fmt::Display for Error {
             Error::InvalidObjectData{description} =>
                 write!(fmt, "Invalid object data: {}", description),
             Error::InvalidTileset(e) => write!(fmt, "{}", e),
+            Error::GroupNestingTooDeep { max_depth } =>
+                write!(fmt, "Group layers are nested deeper than the maximum depth of {}", max_depth),
+            Error::ImageDecodingError { path, err } => {
+                write!(
+                    fmt,
+                    "Could not decode image '{}'. Error: {}",
+                    path.to_string_lossy(),
+                    err
+                )
+            }
+            Error::InvalidAutomappingRules{description} =>
+                write!(fmt, "Invalid automapping rules: {}", description),
+            Error::InvalidLayer{description} =>
+                write!(fmt, "Invalid layer: {}", description),
+            Error::LoadingCancelled => write!(fmt, "Loading was cancelled"),
+            Error::InvalidGid(gid) =>
+                write!(fmt, "Global tile ID {} doesn't belong to any tileset of the map", gid),
+            Error::UnknownElement { name, parent } if parent.is_empty() =>
+                write!(fmt, "Unknown root element <{}>", name),
+            Error::UnknownElement { name, parent } =>
+                write!(fmt, "Unknown element <{}> in <{}>", name, parent),
+            Error::UnknownAttribute { element, name } =>
+                write!(fmt, "Unknown attribute '{}' in <{}>", name, element),
+            #[cfg(feature = "precompiled")]
+            Error::InvalidPrecompiledMap{description} =>
+                write!(fmt, "Invalid precompiled map: {}", description),
+            #[cfg(feature = "watch")]
+            Error::WatchError(e) => write!(fmt, "{}", e),
         }
     }
 }
@@ -168,7 +277,13 @@ impl std::error::Error for Error {
             Error::DecompressingError(e) => Some(e as &dyn std::error::Error),
             Error::Base64DecodingError(e) => Some(e as &dyn std::error::Error),
             Error::XmlDecodingError(e) => Some(e as &dyn std::error::Error),
+            Error::XmlEncodingError(e) => Some(e as &dyn std::error::Error),
+            #[cfg(feature = "json")]
+            Error::JsonDecodingError(e) => Some(e as &dyn std::error::Error),
             Error::ResourceLoadingError { err, .. } => Some(err.as_ref()),
+            Error::ImageDecodingError { err, .. } => Some(err.as_ref()),
+            #[cfg(feature = "watch")]
+            Error::WatchError(e) => Some(e as &dyn std::error::Error),
             _ => None,
         }
     }
@@ -0,0 +1,146 @@
+//! Structures related to applying Tiled's export options to loaded maps.
+
+use std::sync::Arc;
+
+use crate::{LayerData, LayerDataType, Map, ObjectData, TilesetLocation};
+
+/// The export options of a Tiled project, which change how a map would look like once exported.
+///
+/// When set on a [`Loader`](crate::Loader) through
+/// [`Loader::set_export_options()`](crate::Loader::set_export_options), the same resolutions are
+/// applied to every loaded map, so that the runtime data matches what an exported map would
+/// contain. They can also be applied manually through [`Map::apply_export_options()`].
+///
+/// Also see the [Tiled docs](https://doc.mapeditor.org/en/stable/manual/export/#export-options).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExportOptions {
+    /// Whether objects instantiated from templates reference the map's tilesets instead of the
+    /// templates' ones. Tilesets only used by templates are added to the map.
+    pub detach_templates: bool,
+    /// Whether every tileset is owned by its map instead of being shared with other maps through
+    /// the loader's [`ResourceCache`](crate::ResourceCache), like embedded tilesets are.
+    pub embed_tilesets: bool,
+    /// Whether tile objects inherit the type and custom properties of their tile. Properties set
+    /// on the object itself take precedence.
+    pub resolve_object_types_and_properties: bool,
+}
+
+impl ExportOptions {
+    /// Export options with every resolution enabled.
+    pub fn all() -> Self {
+        Self {
+            detach_templates: true,
+            embed_tilesets: true,
+            resolve_object_types_and_properties: true,
+        }
+    }
+}
+
+impl Map {
+    /// Applies the resolutions described by the given [`ExportOptions`] to the map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{ExportOptions, Loader, TilesetLocation};
+    ///
+    /// # fn main() {
+    /// let mut map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_template.tmx")
+    ///     .unwrap();
+    /// map.apply_export_options(&ExportOptions {
+    ///     detach_templates: true,
+    ///     ..Default::default()
+    /// });
+    ///
+    /// for layer in map.layers() {
+    ///     for object in layer.as_object_layer().into_iter().flat_map(|l| l.objects()) {
+    ///         if let Some(tile) = object.tile_data() {
+    ///             assert!(matches!(tile.tileset_location(), TilesetLocation::Map(_)));
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn apply_export_options(&mut self, options: &ExportOptions) {
+        if options.embed_tilesets {
+            for tileset in &mut self.tilesets {
+                *tileset = Arc::new((**tileset).clone());
+            }
+        }
+
+        let mut layers = std::mem::take(&mut self.layers);
+        for_each_object(&mut layers, &mut |object| {
+            if options.detach_templates {
+                self.detach_template_tileset(object);
+            } else if options.embed_tilesets {
+                if let Some(tile) = &mut object.tile {
+                    if let TilesetLocation::Template(tileset) = &mut tile.tileset_location {
+                        *tileset = Arc::new((**tileset).clone());
+                    }
+                }
+            }
+            if options.resolve_object_types_and_properties {
+                self.resolve_object_type(object);
+            }
+        });
+        self.layers = layers;
+    }
+
+    fn detach_template_tileset(&mut self, object: &mut ObjectData) {
+        let Some(tile) = &mut object.tile else {
+            return;
+        };
+        let TilesetLocation::Template(tileset) = &tile.tileset_location else {
+            return;
+        };
+        let index = match self
+            .tilesets
+            .iter()
+            .position(|t| Arc::ptr_eq(t, tileset) || **t == **tileset)
+        {
+            Some(index) => index,
+            None => {
+                self.tilesets.push(tileset.clone());
+                self.gid_table.invalidate();
+                self.tilesets.len() - 1
+            }
+        };
+        tile.tileset_location = TilesetLocation::Map(index);
+    }
+
+    fn resolve_object_type(&self, object: &mut ObjectData) {
+        let Some(tile_data) = &object.tile else {
+            return;
+        };
+        let tileset = match &tile_data.tileset_location {
+            TilesetLocation::Map(index) => &self.tilesets[*index],
+            TilesetLocation::Template(tileset) => tileset,
+        };
+        let Some(tile) = tileset.get_tile(tile_data.id()) else {
+            return;
+        };
+
+        if object.user_type.is_empty() {
+            if let Some(user_type) = &tile.user_type {
+                object.user_type = user_type.clone();
+            }
+        }
+        for (name, value) in &tile.properties {
+            if !object.properties.contains_key(name) {
+                object.properties.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+fn for_each_object(layers: &mut [LayerData], f: &mut impl FnMut(&mut ObjectData)) {
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Objects(objects) => {
+                objects.object_data_mut().iter_mut().for_each(&mut *f)
+            }
+            LayerDataType::Group(group) => for_each_object(group.layer_data_mut(), f),
+            _ => {}
+        }
+    }
+}
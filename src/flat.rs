@@ -0,0 +1,301 @@
+//! A flat, owned representation of a map, designed for spawning its contents into ECS worlds.
+
+use std::sync::Arc;
+
+use crate::{
+    ChunkData, Image, Layer, LayerTileData, LayerType, Map, ObjectShape, Properties, TileId,
+    TileLayer, Tileset, TilesetLocation,
+};
+
+/// A flat, owned view over the contents of a [`Map`], obtained via [`Map::to_flat()`].
+///
+/// Unlike the borrowed handles returned by the map (such as [`Layer`] or
+/// [`Object`](crate::Object)), every value in this structure is owned, which makes it suitable for
+/// moving across an engine integration boundary, e.g. for bulk spawning entities into an ECS world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatMap {
+    /// The tilesets used by the map, in the same order as [`Map::tilesets()`]. Indexed by
+    /// [`FlatTile::tileset_index`].
+    pub tilesets: Vec<Arc<Tileset>>,
+    /// Every non-group layer in the map, in display order. Indexed by the `layer` member of each
+    /// entity.
+    pub layers: Vec<FlatLayer>,
+    /// Every entity in the map, in display order.
+    pub entities: Vec<FlatEntity>,
+}
+
+/// A layer of a [`FlatMap`], with all attributes inherited from its parent groups already applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatLayer {
+    /// The layer's ID.
+    pub id: u32,
+    /// The layer's name.
+    pub name: Arc<str>,
+    /// The IDs of the group layers containing this layer, from the outermost to the innermost one.
+    pub parents: Vec<u32>,
+    /// The position of this layer in display order, counting from 0. Layers with bigger values
+    /// are drawn on top of the ones with lower values.
+    pub z_index: usize,
+    /// Whether the layer and all of its parents are visible.
+    pub visible: bool,
+    /// The opacity of the layer multiplied with the one of all its parents.
+    pub opacity: f32,
+    /// The total offset of the layer (in pixels), including the one of its parents.
+    pub offset: (f32, f32),
+    /// The parallax factor of the layer multiplied with the one of all its parents.
+    pub parallax: (f32, f32),
+    /// The layer's custom properties.
+    pub properties: Properties,
+}
+
+/// An entity of a [`FlatMap`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlatEntity {
+    /// A tile inside of a tile layer.
+    Tile(FlatTile),
+    /// An object inside of an object layer.
+    Object(FlatObject),
+    /// The image of an image layer.
+    Image(FlatImage),
+}
+
+impl FlatEntity {
+    /// Returns the index of the [`FlatLayer`] this entity belongs to.
+    pub fn layer(&self) -> usize {
+        match self {
+            FlatEntity::Tile(tile) => tile.layer,
+            FlatEntity::Object(object) => object.layer,
+            FlatEntity::Image(image) => image.layer,
+        }
+    }
+}
+
+/// A tile of a tile layer, along with its world transform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlatTile {
+    /// The index of the [`FlatLayer`] this tile belongs to.
+    pub layer: usize,
+    /// The position of the tile in the layer, in tiles.
+    pub tile_position: (i32, i32),
+    /// The world position, in pixels, of the top-left corner of the cell that contains this tile.
+    /// Includes the offset of the tile's layer.
+    pub position: (f32, f32),
+    /// The index of the tileset the tile is from in [`FlatMap::tilesets`].
+    pub tileset_index: usize,
+    /// The local ID of the tile within its tileset.
+    pub id: TileId,
+    /// Whether this tile is flipped on its Y axis (horizontally).
+    pub flip_h: bool,
+    /// Whether this tile is flipped on its X axis (vertically).
+    pub flip_v: bool,
+    /// Whether this tile is flipped diagonally.
+    pub flip_d: bool,
+}
+
+/// The tile an [`FlatObject`] is using as image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatObjectTile {
+    /// The tileset the tile is from.
+    pub tileset: Arc<Tileset>,
+    /// The local ID of the tile within its tileset.
+    pub id: TileId,
+    /// Whether this tile is flipped on its Y axis (horizontally).
+    pub flip_h: bool,
+    /// Whether this tile is flipped on its X axis (vertically).
+    pub flip_v: bool,
+    /// Whether this tile is flipped diagonally.
+    pub flip_d: bool,
+}
+
+/// An object of an object layer, with its shape and properties fully resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatObject {
+    /// The index of the [`FlatLayer`] this object belongs to.
+    pub layer: usize,
+    /// The ID of the object.
+    pub id: u32,
+    /// The name of the object.
+    pub name: String,
+    /// The type of the object.
+    pub user_type: Arc<str>,
+    /// The world position of the object in pixels. Includes the offset of the object's layer.
+    pub position: (f32, f32),
+    /// The clockwise rotation of this object around its position, in degrees.
+    pub rotation: f32,
+    /// Whether the object and its layer are visible.
+    pub visible: bool,
+    /// The shape of the object.
+    pub shape: ObjectShape,
+    /// The tile the object is using as image, if any.
+    pub tile: Option<FlatObjectTile>,
+    /// The object's custom properties.
+    pub properties: Properties,
+}
+
+/// The image of an image layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatImage {
+    /// The index of the [`FlatLayer`] this image belongs to.
+    pub layer: usize,
+    /// The world position of the image, in pixels.
+    pub position: (f32, f32),
+    /// The image itself.
+    pub image: Image,
+}
+
+impl Map {
+    /// Converts the map into a [`FlatMap`]: A flat list of owned entities, with all group layers
+    /// resolved, which doesn't borrow from the map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{FlatEntity, Loader};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_group_layers.tmx")
+    ///     .unwrap();
+    /// let flat = map.to_flat();
+    ///
+    /// for entity in &flat.entities {
+    ///     if let FlatEntity::Tile(tile) = entity {
+    ///         let layer = &flat.layers[tile.layer];
+    ///         println!("{} at {:?} on layer {}", tile.id, tile.position, layer.name);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn to_flat(&self) -> FlatMap {
+        let mut flat = FlatMap {
+            tilesets: self.tilesets().to_vec(),
+            layers: Vec::new(),
+            entities: Vec::new(),
+        };
+        let root = FlatLayer {
+            id: 0,
+            name: Arc::from(""),
+            parents: Vec::new(),
+            z_index: 0,
+            visible: true,
+            opacity: 1.0,
+            offset: (0.0, 0.0),
+            parallax: (1.0, 1.0),
+            properties: Properties::new(),
+        };
+        for layer in self.layers() {
+            flatten_layer(self, layer, &root, &mut flat);
+        }
+        flat
+    }
+}
+
+fn flatten_layer(map: &Map, layer: Layer, parent: &FlatLayer, flat: &mut FlatMap) {
+    let mut flat_layer = FlatLayer {
+        id: layer.id(),
+        name: layer.name.clone(),
+        parents: parent.parents.clone(),
+        z_index: flat.layers.len(),
+        visible: parent.visible && layer.visible,
+        opacity: parent.opacity * layer.opacity,
+        offset: (
+            parent.offset.0 + layer.offset_x,
+            parent.offset.1 + layer.offset_y,
+        ),
+        parallax: (
+            parent.parallax.0 * layer.parallax_x,
+            parent.parallax.1 * layer.parallax_y,
+        ),
+        properties: layer.properties.clone(),
+    };
+
+    let layer_index = flat.layers.len();
+    let offset = flat_layer.offset;
+    let visible = flat_layer.visible;
+    match layer.layer_type() {
+        LayerType::Group(group) => {
+            flat_layer.parents.push(layer.id());
+            for child in group.layers() {
+                flatten_layer(map, child, &flat_layer, flat);
+            }
+            return;
+        }
+        LayerType::Tiles(tiles) => {
+            let mut push_tile = |x: i32, y: i32, data: &LayerTileData| {
+                let (px, py) = map.tile_to_world(x, y);
+                flat.entities.push(FlatEntity::Tile(FlatTile {
+                    layer: layer_index,
+                    tile_position: (x, y),
+                    position: (px + offset.0, py + offset.1),
+                    tileset_index: data.tileset_index(),
+                    id: data.id(),
+                    flip_h: data.flip_h,
+                    flip_v: data.flip_v,
+                    flip_d: data.flip_d,
+                }));
+            };
+            match tiles {
+                TileLayer::Finite(finite) => {
+                    for y in 0..finite.height() as i32 {
+                        for x in 0..finite.width() as i32 {
+                            if let Some(data) = finite.get_tile_data(x, y) {
+                                push_tile(x, y, data);
+                            }
+                        }
+                    }
+                }
+                TileLayer::Infinite(infinite) => {
+                    for ((chunk_x, chunk_y), chunk) in infinite.chunk_data() {
+                        for y in 0..ChunkData::HEIGHT as i32 {
+                            for x in 0..ChunkData::WIDTH as i32 {
+                                if let Some(data) = chunk.get_tile_data(x, y) {
+                                    push_tile(
+                                        chunk_x * ChunkData::WIDTH as i32 + x,
+                                        chunk_y * ChunkData::HEIGHT as i32 + y,
+                                        data,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        LayerType::Objects(objects) => {
+            for object in objects.objects() {
+                let tile = object.tile_data().map(|tile| FlatObjectTile {
+                    tileset: match tile.tileset_location() {
+                        TilesetLocation::Map(index) => map.tilesets()[*index].clone(),
+                        TilesetLocation::Template(tileset) => tileset.clone(),
+                    },
+                    id: tile.id(),
+                    flip_h: tile.flip_h,
+                    flip_v: tile.flip_v,
+                    flip_d: tile.flip_d,
+                });
+                flat.entities.push(FlatEntity::Object(FlatObject {
+                    layer: layer_index,
+                    id: object.id(),
+                    name: object.name.clone(),
+                    user_type: object.user_type.clone(),
+                    position: (object.x + offset.0, object.y + offset.1),
+                    rotation: object.rotation,
+                    visible: visible && object.visible,
+                    shape: object.shape.clone(),
+                    tile,
+                    properties: object.properties.clone(),
+                }));
+            }
+        }
+        LayerType::Image(image_layer) => {
+            if let Some(image) = &image_layer.image {
+                flat.entities.push(FlatEntity::Image(FlatImage {
+                    layer: layer_index,
+                    position: offset,
+                    image: image.clone(),
+                }));
+            }
+        }
+    }
+
+    flat.layers.push(flat_layer);
+}
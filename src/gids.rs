@@ -0,0 +1,410 @@
+//! Structures related to assigning global tile IDs to a map's tilesets.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    Error, LayerData, LayerDataType, LayerTileData, Map, ObjectData, ObjectTileData, Result, Tile,
+    TileId, Tileset, TilesetLocation,
+};
+
+/// The first global tile IDs of a map's tilesets as found in the file it was loaded from, along
+/// with the lazily built table of these IDs followed by the ID right after the last tileset.
+/// Never affects comparisons between maps.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct GidTable {
+    /// The first global tile IDs of the file, or none if the map was built in code or its
+    /// tilesets changed since it was loaded.
+    loaded: Vec<u32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    table: OnceLock<Vec<u32>>,
+}
+
+impl GidTable {
+    /// Creates a table of the first global tile IDs of a map's tilesets, as found in its file.
+    pub(crate) fn loaded(first_gids: Vec<u32>) -> Self {
+        Self {
+            loaded: first_gids,
+            table: OnceLock::new(),
+        }
+    }
+
+    /// Discards the table after the tilesets of the map change, which is rebuilt from
+    /// [`Map::first_gids()`] on the next lookup.
+    pub(crate) fn invalidate(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl PartialEq for GidTable {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Tileset {
+    /// The number of global tile IDs this tileset needs once referenced by a map, which is the
+    /// smallest range that fits every tile of the tileset.
+    ///
+    /// Tilesets with an image need one ID per tile in the image. Image collection tilesets only
+    /// need to fit their highest tile ID, which skips the IDs of tiles deleted at the end of the
+    /// tileset.
+    pub fn gid_span(&self) -> u32 {
+        let highest = self.tiles().map(|(id, _)| id + 1).max().unwrap_or(0);
+        if self.image.is_some() {
+            highest.max(self.tilecount)
+        } else {
+            highest
+        }
+    }
+}
+
+impl Map {
+    /// Returns the first global tile ID of each of the map's tilesets, in the same order as
+    /// [`Map::tilesets()`], when these are packed as tightly as possible.
+    ///
+    /// Each tileset is given a contiguous range of [`Tileset::gid_span()`] IDs right after the
+    /// previous one, starting from 1. Files saved by Tiled may have gaps between these ranges,
+    /// which are kept while loading but aren't needed for the map to look the same.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64_external.tmx")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(map.first_gids(), vec![1]);
+    /// # }
+    /// ```
+    pub fn first_gids(&self) -> Vec<u32> {
+        let mut next = 1;
+        self.tilesets
+            .iter()
+            .map(|tileset| {
+                let first_gid = next;
+                next += tileset.gid_span();
+                first_gid
+            })
+            .collect()
+    }
+
+    /// Returns the index of the tileset that the global tile ID given belongs to, or `None` if it
+    /// is 0 or past the IDs of the last tileset.
+    ///
+    /// IDs are numbered as in the file the map was loaded from, so that IDs found in the file or
+    /// in data saved along with it can be looked up as they are, even if the file has gaps
+    /// between its tilesets. Maps built in code, and maps whose tilesets were added or removed
+    /// since they were loaded, number them as [`Map::first_gids()`] does instead.
+    /// [`Map::to_writer()`](crate::Map::to_writer) saves IDs with the same numbering unless
+    /// [`WriteOptions::compact_gids`](crate::WriteOptions::compact_gids) is set.
+    ///
+    /// The flipping bits of the ID are ignored. Lookups are a binary search in a table of the
+    /// first global tile IDs, which is built on the first lookup and kept until the tilesets of
+    /// the map change.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_multiple_tilesets.tmx")
+    ///     .unwrap();
+    /// assert_eq!(map.first_gids(), vec![1, 85, 169]);
+    ///
+    /// assert_eq!(map.tileset_index_for_gid(84), Some(0));
+    /// assert_eq!(map.tileset_index_for_gid(85), Some(1));
+    /// assert_eq!(map.tileset_index_for_gid(0), None);
+    /// # }
+    /// ```
+    pub fn tileset_index_for_gid(&self, gid: u32) -> Option<usize> {
+        let gid = gid & !LayerTileData::ALL_FLIP_FLAGS;
+        let table = self.gid_table();
+        // The table is sorted, so this is the last tileset starting at or before the ID.
+        let index = table.partition_point(|&first_gid| first_gid <= gid);
+        (index > 0 && index < table.len()).then(|| index - 1)
+    }
+
+    /// Returns the tile that the global tile ID given refers to, numbered as described in
+    /// [`Map::tileset_index_for_gid()`], along with how it's flipped according to the flipping bits of the
+    /// ID. The returned [`LayerTileData`] also holds the index of the tile's tileset and its local
+    /// ID.
+    ///
+    /// Returns `None` if no tileset has the ID, as described in [`Map::tileset_index_for_gid()`],
+    /// or if the tileset doesn't have the tile, such as deleted tiles of image collection
+    /// tilesets.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_multiple_tilesets.tmx")
+    ///     .unwrap();
+    ///
+    /// // The horizontal flip flag on the first tile of the second tileset.
+    /// let (tile, data) = map.tile_for_gid(0x80000000 | 85).unwrap();
+    /// assert_eq!(tile.tileset().name, map.tilesets()[1].name);
+    /// assert_eq!((data.tileset_index(), data.id()), (1, 0));
+    /// assert!(data.flip_h && !data.flip_v);
+    /// # }
+    /// ```
+    pub fn tile_for_gid(&self, gid: u32) -> Option<(Tile<'_>, LayerTileData)> {
+        let tileset_index = self.tileset_index_for_gid(gid)?;
+        let id = (gid & !LayerTileData::ALL_FLIP_FLAGS) - self.gid_table()[tileset_index];
+        let tile = self.tilesets.get(tileset_index)?.get_tile(id)?;
+        Some((tile, LayerTileData::with_flags(tileset_index, id, gid)))
+    }
+
+    /// Returns the first global tile ID of each of the map's tilesets, numbered as described in
+    /// [`Map::tileset_index_for_gid()`].
+    pub(crate) fn numbered_first_gids(&self) -> &[u32] {
+        let table = self.gid_table();
+        &table[..table.len() - 1]
+    }
+
+    fn gid_table(&self) -> &[u32] {
+        self.gid_table.table.get_or_init(|| {
+            let loaded = &self.gid_table.loaded;
+            // The loaded IDs are only kept if every tileset still fits before the next one.
+            let mut table = if loaded.len() == self.tilesets.len()
+                && loaded
+                    .windows(2)
+                    .zip(&self.tilesets)
+                    .all(|(pair, tileset)| {
+                        pair[0] < pair[1] && pair[0] + tileset.gid_span() <= pair[1]
+                    }) {
+                loaded.clone()
+            } else {
+                self.first_gids()
+            };
+            let next = match (table.last(), self.tilesets.last()) {
+                (Some(last), Some(tileset)) => last + tileset.gid_span(),
+                _ => 1,
+            };
+            table.push(next);
+            table
+        })
+    }
+
+    /// Removes the tilesets that aren't used by any tile layer or tile object of the map, then
+    /// returns the first global tile IDs of the remaining ones, as given by [`Map::first_gids()`].
+    ///
+    /// The tiles referencing the remaining tilesets are updated to their new
+    /// [tileset index](crate::LayerTileData::tileset_index). Since tile IDs stay the same, the
+    /// map looks the same in Tiled once saved with the returned IDs.
+    pub fn compact_gids(&mut self) -> Vec<u32> {
+        let mut used = vec![false; self.tilesets.len()];
+        mark_used_tilesets(&self.layers, &mut used);
+
+        let mut new_indices = Vec::with_capacity(used.len());
+        let mut next = 0;
+        for &used in &used {
+            new_indices.push(used.then_some(next));
+            next += used as usize;
+        }
+
+        let mut used = used.into_iter();
+        self.tilesets.retain(|_| used.next().unwrap_or(false));
+        self.gid_table.invalidate();
+        remap_tilesets(&mut self.layers, &new_indices);
+
+        self.first_gids()
+    }
+
+    /// Replaces the tiles of every tile layer and tile object of the map according to `mapping`,
+    /// which maps the global tile IDs of the tiles to replace to the IDs of their replacements.
+    ///
+    /// Global tile IDs are numbered as described in [`Map::tileset_index_for_gid()`], which is as
+    /// in the file the map was loaded from. Tiles whose ID isn't in the mapping are left as is,
+    /// and replaced tiles keep how they are flipped. Tile objects using
+    /// the tileset of their template aren't affected.
+    ///
+    /// Fails with [`Error::InvalidGid`] if an ID the mapping replaces tiles with doesn't belong to
+    /// any of the map's tilesets, in which case the map isn't changed.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64_external.tmx")
+    ///     .unwrap();
+    ///
+    /// // Swap the first two tiles of the tileset
+    /// map.remap_gids(&HashMap::from([(1, 2), (2, 1)])).unwrap();
+    /// # }
+    /// ```
+    pub fn remap_gids(&mut self, mapping: &HashMap<u32, u32>) -> Result<()> {
+        let gid_table = self.gid_table().to_vec();
+        let mut replacements = HashMap::with_capacity(mapping.len());
+        for (&from, &to) in mapping {
+            let replacement = self
+                .tileset_index_for_gid(to)
+                .filter(|_| to & LayerTileData::ALL_FLIP_FLAGS == 0)
+                .map(|index| (index, to - gid_table[index]))
+                .filter(|&(index, id)| id < self.tilesets[index].gid_span())
+                .ok_or(Error::InvalidGid(to))?;
+            replacements.insert(from, replacement);
+        }
+
+        remap_tiles(&mut self.layers, &gid_table, &replacements);
+        Ok(())
+    }
+
+    /// Merges the tilesets the map references more than once into a single tileset, then returns
+    /// the first global tile IDs of the remaining ones, as given by [`Map::first_gids()`].
+    ///
+    /// Tilesets are duplicates if they are the same shared tileset, or if they are equal. Only
+    /// the first of the duplicates is kept, and the tiles of every tile layer and tile object
+    /// referencing the other ones are updated to reference it instead.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_multiple_tilesets.tmx")
+    ///     .unwrap();
+    /// assert_eq!(map.tilesets().len(), 3);
+    ///
+    /// assert_eq!(map.consolidate_tilesets(), vec![1, 85]);
+    /// assert_eq!(map.tilesets().len(), 2);
+    /// # }
+    /// ```
+    pub fn consolidate_tilesets(&mut self) -> Vec<u32> {
+        let mut kept: Vec<Arc<Tileset>> = Vec::with_capacity(self.tilesets.len());
+        let mut new_indices = Vec::with_capacity(self.tilesets.len());
+        for tileset in self.tilesets.drain(..) {
+            let duplicate = kept
+                .iter()
+                .position(|kept| Arc::ptr_eq(kept, &tileset) || **kept == *tileset);
+            match duplicate {
+                Some(index) => new_indices.push(Some(index)),
+                None => {
+                    new_indices.push(Some(kept.len()));
+                    kept.push(tileset);
+                }
+            }
+        }
+
+        self.tilesets = kept;
+        self.gid_table.invalidate();
+        remap_tilesets(&mut self.layers, &new_indices);
+
+        self.first_gids()
+    }
+}
+
+fn object_tileset_index(object: &ObjectData) -> Option<usize> {
+    match object.tile.as_ref()?.tileset_location {
+        TilesetLocation::Map(index) => Some(index),
+        TilesetLocation::Template(_) => None,
+    }
+}
+
+fn mark_used_tilesets(layers: &[LayerData], used: &mut [bool]) {
+    for layer in layers {
+        match &layer.layer_type {
+            LayerDataType::Tiles(tiles) => {
+                for tile in tiles.tile_data() {
+                    used[tile.tileset_index()] = true;
+                }
+            }
+            LayerDataType::Objects(objects) => {
+                for index in objects
+                    .object_data()
+                    .iter()
+                    .filter_map(object_tileset_index)
+                {
+                    used[index] = true;
+                }
+            }
+            LayerDataType::Group(group) => mark_used_tilesets(group.layer_data(), used),
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
+
+fn remap_tilesets(layers: &mut [LayerData], new_indices: &[Option<usize>]) {
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Tiles(tiles) => tiles.remap_tilesets(new_indices),
+            LayerDataType::Objects(objects) => {
+                for object in objects.object_data_mut() {
+                    if let Some(tile) = &mut object.tile {
+                        if let TilesetLocation::Map(index) = &mut tile.tileset_location {
+                            if let Some(new_index) = new_indices[*index] {
+                                *index = new_index;
+                            }
+                        }
+                    }
+                }
+            }
+            LayerDataType::Group(group) => remap_tilesets(group.layer_data_mut(), new_indices),
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
+
+fn remap_tiles(
+    layers: &mut [LayerData],
+    gid_table: &[u32],
+    replacements: &HashMap<u32, (usize, TileId)>,
+) {
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Tiles(tiles) => {
+                for tile in tiles.tile_data_mut() {
+                    let gid = gid_table[tile.tileset_index()] + tile.id();
+                    if let Some(&(tileset_index, id)) = replacements.get(&gid) {
+                        let rotate_hex_120 = tile.rotate_hex_120;
+                        *tile = LayerTileData::new(
+                            tileset_index,
+                            id,
+                            tile.flip_h,
+                            tile.flip_v,
+                            tile.flip_d,
+                        );
+                        tile.rotate_hex_120 = rotate_hex_120;
+                    }
+                }
+            }
+            LayerDataType::Objects(objects) => {
+                for object in objects.object_data_mut() {
+                    let Some(tile) = &mut object.tile else {
+                        continue;
+                    };
+                    let TilesetLocation::Map(index) = tile.tileset_location else {
+                        continue;
+                    };
+                    if let Some(&(tileset_index, id)) =
+                        replacements.get(&(gid_table[index] + tile.id()))
+                    {
+                        *tile = ObjectTileData::new(
+                            TilesetLocation::Map(tileset_index),
+                            id,
+                            tile.flip_h,
+                            tile.flip_v,
+                            tile.flip_d,
+                        );
+                    }
+                }
+            }
+            LayerDataType::Group(group) => {
+                remap_tiles(group.layer_data_mut(), gid_table, replacements)
+            }
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Structures related to looking up layers and objects by ID, and to the references objects make
+//! to each other through their properties.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::OnceLock,
+};
+
+use crate::{
+    Layer, LayerData, LayerDataType, Map, Object, ObjectLayer, ObjectLayerData, Properties,
+    PropertyValue,
+};
+
+/// A reference from an object to another one, made through an `object` property.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ObjectEdge {
+    /// The ID of the object the property belongs to.
+    pub from: u32,
+    /// The ID of the referenced object.
+    pub to: u32,
+    /// The name of the property. Members of class properties are named after the path leading to
+    /// them, separated by dots, e.g. `trigger.target`.
+    pub property: String,
+}
+
+/// A directed graph of the objects of a map and the references between them, obtained via
+/// [`Map::object_graph()`].
+///
+/// Properties that don't reference any object (i.e. have an ID of 0) aren't part of the graph.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ObjectGraph {
+    /// Sorted and deduplicated.
+    nodes: Vec<u32>,
+    edges: Vec<ObjectEdge>,
+}
+
+impl ObjectGraph {
+    /// The IDs of every object in the map, in ascending order.
+    #[inline]
+    pub fn nodes(&self) -> &[u32] {
+        &self.nodes
+    }
+
+    /// Every reference between objects, in the order the objects were found in, then sorted by
+    /// property name.
+    #[inline]
+    pub fn edges(&self) -> &[ObjectEdge] {
+        &self.edges
+    }
+
+    /// Whether the map has an object with the ID given.
+    pub fn contains(&self, id: u32) -> bool {
+        self.nodes.binary_search(&id).is_ok()
+    }
+
+    /// Returns an iterator over the references made by the object with the ID given.
+    pub fn edges_from(&self, id: u32) -> impl Iterator<Item = &ObjectEdge> {
+        self.edges.iter().filter(move |edge| edge.from == id)
+    }
+
+    /// Returns an iterator over the references made to the object with the ID given.
+    pub fn edges_to(&self, id: u32) -> impl Iterator<Item = &ObjectEdge> {
+        self.edges.iter().filter(move |edge| edge.to == id)
+    }
+
+    /// Returns an iterator over the references to objects that aren't in the map.
+    pub fn dangling(&self) -> impl Iterator<Item = &ObjectEdge> {
+        self.edges
+            .iter()
+            .filter(move |edge| !self.contains(edge.to))
+    }
+
+    /// Returns the IDs of the objects that can be reached by following references from the object
+    /// with the ID given, in breadth-first order. The object itself is only included if it is
+    /// part of a cycle, and objects that aren't in the map are skipped.
+    pub fn reachable_from(&self, id: u32) -> Vec<u32> {
+        let mut visited = HashSet::new();
+        let mut reachable = Vec::new();
+        let mut queue = VecDeque::from([id]);
+        while let Some(current) = queue.pop_front() {
+            for edge in self.edges_from(current) {
+                if self.contains(edge.to) && visited.insert(edge.to) {
+                    reachable.push(edge.to);
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        reachable
+    }
+}
+
+impl Map {
+    /// Builds the graph of the references objects make to each other through their `object`
+    /// properties, including the ones nested in class properties.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_property.tmx")
+    ///     .unwrap();
+    /// let graph = map.object_graph();
+    ///
+    /// for edge in graph.dangling() {
+    ///     println!(
+    ///         "Object {} references missing object {} through {:?}",
+    ///         edge.from, edge.to, edge.property
+    ///     );
+    /// }
+    /// # }
+    /// ```
+    pub fn object_graph(&self) -> ObjectGraph {
+        let mut graph = ObjectGraph::default();
+        for (_, object) in self.objects() {
+            graph.nodes.push(object.id());
+            push_edges(object.id(), &object.properties, "", &mut graph.edges);
+        }
+        graph.nodes.sort_unstable();
+        graph.nodes.dedup();
+        graph
+    }
+
+    /// Returns the object with the ID given, wherever it is in the map, if there's one.
+    ///
+    /// The first lookup builds an index of the objects of the map by ID, which later lookups
+    /// reuse. The index is discarded when objects are added through [`Map::add_layer()`] or
+    /// [`Map::get_object_layer_mut()`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_property.tmx")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(map.get_object_by_id(3).unwrap().id(), 3);
+    /// assert!(map.get_object_by_id(42).is_none());
+    /// # }
+    /// ```
+    pub fn get_object_by_id(&self, id: u32) -> Option<Object<'_>> {
+        let (path, index) = self.ids().objects.get(&id)?;
+        match &self.layer_data_at(path)?.layer_type {
+            LayerDataType::Objects(objects) => objects
+                .object_data()
+                .get(*index)
+                .map(|data| Object::new(self, data)),
+            _ => None,
+        }
+    }
+
+    /// Returns the layer with the ID given, which may be nested inside of group layers, if there's
+    /// one.
+    ///
+    /// Lookups go through the same index as [`Map::get_object_by_id()`], so they don't depend on
+    /// the number of layers once it is built.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_group_layers.tmx")
+    ///     .unwrap();
+    ///
+    /// let layer = map.get_layer_by_id(5).unwrap();
+    /// assert_eq!(layer.id(), 5);
+    /// assert!(map.get_layer_by_id(42).is_none());
+    /// # }
+    /// ```
+    pub fn get_layer_by_id(&self, id: u32) -> Option<Layer<'_>> {
+        let path = self.ids().layers.get(&id)?;
+        self.layer_data_at(path).map(|data| Layer::new(self, data))
+    }
+
+    fn ids(&self) -> &Ids {
+        self.id_index.0.get_or_init(|| Ids::build(&self.layers))
+    }
+
+    /// Returns the layer at the path of indices given, each one indexing the layers of the group
+    /// layer found with the previous ones.
+    fn layer_data_at(&self, path: &[usize]) -> Option<&LayerData> {
+        let (last, parents) = path.split_last()?;
+        let mut layers = self.layers.as_slice();
+        for &parent in parents {
+            match &layers.get(parent)?.layer_type {
+                LayerDataType::Group(group) => layers = group.layer_data(),
+                _ => return None,
+            }
+        }
+        layers.get(*last)
+    }
+
+    /// Returns the object referenced by the `object` property of the properties given that has
+    /// the name given, if the property exists and references an object of the map. Properties of
+    /// other types and unset references are ignored.
+    ///
+    /// Also see [`Object::resolve_object_property()`].
+    pub fn resolve_object_property(
+        &self,
+        properties: &Properties,
+        name: &str,
+    ) -> Option<Object<'_>> {
+        match properties.get(name)? {
+            PropertyValue::ObjectValue(0) => None,
+            PropertyValue::ObjectValue(id) => self.get_object_by_id(*id),
+            _ => None,
+        }
+    }
+}
+
+impl<'map> Object<'map> {
+    /// Returns the object referenced by the `object` property of this object that has the name
+    /// given, if the property exists and references an object of the map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_property.tmx")
+    ///     .unwrap();
+    /// let object = map.get_object_by_id(2).unwrap();
+    ///
+    /// let target = object.resolve_object_property("object property").unwrap();
+    /// assert_eq!(target.id(), 3);
+    /// // Unset references aren't resolved
+    /// assert!(target.resolve_object_property("object property").is_none());
+    /// # }
+    /// ```
+    pub fn resolve_object_property(&self, name: &str) -> Option<Object<'map>> {
+        self.map().resolve_object_property(&self.properties, name)
+    }
+}
+
+/// The lazily built index of the layers and objects of a map by ID. Never affects comparisons
+/// between maps.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdIndex(OnceLock<Ids>);
+
+impl IdIndex {
+    /// Discards the index, which is rebuilt on the next lookup.
+    pub(crate) fn invalidate(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
+
+impl PartialEq for IdIndex {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Ids {
+    /// The path of indices to each layer.
+    layers: HashMap<u32, Vec<usize>>,
+    /// The path of indices to the layer of each object, along with its index in the layer.
+    objects: HashMap<u32, (Vec<usize>, usize)>,
+}
+
+impl Ids {
+    fn build(layers: &[LayerData]) -> Self {
+        fn visit(layers: &[LayerData], path: &mut Vec<usize>, ids: &mut Ids) {
+            for (layer_index, layer) in layers.iter().enumerate() {
+                path.push(layer_index);
+                // IDs are only shared in hand-edited files, where the first layer or object in
+                // display order wins
+                ids.layers.entry(layer.id()).or_insert_with(|| path.clone());
+                match &layer.layer_type {
+                    LayerDataType::Objects(objects) => {
+                        for (object_index, object) in objects.object_data().iter().enumerate() {
+                            ids.objects
+                                .entry(object.id())
+                                .or_insert_with(|| (path.clone(), object_index));
+                        }
+                    }
+                    LayerDataType::Group(group) => visit(group.layer_data(), path, ids),
+                    _ => {}
+                }
+                path.pop();
+            }
+        }
+
+        let mut ids = Ids::default();
+        visit(layers, &mut Vec::new(), &mut ids);
+        ids
+    }
+}
+
+/// The lazily built index of the objects of an object layer by ID, which stores the index of each
+/// object in the layer. Never affects comparisons between layers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LayerObjectIds(OnceLock<HashMap<u32, usize>>);
+
+impl LayerObjectIds {
+    /// Discards the index, which is rebuilt on the next lookup.
+    pub(crate) fn invalidate(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
+
+impl PartialEq for LayerObjectIds {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<'map> ObjectLayer<'map> {
+    /// Returns the object of this layer with the ID given, if there's one.
+    ///
+    /// The first lookup builds an index of the objects of the layer by ID, which later lookups
+    /// reuse. Also see [`Map::get_object_by_id()`], which looks through every layer of the map.
+    pub fn get_object_by_id(&self, id: u32) -> Option<Object<'map>> {
+        let data: &'map ObjectLayerData = self.data;
+        let objects = data.object_data();
+        let index = data.ids.0.get_or_init(|| {
+            let mut ids = HashMap::with_capacity(objects.len());
+            for (index, object) in objects.iter().enumerate() {
+                ids.entry(object.id()).or_insert(index);
+            }
+            ids
+        });
+        objects
+            .get(*index.get(&id)?)
+            .map(|data| Object::new(self.map, data))
+    }
+}
+
+fn push_edges(from: u32, properties: &Properties, prefix: &str, edges: &mut Vec<ObjectEdge>) {
+    // Properties are stored in a hash map, so they are sorted to keep the edges deterministic.
+    let mut properties: Vec<_> = properties.iter().collect();
+    properties.sort_unstable_by_key(|(name, _)| *name);
+    for (name, value) in properties {
+        let property = format!("{}{}", prefix, name);
+        match value {
+            PropertyValue::ObjectValue(0) => {}
+            PropertyValue::ObjectValue(to) => edges.push(ObjectEdge {
+                from,
+                to: *to,
+                property,
+            }),
+            PropertyValue::ClassValue { properties, .. } => {
+                push_edges(from, properties, &format!("{}.", property), edges)
+            }
+            _ => {}
+        }
+    }
+}
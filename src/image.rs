@@ -4,22 +4,35 @@ use quick_xml::events::attributes::Attribute;
 
 use crate::{
     error::{Error, Result},
+    layers::{decompress, is_supported_compression, parse_base64, unsupported_encoding},
     parse::xml::{Parser, Reader},
     properties::Color,
     util::*,
+    LoaderOptions,
 };
 
-/// A reference to an image stored somewhere within the filesystem.
+/// A reference to an image stored somewhere within the filesystem, or embedded in the file it was
+/// loaded from.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
-    /// The **uncanonicalized** filepath of the image, starting from the path given to load the file
-    /// this image is in. See the example for more details.
-    ///
-    /// ## Note
-    /// The crate does not currently support embedded images (Even though Tiled
-    /// does not allow creating maps with embedded image data, the TMX format does; [source])
-    ///
-    /// [source]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#image
+    /// Where the pixels of the image come from; Usually a path to an image file. See
+    /// [`ImageSource`] for more details.
+    pub source: ImageSource,
+    /// The width in pixels of the image.
+    pub width: i32,
+    /// The height in pixels of the image.
+    pub height: i32,
+    /// A color that should be interpreted as transparent (0 alpha), if any.
+    pub transparent_colour: Option<Color>,
+}
+
+/// Where the pixels of an [`Image`] come from.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageSource {
+    /// The **uncanonicalized** filepath of the image, starting from the path given to load the
+    /// file this image is in. See the example for more details.
     ///
     /// ## Example
     /// ```
@@ -32,7 +45,7 @@ pub struct Image {
     ///
     /// let image_layer = match map
     ///     .layers()
-    ///     .find(|layer| layer.name == "image")
+    ///     .find(|layer| &*layer.name == "image")
     ///     .unwrap()
     ///     .layer_type()
     /// {
@@ -43,7 +56,7 @@ pub struct Image {
     /// // Image layer has an image with the source attribute set to "../tilesheet.png"
     /// // Given the information we gave to the `parse_file` function, the image source should be
     /// // "assets/folder/../tilesheet.png". The filepath is not canonicalized.
-    /// let image_source = &image_layer.image.as_ref().unwrap().source;
+    /// let image_source = image_layer.image.as_ref().unwrap().source.as_path().unwrap();
     ///
     /// assert_eq!(
     ///     image_source,
@@ -58,14 +71,38 @@ pub struct Image {
     /// ```
     /// Check the assets/tiled_relative_paths.tmx file at the crate root to see the structure of the
     /// file this example is referring to.
-    // TODO: Embedded images
-    pub source: PathBuf,
-    /// The width in pixels of the image.
-    pub width: i32,
-    /// The height in pixels of the image.
-    pub height: i32,
-    /// A color that should be interpreted as transparent (0 alpha), if any.
-    pub transparent_colour: Option<Color>,
+    Path(PathBuf),
+    /// The encoded contents of an image embedded in the file, along with their format as stored
+    /// in the file, e.g. `png`, or an empty string if it wasn't specified.
+    ///
+    /// Tiled doesn't create images like these, but the [TMX format] allows them.
+    ///
+    /// [TMX format]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#image
+    Data(Vec<u8>, String),
+}
+
+impl ImageSource {
+    /// Returns the path of the image file, if the image isn't embedded.
+    pub fn as_path(&self) -> Option<&Path> {
+        match self {
+            ImageSource::Path(path) => Some(path),
+            ImageSource::Data(..) => None,
+        }
+    }
+
+    /// Returns the encoded contents and the format of the image, if it is embedded.
+    pub fn as_data(&self) -> Option<(&[u8], &str)> {
+        match self {
+            ImageSource::Path(_) => None,
+            ImageSource::Data(data, format) => Some((data, format)),
+        }
+    }
+}
+
+impl From<PathBuf> for ImageSource {
+    fn from(path: PathBuf) -> Self {
+        ImageSource::Path(path)
+    }
 }
 
 impl Image {
@@ -74,22 +111,84 @@ impl Image {
         attrs: Vec<Attribute<'_>>,
         path_relative_to: impl AsRef<Path>,
     ) -> Result<Image> {
-        let (c, (s, w, h)) = get_attrs!(
+        let (c, s, f, (w, h)) = get_attrs!(
             for v in attrs {
                 Some("trans") => trans ?= v.parse(),
-                "source" => source = v,
+                Some("source") => source = v,
+                Some("format") => format = v,
                 "width" => width ?= v.parse::<i32>(),
                 "height" => height ?= v.parse::<i32>(),
             }
-            (trans, (source, width, height))
+            (trans, source, format, (width, height))
         );
 
-        parse_tag!(parser, "image", {});
-        Ok(Image {
-            source: path_relative_to.as_ref().join(s),
-            width: w,
-            height: h,
-            transparent_colour: c,
-        })
+        let mut data = None;
+        let mut buffer = Vec::new();
+        parse_tag!(parser => &mut buffer, "image", {
+            "data" => for attrs {
+                let (encoding, compression) = get_attrs!(
+                    for v in attrs {
+                        Some("encoding") => encoding = v,
+                        Some("compression") => compression = v,
+                    }
+                    (encoding, compression)
+                );
+                data = Some(parse_embedded_data(parser, encoding, compression).await?);
+                Ok(())
+            },
+        });
+
+        match (s, data) {
+            (Some(s), _) => Ok(Image::with_source(
+                path_relative_to.as_ref().join(s),
+                w,
+                h,
+                c,
+                &parser.options,
+            )),
+            (None, Some(data)) => Ok(Image {
+                source: ImageSource::Data(data, f.unwrap_or_default().to_owned()),
+                width: w,
+                height: h,
+                transparent_colour: c,
+            }),
+            (None, None) => Err(Error::MalformedAttributes(
+                "image must have a source or embedded data".to_owned(),
+            )),
+        }
+    }
+
+    /// Creates an image from its already joined source path, which goes through the
+    /// [image path rewriter](LoaderOptions::image_path_rewriter) if there's one.
+    pub(crate) fn with_source(
+        mut source: PathBuf,
+        width: i32,
+        height: i32,
+        transparent_colour: Option<Color>,
+        options: &LoaderOptions,
+    ) -> Image {
+        if let Some(rewriter) = &options.image_path_rewriter {
+            source = rewriter.rewrite(&source);
+        }
+        Image {
+            source: ImageSource::Path(source),
+            width,
+            height,
+            transparent_colour,
+        }
+    }
+}
+
+/// Parses the contents of a `<data>` element embedded in an image.
+async fn parse_embedded_data<R: Reader>(
+    parser: &mut Parser<R>,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u8>> {
+    match encoding {
+        Some("base64") if is_supported_compression(compression) => parse_base64(parser)
+            .await
+            .and_then(|data| decompress(data, compression)),
+        _ => Err(unsupported_encoding(encoding, compression)),
     }
 }
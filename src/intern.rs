@@ -0,0 +1,22 @@
+//! Sharing of the strings that are repeated throughout a file, such as property names.
+
+use std::{collections::HashSet, sync::Arc};
+
+/// Hands out a single shared allocation for every distinct string it is given, so that strings
+/// repeated throughout a file, such as property names and classes, are only stored once.
+#[derive(Debug, Default)]
+pub(crate) struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Returns the shared copy of the string given, creating it if this is its first occurrence.
+    pub(crate) fn intern(&mut self, string: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(string) {
+            return interned.clone();
+        }
+        let interned: Arc<str> = Arc::from(string);
+        self.strings.insert(interned.clone());
+        interned
+    }
+}
@@ -11,8 +11,9 @@ use crate::{
 
 /// The raw data of a [`GroupLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupLayerData {
-    layers: Vec<LayerData>,
+    pub(crate) layers: Vec<LayerData>,
 }
 
 impl GroupLayerData {
@@ -24,6 +25,35 @@ impl GroupLayerData {
         for_tileset: Option<Arc<Tileset>>,
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
+    ) -> Result<(Self, Properties)> {
+        let max_depth = parser.options.max_group_depth;
+        if parser.group_depth >= max_depth {
+            return Err(Error::GroupNestingTooDeep { max_depth });
+        }
+        parser.group_depth += 1;
+        // restore the depth even on failure, as lenient mode goes on to parse the next layers
+        let result = Self::parse(
+            parser,
+            infinite,
+            map_path,
+            tilesets,
+            for_tileset,
+            read_from,
+            cache,
+        )
+        .await;
+        parser.group_depth -= 1;
+        result
+    }
+
+    async fn parse<R: Reader>(
+        parser: &mut Parser<R>,
+        infinite: bool,
+        map_path: &Path,
+        tilesets: &[MapTilesetGid],
+        for_tileset: Option<Arc<Tileset>>,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
     ) -> Result<(Self, Properties)> {
         let mut properties = HashMap::new();
         let mut layers = Vec::new();
@@ -92,14 +122,30 @@ impl GroupLayerData {
         });
         Ok((Self { layers }, properties))
     }
+
+    /// Returns the data of the layers contained within the group, in display order.
+    #[inline]
+    pub fn layer_data(&self) -> &[LayerData] {
+        &self.layers
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    #[inline]
+    pub(crate) fn layer_data_mut(&mut self) -> &mut Vec<LayerData> {
+        &mut self.layers
+    }
 }
 
 map_wrapper!(
     #[doc = "A group layer, used to organize the layers of the map in a hierarchy."]
     #[doc = "\nAlso see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#group)."]
     #[doc = "## Note"]
-    #[doc = "In Tiled, the properties of the group layer recursively affect child layers.
-    Implementing this behavior is left up to the user of this library."]
+    #[doc = "In Tiled, the offset, parallax factor, opacity, tint color and visibility of the group
+    layer recursively affect child layers. These are combined by [`Layer::effective_attributes()`]
+    and [`Map::layers_flattened()`](crate::Map::layers_flattened)."]
     GroupLayer => GroupLayerData
 );
 
@@ -1,24 +1,39 @@
 use std::{collections::HashMap, path::Path};
 
+use quick_xml::events::attributes::Attribute;
+
 use crate::{
     parse::xml::{Parser, Reader},
     parse_properties,
-    util::{map_wrapper, parse_tag},
+    util::{get_attrs, map_wrapper, parse_tag},
     Error, Image, Properties, Result,
 };
 
 /// The raw data of an [`ImageLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImageLayerData {
     /// The single image this layer contains, if it exists.
     pub image: Option<Image>,
+    /// Whether the image is repeated horizontally to fill the view.
+    pub repeat_x: bool,
+    /// Whether the image is repeated vertically to fill the view.
+    pub repeat_y: bool,
 }
 
 impl ImageLayerData {
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
+        attrs: Vec<Attribute<'_>>,
         map_path: &Path,
     ) -> Result<(Self, Properties)> {
+        let (repeat_x, repeat_y) = get_attrs!(
+            for v in attrs {
+                Some("repeatx") => repeat_x ?= v.parse().map(|x: i32| x == 1),
+                Some("repeaty") => repeat_y ?= v.parse().map(|x: i32| x == 1),
+            }
+            (repeat_x.unwrap_or(false), repeat_y.unwrap_or(false))
+        );
         let mut image: Option<Image> = None;
         let mut properties = HashMap::new();
 
@@ -35,7 +50,14 @@ impl ImageLayerData {
                 Ok(())
             },
         });
-        Ok((ImageLayerData { image }, properties))
+        Ok((
+            ImageLayerData {
+                image,
+                repeat_x,
+                repeat_y,
+            },
+            properties,
+        ))
     }
 }
 
@@ -44,3 +66,104 @@ map_wrapper!(
     #[doc = "\nAlso see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#imagelayer)."]
     ImageLayer => ImageLayerData
 );
+
+/// A rectangular area of the world to draw, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Viewport {
+    /// The X coordinate of the left edge of the viewport.
+    pub x: f32,
+    /// The Y coordinate of the top edge of the viewport.
+    pub y: f32,
+    /// The width of the viewport.
+    pub width: f32,
+    /// The height of the viewport.
+    pub height: f32,
+}
+
+impl<'map> ImageLayer<'map> {
+    /// Returns the world pixel positions of the top-left corners of the copies of the layer's
+    /// image that need to be drawn to cover the viewport given, in row-major order.
+    ///
+    /// The image is placed according to the offsets and parallax factors of the layer and of its
+    /// parent group layers, relative to the camera given, the same way as
+    /// [`Layer::to_affine()`](crate::Layer::to_affine) does. It is then repeated along the axes
+    /// the layer [repeats](ImageLayerData::repeat_x) it on. Returns nothing if the layer has no
+    /// image or the image lies outside of the viewport.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, Viewport};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_image_layer_repeat.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_image_layer().unwrap();
+    ///
+    /// // The 448 pixels wide image is repeated horizontally to fill the view.
+    /// let viewport = Viewport { x: 0.0, y: 0.0, width: 640.0, height: 480.0 };
+    /// assert_eq!(
+    ///     layer.visible_tiles(viewport, (0.0, 0.0)),
+    ///     vec![(0.0, 0.0), (448.0, 0.0)]
+    /// );
+    /// # }
+    /// ```
+    pub fn visible_tiles(&self, viewport: Viewport, camera: (f32, f32)) -> Vec<(f32, f32)> {
+        let Some(image) = &self.image else {
+            return Vec::new();
+        };
+        let Some((_, attributes)) = self.map.layers_flattened().find(|(layer, _)| {
+            layer
+                .as_image_layer()
+                .is_some_and(|layer| std::ptr::eq(layer.data, self.data))
+        }) else {
+            return Vec::new();
+        };
+
+        let camera_x = camera.0 - self.map.parallax_origin_x;
+        let camera_y = camera.1 - self.map.parallax_origin_y;
+        let origin_x = attributes.offset_x + camera_x * (1.0 - attributes.parallax_x);
+        let origin_y = attributes.offset_y + camera_y * (1.0 - attributes.parallax_y);
+        let columns = placements(
+            origin_x,
+            image.width as f32,
+            self.repeat_x,
+            viewport.x,
+            viewport.width,
+        );
+        let rows = placements(
+            origin_y,
+            image.height as f32,
+            self.repeat_y,
+            viewport.y,
+            viewport.height,
+        );
+        rows.iter()
+            .flat_map(|&y| columns.iter().map(move |&x| (x, y)))
+            .collect()
+    }
+}
+
+/// Returns the positions along one axis at which an image of the size given starting at `origin`
+/// overlaps the range of the viewport, repeating it if `repeat` is set.
+fn placements(origin: f32, size: f32, repeat: bool, start: f32, length: f32) -> Vec<f32> {
+    let end = start + length;
+    if size <= 0.0 || length <= 0.0 {
+        return Vec::new();
+    }
+    if !repeat {
+        return if origin < end && origin + size > start {
+            vec![origin]
+        } else {
+            Vec::new()
+        };
+    }
+    let first = ((start - origin) / size).floor();
+    let mut positions = Vec::new();
+    let mut position = origin + first * size;
+    while position < end {
+        positions.push(position);
+        position += size;
+    }
+    positions
+}
@@ -1,4 +1,4 @@
-use std::{path::Path, sync::Arc};
+use std::{future::Future, path::Path, pin::Pin, sync::Arc};
 
 use quick_xml::events::attributes::Attribute;
 
@@ -6,8 +6,10 @@ use crate::{
     error::Result,
     parse::xml::{Parser, ReadFrom, Reader},
     properties::Properties,
+    transform::find_layer,
     util::*,
-    Color, Map, MapTilesetGid, ResourceCache, Tileset,
+    Color, Diagnostic, DiagnosticKind, Image, Map, MapTilesetGid, ObjectData, ParseWarningKind,
+    ResourceCache, Tileset,
 };
 
 mod image;
@@ -20,6 +22,7 @@ mod group;
 pub use group::*;
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum LayerDataType {
     Tiles(TileLayerData),
     Objects(ObjectLayerData),
@@ -35,12 +38,25 @@ pub(crate) enum LayerTag {
     Group,
 }
 
+impl LayerTag {
+    /// The name of the XML element of the layer kind.
+    pub(crate) fn element_name(self) -> &'static str {
+        match self {
+            LayerTag::Tiles => "layer",
+            LayerTag::Objects => "objectgroup",
+            LayerTag::Image => "imagelayer",
+            LayerTag::Group => "group",
+        }
+    }
+}
+
 /// The raw data of a [`Layer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerData {
     /// The layer's name, set arbitrarily by the user.
-    pub name: String,
-    id: u32,
+    pub name: Arc<str>,
+    pub(crate) id: u32,
     /// Whether this layer should be visible or not.
     pub visible: bool,
     /// The layer's x offset (in pixels).
@@ -58,8 +74,8 @@ pub struct LayerData {
     /// The layer's custom properties, as arbitrarily set by the user.
     pub properties: Properties,
     /// The layer's type, which is arbitrarily setby the user.
-    pub user_type: Option<String>,
-    layer_type: LayerDataType,
+    pub user_type: Option<Arc<str>>,
+    pub(crate) layer_type: LayerDataType,
 }
 
 impl LayerData {
@@ -70,9 +86,112 @@ impl LayerData {
         self.id
     }
 
+    /// Parses a layer. In [lenient mode](crate::LoaderOptions::lenient), layers that fail to parse
+    /// are replaced by an empty layer of the same kind, and the failure is recorded in the parser's
+    /// diagnostics.
+    ///
+    /// The returned future is boxed, so that the stack used by every level of nesting of group
+    /// layers, which parse their layers through this function, stays small.
+    // FIXME: fewer arguments?
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new<'a, R: Reader>(
+        parser: &'a mut Parser<R>,
+        attrs: Vec<Attribute<'a>>,
+        tag: LayerTag,
+        infinite: bool,
+        map_path: &'a Path,
+        tilesets: &'a [MapTilesetGid],
+        for_tileset: Option<Arc<Tileset>>,
+        read_from: &'a mut impl ReadFrom,
+        cache: &'a mut impl ResourceCache,
+    ) -> Pin<Box<impl Future<Output = Result<Self>> + 'a>> {
+        Box::pin(Self::new_unboxed(
+            parser,
+            attrs,
+            tag,
+            infinite,
+            map_path,
+            tilesets,
+            for_tileset,
+            read_from,
+            cache,
+        ))
+    }
+
+    // FIXME: fewer arguments?
+    #[allow(clippy::too_many_arguments)]
+    async fn new_unboxed<R: Reader>(
+        parser: &mut Parser<R>,
+        attrs: Vec<Attribute<'_>>,
+        tag: LayerTag,
+        infinite: bool,
+        map_path: &Path,
+        tilesets: &[MapTilesetGid],
+        for_tileset: Option<Arc<Tileset>>,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
+    ) -> Result<Self> {
+        // What a placeholder replacing the layer is made of, in lenient mode
+        let recovery = parser.options.lenient.then(|| {
+            let depth = parser.depth_outside_current_element();
+            let id = find_attr(&attrs, "id").and_then(|id| id.parse().ok());
+            let name = find_attr(&attrs, "name");
+            let size = find_attr(&attrs, "width")
+                .and_then(|w| w.parse().ok())
+                .zip(find_attr(&attrs, "height").and_then(|h| h.parse().ok()));
+            (depth, id, name, size)
+        });
+
+        let result = Self::parse(
+            parser,
+            attrs,
+            tag,
+            infinite,
+            map_path,
+            tilesets,
+            for_tileset,
+            read_from,
+            cache,
+        )
+        .await;
+        match (result, recovery) {
+            (Err(err), Some((depth, id, name, size))) if err.is_recoverable() => {
+                parser.skip_to_depth(depth).await?;
+                let id = id.unwrap_or(0);
+                let name = name.unwrap_or_default();
+                parser.diagnostics.push(Diagnostic {
+                    path: map_path.to_owned(),
+                    kind: DiagnosticKind::ReplacedLayer {
+                        id,
+                        name: name.clone(),
+                    },
+                    message: err.to_string(),
+                });
+
+                Ok(Self::placeholder(
+                    id,
+                    name.into(),
+                    tag,
+                    infinite,
+                    size.unwrap_or((0, 0)),
+                ))
+            }
+            (result, _) => result,
+        }
+    }
+
     // FIXME: fewer arguments?
     #[allow(clippy::too_many_arguments)]
-    pub(crate) async fn new<R: Reader>(
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "parse_layer",
+            level = "debug",
+            skip_all,
+            fields(id = tracing::field::Empty, name = tracing::field::Empty)
+        )
+    )]
+    async fn parse<R: Reader>(
         parser: &mut Parser<R>,
         attrs: Vec<Attribute<'_>>,
         tag: LayerTag,
@@ -106,17 +225,36 @@ impl LayerData {
                 Some("parallaxy") => parallax_y ?= v.parse(),
                 Some("name") => name = v,
                 Some("id") => id ?= v.parse(),
-                Some("type") => user_type ?= v.parse(),
-                Some("class") => user_class ?= v.parse(),
+                Some("type") => user_type = v,
+                Some("class") => user_class = v,
             }
             (opacity, tint_color, visible, offset_x, offset_y, parallax_x, parallax_y, name, id, user_type, user_class)
         );
-        let name = name.map(ToOwned::to_owned);
+        if id.is_none() {
+            parser.warn(ParseWarningKind::MissingAttribute {
+                element: tag.element_name().to_owned(),
+                name: "id".to_owned(),
+                default: "0".to_owned(),
+            });
+        }
+        if user_type.is_some() {
+            parser.warn(ParseWarningKind::DeprecatedAttribute {
+                element: tag.element_name().to_owned(),
+                name: "type".to_owned(),
+                replacement: "class".to_owned(),
+            });
+        }
+        let name = parser.interner.intern(name.unwrap_or_default());
+        let user_type = user_type.or(user_class).map(|t| parser.interner.intern(t));
+        #[cfg(feature = "tracing")]
+        tracing::Span::current()
+            .record("id", id.unwrap_or(0))
+            .record("name", &*name);
 
         let (ty, properties) = match tag {
             LayerTag::Tiles => {
                 let (ty, properties) =
-                    TileLayerData::new(parser, attrs, infinite, tilesets).await?;
+                    TileLayerData::new(parser, attrs, infinite, id.unwrap_or(0), tilesets).await?;
                 (LayerDataType::Tiles(ty), properties)
             }
             LayerTag::Objects => {
@@ -133,7 +271,7 @@ impl LayerData {
                 (LayerDataType::Objects(ty), properties)
             }
             LayerTag::Image => {
-                let (ty, properties) = ImageLayerData::new(parser, map_path).await?;
+                let (ty, properties) = ImageLayerData::new(parser, attrs, map_path).await?;
                 (LayerDataType::Image(ty), properties)
             }
             LayerTag::Group => {
@@ -161,15 +299,139 @@ impl LayerData {
             parallax_y: parallax_y.unwrap_or(1.0),
             opacity: opacity.unwrap_or(1.0),
             tint_color,
-            name: name.unwrap_or_default().to_string(),
+            name,
             id: id.unwrap_or(0),
-            user_type: user_type.or(user_class),
+            user_type,
             properties,
             layer_type: ty,
         })
     }
 }
 
+impl LayerData {
+    /// Creates a finite tile layer with the name and tiles given, to be added to a finite map
+    /// through [`Map::add_layer()`].
+    ///
+    /// The layer is visible and has default attributes, which can be changed through its fields.
+    /// It has an ID of 0 until it is added to a map, which assigns it a fresh one.
+    pub fn new_finite_tile_layer(name: impl Into<Arc<str>>, tiles: FiniteTileLayerData) -> Self {
+        Self::with_defaults(
+            0,
+            name.into(),
+            LayerDataType::Tiles(TileLayerData::Finite(tiles)),
+        )
+    }
+
+    /// Creates an infinite tile layer with the name and tiles given, to be added to an infinite
+    /// map through [`Map::add_layer()`].
+    ///
+    /// The layer is visible and has default attributes, which can be changed through its fields.
+    /// It has an ID of 0 until it is added to a map, which assigns it a fresh one.
+    pub fn new_infinite_tile_layer(
+        name: impl Into<Arc<str>>,
+        tiles: InfiniteTileLayerData,
+    ) -> Self {
+        Self::with_defaults(
+            0,
+            name.into(),
+            LayerDataType::Tiles(TileLayerData::Infinite(tiles)),
+        )
+    }
+
+    /// Creates an object layer with the name and objects given, to be added to a map through
+    /// [`Map::add_layer()`].
+    ///
+    /// The layer is visible and has default attributes, which can be changed through its fields.
+    /// It has an ID of 0 until it is added to a map, which assigns it a fresh one, along with the
+    /// objects that have an ID of 0.
+    pub fn new_object_layer(name: impl Into<Arc<str>>, objects: Vec<ObjectData>) -> Self {
+        Self::with_defaults(
+            0,
+            name.into(),
+            LayerDataType::Objects(ObjectLayerData::with_objects(objects, None)),
+        )
+    }
+
+    /// Creates an image layer with the name and image given, to be added to a map through
+    /// [`Map::add_layer()`].
+    ///
+    /// The layer is visible and has default attributes, which can be changed through its fields.
+    /// It has an ID of 0 until it is added to a map, which assigns it a fresh one.
+    pub fn new_image_layer(name: impl Into<Arc<str>>, image: Option<Image>) -> Self {
+        Self::with_defaults(
+            0,
+            name.into(),
+            LayerDataType::Image(ImageLayerData {
+                image,
+                repeat_x: false,
+                repeat_y: false,
+            }),
+        )
+    }
+
+    /// Creates a group layer with the name and child layers given, to be added to a map through
+    /// [`Map::add_layer()`].
+    ///
+    /// The layer is visible and has default attributes, which can be changed through its fields.
+    /// It has an ID of 0 until it is added to a map, which assigns a fresh one to it and to every
+    /// child layer with an ID of 0.
+    pub fn new_group_layer(name: impl Into<Arc<str>>, layers: Vec<LayerData>) -> Self {
+        Self::with_defaults(
+            0,
+            name.into(),
+            LayerDataType::Group(GroupLayerData { layers }),
+        )
+    }
+
+    /// Creates a visible tile layer with default attributes and the tile data given.
+    pub(crate) fn new_tile_layer(id: u32, name: Arc<str>, tiles: TileLayerData) -> Self {
+        Self::with_defaults(id, name, LayerDataType::Tiles(tiles))
+    }
+
+    /// Creates an empty layer of the kind given, used in place of a layer that failed to parse.
+    pub(crate) fn placeholder(
+        id: u32,
+        name: Arc<str>,
+        tag: LayerTag,
+        infinite: bool,
+        (width, height): (u32, u32),
+    ) -> Self {
+        let layer_type = match tag {
+            LayerTag::Tiles if infinite => {
+                LayerDataType::Tiles(TileLayerData::Infinite(InfiniteTileLayerData::empty()))
+            }
+            LayerTag::Tiles => LayerDataType::Tiles(TileLayerData::Finite(
+                FiniteTileLayerData::empty(width, height),
+            )),
+            LayerTag::Objects => LayerDataType::Objects(ObjectLayerData::empty()),
+            LayerTag::Image => LayerDataType::Image(ImageLayerData {
+                image: None,
+                repeat_x: false,
+                repeat_y: false,
+            }),
+            LayerTag::Group => LayerDataType::Group(GroupLayerData::empty()),
+        };
+        Self::with_defaults(id, name, layer_type)
+    }
+
+    fn with_defaults(id: u32, name: Arc<str>, layer_type: LayerDataType) -> Self {
+        Self {
+            name,
+            id,
+            visible: true,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            parallax_x: 1.0,
+            parallax_y: 1.0,
+            opacity: 1.0,
+            tint_color: None,
+            properties: Properties::new(),
+            user_type: None,
+            layer_type,
+        }
+    }
+}
+
 map_wrapper!(
     #[doc = "A generic map layer, accessed via [`Map::layers()`]."]
     Layer => LayerData
@@ -249,6 +511,130 @@ impl<'map> Layer<'map> {
             _ => None,
         }
     }
+
+    /// Returns the attributes of this layer combined with the ones of the group layers it is
+    /// nested in, which is how Tiled displays it.
+    ///
+    /// Since layers don't reference their parents, these are found by looking for the layer in
+    /// the map, so prefer [`Map::layers_flattened()`] when going through every layer.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_group_attributes.tmx")
+    ///     .unwrap();
+    /// let group = map.get_layer(0).unwrap().as_group_layer().unwrap();
+    /// let layer = group.get_layer(0).unwrap();
+    ///
+    /// assert_eq!(layer.offset_x, 4.0);
+    /// assert_eq!(layer.effective_offset(), (14.0, 6.0));
+    /// # }
+    /// ```
+    pub fn effective_attributes(&self) -> LayerAttributes {
+        let mut chain = Vec::new();
+        find_layer(&self.map.layers, self.data, &mut chain);
+        chain
+            .into_iter()
+            .fold(LayerAttributes::default(), LayerAttributes::combine)
+    }
+
+    /// Returns the offset of this layer in pixels, including the ones of the group layers it is
+    /// nested in. See [`Layer::effective_attributes()`].
+    pub fn effective_offset(&self) -> (f32, f32) {
+        let attributes = self.effective_attributes();
+        (attributes.offset_x, attributes.offset_y)
+    }
+
+    /// Returns the parallax factor of this layer, multiplied by the ones of the group layers it is
+    /// nested in. See [`Layer::effective_attributes()`].
+    pub fn effective_parallax(&self) -> (f32, f32) {
+        let attributes = self.effective_attributes();
+        (attributes.parallax_x, attributes.parallax_y)
+    }
+
+    /// Returns the opacity of this layer, multiplied by the ones of the group layers it is nested
+    /// in. See [`Layer::effective_attributes()`].
+    pub fn effective_opacity(&self) -> f32 {
+        self.effective_attributes().opacity
+    }
+
+    /// Returns the tint color of this layer, multiplied by the ones of the group layers it is
+    /// nested in, or [`None`] if neither it nor its parents are tinted. See
+    /// [`Layer::effective_attributes()`].
+    pub fn effective_tint(&self) -> Option<Color> {
+        self.effective_attributes().tint_color
+    }
+
+    /// Returns whether this layer and all of the group layers it is nested in are visible. See
+    /// [`Layer::effective_attributes()`].
+    pub fn effective_visibility(&self) -> bool {
+        self.effective_attributes().visible
+    }
+}
+
+/// The attributes of a layer that group layers pass on to the layers they contain, combined
+/// along the hierarchy of a map. Returned by [`Layer::effective_attributes()`] and
+/// [`Map::layers_flattened()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerAttributes {
+    /// The sum of the x offsets of the layer and its parents (in pixels).
+    pub offset_x: f32,
+    /// The sum of the y offsets of the layer and its parents (in pixels).
+    pub offset_y: f32,
+    /// The product of the x parallax factors of the layer and its parents.
+    pub parallax_x: f32,
+    /// The product of the y parallax factors of the layer and its parents.
+    pub parallax_y: f32,
+    /// The product of the opacities of the layer and its parents.
+    pub opacity: f32,
+    /// The product of the tint colors of the layer and its parents, if any of them is tinted.
+    pub tint_color: Option<Color>,
+    /// Whether the layer and all of its parents are visible.
+    pub visible: bool,
+}
+
+impl Default for LayerAttributes {
+    fn default() -> Self {
+        Self {
+            offset_x: 0.0,
+            offset_y: 0.0,
+            parallax_x: 1.0,
+            parallax_y: 1.0,
+            opacity: 1.0,
+            tint_color: None,
+            visible: true,
+        }
+    }
+}
+
+impl LayerAttributes {
+    /// Combines these attributes with the ones of a layer nested in the layer they belong to.
+    pub(crate) fn combine(self, layer: &LayerData) -> Self {
+        let tint_color = match (self.tint_color, layer.tint_color) {
+            (Some(a), Some(b)) => {
+                let mul = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+                Some(Color {
+                    alpha: mul(a.alpha, b.alpha),
+                    red: mul(a.red, b.red),
+                    green: mul(a.green, b.green),
+                    blue: mul(a.blue, b.blue),
+                })
+            }
+            (a, b) => a.or(b),
+        };
+        Self {
+            offset_x: self.offset_x + layer.offset_x,
+            offset_y: self.offset_y + layer.offset_y,
+            parallax_x: self.parallax_x * layer.parallax_x,
+            parallax_y: self.parallax_y * layer.parallax_y,
+            opacity: self.opacity * layer.opacity,
+            tint_color,
+            visible: self.visible && layer.visible,
+        }
+    }
 }
 
 /// Represents some kind of map layer.
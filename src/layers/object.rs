@@ -1,20 +1,28 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, ops::Deref, path::Path, sync::Arc};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
+    graph::LayerObjectIds,
     parse::xml::{Parser, ReadFrom, Reader},
     parse_properties,
+    spatial::ObjectIndex,
     util::{get_attrs, map_wrapper, parse_tag},
-    Color, Error, MapTilesetGid, Object, ObjectData, Properties, ResourceCache, Result, Tileset,
+    Color, Error, MapTilesetGid, Object, ObjectData, Properties, ResourceCache, Result, Template,
+    Tileset,
 };
 
 /// Raw data referring to a map object layer or tile collision data.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectLayerData {
-    objects: Vec<ObjectData>,
+    pub(crate) objects: Vec<ObjectData>,
     /// The color used in the editor to display objects in this layer.
     pub colour: Option<Color>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) index: ObjectIndex,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) ids: LayerObjectIds,
 }
 
 impl ObjectLayerData {
@@ -57,7 +65,7 @@ impl ObjectLayerData {
                 Ok(())
             },
         });
-        Ok((ObjectLayerData { objects, colour: c }, properties))
+        Ok((ObjectLayerData::with_objects(objects, c), properties))
     }
 
     /// Returns the data belonging to the objects contained within the layer, in the order they were
@@ -66,6 +74,27 @@ impl ObjectLayerData {
     pub fn object_data(&self) -> &[ObjectData] {
         self.objects.as_ref()
     }
+
+    pub(crate) fn with_objects(objects: Vec<ObjectData>, colour: Option<Color>) -> Self {
+        Self {
+            objects,
+            colour,
+            index: ObjectIndex::default(),
+            ids: LayerObjectIds::default(),
+        }
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self::with_objects(Vec::new(), None)
+    }
+
+    /// Gives mutable access to the objects, discarding the spatial index built from them.
+    #[inline]
+    pub(crate) fn object_data_mut(&mut self) -> &mut Vec<ObjectData> {
+        self.index.invalidate();
+        self.ids.invalidate();
+        &mut self.objects
+    }
 }
 
 map_wrapper!(
@@ -81,6 +110,15 @@ impl<'map> ObjectLayer<'map> {
             .map(|data| Object::new(self.map, data))
     }
 
+    /// Obtains the first object that has the name given, if there's one.
+    pub fn get_object_by_name(&self, name: &str) -> Option<Object<'map>> {
+        self.data
+            .objects
+            .iter()
+            .find(|object| object.name == name)
+            .map(|data| Object::new(self.map, data))
+    }
+
     /// Returns an iterator over the objects present in this layer, in the order they were declared
     /// in in the TMX file.
     ///
@@ -101,7 +139,7 @@ impl<'map> ObjectLayer<'map> {
     ///         _ => None,
     ///     })
     ///     .flat_map(|layer| layer.objects())
-    ///     .filter(|object| object.user_type == "spawn")
+    ///     .filter(|object| &*object.user_type == "spawn")
     ///     .collect();
     ///
     /// dbg!(spawnpoints);
@@ -116,3 +154,69 @@ impl<'map> ObjectLayer<'map> {
             .map(move |object| Object::new(map, object))
     }
 }
+
+/// A mutable handle to the data of an object layer, obtained via
+/// [`Map::get_object_layer_mut()`](crate::Map::get_object_layer_mut). Dereferences to the
+/// [`ObjectLayerData`] of the layer.
+#[derive(Debug)]
+pub struct ObjectLayerMut<'map> {
+    data: &'map mut ObjectLayerData,
+    next_object_id: &'map mut u32,
+}
+
+impl<'map> ObjectLayerMut<'map> {
+    pub(crate) fn new(data: &'map mut ObjectLayerData, next_object_id: &'map mut u32) -> Self {
+        Self {
+            data,
+            next_object_id,
+        }
+    }
+
+    /// Adds an object at the top of the layer, and returns the fresh ID it was given, which is
+    /// unique within the map.
+    pub fn add_object(&mut self, mut object: ObjectData) -> u32 {
+        let id = *self.next_object_id;
+        *self.next_object_id += 1;
+        object.id = id;
+        self.data.object_data_mut().push(object);
+        id
+    }
+
+    /// Creates a new object from a template through [`Template::instantiate_at()`] and adds it to
+    /// the layer. Returns the fresh ID of the object.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, ResourceCache};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let mut map = loader
+    ///     .load_tmx_map("assets/tiled_object_template.tmx")
+    ///     .unwrap();
+    /// let template = loader
+    ///     .cache()
+    ///     .get_template("assets/tiled_object_template.tx")
+    ///     .unwrap();
+    ///
+    /// let mut layer = map.get_object_layer_mut(2).unwrap();
+    /// let id = layer.spawn_from_template(&template, 64.0, 64.0);
+    ///
+    /// assert_eq!(id, 3);
+    /// assert_eq!(layer.object_data().last().unwrap().x, 64.0);
+    /// assert_eq!(map.next_object_id(), 4);
+    /// # }
+    /// ```
+    pub fn spawn_from_template(&mut self, template: &Template, x: f32, y: f32) -> u32 {
+        self.add_object(template.instantiate_at(x, y))
+    }
+}
+
+impl<'map> Deref for ObjectLayerMut<'map> {
+    type Target = ObjectLayerData;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
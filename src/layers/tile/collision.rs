@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+
+use crate::{BoundingBox, LayerTile, Map, ObjectData, ObjectShape, TileLayer, TileRect};
+
+/// A collision shape of a tile placed in a [`TileLayer`], in map pixel coordinates, as returned by
+/// [`TileLayer::collision_shapes()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileCollisionShape<'map> {
+    /// The tiles of the layer the shape comes from. A single tile, unless the shape was merged from
+    /// several solid tiles by [`TileLayer::merged_collision_shapes()`].
+    pub tiles: TileRect,
+    /// The X coordinate of the shape's position, in map pixels: The left edge of rectangles and
+    /// ellipses, and the origin of the points of polygons and polylines.
+    pub x: f32,
+    /// The Y coordinate of the shape's position, in map pixels: The top edge of rectangles and
+    /// ellipses, and the origin of the points of polygons and polylines.
+    pub y: f32,
+    /// The shape, flipped, rotated and scaled along with its tile. It is never rotated itself:
+    /// Rotated rectangles and ellipses are turned into polygons.
+    pub shape: ObjectShape,
+    /// The collision object of the tile the shape comes from, e.g. to read its properties.
+    /// [`None`] for shapes merged from several tiles.
+    pub object: Option<&'map ObjectData>,
+}
+
+impl<'map> TileLayer<'map> {
+    /// Returns the collision shapes of every tile of the layer, as set in the tile collision
+    /// editor, placed in map pixel coordinates and transformed according to the
+    /// [flip flags](crate::LayerTileData::flip_h) of each tile and the
+    /// [render size](crate::Tileset::tile_render_size) of its tileset.
+    ///
+    /// Tiles are placed on an orthogonal grid, aligned to the bottom-left corner of their cell, so
+    /// shapes are only accurate for orthogonal maps. Text objects are skipped.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, ObjectShape};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_tile_collision.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// for collider in layer.collision_shapes() {
+    ///     if let ObjectShape::Rect { width, height } = collider.shape {
+    ///         println!("{}x{} box at ({}, {})", width, height, collider.x, collider.y);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn collision_shapes(&self) -> impl Iterator<Item = TileCollisionShape<'map>> + 'map {
+        let map = self.map();
+        let (tile_width, tile_height) = (map.tile_width as f32, map.tile_height as f32);
+        self.chunks()
+            .flat_map(|chunk| chunk.tiles())
+            .flat_map(move |(x, y, tile)| {
+                let cell = BoundingBox::new(
+                    x as f32 * tile_width,
+                    y as f32 * tile_height,
+                    tile_width,
+                    tile_height,
+                );
+                tile_collision_shapes(x, y, tile, cell)
+            })
+    }
+
+    /// Returns the same shapes as [`TileLayer::collision_shapes()`], but with the shapes of
+    /// adjacent solid tiles merged into as few rectangles as possible, which makes for far fewer
+    /// colliders in levels built out of blocks.
+    ///
+    /// A tile is solid if its only collision shape is a rectangle covering its whole grid cell.
+    /// Rectangles are merged greedily: Each one extends as far right as possible, then as far
+    /// down as the whole row of solid tiles below it allows.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, TileRect};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_tile_collision.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// let shapes = layer.merged_collision_shapes();
+    /// // The six solid tiles in the top-left corner of the map make up a single rectangle.
+    /// assert!(shapes.iter().any(|shape| shape.tiles
+    ///     == TileRect { x: 0, y: 0, width: 3, height: 2 }));
+    /// # }
+    /// ```
+    pub fn merged_collision_shapes(&self) -> Vec<TileCollisionShape<'map>> {
+        let map = self.map();
+        let (tile_width, tile_height) = (map.tile_width as f32, map.tile_height as f32);
+        let mut shapes = Vec::new();
+        let mut solid = Vec::new();
+        for chunk in self.chunks() {
+            for (x, y, tile) in chunk.tiles() {
+                let cell = BoundingBox::new(
+                    x as f32 * tile_width,
+                    y as f32 * tile_height,
+                    tile_width,
+                    tile_height,
+                );
+                let tile_shapes: Vec<_> = tile_collision_shapes(x, y, tile, cell).collect();
+                if let [shape] = tile_shapes.as_slice() {
+                    if covers_cell(shape, &cell) {
+                        solid.push((x, y));
+                        continue;
+                    }
+                }
+                shapes.extend(tile_shapes);
+            }
+        }
+
+        solid.sort_unstable_by_key(|&(x, y)| (y, x));
+        let mut remaining: HashSet<_> = solid.iter().copied().collect();
+        for (x, y) in solid {
+            if !remaining.remove(&(x, y)) {
+                continue;
+            }
+            let mut width = 1;
+            while remaining.remove(&(x + width, y)) {
+                width += 1;
+            }
+            let mut height = 1;
+            while (x..x + width).all(|x| remaining.contains(&(x, y + height))) {
+                for x in x..x + width {
+                    remaining.remove(&(x, y + height));
+                }
+                height += 1;
+            }
+            shapes.push(TileCollisionShape {
+                tiles: TileRect {
+                    x,
+                    y,
+                    width: width as u32,
+                    height: height as u32,
+                },
+                x: x as f32 * tile_width,
+                y: y as f32 * tile_height,
+                shape: ObjectShape::Rect {
+                    width: width as f32 * tile_width,
+                    height: height as f32 * tile_height,
+                },
+                object: None,
+            });
+        }
+        shapes
+    }
+
+    pub(crate) fn map(&self) -> &'map Map {
+        match self {
+            TileLayer::Finite(finite) => finite.map(),
+            TileLayer::Infinite(infinite) => infinite.map(),
+        }
+    }
+}
+
+/// Returns the collision shapes of a tile placed in the grid cell given.
+fn tile_collision_shapes<'map>(
+    x: i32,
+    y: i32,
+    tile: LayerTile<'map>,
+    cell: BoundingBox,
+) -> impl Iterator<Item = TileCollisionShape<'map>> + 'map {
+    let tileset = tile.get_tileset();
+    let objects = tile
+        .get_tile()
+        .and_then(|data| data.data.collision.as_ref())
+        .map(|collision| collision.objects.as_slice())
+        .unwrap_or_default();
+    let transform = tileset
+        .tile_size(tile.id())
+        .zip(tileset.tile_draw_rect(tile.id(), cell))
+        .map(|((width, height), rect)| TileTransform {
+            width,
+            height,
+            rect,
+            flip_h: tile.flip_h,
+            flip_v: tile.flip_v,
+            flip_d: tile.flip_d,
+        });
+    objects.iter().filter_map(move |object| {
+        let (shape_x, shape_y, shape) = transform.as_ref()?.apply(object)?;
+        Some(TileCollisionShape {
+            tiles: TileRect {
+                x,
+                y,
+                width: 1,
+                height: 1,
+            },
+            x: shape_x,
+            y: shape_y,
+            shape,
+            object: Some(object),
+        })
+    })
+}
+
+/// Returns whether a shape is a rectangle covering the whole grid cell given.
+fn covers_cell(shape: &TileCollisionShape, cell: &BoundingBox) -> bool {
+    const EPSILON: f32 = 1e-3;
+    match shape.shape {
+        ObjectShape::Rect { width, height } => {
+            (shape.x - cell.x).abs() < EPSILON
+                && (shape.y - cell.y).abs() < EPSILON
+                && (width - cell.width).abs() < EPSILON
+                && (height - cell.height).abs() < EPSILON
+        }
+        _ => false,
+    }
+}
+
+/// Maps points of a tile's collision layer to map pixel coordinates.
+struct TileTransform {
+    /// The size of the tile's image.
+    width: f32,
+    height: f32,
+    /// The rectangle the tile is drawn in.
+    rect: BoundingBox,
+    flip_h: bool,
+    flip_v: bool,
+    flip_d: bool,
+}
+
+impl TileTransform {
+    fn point(&self, (mut x, mut y): (f32, f32)) -> (f32, f32) {
+        let (mut width, mut height) = (self.width, self.height);
+        // As in Tiled, the diagonal flip is applied first
+        if self.flip_d {
+            std::mem::swap(&mut x, &mut y);
+            std::mem::swap(&mut width, &mut height);
+        }
+        if self.flip_h {
+            x = width - x;
+        }
+        if self.flip_v {
+            y = height - y;
+        }
+        (
+            self.rect.x + x * self.rect.width / width,
+            self.rect.y + y * self.rect.height / height,
+        )
+    }
+
+    /// Returns the position and shape of a collision object in map pixel coordinates.
+    fn apply(&self, object: &ObjectData) -> Option<(f32, f32, ObjectShape)> {
+        let rotated = object.rotation != 0.0;
+        let (sin, cos) = object.rotation.to_radians().sin_cos();
+        let map_point = |(x, y): (f32, f32)| {
+            self.point((object.x + x * cos - y * sin, object.y + x * sin + y * cos))
+        };
+        match &object.shape {
+            ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height }
+                if !rotated =>
+            {
+                let (x1, y1) = map_point((0.0, 0.0));
+                let (x2, y2) = map_point((*width, *height));
+                let (width, height) = ((x2 - x1).abs(), (y2 - y1).abs());
+                let shape = match object.shape {
+                    ObjectShape::Rect { .. } => ObjectShape::Rect { width, height },
+                    _ => ObjectShape::Ellipse { width, height },
+                };
+                Some((x1.min(x2), y1.min(y2), shape))
+            }
+            ObjectShape::Rect { .. } | ObjectShape::Ellipse { .. } => {
+                let (x, y) = map_point((0.0, 0.0));
+                let points = object
+                    .shape
+                    .vertices()
+                    .into_iter()
+                    .map(|point| {
+                        let (point_x, point_y) = map_point(point);
+                        (point_x - x, point_y - y)
+                    })
+                    .collect();
+                Some((x, y, ObjectShape::Polygon { points }))
+            }
+            ObjectShape::Polygon { points } | ObjectShape::Polyline { points } => {
+                let (x, y) = map_point((0.0, 0.0));
+                let points = points
+                    .iter()
+                    .map(|&point| {
+                        let (point_x, point_y) = map_point(point);
+                        (point_x - x, point_y - y)
+                    })
+                    .collect();
+                let shape = match object.shape {
+                    ObjectShape::Polygon { .. } => ObjectShape::Polygon { points },
+                    _ => ObjectShape::Polyline { points },
+                };
+                Some((x, y, shape))
+            }
+            ObjectShape::Point(..) => {
+                let (x, y) = map_point((0.0, 0.0));
+                Some((x, y, ObjectShape::Point(x, y)))
+            }
+            ObjectShape::Text { .. } => None,
+        }
+    }
+}
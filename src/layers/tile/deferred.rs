@@ -0,0 +1,88 @@
+//! Decoding of the tiles of finite tile layers in parallel once a map is parsed, with the `rayon`
+//! feature.
+
+use rayon::prelude::*;
+
+use crate::{
+    layers::LayerDataType,
+    parse::xml::{Parser, Reader},
+    FiniteTileLayerData, LayerData, LoaderOptions, MapTilesetGid, Result, TileLayerData,
+};
+
+use super::util::{decode_tile_data, read_tile_data};
+
+/// The tile data of a finite tile layer, which was read while parsing a map but is only decoded
+/// once the whole map is parsed, along with the data of the other layers.
+///
+/// Deferred data is stored in the parser in the order finite tile layers are parsed, which is the
+/// order they are found in when going through the layers of the map depth-first. Layers without
+/// any data store [`None`] so that this stays true.
+pub(crate) struct DeferredTiles {
+    encoding: Option<String>,
+    compression: Option<String>,
+    data: Option<Vec<u8>>,
+}
+
+impl DeferredTiles {
+    /// Reads the tile data of a `<data>` element, whose encoding and compression format must be
+    /// decodable.
+    pub(crate) async fn read<R: Reader>(
+        parser: &mut Parser<R>,
+        encoding: Option<&str>,
+        compression: Option<&str>,
+    ) -> Result<Self> {
+        Ok(Self {
+            encoding: encoding.map(ToOwned::to_owned),
+            compression: compression.map(ToOwned::to_owned),
+            data: read_tile_data(parser, encoding, "data").await?,
+        })
+    }
+}
+
+/// Decodes the deferred data of the finite tile layers given, in parallel, and stores it in the
+/// layers.
+pub(crate) fn decode_deferred_tiles(
+    layers: &mut [LayerData],
+    deferred: Vec<Option<DeferredTiles>>,
+    tilesets: &[MapTilesetGid],
+    options: &LoaderOptions,
+) -> Result<()> {
+    let mut finite_layers = Vec::new();
+    collect_finite_layers(layers, &mut finite_layers);
+    debug_assert_eq!(finite_layers.len(), deferred.len());
+
+    finite_layers
+        .into_par_iter()
+        .zip(deferred)
+        .try_for_each(|(layer, deferred)| {
+            if let Some(deferred) = deferred {
+                let tiles = decode_tile_data(
+                    deferred.encoding.as_deref(),
+                    deferred.compression.as_deref(),
+                    deferred.data.as_deref(),
+                    tilesets,
+                )?;
+                *layer = FiniteTileLayerData::from_decoded(
+                    layer.width(),
+                    layer.height(),
+                    tiles,
+                    options,
+                );
+            }
+            Ok(())
+        })
+}
+
+/// Lists the finite tile layers among the layers given, depth-first.
+fn collect_finite_layers<'a>(
+    layers: &'a mut [LayerData],
+    finite_layers: &mut Vec<&'a mut FiniteTileLayerData>,
+) {
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(finite)) => finite_layers.push(finite),
+            LayerDataType::Group(group) => collect_finite_layers(&mut group.layers, finite_layers),
+            _ => {}
+        }
+    }
+}
@@ -1,20 +1,114 @@
+use std::collections::HashMap;
+
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     parse::xml::{Parser, Reader},
     util::{get_attrs, map_wrapper},
-    LayerTile, LayerTileData, MapTilesetGid, Result,
+    LayerTile, LayerTileData, LoaderOptions, MapTilesetGid, Result, TileRect,
 };
 
 use super::util::parse_data_line;
+#[cfg(feature = "rayon")]
+use super::{util::is_decodable, DeferredTiles};
 
 /// The raw data of a [`FiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
-#[derive(PartialEq, Clone, Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FiniteTileLayerData {
     width: u32,
     height: u32,
     /// The tiles are arranged in rows.
-    tiles: Vec<Option<LayerTileData>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "serde_tiles::deserialize")
+    )]
+    tiles: FiniteTiles,
+}
+
+/// The storage of the tiles of a finite layer, arranged in rows.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum FiniteTiles {
+    /// Every cell holds its tile data.
+    Full(Vec<Option<LayerTileData>>),
+    /// Every cell holds a 32-bit index into a table of the distinct tiles of the layer, where 0
+    /// stands for an empty cell and `n` for `palette[n - 1]`.
+    Compact {
+        palette: Vec<LayerTileData>,
+        cells: Vec<u32>,
+    },
+}
+
+impl Default for FiniteTiles {
+    fn default() -> Self {
+        Self::Full(Vec::new())
+    }
+}
+
+impl FiniteTiles {
+    fn get(&self, index: usize) -> Option<&LayerTileData> {
+        match self {
+            Self::Full(tiles) => tiles[index].as_ref(),
+            Self::Compact { palette, cells } => match cells[index] {
+                0 => None,
+                cell => Some(&palette[cell as usize - 1]),
+            },
+        }
+    }
+
+    fn set(&mut self, index: usize, tile: Option<LayerTileData>) {
+        match self {
+            Self::Full(tiles) => tiles[index] = tile,
+            Self::Compact { palette, cells } => {
+                cells[index] = match tile {
+                    None => 0,
+                    Some(tile) => match palette.iter().position(|entry| *entry == tile) {
+                        Some(position) => position as u32 + 1,
+                        None => {
+                            palette.push(tile);
+                            palette.len() as u32
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = Option<&LayerTileData>> + '_> {
+        match self {
+            Self::Full(tiles) => Box::new(tiles.iter().map(Option::as_ref)),
+            Self::Compact { palette, cells } => Box::new(
+                cells
+                    .iter()
+                    .map(move |&cell| cell.checked_sub(1).map(|cell| &palette[cell as usize])),
+            ),
+        }
+    }
+
+    fn compact(tiles: &[Option<LayerTileData>]) -> Self {
+        let mut palette = Vec::new();
+        let mut indices = HashMap::new();
+        let cells = tiles
+            .iter()
+            .map(|tile| match tile {
+                None => 0,
+                Some(tile) => *indices.entry(*tile).or_insert_with(|| {
+                    palette.push(*tile);
+                    palette.len() as u32
+                }),
+            })
+            .collect();
+        Self::Compact { palette, cells }
+    }
+}
+
+impl PartialEq for FiniteTileLayerData {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.tiles.iter().eq(other.tiles.iter())
+    }
 }
 
 impl std::fmt::Debug for FiniteTileLayerData {
@@ -39,6 +133,17 @@ impl FiniteTileLayerData {
         self.height
     }
 
+    /// Returns the rectangle of tiles this layer spans, which starts at the origin.
+    #[inline]
+    pub fn bounds(&self) -> TileRect {
+        TileRect {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
         attrs: Vec<Attribute<'_>>,
@@ -54,13 +159,121 @@ impl FiniteTileLayerData {
             (encoding, compression)
         );
 
+        #[cfg(feature = "rayon")]
+        if parser.deferred_tiles.is_some() && is_decodable(e, c) {
+            let tiles = DeferredTiles::read(parser, e, c).await?;
+            if let Some(deferred) = &mut parser.deferred_tiles {
+                deferred.push(Some(tiles));
+            }
+            // The tiles are filled in once the map is parsed
+            return Ok(Self {
+                width,
+                height,
+                tiles: FiniteTiles::Full(Vec::new()),
+            });
+        }
+
         let tiles = parse_data_line(e, c, parser, tilesets).await?;
+        Ok(Self::from_decoded(width, height, tiles, &parser.options))
+    }
 
-        Ok(Self {
+    /// Creates a layer from the tiles decoded from its data, in the representation chosen by the
+    /// options given. Missing tiles, e.g. of empty data, are left empty.
+    pub(crate) fn from_decoded(
+        width: u32,
+        height: u32,
+        mut tiles: Vec<Option<LayerTileData>>,
+        options: &LoaderOptions,
+    ) -> Self {
+        tiles.resize(width as usize * height as usize, None);
+        let mut layer = Self {
             width,
             height,
-            tiles,
-        })
+            tiles: FiniteTiles::Full(tiles),
+        };
+        if options.compact_tile_layers {
+            layer.compact();
+        }
+        layer
+    }
+
+    /// Creates a layer from its tiles, arranged in rows. Missing tiles are left empty.
+    #[cfg(feature = "json")]
+    pub(crate) fn from_tiles(
+        width: u32,
+        height: u32,
+        mut tiles: Vec<Option<LayerTileData>>,
+    ) -> Self {
+        tiles.resize(width as usize * height as usize, None);
+        Self {
+            width,
+            height,
+            tiles: FiniteTiles::Full(tiles),
+        }
+    }
+
+    /// Creates a layer of the given size without any tile in it, e.g. to build a map in code.
+    pub fn empty(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            tiles: FiniteTiles::Full(vec![None; width as usize * height as usize]),
+        }
+    }
+
+    /// Switches the layer to its compact representation, where each tile takes up 4 bytes
+    /// instead of 16 by referring to a table of the distinct tiles of the layer. Reading tiles
+    /// stays as fast, while setting them becomes slower the more distinct tiles the layer has.
+    ///
+    /// Does nothing if the layer is already compact. Layers can also be loaded in this
+    /// representation through [`LoaderOptions::compact_tile_layers`](crate::LoaderOptions::compact_tile_layers).
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{FiniteTileLayerData, LayerTileData};
+    ///
+    /// let mut layer = FiniteTileLayerData::empty(64, 64);
+    /// layer.set_tile_data(3, 4, Some(LayerTileData::new(0, 12, false, false, false)));
+    /// let full = layer.clone();
+    ///
+    /// layer.compact();
+    /// assert!(layer.is_compact());
+    /// assert_eq!(layer.get_tile_data(3, 4).unwrap().id(), 12);
+    /// assert_eq!(layer, full);
+    /// ```
+    pub fn compact(&mut self) {
+        if let FiniteTiles::Full(tiles) = &self.tiles {
+            self.tiles = FiniteTiles::compact(tiles);
+        }
+    }
+
+    /// Returns whether the layer uses the compact representation described in
+    /// [`Self::compact()`].
+    pub fn is_compact(&self) -> bool {
+        matches!(self.tiles, FiniteTiles::Compact { .. })
+    }
+
+    /// Replaces the tile data at the position given, or clears it if `tile` is [`None`].
+    ///
+    /// Positions outside of the layer are ignored.
+    pub fn set_tile_data(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) {
+        if x < self.width as i32 && y < self.height as i32 && x >= 0 && y >= 0 {
+            self.tiles
+                .set(x as usize + y as usize * self.width as usize, tile);
+        }
+    }
+
+    pub(crate) fn tile_data(&self) -> impl Iterator<Item = &LayerTileData> {
+        self.tiles.iter().flatten()
+    }
+
+    /// Returns the tile data of every non-empty cell, or of every distinct tile of compact
+    /// layers, which is shared between the cells holding it.
+    pub(crate) fn tile_data_mut(&mut self) -> Box<dyn Iterator<Item = &mut LayerTileData> + '_> {
+        match &mut self.tiles {
+            FiniteTiles::Full(tiles) => Box::new(tiles.iter_mut().flatten()),
+            FiniteTiles::Compact { palette, .. } => Box::new(palette.iter_mut()),
+        }
     }
 
     /// Obtains the tile data present at the position given.
@@ -70,7 +283,8 @@ impl FiniteTileLayerData {
     /// If you want to get a [`Tile`](`crate::Tile`) instead, use [`FiniteTileLayer::get_tile()`].
     pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
         if x < self.width as i32 && y < self.height as i32 && x >= 0 && y >= 0 {
-            self.tiles[x as usize + y as usize * self.width as usize].as_ref()
+            self.tiles
+                .get(x as usize + y as usize * self.width as usize)
         } else {
             None
         }
@@ -92,3 +306,26 @@ impl<'map> FiniteTileLayer<'map> {
             .map(|data| LayerTile::new(self.map(), data))
     }
 }
+
+#[cfg(feature = "serde")]
+mod serde_tiles {
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    use super::FiniteTiles;
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FiniteTiles, D::Error> {
+        let tiles = FiniteTiles::deserialize(deserializer)?;
+        if let FiniteTiles::Compact { palette, cells } = &tiles {
+            if let Some(&cell) = cells.iter().find(|&&cell| cell as usize > palette.len()) {
+                return Err(D::Error::custom(format!(
+                    "tile {} is past the {} tiles of the palette",
+                    cell,
+                    palette.len()
+                )));
+            }
+        }
+        Ok(tiles)
+    }
+}
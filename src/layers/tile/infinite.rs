@@ -1,23 +1,34 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, Mutex, MutexGuard, Weak},
+};
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     parse::xml::{Parser, Reader},
     util::{floor_div, get_attrs, map_wrapper, parse_tag},
-    Error, LayerTile, LayerTileData, MapTilesetGid, Result,
+    ChunkHandler, Error, LayerTile, LayerTileData, MapTilesetGid, Result, TileRect,
 };
 
-use super::util::parse_data_line;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use super::util::{decode_tile_data, is_decodable, read_tile_data, unsupported_encoding};
 
 /// The raw data of a [`InfiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InfiniteTileLayerData {
+    // Serialized as a list, since formats such as JSON only support string keys
+    #[cfg_attr(feature = "serde", serde(with = "serde_chunks"))]
     chunks: HashMap<(i32, i32), ChunkData>,
 }
 
-impl std::fmt::Debug for InfiniteTileLayerData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl fmt::Debug for InfiniteTileLayerData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("InfiniteTileLayerData").finish()
     }
 }
@@ -26,6 +37,7 @@ impl InfiniteTileLayerData {
     pub(crate) async fn new<R: Reader>(
         parser: &mut Parser<R>,
         attrs: Vec<Attribute<'_>>,
+        layer_id: u32,
         tilesets: &[MapTilesetGid],
     ) -> Result<Self> {
         let (e, c) = get_attrs!(
@@ -36,31 +48,160 @@ impl InfiniteTileLayerData {
             (encoding, compression)
         );
 
-        let mut chunks = HashMap::<(i32, i32), ChunkData>::new();
+        let mut data = Self::empty();
+        let mut chunks = Vec::new();
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "data", {
             "chunk" => for attrs {
-                let chunk = InternalChunk::new(parser, attrs, e, c, tilesets).await?;
-                for x in chunk.x..chunk.x + chunk.width as i32 {
-                    for y in chunk.y..chunk.y + chunk.height as i32 {
-                        let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
-                        let relative_pos = (x - chunk_pos.0 * ChunkData::WIDTH as i32, y - chunk_pos.1 * ChunkData::HEIGHT as i32);
-                        let chunk_index = (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
-                        let internal_pos = (x - chunk.x, y - chunk.y);
-                        let internal_index = (internal_pos.0 + internal_pos.1 * chunk.width as i32) as usize;
-
-                        if internal_index >= chunk.tiles.len() {
-                            return Err(Error::InvalidTileFound);
-                        }
-
-                        chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] = chunk.tiles[internal_index];
-                    }
+                parser.check_cancelled()?;
+                if !is_decodable(e, c) {
+                    return Err(unsupported_encoding(e, c));
+                }
+                chunks.push(RawChunk::new(parser, attrs, e).await?);
+                if chunks.len() == CHUNK_BATCH_SIZE {
+                    data.push_raw_chunks(
+                        layer_id,
+                        std::mem::take(&mut chunks),
+                        (e, c),
+                        tilesets,
+                        parser.options.chunk_handler.as_ref(),
+                    )?;
                 }
                 Ok(())
             }
         });
+        data.push_raw_chunks(
+            layer_id,
+            chunks,
+            (e, c),
+            tilesets,
+            parser.options.chunk_handler.as_ref(),
+        )?;
+        if let Some(pool) = &parser.options.chunk_pool {
+            data.share_chunks(pool);
+        }
+
+        Ok(data)
+    }
+
+    /// Decodes chunks, in parallel with the `rayon` feature, then adds them to the layer in the
+    /// order they were read.
+    fn push_raw_chunks(
+        &mut self,
+        layer_id: u32,
+        chunks: Vec<RawChunk>,
+        (encoding, compression): (Option<&str>, Option<&str>),
+        tilesets: &[MapTilesetGid],
+        handler: Option<&ChunkHandler>,
+    ) -> Result<()> {
+        #[cfg(feature = "rayon")]
+        let chunks = chunks.into_par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let chunks = chunks.into_iter();
+        let chunks: Vec<InternalChunk> = chunks
+            .map(|chunk| chunk.decode(encoding, compression, tilesets))
+            .collect::<Result<_>>()?;
+        for chunk in chunks {
+            self.push_chunk(
+                layer_id,
+                (chunk.x, chunk.y, chunk.width, chunk.height),
+                &chunk.tiles,
+                handler,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Creates a layer without any chunk in it, e.g. to build a map in code.
+    pub fn empty() -> Self {
+        Self {
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Adds a chunk as found in a file, whose tiles are arranged in rows, to the layer. The chunk
+    /// is passed to the chunk handler if there's one, which may prevent it from being stored.
+    pub(crate) fn push_chunk(
+        &mut self,
+        layer_id: u32,
+        (x, y, width, height): (i32, i32, u32, u32),
+        tiles: &[Option<LayerTileData>],
+        handler: Option<&ChunkHandler>,
+    ) -> Result<()> {
+        if tiles.len() < width as usize * height as usize {
+            return Err(Error::InvalidTileFound);
+        }
+        if let Some(handler) = handler {
+            handler.handle(StreamedChunk {
+                layer_id,
+                x,
+                y,
+                width,
+                height,
+                tiles,
+            });
+            if !handler.retains_chunks() {
+                return Ok(());
+            }
+        }
+        for tile_y in 0..height as i32 {
+            for tile_x in 0..width as i32 {
+                let (pos_x, pos_y) = (x + tile_x, y + tile_y);
+                let chunk_pos = ChunkData::tile_to_chunk_pos(pos_x, pos_y);
+                let relative_pos = (
+                    pos_x - chunk_pos.0 * ChunkData::WIDTH as i32,
+                    pos_y - chunk_pos.1 * ChunkData::HEIGHT as i32,
+                );
+                let chunk_index =
+                    (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
+                let chunk = self.chunks.entry(chunk_pos).or_insert_with(ChunkData::new);
+                Arc::make_mut(&mut chunk.tiles)[chunk_index] =
+                    tiles[(tile_x + tile_y * width as i32) as usize];
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn tile_data(&self) -> impl Iterator<Item = &LayerTileData> {
+        self.chunks
+            .values()
+            .flat_map(|chunk| chunk.tiles.iter().flatten())
+    }
 
-        Ok(Self { chunks })
+    pub(crate) fn tile_data_mut(&mut self) -> impl Iterator<Item = &mut LayerTileData> {
+        self.chunks
+            .values_mut()
+            .flat_map(|chunk| Arc::make_mut(&mut chunk.tiles).iter_mut().flatten())
+    }
+
+    /// Replaces the tiles of every chunk of the layer with identical ones from the pool given, if
+    /// there are some, so that they share memory. The other chunks are added to the pool.
+    pub(crate) fn share_chunks(&mut self, pool: &ChunkPool) {
+        for chunk in self.chunks.values_mut() {
+            chunk.tiles = pool.share(chunk.tiles.clone());
+        }
+    }
+
+    /// Replaces the tile data at the position given, or clears it if `tile` is [`None`]. Chunks
+    /// are created as needed.
+    pub fn set_tile_data(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) {
+        let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
+        let relative_pos = (
+            x - chunk_pos.0 * ChunkData::WIDTH as i32,
+            y - chunk_pos.1 * ChunkData::HEIGHT as i32,
+        );
+        let chunk_index = (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
+        match tile {
+            Some(_) => {
+                let chunk = self.chunks.entry(chunk_pos).or_insert_with(ChunkData::new);
+                Arc::make_mut(&mut chunk.tiles)[chunk_index] = tile;
+            }
+            None => {
+                if let Some(chunk) = self.chunks.get_mut(&chunk_pos) {
+                    Arc::make_mut(&mut chunk.tiles)[chunk_index] = None;
+                }
+            }
+        }
     }
 
     /// Obtains the tile data present at the position given.
@@ -106,15 +247,48 @@ impl InfiniteTileLayerData {
     pub fn get_chunk_data(&self, x: i32, y: i32) -> Option<&ChunkData> {
         self.chunks.get(&(x, y))
     }
+
+    /// Returns the smallest rectangle of tiles that contains every chunk with at least one tile
+    /// in it. Since it is aligned to chunks, the rectangle may contain empty rows and columns at
+    /// its edges.
+    ///
+    /// Returns an empty rectangle at the origin if the layer doesn't contain any tile.
+    pub fn bounds(&self) -> TileRect {
+        let mut occupied = self
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| chunk.tiles.iter().any(Option::is_some))
+            .map(|(pos, _)| *pos);
+        let Some(first) = occupied.next() else {
+            return TileRect::default();
+        };
+        let (min, max) = occupied.fold((first, first), |(min, max), (x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        });
+        TileRect {
+            x: min.0 * ChunkData::WIDTH as i32,
+            y: min.1 * ChunkData::HEIGHT as i32,
+            width: (max.0 - min.0 + 1) as u32 * ChunkData::WIDTH,
+            height: (max.1 - min.1 + 1) as u32 * ChunkData::HEIGHT,
+        }
+    }
 }
 
+/// The tiles of a chunk, arranged in rows.
+type ChunkTiles = [Option<LayerTileData>; ChunkData::TILE_COUNT];
+
 /// Part of an infinite tile layer's data.
 ///
 /// Has only the tile data contained within and not a reference to the map it is part of.
 /// In 99.99% of cases you'll actually want to use [`Chunk`].
+///
+/// The tiles of chunks are shared with identical chunks loaded with the same [`ChunkPool`], and
+/// copied when they are edited.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChunkData {
-    tiles: Box<[Option<LayerTileData>; Self::TILE_COUNT]>,
+    #[cfg_attr(feature = "serde", serde(with = "serde_chunk_tiles"))]
+    tiles: Arc<ChunkTiles>,
 }
 
 impl ChunkData {
@@ -130,7 +304,7 @@ impl ChunkData {
 
     pub(crate) fn new() -> Self {
         Self {
-            tiles: Box::new([None; Self::TILE_COUNT]),
+            tiles: Arc::new([None; Self::TILE_COUNT]),
         }
     }
 
@@ -156,6 +330,120 @@ impl ChunkData {
     }
 }
 
+/// A pool of the tiles of the chunks of infinite tile layers, through which identical chunks share
+/// the same memory, set through [`LoaderOptions::chunk_pool`](crate::LoaderOptions::chunk_pool).
+///
+/// Large infinite maps often repeat the same chunks many times, e.g. in regions filled with water
+/// or left empty, and only need to store them once this way. Chunks are shared across every layer
+/// and map loaded with the same pool or any of its clones. The pool only keeps weak references to
+/// chunks, which are pruned as more chunks are added to it, so chunks are freed once every map
+/// using them is dropped and the pool doesn't grow past the chunks still in use.
+///
+/// Two pools are always equal, since sharing chunks doesn't change the maps that are loaded.
+#[derive(Clone, Default)]
+pub struct ChunkPool(Arc<Mutex<PooledChunks>>);
+
+#[derive(Default)]
+struct PooledChunks {
+    /// The chunks, keyed by the hash of their tiles.
+    chunks: HashMap<u64, Vec<Weak<ChunkTiles>>>,
+    /// The number of chunks in `chunks`, including the ones no map uses anymore.
+    len: usize,
+    /// The number of chunks past which the ones no map uses anymore are pruned.
+    prune_at: usize,
+}
+
+impl PooledChunks {
+    /// Removes the chunks that no map uses anymore.
+    fn prune(&mut self) {
+        self.chunks.retain(|_, candidates| {
+            candidates.retain(|candidate| candidate.strong_count() > 0);
+            !candidates.is_empty()
+        });
+        self.len = self.chunks.values().map(Vec::len).sum();
+        // Pruning again once the pool has doubled keeps the cost of pruning constant per chunk.
+        self.prune_at = (self.len * 2).max(64);
+    }
+}
+
+impl ChunkPool {
+    /// Creates an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct chunks in the pool that are used by a map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{ChunkPool, Loader, LoaderOptions};
+    ///
+    /// # fn main() {
+    /// let pool = ChunkPool::new();
+    /// let mut loader = Loader::new();
+    /// loader.set_options(LoaderOptions {
+    ///     chunk_pool: Some(pool.clone()),
+    ///     ..Default::default()
+    /// });
+    /// let map = loader
+    ///     .load_tmx_map("assets/tiled_repeated_chunks.tmx")
+    ///     .unwrap();
+    /// assert!(!pool.is_empty());
+    ///
+    /// drop(map);
+    /// assert_eq!(pool.len(), 0);
+    /// # }
+    /// ```
+    pub fn len(&self) -> usize {
+        let mut pool = self.lock();
+        pool.prune();
+        pool.len
+    }
+
+    /// Returns whether no map uses any chunk of the pool.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns tiles identical to the ones given from the pool if there are some, or adds the
+    /// ones given to the pool and returns them.
+    fn share(&self, tiles: Arc<ChunkTiles>) -> Arc<ChunkTiles> {
+        let mut hasher = DefaultHasher::new();
+        tiles.hash(&mut hasher);
+        let mut pool = self.lock();
+        let candidates = pool.chunks.entry(hasher.finish()).or_default();
+        for candidate in candidates.iter().filter_map(Weak::upgrade) {
+            if candidate == tiles {
+                return candidate;
+            }
+        }
+        candidates.push(Arc::downgrade(&tiles));
+        pool.len += 1;
+        if pool.len > pool.prune_at {
+            pool.prune();
+        }
+        tiles
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PooledChunks> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl fmt::Debug for ChunkPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ChunkPool").finish()
+    }
+}
+
+impl PartialEq for ChunkPool {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ChunkPool {}
+
 map_wrapper!(
     #[doc = "Part of an [`InfiniteTileLayer`]."]
     Chunk => ChunkData
@@ -172,17 +460,49 @@ impl<'map> Chunk<'map> {
     }
 }
 
+/// A chunk of an infinite tile layer, as found in the file, delivered to a
+/// [`ChunkHandler`](crate::ChunkHandler) while the layer is being parsed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StreamedChunk<'a> {
+    /// The ID of the layer this chunk belongs to.
+    pub layer_id: u32,
+    /// The X coordinate of the top-left-most tile in the chunk.
+    pub x: i32,
+    /// The Y coordinate of the top-left-most tile in the chunk.
+    pub y: i32,
+    /// The width of the chunk, in tiles.
+    pub width: u32,
+    /// The height of the chunk, in tiles.
+    pub height: u32,
+    /// The tiles of the chunk, arranged in rows.
+    pub tiles: &'a [Option<LayerTileData>],
+}
+
+impl<'a> StreamedChunk<'a> {
+    /// Obtains the tile data present at the position given, in layer tile coordinates.
+    ///
+    /// If the position is outside of the chunk or the position is empty, this function will
+    /// return [`None`].
+    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&'a LayerTileData> {
+        let (x, y) = (x - self.x, y - self.y);
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        self.tiles[x as usize + y as usize * self.width as usize].as_ref()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
-struct InternalChunk {
+pub(crate) struct InternalChunk {
     /// The X coordinate of the top-left-most tile in the chunk.
     /// Corresponds to the `x` attribute in the TMX format.
-    x: i32,
+    pub(crate) x: i32,
     /// The Y coordinate of the top-left-most tile in the chunk.
     /// Corresponds to the `y` attribute in the TMX format.
-    y: i32,
-    width: u32,
-    height: u32,
-    tiles: Vec<Option<LayerTileData>>,
+    pub(crate) y: i32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) tiles: Vec<Option<LayerTileData>>,
 }
 
 impl InternalChunk {
@@ -192,6 +512,38 @@ impl InternalChunk {
         encoding: Option<&str>,
         compression: Option<&str>,
         tilesets: &[MapTilesetGid],
+    ) -> Result<Self> {
+        if !is_decodable(encoding, compression) {
+            return Err(unsupported_encoding(encoding, compression));
+        }
+        RawChunk::new(parser, attrs, encoding)
+            .await?
+            .decode(encoding, compression, tilesets)
+    }
+}
+
+/// The amount of chunks of an infinite tile layer that are read before being decoded together,
+/// which bounds the memory taken by chunks that weren't decoded yet.
+#[cfg(feature = "rayon")]
+const CHUNK_BATCH_SIZE: usize = 64;
+#[cfg(not(feature = "rayon"))]
+const CHUNK_BATCH_SIZE: usize = 1;
+
+/// A chunk whose tile data was read but not decoded yet, so that several chunks can be decoded
+/// in parallel.
+struct RawChunk {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    data: Option<Vec<u8>>,
+}
+
+impl RawChunk {
+    async fn new<R: Reader>(
+        parser: &mut Parser<R>,
+        attrs: Vec<Attribute<'_>>,
+        encoding: Option<&str>,
     ) -> Result<Self> {
         let (x, y, width, height) = get_attrs!(
             for v in attrs {
@@ -202,14 +554,28 @@ impl InternalChunk {
             }
             (x, y, width, height)
         );
-
-        let tiles = parse_data_line(encoding, compression, parser, tilesets).await?;
-
-        Ok(InternalChunk {
+        let data = read_tile_data(parser, encoding, "chunk").await?;
+        Ok(Self {
             x,
             y,
             width,
             height,
+            data,
+        })
+    }
+
+    fn decode(
+        self,
+        encoding: Option<&str>,
+        compression: Option<&str>,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<InternalChunk> {
+        let tiles = decode_tile_data(encoding, compression, self.data.as_deref(), tilesets)?;
+        Ok(InternalChunk {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
             tiles,
         })
     }
@@ -287,3 +653,58 @@ impl<'map> InfiniteTileLayer<'map> {
             .map(move |data| Chunk::new(map, data))
     }
 }
+
+#[cfg(feature = "serde")]
+mod serde_chunks {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::ChunkData;
+
+    pub(super) fn serialize<S: Serializer>(
+        chunks: &HashMap<(i32, i32), ChunkData>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(chunks)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<(i32, i32), ChunkData>, D::Error> {
+        Ok(Vec::<((i32, i32), ChunkData)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_chunk_tiles {
+    use std::{convert::TryInto, sync::Arc};
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::ChunkTiles;
+    use crate::LayerTileData;
+
+    type Tiles = Arc<ChunkTiles>;
+
+    pub(super) fn serialize<S: Serializer>(
+        tiles: &Tiles,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(tiles.iter())
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Tiles, D::Error> {
+        let tiles = Vec::<Option<LayerTileData>>::deserialize(deserializer)?;
+        let len = tiles.len();
+        let tiles: Box<ChunkTiles> = tiles
+            .into_boxed_slice()
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"as many tiles as there are in a chunk"))?;
+        Ok(tiles.into())
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    BoundingBox, LayerTile, LayerTileData, Orientation, TileId, TileLayer, TileRect, TileTransform,
+    UvRect,
+};
+
+/// The geometry of the tiles of a [`TileLayer`] drawn from the same texture, as returned by
+/// [`TileLayer::meshes()`], ready to be uploaded to vertex and index buffers.
+///
+/// Each tile is a quad of four vertices, listed clockwise from the top-left corner of the
+/// rectangle the tile is drawn in, and made of two triangles. Tiles are listed in the order they
+/// should be drawn in, row by row. The quads of tiles rotated on hexagonal maps are rotated along,
+/// starting from the corner the top-left one was rotated to.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TileMesh {
+    /// The index of the tileset the tiles are from, within the map.
+    pub tileset_index: usize,
+    /// The ID of the tile whose image is the texture of the mesh, for image collection tilesets,
+    /// whose tiles each have their own image. [`None`] if the texture is the tileset's image.
+    pub tile_id: Option<TileId>,
+    /// The position of each vertex, in map pixels.
+    pub positions: Vec<[f32; 2]>,
+    /// The texture coordinates of each vertex, normalized between 0 and 1, with the origin at the
+    /// top left of the texture.
+    pub uvs: Vec<[f32; 2]>,
+    /// The indices of the vertices of each triangle, three per triangle.
+    pub indices: Vec<u32>,
+}
+
+impl TileMesh {
+    /// Appends the quad of a tile drawn in the rectangle given, whose image covers the texture
+    /// coordinates given before being flipped. `hex_rotation` is the rotation of the tile around
+    /// its center on hexagonal maps, in degrees, which its diagonal flip stands for there.
+    fn push_quad(
+        &mut self,
+        rect: BoundingBox,
+        uv: UvRect,
+        tile: &LayerTileData,
+        hex_rotation: Option<f32>,
+    ) {
+        // Rotations by multiples of 60 degrees can't be made by swapping texture coordinates, so
+        // the quad itself is rotated.
+        let rotate = hex_rotation.map(|rotation| {
+            TileTransform {
+                flip_x: false,
+                flip_y: false,
+                rotation,
+            }
+            .to_affine(rect.width, rect.height)
+        });
+        let first = self.positions.len() as u32;
+        for &(x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].iter() {
+            let (dx, dy) = match &rotate {
+                Some(rotate) => rotate.apply(x * rect.width, y * rect.height),
+                None => (x * rect.width, y * rect.height),
+            };
+            self.positions.push([rect.x + dx, rect.y + dy]);
+
+            // Undo the flips of the tile to find the point of its image drawn at this corner. As
+            // in Tiled, the diagonal flip is applied first, so it is undone last.
+            let (mut u, mut v) = (x, y);
+            if tile.flip_v {
+                v = 1.0 - v;
+            }
+            if tile.flip_h {
+                u = 1.0 - u;
+            }
+            if tile.flip_d && hex_rotation.is_none() {
+                std::mem::swap(&mut u, &mut v);
+            }
+            self.uvs.push([
+                uv.u_min + u * (uv.u_max - uv.u_min),
+                uv.v_min + v * (uv.v_max - uv.v_min),
+            ]);
+        }
+        self.indices
+            .extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+}
+
+impl<'map> TileLayer<'map> {
+    /// Converts the tiles of the layer into one [`TileMesh`] per texture they are drawn from,
+    /// ordered by tileset index, so that the layer can be drawn in as many draw calls.
+    ///
+    /// Tiles are placed in the cell given by [`Map::tile_to_world()`](crate::Map::tile_to_world)
+    /// for the orientation of the map, then in the rectangle given by
+    /// [`Tileset::tile_draw_rect()`](crate::Tileset::tile_draw_rect), which includes the offset of
+    /// their tileset. The texture coordinates of each vertex follow the flip flags of its tile,
+    /// which also covers rotated tiles. On hexagonal maps, where the diagonal flip and
+    /// [`rotate_hex_120`](crate::LayerTileData::rotate_hex_120) rotate tiles by 60 and 120 degrees,
+    /// the quads of rotated tiles are rotated around their center instead. Layer offsets and parallax aren't applied; See
+    /// [`Layer::to_affine()`](crate::Layer::to_affine).
+    ///
+    /// Tiles outside of their tileset are skipped, as well as tiles of tilesets whose image has no
+    /// size.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// for mesh in layer.meshes() {
+    ///     let texture = &map.tilesets()[mesh.tileset_index].image;
+    ///     println!(
+    ///         "{} triangles textured with {:?}",
+    ///         mesh.indices.len() / 3,
+    ///         texture.as_ref().map(|image| &image.source)
+    ///     );
+    /// }
+    /// # }
+    /// ```
+    pub fn meshes(&self) -> Vec<TileMesh> {
+        self.meshes_in(self.bounds())
+    }
+
+    /// Same as [`TileLayer::meshes()`], but only includes the tiles within the viewport given, in
+    /// tile coordinates, so that large layers can be drawn a window at a time.
+    pub fn meshes_in(&self, viewport: TileRect) -> Vec<TileMesh> {
+        let map = self.map();
+        let mut meshes = BTreeMap::new();
+        for (x, y, tile) in self.tiles_within(viewport) {
+            let (cell_x, cell_y) = map.tile_to_world(x, y);
+            let cell = BoundingBox::new(
+                cell_x,
+                cell_y,
+                map.tile_width as f32,
+                map.tile_height as f32,
+            );
+            let tileset = tile.get_tileset();
+            let uv = match &tileset.image {
+                Some(image) => tileset.tile_uv(tile.id(), image.width as u32, image.height as u32),
+                None => Some(UvRect {
+                    u_min: 0.0,
+                    v_min: 0.0,
+                    u_max: 1.0,
+                    v_max: 1.0,
+                }),
+            };
+            let Some((rect, uv)) = tileset.tile_draw_rect(tile.id(), cell).zip(uv) else {
+                continue;
+            };
+
+            let hex_rotation = (map.orientation == Orientation::Hexagonal)
+                .then(|| tile.transform().rotation)
+                .filter(|&rotation| rotation != 0.0);
+            let tileset_index = tile.tileset_index();
+            let tile_id = tileset.image.is_none().then_some(tile.id());
+            meshes
+                .entry((tileset_index, tile_id))
+                .or_insert_with(|| TileMesh {
+                    tileset_index,
+                    tile_id,
+                    ..TileMesh::default()
+                })
+                .push_quad(rect, uv, &tile, hex_rotation);
+        }
+        meshes.into_values().collect()
+    }
+
+    /// Returns the tiles of the layer within the viewport given, in tile coordinates, row by row.
+    pub(crate) fn tiles_within(&self, viewport: TileRect) -> Vec<(i32, i32, LayerTile<'map>)> {
+        let mut tiles: Vec<_> = self
+            .chunks()
+            .filter(|chunk| overlaps(&chunk.rect(), &viewport))
+            .flat_map(|chunk| chunk.tiles())
+            .filter(|&(x, y, _)| viewport.contains(x, y))
+            .collect();
+        tiles.sort_unstable_by_key(|&(x, y, _)| (y, x));
+        tiles
+    }
+}
+
+fn overlaps(a: &TileRect, b: &TileRect) -> bool {
+    a.x < b.x + b.width as i32
+        && b.x < a.x + a.width as i32
+        && a.y < b.y + b.height as i32
+        && b.y < a.y + a.height as i32
+}
@@ -9,15 +9,59 @@ use crate::{
     Error, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset,
 };
 
+mod collision;
+#[cfg(feature = "rayon")]
+mod deferred;
 mod finite;
 mod infinite;
+mod mesh;
 mod util;
 
+pub use collision::*;
+#[cfg(feature = "rayon")]
+pub(crate) use deferred::*;
 pub use finite::*;
 pub use infinite::*;
+pub use mesh::*;
+#[cfg(feature = "json")]
+pub(crate) use util::convert_to_tiles;
+pub(crate) use util::{
+    decompress, is_supported_compression, parse_base64, parse_data_line, unsupported_encoding,
+};
+
+/// A rectangle of tiles, in tile coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileRect {
+    /// The X coordinate of the leftmost column of tiles in the rectangle.
+    pub x: i32,
+    /// The Y coordinate of the topmost row of tiles in the rectangle.
+    pub y: i32,
+    /// The width of the rectangle, in tiles.
+    pub width: u32,
+    /// The height of the rectangle, in tiles.
+    pub height: u32,
+}
+
+impl TileRect {
+    /// Returns `true` if the rectangle doesn't contain any tile.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns `true` if the tile at the given position is inside of the rectangle.
+    #[inline]
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && ((x - self.x) as u32) < self.width
+            && ((y - self.y) as u32) < self.height
+    }
+}
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerTileData {
     /// The index of the tileset this tile's in, relative to the tile's map. Guaranteed to be a
     /// valid index of the map tileset container, but **isn't guaranteed to actually contain
@@ -31,6 +75,8 @@ pub struct LayerTileData {
     pub flip_v: bool,
     /// Whether this tile is flipped diagonally.
     pub flip_d: bool,
+    /// Whether this tile is rotated by 120 degrees, which only applies to hexagonal maps.
+    pub rotate_hex_120: bool,
 }
 
 impl LayerTileData {
@@ -51,20 +97,34 @@ impl LayerTileData {
         self.id
     }
 
+    /// Creates the data of a layer tile referencing the tile with the local ID given in the map
+    /// tileset with the index given.
+    ///
+    /// The tileset index is checked when the layer the tile is in is added to a map through
+    /// [`Map::add_layer()`].
+    pub fn new(tileset_index: usize, id: TileId, flip_h: bool, flip_v: bool, flip_d: bool) -> Self {
+        Self {
+            tileset_index,
+            id,
+            flip_h,
+            flip_v,
+            flip_d,
+            rotate_hex_120: false,
+        }
+    }
+
     const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
     const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
     const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
-    const ALL_FLIP_FLAGS: u32 = Self::FLIPPED_HORIZONTALLY_FLAG
+    pub(crate) const ROTATED_HEXAGONAL_120_FLAG: u32 = 0x10000000;
+    pub(crate) const ALL_FLIP_FLAGS: u32 = Self::FLIPPED_HORIZONTALLY_FLAG
         | Self::FLIPPED_VERTICALLY_FLAG
-        | Self::FLIPPED_DIAGONALLY_FLAG;
+        | Self::FLIPPED_DIAGONALLY_FLAG
+        | Self::ROTATED_HEXAGONAL_120_FLAG;
 
     /// Creates a new [`LayerTileData`] from a [`Gid`] plus its flipping bits.
     pub(crate) fn from_bits(bits: u32, tilesets: &[MapTilesetGid]) -> Option<Self> {
-        let flags = bits & Self::ALL_FLIP_FLAGS;
         let gid = Gid(bits & !Self::ALL_FLIP_FLAGS);
-        let flip_d = flags & Self::FLIPPED_DIAGONALLY_FLAG == Self::FLIPPED_DIAGONALLY_FLAG; // Swap x and y axis (anti-diagonally) [flips over y = -x line]
-        let flip_h = flags & Self::FLIPPED_HORIZONTALLY_FLAG == Self::FLIPPED_HORIZONTALLY_FLAG; // Flip tile over y axis
-        let flip_v = flags & Self::FLIPPED_VERTICALLY_FLAG == Self::FLIPPED_VERTICALLY_FLAG; // Flip tile over x axis
 
         if gid == Gid::EMPTY {
             None
@@ -72,13 +132,26 @@ impl LayerTileData {
             let (tileset_index, tileset) = crate::util::get_tileset_for_gid(tilesets, gid)?;
             let id = gid.0 - tileset.first_gid.0;
 
-            Some(Self {
-                tileset_index,
-                id,
-                flip_h,
-                flip_v,
-                flip_d,
-            })
+            Some(Self::with_flags(tileset_index, id, bits))
+        }
+    }
+
+    /// Creates the data of a layer tile flipped according to the flipping bits of `bits`, which
+    /// may also contain a global tile ID.
+    pub(crate) fn with_flags(tileset_index: usize, id: TileId, bits: u32) -> Self {
+        let flags = bits & Self::ALL_FLIP_FLAGS;
+        let flip_d = flags & Self::FLIPPED_DIAGONALLY_FLAG == Self::FLIPPED_DIAGONALLY_FLAG; // Swap x and y axis (anti-diagonally) [flips over y = -x line]
+        let flip_h = flags & Self::FLIPPED_HORIZONTALLY_FLAG == Self::FLIPPED_HORIZONTALLY_FLAG; // Flip tile over y axis
+        let flip_v = flags & Self::FLIPPED_VERTICALLY_FLAG == Self::FLIPPED_VERTICALLY_FLAG; // Flip tile over x axis
+        let rotate_hex_120 = flags & Self::ROTATED_HEXAGONAL_120_FLAG != 0;
+
+        Self {
+            tileset_index,
+            id,
+            flip_h,
+            flip_v,
+            flip_d,
+            rotate_hex_120,
         }
     }
 }
@@ -89,6 +162,7 @@ impl LayerTileData {
 /// dereference [`TileLayer`] into this structure, and even if we could, it wouldn't make much
 /// sense, since we can already deref from the finite/infinite tile layers themselves.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum TileLayerData {
     Finite(FiniteTileLayerData),
     Infinite(InfiniteTileLayerData),
@@ -99,6 +173,7 @@ impl TileLayerData {
         parser: &mut Parser<R>,
         attrs: Vec<Attribute<'_>>,
         infinite: bool,
+        layer_id: u32,
         tilesets: &[MapTilesetGid],
     ) -> Result<(Self, Properties)> {
         let (width, height) = get_attrs!(
@@ -109,14 +184,20 @@ impl TileLayerData {
             (width, height)
         );
         let mut result = Self::Finite(Default::default());
+        #[cfg(feature = "rayon")]
+        let mut has_finite_data = false;
         let mut properties = HashMap::new();
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "layer", {
             "data" => for attrs {
                 if infinite {
-                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, tilesets).await?);
+                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, layer_id, tilesets).await?);
                 } else {
                     result = Self::Finite(FiniteTileLayerData::new(parser, attrs, width, height, tilesets).await?);
+                    #[cfg(feature = "rayon")]
+                    {
+                        has_finite_data = true;
+                    }
                 }
                 Ok(())
             },
@@ -125,9 +206,113 @@ impl TileLayerData {
                 Ok(())
             },
         });
+        // Keeps the deferred tile data in step with the finite tile layers of the map
+        #[cfg(feature = "rayon")]
+        if let (Self::Finite(_), false, Some(deferred)) =
+            (&result, has_finite_data, &mut parser.deferred_tiles)
+        {
+            deferred.push(None);
+        }
 
         Ok((result, properties))
     }
+
+    pub(crate) fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
+        match self {
+            Self::Finite(finite) => finite.get_tile_data(x, y),
+            Self::Infinite(infinite) => infinite.get_tile_data(x, y),
+        }
+    }
+
+    /// Returns an iterator over every non-empty tile of the layer, in no particular order.
+    pub(crate) fn tile_data(&self) -> Box<dyn Iterator<Item = &LayerTileData> + '_> {
+        match self {
+            Self::Finite(finite) => Box::new(finite.tile_data()),
+            Self::Infinite(infinite) => Box::new(infinite.tile_data()),
+        }
+    }
+
+    /// Points every tile of the layer to the new index of its tileset, as given by `new_indices`.
+    /// Tiles whose tileset has no new index are left as is.
+    pub(crate) fn remap_tilesets(&mut self, new_indices: &[Option<usize>]) {
+        for tile in self.tile_data_mut() {
+            if let Some(index) = new_indices[tile.tileset_index] {
+                tile.tileset_index = index;
+            }
+        }
+    }
+
+    /// Iterates over the data of every tile of the layer, mutably.
+    pub(crate) fn tile_data_mut(&mut self) -> Box<dyn Iterator<Item = &mut LayerTileData> + '_> {
+        match self {
+            Self::Finite(finite) => Box::new(finite.tile_data_mut()),
+            Self::Infinite(infinite) => Box::new(infinite.tile_data_mut()),
+        }
+    }
+
+    /// Replaces the tile at the position given. Positions outside of finite layers are ignored.
+    pub(crate) fn set_tile_data(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) {
+        match self {
+            Self::Finite(finite) => finite.set_tile_data(x, y, tile),
+            Self::Infinite(infinite) => infinite.set_tile_data(x, y, tile),
+        }
+    }
+}
+
+/// A mutable handle to the tiles of a tile layer, obtained via
+/// [`Map::get_tile_layer_mut()`](crate::Map::get_tile_layer_mut).
+#[derive(Debug)]
+pub struct TileLayerMut<'map> {
+    data: &'map mut TileLayerData,
+    tileset_count: usize,
+}
+
+impl<'map> TileLayerMut<'map> {
+    pub(crate) fn new(data: &'map mut TileLayerData, tileset_count: usize) -> Self {
+        Self {
+            data,
+            tileset_count,
+        }
+    }
+
+    /// Obtains the tile data present at the position given, if there's a tile there.
+    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
+        self.data.get_tile_data(x, y)
+    }
+
+    /// Replaces the tile at the position given, or clears it if `tile` is [`None`]. Chunks of
+    /// infinite layers are created as needed.
+    ///
+    /// Returns [`Error::InvalidLayer`] without changing the layer if the tile references a
+    /// tileset that isn't in the map, or if the position is outside of a finite layer.
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) -> Result<()> {
+        if let Some(tile) = &tile {
+            if tile.tileset_index() >= self.tileset_count {
+                return Err(Error::InvalidLayer {
+                    description: format!(
+                        "tileset {} isn't in the map, which has {} tilesets",
+                        tile.tileset_index(),
+                        self.tileset_count
+                    ),
+                });
+            }
+        }
+        if let TileLayerData::Finite(finite) = &self.data {
+            if x < 0 || y < 0 || x >= finite.width() as i32 || y >= finite.height() as i32 {
+                return Err(Error::InvalidLayer {
+                    description: format!(
+                        "({}, {}) is outside of the {}x{} layer",
+                        x,
+                        y,
+                        finite.width(),
+                        finite.height()
+                    ),
+                });
+            }
+        }
+        self.data.set_tile_data(x, y, tile);
+        Ok(())
+    }
 }
 
 map_wrapper!(
@@ -176,6 +361,38 @@ impl<'map> TileLayer<'map> {
         }
     }
 
+    /// Returns the rectangle of tiles this layer spans, regardless of whether it is finite or not.
+    ///
+    /// For finite layers, this is `(0, 0)` to `(width, height)`. For infinite layers, this is the
+    /// extent of the chunks that contain tiles; See [`InfiniteTileLayerData::bounds()`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// let bounds = layer.bounds();
+    /// for y in bounds.y..bounds.y + bounds.height as i32 {
+    ///     for x in bounds.x..bounds.x + bounds.width as i32 {
+    ///         if let Some(tile) = layer.get_tile(x, y) {
+    ///             println!("{} at ({}, {})", tile.id(), x, y);
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn bounds(&self) -> TileRect {
+        match self {
+            TileLayer::Finite(finite) => finite.bounds(),
+            TileLayer::Infinite(infinite) => infinite.bounds(),
+        }
+    }
+
     /// The width of this layer, if finite, or `None` if infinite.
     ///
     /// ## Example
@@ -243,4 +460,148 @@ impl<'map> TileLayer<'map> {
             TileLayer::Infinite(_infinite) => None,
         }
     }
+
+    /// Returns an iterator over the blocks of this layer that contain at least one tile, in
+    /// row-major order, so that renderers can cull and stream them one at a time.
+    ///
+    /// Infinite layers yield their [chunks](InfiniteTileLayer::chunks). Finite layers are split
+    /// into blocks of [`ChunkData::WIDTH`] by [`ChunkData::HEIGHT`] tiles, the blocks at the right
+    /// and bottom edges being smaller when the layer size isn't a multiple of it. Use
+    /// [`TileLayer::bounds()`] to obtain the rectangle covered by every block at once.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// for chunk in layer.chunks() {
+    ///     let rect = chunk.rect();
+    ///     assert!(layer.bounds().contains(rect.x, rect.y));
+    ///     for (x, y, tile) in chunk.tiles() {
+    ///         assert_eq!(layer.get_tile(x, y).unwrap().id(), tile.id());
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn chunks(&self) -> impl Iterator<Item = LayerChunk<'map>> + 'map {
+        let mut chunks: Vec<LayerChunk<'map>> = match self {
+            TileLayer::Finite(finite) => {
+                let (map, data): (&'map Map, &'map FiniteTileLayerData) = (finite.map, finite.data);
+                let (width, height) = (data.width(), data.height());
+                (0..height)
+                    .step_by(ChunkData::HEIGHT as usize)
+                    .flat_map(|y| {
+                        (0..width)
+                            .step_by(ChunkData::WIDTH as usize)
+                            .map(move |x| (x, y))
+                    })
+                    .map(|(x, y)| LayerChunk {
+                        map,
+                        rect: TileRect {
+                            x: x as i32,
+                            y: y as i32,
+                            width: ChunkData::WIDTH.min(width - x),
+                            height: ChunkData::HEIGHT.min(height - y),
+                        },
+                        source: LayerChunkSource::Finite(data),
+                    })
+                    .filter(|chunk| !chunk.is_empty())
+                    .collect()
+            }
+            TileLayer::Infinite(infinite) => {
+                let (map, data): (&'map Map, &'map InfiniteTileLayerData) =
+                    (infinite.map, infinite.data);
+                data.chunk_data()
+                    .map(|((x, y), chunk)| LayerChunk {
+                        map,
+                        rect: TileRect {
+                            x: x * ChunkData::WIDTH as i32,
+                            y: y * ChunkData::HEIGHT as i32,
+                            width: ChunkData::WIDTH,
+                            height: ChunkData::HEIGHT,
+                        },
+                        source: LayerChunkSource::Infinite(chunk),
+                    })
+                    .filter(|chunk| !chunk.is_empty())
+                    .collect()
+            }
+        };
+        chunks.sort_unstable_by_key(|chunk| (chunk.rect.y, chunk.rect.x));
+        chunks.into_iter()
+    }
+}
+
+/// A rectangular block of tiles of a [`TileLayer`], as returned by [`TileLayer::chunks()`].
+#[derive(Debug, Clone, Copy)]
+pub struct LayerChunk<'map> {
+    map: &'map Map,
+    rect: TileRect,
+    source: LayerChunkSource<'map>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LayerChunkSource<'map> {
+    Finite(&'map FiniteTileLayerData),
+    Infinite(&'map ChunkData),
+}
+
+impl<'map> LayerChunk<'map> {
+    /// Get the map this chunk is from.
+    #[inline]
+    pub fn map(&self) -> &'map Map {
+        self.map
+    }
+
+    /// The origin and size of the chunk, in tile coordinates of the layer.
+    #[inline]
+    pub fn rect(&self) -> TileRect {
+        self.rect
+    }
+
+    /// Obtains the tile data present at the position given relative to the chunk's
+    /// top-left-most tile.
+    ///
+    /// If the position given is outside of the chunk or the position is empty, this function will
+    /// return [`None`].
+    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&'map LayerTileData> {
+        if !self.rect.contains(self.rect.x + x, self.rect.y + y) {
+            return None;
+        }
+        match self.source {
+            LayerChunkSource::Finite(data) => data.get_tile_data(self.rect.x + x, self.rect.y + y),
+            LayerChunkSource::Infinite(data) => data.get_tile_data(x, y),
+        }
+    }
+
+    /// Obtains the tile present at the position given relative to the chunk's top-left-most tile.
+    ///
+    /// If the position given is outside of the chunk or the position is empty, this function will
+    /// return [`None`].
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<LayerTile<'map>> {
+        self.get_tile_data(x, y)
+            .map(|data| LayerTile::new(self.map, data))
+    }
+
+    /// Returns an iterator over the tiles of the chunk along with their position in the layer, in
+    /// row-major order. Empty positions are skipped.
+    pub fn tiles(&self) -> impl Iterator<Item = (i32, i32, LayerTile<'map>)> + 'map {
+        let chunk = *self;
+        let rect = self.rect;
+        (0..rect.height as i32)
+            .flat_map(move |y| (0..rect.width as i32).map(move |x| (x, y)))
+            .filter_map(move |(x, y)| {
+                chunk
+                    .get_tile(x, y)
+                    .map(|tile| (rect.x + x, rect.y + y, tile))
+            })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tiles().next().is_none()
+    }
 }
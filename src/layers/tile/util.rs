@@ -5,6 +5,7 @@ use quick_xml::events::Event;
 
 use crate::{
     parse::xml::{Parser, Reader},
+    util::get_attrs,
     CsvDecodingError, Error, LayerTileData, MapTilesetGid, Result,
 };
 
@@ -14,47 +15,149 @@ pub(crate) async fn parse_data_line<R: Reader>(
     parser: &mut Parser<R>,
     tilesets: &[MapTilesetGid],
 ) -> Result<Vec<Option<LayerTileData>>> {
-    match (encoding, compression) {
-        (Some("csv"), None) => decode_csv(parser, tilesets).await,
+    if !is_decodable(encoding, compression) {
+        return Err(unsupported_encoding(encoding, compression));
+    }
+    let data = read_tile_data(parser, encoding, "data").await?;
+    decode_tile_data(encoding, compression, data.as_deref(), tilesets)
+}
 
-        (Some("base64"), None) => parse_base64(parser)
-            .await
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("zlib")) => parse_base64(parser)
-            .await
-            .and_then(|data| process_decoder(Ok(flate2::bufread::ZlibDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("gzip")) => parse_base64(parser)
-            .await
-            .and_then(|data| process_decoder(Ok(flate2::bufread::GzDecoder::new(&data[..]))))
+/// Whether tile data with the given encoding and compression format can be decoded.
+pub(crate) fn is_decodable(encoding: Option<&str>, compression: Option<&str>) -> bool {
+    match encoding {
+        // The legacy XML encoding, where each tile is a `<tile>` element.
+        None => compression.is_none(),
+        Some("csv") => compression.is_none(),
+        Some("base64") => is_supported_compression(compression),
+        _ => false,
+    }
+}
+
+/// Reads the tile data of a `<data>` or `<chunk>` element named `close_tag` without decoding it,
+/// or [`None`] if it is empty.
+///
+/// Tile data encoded as XML is made of `<tile>` elements rather than text, and is read into the
+/// same little-endian bytes as uncompressed base64 data.
+pub(crate) async fn read_tile_data<R: Reader>(
+    parser: &mut Parser<R>,
+    encoding: Option<&str>,
+    close_tag: &str,
+) -> Result<Option<Vec<u8>>> {
+    // Empty elements such as `<data/>` have no closing tag to read up to.
+    if parser.last_event_was_empty {
+        return Ok(None);
+    }
+    if encoding.is_some() {
+        return read_data_text(parser).await;
+    }
+    let mut data = Vec::new();
+    loop {
+        let next = parser.read_event().await.map_err(Error::XmlDecodingError)?;
+        match next {
+            Event::Start(tile) | Event::Empty(tile)
+                if tile.local_name().into_inner() == b"tile" =>
+            {
+                let attrs = tile
+                    .attributes()
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|err| Error::XmlDecodingError(err.into()))?;
+                // Empty tiles are written without a `gid`.
+                let gid = get_attrs!(
+                    for v in attrs {
+                        Some("gid") => gid ?= v.parse::<u32>(),
+                    }
+                    gid.unwrap_or(0)
+                );
+                data.extend_from_slice(&gid.to_le_bytes());
+            }
+            Event::End(end) if end.local_name().into_inner() == close_tag.as_bytes() => {
+                return Ok(Some(data));
+            }
+            Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
+            _ => {}
+        }
+    }
+}
+
+/// Reads the text of a `<data>` element without decoding it, or [`None`] if it is empty.
+async fn read_data_text<R: Reader>(parser: &mut Parser<R>) -> Result<Option<Vec<u8>>> {
+    loop {
+        let next = parser.read_event().await.map_err(Error::XmlDecodingError)?;
+        match next {
+            Event::Text(text) => return Ok(Some(text.into_inner().into_owned())),
+            Event::End(end) if end.local_name().into_inner() == b"data" => return Ok(None),
+            Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
+            _ => {}
+        }
+    }
+}
+
+/// Decodes the tile data of a `<data>` element, as read by [`read_tile_data`]. The encoding and
+/// compression format must be decodable, as checked by [`is_decodable`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "decode_tile_data", level = "debug", skip_all, fields(encoding = ?encoding, compression = ?compression))
+)]
+pub(crate) fn decode_tile_data(
+    encoding: Option<&str>,
+    compression: Option<&str>,
+    data: Option<&[u8]>,
+    tilesets: &[MapTilesetGid],
+) -> Result<Vec<Option<LayerTileData>>> {
+    let Some(data) = data else {
+        return Ok(Vec::new());
+    };
+    match encoding {
+        Some("csv") => decode_csv(data, tilesets),
+        None => Ok(convert_to_tiles(data, tilesets)),
+        _ => decode_base64(data)
+            .and_then(|data| decompress(data, compression))
             .map(|v| convert_to_tiles(&v, tilesets)),
+    }
+}
+
+/// Returns the error for tile data with an encoding or compression format that can't be decoded.
+pub(crate) fn unsupported_encoding(encoding: Option<&str>, compression: Option<&str>) -> Error {
+    #[cfg(not(feature = "zstd"))]
+    if encoding == Some("base64") && compression == Some("zstd") {
+        return Error::CompressionFeatureDisabled {
+            compression: "zstd".to_owned(),
+            feature: "zstd",
+        };
+    }
+    Error::InvalidEncodingFormat {
+        encoding: encoding.map(ToOwned::to_owned),
+        compression: compression.map(ToOwned::to_owned),
+    }
+}
+
+/// Whether base64 encoded tile data compressed with the given format can be decompressed.
+pub(crate) fn is_supported_compression(compression: Option<&str>) -> bool {
+    match compression {
+        None | Some("zlib") | Some("gzip") => true,
         #[cfg(feature = "zstd")]
-        (Some("base64"), Some("zstd")) => parse_base64(parser)
-            .await
-            .and_then(|data| process_decoder(zstd::stream::read::Decoder::with_buffer(&data[..])))
-            .map(|v| convert_to_tiles(&v, tilesets)),
+        Some("zstd") => true,
+        _ => false,
+    }
+}
 
-        _ => Err(Error::InvalidEncodingFormat {
-            encoding: encoding.map(ToOwned::to_owned),
-            compression: compression.map(ToOwned::to_owned),
-        }),
+/// Decompresses base64 decoded tile data. The compression format must be supported, as checked by
+/// [`is_supported_compression`].
+pub(crate) fn decompress(data: Vec<u8>, compression: Option<&str>) -> Result<Vec<u8>> {
+    match compression {
+        Some("zlib") => process_decoder(Ok(flate2::bufread::ZlibDecoder::new(&data[..]))),
+        Some("gzip") => process_decoder(Ok(flate2::bufread::GzDecoder::new(&data[..]))),
+        #[cfg(feature = "zstd")]
+        Some("zstd") => process_decoder(zstd::stream::read::Decoder::with_buffer(&data[..])),
+        _ => Ok(data),
     }
 }
 
-async fn parse_base64<R: Reader>(parser: &mut Parser<R>) -> Result<Vec<u8>> {
+pub(crate) async fn parse_base64<R: Reader>(parser: &mut Parser<R>) -> Result<Vec<u8>> {
     loop {
         let next = parser.read_event().await.map_err(Error::XmlDecodingError)?;
         match next {
-            Event::Text(mut text) => {
-                text.inplace_trim_start();
-                text.inplace_trim_end();
-                return base64::engine::GeneralPurpose::new(
-                    &base64::alphabet::STANDARD,
-                    base64::engine::general_purpose::PAD,
-                )
-                .decode(&*text)
-                .map_err(Error::Base64DecodingError);
-            }
+            Event::Text(text) => return decode_base64(&text),
             Event::End(end) if end.local_name().into_inner() == b"data" => return Ok(Vec::new()),
             Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
             _ => {}
@@ -62,6 +165,24 @@ async fn parse_base64<R: Reader>(parser: &mut Parser<R>) -> Result<Vec<u8>> {
     }
 }
 
+/// Decodes base64 data, ignoring the whitespace around it.
+fn decode_base64(data: &[u8]) -> Result<Vec<u8>> {
+    let start = data
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(data.len());
+    let end = data
+        .iter()
+        .rposition(|byte| !byte.is_ascii_whitespace())
+        .map_or(start, |last| last + 1);
+    base64::engine::GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        base64::engine::general_purpose::PAD,
+    )
+    .decode(&data[start..end])
+    .map_err(Error::Base64DecodingError)
+}
+
 fn process_decoder(decoder: std::io::Result<impl Read>) -> Result<Vec<u8>> {
     decoder
         .and_then(|mut decoder| {
@@ -72,37 +193,26 @@ fn process_decoder(decoder: std::io::Result<impl Read>) -> Result<Vec<u8>> {
         .map_err(Error::DecompressingError)
 }
 
-async fn decode_csv<R: Reader>(
-    parser: &mut Parser<R>,
-    tilesets: &[MapTilesetGid],
-) -> Result<Vec<Option<LayerTileData>>> {
-    loop {
-        let next = parser.read_event().await.map_err(Error::XmlDecodingError)?;
-        match next {
-            Event::Text(text) => {
-                let text = std::str::from_utf8(&text)
-                    .map_err(|err| Error::XmlDecodingError(err.into()))?;
-                let mut tiles = Vec::new();
-                for v in text.split(',') {
-                    match v.trim().parse() {
-                        Ok(bits) => tiles.push(LayerTileData::from_bits(bits, tilesets)),
-                        Err(e) => {
-                            return Err(Error::CsvDecodingError(
-                                CsvDecodingError::TileDataParseError(e),
-                            ))
-                        }
-                    }
-                }
-                return Ok(tiles);
+fn decode_csv(data: &[u8], tilesets: &[MapTilesetGid]) -> Result<Vec<Option<LayerTileData>>> {
+    let text = std::str::from_utf8(data).map_err(|err| Error::XmlDecodingError(err.into()))?;
+    let mut tiles = Vec::new();
+    for v in text.split(',') {
+        match v.trim().parse() {
+            Ok(bits) => tiles.push(LayerTileData::from_bits(bits, tilesets)),
+            Err(e) => {
+                return Err(Error::CsvDecodingError(
+                    CsvDecodingError::TileDataParseError(e),
+                ))
             }
-            Event::End(end) if end.local_name().into_inner() == b"data" => return Ok(Vec::new()),
-            Event::Eof => return Err(Error::PrematureEnd("Ran out of XML data".to_owned())),
-            _ => {}
         }
     }
+    Ok(tiles)
 }
 
-fn convert_to_tiles(data: &[u8], tilesets: &[MapTilesetGid]) -> Vec<Option<LayerTileData>> {
+pub(crate) fn convert_to_tiles(
+    data: &[u8],
+    tilesets: &[MapTilesetGid],
+) -> Vec<Option<LayerTileData>> {
     data.chunks_exact(4)
         .map(|chunk| {
             let bits = u32::from_le_bytes(chunk.try_into().unwrap());
@@ -6,33 +6,112 @@
 #![deny(missing_debug_implementations)]
 
 mod animation;
+#[cfg(feature = "image")]
+mod atlas;
+mod automap;
+#[cfg(feature = "bevy")]
+mod bevy;
 mod cache;
+mod changes;
+mod coords;
+#[cfg(feature = "image")]
+mod decode;
+mod dependencies;
+mod diagnostic;
+mod diff;
 mod error;
+mod export;
+mod flat;
+mod gids;
+mod graph;
 mod image;
+mod intern;
 mod layers;
 mod loader;
 mod map;
 mod objects;
+mod owned;
 mod parse;
+#[cfg(feature = "precompiled")]
+mod precompiled;
+mod progress;
+#[cfg(feature = "json")]
+mod project;
 mod properties;
+#[cfg(feature = "serde")]
+mod property_de;
+#[cfg(feature = "serde")]
+mod property_json;
+#[cfg(feature = "rand")]
+mod random;
+mod raw;
 mod reader;
 mod reader_async;
+#[cfg(feature = "fetch")]
+mod reader_fetch;
+#[cfg(feature = "zip")]
+mod reader_zip;
+mod spatial;
+mod stream;
 mod template;
 mod tile;
 mod tileset;
+mod transform;
+mod usage;
 mod util;
+mod validate;
+mod visitor;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "world")]
+mod world;
+mod write;
 
 pub use animation::*;
+#[cfg(feature = "image")]
+pub use atlas::*;
+pub use automap::*;
+#[cfg(feature = "bevy")]
+pub use bevy::*;
 pub use cache::*;
+pub use changes::*;
+#[cfg(feature = "image")]
+pub use decode::*;
+pub use dependencies::*;
+pub use diagnostic::*;
+pub use diff::*;
 pub use error::*;
+pub use export::*;
+pub use flat::*;
+pub use graph::*;
 pub use image::*;
 pub use layers::*;
 pub use loader::*;
 pub use map::*;
 pub use objects::*;
+pub use owned::*;
+pub use progress::*;
+#[cfg(feature = "json")]
+pub use project::*;
 pub use properties::*;
+pub use raw::*;
 pub use reader::*;
 pub use reader_async::*;
+#[cfg(feature = "fetch")]
+pub use reader_fetch::*;
+#[cfg(feature = "zip")]
+pub use reader_zip::*;
+pub use spatial::*;
+pub use stream::*;
 pub use template::*;
 pub use tile::*;
 pub use tileset::*;
+pub use transform::*;
+pub use usage::*;
+pub use validate::*;
+pub use visitor::*;
+#[cfg(feature = "watch")]
+pub use watch::*;
+#[cfg(feature = "world")]
+pub use world::*;
+pub use write::*;
@@ -1,12 +1,25 @@
-use std::path::Path;
+use std::{
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use futures::FutureExt;
+use tokio::io::AsyncRead;
 
 use crate::{
-    parse::xml::{AsyncReadFrom, SyncReadFrom},
-    AsyncResourceReader, DefaultResourceCache, FilesystemResourceReader, Map, ResourceCache,
-    ResourceReader, Result, Tileset,
+    dependencies::RecordingCache,
+    parse::xml::{AsyncReadFrom, InMemoryReadFrom, SyncReadFrom},
+    AsyncResourceReader, AutomappingRules, CancellationToken, ChunkPool, DefaultResourceCache,
+    Error, ExportOptions, FilesystemResourceReader, LoadProgress, Map, MapDependencies,
+    MapStreamVisitor, ParseWarning, ProgressHandler, ResourceCache, ResourcePathBuf,
+    ResourceReader, Result, SharedResourceCache, StreamedChunk, Template, Tileset, WarningHandler,
 };
+#[cfg(feature = "json")]
+use crate::{EnumType, Project};
+#[cfg(feature = "world")]
+use crate::{World, WorldMap};
 
 /// A type used for loading [`Map`]s and [`Tileset`]s.
 ///
@@ -24,8 +37,224 @@ use crate::{
 pub struct Loader<Reader = FilesystemResourceReader, Cache: ResourceCache = DefaultResourceCache> {
     cache: Cache,
     reader: Reader,
+    export_options: ExportOptions,
+    options: LoaderOptions,
+    warnings: WarningLog,
+    #[cfg(feature = "json")]
+    project: Option<Project>,
+}
+
+/// The warnings collected by a [`Loader`] since they were last taken. Clones start out empty, so
+/// that cloned loaders don't share their warnings.
+#[derive(Debug, Default)]
+struct WarningLog(Arc<Mutex<Vec<ParseWarning>>>);
+
+impl Clone for WarningLog {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+/// Options that change how a [`Loader`] parses files.
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, LoaderOptions};
+///
+/// # fn main() {
+/// let mut loader = Loader::new();
+/// loader.set_options(LoaderOptions {
+///     max_group_depth: 8,
+///     ..Default::default()
+/// });
+///
+/// let map = loader.load_tmx_map("assets/tiled_group_layers.tmx").unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoaderOptions {
+    /// The maximum amount of group layers that can be nested within each other. Maps that exceed
+    /// it fail to load with [`Error::GroupNestingTooDeep`](crate::Error::GroupNestingTooDeep),
+    /// which protects against stack overflows when parsing hostile input.
+    ///
+    /// Defaults to 32.
+    pub max_group_depth: usize,
+    /// Whether maps are loaded in lenient mode, where layers and tilesets that fail to load are
+    /// replaced by empty placeholders instead of making the whole map fail to load. The failures
+    /// are recorded in [`Map::diagnostics()`](crate::Map::diagnostics).
+    ///
+    /// Errors caused by malformed XML can't be recovered from, and still make the map fail to
+    /// load. Unless [`strict`](Self::strict) is also set, the elements and attributes that the
+    /// file format doesn't define are reported as
+    /// [warnings](crate::ParseWarningKind::UnknownElement). Defaults to `false`.
+    pub lenient: bool,
+    /// Whether files are parsed in strict mode, where elements and attributes that the
+    /// [TMX format](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/) doesn't
+    /// define make them fail to load with [`Error::UnknownElement`](crate::Error::UnknownElement)
+    /// or [`Error::UnknownAttribute`](crate::Error::UnknownAttribute), which is useful to check
+    /// files in CI. Otherwise, they are skipped, and reported as warnings in
+    /// [lenient mode](Self::lenient).
+    ///
+    /// Only applies to TMX, TSX and TX files. Defaults to `false`.
+    pub strict: bool,
+    /// A hook that rewrites the source path of every image when it is parsed, be it the image of
+    /// a tileset, of a tile in an image collection tileset or of an image layer. Defaults to
+    /// `None`, which keeps paths as they are in the files.
+    ///
+    /// Also see [`Loader::set_image_path_rewriter()`].
+    pub image_path_rewriter: Option<ImagePathRewriter>,
+    /// A hook that receives the chunks of infinite tile layers as they are parsed, and which can
+    /// also prevent them from being stored in the map. Defaults to `None`.
+    ///
+    /// Also see [`Loader::set_chunk_handler()`].
+    pub chunk_handler: Option<ChunkHandler>,
+    /// Whether finite tile layers are stored in their compact representation, which uses a
+    /// quarter of the memory at the cost of slower edits. See
+    /// [`FiniteTileLayerData::compact()`](crate::FiniteTileLayerData::compact)
+    /// for details. Defaults to `false`.
+    pub compact_tile_layers: bool,
+    /// A hook that receives the stages of loading TMX maps, e.g. to update a loading screen.
+    /// Defaults to `None`.
+    ///
+    /// Also see [`Loader::set_progress_handler()`].
+    pub progress_handler: Option<ProgressHandler>,
+    /// A token that cancels the loading of TMX maps once it is cancelled, which makes them fail
+    /// with [`Error::LoadingCancelled`](crate::Error::LoadingCancelled). Defaults to `None`.
+    pub cancellation_token: Option<CancellationToken>,
+    /// A hook that receives the warnings found while parsing TMX, TSX and TX files, which are
+    /// problems that don't prevent them from loading. Defaults to `None`.
+    ///
+    /// Loaders also collect the warnings of the files they load regardless of this handler, see
+    /// [`Loader::take_warnings()`].
+    pub warning_handler: Option<WarningHandler>,
+    /// The enum types that properties are resolved against while parsing: Properties whose type
+    /// is one of them become [`PropertyValue::Enum`](crate::PropertyValue::Enum)s. Defaults to
+    /// none, and is set to the enum types of the project given to [`Loader::set_project()`].
+    #[cfg(feature = "json")]
+    pub enum_types: Vec<EnumType>,
+    /// The pool through which identical chunks of infinite tile layers share memory. Defaults to
+    /// a new pool, so that the chunks of every map loaded with the same options are shared, while
+    /// `None` stores every chunk separately.
+    pub chunk_pool: Option<ChunkPool>,
+}
+
+impl Default for LoaderOptions {
+    fn default() -> Self {
+        Self {
+            max_group_depth: 32,
+            lenient: false,
+            strict: false,
+            image_path_rewriter: None,
+            chunk_handler: None,
+            compact_tile_layers: false,
+            progress_handler: None,
+            cancellation_token: None,
+            warning_handler: None,
+            #[cfg(feature = "json")]
+            enum_types: Vec::new(),
+            chunk_pool: Some(ChunkPool::new()),
+        }
+    }
+}
+
+impl LoaderOptions {
+    /// Returns the enum type with the name given among [`LoaderOptions::enum_types`].
+    #[cfg(feature = "json")]
+    pub(crate) fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        self.enum_types.iter().find(|e| e.name == name)
+    }
+}
+
+/// A function that rewrites image source paths, set through
+/// [`LoaderOptions::image_path_rewriter`].
+///
+/// It receives the path of the image as it would be stored in [`Image::source`](crate::Image::source),
+/// i.e. relative to the working directory, and returns the path to store instead.
+///
+/// Two rewriters are only equal if they are clones of each other.
+#[derive(Clone)]
+pub struct ImagePathRewriter(Arc<dyn Fn(&Path) -> PathBuf + Send + Sync>);
+
+impl ImagePathRewriter {
+    /// Creates a rewriter from the function given.
+    pub fn new(rewrite: impl Fn(&Path) -> PathBuf + Send + Sync + 'static) -> Self {
+        Self(Arc::new(rewrite))
+    }
+
+    /// Rewrites the path given.
+    pub fn rewrite(&self, path: &Path) -> PathBuf {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for ImagePathRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ImagePathRewriter").finish()
+    }
+}
+
+impl PartialEq for ImagePathRewriter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ImagePathRewriter {}
+
+/// A function that receives the chunks of infinite tile layers as they are parsed, set through
+/// [`LoaderOptions::chunk_handler`].
+///
+/// Chunks are delivered in file order, as they are found in the file, which allows building
+/// worlds while streaming them in. If chunks aren't retained, infinite tile layers are left empty
+/// once loaded, which bounds the memory used by huge maps.
+///
+/// Two handlers are only equal if they are clones of each other.
+#[derive(Clone)]
+pub struct ChunkHandler {
+    handle: Arc<dyn Fn(StreamedChunk<'_>) + Send + Sync>,
+    retain_chunks: bool,
+}
+
+impl ChunkHandler {
+    /// Creates a handler from the function given. If `retain_chunks` is false, the chunks aren't
+    /// stored in the infinite tile layers they belong to.
+    pub fn new(
+        retain_chunks: bool,
+        handle: impl Fn(StreamedChunk<'_>) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            handle: Arc::new(handle),
+            retain_chunks,
+        }
+    }
+
+    /// Whether chunks are still stored in the infinite tile layers they belong to.
+    pub fn retains_chunks(&self) -> bool {
+        self.retain_chunks
+    }
+
+    /// Passes a chunk to the handler.
+    pub fn handle(&self, chunk: StreamedChunk<'_>) {
+        (self.handle)(chunk)
+    }
+}
+
+impl fmt::Debug for ChunkHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkHandler")
+            .field("retain_chunks", &self.retain_chunks)
+            .finish_non_exhaustive()
+    }
 }
 
+impl PartialEq for ChunkHandler {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.handle, &other.handle) && self.retain_chunks == other.retain_chunks
+    }
+}
+
+impl Eq for ChunkHandler {}
+
 impl Loader {
     /// Creates a new loader, creating a default resource cache and reader
     /// ([`DefaultResourceCache`] & [`FilesystemResourceReader`] respectively) in the process.
@@ -33,10 +262,24 @@ impl Loader {
         Self {
             cache: DefaultResourceCache::new(),
             reader: FilesystemResourceReader::new(),
+            export_options: ExportOptions::default(),
+            options: LoaderOptions::default(),
+            warnings: WarningLog::default(),
+            #[cfg(feature = "json")]
+            project: None,
         }
     }
 }
 
+impl Loader<FilesystemResourceReader, SharedResourceCache> {
+    /// Creates a new loader whose clones share its resource cache, to load maps from several
+    /// threads without parsing the tilesets and templates they have in common more than once.
+    /// See [`SharedResourceCache`].
+    pub fn new_shared() -> Self {
+        Self::with_cache_and_reader(SharedResourceCache::new(), FilesystemResourceReader::new())
+    }
+}
+
 impl<Reader> Loader<Reader, DefaultResourceCache> {
     /// Creates a new loader using a specific reader and the default resource cache ([`DefaultResourceCache`]).
     /// Shorthand for `Loader::with_cache_and_reader(DefaultResourceCache::new(), reader)`.
@@ -68,8 +311,8 @@ impl<Reader> Loader<Reader, DefaultResourceCache> {
     /// let map = loader.load_tmx_map("/my-map.tmx")?;
     ///
     /// assert_eq!(
-    ///     map.tilesets()[0].image.as_ref().unwrap().source,
-    ///     Path::new("/tilesheet.png")
+    ///     map.tilesets()[0].image.as_ref().unwrap().source.as_path(),
+    ///     Some(Path::new("/tilesheet.png"))
     /// );
     ///
     /// # Ok(())
@@ -79,6 +322,11 @@ impl<Reader> Loader<Reader, DefaultResourceCache> {
         Self {
             cache: DefaultResourceCache::new(),
             reader,
+            export_options: ExportOptions::default(),
+            options: LoaderOptions::default(),
+            warnings: WarningLog::default(),
+            #[cfg(feature = "json")]
+            project: None,
         }
     }
 }
@@ -148,15 +396,23 @@ impl<Reader, Cache: ResourceCache> Loader<Reader, Cache> {
     /// let map = loader.load_tmx_map("/my-map.tmx")?;
     ///
     /// assert_eq!(
-    ///     map.tilesets()[0].image.as_ref().unwrap().source,
-    ///     Path::new("/tilesheet.png")
+    ///     map.tilesets()[0].image.as_ref().unwrap().source.as_path(),
+    ///     Some(Path::new("/tilesheet.png"))
     /// );
     ///
     /// # Ok(())
     /// # }
     /// ```
     pub fn with_cache_and_reader(cache: Cache, reader: Reader) -> Self {
-        Self { cache, reader }
+        Self {
+            cache,
+            reader,
+            export_options: ExportOptions::default(),
+            options: LoaderOptions::default(),
+            warnings: WarningLog::default(),
+            #[cfg(feature = "json")]
+            project: None,
+        }
     }
 
     /// Returns a reference to the loader's internal [`ResourceCache`].
@@ -179,6 +435,249 @@ impl<Reader, Cache: ResourceCache> Loader<Reader, Cache> {
         &mut self.reader
     }
 
+    /// Returns the [`LoaderOptions`] used when parsing files.
+    pub fn options(&self) -> &LoaderOptions {
+        &self.options
+    }
+
+    /// Sets the [`LoaderOptions`] used when parsing files.
+    pub fn set_options(&mut self, options: LoaderOptions) {
+        self.options = options;
+    }
+
+    /// Returns the [warnings](ParseWarning) found while parsing the files loaded since the last
+    /// call, and clears them. Warnings are problems that don't prevent files from loading, such
+    /// as deprecated attributes.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, ParseWarningKind};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// loader.load_tmx_map("assets/tiled_warnings.tmx").unwrap();
+    ///
+    /// let warnings = loader.take_warnings();
+    /// assert!(warnings.iter().any(|warning| matches!(
+    ///     &warning.kind,
+    ///     ParseWarningKind::DeprecatedAttribute { name, .. } if name == "class"
+    /// )));
+    /// assert!(loader.take_warnings().is_empty());
+    /// # }
+    /// ```
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut *self.warnings.0.lock().unwrap())
+    }
+
+    /// Returns the options files are parsed with, which are the loader's [`LoaderOptions`] with a
+    /// warning handler that also collects warnings for [`Loader::take_warnings()`].
+    fn parse_options(&self) -> LoaderOptions {
+        let mut options = self.options.clone();
+        let warnings = self.warnings.0.clone();
+        let handler = options.warning_handler.take();
+        options.warning_handler = Some(WarningHandler::new(move |warning| {
+            if let Some(handler) = &handler {
+                handler.handle(warning);
+            }
+            warnings.lock().unwrap().push(warning.clone());
+        }));
+        options
+    }
+
+    /// Sets the function used to rewrite the source path of every image when it is parsed. This
+    /// is a shorthand for setting [`LoaderOptions::image_path_rewriter`].
+    ///
+    /// Note that tilesets already present in the loader's cache aren't parsed again, and
+    /// therefore keep the paths they were loaded with.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// loader.set_image_path_rewriter(|path| path.with_extension("ktx2"));
+    ///
+    /// let map = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    /// assert_eq!(
+    ///     map.tilesets()[0].image.as_ref().unwrap().source.as_path(),
+    ///     Some(Path::new("assets/tilesheet.ktx2"))
+    /// );
+    /// # }
+    /// ```
+    pub fn set_image_path_rewriter(
+        &mut self,
+        rewrite: impl Fn(&Path) -> PathBuf + Send + Sync + 'static,
+    ) {
+        self.options.image_path_rewriter = Some(ImagePathRewriter::new(rewrite));
+    }
+
+    /// Sets the function that receives the chunks of infinite tile layers as they are parsed. This
+    /// is a shorthand for setting [`LoaderOptions::chunk_handler`].
+    ///
+    /// If `retain_chunks` is false, the chunks aren't stored in the loaded map, so that huge
+    /// infinite maps can be streamed with bounded memory.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::mpsc;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut loader = Loader::new();
+    /// loader.set_chunk_handler(false, move |chunk| {
+    ///     let tiles = chunk.tiles.iter().filter(|tile| tile.is_some()).count();
+    ///     sender.send((chunk.layer_id, chunk.x, chunk.y, tiles)).unwrap();
+    /// });
+    ///
+    /// let map = loader
+    ///     .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+    ///     .unwrap();
+    /// drop(loader);
+    ///
+    /// for (layer, x, y, tiles) in receiver {
+    ///     println!("Layer {}: {} tiles in chunk at ({}, {})", layer, tiles, x, y);
+    /// }
+    /// # }
+    /// ```
+    pub fn set_chunk_handler(
+        &mut self,
+        retain_chunks: bool,
+        handle: impl Fn(StreamedChunk<'_>) + Send + Sync + 'static,
+    ) {
+        self.options.chunk_handler = Some(ChunkHandler::new(retain_chunks, handle));
+    }
+
+    /// Sets the function that receives the stages of loading TMX maps, such as every tileset and
+    /// layer being loaded. This is a shorthand for setting [`LoaderOptions::progress_handler`].
+    ///
+    /// Along with [`LoaderOptions::cancellation_token`], this makes it possible to show the
+    /// progress of maps loaded through an [`AsyncResourceReader`] and to abort it.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::mpsc;
+    /// use tiled::{CancellationToken, Error, LoadProgress, Loader};
+    ///
+    /// # fn main() {
+    /// let (sender, receiver) = mpsc::channel();
+    /// let token = CancellationToken::new();
+    /// let mut loader = Loader::new();
+    /// loader.set_progress_handler(move |progress| {
+    ///     if let LoadProgress::TilesetLoaded { index, count, .. } = progress {
+    ///         sender.send((index + 1, count)).unwrap();
+    ///     }
+    /// });
+    ///
+    /// loader.load_tmx_map("assets/tiled_multiple_tilesets.tmx").unwrap();
+    /// assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [(1, 3), (2, 3), (3, 3)]);
+    ///
+    /// let mut options = loader.options().clone();
+    /// options.cancellation_token = Some(token.clone());
+    /// loader.set_options(options);
+    /// token.cancel();
+    /// assert!(matches!(
+    ///     loader.load_tmx_map("assets/tiled_multiple_tilesets.tmx"),
+    ///     Err(Error::LoadingCancelled)
+    /// ));
+    /// # }
+    /// ```
+    pub fn set_progress_handler(
+        &mut self,
+        handle: impl Fn(LoadProgress<'_>) + Send + Sync + 'static,
+    ) {
+        self.options.progress_handler = Some(ProgressHandler::new(handle));
+    }
+
+    /// Returns the [`ExportOptions`] applied to every map loaded by this loader.
+    pub fn export_options(&self) -> &ExportOptions {
+        &self.export_options
+    }
+
+    /// Sets the [`ExportOptions`] applied to every map loaded by this loader, so that the loaded
+    /// maps match what Tiled would export. By default, no resolutions are applied.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{ExportOptions, Loader};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// loader.set_export_options(ExportOptions::all());
+    ///
+    /// let map = loader.load_tmx_map("assets/tiled_object_template.tmx").unwrap();
+    /// # }
+    /// ```
+    pub fn set_export_options(&mut self, options: ExportOptions) {
+        self.export_options = options;
+    }
+
+    /// Returns the [`Project`] whose property types are resolved on every map and tileset loaded
+    /// by this loader, if there's one.
+    #[cfg(feature = "json")]
+    pub fn project(&self) -> Option<&Project> {
+        self.project.as_ref()
+    }
+
+    /// Sets the [`Project`] whose property types are resolved on every map and tileset loaded by
+    /// this loader, so that class properties contain the default values of the members that
+    /// weren't changed. See [`Project::resolve_properties()`] for more information.
+    ///
+    /// This also sets [`LoaderOptions::enum_types`] to the enum types of the project, so that
+    /// enum properties are loaded as [`PropertyValue::Enum`](crate::PropertyValue::Enum)s.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let project = loader
+    ///     .load_project("assets/tiled_class_property.tiled-project")
+    ///     .unwrap();
+    /// loader.set_project(Some(project));
+    ///
+    /// let map = loader.load_tmx_map("assets/tiled_nested_class_property.tmx").unwrap();
+    /// let Some(PropertyValue::ClassValue { properties, .. }) =
+    ///     map.properties.get("map class property")
+    /// else {
+    ///     panic!("expected a class property");
+    /// };
+    /// assert_eq!(properties["volume"], PropertyValue::FloatValue(0.8));
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn set_project(&mut self, project: Option<Project>) {
+        self.options.enum_types = project
+            .iter()
+            .flat_map(|project| project.enum_types())
+            .cloned()
+            .collect();
+        self.project = project;
+    }
+
+    /// Applies the loader's export options and project to a freshly loaded map.
+    fn finish_map(&self, map: &mut Map) {
+        map.apply_export_options(&self.export_options);
+        #[cfg(feature = "json")]
+        if let Some(project) = &self.project {
+            project.resolve_map(map);
+        }
+    }
+
+    /// Applies the loader's project to a freshly loaded tileset.
+    fn finish_tileset(&self, tileset: Tileset) -> Tileset {
+        #[cfg(feature = "json")]
+        if let Some(project) = &self.project {
+            let mut tileset = tileset;
+            project.resolve_tileset(&mut tileset);
+            return tileset;
+        }
+        tileset
+    }
+
     /// Consumes the loader and returns its internal [`ResourceCache`] and [`ResourceReader`].
     pub fn into_inner(self) -> (Cache, Reader) {
         (self.cache, self.reader)
@@ -191,16 +690,178 @@ impl<Reader: ResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     ///
     /// All intermediate objects such as map tilesets will be stored in the [internal loader cache].
     ///
+    /// Gzip-compressed files, such as `.tmx.gz` maps and the tilesets they reference, are
+    /// decompressed transparently.
+    ///
     /// [internal loader cache]: Loader::cache()
     pub fn load_tmx_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let options = self.parse_options();
         let mut read_from = SyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache)
+        let mut map = crate::parse::xml::parse_map(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &options,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TMX map stayed pending; this is a bug, please report it",
+        )?;
+        self.finish_map(&mut map);
+        Ok(map)
+    }
+
+    /// Parses a Tiled map from a reader instead of the file at a path, e.g. for maps embedded in
+    /// the executable or received over the network. The contents are read until the end before
+    /// parsing, and may be gzip-compressed.
+    ///
+    /// The map is parsed as if it was read from `virtual_path`: External files such as tilesets
+    /// and templates are still loaded through the loader's [`ResourceReader`], relative to it.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let tmx = include_bytes!("../assets/tiled_base64_external.tmx");
+    /// let map = Loader::new()
+    ///     .load_tmx_map_from(&tmx[..], "assets/tiled_base64_external.tmx")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(map.tilesets()[0].name, "tilesheet");
+    /// # }
+    /// ```
+    pub fn load_tmx_map_from(
+        &mut self,
+        mut reader: impl Read,
+        virtual_path: impl AsRef<Path>,
+    ) -> Result<Map> {
+        let options = self.parse_options();
+        let path = virtual_path.as_ref();
+        let mut contents = Vec::new();
+        reader
+            .read_to_end(&mut contents)
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err: Box::new(err),
+            })?;
+        let mut read_from = InMemoryReadFrom::new(path, contents, SyncReadFrom(&mut self.reader));
+        let mut map = crate::parse::xml::parse_map(path, &mut read_from, &mut self.cache, &options)
             .now_or_never()
             .expect(
                 "synchronously loading a TMX map stayed pending; this is a bug, please report it",
+            )?;
+        self.finish_map(&mut map);
+        Ok(map)
+    }
+
+    /// Loads a map like [`Loader::load_tmx_map()`] does, and also returns the external resources
+    /// it pulled in, be it from files or from the [internal loader cache].
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let (map, dependencies) = Loader::new()
+    ///     .load_tmx_map_with_dependencies("assets/tiled_object_template.tmx")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(dependencies.templates.len(), 1);
+    /// assert_eq!(dependencies.images, [Path::new("assets/tilesheet.png")]);
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn load_tmx_map_with_dependencies(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Map, MapDependencies)> {
+        let options = self.parse_options();
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        let mut cache = RecordingCache::new(&mut self.cache);
+        let mut map = crate::parse::xml::parse_map(
+            path.as_ref(),
+            &mut read_from,
+            &mut cache,
+            &options,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TMX map stayed pending; this is a bug, please report it",
+        )?;
+        let dependencies = cache.into_dependencies(&map);
+        self.finish_map(&mut map);
+        Ok((map, dependencies))
+    }
+
+    /// Lists the external resources a TMX map references, without loading it: The paths of its
+    /// tilesets, templates and images, along with the ones referenced by those tilesets and
+    /// templates.
+    ///
+    /// Only the attributes of the files are looked at, so this is much cheaper than loading the
+    /// map, and the [internal loader cache] is neither used nor populated. Paths are relative to
+    /// the working directory like the path given, listed in the order they are found and without
+    /// duplicates. Tilesets and templates in the JSON formats are listed, but not scanned.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::PathBuf;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let dependencies = Loader::new()
+    ///     .scan_tmx_dependencies("assets/tiled_object_template.tmx")
+    ///     .unwrap();
+    ///
+    /// assert!(dependencies.contains(&PathBuf::from("assets/tiled_object_template.tx")));
+    /// assert!(dependencies.contains(&PathBuf::from("assets/tilesheet.png")));
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn scan_tmx_dependencies(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<ResourcePathBuf>> {
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::xml::scan_dependencies(path.as_ref(), &mut read_from)
+            .now_or_never()
+            .expect(
+                "synchronously scanning a TMX map stayed pending; this is a bug, please report it",
             )
     }
 
+    /// Reads a TMX map piece by piece, handing its layers, tiles and objects to the visitor given
+    /// as they are found instead of storing them in a [`Map`]. See [`MapStreamVisitor`] for an
+    /// example.
+    ///
+    /// This is meant for huge maps of which only a part of the data is needed: Tiles are discarded
+    /// once they are visited, and the contents of layers the visitor skips aren't decoded at all.
+    /// Tilesets and templates are still loaded through the [internal loader cache], since they are
+    /// needed to make sense of tiles and objects. The [export options](Loader::set_export_options)
+    /// and [project](Loader::set_project) of the loader aren't applied to streamed objects.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn stream_tmx_map(
+        &mut self,
+        path: impl AsRef<Path>,
+        visitor: &mut impl MapStreamVisitor,
+    ) -> Result<()> {
+        let options = self.parse_options();
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map_with_visitor(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &options,
+            visitor,
+        )
+        .now_or_never()
+        .expect("synchronously streaming a TMX map stayed pending; this is a bug, please report it")
+    }
+
     /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
     /// will be loaded relative to the path given.
     ///
@@ -211,13 +872,382 @@ impl<Reader: ResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
     /// in this context it is not an intermediate object.
     pub fn load_tsx_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let options = self.parse_options();
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_tileset(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &options,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TSX tileset stayed pending; this is a bug, please report it",
+        )
+        .map(|tileset| self.finish_tileset(tileset))
+    }
+
+    /// Parses a file hopefully containing a Tiled map in the JSON format (TMJ) and tries to parse
+    /// it. All external files will be loaded relative to the path given.
+    ///
+    /// The map is loaded into the same structures as with [`Loader::load_tmx_map()`]. External
+    /// tilesets and templates may use either format: TSX and TX files are parsed as XML, while
+    /// any other file is parsed as JSON.
+    ///
+    /// ## Note
+    /// Unlike the XML format, the JSON format doesn't store the types of the members of class
+    /// properties, so they are inferred from their values instead.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let json_map = loader.load_tmj_map("assets/tiled_base64_external.tmj").unwrap();
+    /// let xml_map = loader.load_tmx_map("assets/tiled_base64_external.tmx").unwrap();
+    ///
+    /// assert_eq!(json_map.layers().len(), xml_map.layers().len());
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn load_tmj_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let options = self.parse_options();
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        let mut map = crate::parse::json::parse_map(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &options,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TMJ map stayed pending; this is a bug, please report it",
+        )?;
+        self.finish_map(&mut map);
+        Ok(map)
+    }
+
+    /// Parses a file hopefully containing a Tiled tileset in the JSON format (TSJ) and tries to
+    /// parse it. All external files will be loaded relative to the path given.
+    ///
+    /// See [`Loader::load_tsx_tileset()`] for more information.
+    #[cfg(feature = "json")]
+    pub fn load_tsj_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let options = self.parse_options();
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_tileset(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &options,
+        )
+        .now_or_never()
+        .expect(
+            "synchronously loading a TSJ tileset stayed pending; this is a bug, please report it",
+        )
+        .map(|tileset| self.finish_tileset(tileset))
+    }
+
+    /// Loads the template at the path given, or fetches it from the [internal loader cache] if it
+    /// was already loaded, e.g. by a map with objects based on it. Newly loaded templates are
+    /// stored in the cache, along with their tileset, so that maps loaded afterwards reuse them.
+    ///
+    /// TX files are parsed as XML. With the `json` feature, TJ and JSON files are parsed as JSON.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let template = loader
+    ///     .load_template("assets/tiled_object_template.tx")
+    ///     .unwrap();
+    ///
+    /// assert!(template.tileset.is_some());
+    /// // Loading it again fetches it from the cache
+    /// let cached = loader
+    ///     .load_template("assets/tiled_object_template.tx")
+    ///     .unwrap();
+    /// assert!(Arc::ptr_eq(&template, &cached));
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn load_template(&mut self, path: impl AsRef<Path>) -> Result<Arc<Template>> {
+        let options = self.parse_options();
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        Template::load_cached(path.as_ref(), &mut read_from, &mut self.cache, &options)
+            .now_or_never()
+            .expect(
+                "synchronously loading a template stayed pending; this is a bug, please report it",
+            )
+    }
+
+    /// Loads the tilesets at the paths given into the [internal loader cache], so that maps
+    /// loaded afterwards use them instead of reading and parsing their files, which makes the
+    /// time it takes to load maps predictable. Tilesets that are already cached aren't loaded
+    /// again.
+    ///
+    /// Returns the tilesets in the same order as the paths. TSX files are parsed as XML. With the
+    /// `json` feature, TSJ and JSON files are parsed as JSON. Unlike
+    /// [`Loader::load_tsx_tileset()`], the [project](Loader::set_project) of the loader isn't
+    /// applied to the tilesets, since it is applied to the maps using them.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let tilesets = loader
+    ///     .preload_tilesets(["assets/tilesheet.tsx", "assets/tilesheet_wangsets.tsx"].iter())
+    ///     .unwrap();
+    ///
+    /// let map = loader.load_tmx_map("assets/tiled_base64_external.tmx").unwrap();
+    /// assert!(Arc::ptr_eq(&map.tilesets()[0], &tilesets[0]));
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn preload_tilesets<P: AsRef<Path>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Vec<Arc<Tileset>>> {
+        let options = self.parse_options();
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        let mut tilesets = Vec::new();
+        for path in paths {
+            tilesets.push(
+                Tileset::load_cached(
+                    path.as_ref(),
+                    &mut read_from,
+                    &mut self.cache,
+                    &options,
+                )
+                .now_or_never()
+                .expect(
+                    "synchronously loading a tileset stayed pending; this is a bug, please report it",
+                )?,
+            );
+        }
+        Ok(tilesets)
+    }
+
+    /// Loads a Tiled world from a `.world` file. The maps of the world aren't loaded, which can
+    /// be done when needed through [`Loader::load_world_map()`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let world = loader.load_world("assets/world/tiled.world").unwrap();
+    ///
+    /// let origin = world.maps_at(0, 0).next().unwrap();
+    /// let map = loader.load_world_map(origin).unwrap();
+    /// assert_eq!(map.width * map.tile_width, origin.width);
+    /// # }
+    /// ```
+    #[cfg(feature = "world")]
+    pub fn load_world(&mut self, path: impl AsRef<Path>) -> Result<World> {
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_world(path.as_ref(), &mut read_from)
+            .now_or_never()
+            .expect("synchronously loading a world stayed pending; this is a bug, please report it")
+    }
+
+    /// Loads a map of a world. TMJ and JSON files are loaded like [`Loader::load_tmj_map()`]
+    /// does, and any other file like [`Loader::load_tmx_map()`] does.
+    #[cfg(feature = "world")]
+    pub fn load_world_map(&mut self, map: &WorldMap) -> Result<Map> {
+        if is_json_map(&map.source) {
+            self.load_tmj_map(&map.source)
+        } else {
+            self.load_tmx_map(&map.source)
+        }
+    }
+
+    /// Loads a Tiled project from a `.tiled-project` file, which declares the custom property
+    /// types used by maps. Set it through [`Loader::set_project()`] so that it is applied to
+    /// every loaded map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// # fn main() {
+    /// let project = Loader::new()
+    ///     .load_project("assets/tiled_class_property.tiled-project")
+    ///     .unwrap();
+    ///
+    /// let settings = project.class_type("map_settings").unwrap();
+    /// assert_eq!(settings.members["volume"], PropertyValue::FloatValue(0.8));
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn load_project(&mut self, path: impl AsRef<Path>) -> Result<Project> {
+        let mut read_from = SyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_project(path.as_ref(), &mut read_from)
+            .now_or_never()
+            .expect(
+                "synchronously loading a project stayed pending; this is a bug, please report it",
+            )
+    }
+
+    /// Loads a set of automapping rules from a file. All external files will be loaded relative
+    /// to the path given.
+    ///
+    /// The file can either be a `rules.txt` file listing rule map files (and other rule files)
+    /// line by line, or a single rule map. See [`RuleMap`](crate::RuleMap) for more information.
+    ///
+    /// Maps don't know their file name, so the map name filters of `rules.txt` files (`[pattern]`
+    /// lines) can't be honored: they make loading fail with
+    /// [`Error::InvalidAutomappingRules`](crate::Error::InvalidAutomappingRules), except for `[*]`,
+    /// which matches every map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let rules = loader
+    ///     .load_automapping_rules("assets/automap/rules.txt")
+    ///     .unwrap();
+    /// let mut map = loader.load_tmx_map("assets/automap/target.tmx").unwrap();
+    ///
+    /// rules.apply(&mut map);
+    /// # }
+    /// ```
+    pub fn load_automapping_rules(&mut self, path: impl AsRef<Path>) -> Result<AutomappingRules> {
+        let options = self.parse_options();
         let mut read_from = SyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_tileset(path.as_ref(), &mut read_from, &mut self.cache)
+        AutomappingRules::parse(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &options,
+        )
             .now_or_never()
             .expect(
-                "synchronously loading a TSX tileset stayed pending; this is a bug, please report it",
+                "synchronously loading automapping rules stayed pending; this is a bug, please report it",
             )
     }
+
+    /// Starts watching the files of a TMX map for changes, so that the map can be reloaded as
+    /// they are edited through [`Loader::reload_if_changed()`]. Available under the `watch`
+    /// feature.
+    ///
+    /// The files watched are the map and the files listed by
+    /// [`Loader::scan_tmx_dependencies()`]. The map itself isn't loaded.
+    #[cfg(feature = "watch")]
+    pub fn watch_tmx_map(&mut self, path: impl AsRef<Path>) -> Result<crate::MapWatcher> {
+        let path = path.as_ref();
+        let dependencies = self.scan_tmx_dependencies(path)?;
+        crate::MapWatcher::new(path, dependencies)
+    }
+
+    /// Loads the map the watcher given watches again if any of its files changed since the last
+    /// call, or since the watcher was created. Returns [`None`] if nothing changed. Available
+    /// under the `watch` feature.
+    ///
+    /// Only the resources that were edited are parsed again: Changed tilesets and templates are
+    /// removed from the [internal loader cache] before the map is reloaded, while the others are
+    /// reused from it. Templates are parsed again whenever a tileset changed, since they may hold
+    /// it. The files watched are updated to match the reloaded map, whose tilesets and templates
+    /// may have changed too.
+    ///
+    /// If the map fails to load, e.g. because a file was read while it was being saved, the error
+    /// is returned, and the map is loaded again once its files change again.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let mut map = loader.load_tmx_map("assets/level.tmx")?;
+    /// let mut watcher = loader.watch_tmx_map("assets/level.tmx")?;
+    ///
+    /// loop {
+    ///     // Called once per frame
+    ///     if let Some(reload) = loader.reload_if_changed(&mut watcher)? {
+    ///         map = reload.map;
+    ///     }
+    ///     # break;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    #[cfg(feature = "watch")]
+    pub fn reload_if_changed(
+        &mut self,
+        watcher: &mut crate::MapWatcher,
+    ) -> Result<Option<crate::MapReload>> {
+        let changed_files = watcher.changed_files()?;
+        if changed_files.is_empty() {
+            return Ok(None);
+        }
+        let mut tileset_changed = false;
+        for path in &changed_files {
+            tileset_changed |= self.cache.remove_tileset(path).is_some();
+            self.cache.remove_template(path);
+        }
+        if tileset_changed {
+            for path in watcher.files() {
+                self.cache.remove_template(path);
+            }
+        }
+
+        let path = watcher.path().to_owned();
+        let map = self.load_tmx_map(&path)?;
+        watcher.set_dependencies(self.scan_tmx_dependencies(&path)?)?;
+        Ok(Some(crate::MapReload { map, changed_files }))
+    }
+
+    /// Loads a map written by [`Map::to_bytes()`], which skips parsing XML and decompressing tile
+    /// data entirely. Available under the `precompiled` feature.
+    ///
+    /// The map is returned as it was serialized: Its tilesets aren't stored in the
+    /// [internal loader cache], and the loader's [`ExportOptions`] and project aren't applied
+    /// again.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// // Produced ahead of time with `Map::to_bytes()`, e.g. by a build script
+    /// let map = Loader::new().load_precompiled_map("assets/level.tmxb")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    #[cfg(feature = "precompiled")]
+    pub fn load_precompiled_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let path = path.as_ref();
+        let load_error =
+            |err: Box<dyn std::error::Error + Send + Sync>| crate::Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err,
+            };
+        let mut bytes = Vec::new();
+        let mut resource = self
+            .reader
+            .read_from(path)
+            .map_err(|err| load_error(Box::new(err)))?;
+        std::io::Read::read_to_end(&mut resource, &mut bytes)
+            .map_err(|err| load_error(Box::new(err)))?;
+        Map::from_bytes(&bytes)
+    }
 }
 
 impl<Reader: AsyncResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
@@ -228,8 +1258,87 @@ impl<Reader: AsyncResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     ///
     /// [internal loader cache]: Loader::cache()
     pub async fn load_tmx_map_async(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        let mut map =
+            crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache, &options)
+                .await?;
+        self.finish_map(&mut map);
+        Ok(map)
+    }
+
+    /// Parses a Tiled map from an asynchronous reader instead of the file at a path.
+    ///
+    /// See [`Loader::load_tmx_map_from()`] for more information.
+    pub async fn load_tmx_map_from_async(
+        &mut self,
+        mut reader: impl AsyncRead + Unpin,
+        virtual_path: impl AsRef<Path>,
+    ) -> Result<Map> {
+        let options = self.parse_options();
+        let path = virtual_path.as_ref();
+        let mut contents = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut contents)
+            .await
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err: Box::new(err),
+            })?;
+        let mut read_from = InMemoryReadFrom::new(path, contents, AsyncReadFrom(&mut self.reader));
+        let mut map =
+            crate::parse::xml::parse_map(path, &mut read_from, &mut self.cache, &options).await?;
+        self.finish_map(&mut map);
+        Ok(map)
+    }
+
+    /// Loads a map like [`Loader::load_tmx_map_async()`] does, and also returns the external
+    /// resources it pulled in.
+    ///
+    /// See [`Loader::load_tmx_map_with_dependencies()`] for more information.
+    pub async fn load_tmx_map_with_dependencies_async(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<(Map, MapDependencies)> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        let mut cache = RecordingCache::new(&mut self.cache);
+        let mut map =
+            crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut cache, &options)
+                .await?;
+        let dependencies = cache.into_dependencies(&map);
+        self.finish_map(&mut map);
+        Ok((map, dependencies))
+    }
+
+    /// Lists the external resources a TMX map references, without loading it.
+    ///
+    /// See [`Loader::scan_tmx_dependencies()`] for more information.
+    pub async fn scan_tmx_dependencies_async(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<ResourcePathBuf>> {
         let mut read_from = AsyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_map(path.as_ref(), &mut read_from, &mut self.cache).await
+        crate::parse::xml::scan_dependencies(path.as_ref(), &mut read_from).await
+    }
+
+    /// Reads a TMX map piece by piece, handing its contents to the visitor given.
+    ///
+    /// See [`Loader::stream_tmx_map()`] for more information.
+    pub async fn stream_tmx_map_async(
+        &mut self,
+        path: impl AsRef<Path>,
+        visitor: &mut impl MapStreamVisitor,
+    ) -> Result<()> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_map_with_visitor(
+            path.as_ref(),
+            &mut read_from,
+            &mut self.cache,
+            &options,
+            visitor,
+        )
+        .await
     }
 
     /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
@@ -242,7 +1351,144 @@ impl<Reader: AsyncResourceReader, Cache: ResourceCache> Loader<Reader, Cache> {
     /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
     /// in this context it is not an intermediate object.
     pub async fn load_tsx_tileset_async(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::xml::parse_tileset(path.as_ref(), &mut read_from, &mut self.cache, &options)
+            .await
+            .map(|tileset| self.finish_tileset(tileset))
+    }
+
+    /// Parses a file hopefully containing a Tiled map in the JSON format (TMJ) and tries to parse
+    /// it. All external files will be loaded relative to the path given.
+    ///
+    /// See [`Loader::load_tmj_map()`] for more information.
+    #[cfg(feature = "json")]
+    pub async fn load_tmj_map_async(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        let mut map =
+            crate::parse::json::parse_map(path.as_ref(), &mut read_from, &mut self.cache, &options)
+                .await?;
+        self.finish_map(&mut map);
+        Ok(map)
+    }
+
+    /// Parses a file hopefully containing a Tiled tileset in the JSON format (TSJ) and tries to
+    /// parse it. All external files will be loaded relative to the path given.
+    ///
+    /// See [`Loader::load_tsx_tileset()`] for more information.
+    #[cfg(feature = "json")]
+    pub async fn load_tsj_tileset_async(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_tileset(path.as_ref(), &mut read_from, &mut self.cache, &options)
+            .await
+            .map(|tileset| self.finish_tileset(tileset))
+    }
+
+    /// Loads a template, or fetches it from the [internal loader cache].
+    ///
+    /// See [`Loader::load_template()`] for more information.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub async fn load_template_async(&mut self, path: impl AsRef<Path>) -> Result<Arc<Template>> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        Template::load_cached(path.as_ref(), &mut read_from, &mut self.cache, &options).await
+    }
+
+    /// Loads tilesets into the [internal loader cache] ahead of the maps using them.
+    ///
+    /// See [`Loader::preload_tilesets()`] for more information.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub async fn preload_tilesets_async<P: AsRef<Path>>(
+        &mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<Vec<Arc<Tileset>>> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        let mut tilesets = Vec::new();
+        for path in paths {
+            tilesets.push(
+                Tileset::load_cached(path.as_ref(), &mut read_from, &mut self.cache, &options)
+                    .await?,
+            );
+        }
+        Ok(tilesets)
+    }
+
+    /// Loads a Tiled world from a `.world` file.
+    ///
+    /// See [`Loader::load_world()`] for more information.
+    #[cfg(feature = "world")]
+    pub async fn load_world_async(&mut self, path: impl AsRef<Path>) -> Result<World> {
         let mut read_from = AsyncReadFrom(&mut self.reader);
-        crate::parse::xml::parse_tileset(path.as_ref(), &mut read_from, &mut self.cache).await
+        crate::parse::json::parse_world(path.as_ref(), &mut read_from).await
     }
+
+    /// Loads a map of a world.
+    ///
+    /// See [`Loader::load_world_map()`] for more information.
+    #[cfg(feature = "world")]
+    pub async fn load_world_map_async(&mut self, map: &WorldMap) -> Result<Map> {
+        if is_json_map(&map.source) {
+            self.load_tmj_map_async(&map.source).await
+        } else {
+            self.load_tmx_map_async(&map.source).await
+        }
+    }
+
+    /// Loads a Tiled project from a `.tiled-project` file.
+    ///
+    /// See [`Loader::load_project()`] for more information.
+    #[cfg(feature = "json")]
+    pub async fn load_project_async(&mut self, path: impl AsRef<Path>) -> Result<Project> {
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        crate::parse::json::parse_project(path.as_ref(), &mut read_from).await
+    }
+
+    /// Loads a set of automapping rules from a file. All external files will be loaded relative
+    /// to the path given.
+    ///
+    /// See [`Loader::load_automapping_rules`] for more information.
+    pub async fn load_automapping_rules_async(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<AutomappingRules> {
+        let options = self.parse_options();
+        let mut read_from = AsyncReadFrom(&mut self.reader);
+        AutomappingRules::parse(path.as_ref(), &mut read_from, &mut self.cache, &options).await
+    }
+
+    /// Loads a map written by [`Map::to_bytes()`]. Available under the `precompiled` feature.
+    ///
+    /// See [`Loader::load_precompiled_map()`] for more information.
+    #[cfg(feature = "precompiled")]
+    pub async fn load_precompiled_map_async(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let path = path.as_ref();
+        let load_error =
+            |err: Box<dyn std::error::Error + Send + Sync>| crate::Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err,
+            };
+        let mut bytes = Vec::new();
+        let mut resource = self
+            .reader
+            .read_from(path)
+            .await
+            .map_err(|err| load_error(Box::new(err)))?;
+        tokio::io::AsyncReadExt::read_to_end(&mut resource, &mut bytes)
+            .await
+            .map_err(|err| load_error(Box::new(err)))?;
+        Map::from_bytes(&bytes)
+    }
+}
+
+/// Whether a map of a world is in the JSON format, judging by its extension.
+#[cfg(feature = "world")]
+fn is_json_map(path: &Path) -> bool {
+    let extension = crate::util::format_extension(path);
+    extension == Some(std::ffi::OsStr::new("tmj"))
+        || extension == Some(std::ffi::OsStr::new("json"))
 }
@@ -1,17 +1,28 @@
 //! Structures related to Tiled maps.
 
-use std::{collections::HashMap, fmt, path::Path, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use itertools::Itertools;
 
 use quick_xml::events::attributes::Attribute;
 
 use crate::{
     error::{Error, Result},
-    layers::{LayerData, LayerTag},
+    gids::GidTable,
+    layers::{LayerData, LayerDataType, LayerTag},
     parse::xml::{Parser, ReadFrom, Reader},
     properties::{parse_properties, Color, Properties},
     tileset::Tileset,
-    util::{get_attrs, parse_tag},
-    EmbeddedParseResultType, Layer, ResourceCache,
+    util::{debug_event, find_attr, get_attrs, parse_tag},
+    Diagnostic, DiagnosticKind, EmbeddedParseResultType, IdIndex, Layer, LayerAttributes,
+    LayerType, LoadProgress, Object, ObjectData, ObjectLayerData, ObjectLayerMut, ParseWarningKind,
+    ResourceCache, TileLayerData, TileLayerMut, TilesetLocation,
 };
 
 pub(crate) struct MapTilesetGid {
@@ -20,9 +31,15 @@ pub(crate) struct MapTilesetGid {
 }
 
 /// All Tiled map files will be parsed into this. Holds all the layers and tilesets.
+///
+/// With the `serde` feature, maps implement `Serialize` and `Deserialize`, so that loaded maps can
+/// be cached in any serde format instead of being parsed again. Tilesets shared between maps or
+/// templates are serialized along with each of their users, and aren't shared anymore once
+/// deserialized. Deserialized maps are trusted to be valid, like the ones built in code.
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Map {
-    version: String,
+    pub(crate) version: String,
     /// The way tiles are laid out in the map.
     pub orientation: Orientation,
     /// Width of the map, in tiles.
@@ -53,17 +70,33 @@ pub struct Map {
     pub stagger_axis: StaggerAxis,
     /// The stagger index of Hexagonal/Staggered map.
     pub stagger_index: StaggerIndex,
+    /// The length in pixels of the sides of the tiles of a Hexagonal map that are parallel to
+    /// the axis that isn't the [stagger axis](Self::stagger_axis). Always 0 for other maps.
+    pub hex_side_length: u32,
+    /// The X coordinate of the parallax origin, in pixels. Layers with a parallax factor are at
+    /// their unscrolled position when the camera is at the parallax origin.
+    pub parallax_origin_x: f32,
+    /// The Y coordinate of the parallax origin, in pixels.
+    pub parallax_origin_y: f32,
     /// The tilesets present on this map.
-    tilesets: Vec<Arc<Tileset>>,
+    pub(crate) tilesets: Vec<Arc<Tileset>>,
     /// The layers present in this map.
-    layers: Vec<LayerData>,
+    pub(crate) layers: Vec<LayerData>,
     /// The custom properties of this map.
     pub properties: Properties,
     /// The background color of this map, if any.
     pub background_color: Option<Color>,
-    infinite: bool,
+    /// The settings of the editor saved along with this map, if any.
+    pub editor_settings: Option<EditorSettings>,
+    pub(crate) infinite: bool,
     /// The type of the map, which is arbitrary and set by the user.
-    pub user_type: Option<String>,
+    pub user_type: Option<Arc<str>>,
+    pub(crate) next_object_id: u32,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) id_index: IdIndex,
+    #[cfg_attr(feature = "serde", serde(default, rename = "first_gids"))]
+    pub(crate) gid_table: GidTable,
 }
 
 impl Map {
@@ -79,15 +112,219 @@ impl Map {
     pub fn infinite(&self) -> bool {
         self.infinite
     }
+
+    /// The ID the next object added to this map will get. Equivalent to the map file's
+    /// `nextobjectid` attribute, or one more than the highest object ID if the file doesn't have
+    /// one.
+    pub fn next_object_id(&self) -> u32 {
+        self.next_object_id
+    }
 }
 
 impl Map {
+    /// Creates a map without any tileset or layer, e.g. to generate maps in code. Add tilesets
+    /// and layers to it through [`Map::add_tileset()`] and [`Map::add_layer()`].
+    ///
+    /// The map is finite with the size given in tiles unless `infinite` is true, in which case
+    /// the size is only a hint for editors. Its other attributes, which have default values, can be
+    /// changed through its fields.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tiled::{
+    ///     FiniteTileLayerData, LayerData, LayerTileData, Map, ObjectData, ObjectShape,
+    ///     Orientation, Tileset,
+    /// };
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Map::new(Orientation::Orthogonal, 8, 8, 32, 32, false);
+    /// let tileset = map.add_tileset(Arc::new(Tileset::new("terrain", 32, 32)));
+    ///
+    /// let mut tiles = FiniteTileLayerData::empty(8, 8);
+    /// tiles.set_tile_data(2, 3, Some(LayerTileData::new(tileset, 5, false, false, false)));
+    /// let ground = map.add_layer(LayerData::new_finite_tile_layer("ground", tiles))?;
+    ///
+    /// let spawn = ObjectData::from_shape(64.0, 96.0, ObjectShape::Point(64.0, 96.0));
+    /// let objects = map.add_layer(LayerData::new_object_layer("objects", vec![spawn]))?;
+    ///
+    /// assert_eq!((ground, objects), (1, 2));
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    /// assert_eq!(layer.get_tile(2, 3).unwrap().id(), 5);
+    /// assert_eq!(map.next_object_id(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        orientation: Orientation,
+        width: u32,
+        height: u32,
+        tile_width: u32,
+        tile_height: u32,
+        infinite: bool,
+    ) -> Self {
+        Self {
+            version: "1.10".to_owned(),
+            orientation,
+            width,
+            height,
+            tile_width,
+            tile_height,
+            stagger_axis: StaggerAxis::default(),
+            stagger_index: StaggerIndex::default(),
+            hex_side_length: 0,
+            parallax_origin_x: 0.0,
+            parallax_origin_y: 0.0,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            properties: Properties::new(),
+            background_color: None,
+            editor_settings: None,
+            infinite,
+            user_type: None,
+            next_object_id: 1,
+            diagnostics: Vec::new(),
+            id_index: IdIndex::default(),
+            gid_table: GidTable::default(),
+        }
+    }
+
+    /// Adds a tileset after the other tilesets of the map, and returns its index, to be used in
+    /// [`LayerTileData::new()`](crate::LayerTileData::new) and
+    /// [`TilesetLocation::Map`](crate::TilesetLocation::Map).
+    ///
+    /// Tiles refer to tilesets by index, so the first global tile ID of the new tileset doesn't
+    /// need to be chosen: It follows the ones of the tilesets before it, as given by
+    /// [`Map::first_gids()`], and is assigned when the map is written.
+    pub fn add_tileset(&mut self, tileset: Arc<Tileset>) -> usize {
+        self.tilesets.push(tileset);
+        self.gid_table.invalidate();
+        self.tilesets.len() - 1
+    }
+
+    /// Adds a layer on top of the other layers of the map, and returns its ID.
+    ///
+    /// The layer and the layers nested in it, if it is a group, are given fresh IDs unless they
+    /// already have one, and so are their objects. Returns [`Error::InvalidLayer`] without adding
+    /// the layer if it contains a tile layer that isn't of the same kind as the map (finite or
+    /// infinite) or tiles referencing a tileset that isn't in the map.
+    pub fn add_layer(&mut self, mut layer: LayerData) -> Result<u32> {
+        fn check(layer: &LayerData, infinite: bool, tileset_count: usize) -> Result<()> {
+            let invalid = |description: String| Err(Error::InvalidLayer { description });
+            match &layer.layer_type {
+                LayerDataType::Tiles(tiles) => {
+                    if matches!(tiles, TileLayerData::Infinite(_)) != infinite {
+                        return invalid(format!(
+                            "layer '{}' isn't {} like its map",
+                            layer.name,
+                            if infinite { "infinite" } else { "finite" }
+                        ));
+                    }
+                    if tiles
+                        .tile_data()
+                        .any(|tile| tile.tileset_index() >= tileset_count)
+                    {
+                        return invalid(format!(
+                            "layer '{}' has tiles from a tileset that isn't in the map",
+                            layer.name
+                        ));
+                    }
+                }
+                LayerDataType::Objects(objects) => {
+                    let outside_map = |object: &ObjectData| {
+                        matches!(
+                            object.tile_data().map(|tile| tile.tileset_location().clone()),
+                            Some(TilesetLocation::Map(index)) if index >= tileset_count
+                        )
+                    };
+                    if objects.objects.iter().any(outside_map) {
+                        return invalid(format!(
+                            "layer '{}' has tile objects from a tileset that isn't in the map",
+                            layer.name
+                        ));
+                    }
+                }
+                LayerDataType::Image(_) => {}
+                LayerDataType::Group(group) => {
+                    for layer in &group.layers {
+                        check(layer, infinite, tileset_count)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        fn highest_ids(layer: &LayerData, highest: &mut (u32, u32)) {
+            highest.0 = highest.0.max(layer.id);
+            match &layer.layer_type {
+                LayerDataType::Objects(objects) => {
+                    for object in &objects.objects {
+                        highest.1 = highest.1.max(object.id);
+                    }
+                }
+                LayerDataType::Group(group) => {
+                    for layer in &group.layers {
+                        highest_ids(layer, highest);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fn assign_ids(layer: &mut LayerData, next_layer_id: &mut u32, next_object_id: &mut u32) {
+            if layer.id == 0 {
+                layer.id = *next_layer_id;
+                *next_layer_id += 1;
+            }
+            match &mut layer.layer_type {
+                LayerDataType::Objects(objects) => {
+                    for object in objects
+                        .object_data_mut()
+                        .iter_mut()
+                        .filter(|object| object.id == 0)
+                    {
+                        object.id = *next_object_id;
+                        *next_object_id += 1;
+                    }
+                }
+                LayerDataType::Group(group) => {
+                    for layer in &mut group.layers {
+                        assign_ids(layer, next_layer_id, next_object_id);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        check(&layer, self.infinite, self.tilesets.len())?;
+
+        let mut highest = (0, 0);
+        for existing in self.layers.iter().chain(std::iter::once(&layer)) {
+            highest_ids(existing, &mut highest);
+        }
+        let mut next_layer_id = highest.0 + 1;
+        self.next_object_id = self.next_object_id.max(highest.1 + 1);
+        assign_ids(&mut layer, &mut next_layer_id, &mut self.next_object_id);
+
+        let id = layer.id;
+        self.layers.push(layer);
+        self.id_index.invalidate();
+        Ok(id)
+    }
+
     /// Get a reference to the map's tilesets.
     #[inline]
     pub fn tilesets(&self) -> &[Arc<Tileset>] {
         self.tilesets.as_ref()
     }
 
+    /// The problems that were recovered from while loading this map in
+    /// [lenient mode](crate::LoaderOptions::lenient). Always empty otherwise.
+    #[inline]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     /// Get an iterator over all the layers in the map in ascending order of their layer index.
     ///
     /// ## Example
@@ -119,10 +356,222 @@ impl Map {
         self.layers.iter().map(move |layer| Layer::new(self, layer))
     }
 
+    /// Get an iterator over the layers of the map that aren't group layers, including the ones
+    /// nested in group layers, in display order. Each one is paired with its attributes combined
+    /// with the ones of the group layers it is nested in, which is how Tiled displays it.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_group_attributes.tmx")
+    ///     .unwrap();
+    ///
+    /// for (layer, attributes) in map.layers_flattened() {
+    ///     if attributes.visible {
+    ///         println!("{} is drawn at {:?}", layer.name, (attributes.offset_x, attributes.offset_y));
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn layers_flattened(&self) -> impl Iterator<Item = (Layer<'_>, LayerAttributes)> {
+        fn flatten<'map>(
+            map: &'map Map,
+            layers: &'map [LayerData],
+            parent: LayerAttributes,
+            out: &mut Vec<(Layer<'map>, LayerAttributes)>,
+        ) {
+            for layer in layers {
+                let attributes = parent.combine(layer);
+                match &layer.layer_type {
+                    LayerDataType::Group(group) => flatten(map, &group.layers, attributes, out),
+                    _ => out.push((Layer::new(map, layer), attributes)),
+                }
+            }
+        }
+
+        let mut layers = Vec::new();
+        flatten(self, &self.layers, LayerAttributes::default(), &mut layers);
+        layers.into_iter()
+    }
+
     /// Returns the layer that has the specified index, if it exists.
     pub fn get_layer(&self, index: usize) -> Option<Layer> {
         self.layers.get(index).map(|data| Layer::new(self, data))
     }
+
+    /// Returns the first layer that has the specified name, searching group layers recursively
+    /// in display order, if it exists.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_group_layers.tmx")
+    ///     .unwrap();
+    ///
+    /// let layer = map.get_layer_by_name("tile-3").unwrap();
+    /// assert!(layer.as_tile_layer().is_some());
+    /// # }
+    /// ```
+    pub fn get_layer_by_name(&self, name: &str) -> Option<Layer<'_>> {
+        fn find<'map>(layers: &'map [LayerData], name: &str) -> Option<&'map LayerData> {
+            layers.iter().find_map(|layer| {
+                if &*layer.name == name {
+                    return Some(layer);
+                }
+                match &layer.layer_type {
+                    LayerDataType::Group(group) => find(group.layer_data(), name),
+                    _ => None,
+                }
+            })
+        }
+
+        find(&self.layers, name).map(|data| Layer::new(self, data))
+    }
+
+    /// Returns the layer at the specified path, which is made of the names of the group layers
+    /// leading to it followed by its own name, separated by slashes. If several layers of a group
+    /// have the same name, the first one is used.
+    ///
+    /// Layers whose names contain slashes can't be found this way; Use
+    /// [`Map::get_layer_by_name()`] or [`GroupLayer::layers()`](crate::GroupLayer::layers)
+    /// instead.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_group_layers.tmx")
+    ///     .unwrap();
+    ///
+    /// let layer = map.layer_at_path("group-2/group-3/tile-3").unwrap();
+    /// assert_eq!(&*layer.name, "tile-3");
+    /// assert!(map.layer_at_path("group-2/tile-3").is_none());
+    /// # }
+    /// ```
+    pub fn layer_at_path(&self, path: &str) -> Option<Layer<'_>> {
+        let mut found: Option<&LayerData> = None;
+        for name in path.split('/') {
+            let layers = match found {
+                None => &self.layers,
+                Some(LayerData {
+                    layer_type: LayerDataType::Group(group),
+                    ..
+                }) => group.layer_data(),
+                Some(_) => return None,
+            };
+            found = Some(layers.iter().find(|layer| &*layer.name == name)?);
+        }
+        found.map(|data| Layer::new(self, data))
+    }
+
+    /// Returns a mutable handle to the object layer that has the specified ID, which may be nested
+    /// inside of group layers, if it exists.
+    pub fn get_object_layer_mut(&mut self, id: u32) -> Option<ObjectLayerMut<'_>> {
+        fn find(layers: &mut [LayerData], id: u32) -> Option<&mut ObjectLayerData> {
+            layers.iter_mut().find_map(|layer| {
+                let layer_id = layer.id();
+                match &mut layer.layer_type {
+                    LayerDataType::Objects(objects) if layer_id == id => Some(objects),
+                    LayerDataType::Group(group) => find(group.layer_data_mut(), id),
+                    _ => None,
+                }
+            })
+        }
+
+        let data = find(&mut self.layers, id)?;
+        self.id_index.invalidate();
+        Some(ObjectLayerMut::new(data, &mut self.next_object_id))
+    }
+
+    /// Returns a mutable handle to the tile layer that has the specified ID, which may be nested
+    /// inside of group layers, if it exists.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{LayerTileData, Loader};
+    ///
+    /// # fn main() {
+    /// let mut map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64_zlib.tmx")
+    ///     .unwrap();
+    ///
+    /// let mut layer = map.get_tile_layer_mut(1).unwrap();
+    /// layer
+    ///     .set_tile(0, 0, Some(LayerTileData::new(0, 5, false, false, false)))
+    ///     .unwrap();
+    /// // Tiles can only come from the tilesets of the map
+    /// assert!(layer
+    ///     .set_tile(0, 0, Some(LayerTileData::new(1, 5, false, false, false)))
+    ///     .is_err());
+    ///
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    /// assert_eq!(layer.get_tile(0, 0).unwrap().id(), 5);
+    /// # }
+    /// ```
+    pub fn get_tile_layer_mut(&mut self, id: u32) -> Option<TileLayerMut<'_>> {
+        fn find(layers: &mut [LayerData], id: u32) -> Option<&mut TileLayerData> {
+            layers.iter_mut().find_map(|layer| {
+                let layer_id = layer.id();
+                match &mut layer.layer_type {
+                    LayerDataType::Tiles(tiles) if layer_id == id => Some(tiles),
+                    LayerDataType::Group(group) => find(group.layer_data_mut(), id),
+                    _ => None,
+                }
+            })
+        }
+
+        let data = find(&mut self.layers, id)?;
+        Some(TileLayerMut::new(data, self.tilesets.len()))
+    }
+
+    /// Get an iterator over every object in the map, each paired with the object layer that
+    /// contains it. Object layers inside of group layers are included, in display order.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_groups.tmx")
+    ///     .unwrap();
+    ///
+    /// for (layer, object) in map.objects() {
+    ///     println!("{} is in layer {}", object.name, layer.name);
+    /// }
+    /// # }
+    /// ```
+    pub fn objects(&self) -> impl Iterator<Item = (Layer<'_>, Object<'_>)> {
+        fn collect_object_layers<'map>(
+            layers: impl Iterator<Item = Layer<'map>>,
+            out: &mut Vec<Layer<'map>>,
+        ) {
+            for layer in layers {
+                match layer.layer_type() {
+                    LayerType::Objects(_) => out.push(layer),
+                    LayerType::Group(group) => collect_object_layers(group.layers(), out),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut object_layers = Vec::new();
+        collect_object_layers(self.layers(), &mut object_layers);
+        object_layers.into_iter().flat_map(|layer| {
+            layer
+                .as_object_layer()
+                .into_iter()
+                .flat_map(move |objects| objects.objects().map(move |object| (layer, object)))
+        })
+    }
 }
 
 impl Map {
@@ -133,17 +582,22 @@ impl Map {
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
     ) -> Result<Map> {
+        let next_object_id = find_attr(&attrs, "nextobjectid").and_then(|id| id.parse().ok());
         let (
-            (c, infinite, user_type, user_class, stagger_axis, stagger_index),
+            (c, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length),
+            (parallax_origin_x, parallax_origin_y),
             (v, o, w, h, tw, th),
         ) = get_attrs!(
             for v in attrs {
                 Some("backgroundcolor") => colour ?= v.parse(),
                 Some("infinite") => infinite = v == "1",
-                Some("type") => user_type ?= v.parse(),
-                Some("class") => user_class ?= v.parse(),
+                Some("type") => user_type = v,
+                Some("class") => user_class = v,
                 Some("staggeraxis") => stagger_axis ?= v.parse::<StaggerAxis>(),
                 Some("staggerindex") => stagger_index ?= v.parse::<StaggerIndex>(),
+                Some("hexsidelength") => hex_side_length ?= v.parse::<u32>(),
+                Some("parallaxoriginx") => parallax_origin_x ?= v.parse::<f32>(),
+                Some("parallaxoriginy") => parallax_origin_y ?= v.parse::<f32>(),
                 "version" => version = v,
                 "orientation" => orientation ?= v.parse::<Orientation>(),
                 "width" => width ?= v.parse::<u32>(),
@@ -151,45 +605,97 @@ impl Map {
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index), (version, orientation, width, height, tile_width, tile_height))
+            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length), (parallax_origin_x, parallax_origin_y), (version, orientation, width, height, tile_width, tile_height))
         );
 
         let infinite = infinite.unwrap_or(false);
-        let user_type = user_type.or(user_class);
+        if user_type.is_some() {
+            parser.warn(ParseWarningKind::DeprecatedAttribute {
+                element: "map".to_owned(),
+                name: "type".to_owned(),
+                replacement: "class".to_owned(),
+            });
+        }
+        let user_type = user_type.or(user_class).map(|t| parser.interner.intern(t));
         let stagger_axis = stagger_axis.unwrap_or_default();
         let stagger_index = stagger_index.unwrap_or_default();
+        let hex_side_length = match o {
+            Orientation::Hexagonal => hex_side_length.unwrap_or(0),
+            _ => 0,
+        };
+        parser.report(LoadProgress::MapStarted { path: map_path })?;
+        // Finite tile layers are decoded in parallel once all of them are parsed, except in
+        // lenient mode, where they are decoded as they are parsed so that failures are recovered
+        // from layer by layer.
+        #[cfg(feature = "rayon")]
+        if !parser.options.lenient {
+            parser.deferred_tiles = Some(Vec::new());
+        }
 
         // We can only parse sequentally, but tilesets are guaranteed to appear before layers.
         // So we can pass in tileset data to layer construction without worrying about unfinished
         // data usage.
         let mut layers = Vec::new();
         let mut properties = HashMap::new();
+        let mut editor_settings = None;
         let mut tilesets = Vec::new();
+        // External tilesets that weren't cached, which are loaded all at once before the first
+        // layer, so that async readers can read them concurrently. `None` once they are loaded.
+        let mut pending_tilesets = Some(Vec::new());
 
         let mut buffer = Vec::new();
         parse_tag!(parser => &mut buffer, "map", {
             "tileset" => for attrs {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path,  read_from, cache).await?;
-                match res.result_type {
-                    EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        let tileset = if let Some(ts) = cache.get_tileset(&tileset_path) {
-                            ts
-                        } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, read_from, cache).await?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
-                            tileset
+                let depth = parser.depth_outside_current_element();
+                match parse_map_tileset(parser, &attrs, map_path, read_from, cache).await {
+                    Ok(MapTileset::Loaded(tileset)) => tilesets.push(tileset),
+                    Ok(MapTileset::Pending { first_gid, tileset_path }) => {
+                        // Replaced once all the tilesets of the map are known
+                        pending_tilesets
+                            .get_or_insert_with(Vec::new)
+                            .push((tilesets.len(), tileset_path));
+                        tilesets.push(MapTilesetGid {
+                            first_gid,
+                            tileset: Arc::new(Tileset::new("", tw, th)),
+                        });
+                    }
+                    Err(err) if parser.options.lenient && err.is_recoverable() => {
+                        // The GID range of the tileset is needed to keep the following tilesets
+                        // valid, so the failure can't be recovered from without it.
+                        let Some(first_gid) =
+                            find_attr(&attrs, "firstgid").and_then(|gid| gid.parse().ok())
+                        else {
+                            return Err(err);
                         };
-
-                        tilesets.push(MapTilesetGid { first_gid: res.first_gid, tileset } );
+                        parser.skip_to_depth(depth).await?;
+                        parser.diagnostics.push(Diagnostic {
+                            path: map_path.to_owned(),
+                            kind: DiagnosticKind::ReplacedTileset { index: tilesets.len() },
+                            message: err.to_string(),
+                        });
+                        tilesets.push(MapTilesetGid {
+                            first_gid: Gid(first_gid),
+                            tileset: Arc::new(Tileset::new(
+                                find_attr(&attrs, "name").unwrap_or_default(),
+                                tw,
+                                th,
+                            )),
+                        });
                     }
-                    EmbeddedParseResultType::Embedded { tileset } => {
-                        tilesets.push(MapTilesetGid { first_gid: res.first_gid, tileset: Arc::new(tileset) });
-                    },
-                };
+                    Err(err) => return Err(err),
+                }
                 Ok(())
             },
             "layer" => for attrs {
-                layers.push(LayerData::new(
+                load_map_tilesets(
+                    parser,
+                    &mut pending_tilesets,
+                    &mut tilesets,
+                    map_path,
+                    read_from,
+                    cache,
+                ).await?;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Tiles,
@@ -199,11 +705,24 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                parser.report(LoadProgress::LayerLoaded {
+                    index: layers.len(),
+                    name: &layer.name,
+                })?;
+                layers.push(layer);
                 Ok(())
             },
             "imagelayer" => for attrs {
-                layers.push(LayerData::new(
+                load_map_tilesets(
+                    parser,
+                    &mut pending_tilesets,
+                    &mut tilesets,
+                    map_path,
+                    read_from,
+                    cache,
+                ).await?;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Image,
@@ -213,11 +732,24 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                parser.report(LoadProgress::LayerLoaded {
+                    index: layers.len(),
+                    name: &layer.name,
+                })?;
+                layers.push(layer);
                 Ok(())
             },
             "objectgroup" => for attrs {
-                layers.push(LayerData::new(
+                load_map_tilesets(
+                    parser,
+                    &mut pending_tilesets,
+                    &mut tilesets,
+                    map_path,
+                    read_from,
+                    cache,
+                ).await?;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Objects,
@@ -227,11 +759,24 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                parser.report(LoadProgress::LayerLoaded {
+                    index: layers.len(),
+                    name: &layer.name,
+                })?;
+                layers.push(layer);
                 Ok(())
             },
             "group" => for attrs {
-                layers.push(LayerData::new(
+                load_map_tilesets(
+                    parser,
+                    &mut pending_tilesets,
+                    &mut tilesets,
+                    map_path,
+                    read_from,
+                    cache,
+                ).await?;
+                let layer = LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Group,
@@ -241,19 +786,50 @@ impl Map {
                     None,
                     read_from,
                     cache
-                ).await?);
+                ).await?;
+                parser.report(LoadProgress::LayerLoaded {
+                    index: layers.len(),
+                    name: &layer.name,
+                })?;
+                layers.push(layer);
                 Ok(())
             },
             "properties" => {
                 properties = parse_properties(parser).await?;
                 Ok(())
             },
+            "editorsettings" => {
+                editor_settings = Some(EditorSettings::parse_xml(parser).await?);
+                Ok(())
+            },
         });
 
-        // We do not need first GIDs any more
+        load_map_tilesets(
+            parser,
+            &mut pending_tilesets,
+            &mut tilesets,
+            map_path,
+            read_from,
+            cache,
+        )
+        .await?;
+
+        #[cfg(feature = "rayon")]
+        if let Some(deferred) = parser.deferred_tiles.take() {
+            parser.check_cancelled()?;
+            crate::layers::decode_deferred_tiles(
+                &mut layers,
+                deferred,
+                &tilesets,
+                &parser.options,
+            )?;
+        }
+
+        // First GIDs are only kept to look up the GIDs of the file
+        let gid_table = GidTable::loaded(tilesets.iter().map(|ts| ts.first_gid.0).collect());
         let tilesets = tilesets.into_iter().map(|ts| ts.tileset).collect();
 
-        Ok(Map {
+        let mut map = Map {
             version: v.to_owned(),
             orientation: o,
             width: w,
@@ -262,19 +838,212 @@ impl Map {
             tile_height: th,
             stagger_axis,
             stagger_index,
+            hex_side_length,
+            parallax_origin_x: parallax_origin_x.unwrap_or(0.0),
+            parallax_origin_y: parallax_origin_y.unwrap_or(0.0),
             tilesets,
             layers,
             properties,
             background_color: c,
+            editor_settings,
             infinite,
             user_type,
-        })
+            next_object_id: 1,
+            diagnostics: std::mem::take(&mut parser.diagnostics),
+            id_index: IdIndex::default(),
+            gid_table,
+        };
+        map.next_object_id = next_object_id.unwrap_or_else(|| map.highest_object_id() + 1);
+        parser.report(LoadProgress::MapLoaded { path: map_path })?;
+        Ok(map)
+    }
+
+    pub(crate) fn highest_object_id(&self) -> u32 {
+        self.objects()
+            .map(|(_, object)| object.id())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A tileset of a map, whose file may still have to be loaded.
+pub(crate) enum MapTileset {
+    Loaded(MapTilesetGid),
+    Pending {
+        first_gid: Gid,
+        tileset_path: PathBuf,
+    },
+}
+
+pub(crate) async fn parse_map_tileset<R: Reader>(
+    parser: &mut Parser<R>,
+    attrs: &[Attribute<'_>],
+    map_path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+) -> Result<MapTileset> {
+    let res = Tileset::parse_xml_in_map(parser, attrs, map_path, read_from, cache).await?;
+    let tileset = match res.result_type {
+        EmbeddedParseResultType::ExternalReference { tileset_path } => {
+            if let Some(ts) = cache.get_tileset(&tileset_path) {
+                debug_event!(path = %tileset_path.display(), "tileset cache hit");
+                ts
+            } else {
+                debug_event!(path = %tileset_path.display(), "tileset cache miss");
+                return Ok(MapTileset::Pending {
+                    first_gid: res.first_gid,
+                    tileset_path,
+                });
+            }
+        }
+        EmbeddedParseResultType::Embedded { tileset } => Arc::new(tileset),
+    };
+    Ok(MapTileset::Loaded(MapTilesetGid {
+        first_gid: res.first_gid,
+        tileset,
+    }))
+}
+
+/// Loads the pending tilesets of a map once all of its tilesets are known, then reports every
+/// tileset of the map as loaded. Does nothing if this was already done.
+async fn load_map_tilesets<R: Reader>(
+    parser: &mut Parser<R>,
+    pending: &mut Option<Vec<(usize, PathBuf)>>,
+    tilesets: &mut [MapTilesetGid],
+    map_path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+) -> Result<()> {
+    let Some(mut pending) = pending.take() else {
+        return Ok(());
+    };
+    parser.check_cancelled()?;
+    load_pending_tilesets(parser, &mut pending, tilesets, map_path, read_from, cache).await?;
+    for (index, tileset) in tilesets.iter().enumerate() {
+        parser.report(LoadProgress::TilesetLoaded {
+            index,
+            count: tilesets.len(),
+            name: &tileset.tileset.name,
+        })?;
+    }
+    Ok(())
+}
+
+/// Loads the external tilesets that weren't cached when they were found, reading their files at
+/// once so that async readers can fetch them concurrently. Tilesets referenced more than once are
+/// only loaded once.
+pub(crate) async fn load_pending_tilesets<R: Reader>(
+    parser: &mut Parser<R>,
+    pending: &mut Vec<(usize, PathBuf)>,
+    tilesets: &mut [MapTilesetGid],
+    map_path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+) -> Result<()> {
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let pending = std::mem::take(pending);
+    let paths: Vec<&Path> = pending
+        .iter()
+        .map(|(_, path)| path.as_path())
+        .unique()
+        .collect();
+
+    let readers = read_from.read_all(&paths).await;
+    let mut loaded = HashMap::new();
+    for (path, reader) in paths.into_iter().zip(readers) {
+        let result = match reader {
+            Ok(reader) => {
+                crate::parse::xml::parse_tileset_from(
+                    reader,
+                    path,
+                    read_from,
+                    cache,
+                    &parser.options,
+                )
+                .await
+            }
+            Err(err) => Err(Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err: Box::new(err),
+            }),
+        };
+        match result {
+            Ok(tileset) => {
+                let tileset = cache.get_or_insert_tileset(path, Arc::new(tileset));
+                loaded.insert(path, Ok(tileset));
+            }
+            Err(err) if parser.options.lenient && err.is_recoverable() => {
+                loaded.insert(path, Err(err.to_string()));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    for (index, path) in &pending {
+        match &loaded[path.as_path()] {
+            Ok(tileset) => tilesets[*index].tileset = tileset.clone(),
+            // The placeholder the tileset was given is kept
+            Err(message) => parser.diagnostics.push(Diagnostic {
+                path: map_path.to_owned(),
+                kind: DiagnosticKind::ReplacedTileset { index: *index },
+                message: message.clone(),
+            }),
+        }
+    }
+    Ok(())
+}
+
+/// The settings of the Tiled editor saved in a map's `<editorsettings>` element.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EditorSettings {
+    /// The size of the chunks Tiled saves the layers of infinite maps in, as `(width, height)`
+    /// in tiles, if it isn't the default of 16×16.
+    pub chunk_size: Option<(u32, u32)>,
+    /// The file the map was last exported to, relative to the map, if any.
+    pub export_target: Option<String>,
+    /// The name of the format the map was last exported in, e.g. `json`, if any.
+    pub export_format: Option<String>,
+}
+
+impl EditorSettings {
+    async fn parse_xml<R: Reader>(parser: &mut Parser<R>) -> Result<Self> {
+        let mut settings = Self::default();
+        parse_tag!(parser, "editorsettings", {
+            "chunksize" => for attrs {
+                let (width, height) = get_attrs!(
+                    for v in attrs {
+                        "width" => width ?= v.parse::<u32>(),
+                        "height" => height ?= v.parse::<u32>(),
+                    }
+                    (width, height)
+                );
+                settings.chunk_size = Some((width, height));
+                Ok(())
+            },
+            "export" => for attrs {
+                let (target, format) = get_attrs!(
+                    for v in attrs {
+                        Some("target") => target = v.to_string(),
+                        Some("format") => format = v.to_string(),
+                    }
+                    (target, format)
+                );
+                settings.export_target = target;
+                settings.export_format = format;
+                Ok(())
+            },
+        });
+        Ok(settings)
     }
 }
 
 // Specifies whether the odd or even rows/columns are shifted half a tile
 // right/down. Only applies to Staggered and Hexagonal map orientations.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum StaggerIndex {
     Even,
@@ -315,6 +1084,7 @@ impl FromStr for StaggerIndex {
 // Specifies which axis is staggered. Only applies to Staggered and Hexagonal
 // map orientations.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum StaggerAxis {
     X,
@@ -354,6 +1124,7 @@ impl FromStr for StaggerAxis {
 
 /// Represents the way tiles are laid out in a map.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum Orientation {
     Orthogonal,
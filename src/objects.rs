@@ -1,20 +1,25 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    sync::Arc,
+};
 
 use quick_xml::events::{attributes::Attribute, Event};
 
 use crate::{
     error::{Error, Result},
     parse::xml::{Parser, ReadFrom, Reader},
-    properties::{parse_properties, Properties},
+    properties::{merge_properties, parse_properties, Properties},
     template::Template,
-    util::{get_attrs, map_wrapper, parse_tag},
-    Color, Gid, MapTilesetGid, ResourceCache, Tile, TileId, Tileset,
+    util::{debug_event, get_attrs, map_wrapper, parse_tag},
+    Color, Gid, MapTilesetGid, ParseWarningKind, ResourceCache, Tile, TileId, Tileset,
 };
 
 /// The location of the tileset this tile is in
 ///
 /// Tilesets can be contained within either a map or a template.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TilesetLocation {
     /// Index into the Map's tileset list, guaranteed to be a valid index of the map tileset container.
     Map(usize),
@@ -24,9 +29,10 @@ pub enum TilesetLocation {
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectTileData {
     /// A valid TilesetLocation that points to a tileset that **may or may not contain** this tile.
-    tileset_location: TilesetLocation,
+    pub(crate) tileset_location: TilesetLocation,
     /// The local ID of the tile in the tileset it's in.
     id: TileId,
     /// Whether this tile is flipped on its Y axis (horizontally).
@@ -51,6 +57,27 @@ impl ObjectTileData {
         &self.tileset_location
     }
 
+    /// Creates the data of an object tile referencing the tile with the local ID given in the
+    /// tileset given.
+    ///
+    /// [`TilesetLocation::Map`] indices are checked when the layer the object is in is added to a
+    /// map through [`Map::add_layer()`](crate::Map::add_layer).
+    pub fn new(
+        tileset_location: TilesetLocation,
+        id: TileId,
+        flip_h: bool,
+        flip_v: bool,
+        flip_d: bool,
+    ) -> Self {
+        Self {
+            tileset_location,
+            id,
+            flip_h,
+            flip_v,
+            flip_d,
+        }
+    }
+
     const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
     const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
     const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
@@ -119,6 +146,7 @@ impl<'map> ObjectTile<'map> {
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum ObjectShape {
     Rect {
@@ -157,6 +185,7 @@ pub enum ObjectShape {
 
 /// The horizontal alignment of an [`ObjectShape::Text`].
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum HorizontalAlignment {
     #[default]
@@ -168,6 +197,7 @@ pub enum HorizontalAlignment {
 
 /// The vertical alignment of an [`ObjectShape::Text`].
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum VerticalAlignment {
     #[default]
@@ -176,17 +206,66 @@ pub enum VerticalAlignment {
     Bottom,
 }
 
+/// The fields of an object instantiated from a template that the object sets itself instead of
+/// inheriting them from the template, as returned by [`ObjectData::overridden_fields()`].
+///
+/// The position of an object is always its own, so it isn't listed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OverriddenFields {
+    /// Whether the object has its own name.
+    pub name: bool,
+    /// Whether the object has its own type.
+    pub user_type: bool,
+    /// Whether the object has its own visibility.
+    pub visible: bool,
+    /// Whether the object has its own rotation.
+    pub rotation: bool,
+    /// Whether the object references its own tile.
+    pub tile: bool,
+    /// Whether the object has its own shape.
+    pub shape: bool,
+    /// The names of the custom properties the object sets itself, whether or not the template
+    /// has them.
+    pub properties: BTreeSet<Arc<str>>,
+}
+
+/// The template an object was instantiated from, along with the fields it overrides. Never
+/// affects comparisons between objects, since templates aren't kept once objects are written.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct TemplateOrigin {
+    template: Option<Arc<Template>>,
+    overridden: OverriddenFields,
+}
+
+impl TemplateOrigin {
+    pub(crate) fn new(template: Option<Arc<Template>>, overridden: OverriddenFields) -> Self {
+        Self {
+            template,
+            overridden,
+        }
+    }
+}
+
+impl PartialEq for TemplateOrigin {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
 /// Raw data belonging to an object. Used internally and for tile collisions.
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectData {
-    id: u32,
-    tile: Option<ObjectTileData>,
+    pub(crate) id: u32,
+    pub(crate) tile: Option<ObjectTileData>,
     /// The name of the object, which is arbitrary and set by the user.
     pub name: String,
     /// The type of the object, which is arbitrary and set by the user.
-    pub user_type: String,
+    pub user_type: Arc<str>,
     /// The X coordinate of this object in pixels.
     pub x: f32,
     /// The Y coordinate of this object in pixels.
@@ -199,6 +278,7 @@ pub struct ObjectData {
     pub shape: ObjectShape,
     /// The object's custom properties as set by the user.
     pub properties: Properties,
+    pub(crate) origin: TemplateOrigin,
 }
 
 impl ObjectData {
@@ -215,6 +295,102 @@ impl ObjectData {
     pub fn tile_data(&self) -> Option<ObjectTileData> {
         self.tile.clone()
     }
+
+    /// Returns the template the object was instantiated from when it was loaded, if any.
+    #[inline]
+    pub fn template(&self) -> Option<&Arc<Template>> {
+        self.origin.template.as_ref()
+    }
+
+    /// Returns which fields the object set itself instead of inheriting them from its
+    /// [template](Self::template) when it was loaded, or `None` if it doesn't have a template.
+    ///
+    /// Fields changed after loading the object aren't tracked.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_template.tmx")
+    ///     .unwrap();
+    /// let (_, object) = map.objects().find(|(_, o)| o.template().is_some()).unwrap();
+    ///
+    /// let overridden = object.overridden_fields().unwrap();
+    /// assert!(!overridden.shape && !overridden.tile);
+    /// # }
+    /// ```
+    #[inline]
+    pub fn overridden_fields(&self) -> Option<&OverriddenFields> {
+        let origin = &self.origin;
+        origin.template.as_ref().map(|_| &origin.overridden)
+    }
+
+    /// Creates a visible object with the shape given, at the position given in pixels, without a
+    /// name, type, rotation or properties. Note that [points](ObjectShape::Point) hold the
+    /// position of their object as well.
+    ///
+    /// The object has an ID of 0 until it is added to a map, e.g. through
+    /// [`ObjectLayerMut::add_object()`](crate::ObjectLayerMut::add_object) or
+    /// [`Map::add_layer()`](crate::Map::add_layer), which assign it a fresh one.
+    pub fn from_shape(x: f32, y: f32, shape: ObjectShape) -> Self {
+        Self {
+            id: 0,
+            tile: None,
+            name: String::new(),
+            user_type: Arc::from(""),
+            x,
+            y,
+            rotation: 0.0,
+            visible: true,
+            shape,
+            properties: Properties::new(),
+            origin: TemplateOrigin::default(),
+        }
+    }
+
+    /// Sets the tile this object is displaying, which makes it a tile object, or removes it if
+    /// `tile` is [`None`].
+    #[inline]
+    pub fn set_tile(&mut self, tile: Option<ObjectTileData>) {
+        self.tile = tile;
+    }
+
+    /// Deserializes the object's custom properties into a type implementing `Deserialize`, as
+    /// described in [`PropertiesExt::deserialize_as()`](crate::PropertiesExt::deserialize_as).
+    /// Available under the `serde` feature.
+    ///
+    /// ## Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use tiled::Loader;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct TestType {
+    ///     test_property_1: i32,
+    /// }
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Properties {
+    ///     #[serde(rename = "class property")]
+    ///     class_property: TestType,
+    /// }
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_class_property.tmx")
+    ///     .unwrap();
+    /// let object = map.objects().find(|(_, object)| object.id() == 2).unwrap().1;
+    ///
+    /// let properties: Properties = object.properties_as().unwrap();
+    /// assert_eq!(properties.class_property.test_property_1, 3);
+    /// # }
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn properties_as<'de, T: serde::Deserialize<'de>>(&'de self) -> Result<T> {
+        crate::property_de::from_properties(&self.properties)
+    }
 }
 
 impl ObjectData {
@@ -230,13 +406,13 @@ impl ObjectData {
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
     ) -> Result<ObjectData> {
-        let (id, tile, mut n, mut t, c, w, h, mut v, mut r, template, x, y) = get_attrs!(
+        let (id, tile, mut n, t, c, w, h, mut v, mut r, template, x, y) = get_attrs!(
             for v in attrs {
                 Some("id") => id ?= v.parse(),
                 Some("gid") => tile ?= v.parse::<u32>(),
                 Some("name") => name ?= v.parse(),
-                Some("type") => user_type ?= v.parse(),
-                Some("class") => user_class ?= v.parse(),
+                Some("type") => user_type = v,
+                Some("class") => user_class = v,
                 Some("width") => width ?= v.parse(),
                 Some("height") => height ?= v.parse(),
                 Some("visible") => visible ?= v.parse().map(|x:i32| x == 1),
@@ -247,11 +423,27 @@ impl ObjectData {
             }
             (id, tile, name, user_type, user_class, width, height, visible, rotation, template, x, y)
         );
+        if c.is_some() {
+            parser.warn(ParseWarningKind::DeprecatedAttribute {
+                element: "object".to_owned(),
+                name: "class".to_owned(),
+                replacement: "type".to_owned(),
+            });
+        }
+        let mut t = t.map(|t| parser.interner.intern(t));
         let x = x.unwrap_or(0.);
         let y = y.unwrap_or(0.);
         let mut tile = tile.and_then(|bits| {
             ObjectTileData::from_bits(bits, tilesets?, for_tileset.as_ref().cloned())
         });
+        let mut overridden = OverriddenFields {
+            name: n.is_some(),
+            user_type: t.is_some(),
+            visible: v.is_some(),
+            rotation: r.is_some(),
+            tile: tile.is_some(),
+            ..OverriddenFields::default()
+        };
         // If the template attribute is there, we need to go fetch the template file
         let template: Option<Arc<Template>> = match template {
             Some(template_path) => {
@@ -259,16 +451,21 @@ impl ObjectData {
 
                 // Check the cache to see if this template exists
                 let template = if let Some(templ) = cache.get_template(&template_path) {
+                    debug_event!(path = %template_path.display(), "template cache hit");
                     templ
                 } else {
+                    debug_event!(path = %template_path.display(), "template cache miss");
                     // add indirection because the returned async state machine is a recursive data structure
                     // (`Template::parse_template` eventually calls this function)
-                    let template =
-                        Box::pin(Template::parse_template(&template_path, read_from, cache))
-                            .await?;
+                    let template = Box::pin(Template::parse_template(
+                        &template_path,
+                        read_from,
+                        cache,
+                        &parser.options,
+                    ))
+                    .await?;
                     // Insert it into the cache
-                    cache.insert_template(&template_path, template.clone());
-                    template
+                    cache.get_or_insert_template(&template_path, template)
                 };
 
                 // The template sets the default values for the object
@@ -291,7 +488,7 @@ impl ObjectData {
         let rotation = r.unwrap_or(0f32);
         let id = id.unwrap_or(0u32);
         let name = n.unwrap_or_default();
-        let user_type: String = t.or(c).unwrap_or_default();
+        let user_type = t.unwrap_or_else(|| parser.interner.intern(c.unwrap_or_default()));
         let mut shape = None;
         let mut properties = HashMap::new();
 
@@ -328,14 +525,12 @@ impl ObjectData {
 
         // Possibly copy properties from the template into the object
         // Any that already exist in the object's map don't get copied over
-        if let Some(templ) = template {
+        if let Some(templ) = &template {
+            overridden.shape = shape.is_some();
+            overridden.properties = properties.keys().cloned().collect();
             shape.get_or_insert(templ.object.shape.clone());
 
-            for (k, v) in &templ.object.properties {
-                if !properties.contains_key(k) {
-                    properties.insert(k.clone(), v.clone());
-                }
-            }
+            merge_properties(&mut properties, &templ.object.properties);
         }
 
         let shape = shape.unwrap_or(ObjectShape::Rect { width, height });
@@ -351,6 +546,7 @@ impl ObjectData {
             visible,
             shape,
             properties,
+            origin: TemplateOrigin::new(template, overridden),
         })
     }
 }
@@ -526,4 +722,69 @@ impl<'map> Object<'map> {
             .as_ref()
             .map(|tile| ObjectTile::new(self.map, tile))
     }
+
+    /// Returns the point this object is rotated around, relative to the top-left corner of its
+    /// unrotated bounding box.
+    ///
+    /// Tiled rotates every object around its position, `(x, y)`. For most objects, the position is
+    /// their top-left corner, but tile objects are anchored to the point of their image given by
+    /// the [object alignment](crate::Tileset::object_alignment) of their tileset, which defaults
+    /// to the bottom-left corner in orthogonal maps and to the bottom-center in isometric ones.
+    /// The top-left corner of an object's unrotated bounding box is therefore
+    /// `(x - pivot.0, y - pivot.1)`.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_rotation.tmx")
+    ///     .unwrap();
+    /// let chest = map.objects().find(|(_, object)| object.name == "chest").unwrap().1;
+    ///
+    /// // The tile object is anchored at the bottom-left corner of its 32x32 image.
+    /// assert_eq!(chest.pivot(), (0.0, 32.0));
+    /// # }
+    /// ```
+    pub fn pivot(&self) -> (f32, f32) {
+        match (self.get_tile(), &self.data.shape) {
+            (Some(tile), ObjectShape::Rect { width, height }) => tile
+                .get_tileset()
+                .object_alignment
+                .offset(*width, *height, self.map.orientation),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Applies the object's rotation to a point given in map pixel coordinates, i.e. rotates it
+    /// clockwise by [`rotation`](ObjectData::rotation) degrees around the object's position.
+    ///
+    /// This is useful for placing the corners or the shape points of an object the same way
+    /// Tiled displays them.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_rotation.tmx")
+    ///     .unwrap();
+    /// let crate_object = map.objects().find(|(_, object)| object.name == "crate").unwrap().1;
+    ///
+    /// // The crate is rotated by 90 degrees around its top-left corner at (32, 32), so its
+    /// // top-right corner ends up below it.
+    /// let (x, y) = crate_object.apply_rotation((64.0, 32.0));
+    /// assert!((x - 32.0).abs() < 1e-4 && (y - 64.0).abs() < 1e-4);
+    /// # }
+    /// ```
+    pub fn apply_rotation(&self, point: (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.data.rotation.to_radians().sin_cos();
+        let (dx, dy) = (point.0 - self.data.x, point.1 - self.data.y);
+        (
+            self.data.x + dx * cos - dy * sin,
+            self.data.y + dx * sin + dy * cos,
+        )
+    }
 }
@@ -0,0 +1,266 @@
+//! Owned handles to map elements, which keep their map alive through an [`Arc`].
+
+use std::{ops::Deref, sync::Arc};
+
+use crate::{
+    GroupLayer, ImageLayer, Layer, LayerData, LayerDataType, LayerType, Map, Object, ObjectData,
+    ObjectLayer, ObjectTile, TileLayer,
+};
+
+/// An owned equivalent of [`Layer`], which holds a reference-counted pointer to its map instead of
+/// borrowing it.
+///
+/// Since it doesn't have a lifetime, it can be stored in long-lived state without having to keep
+/// the map borrowed. It dereferences to [`LayerData`] and has the same accessors as [`Layer`],
+/// which return handles borrowing from the owned layer itself.
+///
+/// ## Example
+/// ```
+/// use std::sync::Arc;
+/// use tiled::{Loader, OwnedLayer};
+///
+/// # fn main() {
+/// struct GameState {
+///     ground: OwnedLayer,
+/// }
+///
+/// let map = Arc::new(
+///     Loader::new()
+///         .load_tmx_map("assets/tiled_group_layers.tmx")
+///         .unwrap(),
+/// );
+/// let state = GameState {
+///     ground: map.get_owned_layer(0).unwrap(),
+/// };
+/// drop(map);
+///
+/// assert_eq!(&*state.ground.name, "tile-1");
+/// assert!(state.ground.as_tile_layer().is_some());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OwnedLayer {
+    map: Arc<Map>,
+    path: Vec<usize>,
+}
+
+impl OwnedLayer {
+    /// Creates an owned handle to the layer found by following the given path of layer indices,
+    /// starting from the map's top-level layers and descending into groups.
+    ///
+    /// Returns [`None`] if the path is empty or does not lead to a layer.
+    pub fn new(map: Arc<Map>, path: impl Into<Vec<usize>>) -> Option<Self> {
+        let path = path.into();
+        resolve_layer_path(&map, &path)?;
+        Some(Self { map, path })
+    }
+
+    /// Get the map this layer is from.
+    #[inline]
+    pub fn map(&self) -> &Arc<Map> {
+        &self.map
+    }
+
+    /// The path of layer indices leading to this layer, starting from the map's top-level layers.
+    #[inline]
+    pub fn path(&self) -> &[usize] {
+        &self.path
+    }
+
+    /// Returns the borrowed [`Layer`] this handle points to.
+    #[inline]
+    pub fn get(&self) -> Layer<'_> {
+        Layer::new(&self.map, self.data())
+    }
+
+    fn data(&self) -> &LayerData {
+        // The path was validated on creation and maps behind an `Arc` are immutable.
+        resolve_layer_path(&self.map, &self.path).expect("owned layer path should be valid")
+    }
+
+    /// Get the layer's type.
+    #[inline]
+    pub fn layer_type(&self) -> LayerType<'_> {
+        self.get().layer_type()
+    }
+
+    /// Convenience method to return this layer as a tile layer, only if it is one.
+    #[inline]
+    pub fn as_tile_layer(&self) -> Option<TileLayer<'_>> {
+        self.get().as_tile_layer()
+    }
+
+    /// Convenience method to return this layer as an object group, only if it is one.
+    #[inline]
+    pub fn as_object_layer(&self) -> Option<ObjectLayer<'_>> {
+        self.get().as_object_layer()
+    }
+
+    /// Convenience method to return this layer as an image layer, only if it is one.
+    #[inline]
+    pub fn as_image_layer(&self) -> Option<ImageLayer<'_>> {
+        self.get().as_image_layer()
+    }
+
+    /// Convenience method to return this layer as a group layer, only if it is one.
+    #[inline]
+    pub fn as_group_layer(&self) -> Option<GroupLayer<'_>> {
+        self.get().as_group_layer()
+    }
+
+    /// Returns owned handles to the layers contained within this layer if it is a group, in
+    /// display order. Returns an empty iterator otherwise.
+    pub fn layers(&self) -> impl ExactSizeIterator<Item = OwnedLayer> + '_ {
+        let count = match &self.data().layer_type {
+            LayerDataType::Group(group) => group.layer_data().len(),
+            _ => 0,
+        };
+        (0..count).map(move |index| self.child(index))
+    }
+
+    /// Gets an owned handle to a specific layer from this group by index. Returns [`None`] if the
+    /// layer is not a group or the index is out of bounds.
+    pub fn get_layer(&self, index: usize) -> Option<OwnedLayer> {
+        match &self.data().layer_type {
+            LayerDataType::Group(group) if index < group.layer_data().len() => {
+                Some(self.child(index))
+            }
+            _ => None,
+        }
+    }
+
+    fn child(&self, index: usize) -> OwnedLayer {
+        let mut path = self.path.clone();
+        path.push(index);
+        OwnedLayer {
+            map: self.map.clone(),
+            path,
+        }
+    }
+
+    /// Returns owned handles to the objects contained within this layer if it is an object layer,
+    /// in the order they were declared in the TMX file. Returns an empty iterator otherwise.
+    pub fn objects(&self) -> impl ExactSizeIterator<Item = OwnedObject> + '_ {
+        let count = match &self.data().layer_type {
+            LayerDataType::Objects(objects) => objects.object_data().len(),
+            _ => 0,
+        };
+        (0..count).map(move |index| OwnedObject {
+            layer: self.clone(),
+            index,
+        })
+    }
+
+    /// Obtains an owned handle to the object corresponding to the index given. Returns [`None`]
+    /// if the layer is not an object layer or the index is out of bounds.
+    pub fn get_object(&self, index: usize) -> Option<OwnedObject> {
+        match &self.data().layer_type {
+            LayerDataType::Objects(objects) if index < objects.object_data().len() => {
+                Some(OwnedObject {
+                    layer: self.clone(),
+                    index,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Deref for OwnedLayer {
+    type Target = LayerData;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.data()
+    }
+}
+
+/// An owned equivalent of [`Object`], which holds a reference-counted pointer to its map instead of
+/// borrowing it.
+///
+/// It dereferences to [`ObjectData`] and has the same accessors as [`Object`].
+#[derive(Debug, Clone)]
+pub struct OwnedObject {
+    layer: OwnedLayer,
+    index: usize,
+}
+
+impl OwnedObject {
+    /// Get the map this object is from.
+    #[inline]
+    pub fn map(&self) -> &Arc<Map> {
+        self.layer.map()
+    }
+
+    /// Get the object layer this object is contained in.
+    #[inline]
+    pub fn layer(&self) -> &OwnedLayer {
+        &self.layer
+    }
+
+    /// The index of this object inside of its layer.
+    #[inline]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the borrowed [`Object`] this handle points to.
+    #[inline]
+    pub fn get(&self) -> Object<'_> {
+        Object::new(&self.layer.map, self.data())
+    }
+
+    fn data(&self) -> &ObjectData {
+        match &self.layer.data().layer_type {
+            LayerDataType::Objects(objects) => &objects.object_data()[self.index],
+            _ => unreachable!("owned objects are always contained in object layers"),
+        }
+    }
+
+    /// Returns the tile that the object is using as image, if any.
+    #[inline]
+    pub fn get_tile(&self) -> Option<ObjectTile<'_>> {
+        self.get().get_tile()
+    }
+}
+
+impl Deref for OwnedObject {
+    type Target = ObjectData;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.data()
+    }
+}
+
+impl Map {
+    /// Get an iterator over owned handles to all the top-level layers in the map, in ascending
+    /// order of their layer index.
+    ///
+    /// See [`OwnedLayer`] for more information.
+    pub fn owned_layers(self: &Arc<Self>) -> impl ExactSizeIterator<Item = OwnedLayer> + '_ {
+        (0..self.layers.len()).map(move |index| OwnedLayer {
+            map: self.clone(),
+            path: vec![index],
+        })
+    }
+
+    /// Returns an owned handle to the top-level layer that has the specified index, if it exists.
+    ///
+    /// See [`OwnedLayer`] for more information.
+    pub fn get_owned_layer(self: &Arc<Self>, index: usize) -> Option<OwnedLayer> {
+        OwnedLayer::new(self.clone(), vec![index])
+    }
+}
+
+fn resolve_layer_path<'map>(map: &'map Map, path: &[usize]) -> Option<&'map LayerData> {
+    let (first, rest) = path.split_first()?;
+    let mut layer = map.layers.get(*first)?;
+    for index in rest {
+        layer = match &layer.layer_type {
+            LayerDataType::Group(group) => group.layer_data().get(*index)?,
+            _ => return None,
+        };
+    }
+    Some(layer)
+}
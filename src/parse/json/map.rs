@@ -0,0 +1,397 @@
+use std::{convert::TryFrom, path::Path, sync::Arc};
+
+use base64::Engine;
+use serde_json::Value;
+
+use super::{read_json, JsonObject, JsonParser};
+use crate::{
+    gids::GidTable,
+    layers::{
+        convert_to_tiles, decompress, is_supported_compression, unsupported_encoding,
+        LayerDataType, LayerTag,
+    },
+    parse::xml::ReadFrom,
+    Diagnostic, DiagnosticKind, EditorSettings, Error, FiniteTileLayerData, Gid, GroupLayerData,
+    ImageLayerData, InfiniteTileLayerData, LayerData, LayerTileData, LoaderOptions, Map,
+    MapTilesetGid, Orientation, ResourceCache, Result, StaggerAxis, StaggerIndex, TileLayerData,
+    Tileset,
+};
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse_map", level = "debug", skip_all, fields(path = %path.display()))
+)]
+pub async fn parse_map(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    options: &LoaderOptions,
+) -> Result<Map> {
+    let value = read_json(path, read_from).await?;
+    let map = JsonObject::new(&value, "the map")?;
+    JsonParser::new(read_from, cache, options)
+        .parse_map(map, path)
+        .await
+}
+
+impl<RF: ReadFrom, C: ResourceCache> JsonParser<'_, RF, C> {
+    async fn parse_map(&mut self, map: JsonObject<'_>, map_path: &Path) -> Result<Map> {
+        // Older versions of Tiled saved the version as a number.
+        let version = match map.get("version") {
+            Some(Value::String(version)) => version.clone(),
+            Some(Value::Number(version)) => version.to_string(),
+            _ => String::new(),
+        };
+        let orientation = map
+            .str("orientation")?
+            .parse::<Orientation>()
+            .map_err(|err| Error::MalformedAttributes(err.to_string()))?;
+        let stagger_axis = map
+            .opt_str("staggeraxis")?
+            .map(str::parse::<StaggerAxis>)
+            .transpose()
+            .map_err(|err| Error::MalformedAttributes(err.to_string()))?
+            .unwrap_or_default();
+        let stagger_index = map
+            .opt_str("staggerindex")?
+            .map(str::parse::<StaggerIndex>)
+            .transpose()
+            .map_err(|err| Error::MalformedAttributes(err.to_string()))?
+            .unwrap_or_default();
+        let hex_side_length = match orientation {
+            Orientation::Hexagonal => map.opt_u32("hexsidelength")?.unwrap_or(0),
+            _ => 0,
+        };
+        let tile_width = map.u32("tilewidth")?;
+        let tile_height = map.u32("tileheight")?;
+        let infinite = map.opt_bool("infinite")?.unwrap_or(false);
+
+        let mut tilesets = Vec::new();
+        for tileset in map.array("tilesets")? {
+            match self.parse_map_tileset(tileset, map_path).await {
+                Ok(tileset) => tilesets.push(tileset),
+                Err(err) if self.options.lenient && err.is_recoverable() => {
+                    // The GID range of the tileset is needed to keep the following tilesets
+                    // valid, so the failure can't be recovered from without it.
+                    let reference = JsonObject::new(tileset, "a map tileset").ok();
+                    let Some(first_gid) = reference.and_then(|r| r.u32("firstgid").ok()) else {
+                        return Err(err);
+                    };
+                    self.diagnostics.push(Diagnostic {
+                        path: map_path.to_owned(),
+                        kind: DiagnosticKind::ReplacedTileset {
+                            index: tilesets.len(),
+                        },
+                        message: err.to_string(),
+                    });
+                    let name = reference.and_then(|r| r.opt_str("name").ok().flatten());
+                    tilesets.push(MapTilesetGid {
+                        first_gid: Gid(first_gid),
+                        tileset: Arc::new(Tileset::new(
+                            name.unwrap_or_default().to_owned(),
+                            tile_width,
+                            tile_height,
+                        )),
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        let layers = self
+            .parse_layers(map.array("layers")?, infinite, map_path, &tilesets)
+            .await?;
+
+        let gid_table = GidTable::loaded(tilesets.iter().map(|ts| ts.first_gid.0).collect());
+        let mut map = Map {
+            version,
+            orientation,
+            width: map.u32("width")?,
+            height: map.u32("height")?,
+            tile_width,
+            tile_height,
+            stagger_axis,
+            stagger_index,
+            hex_side_length,
+            parallax_origin_x: map.opt_f32("parallaxoriginx")?.unwrap_or(0.0),
+            parallax_origin_y: map.opt_f32("parallaxoriginy")?.unwrap_or(0.0),
+            tilesets: tilesets.into_iter().map(|ts| ts.tileset).collect(),
+            layers,
+            properties: map.properties(
+                self.options,
+                map_path.parent().ok_or(Error::PathIsNotFile)?,
+                &mut self.interner,
+            )?,
+            background_color: map.opt_color("backgroundcolor")?,
+            editor_settings: map
+                .opt_object("editorsettings", "editor settings")?
+                .map(parse_editor_settings)
+                .transpose()?,
+            infinite,
+            user_type: map.opt_str("class")?.map(|t| self.interner.intern(t)),
+            next_object_id: map.opt_u32("nextobjectid")?.unwrap_or(0),
+            diagnostics: std::mem::take(&mut self.diagnostics),
+            id_index: Default::default(),
+            gid_table,
+        };
+        if map.next_object_id == 0 {
+            map.next_object_id = map.highest_object_id() + 1;
+        }
+        Ok(map)
+    }
+
+    /// Parses a tileset of a map or template, which is either embedded or refers to a tileset
+    /// file.
+    pub(super) async fn parse_map_tileset(
+        &mut self,
+        value: &Value,
+        path: &Path, // Template or Map file
+    ) -> Result<MapTilesetGid> {
+        let tileset = JsonObject::new(value, "a map tileset")?;
+        let first_gid = Gid(tileset.u32("firstgid")?);
+        let tileset = match tileset.opt_str("source")? {
+            Some(source) => {
+                let tileset_path = path.parent().ok_or(Error::PathIsNotFile)?.join(source);
+                self.load_tileset(&tileset_path).await?
+            }
+            None => Arc::new(self.parse_tileset(tileset, path).await?),
+        };
+        Ok(MapTilesetGid { first_gid, tileset })
+    }
+
+    async fn parse_layers(
+        &mut self,
+        layers: &[Value],
+        infinite: bool,
+        map_path: &Path,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<Vec<LayerData>> {
+        let mut parsed = Vec::with_capacity(layers.len());
+        for layer in layers {
+            parsed.push(
+                self.parse_layer(layer, infinite, map_path, tilesets)
+                    .await?,
+            );
+        }
+        Ok(parsed)
+    }
+
+    /// Parses a layer. In [lenient mode](LoaderOptions::lenient), layers that fail to parse are
+    /// replaced by an empty layer of the same kind, and the failure is recorded in the
+    /// diagnostics.
+    async fn parse_layer(
+        &mut self,
+        value: &Value,
+        infinite: bool,
+        map_path: &Path,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<LayerData> {
+        let err = match self
+            .parse_layer_inner(value, infinite, map_path, tilesets)
+            .await
+        {
+            Err(err) if self.options.lenient && err.is_recoverable() => err,
+            result => return result,
+        };
+        let Ok(layer) = JsonObject::new(value, "a layer") else {
+            return Err(err);
+        };
+        let tag = match layer.opt_str("type") {
+            Ok(Some("tilelayer")) => LayerTag::Tiles,
+            Ok(Some("objectgroup")) => LayerTag::Objects,
+            Ok(Some("imagelayer")) => LayerTag::Image,
+            Ok(Some("group")) => LayerTag::Group,
+            _ => return Err(err),
+        };
+        let id = layer.opt_u32("id").ok().flatten().unwrap_or(0);
+        let name = layer
+            .opt_str("name")
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+            .to_owned();
+        let size = layer
+            .opt_u32("width")
+            .ok()
+            .flatten()
+            .zip(layer.opt_u32("height").ok().flatten());
+        self.diagnostics.push(Diagnostic {
+            path: map_path.to_owned(),
+            kind: DiagnosticKind::ReplacedLayer {
+                id,
+                name: name.clone(),
+            },
+            message: err.to_string(),
+        });
+        Ok(LayerData::placeholder(
+            id,
+            name.into(),
+            tag,
+            infinite,
+            size.unwrap_or((0, 0)),
+        ))
+    }
+
+    async fn parse_layer_inner(
+        &mut self,
+        value: &Value,
+        infinite: bool,
+        map_path: &Path,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<LayerData> {
+        let layer = JsonObject::new(value, "a layer")?;
+        let id = layer.opt_u32("id")?.unwrap_or(0);
+        let path_relative_to = map_path.parent().ok_or(Error::PathIsNotFile)?;
+
+        let layer_type = match layer.str("type")? {
+            "tilelayer" => {
+                LayerDataType::Tiles(self.parse_tile_layer(layer, infinite, id, tilesets)?)
+            }
+            "objectgroup" => LayerDataType::Objects(
+                self.parse_object_layer(layer, Some(tilesets), path_relative_to)
+                    .await?,
+            ),
+            "imagelayer" => LayerDataType::Image(ImageLayerData {
+                image: self.parse_image(layer, path_relative_to)?,
+                repeat_x: layer.opt_bool("repeatx")?.unwrap_or(false),
+                repeat_y: layer.opt_bool("repeaty")?.unwrap_or(false),
+            }),
+            "group" => {
+                let max_depth = self.options.max_group_depth;
+                if self.group_depth >= max_depth {
+                    return Err(Error::GroupNestingTooDeep { max_depth });
+                }
+                self.group_depth += 1;
+                // add indirection because the returned async state machine is a recursive data
+                // structure (`parse_layers` eventually calls this function)
+                let layers = Box::pin(self.parse_layers(
+                    layer.array("layers")?,
+                    infinite,
+                    map_path,
+                    tilesets,
+                ))
+                .await;
+                self.group_depth -= 1;
+                LayerDataType::Group(GroupLayerData { layers: layers? })
+            }
+            other => {
+                return Err(Error::MalformedAttributes(format!(
+                    "unknown layer type `{}`",
+                    other
+                )))
+            }
+        };
+
+        Ok(LayerData {
+            name: self
+                .interner
+                .intern(layer.opt_str("name")?.unwrap_or_default()),
+            id,
+            visible: layer.opt_bool("visible")?.unwrap_or(true),
+            offset_x: layer.opt_f32("offsetx")?.unwrap_or(0.0),
+            offset_y: layer.opt_f32("offsety")?.unwrap_or(0.0),
+            parallax_x: layer.opt_f32("parallaxx")?.unwrap_or(1.0),
+            parallax_y: layer.opt_f32("parallaxy")?.unwrap_or(1.0),
+            opacity: layer.opt_f32("opacity")?.unwrap_or(1.0),
+            tint_color: layer.opt_color("tintcolor")?,
+            properties: layer.properties(self.options, path_relative_to, &mut self.interner)?,
+            user_type: layer.opt_str("class")?.map(|t| self.interner.intern(t)),
+            layer_type,
+        })
+    }
+
+    fn parse_tile_layer(
+        &self,
+        layer: JsonObject,
+        infinite: bool,
+        layer_id: u32,
+        tilesets: &[MapTilesetGid],
+    ) -> Result<TileLayerData> {
+        let encoding = layer.opt_str("encoding")?.unwrap_or("csv");
+        let compression = layer.opt_str("compression")?.filter(|c| !c.is_empty());
+
+        if !infinite {
+            let tiles = parse_tile_data(layer, encoding, compression, tilesets)?;
+            let mut data =
+                FiniteTileLayerData::from_tiles(layer.u32("width")?, layer.u32("height")?, tiles);
+            if self.options.compact_tile_layers {
+                data.compact();
+            }
+            return Ok(TileLayerData::Finite(data));
+        }
+
+        let mut data = InfiniteTileLayerData::empty();
+        for chunk in layer.array("chunks")? {
+            let chunk = JsonObject::new(chunk, "a chunk")?;
+            let tiles = parse_tile_data(chunk, encoding, compression, tilesets)?;
+            data.push_chunk(
+                layer_id,
+                (
+                    chunk.i32("x")?,
+                    chunk.i32("y")?,
+                    chunk.u32("width")?,
+                    chunk.u32("height")?,
+                ),
+                &tiles,
+                self.options.chunk_handler.as_ref(),
+            )?;
+        }
+        if let Some(pool) = &self.options.chunk_pool {
+            data.share_chunks(pool);
+        }
+        Ok(TileLayerData::Infinite(data))
+    }
+}
+
+/// Decodes the `data` member of a tile layer or chunk.
+fn parse_tile_data(
+    object: JsonObject,
+    encoding: &str,
+    compression: Option<&str>,
+    tilesets: &[MapTilesetGid],
+) -> Result<Vec<Option<LayerTileData>>> {
+    match (encoding, object.get("data")) {
+        (_, None) => Ok(Vec::new()),
+        ("csv", Some(Value::Array(data))) if compression.is_none() => data
+            .iter()
+            .map(|bits| {
+                bits.as_u64()
+                    .and_then(|bits| u32::try_from(bits).ok())
+                    .map(|bits| LayerTileData::from_bits(bits, tilesets))
+                    .ok_or(Error::InvalidTileFound)
+            })
+            .collect(),
+        ("base64", Some(Value::String(data))) if is_supported_compression(compression) => {
+            base64::engine::GeneralPurpose::new(
+                &base64::alphabet::STANDARD,
+                base64::engine::general_purpose::PAD,
+            )
+            .decode(data.trim())
+            .map_err(Error::Base64DecodingError)
+            .and_then(|data| decompress(data, compression))
+            .map(|data| convert_to_tiles(&data, tilesets))
+        }
+        _ => Err(unsupported_encoding(Some(encoding), compression)),
+    }
+}
+
+fn parse_editor_settings(settings: JsonObject) -> Result<EditorSettings> {
+    let chunk_size = settings
+        .opt_object("chunksize", "chunk size")?
+        .map(|size| Ok::<_, Error>((size.u32("width")?, size.u32("height")?)))
+        .transpose()?;
+    let export = settings.opt_object("export", "export settings")?;
+    Ok(EditorSettings {
+        chunk_size,
+        export_target: export
+            .as_ref()
+            .map(|export| export.opt_string("target"))
+            .transpose()?
+            .flatten(),
+        export_format: export
+            .as_ref()
+            .map(|export| export.opt_string("format"))
+            .transpose()?
+            .flatten(),
+    })
+}
@@ -0,0 +1,320 @@
+//! Parsing of the JSON map and tileset formats, i.e. TMJ, TSJ and TJ files, along with world
+//! files.
+//!
+//! The JSON formats mirror the XML ones, so files are parsed into the same structures. The only
+//! notable difference is that class properties don't store the types of their members, which are
+//! instead inferred from the JSON values.
+
+use std::{convert::TryFrom, path::Path};
+
+use serde_json::{Map as JsonMap, Value};
+
+use super::xml::{ReadFrom, Reader};
+use crate::{
+    intern::Interner, Color, Diagnostic, Error, Image, LoaderOptions, Properties, PropertyValue,
+    Result,
+};
+
+mod map;
+mod object;
+mod project;
+mod tileset;
+#[cfg(feature = "world")]
+mod world;
+
+pub(crate) use map::*;
+pub(crate) use object::*;
+pub(crate) use project::*;
+pub(crate) use tileset::*;
+#[cfg(feature = "world")]
+pub(crate) use world::*;
+
+/// Reads the whole resource at the path given and deserializes it.
+async fn read_json(path: &Path, read_from: &mut impl ReadFrom) -> Result<Value> {
+    let mut reader =
+        read_from
+            .read_from(path)
+            .await
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err: Box::new(err),
+            })?;
+    let mut data = Vec::new();
+    reader
+        .read_to_end(&mut data)
+        .await
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    serde_json::from_slice(&data).map_err(Error::JsonDecodingError)
+}
+
+/// The state shared by the files parsed while loading a map or tileset.
+struct JsonParser<'p, RF, C> {
+    read_from: &'p mut RF,
+    cache: &'p mut C,
+    /// The options the files are being parsed with.
+    options: &'p LoaderOptions,
+    /// The amount of group layers currently being parsed, used to enforce
+    /// [`LoaderOptions::max_group_depth`].
+    group_depth: usize,
+    /// The problems recovered from so far, in [lenient mode](LoaderOptions::lenient).
+    diagnostics: Vec<Diagnostic>,
+    /// The property names, classes and layer names parsed so far.
+    interner: Interner,
+}
+
+impl<'p, RF, C> JsonParser<'p, RF, C> {
+    fn new(read_from: &'p mut RF, cache: &'p mut C, options: &'p LoaderOptions) -> Self {
+        Self {
+            read_from,
+            cache,
+            options,
+            group_depth: 0,
+            diagnostics: Vec::new(),
+            interner: Interner::default(),
+        }
+    }
+
+    /// Parses the `image` member of an object, along with the members describing it.
+    fn parse_image(&self, object: JsonObject, path_relative_to: &Path) -> Result<Option<Image>> {
+        let Some(source) = object.opt_str("image")?.filter(|source| !source.is_empty()) else {
+            return Ok(None);
+        };
+        Ok(Some(Image::with_source(
+            path_relative_to.join(source),
+            object.opt_i32("imagewidth")?.unwrap_or(0),
+            object.opt_i32("imageheight")?.unwrap_or(0),
+            object.opt_color("transparentcolor")?,
+            self.options,
+        )))
+    }
+}
+
+/// A JSON object describing an element of a file, e.g. a layer.
+#[derive(Clone, Copy)]
+struct JsonObject<'a> {
+    members: &'a JsonMap<String, Value>,
+    /// What the object describes, used in error messages.
+    kind: &'static str,
+}
+
+impl<'a> JsonObject<'a> {
+    fn new(value: &'a Value, kind: &'static str) -> Result<Self> {
+        value
+            .as_object()
+            .map(|members| Self { members, kind })
+            .ok_or_else(|| Error::MalformedAttributes(format!("{} is not a JSON object", kind)))
+    }
+
+    /// Returns the member with the name given. `null` members are considered missing.
+    fn get(&self, name: &str) -> Option<&'a Value> {
+        self.members.get(name).filter(|value| !value.is_null())
+    }
+
+    fn optional<T>(
+        &self,
+        name: &str,
+        expected: &str,
+        convert: impl FnOnce(&'a Value) -> Option<T>,
+    ) -> Result<Option<T>> {
+        match self.get(name) {
+            Some(value) => convert(value).map(Some).ok_or_else(|| {
+                Error::MalformedAttributes(format!(
+                    "the `{}` member of {} is not {}",
+                    name, self.kind, expected
+                ))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn required<T>(
+        &self,
+        name: &str,
+        expected: &str,
+        convert: impl FnOnce(&'a Value) -> Option<T>,
+    ) -> Result<T> {
+        self.optional(name, expected, convert)?.ok_or_else(|| {
+            Error::MalformedAttributes(format!("{} has no `{}` member", self.kind, name))
+        })
+    }
+
+    fn opt_str(&self, name: &str) -> Result<Option<&'a str>> {
+        self.optional(name, "a string", Value::as_str)
+    }
+
+    fn str(&self, name: &str) -> Result<&'a str> {
+        self.required(name, "a string", Value::as_str)
+    }
+
+    /// Returns the string member with the name given, with empty strings considered missing.
+    fn opt_string(&self, name: &str) -> Result<Option<String>> {
+        Ok(self
+            .opt_str(name)?
+            .filter(|s| !s.is_empty())
+            .map(ToOwned::to_owned))
+    }
+
+    fn opt_u32(&self, name: &str) -> Result<Option<u32>> {
+        self.optional(name, "an unsigned integer", |value| {
+            value.as_u64().and_then(|v| u32::try_from(v).ok())
+        })
+    }
+
+    fn u32(&self, name: &str) -> Result<u32> {
+        self.required(name, "an unsigned integer", |value| {
+            value.as_u64().and_then(|v| u32::try_from(v).ok())
+        })
+    }
+
+    fn opt_i32(&self, name: &str) -> Result<Option<i32>> {
+        self.optional(name, "an integer", |value| {
+            value.as_i64().and_then(|v| i32::try_from(v).ok())
+        })
+    }
+
+    fn i32(&self, name: &str) -> Result<i32> {
+        self.required(name, "an integer", |value| {
+            value.as_i64().and_then(|v| i32::try_from(v).ok())
+        })
+    }
+
+    fn opt_f32(&self, name: &str) -> Result<Option<f32>> {
+        self.optional(name, "a number", |value| value.as_f64().map(|v| v as f32))
+    }
+
+    fn opt_bool(&self, name: &str) -> Result<Option<bool>> {
+        self.optional(name, "a boolean", Value::as_bool)
+    }
+
+    /// Returns the color member with the name given, with empty strings considered missing.
+    fn opt_color(&self, name: &str) -> Result<Option<Color>> {
+        match self.opt_str(name)? {
+            None | Some("") => Ok(None),
+            Some(color) => color.parse().map(Some).map_err(|()| {
+                Error::MalformedAttributes(format!(
+                    "the `{}` member of {} is not a valid color",
+                    name, self.kind
+                ))
+            }),
+        }
+    }
+
+    /// Returns the array member with the name given, or an empty slice if it is missing.
+    fn array(&self, name: &str) -> Result<&'a [Value]> {
+        self.optional(name, "an array", |value| {
+            value.as_array().map(Vec::as_slice)
+        })
+        .map(Option::unwrap_or_default)
+    }
+
+    fn opt_object(&self, name: &str, kind: &'static str) -> Result<Option<JsonObject<'a>>> {
+        self.get(name)
+            .map(|value| Self::new(value, kind))
+            .transpose()
+    }
+
+    /// Parses the properties of the object, resolving the ones whose type is among
+    /// [`LoaderOptions::enum_types`], and joining the paths of `file` properties with the
+    /// directory of the file they are declared in.
+    fn properties(
+        &self,
+        options: &LoaderOptions,
+        directory: &Path,
+        interner: &mut Interner,
+    ) -> Result<Properties> {
+        let mut properties = Properties::new();
+        for property in self.array("properties")? {
+            let property = JsonObject::new(property, "a property")?;
+            let name = interner.intern(property.str("name")?);
+            let property_type = property.opt_str("propertytype")?;
+            let value = parse_property_value(
+                &name,
+                property.opt_str("type")?.unwrap_or("string"),
+                property_type,
+                property.get("value"),
+                interner,
+            )?
+            .with_directory(directory);
+            let value = match property_type.and_then(|ty| options.enum_type(ty)) {
+                Some(enum_type) => enum_type.resolve(&value).unwrap_or(value),
+                None => value,
+            };
+            properties.insert(name, value);
+        }
+        Ok(properties)
+    }
+}
+
+fn parse_property_value(
+    name: &str,
+    property_type: &str,
+    class: Option<&str>,
+    value: Option<&Value>,
+    interner: &mut Interner,
+) -> Result<PropertyValue> {
+    match (property_type, value) {
+        ("class", value) => {
+            // Only the members that were set are saved, and their types aren't.
+            let properties = match value {
+                Some(Value::Object(members)) => members
+                    .iter()
+                    .map(|(name, value)| {
+                        Ok((
+                            interner.intern(name),
+                            infer_property_value(name, value, interner)?,
+                        ))
+                    })
+                    .collect::<Result<_>>()?,
+                Some(value) => {
+                    return Err(Error::InvalidPropertyValue {
+                        description: format!("expected a class value, found {}", value),
+                    })
+                }
+                None => Properties::new(),
+            };
+            Ok(PropertyValue::ClassValue {
+                property_type: class.unwrap_or_default().to_owned(),
+                properties,
+            })
+        }
+        (_, None) => Err(Error::MalformedAttributes(format!(
+            "property '{}' is missing a value",
+            name
+        ))),
+        (_, Some(Value::String(value))) => {
+            PropertyValue::new(property_type.to_owned(), value.clone())
+        }
+        (_, Some(Value::Number(value))) => {
+            PropertyValue::new(property_type.to_owned(), value.to_string())
+        }
+        (_, Some(Value::Bool(value))) => {
+            PropertyValue::new(property_type.to_owned(), value.to_string())
+        }
+        (_, Some(value)) => Err(Error::InvalidPropertyValue {
+            description: format!("expected a {} value, found {}", property_type, value),
+        }),
+    }
+}
+
+/// Infers the type of a class member from its JSON value, since the JSON formats don't store it.
+fn infer_property_value(
+    name: &str,
+    value: &Value,
+    interner: &mut Interner,
+) -> Result<PropertyValue> {
+    match value {
+        Value::Bool(value) => Ok(PropertyValue::BoolValue(*value)),
+        Value::Number(number) => Ok(match number.as_i64().map(i32::try_from) {
+            Some(Ok(value)) => PropertyValue::IntValue(value),
+            _ => PropertyValue::FloatValue(number.as_f64().unwrap_or_default() as f32),
+        }),
+        Value::String(value) => Ok(PropertyValue::StringValue(value.clone())),
+        Value::Object(_) => parse_property_value(name, "class", None, Some(value), interner),
+        _ => Err(Error::InvalidPropertyValue {
+            description: format!("class members can't be {}", value),
+        }),
+    }
+}
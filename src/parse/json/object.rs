@@ -0,0 +1,255 @@
+use std::{ffi::OsStr, path::Path, sync::Arc};
+
+use serde_json::Value;
+
+use super::{read_json, JsonObject, JsonParser};
+use crate::{
+    parse::xml::ReadFrom,
+    properties::merge_properties,
+    util::{debug_event, format_extension},
+    Color, Error, HorizontalAlignment, LoaderOptions, MapTilesetGid, ObjectData, ObjectLayerData,
+    ObjectShape, ObjectTileData, OverriddenFields, ResourceCache, Result, Template, TemplateOrigin,
+    Tileset, VerticalAlignment,
+};
+
+/// Parses a template in the JSON format (TJ).
+pub async fn parse_template(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    options: &LoaderOptions,
+) -> Result<Arc<Template>> {
+    JsonParser::new(read_from, cache, options)
+        .parse_template(path)
+        .await
+}
+
+impl<RF: ReadFrom, C: ResourceCache> JsonParser<'_, RF, C> {
+    /// If it is known that there are no objects with tile images in it (i.e. collision data)
+    /// then we can pass in [`None`] as the tilesets
+    pub(super) async fn parse_object_layer(
+        &mut self,
+        layer: JsonObject<'_>,
+        tilesets: Option<&[MapTilesetGid]>,
+        // path_relative_to is a directory to which all other files are relative to
+        path_relative_to: &Path,
+    ) -> Result<ObjectLayerData> {
+        let mut objects = Vec::new();
+        for object in layer.array("objects")? {
+            objects.push(
+                self.parse_object(object, tilesets, None, path_relative_to)
+                    .await?,
+            );
+        }
+        Ok(ObjectLayerData::with_objects(
+            objects,
+            layer.opt_color("color")?,
+        ))
+    }
+
+    async fn parse_object(
+        &mut self,
+        value: &Value,
+        tilesets: Option<&[MapTilesetGid]>,
+        for_tileset: Option<Arc<Tileset>>,
+        // Base path is a directory to which all other files are relative to
+        base_path: &Path,
+    ) -> Result<ObjectData> {
+        let object = JsonObject::new(value, "an object")?;
+        let x = object.opt_f32("x")?.unwrap_or(0.);
+        let y = object.opt_f32("y")?.unwrap_or(0.);
+        let width = object.opt_f32("width")?.unwrap_or(0.);
+        let height = object.opt_f32("height")?.unwrap_or(0.);
+        let mut tile = object
+            .opt_u32("gid")?
+            .and_then(|bits| ObjectTileData::from_bits(bits, tilesets?, for_tileset));
+        let mut visible = object.opt_bool("visible")?;
+        let mut rotation = object.opt_f32("rotation")?;
+        let mut name = object.opt_str("name")?.map(ToOwned::to_owned);
+        let mut user_type = object
+            .opt_str("type")?
+            .or(object.opt_str("class")?)
+            .map(|t| self.interner.intern(t));
+
+        let mut overridden = OverriddenFields {
+            name: name.is_some(),
+            user_type: user_type.is_some(),
+            visible: visible.is_some(),
+            rotation: rotation.is_some(),
+            tile: tile.is_some(),
+            ..OverriddenFields::default()
+        };
+        // If the template member is there, we need to go fetch the template file
+        let template = match object.opt_str("template")? {
+            Some(template_path) => {
+                // add indirection because the returned async state machine is a recursive data
+                // structure (`load_template` eventually calls this function)
+                let template = Box::pin(self.load_template(&base_path.join(template_path))).await?;
+
+                // The template sets the default values for the object
+                let obj = &template.object;
+                visible.get_or_insert(obj.visible);
+                rotation.get_or_insert(obj.rotation);
+                name.get_or_insert_with(|| obj.name.clone());
+                user_type.get_or_insert_with(|| obj.user_type.clone());
+                if let Some(templ_tile) = &obj.tile {
+                    tile.get_or_insert_with(|| templ_tile.clone());
+                }
+                Some(template)
+            }
+            None => None,
+        };
+
+        let mut shape = if object.opt_bool("ellipse")? == Some(true) {
+            Some(ObjectShape::Ellipse { width, height })
+        } else if object.opt_bool("point")? == Some(true) {
+            Some(ObjectShape::Point(x, y))
+        } else if object.get("polygon").is_some() {
+            Some(ObjectShape::Polygon {
+                points: parse_points(object, "polygon")?,
+            })
+        } else if object.get("polyline").is_some() {
+            Some(ObjectShape::Polyline {
+                points: parse_points(object, "polyline")?,
+            })
+        } else if let Some(text) = object.opt_object("text", "a text object")? {
+            Some(parse_text(text, width, height)?)
+        } else {
+            None
+        };
+        let mut properties = object.properties(self.options, base_path, &mut self.interner)?;
+
+        // Possibly copy properties from the template into the object
+        // Any that already exist in the object's map don't get copied over
+        if let Some(templ) = &template {
+            overridden.shape = shape.is_some();
+            overridden.properties = properties.keys().cloned().collect();
+            shape.get_or_insert(templ.object.shape.clone());
+
+            merge_properties(&mut properties, &templ.object.properties);
+        }
+
+        Ok(ObjectData {
+            id: object.opt_u32("id")?.unwrap_or(0),
+            tile,
+            name: name.unwrap_or_default(),
+            user_type: user_type.unwrap_or_else(|| self.interner.intern("")),
+            x,
+            y,
+            rotation: rotation.unwrap_or(0.),
+            visible: visible.unwrap_or(true),
+            shape: shape.unwrap_or(ObjectShape::Rect { width, height }),
+            properties,
+            origin: TemplateOrigin::new(template, overridden),
+        })
+    }
+
+    /// Loads the template file at the path given, or fetches it from the cache. TX files are
+    /// parsed as XML, and any other file as JSON.
+    async fn load_template(&mut self, template_path: &Path) -> Result<Arc<Template>> {
+        if let Some(templ) = self.cache.get_template(template_path) {
+            debug_event!(path = %template_path.display(), "template cache hit");
+            return Ok(templ);
+        }
+        debug_event!(path = %template_path.display(), "template cache miss");
+        let template = if format_extension(template_path) == Some(OsStr::new("tx")) {
+            Template::parse_template(template_path, self.read_from, self.cache, self.options)
+                .await?
+        } else {
+            self.parse_template(template_path).await?
+        };
+        Ok(self.cache.get_or_insert_template(template_path, template))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "parse_template", level = "debug", skip_all, fields(path = %path.display()))
+    )]
+    async fn parse_template(&mut self, path: &Path) -> Result<Arc<Template>> {
+        let value = read_json(path, self.read_from).await?;
+        let template = JsonObject::new(&value, "the template")?;
+
+        let mut tileset = None;
+        let mut tileset_gid = Vec::new();
+        if let Some(reference) = template.get("tileset") {
+            let map_tileset = self.parse_map_tileset(reference, path).await?;
+            tileset = Some(map_tileset.tileset.clone());
+            tileset_gid.push(map_tileset);
+        }
+
+        let object = template.get("object").ok_or(Error::TemplateHasNoObject)?;
+        let object = self
+            .parse_object(
+                object,
+                Some(&tileset_gid),
+                tileset.clone(),
+                path.parent().ok_or(Error::PathIsNotFile)?,
+            )
+            .await?;
+
+        Ok(Arc::new(Template { tileset, object }))
+    }
+}
+
+/// Parses the points of a polygon or polyline.
+fn parse_points(object: JsonObject, name: &str) -> Result<Vec<(f32, f32)>> {
+    object
+        .array(name)?
+        .iter()
+        .map(|point| {
+            let point = JsonObject::new(point, "a point")?;
+            Ok((
+                point.opt_f32("x")?.unwrap_or(0.),
+                point.opt_f32("y")?.unwrap_or(0.),
+            ))
+        })
+        .collect()
+}
+
+fn parse_text(text: JsonObject, width: f32, height: f32) -> Result<ObjectShape> {
+    let halign = match text.opt_str("halign")? {
+        None | Some("left") => HorizontalAlignment::Left,
+        Some("center") => HorizontalAlignment::Center,
+        Some("right") => HorizontalAlignment::Right,
+        Some("justify") => HorizontalAlignment::Justify,
+        Some(_) => return Err(Error::MalformedAttributes(
+            "`halign` property did not contain a valid value of 'left', 'center', 'right' or 'justify'"
+                .to_string(),
+        )),
+    };
+    let valign =
+        match text.opt_str("valign")? {
+            None | Some("top") => VerticalAlignment::Top,
+            Some("center") => VerticalAlignment::Center,
+            Some("bottom") => VerticalAlignment::Bottom,
+            Some(_) => return Err(Error::MalformedAttributes(
+                "`valign` property did not contain a valid value of 'top', 'center' or 'bottom'"
+                    .to_string(),
+            )),
+        };
+
+    Ok(ObjectShape::Text {
+        font_family: text
+            .opt_str("fontfamily")?
+            .unwrap_or("sans-serif")
+            .to_owned(),
+        pixel_size: text.opt_u32("pixelsize")?.unwrap_or(16) as usize,
+        wrap: text.opt_bool("wrap")?.unwrap_or(false),
+        color: text.opt_color("color")?.unwrap_or(Color {
+            red: 0,
+            green: 0,
+            blue: 0,
+            alpha: 255,
+        }),
+        bold: text.opt_bool("bold")?.unwrap_or(false),
+        italic: text.opt_bool("italic")?.unwrap_or(false),
+        underline: text.opt_bool("underline")?.unwrap_or(false),
+        strikeout: text.opt_bool("strikeout")?.unwrap_or(false),
+        kerning: text.opt_bool("kerning")?.unwrap_or(true),
+        halign,
+        valign,
+        text: text.opt_str("text")?.unwrap_or_default().to_owned(),
+        width,
+        height,
+    })
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use super::{parse_property_value, read_json, JsonObject};
+use crate::{
+    intern::Interner, parse::xml::ReadFrom, ClassType, EnumStorageType, EnumType, Error, Project,
+    Properties, PropertyType, Result,
+};
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse_project", level = "debug", skip_all, fields(path = %path.display()))
+)]
+pub async fn parse_project(path: &Path, read_from: &mut impl ReadFrom) -> Result<Project> {
+    let value = read_json(path, read_from).await?;
+    let project = JsonObject::new(&value, "the project")?;
+    let project_dir = path.parent().ok_or(Error::PathIsNotFile)?;
+
+    let folders = project
+        .array("folders")?
+        .iter()
+        .map(|folder| {
+            folder
+                .as_str()
+                .map(|folder| project_dir.join(folder))
+                .ok_or_else(|| {
+                    Error::MalformedAttributes("a project folder is not a string".to_owned())
+                })
+        })
+        .collect::<Result<_>>()?;
+
+    let property_types = project
+        .array("propertyTypes")?
+        .iter()
+        .map(|property_type| JsonObject::new(property_type, "a property type"))
+        .collect::<Result<Vec<_>>>()?;
+    // Enums are parsed first, since class members can be of any enum of the project
+    let enum_types = property_types
+        .iter()
+        .filter(|property_type| matches!(property_type.str("type"), Ok("enum")))
+        .map(|&property_type| parse_enum_type(property_type))
+        .collect::<Result<Vec<_>>>()?;
+    let property_types = property_types
+        .into_iter()
+        .map(|property_type| parse_property_type(property_type, &enum_types))
+        .collect::<Result<_>>()?;
+
+    Ok(Project {
+        folders,
+        property_types,
+    })
+}
+
+fn parse_enum_type(property_type: JsonObject) -> Result<EnumType> {
+    Ok(EnumType {
+        id: property_type.u32("id")?,
+        name: property_type.str("name")?.to_owned(),
+        storage_type: match property_type.opt_str("storageType")? {
+            None | Some("string") => EnumStorageType::String,
+            Some("int") => EnumStorageType::Int,
+            Some(other) => {
+                return Err(Error::MalformedAttributes(format!(
+                    "unknown enum storage type `{}`",
+                    other
+                )))
+            }
+        },
+        values: property_type
+            .array("values")?
+            .iter()
+            .map(|value| {
+                value.as_str().map(ToOwned::to_owned).ok_or_else(|| {
+                    Error::MalformedAttributes("an enum value is not a string".to_owned())
+                })
+            })
+            .collect::<Result<_>>()?,
+        values_as_flags: property_type.opt_bool("valuesAsFlags")?.unwrap_or(false),
+    })
+}
+
+fn parse_property_type(property_type: JsonObject, enum_types: &[EnumType]) -> Result<PropertyType> {
+    let id = property_type.u32("id")?;
+    let name = property_type.str("name")?.to_owned();
+    match property_type.str("type")? {
+        "enum" => Ok(PropertyType::Enum(parse_enum_type(property_type)?)),
+        "class" => {
+            let mut members = Properties::new();
+            let mut interner = Interner::default();
+            for member in property_type.array("members")? {
+                let member = JsonObject::new(member, "a class member")?;
+                let name = interner.intern(member.str("name")?);
+                let member_type = member.opt_str("propertyType")?;
+                let value = parse_property_value(
+                    &name,
+                    member.opt_str("type")?.unwrap_or("string"),
+                    member_type,
+                    member.get("value"),
+                    &mut interner,
+                )?;
+                let value = match member_type
+                    .and_then(|ty| enum_types.iter().find(|enum_type| enum_type.name == ty))
+                {
+                    Some(enum_type) => enum_type.resolve(&value).unwrap_or(value),
+                    None => value,
+                };
+                members.insert(name, value);
+            }
+            Ok(PropertyType::Class(ClassType {
+                id,
+                name,
+                color: property_type.opt_color("color")?,
+                use_as: property_type
+                    .array("useAs")?
+                    .iter()
+                    .filter_map(|use_as| use_as.as_str().map(ToOwned::to_owned))
+                    .collect(),
+                members,
+            }))
+        }
+        other => Err(Error::MalformedAttributes(format!(
+            "unknown property type kind `{}`",
+            other
+        ))),
+    }
+}
@@ -0,0 +1,258 @@
+use std::{collections::HashMap, convert::TryFrom, ffi::OsStr, path::Path, sync::Arc};
+
+use serde_json::Value;
+
+use super::{read_json, JsonObject, JsonParser};
+use crate::{
+    intern::Interner,
+    parse::xml::ReadFrom,
+    util::{debug_event, format_extension},
+    Error, FillMode, Frame, InvalidTilesetError, LoaderOptions, ObjectAlignment, ResourceCache,
+    Result, TileData, TileId, TileRenderSize, Tileset, TilesetTransformations, WangColor, WangId,
+    WangSet, WangSetType, WangTile,
+};
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse_tileset", level = "debug", skip_all, fields(path = %path.display()))
+)]
+pub async fn parse_tileset(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    options: &LoaderOptions,
+) -> Result<Tileset> {
+    let value = read_json(path, read_from).await?;
+    let tileset = JsonObject::new(&value, "the tileset")?;
+    JsonParser::new(read_from, cache, options)
+        .parse_tileset(tileset, path)
+        .await
+}
+
+impl<RF: ReadFrom, C: ResourceCache> JsonParser<'_, RF, C> {
+    /// Loads the tileset file at the path given, or fetches it from the cache. TSX files are
+    /// parsed as XML, and any other file as JSON.
+    pub(super) async fn load_tileset(&mut self, tileset_path: &Path) -> Result<Arc<Tileset>> {
+        if let Some(ts) = self.cache.get_tileset(tileset_path) {
+            debug_event!(path = %tileset_path.display(), "tileset cache hit");
+            return Ok(ts);
+        }
+        debug_event!(path = %tileset_path.display(), "tileset cache miss");
+        let tileset = if format_extension(tileset_path) == Some(OsStr::new("tsx")) {
+            crate::parse::xml::parse_tileset(tileset_path, self.read_from, self.cache, self.options)
+                .await?
+        } else {
+            let value = read_json(tileset_path, self.read_from).await?;
+            let tileset = JsonObject::new(&value, "the tileset")?;
+            self.parse_tileset(tileset, tileset_path).await?
+        };
+        Ok(self
+            .cache
+            .get_or_insert_tileset(tileset_path, Arc::new(tileset)))
+    }
+
+    /// Parses a tileset, embedded or not. `path` is the file it is contained in.
+    pub(super) async fn parse_tileset(
+        &mut self,
+        tileset: JsonObject<'_>,
+        path: &Path,
+    ) -> Result<Tileset> {
+        let root_path = path.parent().ok_or(Error::PathIsNotFile)?;
+        let tile_width = tileset.u32("tilewidth")?;
+        let tile_height = tileset.u32("tileheight")?;
+        let tilecount = tileset.u32("tilecount")?;
+        let image = self.parse_image(tileset, root_path)?;
+        let (offset_x, offset_y) = match tileset.opt_object("tileoffset", "a tile offset")? {
+            Some(offset) => (
+                offset.opt_i32("x")?.unwrap_or(0),
+                offset.opt_i32("y")?.unwrap_or(0),
+            ),
+            None => (0, 0),
+        };
+
+        let mut tiles = HashMap::with_capacity(tilecount as usize);
+        for tile in tileset.array("tiles")? {
+            let (id, tile) = self.parse_tile(tile, root_path).await?;
+            tiles.insert(id, tile);
+        }
+        let wang_sets = tileset
+            .array("wangsets")?
+            .iter()
+            .map(|set| parse_wang_set(set, self.options, root_path, &mut self.interner))
+            .collect::<Result<_>>()?;
+        let transformations =
+            match tileset.opt_object("transformations", "tileset transformations")? {
+                Some(transformations) => TilesetTransformations {
+                    hflip: transformations.opt_bool("hflip")?.unwrap_or(false),
+                    vflip: transformations.opt_bool("vflip")?.unwrap_or(false),
+                    rotate: transformations.opt_bool("rotate")?.unwrap_or(false),
+                    prefer_untransformed: transformations
+                        .opt_bool("preferuntransformed")?
+                        .unwrap_or(false),
+                },
+                None => TilesetTransformations::default(),
+            };
+
+        let tile_render_size = tileset
+            .opt_str("tilerendersize")?
+            .map(str::parse::<TileRenderSize>)
+            .transpose()
+            .map_err(|err| Error::MalformedAttributes(err.to_string()))?
+            .unwrap_or_default();
+        let fill_mode = tileset
+            .opt_str("fillmode")?
+            .map(str::parse::<FillMode>)
+            .transpose()
+            .map_err(|err| Error::MalformedAttributes(err.to_string()))?
+            .unwrap_or_default();
+        let object_alignment = tileset
+            .opt_str("objectalignment")?
+            .map(str::parse::<ObjectAlignment>)
+            .transpose()
+            .map_err(|err| Error::MalformedAttributes(err.to_string()))?
+            .unwrap_or_default();
+
+        // A tileset is considered an image collection tileset if there is no image attribute (because its tiles do).
+        if image.is_some() {
+            if tile_width == 0 || tile_height == 0 {
+                return Err(Error::InvalidTileset(
+                    InvalidTilesetError::InvalidTileDimensions,
+                ));
+            }
+
+            for tile_id in 0..tilecount {
+                tiles.entry(tile_id).or_default();
+            }
+        }
+
+        let margin = tileset.opt_u32("margin")?.unwrap_or(0);
+        let spacing = tileset.opt_u32("spacing")?.unwrap_or(0);
+        let columns = match tileset.opt_u32("columns")? {
+            Some(columns) => columns,
+            None => Tileset::calculate_columns(&image, tile_width, margin, spacing)?,
+        };
+
+        Ok(Tileset {
+            name: tileset.opt_str("name")?.unwrap_or_default().to_owned(),
+            user_type: tileset.opt_str("class")?.map(|t| self.interner.intern(t)),
+            tile_width,
+            tile_height,
+            spacing,
+            margin,
+            columns,
+            offset_x,
+            offset_y,
+            tilecount,
+            image,
+            tiles,
+            wang_sets,
+            transformations,
+            tile_render_size,
+            fill_mode,
+            object_alignment,
+            properties: tileset.properties(self.options, root_path, &mut self.interner)?,
+        })
+    }
+
+    async fn parse_tile(&mut self, value: &Value, root_path: &Path) -> Result<(TileId, TileData)> {
+        let tile = JsonObject::new(value, "a tile")?;
+        // Tile objects are not allowed within tile object groups, so no tilesets are needed
+        let collision = match tile.opt_object("objectgroup", "a tile collision layer")? {
+            Some(layer) => Some(self.parse_object_layer(layer, None, root_path).await?),
+            None => None,
+        };
+        let animation = match tile.get("animation") {
+            Some(_) => Some(
+                tile.array("animation")?
+                    .iter()
+                    .map(|frame| {
+                        let frame = JsonObject::new(frame, "an animation frame")?;
+                        Ok(Frame {
+                            tile_id: frame.u32("tileid")?,
+                            duration: frame.u32("duration")?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            None => None,
+        };
+
+        Ok((
+            tile.u32("id")?,
+            TileData {
+                image: self.parse_image(tile, root_path)?,
+                properties: tile.properties(self.options, root_path, &mut self.interner)?,
+                collision,
+                animation,
+                user_type: tile
+                    .opt_str("type")?
+                    .or(tile.opt_str("class")?)
+                    .map(|t| self.interner.intern(t)),
+                probability: tile.opt_f32("probability")?.unwrap_or(1.0),
+            },
+        ))
+    }
+}
+
+fn parse_wang_set(
+    value: &Value,
+    options: &LoaderOptions,
+    root_path: &Path,
+    interner: &mut Interner,
+) -> Result<WangSet> {
+    let set = JsonObject::new(value, "a Wang set")?;
+    let wang_set_type = match set.opt_str("type")? {
+        Some("corner") => WangSetType::Corner,
+        Some("edge") => WangSetType::Edge,
+        _ => WangSetType::default(),
+    };
+
+    let mut wang_colors = Vec::new();
+    for color in set.array("colors")? {
+        let color = JsonObject::new(color, "a Wang color")?;
+        wang_colors.push(WangColor {
+            name: color.opt_str("name")?.unwrap_or_default().to_owned(),
+            color: color.opt_color("color")?.ok_or_else(|| {
+                Error::MalformedAttributes("a Wang color has no `color` member".to_owned())
+            })?,
+            tile: tile_id(color)?,
+            probability: color.opt_f32("probability")?.unwrap_or(1.0),
+            properties: color.properties(options, root_path, interner)?,
+        });
+    }
+
+    let mut wang_tiles = HashMap::new();
+    for tile in set.array("wangtiles")? {
+        let tile = JsonObject::new(tile, "a Wang tile")?;
+        let wang_id = tile.required("wangid", "an array of 8 color indices", |value| {
+            let indices = value
+                .as_array()?
+                .iter()
+                .map(|index| index.as_u64().and_then(|index| u8::try_from(index).ok()))
+                .collect::<Option<Vec<_>>>()?;
+            <[u8; 8]>::try_from(indices).ok()
+        })?;
+        wang_tiles.insert(
+            tile.u32("tileid")?,
+            WangTile {
+                wang_id: WangId(wang_id),
+            },
+        );
+    }
+
+    Ok(WangSet {
+        name: set.opt_str("name")?.unwrap_or_default().to_owned(),
+        wang_set_type,
+        tile: tile_id(set)?,
+        wang_colors,
+        wang_tiles,
+        properties: set.properties(options, root_path, interner)?,
+    })
+}
+
+/// Parses the `tile` member of a Wang set or color, which is -1 when it has no tile.
+fn tile_id(object: JsonObject) -> Result<Option<TileId>> {
+    Ok(object
+        .opt_i32("tile")?
+        .and_then(|tile| TileId::try_from(tile).ok()))
+}
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use regex::Regex;
+
+use super::{read_json, JsonObject};
+use crate::{parse::xml::ReadFrom, Error, Result, World, WorldMap, WorldPattern};
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse_world", level = "debug", skip_all, fields(path = %path.display()))
+)]
+pub async fn parse_world(path: &Path, read_from: &mut impl ReadFrom) -> Result<World> {
+    let value = read_json(path, read_from).await?;
+    let world = JsonObject::new(&value, "the world")?;
+    let world_dir = path.parent().ok_or(Error::PathIsNotFile)?;
+
+    let maps = world
+        .array("maps")?
+        .iter()
+        .map(|map| {
+            let map = JsonObject::new(map, "a world map")?;
+            Ok(WorldMap {
+                source: world_dir.join(map.str("fileName")?),
+                x: map.opt_i32("x")?.unwrap_or(0),
+                y: map.opt_i32("y")?.unwrap_or(0),
+                width: map.opt_u32("width")?.unwrap_or(0),
+                height: map.opt_u32("height")?.unwrap_or(0),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    let patterns = world
+        .array("patterns")?
+        .iter()
+        .map(|pattern| {
+            let pattern = JsonObject::new(pattern, "a world pattern")?;
+            let regexp = Regex::new(pattern.str("regexp")?).map_err(|err| {
+                Error::MalformedAttributes(format!("invalid world pattern: {}", err))
+            })?;
+            let multiplier_x = pattern.opt_i32("multiplierX")?.unwrap_or(1);
+            let multiplier_y = pattern.opt_i32("multiplierY")?.unwrap_or(1);
+            Ok(WorldPattern {
+                regexp,
+                multiplier_x,
+                multiplier_y,
+                offset_x: pattern.opt_i32("offsetX")?.unwrap_or(0),
+                offset_y: pattern.opt_i32("offsetY")?.unwrap_or(0),
+                // Maps are as big as the distance between them unless specified otherwise
+                map_width: match pattern.opt_u32("mapWidth")? {
+                    Some(width) => width,
+                    None => multiplier_x.unsigned_abs(),
+                },
+                map_height: match pattern.opt_u32("mapHeight")? {
+                    Some(height) => height,
+                    None => multiplier_y.unsigned_abs(),
+                },
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(World {
+        maps,
+        patterns,
+        only_show_adjacent_maps: world.opt_bool("onlyShowAdjacentMaps")?.unwrap_or(false),
+    })
+}
@@ -4,12 +4,17 @@ use itertools::Itertools;
 use quick_xml::events::Event;
 
 use super::{Parser, ReadFrom, Reader};
-use crate::{Error, Map, ResourceCache, Result};
+use crate::{Error, LoaderOptions, Map, ResourceCache, Result};
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse_map", level = "debug", skip_all, fields(path = %path.display()))
+)]
 pub async fn parse_map(
     path: &Path,
     read_from: &mut impl ReadFrom,
     cache: &mut impl ResourceCache,
+    options: &LoaderOptions,
 ) -> Result<Map> {
     let mut reader =
         read_from
@@ -21,6 +26,7 @@ pub async fn parse_map(
             })?;
     let mut buffer = Vec::new();
     loop {
+        let position = reader.buffer_position();
         match reader
             .read_event_into(&mut buffer)
             .await
@@ -32,6 +38,11 @@ pub async fn parse_map(
                     .try_collect()
                     .map_err(|err| Error::XmlDecodingError(err.into()))?;
                 let mut parser = Parser::with_reader(reader);
+                parser.options = options.clone();
+                parser.directory = path.parent().map(Path::to_owned).unwrap_or_default();
+                parser.path = path.to_owned();
+                parser.element_position = position;
+                parser.check_element("", &start)?;
                 return Map::parse_xml(&mut parser, attributes, path, read_from, cache).await;
             }
             Event::Eof => {
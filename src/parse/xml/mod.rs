@@ -1,17 +1,29 @@
 mod map;
 use std::io::BufRead;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub(crate) use map::*;
+mod scan;
+mod stream;
+mod strict;
 mod tileset;
+mod transcode;
+use futures::future::join_all;
 pub(crate) use quick_xml::events::Event;
 pub(crate) use quick_xml::Reader as RawReader;
 pub(crate) use quick_xml::Result as ReadResult;
+pub(crate) use scan::*;
+pub(crate) use stream::*;
+pub(crate) use strict::*;
 pub(crate) use tileset::*;
 use tokio::io::AsyncBufRead;
+use transcode::Transcoded;
 
 use crate::AsyncResourceReader;
 use crate::ResourceReader;
+use crate::{
+    intern::Interner, Diagnostic, LoadProgress, LoaderOptions, ParseWarning, ParseWarningKind,
+};
 
 /// An abstraction of [`RawReader`] that comes in two flavors: [`SyncEventReader`] and
 /// [`AsyncEventReader`].
@@ -19,6 +31,12 @@ pub(crate) trait Reader {
     /// Delegates to either [`RawReader::read_event_into`] or [`RawReader::read_event_into_async`],
     /// depending on the implementor.
     async fn read_event_into<'b>(&mut self, buf: &'b mut Vec<u8>) -> ReadResult<Event<'b>>;
+
+    /// Reads the rest of the underlying resource as raw bytes, bypassing XML parsing.
+    async fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize>;
+
+    /// Delegates to [`RawReader::buffer_position`].
+    fn buffer_position(&self) -> u64;
 }
 
 /// A [`RawReader`] in 'sync' mode, i.e. that will delegate to [`RawReader::read_event_into`].
@@ -29,6 +47,14 @@ impl<R: BufRead> Reader for SyncReader<R> {
     async fn read_event_into<'b>(&mut self, buf: &'b mut Vec<u8>) -> ReadResult<Event<'b>> {
         self.0.read_event_into(buf)
     }
+
+    async fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        std::io::Read::read_to_end(self.0.get_mut(), buf)
+    }
+
+    fn buffer_position(&self) -> u64 {
+        self.0.buffer_position()
+    }
 }
 
 /// A [`RawReader`] in 'async' mode, i.e. that will delegate to [`RawReader::read_event_into_async`].
@@ -38,6 +64,14 @@ impl<R: AsyncBufRead + Unpin> Reader for AsyncReader<R> {
     async fn read_event_into<'b>(&mut self, buf: &'b mut Vec<u8>) -> ReadResult<Event<'b>> {
         self.0.read_event_into_async(buf).await
     }
+
+    async fn read_to_end(&mut self, buf: &mut Vec<u8>) -> std::io::Result<usize> {
+        tokio::io::AsyncReadExt::read_to_end(self.0.get_mut(), buf).await
+    }
+
+    fn buffer_position(&self) -> u64 {
+        self.0.buffer_position()
+    }
 }
 
 /// An abstraction of the [`ResourceReader`] and [`AsyncResourceReader`] traits that comes in two
@@ -46,33 +80,105 @@ pub(crate) trait ReadFrom {
     type Reader: Reader;
     type Error: std::error::Error + Send + Sync + 'static;
     async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, Self::Error>;
+
+    /// Reads several resources at once, concurrently if the underlying reader supports it.
+    async fn read_all(&mut self, paths: &[&Path]) -> Vec<Result<Self::Reader, Self::Error>>;
+
+    /// Wraps the contents of a resource that were already read into memory.
+    fn reader_from_bytes(bytes: Vec<u8>) -> Self::Reader;
 }
 
 /// Wraps a [`ResourceReader`].
 pub(crate) struct SyncReadFrom<'r, R>(pub(crate) &'r mut R);
 
 impl<R: ResourceReader> ReadFrom for SyncReadFrom<'_, R> {
-    type Reader = SyncReader<R::Resource>;
+    type Reader = SyncReader<Transcoded<R::Resource>>;
     type Error = R::Error;
 
     /// Returns on the first poll.
     async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, Self::Error> {
-        let resource = self.0.read_from(path)?;
+        let resource = Transcoded::new(self.0.read_from(path)?);
         Ok(SyncReader(RawReader::from_reader(resource)))
     }
+
+    /// Returns on the first poll.
+    async fn read_all(&mut self, paths: &[&Path]) -> Vec<Result<Self::Reader, Self::Error>> {
+        let mut readers = Vec::with_capacity(paths.len());
+        for path in paths {
+            readers.push(self.read_from(path).await);
+        }
+        readers
+    }
+
+    fn reader_from_bytes(bytes: Vec<u8>) -> Self::Reader {
+        SyncReader(RawReader::from_reader(Transcoded::from_bytes(bytes)))
+    }
 }
 
 /// Wraps an [`AsyncResourceReader`].
 pub(crate) struct AsyncReadFrom<'r, R>(pub(crate) &'r mut R);
 
 impl<R: AsyncResourceReader> ReadFrom for AsyncReadFrom<'_, R> {
-    type Reader = AsyncReader<R::Resource>;
+    type Reader = AsyncReader<Transcoded<R::Resource>>;
     type Error = R::Error;
 
     async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, Self::Error> {
-        let resource = self.0.read_from(path).await?;
+        let resource = Transcoded::new_async(self.0.read_from(path).await?).await;
         Ok(AsyncReader(RawReader::from_reader(resource)))
     }
+
+    async fn read_all(&mut self, paths: &[&Path]) -> Vec<Result<Self::Reader, Self::Error>> {
+        let resources = self.0.read_all(paths).await;
+        join_all(resources.into_iter().map(|resource| async {
+            let resource = Transcoded::new_async(resource?).await;
+            Ok(AsyncReader(RawReader::from_reader(resource)))
+        }))
+        .await
+    }
+
+    fn reader_from_bytes(bytes: Vec<u8>) -> Self::Reader {
+        AsyncReader(RawReader::from_reader(Transcoded::from_bytes(bytes)))
+    }
+}
+
+/// Wraps another [`ReadFrom`], but reads the resource at one path from memory, e.g. the root map of
+/// a loader which was handed its contents instead of a path to read them from.
+pub(crate) struct InMemoryReadFrom<'p, RF> {
+    path: &'p Path,
+    contents: Option<Vec<u8>>,
+    inner: RF,
+}
+
+impl<'p, RF> InMemoryReadFrom<'p, RF> {
+    pub(crate) fn new(path: &'p Path, contents: Vec<u8>, inner: RF) -> Self {
+        Self {
+            path,
+            contents: Some(contents),
+            inner,
+        }
+    }
+}
+
+impl<RF: ReadFrom> ReadFrom for InMemoryReadFrom<'_, RF> {
+    type Reader = RF::Reader;
+    type Error = RF::Error;
+
+    async fn read_from(&mut self, path: &Path) -> Result<Self::Reader, Self::Error> {
+        if path == self.path {
+            if let Some(contents) = self.contents.take() {
+                return Ok(RF::reader_from_bytes(contents));
+            }
+        }
+        self.inner.read_from(path).await
+    }
+
+    async fn read_all(&mut self, paths: &[&Path]) -> Vec<Result<Self::Reader, Self::Error>> {
+        self.inner.read_all(paths).await
+    }
+
+    fn reader_from_bytes(bytes: Vec<u8>) -> Self::Reader {
+        RF::reader_from_bytes(bytes)
+    }
 }
 
 /// A [`Reader`]-buffer pair.
@@ -80,6 +186,31 @@ pub(crate) struct Parser<R> {
     reader: R,
     pub(crate) buffer: Vec<u8>,
     pub(crate) last_event_was_empty: bool,
+    /// The options the file is being parsed with.
+    pub(crate) options: LoaderOptions,
+    /// The directory of the file being parsed, which the paths of `file` properties are relative
+    /// to.
+    pub(crate) directory: PathBuf,
+    /// The path of the file being parsed, which [warnings](ParseWarning) are reported for.
+    pub(crate) path: PathBuf,
+    /// The byte offset of the element that was opened last.
+    pub(crate) element_position: u64,
+    /// The amount of group layers currently being parsed, used to enforce
+    /// [`LoaderOptions::max_group_depth`].
+    pub(crate) group_depth: usize,
+    /// The amount of elements that were opened but not closed yet.
+    depth: usize,
+    /// The depth outside of the unknown element the parser is in, if any, so that the elements
+    /// inside of it aren't reported as unknown too.
+    unknown_depth: Option<usize>,
+    /// The problems recovered from so far, in [lenient mode](LoaderOptions::lenient).
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    /// The property names, classes and layer names parsed so far.
+    pub(crate) interner: Interner,
+    /// The tile data of the finite tile layers parsed so far, if it is to be decoded in parallel
+    /// once the map is parsed.
+    #[cfg(feature = "rayon")]
+    pub(crate) deferred_tiles: Option<Vec<Option<crate::layers::DeferredTiles>>>,
 }
 
 impl<R> Parser<R> {
@@ -89,14 +220,109 @@ impl<R> Parser<R> {
             reader,
             buffer: Vec::new(),
             last_event_was_empty: false,
+            options: LoaderOptions::default(),
+            directory: PathBuf::new(),
+            path: PathBuf::new(),
+            element_position: 0,
+            group_depth: 0,
+            depth: 0,
+            unknown_depth: None,
+            diagnostics: Vec::new(),
+            interner: Interner::default(),
+            #[cfg(feature = "rayon")]
+            deferred_tiles: None,
+        }
+    }
+
+    /// Fails with [`Error::LoadingCancelled`](crate::Error::LoadingCancelled) if the
+    /// [cancellation token](LoaderOptions::cancellation_token) of the parser was cancelled.
+    pub(crate) fn check_cancelled(&self) -> crate::Result<()> {
+        match &self.options.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(crate::Error::LoadingCancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// Passes a stage to the [progress handler](LoaderOptions::progress_handler) of the parser,
+    /// then checks whether loading was cancelled.
+    pub(crate) fn report(&self, progress: LoadProgress<'_>) -> crate::Result<()> {
+        if let Some(handler) = &self.options.progress_handler {
+            handler.handle(progress);
+        }
+        self.check_cancelled()
+    }
+
+    /// Passes a warning about the element that was opened last to the
+    /// [warning handler](LoaderOptions::warning_handler) of the parser.
+    pub(crate) fn warn(&self, kind: ParseWarningKind) {
+        if let Some(handler) = &self.options.warning_handler {
+            handler.handle(&ParseWarning {
+                path: self.path.clone(),
+                position: self.element_position,
+                kind,
+            });
+        }
+    }
+
+    /// Checks the element that was just opened inside of `parent` against the file format. In
+    /// [strict mode](LoaderOptions::strict), the elements and attributes it doesn't define are
+    /// errors, and in [lenient mode](LoaderOptions::lenient) they are reported as warnings.
+    pub(crate) fn check_element(
+        &mut self,
+        parent: &str,
+        element: &quick_xml::events::BytesStart,
+    ) -> crate::Result<()> {
+        if self.options.strict {
+            return check_element(parent, element);
+        }
+        if !self.options.lenient || self.options.warning_handler.is_none() {
+            return Ok(());
+        }
+        let depth = self.depth_outside_current_element();
+        match self.unknown_depth {
+            Some(unknown_depth) if depth > unknown_depth => return Ok(()),
+            _ => self.unknown_depth = None,
+        }
+        for unknown in unknown_parts(parent, element)? {
+            let kind = match unknown {
+                crate::Error::UnknownElement { name, parent } => {
+                    self.unknown_depth = Some(depth);
+                    ParseWarningKind::UnknownElement { name, parent }
+                }
+                crate::Error::UnknownAttribute { element, name } => {
+                    ParseWarningKind::UnknownAttribute { element, name }
+                }
+                _ => continue,
+            };
+            self.warn(kind);
+        }
+        Ok(())
+    }
+
+    /// Returns the element depth the parser will be at once the element that was just opened is
+    /// closed.
+    pub(crate) fn depth_outside_current_element(&self) -> usize {
+        if self.last_event_was_empty {
+            self.depth
+        } else {
+            self.depth.saturating_sub(1)
         }
     }
 }
 
 impl<R: Reader> Parser<R> {
     pub(crate) async fn read_event(&mut self) -> ReadResult<Event> {
+        let position = self.reader.buffer_position();
         let event = self.reader.read_event_into(&mut self.buffer).await?;
         self.last_event_was_empty = matches!(event, Event::Empty(_));
+        if let Event::Start(_) | Event::Empty(_) = event {
+            self.element_position = position;
+        }
+        match event {
+            Event::Start(_) => self.depth += 1,
+            Event::End(_) => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
         Ok(event)
     }
 
@@ -104,8 +330,34 @@ impl<R: Reader> Parser<R> {
         &mut self,
         buf: &'a mut Vec<u8>,
     ) -> ReadResult<Event<'a>> {
+        let position = self.reader.buffer_position();
         let event = self.reader.read_event_into(buf).await?;
         self.last_event_was_empty = matches!(event, Event::Empty(_));
+        if let Event::Start(_) | Event::Empty(_) = event {
+            self.element_position = position;
+        }
+        match event {
+            Event::Start(_) => self.depth += 1,
+            Event::End(_) => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
         Ok(event)
     }
+
+    /// Skips events until the parser is back at the element depth given, which is usually
+    /// obtained through [`Parser::depth_outside_current_element`] before parsing an element.
+    pub(crate) async fn skip_to_depth(&mut self, depth: usize) -> crate::Result<()> {
+        while self.depth > depth {
+            let event = self
+                .read_event()
+                .await
+                .map_err(crate::Error::XmlDecodingError)?;
+            if let Event::Eof = event {
+                return Err(crate::Error::PrematureEnd(
+                    "Document ended before we expected.".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use quick_xml::events::{BytesStart, Event};
+
+use super::{ReadFrom, Reader};
+use crate::{util::is_json_resource, Error, ResourcePathBuf, Result};
+
+/// Lists the tilesets, templates and images referenced by a TMX file and, recursively, by the
+/// tilesets and templates it references, in the order they are found and without duplicates.
+///
+/// Only the attributes of elements are looked at, so tile data isn't decoded and nothing is
+/// cached. Tilesets and templates in the JSON formats are listed, but not scanned.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "scan_dependencies", level = "debug", skip_all, fields(path = %path.display()))
+)]
+pub async fn scan_dependencies(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+) -> Result<Vec<ResourcePathBuf>> {
+    let mut dependencies = Vec::new();
+    scan_file(path, read_from, &mut dependencies).await?;
+
+    // Files are scanned breadth-first, which avoids recursing into async functions
+    let mut scanned = 0;
+    while scanned < dependencies.len() {
+        let (dependency, is_resource) = dependencies[scanned].clone();
+        scanned += 1;
+        if is_resource && !is_json_resource(&dependency) {
+            scan_file(&dependency, read_from, &mut dependencies).await?;
+        }
+    }
+    Ok(dependencies.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Collects the `source` and `template` attributes of every element in a file. Images are
+/// recorded as not being resources that could reference other files.
+async fn scan_file(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    dependencies: &mut Vec<(PathBuf, bool)>,
+) -> Result<()> {
+    let mut reader =
+        read_from
+            .read_from(path)
+            .await
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err: Box::new(err),
+            })?;
+    let dir = path.parent().ok_or(Error::PathIsNotFile)?;
+    let mut buffer = Vec::new();
+    loop {
+        match reader
+            .read_event_into(&mut buffer)
+            .await
+            .map_err(Error::XmlDecodingError)?
+        {
+            Event::Start(element) | Event::Empty(element) => {
+                scan_element(&element, dir, dependencies)?
+            }
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+        buffer.clear();
+    }
+}
+
+fn scan_element(
+    element: &BytesStart,
+    dir: &Path,
+    dependencies: &mut Vec<(PathBuf, bool)>,
+) -> Result<()> {
+    let is_image = element.local_name().into_inner() == b"image";
+    for attr in element.attributes() {
+        let attr = attr.map_err(|err| Error::XmlDecodingError(err.into()))?;
+        let is_resource = match attr.key.local_name().into_inner() {
+            b"source" => !is_image,
+            b"template" => true,
+            _ => continue,
+        };
+        let value =
+            std::str::from_utf8(&attr.value).map_err(|err| Error::XmlDecodingError(err.into()))?;
+        if value.is_empty() {
+            continue;
+        }
+        let dependency = dir.join(value);
+        if !dependencies.iter().any(|(path, _)| *path == dependency) {
+            dependencies.push((dependency, is_resource));
+        }
+    }
+    Ok(())
+}
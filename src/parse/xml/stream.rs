@@ -0,0 +1,318 @@
+use std::{path::Path, sync::Arc};
+
+use quick_xml::events::{attributes::Attribute, Event};
+
+use super::{Parser, ReadFrom, Reader};
+use crate::{
+    load_pending_tilesets, parse_map_tileset, parse_properties,
+    util::{get_attrs, parse_tag},
+    Error, InternalChunk, LayerTileData, LoaderOptions, MapStreamVisitor, MapTileset,
+    MapTilesetGid, ObjectData, Orientation, ResourceCache, Result, StreamedChunk, StreamedLayer,
+    StreamedLayerType, StreamedMap, Tileset,
+};
+
+/// Reads a TMX map element by element, handing its contents to the visitor given instead of
+/// building a [`Map`](crate::Map).
+///
+/// Tilesets and templates are loaded as usual, since they are needed to make sense of tiles and
+/// objects. Layers are tracked with an explicit stack, so that group layers don't need recursion.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "stream_map", level = "debug", skip_all, fields(path = %path.display()))
+)]
+pub async fn parse_map_with_visitor(
+    path: &Path,
+    read_from: &mut impl ReadFrom,
+    cache: &mut impl ResourceCache,
+    options: &LoaderOptions,
+    visitor: &mut impl MapStreamVisitor,
+) -> Result<()> {
+    let reader = read_from
+        .read_from(path)
+        .await
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    let map_dir = path.parent().ok_or(Error::PathIsNotFile)?;
+    let mut parser = Parser::with_reader(reader);
+    parser.options = options.clone();
+    parser.directory = map_dir.to_owned();
+    parser.path = path.to_owned();
+
+    let mut buffer = Vec::new();
+    let map = loop {
+        match parser
+            .read_event_into(&mut buffer)
+            .await
+            .map_err(Error::XmlDecodingError)?
+        {
+            Event::Start(start) if start.local_name().into_inner() == b"map" => {
+                let attrs = collect_attrs(start.attributes())?;
+                break parse_map_attrs(&attrs)?;
+            }
+            Event::Eof => {
+                return Err(Error::PrematureEnd(
+                    "Document ended before map was parsed".to_string(),
+                ))
+            }
+            _ => buffer.clear(),
+        }
+    };
+    visitor.visit_map(&map);
+
+    let mut tilesets = Vec::new();
+    let mut pending_tilesets = Vec::new();
+    let mut tilesets_visited = false;
+    // The layers that were opened but not closed yet, innermost last.
+    let mut open_layers: Vec<StreamedLayer> = Vec::new();
+    loop {
+        buffer.clear();
+        let (start, is_empty) = match parser
+            .read_event_into(&mut buffer)
+            .await
+            .map_err(Error::XmlDecodingError)?
+        {
+            Event::Start(start) => (start, false),
+            Event::Empty(start) => (start, true),
+            Event::End(end) => {
+                match end.local_name().into_inner() {
+                    b"map" => break,
+                    b"layer" | b"objectgroup" | b"imagelayer" | b"group" => {
+                        if let Some(layer) = open_layers.pop() {
+                            leave_layer(&mut parser, visitor, &layer);
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            Event::Eof => {
+                return Err(Error::PrematureEnd(
+                    "Document ended before we expected.".to_string(),
+                ))
+            }
+            _ => continue,
+        };
+        let attrs = collect_attrs(start.attributes())?;
+        let depth = parser.depth_outside_current_element();
+
+        if let Some(layer) = parse_layer_attrs(start.local_name().into_inner(), &attrs)? {
+            if !tilesets_visited {
+                load_pending_tilesets(
+                    &mut parser,
+                    &mut pending_tilesets,
+                    &mut tilesets,
+                    path,
+                    read_from,
+                    cache,
+                )
+                .await?;
+                visit_tilesets(visitor, &tilesets);
+                tilesets_visited = true;
+            }
+            if layer.layer_type == StreamedLayerType::Group {
+                let max_depth = parser.options.max_group_depth;
+                if parser.group_depth >= max_depth {
+                    return Err(Error::GroupNestingTooDeep { max_depth });
+                }
+                parser.group_depth += 1;
+            }
+            if visitor.visit_layer(&layer) && !is_empty {
+                open_layers.push(layer);
+            } else {
+                parser.skip_to_depth(depth).await?;
+                leave_layer(&mut parser, visitor, &layer);
+            }
+            continue;
+        }
+
+        let current_layer = open_layers.last();
+        match (start.local_name().into_inner(), current_layer) {
+            (b"tileset", None) => {
+                match parse_map_tileset(&mut parser, &attrs, path, read_from, cache).await? {
+                    MapTileset::Loaded(tileset) => tilesets.push(tileset),
+                    MapTileset::Pending {
+                        first_gid,
+                        tileset_path,
+                    } => {
+                        // Replaced once all the tilesets of the map are known
+                        pending_tilesets.push((tilesets.len(), tileset_path));
+                        tilesets.push(MapTilesetGid {
+                            first_gid,
+                            tileset: Arc::new(Tileset::new("", map.tile_width, map.tile_height)),
+                        });
+                    }
+                }
+            }
+            (b"properties", _) => {
+                let layer_id = current_layer.map(|layer| layer.id);
+                let properties = parse_properties(&mut parser).await?;
+                visitor.visit_properties(layer_id, &properties);
+            }
+            (b"data", Some(layer)) => {
+                if let StreamedLayerType::Tiles { width, height } = layer.layer_type {
+                    let layer_id = layer.id;
+                    let size = (!map.infinite).then_some((width, height));
+                    parse_tile_data(&mut parser, &attrs, layer_id, size, &tilesets, visitor)
+                        .await?;
+                }
+            }
+            (b"object", Some(layer)) if layer.layer_type == StreamedLayerType::Objects => {
+                let layer_id = layer.id;
+                let object = ObjectData::new(
+                    &mut parser,
+                    attrs,
+                    Some(&tilesets),
+                    None,
+                    map_dir,
+                    read_from,
+                    cache,
+                )
+                .await?;
+                visitor.visit_object(layer_id, &object);
+            }
+            _ => {}
+        }
+    }
+
+    // Maps without layers still have their tilesets visited
+    if !tilesets_visited {
+        load_pending_tilesets(
+            &mut parser,
+            &mut pending_tilesets,
+            &mut tilesets,
+            path,
+            read_from,
+            cache,
+        )
+        .await?;
+        visit_tilesets(visitor, &tilesets);
+    }
+    Ok(())
+}
+
+fn collect_attrs<'a>(
+    attributes: quick_xml::events::attributes::Attributes<'a>,
+) -> Result<Vec<Attribute<'a>>> {
+    attributes
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|err| Error::XmlDecodingError(err.into()))
+}
+
+fn parse_map_attrs(attrs: &[Attribute]) -> Result<StreamedMap> {
+    let (infinite, (orientation, width, height, tile_width, tile_height)) = get_attrs!(
+        for v in attrs {
+            Some("infinite") => infinite = v == "1",
+            "orientation" => orientation ?= v.parse::<Orientation>(),
+            "width" => width ?= v.parse::<u32>(),
+            "height" => height ?= v.parse::<u32>(),
+            "tilewidth" => tile_width ?= v.parse::<u32>(),
+            "tileheight" => tile_height ?= v.parse::<u32>(),
+        }
+        (infinite, (orientation, width, height, tile_width, tile_height))
+    );
+    Ok(StreamedMap {
+        orientation,
+        width,
+        height,
+        tile_width,
+        tile_height,
+        infinite: infinite.unwrap_or(false),
+    })
+}
+
+/// Returns the attributes of the layer the element given opens, or [`None`] if it isn't a layer.
+fn parse_layer_attrs(tag: &[u8], attrs: &[Attribute]) -> Result<Option<StreamedLayer>> {
+    let (id, layer_name, visible, width, height) = get_attrs!(
+        for v in attrs {
+            Some("id") => id ?= v.parse::<u32>(),
+            Some("name") => name = v.to_owned(),
+            Some("visible") => visible ?= v.parse::<i32>().map(|x| x == 1),
+            Some("width") => width ?= v.parse::<u32>(),
+            Some("height") => height ?= v.parse::<u32>(),
+        }
+        (id, name, visible, width, height)
+    );
+    let layer_type = match tag {
+        b"layer" => StreamedLayerType::Tiles {
+            width: width.unwrap_or(0),
+            height: height.unwrap_or(0),
+        },
+        b"objectgroup" => StreamedLayerType::Objects,
+        b"imagelayer" => StreamedLayerType::Image,
+        b"group" => StreamedLayerType::Group,
+        _ => return Ok(None),
+    };
+    Ok(Some(StreamedLayer {
+        id: id.unwrap_or(0),
+        name: layer_name.unwrap_or_default(),
+        visible: visible.unwrap_or(true),
+        layer_type,
+    }))
+}
+
+fn visit_tilesets(visitor: &mut impl MapStreamVisitor, tilesets: &[MapTilesetGid]) {
+    for (index, tileset) in tilesets.iter().enumerate() {
+        visitor.visit_tileset(index, &tileset.tileset);
+    }
+}
+
+fn leave_layer<R>(
+    parser: &mut Parser<R>,
+    visitor: &mut impl MapStreamVisitor,
+    layer: &StreamedLayer,
+) {
+    if layer.layer_type == StreamedLayerType::Group {
+        parser.group_depth -= 1;
+    }
+    visitor.leave_layer(layer);
+}
+
+/// Decodes the `<data>` element of a tile layer, handing its tiles to the visitor: As a single
+/// chunk of the size given for finite layers, or chunk by chunk for infinite ones.
+async fn parse_tile_data<R: Reader>(
+    parser: &mut Parser<R>,
+    attrs: &[Attribute<'_>],
+    layer_id: u32,
+    size: Option<(u32, u32)>,
+    tilesets: &[MapTilesetGid],
+    visitor: &mut impl MapStreamVisitor,
+) -> Result<()> {
+    let (e, c) = get_attrs!(
+        for v in attrs {
+            Some("encoding") => encoding = v,
+            Some("compression") => compression = v,
+        }
+        (encoding, compression)
+    );
+
+    let mut visit_chunk =
+        |x: i32, y: i32, width: u32, height: u32, tiles: &[Option<LayerTileData>]| {
+            if tiles.len() < width as usize * height as usize {
+                return Err(Error::InvalidTileFound);
+            }
+            visitor.visit_chunk(StreamedChunk {
+                layer_id,
+                x,
+                y,
+                width,
+                height,
+                tiles,
+            });
+            Ok(())
+        };
+
+    if let Some((width, height)) = size {
+        let tiles = crate::layers::parse_data_line(e, c, parser, tilesets).await?;
+        return visit_chunk(0, 0, width, height, &tiles);
+    }
+    let mut buffer = Vec::new();
+    parse_tag!(parser => &mut buffer, "data", {
+        "chunk" => for attrs {
+            let chunk = InternalChunk::new(parser, attrs, e, c, tilesets).await?;
+            visit_chunk(chunk.x, chunk.y, chunk.width, chunk.height, &chunk.tiles)
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,321 @@
+use quick_xml::events::BytesStart;
+
+use crate::{Error, Result};
+
+/// An element of the TMX, TSX and TX formats.
+struct ElementSpec {
+    name: &'static str,
+    /// The attributes the element can have.
+    attributes: &'static [&'static str],
+    /// The elements that can be found directly inside of it.
+    children: &'static [&'static str],
+}
+
+/// The attributes shared by every kind of layer.
+macro_rules! layer_attributes {
+    ($($extra:literal),*) => {
+        &[
+            "id", "name", "class", "x", "y", "width", "height", "opacity", "visible", "tintcolor",
+            "offsetx", "offsety", "parallaxx", "parallaxy", "locked", $($extra),*
+        ]
+    };
+}
+
+/// The elements defined by the [TMX format reference](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/).
+///
+/// Elements that only group other elements, such as `<wangsets>`, aren't descended into while
+/// parsing, so their children are also listed as children of the element containing them.
+const ELEMENTS: &[ElementSpec] = &[
+    ElementSpec {
+        // The document itself.
+        name: "",
+        attributes: &[],
+        children: &["map", "tileset", "template"],
+    },
+    ElementSpec {
+        name: "map",
+        attributes: &[
+            "version",
+            "tiledversion",
+            "class",
+            "orientation",
+            "renderorder",
+            "compressionlevel",
+            "width",
+            "height",
+            "tilewidth",
+            "tileheight",
+            "hexsidelength",
+            "staggeraxis",
+            "staggerindex",
+            "parallaxoriginx",
+            "parallaxoriginy",
+            "backgroundcolor",
+            "nextlayerid",
+            "nextobjectid",
+            "infinite",
+        ],
+        children: &[
+            "properties",
+            "editorsettings",
+            "tileset",
+            "layer",
+            "objectgroup",
+            "imagelayer",
+            "group",
+        ],
+    },
+    ElementSpec {
+        name: "editorsettings",
+        attributes: &[],
+        children: &["chunksize", "export"],
+    },
+    ElementSpec {
+        name: "chunksize",
+        attributes: &["width", "height"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "export",
+        attributes: &["target", "format"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "tileset",
+        attributes: &[
+            "firstgid",
+            "source",
+            "name",
+            "class",
+            "tilewidth",
+            "tileheight",
+            "spacing",
+            "margin",
+            "tilecount",
+            "columns",
+            "objectalignment",
+            "tilerendersize",
+            "fillmode",
+            "version",
+            "tiledversion",
+            "backgroundcolor",
+        ],
+        children: &[
+            "tileoffset",
+            "grid",
+            "image",
+            "transformations",
+            "properties",
+            "tile",
+            "wangsets",
+            "wangset",
+        ],
+    },
+    ElementSpec {
+        name: "tileoffset",
+        attributes: &["x", "y"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "grid",
+        attributes: &["orientation", "width", "height"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "image",
+        attributes: &["format", "source", "trans", "width", "height"],
+        children: &["data"],
+    },
+    ElementSpec {
+        name: "transformations",
+        attributes: &["hflip", "vflip", "rotate", "preferuntransformed"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "tile",
+        // `gid` is the attribute of the tiles of layer data encoded as XML.
+        attributes: &[
+            "id",
+            "type",
+            "class",
+            "probability",
+            "x",
+            "y",
+            "width",
+            "height",
+            "gid",
+        ],
+        children: &["properties", "image", "objectgroup", "animation"],
+    },
+    ElementSpec {
+        name: "animation",
+        attributes: &[],
+        children: &["frame"],
+    },
+    ElementSpec {
+        name: "frame",
+        attributes: &["tileid", "duration"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "wangsets",
+        attributes: &[],
+        children: &["wangset"],
+    },
+    ElementSpec {
+        name: "wangset",
+        attributes: &["name", "class", "type", "tile"],
+        children: &["properties", "wangcolor", "wangtile"],
+    },
+    ElementSpec {
+        name: "wangcolor",
+        attributes: &["name", "class", "color", "tile", "probability"],
+        children: &["properties"],
+    },
+    ElementSpec {
+        name: "wangtile",
+        attributes: &["tileid", "wangid"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "layer",
+        attributes: layer_attributes!(),
+        children: &["properties", "data"],
+    },
+    ElementSpec {
+        name: "data",
+        attributes: &["encoding", "compression"],
+        children: &["tile", "chunk"],
+    },
+    ElementSpec {
+        name: "chunk",
+        attributes: &["x", "y", "width", "height"],
+        children: &["tile"],
+    },
+    ElementSpec {
+        name: "objectgroup",
+        attributes: layer_attributes!("color", "draworder"),
+        children: &["properties", "object"],
+    },
+    ElementSpec {
+        name: "object",
+        attributes: &[
+            "id", "name", "type", "class", "x", "y", "width", "height", "rotation", "gid",
+            "visible", "template",
+        ],
+        children: &[
+            "properties",
+            "ellipse",
+            "point",
+            "polygon",
+            "polyline",
+            "text",
+        ],
+    },
+    ElementSpec {
+        name: "ellipse",
+        attributes: &[],
+        children: &[],
+    },
+    ElementSpec {
+        name: "point",
+        attributes: &[],
+        children: &[],
+    },
+    ElementSpec {
+        name: "polygon",
+        attributes: &["points"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "polyline",
+        attributes: &["points"],
+        children: &[],
+    },
+    ElementSpec {
+        name: "text",
+        attributes: &[
+            "fontfamily",
+            "pixelsize",
+            "wrap",
+            "color",
+            "bold",
+            "italic",
+            "underline",
+            "strikeout",
+            "kerning",
+            "halign",
+            "valign",
+        ],
+        children: &[],
+    },
+    ElementSpec {
+        name: "imagelayer",
+        attributes: layer_attributes!("repeatx", "repeaty"),
+        children: &["properties", "image"],
+    },
+    ElementSpec {
+        name: "group",
+        attributes: layer_attributes!(),
+        children: &["properties", "layer", "objectgroup", "imagelayer", "group"],
+    },
+    ElementSpec {
+        name: "properties",
+        attributes: &[],
+        children: &["property"],
+    },
+    ElementSpec {
+        name: "property",
+        attributes: &["name", "type", "propertytype", "value"],
+        children: &["properties"],
+    },
+    ElementSpec {
+        name: "template",
+        attributes: &[],
+        children: &["tileset", "object"],
+    },
+];
+
+/// Checks that `element` is one the file format defines inside of `parent`, which is empty for
+/// the root element, and that it only has the attributes it defines for it.
+///
+/// Used in [strict mode](crate::LoaderOptions::strict).
+pub(crate) fn check_element(parent: &str, element: &BytesStart) -> Result<()> {
+    match unknown_parts(parent, element)?.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Returns an [`Error::UnknownElement`] if `element` isn't one the file format defines inside of
+/// `parent`, or an [`Error::UnknownAttribute`] for each attribute it doesn't define for it.
+pub(crate) fn unknown_parts(parent: &str, element: &BytesStart) -> Result<Vec<Error>> {
+    let name = String::from_utf8_lossy(element.local_name().into_inner()).into_owned();
+    let spec = ELEMENTS.iter().find(|spec| spec.name == name);
+    let known_in_parent = ELEMENTS
+        .iter()
+        .find(|spec| spec.name == parent)
+        .is_some_and(|parent| parent.children.contains(&name.as_str()));
+    let spec = match spec {
+        Some(spec) if known_in_parent => spec,
+        _ => {
+            return Ok(vec![Error::UnknownElement {
+                name,
+                parent: parent.to_owned(),
+            }])
+        }
+    };
+
+    let mut unknown = Vec::new();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|err| Error::XmlDecodingError(err.into()))?;
+        let key = attribute.key.local_name().into_inner();
+        if !spec.attributes.iter().any(|known| known.as_bytes() == key) {
+            unknown.push(Error::UnknownAttribute {
+                element: name.clone(),
+                name: String::from_utf8_lossy(key).into_owned(),
+            });
+        }
+    }
+    Ok(unknown)
+}
@@ -3,7 +3,7 @@ use std::path::Path;
 use itertools::Itertools;
 use quick_xml::events::Event;
 
-use crate::{Error, ResourceCache, Result, Tileset};
+use crate::{Error, LoaderOptions, ResourceCache, Result, Tileset};
 
 use super::{Parser, ReadFrom, Reader};
 
@@ -11,17 +11,34 @@ pub async fn parse_tileset(
     path: &Path,
     read_from: &mut impl ReadFrom,
     cache: &mut impl ResourceCache,
+    options: &LoaderOptions,
+) -> Result<Tileset> {
+    let reader = read_from
+        .read_from(path)
+        .await
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    parse_tileset_from(reader, path, read_from, cache, options).await
+}
+
+/// Parses an external tileset whose file was already opened, such as the ones read at once
+/// through [`ReadFrom::read_all`].
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "parse_tileset", level = "debug", skip_all, fields(path = %path.display()))
+)]
+pub async fn parse_tileset_from<RF: ReadFrom>(
+    mut reader: RF::Reader,
+    path: &Path,
+    read_from: &mut RF,
+    cache: &mut impl ResourceCache,
+    options: &LoaderOptions,
 ) -> Result<Tileset> {
-    let mut reader =
-        read_from
-            .read_from(path)
-            .await
-            .map_err(|err| Error::ResourceLoadingError {
-                path: path.to_owned(),
-                err: Box::new(err),
-            })?;
     let mut buffer = Vec::new();
     loop {
+        let position = reader.buffer_position();
         match reader
             .read_event_into(&mut buffer)
             .await
@@ -35,8 +52,14 @@ pub async fn parse_tileset(
                     .try_collect()
                     .map_err(|err| Error::XmlDecodingError(err.into()))?;
 
+                let mut parser = Parser::with_reader(reader);
+                parser.options = options.clone();
+                parser.directory = path.parent().map(Path::to_owned).unwrap_or_default();
+                parser.path = path.to_owned();
+                parser.element_position = position;
+                parser.check_element("", &start)?;
                 return Tileset::parse_external_tileset(
-                    &mut Parser::with_reader(reader),
+                    &mut parser,
                     &attributes,
                     path,
                     read_from,
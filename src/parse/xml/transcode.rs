@@ -0,0 +1,233 @@
+use std::{
+    io::{self, BufRead, Cursor, Read},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use flate2::read::MultiGzDecoder;
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+
+/// The encoding of a document, as detected from its first bytes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    /// UTF-8 without a byte order mark, which is read as is.
+    Utf8,
+    /// UTF-8 with a byte order mark, which is skipped.
+    Utf8Bom,
+    /// UTF-16, with a byte order mark if `bom` is true.
+    Utf16 { big_endian: bool, bom: bool },
+    /// A gzip-compressed document, whose encoding is detected once it is decompressed.
+    Gzip,
+}
+
+impl Encoding {
+    /// Detects the encoding of a document from its first bytes, as described in the
+    /// [XML specification](https://www.w3.org/TR/xml/#sec-guessing-no-ext-info).
+    fn detect(start: &[u8]) -> Self {
+        if start.starts_with(GZIP_MAGIC) {
+            return Encoding::Gzip;
+        }
+        match start {
+            [0xEF, 0xBB, 0xBF, ..] => Encoding::Utf8Bom,
+            [0xFE, 0xFF, ..] => Encoding::Utf16 {
+                big_endian: true,
+                bom: true,
+            },
+            [0xFF, 0xFE, ..] => Encoding::Utf16 {
+                big_endian: false,
+                bom: true,
+            },
+            [0x00, b'<', 0x00, b'?', ..] => Encoding::Utf16 {
+                big_endian: true,
+                bom: false,
+            },
+            [b'<', 0x00, b'?', 0x00, ..] => Encoding::Utf16 {
+                big_endian: false,
+                bom: false,
+            },
+            _ => Encoding::Utf8,
+        }
+    }
+}
+
+/// A resource whose contents are guaranteed to be UTF-8 without a byte order mark, which is the
+/// only encoding the XML parser handles.
+///
+/// UTF-8 resources are read as is, while UTF-16 ones are read entirely and transcoded upfront.
+/// Gzip-compressed resources, such as `.tmx.gz` files, are read entirely and decompressed upfront
+/// as well.
+pub(crate) enum Transcoded<R> {
+    /// The resource is read directly.
+    Source(R),
+    /// The transcoded contents of the resource.
+    Decoded(Cursor<Vec<u8>>),
+    /// Reading the resource failed while detecting or transcoding it. The error is reported on
+    /// the next read.
+    Failed(Option<io::Error>),
+}
+
+impl<R> Transcoded<R> {
+    fn from_utf16(bytes: &[u8], big_endian: bool, bom: bool) -> Self {
+        let bytes = if bom { &bytes[2..] } else { bytes };
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if big_endian {
+                    u16::from_be_bytes(pair)
+                } else {
+                    u16::from_le_bytes(pair)
+                }
+            })
+            .collect();
+        Transcoded::Decoded(Cursor::new(String::from_utf16_lossy(&units).into_bytes()))
+    }
+
+    fn from_gzip(bytes: &[u8]) -> Self {
+        let mut decompressed = Vec::new();
+        if let Err(err) = MultiGzDecoder::new(bytes).read_to_end(&mut decompressed) {
+            return Transcoded::Failed(Some(err));
+        }
+        // The decompressed document may be in any encoding
+        Self::from_bytes(decompressed)
+    }
+
+    /// Detects the encoding of a document already read into memory and transcodes it if needed.
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        match Transcoded::new(Cursor::new(bytes)) {
+            Transcoded::Source(cursor) | Transcoded::Decoded(cursor) => Transcoded::Decoded(cursor),
+            Transcoded::Failed(error) => Transcoded::Failed(error),
+        }
+    }
+
+    fn take_error(error: &mut Option<io::Error>) -> io::Error {
+        error
+            .take()
+            .unwrap_or_else(|| io::Error::other("resource could not be transcoded"))
+    }
+}
+
+impl<R: BufRead> Transcoded<R> {
+    /// Detects the encoding of a resource and wraps it so that it is read as UTF-8.
+    pub(crate) fn new(mut source: R) -> Self {
+        let encoding = match source.fill_buf() {
+            Ok(start) => Encoding::detect(start),
+            Err(err) => return Transcoded::Failed(Some(err)),
+        };
+        match encoding {
+            Encoding::Utf8 => Transcoded::Source(source),
+            Encoding::Utf8Bom => {
+                source.consume(UTF8_BOM.len());
+                Transcoded::Source(source)
+            }
+            Encoding::Utf16 { big_endian, bom } => {
+                let mut bytes = Vec::new();
+                match source.read_to_end(&mut bytes) {
+                    Ok(_) => Self::from_utf16(&bytes, big_endian, bom),
+                    Err(err) => Transcoded::Failed(Some(err)),
+                }
+            }
+            Encoding::Gzip => {
+                let mut bytes = Vec::new();
+                match source.read_to_end(&mut bytes) {
+                    Ok(_) => Self::from_gzip(&bytes),
+                    Err(err) => Transcoded::Failed(Some(err)),
+                }
+            }
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> Transcoded<R> {
+    /// Detects the encoding of an asynchronous resource and wraps it so that it is read as UTF-8.
+    pub(crate) async fn new_async(mut source: R) -> Self {
+        let encoding = match tokio::io::AsyncBufReadExt::fill_buf(&mut source).await {
+            Ok(start) => Encoding::detect(start),
+            Err(err) => return Transcoded::Failed(Some(err)),
+        };
+        match encoding {
+            Encoding::Utf8 => Transcoded::Source(source),
+            Encoding::Utf8Bom => {
+                Pin::new(&mut source).consume(UTF8_BOM.len());
+                Transcoded::Source(source)
+            }
+            Encoding::Utf16 { big_endian, bom } => {
+                let mut bytes = Vec::new();
+                match tokio::io::AsyncReadExt::read_to_end(&mut source, &mut bytes).await {
+                    Ok(_) => Self::from_utf16(&bytes, big_endian, bom),
+                    Err(err) => Transcoded::Failed(Some(err)),
+                }
+            }
+            Encoding::Gzip => {
+                let mut bytes = Vec::new();
+                match tokio::io::AsyncReadExt::read_to_end(&mut source, &mut bytes).await {
+                    Ok(_) => Self::from_gzip(&bytes),
+                    Err(err) => Transcoded::Failed(Some(err)),
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for Transcoded<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Transcoded::Source(source) => source.read(buf),
+            Transcoded::Decoded(cursor) => cursor.read(buf),
+            Transcoded::Failed(error) => Err(Self::take_error(error)),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for Transcoded<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Transcoded::Source(source) => source.fill_buf(),
+            Transcoded::Decoded(cursor) => cursor.fill_buf(),
+            Transcoded::Failed(error) => Err(Self::take_error(error)),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Transcoded::Source(source) => source.consume(amt),
+            Transcoded::Decoded(cursor) => cursor.consume(amt),
+            Transcoded::Failed(_) => {}
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Transcoded<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Transcoded::Source(source) => Pin::new(source).poll_read(cx, buf),
+            Transcoded::Decoded(cursor) => Pin::new(cursor).poll_read(cx, buf),
+            Transcoded::Failed(error) => Poll::Ready(Err(Self::take_error(error))),
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncBufRead for Transcoded<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        match self.get_mut() {
+            Transcoded::Source(source) => Pin::new(source).poll_fill_buf(cx),
+            Transcoded::Decoded(cursor) => Pin::new(cursor).poll_fill_buf(cx),
+            Transcoded::Failed(error) => Poll::Ready(Err(Self::take_error(error))),
+        }
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        match self.get_mut() {
+            Transcoded::Source(source) => Pin::new(source).consume(amt),
+            Transcoded::Decoded(cursor) => Pin::new(cursor).consume(amt),
+            Transcoded::Failed(_) => {}
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Structures related to storing fully loaded maps in a binary format that loads faster than
+//! TMX or TMJ files.
+
+use crate::{Error, Map, Result};
+
+/// The bytes every precompiled map starts with.
+const MAGIC: &[u8] = b"TILEDMAP";
+
+/// The version of the crate that wrote a precompiled map, which is checked when reading it back,
+/// since the layout of the serialized data changes between versions.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+impl Map {
+    /// Serializes the map, along with its tilesets and the templates of its objects, into a
+    /// compact binary blob that [`Map::from_bytes()`] and
+    /// [`Loader::load_precompiled_map()`](crate::Loader::load_precompiled_map) read back without
+    /// parsing XML or decompressing tile data. Available under the `precompiled` feature.
+    ///
+    /// Blobs can only be read by the same version of the crate that wrote them, so they are meant
+    /// to be produced as part of the build of an application, not to be distributed on their own.
+    /// Images aren't embedded: Only their paths and embedded data are stored, as in the map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, Map};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64_zlib.tmx")
+    ///     .unwrap();
+    /// let bytes = map.to_bytes().unwrap();
+    ///
+    /// assert_eq!(Map::from_bytes(&bytes).unwrap(), map);
+    /// # }
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + VERSION.len() + 1);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(VERSION.as_bytes());
+        bytes.push(0);
+        bincode::serialize_into(&mut bytes, self).map_err(invalid)?;
+        Ok(bytes)
+    }
+
+    /// Reads a map written by [`Map::to_bytes()`]. Available under the `precompiled` feature.
+    ///
+    /// Returns [`Error::InvalidPrecompiledMap`] if the bytes don't hold a precompiled map, or if
+    /// it was written by another version of the crate.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Map> {
+        let data = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("the data isn't a precompiled map"))?;
+        let version_end = data
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| invalid("the data isn't a precompiled map"))?;
+        let version = String::from_utf8_lossy(&data[..version_end]);
+        if version != VERSION {
+            return Err(invalid(format!(
+                "the map was precompiled with version {} of the crate, but this is version {}",
+                version, VERSION
+            )));
+        }
+        bincode::deserialize(&data[version_end + 1..]).map_err(invalid)
+    }
+}
+
+fn invalid(description: impl ToString) -> Error {
+    Error::InvalidPrecompiledMap {
+        description: description.to_string(),
+    }
+}
@@ -0,0 +1,116 @@
+//! Structures related to reporting the progress of loading maps and cancelling it.
+
+use std::{
+    fmt,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// A stage reached while loading a TMX map, as reported to a [`ProgressHandler`].
+///
+/// Stages are reported in the order below: Once the map has started, every tileset is reported
+/// as soon as all of them are loaded, which is right before the first layer is parsed. Layers
+/// are then reported one by one, and finally the map itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoadProgress<'a> {
+    /// The attributes of the map were parsed, and its contents are about to be.
+    MapStarted {
+        /// The path of the map.
+        path: &'a Path,
+    },
+    /// A tileset of the map was loaded.
+    TilesetLoaded {
+        /// The index of the tileset within the map.
+        index: usize,
+        /// The amount of tilesets of the map.
+        count: usize,
+        /// The name of the tileset.
+        name: &'a str,
+    },
+    /// A layer at the root of the map was parsed, along with the layers it contains.
+    LayerLoaded {
+        /// The index of the layer within the map.
+        index: usize,
+        /// The name of the layer.
+        name: &'a str,
+    },
+    /// The map was fully loaded.
+    MapLoaded {
+        /// The path of the map.
+        path: &'a Path,
+    },
+}
+
+/// A function that receives the [stages](LoadProgress) of loading maps, set through
+/// [`LoaderOptions::progress_handler`](crate::LoaderOptions::progress_handler).
+///
+/// Two handlers are only equal if they are clones of each other.
+#[derive(Clone)]
+pub struct ProgressHandler(Arc<dyn Fn(LoadProgress<'_>) + Send + Sync>);
+
+impl ProgressHandler {
+    /// Creates a handler from the function given.
+    pub fn new(handle: impl Fn(LoadProgress<'_>) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(handle))
+    }
+
+    /// Passes a stage to the handler.
+    pub fn handle(&self, progress: LoadProgress<'_>) {
+        (self.0)(progress)
+    }
+}
+
+impl fmt::Debug for ProgressHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ProgressHandler").finish()
+    }
+}
+
+impl PartialEq for ProgressHandler {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ProgressHandler {}
+
+/// A flag that cancels the loading of maps once set, set through
+/// [`LoaderOptions::cancellation_token`](crate::LoaderOptions::cancellation_token).
+///
+/// Cancellation is cooperative: The loader checks the token whenever it reports progress, as well
+/// as before loading external tilesets and between the chunks of infinite tile layers, and then
+/// fails with [`Error::LoadingCancelled`](crate::Error::LoadingCancelled). Clones of a token share
+/// the same flag, so it can be cancelled from another thread or task.
+///
+/// Two tokens are equal if they share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the loading operations using this token or any of its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`CancellationToken::cancel()`] was called on this token or any of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
@@ -0,0 +1,382 @@
+//! Structures related to Tiled projects, which declare the custom property types used by maps.
+
+use std::{convert::TryFrom, path::PathBuf, sync::Arc};
+
+use crate::{
+    properties::merge_properties, Color, LayerData, LayerDataType, Map, Properties, PropertyValue,
+    Tileset,
+};
+
+/// A Tiled project, loaded from a `.tiled-project` file through
+/// [`Loader::load_project()`](crate::Loader::load_project).
+///
+/// Projects declare the enums and classes used by custom properties, along with the default
+/// values of the members of classes. Since maps only store the members of class properties that
+/// were changed, a project is required to know the value of the other ones; See
+/// [`Project::resolve_map()`] and [`Loader::set_project()`](crate::Loader::set_project).
+///
+/// Also see the [Tiled docs](https://doc.mapeditor.org/en/stable/manual/projects/).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Project {
+    /// The folders included in the project.
+    pub folders: Vec<PathBuf>,
+    /// The custom property types declared by the project.
+    pub property_types: Vec<PropertyType>,
+}
+
+/// A custom property type declared by a [`Project`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyType {
+    /// An enum type, whose values are one of a set of names.
+    Enum(EnumType),
+    /// A class type, whose values are a set of members.
+    Class(ClassType),
+}
+
+impl PropertyType {
+    /// Returns the ID of the type, unique within its project.
+    pub fn id(&self) -> u32 {
+        match self {
+            PropertyType::Enum(e) => e.id,
+            PropertyType::Class(c) => c.id,
+        }
+    }
+
+    /// Returns the name of the type, which properties of this type reference.
+    pub fn name(&self) -> &str {
+        match self {
+            PropertyType::Enum(e) => &e.name,
+            PropertyType::Class(c) => &c.name,
+        }
+    }
+}
+
+/// How the values of an [`EnumType`] are stored in properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumStorageType {
+    /// Values are stored by name, as [`PropertyValue::StringValue`]s.
+    String,
+    /// Values are stored by index, as [`PropertyValue::IntValue`]s.
+    Int,
+}
+
+/// An enum property type declared by a [`Project`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumType {
+    /// The ID of the type, unique within its project.
+    pub id: u32,
+    /// The name of the type.
+    pub name: String,
+    /// How the values of this type are stored in properties.
+    pub storage_type: EnumStorageType,
+    /// The names of the values of this type.
+    pub values: Vec<String>,
+    /// Whether properties of this type can hold several values at once, in which case they are
+    /// stored as comma-separated names or as bit flags.
+    pub values_as_flags: bool,
+}
+
+impl EnumType {
+    /// Resolves a property of this type, as stored in the files, into a
+    /// [`PropertyValue::Enum`].
+    ///
+    /// Returns [`None`] if the value isn't stored the way this type stores its values, or if it
+    /// refers to a value this type doesn't declare.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{EnumStorageType, EnumType, PropertyValue};
+    ///
+    /// let directions = EnumType {
+    ///     id: 1,
+    ///     name: "Directions".to_owned(),
+    ///     storage_type: EnumStorageType::Int,
+    ///     values: vec!["North".to_owned(), "East".to_owned(), "South".to_owned()],
+    ///     values_as_flags: true,
+    /// };
+    /// assert_eq!(
+    ///     directions.resolve(&PropertyValue::IntValue(0b101)),
+    ///     Some(PropertyValue::Enum {
+    ///         name: "Directions".to_owned(),
+    ///         value: "North,South".to_owned(),
+    ///         flags: vec!["North".to_owned(), "South".to_owned()],
+    ///     })
+    /// );
+    /// assert_eq!(directions.resolve(&PropertyValue::IntValue(0b1000)), None);
+    /// ```
+    pub fn resolve(&self, value: &PropertyValue) -> Option<PropertyValue> {
+        let names: Vec<String> = match (self.storage_type, value) {
+            (EnumStorageType::String, PropertyValue::StringValue(value)) => {
+                if !self.values_as_flags {
+                    vec![value.clone()]
+                } else if value.is_empty() {
+                    Vec::new()
+                } else {
+                    value.split(',').map(ToOwned::to_owned).collect()
+                }
+            }
+            (EnumStorageType::Int, PropertyValue::IntValue(value)) if self.values_as_flags => {
+                // Each value is a bit flag, the first one being the lowest bit
+                let bits = *value as u32;
+                let known = (0..self.values.len().min(32)).fold(0u32, |known, i| known | 1 << i);
+                if bits & !known != 0 {
+                    return None;
+                }
+                self.values
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| i < 32 && bits & 1 << i != 0)
+                    .map(|(_, name)| name.clone())
+                    .collect()
+            }
+            (EnumStorageType::Int, PropertyValue::IntValue(value)) => {
+                vec![self.values.get(usize::try_from(*value).ok()?)?.clone()]
+            }
+            _ => return None,
+        };
+        if !names.iter().all(|name| self.values.contains(name)) {
+            return None;
+        }
+
+        Some(PropertyValue::Enum {
+            name: self.name.clone(),
+            value: names.join(","),
+            flags: if self.values_as_flags {
+                names
+            } else {
+                Vec::new()
+            },
+        })
+    }
+}
+
+/// A class property type declared by a [`Project`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassType {
+    /// The ID of the type, unique within its project.
+    pub id: u32,
+    /// The name of the type.
+    pub name: String,
+    /// The color the type is displayed with in the editor.
+    pub color: Option<Color>,
+    /// What the type can be used for, e.g. `"property"` or `"object"`.
+    pub use_as: Vec<String>,
+    /// The members of the type, along with their default values.
+    pub members: Properties,
+}
+
+impl Project {
+    /// Returns the property type with the name given, if there's one.
+    pub fn property_type(&self, name: &str) -> Option<&PropertyType> {
+        self.property_types.iter().find(|t| t.name() == name)
+    }
+
+    /// Returns the class type with the name given, if there's one.
+    pub fn class_type(&self, name: &str) -> Option<&ClassType> {
+        self.property_types.iter().find_map(|t| match t {
+            PropertyType::Class(class) if class.name == name => Some(class),
+            _ => None,
+        })
+    }
+
+    /// Returns the enum type with the name given, if there's one.
+    pub fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        self.property_types.iter().find_map(|t| match t {
+            PropertyType::Enum(e) if e.name == name => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over the enum types declared by the project.
+    pub fn enum_types(&self) -> impl Iterator<Item = &EnumType> {
+        self.property_types.iter().filter_map(|t| match t {
+            PropertyType::Enum(e) => Some(e),
+            _ => None,
+        })
+    }
+
+    /// Resolves the class properties given against the classes declared by the project.
+    ///
+    /// Members missing from class properties are set to their default value, including within
+    /// nested class properties, whose defaults can be overridden by the classes they are members
+    /// of. Members whose type doesn't match the declared one are converted
+    /// when possible, which is needed for class properties loaded from the JSON formats, since
+    /// they don't store the types of members: An integer member declared as a float becomes a
+    /// float, a string member declared as a color, file or object becomes one, and a string or
+    /// integer member declared as an enum becomes a [`PropertyValue::Enum`]. The directory of the
+    /// file the members were declared in isn't known anymore at this point, so the paths of file
+    /// members converted this way stay relative to it.
+    ///
+    /// Properties of classes the project doesn't declare are left as they are.
+    pub fn resolve_properties(&self, properties: &mut Properties) {
+        for value in properties.values_mut() {
+            self.resolve_value(value);
+        }
+    }
+
+    fn resolve_value(&self, value: &mut PropertyValue) {
+        let PropertyValue::ClassValue {
+            property_type,
+            properties,
+        } = value
+        else {
+            return;
+        };
+        if let Some(class) = self.class_type(property_type) {
+            for (name, default) in &class.members {
+                let Some(member) = properties.get_mut(name) else {
+                    continue;
+                };
+                match default {
+                    PropertyValue::Enum { name, .. } => {
+                        if let Some(resolved) = self.enum_type(name).and_then(|e| e.resolve(member))
+                        {
+                            *member = resolved;
+                        }
+                    }
+                    default => convert_member(member, default),
+                }
+            }
+            merge_properties(properties, &class.members);
+        }
+        self.resolve_properties(properties);
+    }
+
+    /// Resolves the class properties of a map against the project, along with the ones of its
+    /// layers, objects and tilesets. See [`Project::resolve_properties()`].
+    ///
+    /// Tilesets shared with other maps are only copied if they contain properties that change.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// # fn main() {
+    /// let mut loader = Loader::new();
+    /// let project = loader
+    ///     .load_project("assets/tiled_class_property.tiled-project")
+    ///     .unwrap();
+    /// let mut map = loader
+    ///     .load_tmx_map("assets/tiled_nested_class_property.tmx")
+    ///     .unwrap();
+    /// project.resolve_map(&mut map);
+    ///
+    /// let Some(PropertyValue::ClassValue { properties, .. }) =
+    ///     map.properties.get("map class property")
+    /// else {
+    ///     panic!("expected a class property");
+    /// };
+    /// assert_eq!(properties["volume"], PropertyValue::FloatValue(0.8));
+    /// # }
+    /// ```
+    pub fn resolve_map(&self, map: &mut Map) {
+        self.resolve_properties(&mut map.properties);
+        for tileset in &mut map.tilesets {
+            if self.changes_tileset(tileset) {
+                self.resolve_tileset(Arc::make_mut(tileset));
+            }
+        }
+        for layer in map.layers.iter_mut() {
+            self.resolve_layer(layer);
+        }
+    }
+
+    fn resolve_layer(&self, layer: &mut LayerData) {
+        self.resolve_properties(&mut layer.properties);
+        match &mut layer.layer_type {
+            LayerDataType::Objects(objects) => {
+                for object in objects.object_data_mut() {
+                    self.resolve_properties(&mut object.properties);
+                }
+            }
+            LayerDataType::Group(group) => {
+                for layer in group.layer_data_mut() {
+                    self.resolve_layer(layer);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether resolving the properties of a tileset would change any of them, checked without
+    /// copying the rest of the tileset.
+    fn changes_tileset(&self, tileset: &Tileset) -> bool {
+        let changes = |properties: &Properties| {
+            let mut resolved = properties.clone();
+            self.resolve_properties(&mut resolved);
+            resolved != *properties
+        };
+        changes(&tileset.properties)
+            || tileset.tiles.values().any(|tile| {
+                changes(&tile.properties)
+                    || tile
+                        .collision
+                        .iter()
+                        .flat_map(|objects| objects.object_data())
+                        .any(|object| changes(&object.properties))
+            })
+            || tileset.wang_sets.iter().any(|wang_set| {
+                changes(&wang_set.properties)
+                    || wang_set
+                        .wang_colors
+                        .iter()
+                        .any(|color| changes(&color.properties))
+            })
+    }
+
+    /// Resolves the class properties of a tileset against the project, along with the ones of
+    /// its tiles and Wang sets. See [`Project::resolve_properties()`].
+    pub fn resolve_tileset(&self, tileset: &mut Tileset) {
+        self.resolve_properties(&mut tileset.properties);
+        for tile in tileset.tiles.values_mut() {
+            self.resolve_properties(&mut tile.properties);
+            if let Some(objects) = &mut tile.collision {
+                for object in objects.object_data_mut() {
+                    self.resolve_properties(&mut object.properties);
+                }
+            }
+        }
+        for wang_set in &mut tileset.wang_sets {
+            self.resolve_properties(&mut wang_set.properties);
+            for color in &mut wang_set.wang_colors {
+                self.resolve_properties(&mut color.properties);
+            }
+        }
+    }
+}
+
+/// Converts a class member to the type of its declared default value, if they differ and the
+/// conversion is lossless.
+fn convert_member(member: &mut PropertyValue, default: &PropertyValue) {
+    let converted = match (&*member, default) {
+        (PropertyValue::IntValue(v), PropertyValue::FloatValue(_)) => {
+            PropertyValue::FloatValue(*v as f32)
+        }
+        (PropertyValue::IntValue(v), PropertyValue::ObjectValue(_)) if *v >= 0 => {
+            PropertyValue::ObjectValue(*v as u32)
+        }
+        (PropertyValue::StringValue(v), PropertyValue::FileValue(_)) => {
+            PropertyValue::FileValue(v.into())
+        }
+        (PropertyValue::StringValue(v), PropertyValue::ColorValue(_)) => match v.parse() {
+            Ok(color) => PropertyValue::ColorValue(color),
+            Err(()) => return,
+        },
+        // Class members without a type are inferred as untyped classes
+        (
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            },
+            PropertyValue::ClassValue {
+                property_type: default_type,
+                ..
+            },
+        ) if property_type.is_empty() => PropertyValue::ClassValue {
+            property_type: default_type.clone(),
+            properties: properties.clone(),
+        },
+        _ => return,
+    };
+    *member = converted;
+}
@@ -1,4 +1,11 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
 use quick_xml::events::{attributes::Attribute, Event};
 
@@ -10,6 +17,7 @@ use crate::{
 
 /// Represents a RGBA color with 8-bit depth on each channel.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub struct Color {
     pub alpha: u8,
@@ -66,6 +74,7 @@ impl FromStr for Color {
 ///
 /// Also read the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-properties).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PropertyValue {
     /// A boolean value. Corresponds to the `bool` property type.
     BoolValue(bool),
@@ -78,8 +87,12 @@ pub enum PropertyValue {
     /// A string value. Corresponds to the `string` property type.
     StringValue(String),
     /// A filepath value. Corresponds to the `file` property type.
-    /// Holds the path relative to the map or tileset.
-    FileValue(String),
+    ///
+    /// Holds the path joined with the directory of the file the property was declared in, i.e.
+    /// relative to the working directory like [`Image::source`](crate::Image::source), so that it
+    /// can be read through the same [`ResourceReader`](crate::ResourceReader) as the map. Unset
+    /// paths stay empty.
+    FileValue(PathBuf),
     /// An object ID value. Corresponds to the `object` property type.
     /// Holds the id of a referenced object, or 0 if unset.
     ObjectValue(u32),
@@ -91,10 +104,23 @@ pub enum PropertyValue {
         /// A set of properties.
         properties: Properties,
     },
+    /// A value of an enum declared by a [`Project`](crate::Project), which is stored in the files
+    /// as a `string` or `int` property depending on the enum's storage type. Properties are only
+    /// resolved into enum values when the enum types of a project are known while parsing; See
+    /// [`Loader::set_project()`](crate::Loader::set_project).
+    Enum {
+        /// The name of the enum type.
+        name: String,
+        /// The name of the value. For enums whose values are flags, the names of the values set,
+        /// separated by commas.
+        value: String,
+        /// The names of the values set, for enums whose values are flags. Empty for other enums.
+        flags: Vec<String>,
+    },
 }
 
 impl PropertyValue {
-    fn new(property_type: String, value: String) -> Result<PropertyValue> {
+    pub(crate) fn new(property_type: String, value: String) -> Result<PropertyValue> {
         // Check the property type against the value.
         match property_type.as_str() {
             "bool" => match value.parse() {
@@ -127,7 +153,7 @@ impl PropertyValue {
                     description: err.to_string(),
                 }),
             },
-            "file" => Ok(PropertyValue::FileValue(value)),
+            "file" => Ok(PropertyValue::FileValue(PathBuf::from(value))),
             _ => Err(Error::UnknownPropertyType {
                 type_name: property_type,
             }),
@@ -135,8 +161,281 @@ impl PropertyValue {
     }
 }
 
+impl PropertyValue {
+    /// Joins the path of a `file` value with the directory of the file it was declared in, unless
+    /// the path is unset.
+    pub(crate) fn with_directory(self, directory: &Path) -> PropertyValue {
+        match self {
+            PropertyValue::FileValue(path) if !path.as_os_str().is_empty() => {
+                PropertyValue::FileValue(directory.join(path))
+            }
+            value => value,
+        }
+    }
+
+    /// Returns the name of the Tiled property type this value corresponds to, e.g. `"int"` for
+    /// [`PropertyValue::IntValue`].
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::BoolValue(_) => "bool",
+            PropertyValue::FloatValue(_) => "float",
+            PropertyValue::IntValue(_) => "int",
+            PropertyValue::ColorValue(_) => "color",
+            PropertyValue::StringValue(_) => "string",
+            PropertyValue::FileValue(_) => "file",
+            PropertyValue::ObjectValue(_) => "object",
+            PropertyValue::ClassValue { .. } => "class",
+            PropertyValue::Enum { .. } => "enum",
+        }
+    }
+}
+
+/// The ID of an object, as referenced by an `object` property.
+///
+/// Mainly used for converting from and into [`PropertyValue::ObjectValue`]. An ID of 0 means that
+/// the property doesn't reference any object.
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone, Default)]
+pub struct ObjectId(pub u32);
+
+/// An error arising from trying to convert a [`PropertyValue`] into a type that doesn't correspond
+/// to its property type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PropertyTypeError {
+    /// The name of the property type the conversion expected, e.g. `"int"`.
+    pub expected: &'static str,
+    /// The name of the property type of the value, e.g. `"string"`.
+    pub found: &'static str,
+}
+
+impl fmt::Display for PropertyTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a property of type `{}`, but got one of type `{}`",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for PropertyTypeError {}
+
+/// Implements conversions between a type and the [`PropertyValue`] variant corresponding to it.
+macro_rules! property_conversions {
+    ($($ty:ty => $variant:ident($type_name:literal) { into: $into:expr, from: $from:expr }),* $(,)?) => {
+        $(
+            impl TryFrom<&PropertyValue> for $ty {
+                type Error = PropertyTypeError;
+
+                fn try_from(value: &PropertyValue) -> std::result::Result<Self, Self::Error> {
+                    match value {
+                        PropertyValue::$variant(v) => Ok($into(v)),
+                        _ => Err(PropertyTypeError {
+                            expected: $type_name,
+                            found: value.type_name(),
+                        }),
+                    }
+                }
+            }
+
+            impl From<$ty> for PropertyValue {
+                fn from(value: $ty) -> Self {
+                    PropertyValue::$variant($from(value))
+                }
+            }
+        )*
+    };
+}
+
+property_conversions! {
+    bool => BoolValue("bool") { into: |v: &bool| *v, from: |v| v },
+    f32 => FloatValue("float") { into: |v: &f32| *v, from: |v| v },
+    i32 => IntValue("int") { into: |v: &i32| *v, from: |v| v },
+    Color => ColorValue("color") { into: |v: &Color| *v, from: |v| v },
+    String => StringValue("string") { into: |v: &String| v.clone(), from: |v| v },
+    PathBuf => FileValue("file") { into: |v: &PathBuf| v.clone(), from: |v| v },
+    ObjectId => ObjectValue("object") { into: |v: &u32| ObjectId(*v), from: |v: ObjectId| v.0 },
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        PropertyValue::StringValue(value.to_owned())
+    }
+}
+
 /// A custom property container.
-pub type Properties = HashMap<String, PropertyValue>;
+pub type Properties = HashMap<Arc<str>, PropertyValue>;
+
+/// Typed accessors for [`Properties`], so that reading a property doesn't require matching on
+/// [`PropertyValue`].
+///
+/// The `get_*` methods return [`None`] both when the property is missing and when it has another
+/// type. Use [`PropertiesExt::get_as()`] to tell these cases apart.
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, PropertiesExt};
+///
+/// # fn main() {
+/// let map = Loader::new()
+///     .load_tmx_map("assets/tiled_base64_zlib.tmx")
+///     .unwrap();
+/// let tileset = &map.tilesets()[0];
+///
+/// assert_eq!(tileset.properties.get_string("tileset property"), Some("tsp"));
+/// assert_eq!(tileset.properties.get_int("tileset property"), None);
+/// # }
+/// ```
+pub trait PropertiesExt {
+    /// Returns the value of a property converted to the type given, [`None`] if there's no
+    /// property with that name, or an error if the property has another type.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Properties, PropertiesExt, PropertyTypeError};
+    ///
+    /// let mut properties = Properties::new();
+    /// properties.insert("speed".into(), 3.5.into());
+    ///
+    /// assert_eq!(properties.get_as::<f32>("speed"), Ok(Some(3.5)));
+    /// assert_eq!(properties.get_as::<f32>("health"), Ok(None));
+    /// assert_eq!(
+    ///     properties.get_as::<i32>("speed"),
+    ///     Err(PropertyTypeError { expected: "int", found: "float" })
+    /// );
+    /// ```
+    fn get_as<T>(&self, name: &str) -> std::result::Result<Option<T>, PropertyTypeError>
+    where
+        T: for<'a> TryFrom<&'a PropertyValue, Error = PropertyTypeError>;
+
+    /// Returns the value of a `string` property.
+    fn get_string(&self, name: &str) -> Option<&str>;
+
+    /// Returns the value of an `int` property.
+    fn get_int(&self, name: &str) -> Option<i32>;
+
+    /// Returns the value of a `float` property.
+    fn get_float(&self, name: &str) -> Option<f32>;
+
+    /// Returns the value of a `bool` property.
+    fn get_bool(&self, name: &str) -> Option<bool>;
+
+    /// Returns the value of a `color` property.
+    fn get_color(&self, name: &str) -> Option<Color>;
+
+    /// Returns the value of a `file` property, which is relative to the working directory; See
+    /// [`PropertyValue::FileValue`].
+    fn get_file(&self, name: &str) -> Option<&Path>;
+
+    /// Returns the ID of the object an `object` property references, which is 0 if the property
+    /// doesn't reference any object.
+    fn get_object_ref(&self, name: &str) -> Option<ObjectId>;
+
+    /// Deserializes the properties into a type implementing `Deserialize`, with each property
+    /// mapped to the field of the same name. Class properties are deserialized as nested structs.
+    /// Available under the `serde` feature.
+    ///
+    /// Returns [`Error::InvalidPropertyValue`] if a property has a type that doesn't match its
+    /// field, or if a field without a default value has no property.
+    ///
+    /// ## Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use tiled::{Properties, PropertiesExt};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Door {
+    ///     locked: bool,
+    ///     key_id: u32,
+    ///     #[serde(default)]
+    ///     label: String,
+    /// }
+    ///
+    /// let mut properties = Properties::new();
+    /// properties.insert("locked".into(), true.into());
+    /// properties.insert("key_id".into(), 4.into());
+    ///
+    /// let door: Door = properties.deserialize_as().unwrap();
+    /// assert!(door.locked);
+    /// assert_eq!(door.key_id, 4);
+    /// assert!(door.label.is_empty());
+    /// ```
+    #[cfg(feature = "serde")]
+    fn deserialize_as<'de, T: serde::Deserialize<'de>>(&'de self) -> Result<T>;
+}
+
+impl PropertiesExt for Properties {
+    fn get_as<T>(&self, name: &str) -> std::result::Result<Option<T>, PropertyTypeError>
+    where
+        T: for<'a> TryFrom<&'a PropertyValue, Error = PropertyTypeError>,
+    {
+        self.get(name).map(T::try_from).transpose()
+    }
+
+    fn get_string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            PropertyValue::StringValue(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn get_int(&self, name: &str) -> Option<i32> {
+        self.get_as(name).ok().flatten()
+    }
+
+    fn get_float(&self, name: &str) -> Option<f32> {
+        self.get_as(name).ok().flatten()
+    }
+
+    fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get_as(name).ok().flatten()
+    }
+
+    fn get_color(&self, name: &str) -> Option<Color> {
+        self.get_as(name).ok().flatten()
+    }
+
+    fn get_file(&self, name: &str) -> Option<&Path> {
+        match self.get(name)? {
+            PropertyValue::FileValue(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn get_object_ref(&self, name: &str) -> Option<ObjectId> {
+        self.get_as(name).ok().flatten()
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize_as<'de, T: serde::Deserialize<'de>>(&'de self) -> Result<T> {
+        crate::property_de::from_properties(self)
+    }
+}
+
+/// Adds the properties of `defaults` that are missing from `properties`, such as the properties of
+/// a template that an object doesn't override.
+///
+/// Only the members that were changed are saved in the class properties of an object, so the
+/// members of class properties present in both are merged the same way.
+pub(crate) fn merge_properties(properties: &mut Properties, defaults: &Properties) {
+    for (name, default) in defaults {
+        match (properties.get_mut(name), default) {
+            (None, _) => {
+                properties.insert(name.clone(), default.clone());
+            }
+            (
+                Some(PropertyValue::ClassValue {
+                    property_type,
+                    properties: members,
+                }),
+                PropertyValue::ClassValue {
+                    property_type: default_type,
+                    properties: default_members,
+                },
+            ) if property_type == default_type => merge_properties(members, default_members),
+            (Some(_), _) => {}
+        }
+    }
+}
 
 pub(crate) async fn parse_properties<R: Reader>(parser: &mut Parser<R>) -> Result<Properties> {
     let mut p = HashMap::new();
@@ -153,7 +452,7 @@ pub(crate) async fn parse_properties<R: Reader>(parser: &mut Parser<R>) -> Resul
 
 async fn parse_properties_inner<R: Reader>(
     parser: &mut Parser<R>,
-    p: &mut HashMap<String, PropertyValue>,
+    p: &mut Properties,
     attrs: Vec<Attribute<'_>>,
 ) -> Result<()> {
     let (t, v_attr, k, p_t) = get_attrs!(
@@ -176,7 +475,7 @@ async fn parse_properties_inner<R: Reader>(
             HashMap::new()
         };
         p.insert(
-            k.to_string(),
+            parser.interner.intern(k),
             PropertyValue::ClassValue {
                 property_type: p_t.unwrap_or_default().to_string(),
                 properties,
@@ -205,7 +504,13 @@ async fn parse_properties_inner<R: Reader>(
         }
     };
 
-    p.insert(k.to_string(), PropertyValue::new(t, v)?);
+    let value = PropertyValue::new(t, v)?.with_directory(&parser.directory);
+    #[cfg(feature = "json")]
+    let value = match p_t.and_then(|p_t| parser.options.enum_type(p_t)) {
+        Some(enum_type) => enum_type.resolve(&value).unwrap_or(value),
+        None => value,
+    };
+    p.insert(parser.interner.intern(k), value);
     Ok(())
 }
 
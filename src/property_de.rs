@@ -0,0 +1,172 @@
+//! A serde [`Deserializer`] over [`Properties`], so that custom properties can be read into types
+//! deriving `Deserialize`.
+//!
+//! Properties are deserialized as maps from their names to their values, and class properties as
+//! nested maps. Colors are deserialized as [`Color`](crate::Color)s, or as `#AARRGGBB` strings
+//! when a string is expected. Enum properties are deserialized into unit variants, by name when
+//! they are stored as strings or [resolved](PropertyValue::Enum) and by index when they are
+//! stored as integers.
+
+use std::{convert::TryFrom, fmt};
+
+use serde::de::{self, value::MapDeserializer, Deserializer, IntoDeserializer, Visitor};
+
+use crate::{Error, Properties, PropertyValue, Result};
+
+/// Deserializes the properties given into a value of the type given.
+pub(crate) fn from_properties<'de, T: de::Deserialize<'de>>(
+    properties: &'de Properties,
+) -> Result<T> {
+    T::deserialize(PropertiesDeserializer(properties))
+        .map_err(|err| Error::InvalidPropertyValue { description: err.0 })
+}
+
+#[derive(Debug)]
+struct DeError(String);
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError(msg.to_string())
+    }
+}
+
+struct PropertiesDeserializer<'de>(&'de Properties);
+
+impl<'de> Deserializer<'de> for PropertiesDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_map(MapDeserializer::new(
+            self.0
+                .iter()
+                .map(|(name, value)| (&**name, PropertyValueDeserializer(value))),
+        ))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+struct PropertyValueDeserializer<'de>(&'de PropertyValue);
+
+impl<'de> IntoDeserializer<'de, DeError> for PropertyValueDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for PropertyValueDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.0 {
+            PropertyValue::BoolValue(v) => visitor.visit_bool(*v),
+            PropertyValue::FloatValue(v) => visitor.visit_f32(*v),
+            PropertyValue::IntValue(v) => visitor.visit_i32(*v),
+            PropertyValue::ColorValue(c) => visitor.visit_map(MapDeserializer::new(
+                [
+                    ("alpha", c.alpha),
+                    ("red", c.red),
+                    ("green", c.green),
+                    ("blue", c.blue),
+                ]
+                .iter()
+                .copied(),
+            )),
+            PropertyValue::StringValue(v) | PropertyValue::Enum { value: v, .. } => {
+                visitor.visit_borrowed_str(v)
+            }
+            PropertyValue::FileValue(path) => match path.to_str() {
+                Some(path) => visitor.visit_borrowed_str(path),
+                None => Err(de::Error::custom(format!(
+                    "file path {} is not valid UTF-8",
+                    path.display()
+                ))),
+            },
+            PropertyValue::ObjectValue(v) => visitor.visit_u32(*v),
+            PropertyValue::ClassValue { properties, .. } => {
+                PropertiesDeserializer(properties).deserialize_any(visitor)
+            }
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.0 {
+            PropertyValue::ColorValue(c) => visitor.visit_string(format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                c.alpha, c.red, c.green, c.blue
+            )),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, DeError> {
+        match self.0 {
+            PropertyValue::StringValue(v) | PropertyValue::Enum { value: v, .. } => {
+                visitor.visit_enum(v.as_str().into_deserializer())
+            }
+            PropertyValue::IntValue(v) => match u32::try_from(*v) {
+                Ok(index) => visitor.visit_enum(index.into_deserializer()),
+                Err(_) => Err(de::Error::invalid_value(
+                    de::Unexpected::Signed(*v as i64),
+                    &"a variant index",
+                )),
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
@@ -0,0 +1,189 @@
+//! Conversions between [`PropertyValue`]s and [`serde_json::Value`]s.
+//!
+//! Values are converted to their natural JSON counterparts when there's one. Colors, files,
+//! object references and non-finite floats are converted to strings tagged with their type, such
+//! as `"color:#ff00ff00"`, `"file:../sprites.png"`, `"object:12"` or `"float:inf"`. Plain strings
+//! that would look like tagged ones are tagged with `string:`, so that every value converts back
+//! to the same property. Class values are converted to JSON objects of the form
+//! `{"propertytype": "...", "properties": {...}}`, and enum values to JSON objects of the form
+//! `{"enum": "...", "value": "...", "flags": [...]}`.
+
+use std::{collections::HashMap, convert::TryFrom};
+
+use serde_json::{Map as JsonMap, Number, Value};
+
+use crate::{Color, PropertyTypeError, PropertyValue};
+
+const TAGS: &[&str] = &["color", "file", "object", "float", "string"];
+
+fn tagged(tag: &str, value: impl std::fmt::Display) -> Value {
+    Value::String(format!("{}:{}", tag, value))
+}
+
+fn split_tag(s: &str) -> Option<(&str, &str)> {
+    s.split_once(':').filter(|(tag, _)| TAGS.contains(tag))
+}
+
+impl From<&PropertyValue> for Value {
+    fn from(value: &PropertyValue) -> Self {
+        match value {
+            PropertyValue::BoolValue(v) => Value::Bool(*v),
+            PropertyValue::IntValue(v) => Value::Number((*v).into()),
+            // Going through the shortest representation of the float keeps values such as 0.1
+            // readable, while still converting back to the exact same float.
+            PropertyValue::FloatValue(v) => v
+                .to_string()
+                .parse()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number)
+                .unwrap_or_else(|| tagged("float", v)),
+            PropertyValue::ColorValue(c) => tagged(
+                "color",
+                format_args!("#{:02x}{:02x}{:02x}{:02x}", c.alpha, c.red, c.green, c.blue),
+            ),
+            PropertyValue::StringValue(s) if split_tag(s).is_some() => tagged("string", s),
+            PropertyValue::StringValue(s) => Value::String(s.clone()),
+            PropertyValue::FileValue(path) => tagged("file", path.display()),
+            PropertyValue::ObjectValue(id) => tagged("object", id),
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } => {
+                let properties = properties
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), Value::from(value)))
+                    .collect();
+                let mut object = JsonMap::new();
+                object.insert(
+                    "propertytype".to_owned(),
+                    Value::String(property_type.clone()),
+                );
+                object.insert("properties".to_owned(), Value::Object(properties));
+                Value::Object(object)
+            }
+            PropertyValue::Enum { name, value, flags } => {
+                let mut object = JsonMap::new();
+                object.insert("enum".to_owned(), Value::String(name.clone()));
+                object.insert("value".to_owned(), Value::String(value.clone()));
+                object.insert(
+                    "flags".to_owned(),
+                    flags.iter().cloned().map(Value::String).collect(),
+                );
+                Value::Object(object)
+            }
+        }
+    }
+}
+
+impl From<PropertyValue> for Value {
+    fn from(value: PropertyValue) -> Self {
+        Value::from(&value)
+    }
+}
+
+/// Converts a JSON value back into a property.
+///
+/// Integers that fit in an `i32` become [`PropertyValue::IntValue`]s, while every other number
+/// becomes a [`PropertyValue::FloatValue`]. JSON objects that aren't of the class or enum value
+/// forms are converted to class values without a type, whose properties are the object's members.
+/// `null` and arrays have no property counterpart and can't be converted.
+impl TryFrom<&Value> for PropertyValue {
+    type Error = PropertyTypeError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Null => Err(PropertyTypeError {
+                expected: "property value",
+                found: "null",
+            }),
+            Value::Array(_) => Err(PropertyTypeError {
+                expected: "property value",
+                found: "array",
+            }),
+            Value::Bool(v) => Ok(PropertyValue::BoolValue(*v)),
+            Value::Number(n) => Ok(match n.as_i64().and_then(|v| i32::try_from(v).ok()) {
+                Some(v) => PropertyValue::IntValue(v),
+                None => PropertyValue::FloatValue(n.as_f64().unwrap_or_default() as f32),
+            }),
+            Value::String(s) => {
+                let Some((tag, rest)) = split_tag(s) else {
+                    return Ok(PropertyValue::StringValue(s.clone()));
+                };
+                let invalid = |expected| PropertyTypeError {
+                    expected,
+                    found: "string",
+                };
+                match tag {
+                    "color" => rest
+                        .parse::<Color>()
+                        .map(PropertyValue::ColorValue)
+                        .map_err(|_| invalid("color")),
+                    "file" => Ok(PropertyValue::FileValue(rest.into())),
+                    "object" => rest
+                        .parse()
+                        .map(PropertyValue::ObjectValue)
+                        .map_err(|_| invalid("object")),
+                    "float" => rest
+                        .parse()
+                        .map(PropertyValue::FloatValue)
+                        .map_err(|_| invalid("float")),
+                    _ => Ok(PropertyValue::StringValue(rest.to_owned())),
+                }
+            }
+            Value::Object(object) => {
+                if let Some(value) = enum_value(object) {
+                    return Ok(value);
+                }
+                let (property_type, members) =
+                    match (object.get("propertytype"), object.get("properties")) {
+                        (Some(Value::String(ty)), Some(Value::Object(members)))
+                            if object.len() == 2 =>
+                        {
+                            (ty.clone(), members)
+                        }
+                        _ => (String::new(), object),
+                    };
+                let properties = members
+                    .iter()
+                    .map(|(name, value)| {
+                        Ok((name.as_str().into(), PropertyValue::try_from(value)?))
+                    })
+                    .collect::<Result<HashMap<_, _>, _>>()?;
+                Ok(PropertyValue::ClassValue {
+                    property_type,
+                    properties,
+                })
+            }
+        }
+    }
+}
+
+/// Converts a JSON object of the enum value form back into an enum value.
+fn enum_value(object: &JsonMap<String, Value>) -> Option<PropertyValue> {
+    match (
+        object.get("enum")?,
+        object.get("value")?,
+        object.get("flags")?,
+    ) {
+        (Value::String(name), Value::String(value), Value::Array(flags)) if object.len() == 3 => {
+            Some(PropertyValue::Enum {
+                name: name.clone(),
+                value: value.clone(),
+                flags: flags
+                    .iter()
+                    .map(|flag| flag.as_str().map(ToOwned::to_owned))
+                    .collect::<Option<_>>()?,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl TryFrom<Value> for PropertyValue {
+    type Error = PropertyTypeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        PropertyValue::try_from(&value)
+    }
+}
@@ -0,0 +1,98 @@
+//! Picking random tiles the way Tiled's random mode does. Only available with the `rand` feature.
+
+use rand::{Rng, RngExt};
+
+use crate::{TileId, Tileset, WangId};
+
+impl Tileset {
+    /// Picks a random tile of the tileset, like Tiled's random mode does: Each tile is chosen
+    /// with a chance proportional to its [probability](crate::TileData::probability), which
+    /// defaults to 1 and can be set to 0 to never pick a tile.
+    ///
+    /// Every tile within the [tile count](Self::tilecount) is a candidate for tilesets with an
+    /// image, and every tile of image collection tilesets is. Returns `None` if no tile has a
+    /// positive probability.
+    ///
+    /// ## Example
+    /// ```
+    /// use rand::{rngs::SmallRng, SeedableRng};
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let tileset = Loader::new()
+    ///     .load_tsx_tileset("assets/tiled_tile_probability.tsx")
+    ///     .unwrap();
+    /// let mut rng = SmallRng::seed_from_u64(0);
+    ///
+    /// // Tiles 0 and 3 have a probability of 0.
+    /// let tile = tileset.random_tile(&mut rng).unwrap();
+    /// assert!(tile == 1 || tile == 2);
+    /// # }
+    /// ```
+    pub fn random_tile(&self, rng: &mut impl Rng) -> Option<TileId> {
+        if self.image.is_some() {
+            pick_weighted(
+                (0..self.tilecount).map(|id| (id, self.tile_probability(id))),
+                rng,
+            )
+        } else {
+            pick_weighted(self.tiles().map(|(id, tile)| (id, tile.probability)), rng)
+        }
+    }
+
+    /// Picks a random tile among the tiles of a [Wang set](crate::WangSet) that match a Wang ID,
+    /// like Tiled's terrain brush does. As in [`WangSet::tiles_matching()`], positions with no
+    /// color (0) in the Wang ID match any color, so `WangId([color; 8])` picks among the tiles
+    /// fully covered by a color.
+    ///
+    /// The chance of each tile is proportional to its [probability](crate::TileData::probability)
+    /// multiplied by the [probabilities](crate::WangColor::probability) of the colors at each
+    /// position of its own Wang ID. Returns `None` if the Wang set doesn't exist or no matching
+    /// tile has a positive probability.
+    ///
+    /// [`WangSet::tiles_matching()`]: crate::WangSet::tiles_matching
+    pub fn random_wang_tile(
+        &self,
+        wang_set: usize,
+        wang_id: WangId,
+        rng: &mut impl Rng,
+    ) -> Option<TileId> {
+        let wang_set = self.wang_sets.get(wang_set)?;
+        let candidates = wang_set.tiles_matching(wang_id).into_iter().map(|id| {
+            let colors: f32 = wang_set.wang_tiles[&id]
+                .wang_id
+                .0
+                .iter()
+                .filter_map(|&color| wang_set.get_wang_color(color))
+                .map(|color| color.probability)
+                .product();
+            (id, self.tile_probability(id) * colors)
+        });
+        pick_weighted(candidates, rng)
+    }
+
+    fn tile_probability(&self, id: TileId) -> f32 {
+        self.get_tile(id).map_or(1.0, |tile| tile.probability)
+    }
+}
+
+/// Picks one of the candidates with a chance proportional to its weight, ignoring the ones whose
+/// weight isn't positive.
+fn pick_weighted(
+    candidates: impl Iterator<Item = (TileId, f32)>,
+    rng: &mut impl Rng,
+) -> Option<TileId> {
+    let mut candidates: Vec<_> = candidates.filter(|(_, weight)| *weight > 0.0).collect();
+    // Tiles are stored in a hash map, so they are sorted for seeded generators to be reproducible.
+    candidates.sort_unstable_by_key(|(id, _)| *id);
+    let total: f32 = candidates.iter().map(|(_, weight)| weight).sum();
+    let mut remaining = rng.random::<f32>() * total;
+    for &(id, weight) in &candidates {
+        if remaining < weight {
+            return Some(id);
+        }
+        remaining -= weight;
+    }
+    // Rounding errors may leave a tiny remainder past the last candidate.
+    candidates.last().map(|(id, _)| *id)
+}
@@ -0,0 +1,212 @@
+//! Low-level parsing functions that return plain owned data, without any caching involved.
+
+use std::{path::Path, sync::Arc};
+
+use futures::FutureExt;
+
+use crate::{
+    parse::xml::{AsyncReadFrom, SyncReadFrom},
+    AsyncResourceReader, Color, DefaultResourceCache, EditorSettings, LayerData, LoaderOptions,
+    Map, Orientation, Properties, ResourceReader, Result, StaggerAxis, StaggerIndex, Tileset,
+};
+
+/// The plain owned contents of a map file, obtained via [`parse_map_data()`].
+///
+/// Unlike [`Map`], the tilesets are owned directly rather than shared through [`Arc`]s, which
+/// makes this type convenient for tools that transform files in bulk. Note that tilesets used by
+/// template objects are still referenced through [`TilesetLocation`](crate::TilesetLocation).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapData {
+    /// The TMX format version this map was saved to.
+    pub version: String,
+    /// The way tiles are laid out in the map.
+    pub orientation: Orientation,
+    /// Width of the map, in tiles.
+    pub width: u32,
+    /// Height of the map, in tiles.
+    pub height: u32,
+    /// Tile width, in pixels.
+    pub tile_width: u32,
+    /// Tile height, in pixels.
+    pub tile_height: u32,
+    /// The stagger axis of Hexagonal/Staggered map.
+    pub stagger_axis: StaggerAxis,
+    /// The stagger index of Hexagonal/Staggered map.
+    pub stagger_index: StaggerIndex,
+    /// The length in pixels of the sides of the tiles of a Hexagonal map that are parallel to
+    /// the axis that isn't the stagger axis. Always 0 for other maps.
+    pub hex_side_length: u32,
+    /// The X coordinate of the parallax origin, in pixels.
+    pub parallax_origin_x: f32,
+    /// The Y coordinate of the parallax origin, in pixels.
+    pub parallax_origin_y: f32,
+    /// The tilesets present on this map, in the same order as [`Map::tilesets()`].
+    pub tilesets: Vec<Tileset>,
+    /// The top-level layers present in this map, in display order.
+    pub layers: Vec<LayerData>,
+    /// The custom properties of this map.
+    pub properties: Properties,
+    /// The background color of this map, if any.
+    pub background_color: Option<Color>,
+    /// The settings of the editor saved along with this map, if any.
+    pub editor_settings: Option<EditorSettings>,
+    /// Whether this map is infinite.
+    pub infinite: bool,
+    /// The type of the map, which is arbitrary and set by the user.
+    pub user_type: Option<Arc<str>>,
+    /// The ID the next object added to this map will get.
+    pub next_object_id: u32,
+}
+
+impl From<Map> for MapData {
+    fn from(map: Map) -> Self {
+        Self {
+            version: map.version,
+            orientation: map.orientation,
+            width: map.width,
+            height: map.height,
+            tile_width: map.tile_width,
+            tile_height: map.tile_height,
+            stagger_axis: map.stagger_axis,
+            stagger_index: map.stagger_index,
+            hex_side_length: map.hex_side_length,
+            parallax_origin_x: map.parallax_origin_x,
+            parallax_origin_y: map.parallax_origin_y,
+            tilesets: map
+                .tilesets
+                .into_iter()
+                .map(|tileset| Arc::try_unwrap(tileset).unwrap_or_else(|arc| (*arc).clone()))
+                .collect(),
+            layers: map.layers,
+            properties: map.properties,
+            background_color: map.background_color,
+            editor_settings: map.editor_settings,
+            infinite: map.infinite,
+            user_type: map.user_type,
+            next_object_id: map.next_object_id,
+        }
+    }
+}
+
+impl From<MapData> for Map {
+    fn from(data: MapData) -> Self {
+        Self {
+            version: data.version,
+            orientation: data.orientation,
+            width: data.width,
+            height: data.height,
+            tile_width: data.tile_width,
+            tile_height: data.tile_height,
+            stagger_axis: data.stagger_axis,
+            stagger_index: data.stagger_index,
+            hex_side_length: data.hex_side_length,
+            parallax_origin_x: data.parallax_origin_x,
+            parallax_origin_y: data.parallax_origin_y,
+            tilesets: data.tilesets.into_iter().map(Arc::new).collect(),
+            layers: data.layers,
+            properties: data.properties,
+            background_color: data.background_color,
+            editor_settings: data.editor_settings,
+            infinite: data.infinite,
+            user_type: data.user_type,
+            next_object_id: data.next_object_id,
+            diagnostics: Vec::new(),
+            id_index: Default::default(),
+            gid_table: Default::default(),
+        }
+    }
+}
+
+/// Parses a map file into its plain owned contents. External files are loaded relative to the
+/// path given, through the reader given.
+///
+/// No state is kept between calls: every external tileset and template is parsed again, and
+/// dropped once the map has been parsed. Use a [`Loader`](crate::Loader) instead when loading
+/// several maps that share resources.
+///
+/// ## Example
+/// ```
+/// use tiled::{parse_map_data, FilesystemResourceReader};
+///
+/// # fn main() {
+/// let map = parse_map_data(
+///     "assets/tiled_base64_external.tmx",
+///     &mut FilesystemResourceReader::new(),
+/// )
+/// .unwrap();
+///
+/// assert_eq!(map.tilesets[0].name, "tilesheet");
+/// # }
+/// ```
+pub fn parse_map_data(path: impl AsRef<Path>, reader: &mut impl ResourceReader) -> Result<MapData> {
+    let mut read_from = SyncReadFrom(reader);
+    // The cache is only used for sharing templates within the map, and is dropped before the
+    // tilesets are unwrapped so that they don't have to be cloned.
+    let mut cache = DefaultResourceCache::new();
+    let map = crate::parse::xml::parse_map(
+        path.as_ref(),
+        &mut read_from,
+        &mut cache,
+        &LoaderOptions::default(),
+    )
+    .now_or_never()
+    .expect("synchronously loading a TMX map stayed pending; this is a bug, please report it")?;
+    drop(cache);
+    Ok(map.into())
+}
+
+/// Parses a tileset file into a plain owned [`Tileset`]. External files are loaded relative to
+/// the path given, through the reader given.
+///
+/// No state is kept between calls.
+pub fn parse_tileset_data(
+    path: impl AsRef<Path>,
+    reader: &mut impl ResourceReader,
+) -> Result<Tileset> {
+    let mut read_from = SyncReadFrom(reader);
+    crate::parse::xml::parse_tileset(
+        path.as_ref(),
+        &mut read_from,
+        &mut DefaultResourceCache::new(),
+        &LoaderOptions::default(),
+    )
+    .now_or_never()
+    .expect("synchronously loading a TSX tileset stayed pending; this is a bug, please report it")
+}
+
+/// Parses a map file into its plain owned contents asynchronously.
+///
+/// See [`parse_map_data()`] for more information.
+pub async fn parse_map_data_async(
+    path: impl AsRef<Path>,
+    reader: &mut impl AsyncResourceReader,
+) -> Result<MapData> {
+    let mut read_from = AsyncReadFrom(reader);
+    let mut cache = DefaultResourceCache::new();
+    let map = crate::parse::xml::parse_map(
+        path.as_ref(),
+        &mut read_from,
+        &mut cache,
+        &LoaderOptions::default(),
+    )
+    .await?;
+    drop(cache);
+    Ok(map.into())
+}
+
+/// Parses a tileset file into a plain owned [`Tileset`] asynchronously.
+///
+/// See [`parse_tileset_data()`] for more information.
+pub async fn parse_tileset_data_async(
+    path: impl AsRef<Path>,
+    reader: &mut impl AsyncResourceReader,
+) -> Result<Tileset> {
+    let mut read_from = AsyncReadFrom(reader);
+    crate::parse::xml::parse_tileset(
+        path.as_ref(),
+        &mut read_from,
+        &mut DefaultResourceCache::new(),
+        &LoaderOptions::default(),
+    )
+    .await
+}
@@ -1,5 +1,7 @@
 use std::{future::Future, path::Path};
 
+use futures::future::join_all;
+
 use tokio::{
     fs::File,
     io::{AsyncBufRead, BufReader},
@@ -24,6 +26,24 @@ pub trait AsyncResourceReader {
         &mut self,
         path: &Path,
     ) -> impl Future<Output = Result<Self::Resource, Self::Error>>;
+
+    /// Try to return reader objects for several paths at once, in the same order as the paths.
+    ///
+    /// This is used to read the external tilesets of a map, and reads the resources one after the
+    /// other by default. Readers that can have several reads in flight, e.g. over a network,
+    /// should override it so that the resources are read concurrently.
+    fn read_all(
+        &mut self,
+        paths: &[&Path],
+    ) -> impl Future<Output = Vec<Result<Self::Resource, Self::Error>>> {
+        async move {
+            let mut resources = Vec::with_capacity(paths.len());
+            for path in paths {
+                resources.push(self.read_from(path).await);
+            }
+            resources
+        }
+    }
 }
 
 /// An [`AsyncResourceReader`] that reads from Tokio [`File`] handles.
@@ -45,6 +65,17 @@ impl AsyncResourceReader for AsyncFilesystemResourceReader {
         let file = File::open(path).await?;
         Ok(BufReader::new(file))
     }
+
+    async fn read_all(
+        &mut self,
+        paths: &[&Path],
+    ) -> Vec<std::result::Result<Self::Resource, Self::Error>> {
+        join_all(paths.iter().map(|path| async move {
+            let file = File::open(path).await?;
+            Ok(BufReader::new(file))
+        }))
+        .await
+    }
 }
 
 impl<T, F, R, E> AsyncResourceReader for T
@@ -65,4 +96,10 @@ where
     fn read_from(&mut self, path: &Path) -> F {
         self(path)
     }
+
+    /// Calls the function for every path at once, since it only needs a shared reference.
+    fn read_all(&mut self, paths: &[&Path]) -> impl Future<Output = Vec<Result<R, E>>> {
+        let this = &*self;
+        join_all(paths.iter().map(|path| this(path)))
+    }
 }
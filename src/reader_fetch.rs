@@ -0,0 +1,173 @@
+use std::{error::Error, fmt, future::Future, io::Cursor, path::Path};
+
+use futures::future::join_all;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Response, Window, WorkerGlobalScope};
+
+use crate::AsyncResourceReader;
+
+/// An [`AsyncResourceReader`] that downloads files with the `fetch` API of browsers, for
+/// applications compiled to WebAssembly. Available under the `fetch` feature.
+///
+/// Paths are turned into URLs by appending them to a base URL, using `/` as a separator. The
+/// external tilesets of a map are downloaded concurrently.
+///
+/// This reader only works on `wasm32` targets running in a browser window or worker, and must be
+/// used through the asynchronous loading functions of [`Loader`](crate::Loader).
+///
+/// ## Example
+/// ```no_run
+/// use tiled::{FetchResourceReader, Loader};
+///
+/// # async fn load() -> tiled::Result<()> {
+/// let mut loader = Loader::with_reader(FetchResourceReader::new("https://example.com/assets"));
+///
+/// // Downloads https://example.com/assets/maps/level.tmx and the files it references
+/// let map = loader.load_tmx_map_async("maps/level.tmx").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FetchResourceReader {
+    base_url: String,
+}
+
+impl FetchResourceReader {
+    /// Creates a reader that downloads files relative to the URL given. An empty base URL makes
+    /// paths relative to the URL of the page.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+
+    /// Returns the base URL paths are appended to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the URL the path given is downloaded from.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::FetchResourceReader;
+    ///
+    /// let reader = FetchResourceReader::new("https://example.com/assets/");
+    /// assert_eq!(
+    ///     reader.url(Path::new("maps/../tilesheet.tsx")),
+    ///     "https://example.com/assets/maps/../tilesheet.tsx"
+    /// );
+    /// ```
+    pub fn url(&self, path: &Path) -> String {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let path = path.trim_start_matches('/');
+        if self.base_url.is_empty() {
+            path.to_owned()
+        } else {
+            format!("{}/{}", self.base_url.trim_end_matches('/'), path)
+        }
+    }
+}
+
+impl AsyncResourceReader for FetchResourceReader {
+    type Resource = Cursor<Vec<u8>>;
+    type Error = FetchError;
+
+    fn read_from(
+        &mut self,
+        path: &Path,
+    ) -> impl Future<Output = Result<Self::Resource, Self::Error>> {
+        let url = self.url(path);
+        async move { fetch(url).await.map(Cursor::new) }
+    }
+
+    fn read_all(
+        &mut self,
+        paths: &[&Path],
+    ) -> impl Future<Output = Vec<Result<Self::Resource, Self::Error>>> {
+        let urls: Vec<_> = paths.iter().map(|path| self.url(path)).collect();
+        async move {
+            join_all(
+                urls.into_iter()
+                    .map(|url| async move { fetch(url).await.map(Cursor::new) }),
+            )
+            .await
+        }
+    }
+}
+
+/// Downloads the file at the URL given, through the global scope of the window or worker.
+async fn fetch(url: String) -> Result<Vec<u8>, FetchError> {
+    let global = js_sys::global();
+    let promise = if let Some(window) = global.dyn_ref::<Window>() {
+        window.fetch_with_str(&url)
+    } else if let Some(worker) = global.dyn_ref::<WorkerGlobalScope>() {
+        worker.fetch_with_str(&url)
+    } else {
+        return Err(FetchError::new(
+            url,
+            None,
+            "fetch is only available in windows and workers".to_owned(),
+        ));
+    };
+
+    let response = match JsFuture::from(promise).await {
+        Ok(response) => response.unchecked_into::<Response>(),
+        Err(err) => return Err(FetchError::from_js(url, None, err)),
+    };
+    let status = response.status();
+    if !response.ok() {
+        return Err(FetchError::new(url, Some(status), response.status_text()));
+    }
+    let buffer = match response.array_buffer() {
+        Ok(promise) => JsFuture::from(promise).await,
+        Err(err) => Err(err),
+    };
+    match buffer {
+        Ok(buffer) => Ok(js_sys::Uint8Array::new(&buffer).to_vec()),
+        Err(err) => Err(FetchError::from_js(url, Some(status), err)),
+    }
+}
+
+/// An error that occurred while downloading a file through a [`FetchResourceReader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchError {
+    /// The URL of the file.
+    pub url: String,
+    /// The HTTP status code of the response, if the server responded.
+    pub status: Option<u16>,
+    /// A description of the error.
+    pub message: String,
+}
+
+impl FetchError {
+    fn new(url: String, status: Option<u16>, message: String) -> Self {
+        Self {
+            url,
+            status,
+            message,
+        }
+    }
+
+    fn from_js(url: String, status: Option<u16>, err: JsValue) -> Self {
+        let message = err.as_string().unwrap_or_else(|| format!("{:?}", err));
+        Self::new(url, status, message)
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            Some(status) => write!(
+                f,
+                "failed to fetch {} ({}): {}",
+                self.url, status, self.message
+            ),
+            None => write!(f, "failed to fetch {}: {}", self.url, self.message),
+        }
+    }
+}
+
+impl Error for FetchError {}
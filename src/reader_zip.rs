@@ -0,0 +1,104 @@
+use std::{
+    fs::File,
+    future::Future,
+    io::{Cursor, Read, Seek},
+    path::{Component, Path},
+};
+
+use zip::{result::ZipError, ZipArchive};
+
+use crate::{AsyncResourceReader, ResourceReader};
+
+/// A [`ResourceReader`] and [`AsyncResourceReader`] that reads files from a zip archive, e.g. an
+/// asset pack shipped with a game. Available under the `zip` feature.
+///
+/// Paths are looked up relative to the root of the archive. They are normalized beforehand, so
+/// that the `..` components the loader produces when resolving the paths of tilesets, templates
+/// and images relative to the file that references them are resolved within the archive.
+///
+/// Files are decompressed in memory as a whole when they are read.
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, ZipResourceReader};
+///
+/// # fn main() {
+/// let reader = ZipResourceReader::open("assets/tiled_pack.zip").unwrap();
+/// let mut loader = Loader::with_reader(reader);
+///
+/// // The map references "../tilesheet.tsx", which is read from the archive's root
+/// let map = loader.load_tmx_map("maps/level.tmx").unwrap();
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ZipResourceReader<R = File> {
+    archive: ZipArchive<R>,
+}
+
+impl ZipResourceReader {
+    /// Opens the zip archive at the path given.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ZipError> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> ZipResourceReader<R> {
+    /// Creates a reader over the zip archive given, e.g. a [`File`] or a [`Cursor`] over bytes
+    /// embedded in the executable.
+    pub fn new(archive: R) -> Result<Self, ZipError> {
+        Ok(Self {
+            archive: ZipArchive::new(archive)?,
+        })
+    }
+
+    /// Returns the underlying archive.
+    pub fn archive(&self) -> &ZipArchive<R> {
+        &self.archive
+    }
+
+    fn read_file(&mut self, path: &Path) -> Result<Cursor<Vec<u8>>, ZipError> {
+        let name = entry_name(path).ok_or(ZipError::FileNotFound)?;
+        let mut file = self.archive.by_name(&name)?;
+        let mut contents = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut contents)?;
+        Ok(Cursor::new(contents))
+    }
+}
+
+impl<R: Read + Seek> ResourceReader for ZipResourceReader<R> {
+    type Resource = Cursor<Vec<u8>>;
+    type Error = ZipError;
+
+    fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        self.read_file(path)
+    }
+}
+
+impl<R: Read + Seek> AsyncResourceReader for ZipResourceReader<R> {
+    type Resource = Cursor<Vec<u8>>;
+    type Error = ZipError;
+
+    fn read_from(
+        &mut self,
+        path: &Path,
+    ) -> impl Future<Output = std::result::Result<Self::Resource, Self::Error>> {
+        std::future::ready(self.read_file(path))
+    }
+}
+
+/// Returns the name of the archive entry a path refers to, with `.` and `..` components resolved
+/// and `/` separators, or [`None`] if the path leaves the root of the archive.
+fn entry_name(path: &Path) -> Option<String> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => components.push(name.to_str()?),
+            Component::ParentDir => {
+                components.pop()?;
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Some(components.join("/"))
+}
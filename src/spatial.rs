@@ -0,0 +1,540 @@
+//! Structures related to the geometry of objects and finding the objects and tiles of a layer by
+//! position.
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use crate::{Layer, LayerTile, LayerType, Map, Object, ObjectLayer, ObjectShape, TileRect};
+
+/// An axis-aligned rectangle in map pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoundingBox {
+    /// The X coordinate of the left edge of the rectangle.
+    pub x: f32,
+    /// The Y coordinate of the top edge of the rectangle.
+    pub y: f32,
+    /// The width of the rectangle.
+    pub width: f32,
+    /// The height of the rectangle.
+    pub height: f32,
+}
+
+impl BoundingBox {
+    /// Creates a rectangle from the position of its top-left corner and its size.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns whether the point given is inside the rectangle or on its edges.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+
+    /// Returns whether the rectangles overlap or touch.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+
+    fn from_points(points: impl IntoIterator<Item = (f32, f32)>) -> Self {
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for (x, y) in points {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        if min_x > max_x || min_y > max_y {
+            return Self::default();
+        }
+        Self::new(min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+}
+
+/// The number of vertices ellipses are approximated with.
+const ELLIPSE_SEGMENTS: usize = 32;
+
+impl ObjectShape {
+    /// Returns the outline of the shape, relative to the top-left corner of its unrotated bounding
+    /// box: The four corners of rectangles and texts in clockwise order, the points of polygons
+    /// and polylines, and a single point at the origin for points.
+    ///
+    /// Ellipses are approximated by a polygon of 32 vertices.
+    pub fn vertices(&self) -> Vec<(f32, f32)> {
+        match self {
+            ObjectShape::Rect { width, height } | ObjectShape::Text { width, height, .. } => {
+                vec![(0.0, 0.0), (*width, 0.0), (*width, *height), (0.0, *height)]
+            }
+            ObjectShape::Ellipse { width, height } => {
+                let (radius_x, radius_y) = (width / 2.0, height / 2.0);
+                (0..ELLIPSE_SEGMENTS)
+                    .map(|i| {
+                        let angle = i as f32 * std::f32::consts::TAU / ELLIPSE_SEGMENTS as f32;
+                        let (sin, cos) = angle.sin_cos();
+                        (radius_x + radius_x * cos, radius_y + radius_y * sin)
+                    })
+                    .collect()
+            }
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => points.clone(),
+            ObjectShape::Point(..) => vec![(0.0, 0.0)],
+        }
+    }
+
+    /// Returns the smallest axis-aligned rectangle containing the unrotated shape, relative to the
+    /// position of its object. Also see [`Object::bounding_box()`], which is in map coordinates.
+    pub fn bounding_rect(&self) -> BoundingBox {
+        match self {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => {
+                BoundingBox::new(0.0, 0.0, *width, *height)
+            }
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                BoundingBox::from_points(points.iter().copied())
+            }
+            ObjectShape::Point(..) => BoundingBox::default(),
+        }
+    }
+
+    /// Returns whether the point given, relative to the top-left corner of the unrotated bounding
+    /// box of the shape, is inside the shape or on its edges.
+    ///
+    /// Polylines and points have no area, so they never contain any point.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        match self {
+            ObjectShape::Rect { .. } | ObjectShape::Text { .. } => {
+                self.bounding_rect().contains_point(x, y)
+            }
+            ObjectShape::Ellipse { width, height } => {
+                let (radius_x, radius_y) = (width / 2.0, height / 2.0);
+                if radius_x <= 0.0 || radius_y <= 0.0 {
+                    return false;
+                }
+                let (dx, dy) = ((x - radius_x) / radius_x, (y - radius_y) / radius_y);
+                dx * dx + dy * dy <= 1.0
+            }
+            ObjectShape::Polygon { points } => polygon_contains_point(points, x, y),
+            ObjectShape::Polyline { .. } | ObjectShape::Point(..) => false,
+        }
+    }
+}
+
+/// Tests whether a point is inside a polygon with the even-odd rule.
+fn polygon_contains_point(points: &[(f32, f32)], x: f32, y: f32) -> bool {
+    let mut inside = false;
+    let mut previous = match points.last() {
+        Some(&point) => point,
+        None => return false,
+    };
+    for &(point_x, point_y) in points {
+        let (previous_x, previous_y) = previous;
+        if (point_y > y) != (previous_y > y)
+            && x < (previous_x - point_x) * (y - point_y) / (previous_y - point_y) + point_x
+        {
+            inside = !inside;
+        }
+        previous = (point_x, point_y);
+    }
+    inside
+}
+
+impl Object<'_> {
+    /// Returns the [outline](ObjectShape::vertices) of the object in map pixel coordinates, as
+    /// Tiled displays it, taking its position, [rotation](crate::ObjectData::rotation) and
+    /// [pivot](Object::pivot) into account.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_rotation.tmx")
+    ///     .unwrap();
+    /// let crate_object = map.objects().find(|(_, object)| object.name == "crate").unwrap().1;
+    ///
+    /// // The 32x16 crate is rotated by 90 degrees around its top-left corner at (32, 32).
+    /// let expected = [(32.0, 32.0), (32.0, 64.0), (16.0, 64.0), (16.0, 32.0)];
+    /// let vertices = crate_object.world_vertices();
+    /// for ((x, y), (expected_x, expected_y)) in vertices.into_iter().zip(expected) {
+    ///     assert!((x - expected_x).abs() < 1e-4 && (y - expected_y).abs() < 1e-4);
+    /// }
+    /// # }
+    /// ```
+    pub fn world_vertices(&self) -> Vec<(f32, f32)> {
+        let (pivot_x, pivot_y) = self.pivot();
+        self.shape
+            .vertices()
+            .into_iter()
+            .map(|(x, y)| self.apply_rotation((self.x + x - pivot_x, self.y + y - pivot_y)))
+            .collect()
+    }
+
+    /// Returns whether the point given in map pixel coordinates is inside the object as Tiled
+    /// displays it, taking its [rotation](crate::ObjectData::rotation) and [pivot](Object::pivot)
+    /// into account. Also see [`ObjectShape::contains_point()`].
+    ///
+    /// Unlike [`ObjectLayer::objects_at_point()`], which tests bounding boxes, this follows the
+    /// actual shape of ellipses, polygons and rotated objects.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (dx, dy) = (x - self.x, y - self.y);
+        let (pivot_x, pivot_y) = self.pivot();
+        self.shape
+            .contains_point(dx * cos + dy * sin + pivot_x, dy * cos - dx * sin + pivot_y)
+    }
+
+    /// Returns the smallest axis-aligned rectangle containing the object as Tiled displays it, in
+    /// map pixel coordinates, taking its [rotation](crate::ObjectData::rotation) and
+    /// [pivot](Object::pivot) into account.
+    ///
+    /// The rectangle bounds the [outline](Object::world_vertices) of the object, so the one of an
+    /// ellipse bounds its approximation. Point objects have an empty bounding box at their
+    /// position.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_rotation.tmx")
+    ///     .unwrap();
+    /// let chest = map.objects().find(|(_, object)| object.name == "chest").unwrap().1;
+    ///
+    /// // The 32x32 tile object is rotated by 90 degrees around its bottom-left corner at (64, 96),
+    /// // so it ends up below and to the right of it.
+    /// let bounds = chest.bounding_box();
+    /// assert!((bounds.x - 64.0).abs() < 1e-4 && (bounds.y - 96.0).abs() < 1e-4);
+    /// assert!((bounds.width - 32.0).abs() < 1e-4 && (bounds.height - 32.0).abs() < 1e-4);
+    /// # }
+    /// ```
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(self.world_vertices())
+    }
+}
+
+impl<'map> ObjectLayer<'map> {
+    /// Returns the objects of the layer whose [bounding box](Object::bounding_box) overlaps the
+    /// rectangle given, in the order they were declared in the file.
+    ///
+    /// The first spatial query on a layer builds an index of the bounding boxes of its objects,
+    /// which later queries reuse, so that they don't need to go through every object. The index is
+    /// discarded when objects are added through [`ObjectLayerMut`](crate::ObjectLayerMut).
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{BoundingBox, Loader};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_object_rotation.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    ///
+    /// for object in layer.objects_in_rect(BoundingBox::new(0.0, 0.0, 64.0, 64.0)) {
+    ///     println!("{} is in the top-left corner", object.name);
+    /// }
+    /// # }
+    /// ```
+    pub fn objects_in_rect(&self, rect: BoundingBox) -> impl Iterator<Item = Object<'map>> + 'map {
+        let map = self.map;
+        let objects = &self.data.objects;
+        self.index()
+            .query(&rect, |bounds| bounds.intersects(&rect))
+            .into_iter()
+            .map(move |index| Object::new(map, &objects[index]))
+    }
+
+    /// Returns the objects of the layer whose [bounding box](Object::bounding_box) contains the
+    /// point given, in the order they were declared in the file. Also see
+    /// [`objects_in_rect()`](Self::objects_in_rect).
+    pub fn objects_at_point(&self, x: f32, y: f32) -> impl Iterator<Item = Object<'map>> + 'map {
+        let map = self.map;
+        let objects = &self.data.objects;
+        self.index()
+            .query(&BoundingBox::new(x, y, 0.0, 0.0), |bounds| {
+                bounds.contains_point(x, y)
+            })
+            .into_iter()
+            .map(move |index| Object::new(map, &objects[index]))
+    }
+
+    fn index(&self) -> &'map ObjectGrid {
+        let layer = *self;
+        self.data.index.0.get_or_init(|| ObjectGrid::new(layer))
+    }
+}
+
+/// A tile found by [`Layer::tiles_in_rect()`], along with where it is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedTile<'map> {
+    /// The tile layer the tile is in, which may be nested in the group layer that was queried.
+    pub layer: Layer<'map>,
+    /// The tile itself.
+    pub tile: LayerTile<'map>,
+    /// The X coordinate of the tile within its layer.
+    pub x: i32,
+    /// The Y coordinate of the tile within its layer.
+    pub y: i32,
+    /// The rectangle the image of the tile is drawn in, in world pixels, as given by
+    /// [`Tileset::tile_draw_rect()`](crate::Tileset::tile_draw_rect) and moved by the offsets
+    /// and parallax scrolling of its layer.
+    pub rect: BoundingBox,
+}
+
+impl<'map> Layer<'map> {
+    /// Returns the tiles whose image overlaps the rectangle given, in world pixels, for tile
+    /// layers as well as every tile layer nested in group layers. Other kinds of layers have no
+    /// tiles.
+    ///
+    /// Finite and infinite layers are queried the same way, for every map orientation, so that
+    /// cameras can cull tiles without knowing how each layer is laid out. Tiles are placed as in
+    /// [`TileLayer::meshes()`](crate::TileLayer::meshes), then moved by the offsets of their layer and its parents and by
+    /// parallax scrolling relative to the camera given, as in [`Layer::to_affine()`]. Images
+    /// larger than the map's tiles are found even when their cell is outside of the rectangle.
+    ///
+    /// Tiles are returned layer by layer in drawing order, and row by row within a layer.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{BoundingBox, Loader};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_base64.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap();
+    ///
+    /// // The top-left 2x2 tiles of the map.
+    /// let tiles = layer.tiles_in_rect(BoundingBox::new(0.0, 0.0, 64.0, 64.0), (0.0, 0.0));
+    /// assert_eq!(tiles.len(), 4);
+    /// assert_eq!(tiles[3].rect, BoundingBox::new(32.0, 32.0, 32.0, 32.0));
+    /// # }
+    /// ```
+    pub fn tiles_in_rect(&self, rect: BoundingBox, camera: (f32, f32)) -> Vec<PlacedTile<'map>> {
+        let mut tiles = Vec::new();
+        self.push_tiles_in_rect(&rect, camera, &mut tiles);
+        tiles
+    }
+
+    fn push_tiles_in_rect(
+        &self,
+        rect: &BoundingBox,
+        camera: (f32, f32),
+        tiles: &mut Vec<PlacedTile<'map>>,
+    ) {
+        let tile_layer = match self.layer_type() {
+            LayerType::Tiles(tile_layer) => tile_layer,
+            LayerType::Group(group) => {
+                for layer in group.layers() {
+                    layer.push_tiles_in_rect(rect, camera, tiles);
+                }
+                return;
+            }
+            LayerType::Objects(_) | LayerType::Image(_) => return,
+        };
+
+        let attributes = self.effective_attributes();
+        let map = self.map;
+        let shift_x = attributes.offset_x
+            + (camera.0 - map.parallax_origin_x) * (1.0 - attributes.parallax_x);
+        let shift_y = attributes.offset_y
+            + (camera.1 - map.parallax_origin_y) * (1.0 - attributes.parallax_y);
+        let (margin_x, margin_y) = draw_margins(map);
+        let cells = cells_in_rect(
+            map,
+            &BoundingBox::new(
+                rect.x - shift_x - margin_x,
+                rect.y - shift_y - margin_y,
+                rect.width + 2.0 * margin_x,
+                rect.height + 2.0 * margin_y,
+            ),
+        );
+
+        for (x, y, tile) in tile_layer.tiles_within(cells) {
+            let (cell_x, cell_y) = map.tile_to_world(x, y);
+            let cell = BoundingBox::new(
+                cell_x,
+                cell_y,
+                map.tile_width as f32,
+                map.tile_height as f32,
+            );
+            let Some(mut drawn) = tile.get_tileset().tile_draw_rect(tile.id(), cell) else {
+                continue;
+            };
+            drawn.x += shift_x;
+            drawn.y += shift_y;
+            // Tiles that only touch the edges of the rectangle aren't visible in it.
+            let overlaps = drawn.x < rect.x + rect.width
+                && rect.x < drawn.x + drawn.width
+                && drawn.y < rect.y + rect.height
+                && rect.y < drawn.y + drawn.height;
+            if overlaps {
+                tiles.push(PlacedTile {
+                    layer: *self,
+                    tile,
+                    x,
+                    y,
+                    rect: drawn,
+                });
+            }
+        }
+    }
+}
+
+/// Returns how far the image of a tile of the map may be drawn from its cell, horizontally and
+/// vertically, which is at most the size of the largest tile image along with its tileset offset.
+fn draw_margins(map: &Map) -> (f32, f32) {
+    map.tilesets()
+        .iter()
+        .map(|tileset| {
+            let (width, height) = if tileset.image.is_some() {
+                (tileset.tile_width, tileset.tile_height)
+            } else {
+                tileset
+                    .tiles()
+                    .filter_map(|(_, tile)| {
+                        let image = tile.image.as_ref()?;
+                        Some((image.width as u32, image.height as u32))
+                    })
+                    .fold((0, 0), |(width, height), (image_width, image_height)| {
+                        (width.max(image_width), height.max(image_height))
+                    })
+            };
+            (
+                width as f32 + tileset.offset_x.unsigned_abs() as f32,
+                height as f32 + tileset.offset_y.unsigned_abs() as f32,
+            )
+        })
+        .fold((0.0, 0.0), |(x, y): (f32, f32), (width, height)| {
+            (x.max(width), y.max(height))
+        })
+}
+
+/// Returns the tile cells covering the rectangle given, in world pixels, with a cell of margin for
+/// the shifted rows and columns of staggered and hexagonal maps.
+fn cells_in_rect(map: &Map, rect: &BoundingBox) -> TileRect {
+    let corners = [
+        map.world_to_tile(rect.x, rect.y),
+        map.world_to_tile(rect.x + rect.width, rect.y),
+        map.world_to_tile(rect.x, rect.y + rect.height),
+        map.world_to_tile(rect.x + rect.width, rect.y + rect.height),
+    ];
+    let min_x = corners.iter().map(|&(x, _)| x).min().unwrap_or(0) - 1;
+    let min_y = corners.iter().map(|&(_, y)| y).min().unwrap_or(0) - 1;
+    let max_x = corners.iter().map(|&(x, _)| x).max().unwrap_or(0) + 1;
+    let max_y = corners.iter().map(|&(_, y)| y).max().unwrap_or(0) + 1;
+    TileRect {
+        x: min_x,
+        y: min_y,
+        width: (max_x - min_x + 1) as u32,
+        height: (max_y - min_y + 1) as u32,
+    }
+}
+
+/// The lazily built spatial index of an object layer. Never affects comparisons between layers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ObjectIndex(OnceLock<ObjectGrid>);
+
+impl ObjectIndex {
+    /// Discards the index, which is rebuilt on the next query.
+    pub(crate) fn invalidate(&mut self) {
+        self.0 = OnceLock::new();
+    }
+}
+
+impl PartialEq for ObjectIndex {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// The maximum number of cells an object is stored in. Larger objects are checked on every query.
+const MAX_CELLS_PER_OBJECT: i64 = 64;
+
+/// A uniform grid storing the objects overlapping each of its cells.
+#[derive(Debug, Clone)]
+struct ObjectGrid {
+    cell_width: f32,
+    cell_height: f32,
+    bounds: Vec<BoundingBox>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    large: Vec<usize>,
+}
+
+impl ObjectGrid {
+    fn new(layer: ObjectLayer) -> Self {
+        // Cells of a few tiles are a good fit for the collision and trigger areas maps contain.
+        let mut grid = Self {
+            cell_width: (layer.map.tile_width.max(1) * 4) as f32,
+            cell_height: (layer.map.tile_height.max(1) * 4) as f32,
+            bounds: layer
+                .objects()
+                .map(|object| object.bounding_box())
+                .collect(),
+            cells: HashMap::new(),
+            large: Vec::new(),
+        };
+        for index in 0..grid.bounds.len() {
+            let (min, max) = grid.cell_range(&grid.bounds[index]);
+            if grid.cell_count(min, max) > MAX_CELLS_PER_OBJECT {
+                grid.large.push(index);
+                continue;
+            }
+            for y in min.1..=max.1 {
+                for x in min.0..=max.0 {
+                    grid.cells.entry((x, y)).or_default().push(index);
+                }
+            }
+        }
+        grid
+    }
+
+    fn cell_range(&self, bounds: &BoundingBox) -> ((i32, i32), (i32, i32)) {
+        let cell = |x: f32, y: f32| {
+            (
+                (x / self.cell_width).floor() as i32,
+                (y / self.cell_height).floor() as i32,
+            )
+        };
+        (
+            cell(bounds.x, bounds.y),
+            cell(bounds.x + bounds.width, bounds.y + bounds.height),
+        )
+    }
+
+    fn cell_count(&self, min: (i32, i32), max: (i32, i32)) -> i64 {
+        (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1)
+    }
+
+    /// Returns the indices of the objects near the area given whose bounds match the filter, in
+    /// ascending order.
+    fn query(&self, area: &BoundingBox, filter: impl Fn(&BoundingBox) -> bool) -> Vec<usize> {
+        let (min, max) = self.cell_range(area);
+        let mut found: Vec<usize> = if self.cell_count(min, max) > self.cells.len() as i64 {
+            // Visiting every cell of large areas would be slower than checking every object.
+            (0..self.bounds.len()).collect()
+        } else {
+            let mut found = self.large.clone();
+            for y in min.1..=max.1 {
+                for x in min.0..=max.0 {
+                    found.extend(self.cells.get(&(x, y)).into_iter().flatten());
+                }
+            }
+            found.sort_unstable();
+            found.dedup();
+            found
+        };
+        found.retain(|&index| filter(&self.bounds[index]));
+        found
+    }
+}
@@ -0,0 +1,121 @@
+//! Structures related to reading maps piece by piece, without building a [`Map`](crate::Map).
+
+use std::sync::Arc;
+
+use crate::{ObjectData, Orientation, Properties, StreamedChunk, Tileset};
+
+/// The attributes of a map being streamed, passed to [`MapStreamVisitor::visit_map()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamedMap {
+    /// The way tiles are laid out in the map.
+    pub orientation: Orientation,
+    /// Width of the map, in tiles.
+    pub width: u32,
+    /// Height of the map, in tiles.
+    pub height: u32,
+    /// Tile width, in pixels.
+    pub tile_width: u32,
+    /// Tile height, in pixels.
+    pub tile_height: u32,
+    /// Whether the map is infinite, in which case the tiles of its tile layers are delivered
+    /// chunk by chunk.
+    pub infinite: bool,
+}
+
+/// The attributes of a layer being streamed, passed to [`MapStreamVisitor::visit_layer()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedLayer {
+    /// The ID of the layer, or 0 if the file doesn't specify it.
+    pub id: u32,
+    /// The name of the layer.
+    pub name: String,
+    /// Whether the layer is shown or hidden in the editor.
+    pub visible: bool,
+    /// The kind of layer, which determines the callbacks its contents are delivered through.
+    pub layer_type: StreamedLayerType,
+}
+
+/// The kind of a [`StreamedLayer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamedLayerType {
+    /// A tile layer, whose tiles are delivered through [`MapStreamVisitor::visit_chunk()`].
+    Tiles {
+        /// The width of the layer, in tiles.
+        width: u32,
+        /// The height of the layer, in tiles.
+        height: u32,
+    },
+    /// An object layer, whose objects are delivered through [`MapStreamVisitor::visit_object()`].
+    Objects,
+    /// An image layer. Its image isn't delivered.
+    Image,
+    /// A group layer, whose children are visited before it is left.
+    Group,
+}
+
+/// A type that receives the contents of a map as they are read with
+/// [`Loader::stream_tmx_map()`](crate::Loader::stream_tmx_map), instead of them being stored in a
+/// [`Map`](crate::Map).
+///
+/// Every method has an empty default implementation, so implementors only need to override the
+/// callbacks they are interested in. Callbacks are called in file order.
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, MapStreamVisitor, ObjectData, StreamedLayer, StreamedLayerType};
+///
+/// # fn main() {
+/// /// Collects the names of objects without decoding any tile data.
+/// #[derive(Default)]
+/// struct ObjectNames(Vec<String>);
+///
+/// impl MapStreamVisitor for ObjectNames {
+///     fn visit_layer(&mut self, layer: &StreamedLayer) -> bool {
+///         !matches!(layer.layer_type, StreamedLayerType::Tiles { .. })
+///     }
+///
+///     fn visit_object(&mut self, _layer_id: u32, object: &ObjectData) {
+///         self.0.push(object.name.clone());
+///     }
+/// }
+///
+/// let mut names = ObjectNames::default();
+/// Loader::new()
+///     .stream_tmx_map("assets/tiled_object_rotation.tmx", &mut names)
+///     .unwrap();
+/// assert_eq!(names.0, ["crate", "chest", "marker"]);
+/// # }
+/// ```
+#[allow(unused_variables)]
+pub trait MapStreamVisitor {
+    /// Called once before anything else, with the attributes of the map.
+    fn visit_map(&mut self, map: &StreamedMap) {}
+
+    /// Called for each of the map's tilesets, in order, before the first layer is visited.
+    fn visit_tileset(&mut self, index: usize, tileset: &Arc<Tileset>) {}
+
+    /// Called for every set of custom properties of the map or of a layer, along with the ID of
+    /// the layer, or [`None`] for the properties of the map. Properties are found before the
+    /// contents of the layer they belong to.
+    fn visit_properties(&mut self, layer_id: Option<u32>, properties: &Properties) {}
+
+    /// Called when a layer is found, including nested ones, before its contents. Returning
+    /// `false` skips the contents of the layer without decoding them, including its properties
+    /// and the children of group layers. Returns `true` by default.
+    fn visit_layer(&mut self, layer: &StreamedLayer) -> bool {
+        true
+    }
+
+    /// Called once the contents of a layer have been read or skipped. Every call to
+    /// [`Self::visit_layer()`] is paired with a call to this method, and the children of a group
+    /// layer are visited in between.
+    fn leave_layer(&mut self, layer: &StreamedLayer) {}
+
+    /// Called with the tiles of tile layers. Finite layers are delivered as a single chunk
+    /// spanning the whole layer, and infinite ones chunk by chunk, as they are stored in the
+    /// file. The tiles are discarded once this method returns.
+    fn visit_chunk(&mut self, chunk: StreamedChunk<'_>) {}
+
+    /// Called for every object of an object layer, along with the ID of the layer.
+    fn visit_object(&mut self, layer_id: u32, object: &ObjectData) {}
+}
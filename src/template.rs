@@ -5,8 +5,8 @@ use quick_xml::events::Event;
 
 use crate::parse::xml::{Parser, ReadFrom, Reader};
 use crate::{
-    util::*, EmbeddedParseResultType, Error, MapTilesetGid, ObjectData, ResourceCache, Result,
-    Tileset,
+    util::*, EmbeddedParseResultType, Error, LoaderOptions, MapTilesetGid, ObjectData,
+    ResourceCache, Result, Tileset,
 };
 
 /// A template, consisting of an object and a tileset
@@ -14,6 +14,7 @@ use crate::{
 /// Templates define a tileset and object data to use for an object that can be shared between multiple objects and
 /// maps.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Template {
     /// The tileset this template contains a reference to
     pub tileset: Option<Arc<Tileset>>,
@@ -22,10 +23,54 @@ pub struct Template {
 }
 
 impl Template {
+    /// Creates a new object from this template, with the template's shape, tile and properties,
+    /// placed at the given position in pixels.
+    ///
+    /// The object has an ID of 0 until it is added to a map, e.g. through
+    /// [`ObjectLayerMut::spawn_from_template()`](crate::ObjectLayerMut::spawn_from_template)
+    /// which assigns it a fresh one.
+    pub fn instantiate_at(&self, x: f32, y: f32) -> ObjectData {
+        ObjectData {
+            id: 0,
+            x,
+            y,
+            ..self.object.clone()
+        }
+    }
+
+    /// Loads the template at the path given, or fetches it from the cache. Templates in the JSON
+    /// format are parsed as JSON if the `json` feature is enabled, and any other file as XML.
+    pub(crate) async fn load_cached(
+        path: &Path,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
+        options: &LoaderOptions,
+    ) -> Result<Arc<Template>> {
+        if let Some(template) = cache.get_template(path) {
+            debug_event!(path = %path.display(), "template cache hit");
+            return Ok(template);
+        }
+        debug_event!(path = %path.display(), "template cache miss");
+        #[cfg(feature = "json")]
+        let template = if is_json_resource(path) {
+            crate::parse::json::parse_template(path, read_from, cache, options).await?
+        } else {
+            Self::parse_template(path, read_from, cache, options).await?
+        };
+        #[cfg(not(feature = "json"))]
+        let template = Self::parse_template(path, read_from, cache, options).await?;
+        Ok(cache.get_or_insert_template(path, template))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "parse_template", level = "debug", skip_all, fields(path = %path.display()))
+    )]
     pub(crate) async fn parse_template(
         path: &Path,
         read_from: &mut impl ReadFrom,
         cache: &mut impl ResourceCache,
+        options: &LoaderOptions,
     ) -> Result<Arc<Template>> {
         // Open the template file
         let mut file =
@@ -38,19 +83,21 @@ impl Template {
                 })?;
         let mut buffer = Vec::new();
         loop {
+            let position = file.buffer_position();
             let next = file
                 .read_event_into(&mut buffer)
                 .await
                 .map_err(Error::XmlDecodingError)?;
             match next {
                 Event::Start(start) if start.local_name().into_inner() == b"template" => {
-                    let template = Self::parse_external_template(
-                        &mut Parser::with_reader(file),
-                        path,
-                        read_from,
-                        cache,
-                    )
-                    .await?;
+                    let mut parser = Parser::with_reader(file);
+                    parser.options = options.clone();
+                    parser.directory = path.parent().map(Path::to_owned).unwrap_or_default();
+                    parser.path = path.to_owned();
+                    parser.element_position = position;
+                    parser.check_element("", &start)?;
+                    let template =
+                        Self::parse_external_template(&mut parser, path, read_from, cache).await?;
                     return Ok(template);
                 }
                 Event::Eof => {
@@ -92,11 +139,12 @@ impl Template {
                 match res.result_type {
                     EmbeddedParseResultType::ExternalReference { tileset_path } => {
                         tileset = Some(if let Some(ts) = cache.get_tileset(&tileset_path) {
+                            debug_event!(path = %tileset_path.display(), "tileset cache hit");
                             ts
                         } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, read_from, cache).await?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
-                            tileset
+                            debug_event!(path = %tileset_path.display(), "tileset cache miss");
+                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, read_from, cache, &parser.options).await?);
+                            cache.get_or_insert_tileset(&tileset_path, tileset)
                         });
                     }
                     EmbeddedParseResultType::Embedded { tileset: embedded_tileset } => {
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
 use quick_xml::events::attributes::Attribute;
 
@@ -10,14 +10,15 @@ use crate::{
     parse::xml::{Parser, ReadFrom, Reader},
     properties::{parse_properties, Properties},
     util::{get_attrs, parse_tag},
-    ResourceCache, Result, Tileset,
+    ParseWarningKind, ResourceCache, Result, Tileset,
 };
 
 /// A tile ID, local to a tileset.
 pub type TileId = u32;
 
 /// Raw data belonging to a tile.
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileData {
     /// The image of the tile. Only set when the tile is part of an "image collection" tileset.
     pub image: Option<Image>,
@@ -28,11 +29,24 @@ pub struct TileData {
     /// The animation frames of this tile.
     pub animation: Option<Vec<Frame>>,
     /// The type of this tile.
-    pub user_type: Option<String>,
+    pub user_type: Option<Arc<str>>,
     /// The probability of this tile.
     pub probability: f32,
 }
 
+impl Default for TileData {
+    fn default() -> Self {
+        Self {
+            image: None,
+            properties: Properties::default(),
+            collision: None,
+            animation: None,
+            user_type: None,
+            probability: 1.0,
+        }
+    }
+}
+
 /// Points to a tile belonging to a tileset.
 #[derive(Debug)]
 pub struct Tile<'tileset> {
@@ -70,14 +84,21 @@ impl TileData {
     ) -> Result<(TileId, TileData)> {
         let ((user_type, user_class, probability), id) = get_attrs!(
             for v in attrs {
-                Some("type") => user_type ?= v.parse(),
-                Some("class") => user_class ?= v.parse(),
+                Some("type") => user_type = v,
+                Some("class") => user_class = v,
                 Some("probability") => probability ?= v.parse(),
                 "id" => id ?= v.parse::<u32>(),
             }
             ((user_type, user_class, probability), id)
         );
-        let user_type = user_type.or(user_class);
+        if user_class.is_some() {
+            parser.warn(ParseWarningKind::DeprecatedAttribute {
+                element: "tile".to_owned(),
+                name: "class".to_owned(),
+                replacement: "type".to_owned(),
+            });
+        }
+        let user_type = user_type.or(user_class).map(|t| parser.interner.intern(t));
         let mut image = Option::None;
         let mut properties = HashMap::new();
         let mut objectgroup = None;
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 
 use quick_xml::events::attributes::Attribute;
 
@@ -7,8 +10,12 @@ use crate::error::{Error, Result};
 use crate::image::Image;
 use crate::parse::xml::{Parser, ReadFrom, Reader};
 use crate::properties::{parse_properties, Properties};
+use crate::spatial::BoundingBox;
 use crate::tile::TileData;
-use crate::{util::*, Gid, InvalidTilesetError, ResourceCache, Tile, TileId};
+use crate::{
+    util::*, Gid, InvalidTilesetError, LoaderOptions, Orientation, ParseWarningKind, ResourceCache,
+    Tile, TileId,
+};
 
 mod wangset;
 pub use wangset::*;
@@ -17,6 +24,7 @@ pub use wangset::*;
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tileset).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tileset {
     /// The name of the tileset, set by the user.
     pub name: String,
@@ -58,16 +66,28 @@ pub struct Tileset {
     pub image: Option<Image>,
 
     /// All the tiles present in this tileset, indexed by their local IDs.
-    tiles: HashMap<TileId, TileData>,
+    pub(crate) tiles: HashMap<TileId, TileData>,
 
     /// All the wangsets present in this tileset.
     pub wang_sets: Vec<WangSet>,
 
+    /// The ways the tiles of this tileset may be flipped and rotated when they are placed.
+    pub transformations: TilesetTransformations,
+
+    /// The size tiles of this tileset are drawn at: Their own, or the one of the map's grid.
+    pub tile_render_size: TileRenderSize,
+    /// How tiles are scaled when drawn at the size of the map's grid. Only relevant if the
+    /// [render size](Self::tile_render_size) is [`TileRenderSize::Grid`].
+    pub fill_mode: FillMode,
+    /// The point of the tiles of this tileset that tile objects using them are positioned and
+    /// rotated around.
+    pub object_alignment: ObjectAlignment,
+
     /// The custom properties of the tileset.
     pub properties: Properties,
 
     /// The custom tileset type, arbitrarily set by the user.
-    pub user_type: Option<String>,
+    pub user_type: Option<Arc<str>>,
 }
 
 // FIXME: box large enum variant?
@@ -89,13 +109,296 @@ struct TilesetProperties {
     tilecount: u32,
     columns: Option<u32>,
     name: String,
-    user_type: Option<String>,
+    user_type: Option<Arc<str>>,
     tile_width: u32,
     tile_height: u32,
+    tile_render_size: TileRenderSize,
+    fill_mode: FillMode,
+    object_alignment: ObjectAlignment,
     /// The root all non-absolute paths contained within the tileset are relative to.
     root_path: PathBuf,
 }
 
+/// The ways the tiles of a tileset may be transformed when they are placed, which the editor uses
+/// to pick flipped and rotated variants of tiles when painting with terrains, and which runtime
+/// auto-tiling can use likewise.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#transformations).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TilesetTransformations {
+    /// Whether tiles can be flipped horizontally.
+    pub hflip: bool,
+    /// Whether tiles can be flipped vertically.
+    pub vflip: bool,
+    /// Whether tiles can be rotated in 90-degree increments.
+    pub rotate: bool,
+    /// Whether untransformed tiles are preferred over transformed variants of other tiles that
+    /// would fit as well.
+    pub prefer_untransformed: bool,
+}
+
+/// The size the tiles of a tileset are drawn at.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileRenderSize {
+    /// Tiles are drawn at their own size, which may exceed the one of the map's grid.
+    #[default]
+    Tile,
+    /// Tiles are scaled to the size of the map's grid, according to the tileset's [`FillMode`].
+    Grid,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`TileRenderSize`] that is not valid.
+pub struct TileRenderSizeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for TileRenderSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse tile render size, valid options are `tile`, `grid` but got `{}` \
+            instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for TileRenderSizeError {}
+
+impl FromStr for TileRenderSize {
+    type Err = TileRenderSizeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "tile" => Ok(TileRenderSize::Tile),
+            "grid" => Ok(TileRenderSize::Grid),
+            _ => Err(TileRenderSizeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for TileRenderSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileRenderSize::Tile => write!(f, "tile"),
+            TileRenderSize::Grid => write!(f, "grid"),
+        }
+    }
+}
+
+/// The way tiles are scaled when drawn at the size of the map's grid.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillMode {
+    /// Tiles are stretched to fill the whole grid cell.
+    #[default]
+    Stretch,
+    /// Tiles are scaled as much as possible while keeping their aspect ratio, and centered in the
+    /// grid cell.
+    PreserveAspectFit,
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`FillMode`] that is not valid.
+pub struct FillModeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for FillModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse fill mode, valid options are `stretch`, `preserve-aspect-fit` but \
+            got `{}` instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for FillModeError {}
+
+impl FromStr for FillMode {
+    type Err = FillModeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "stretch" => Ok(FillMode::Stretch),
+            "preserve-aspect-fit" => Ok(FillMode::PreserveAspectFit),
+            _ => Err(FillModeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for FillMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FillMode::Stretch => write!(f, "stretch"),
+            FillMode::PreserveAspectFit => write!(f, "preserve-aspect-fit"),
+        }
+    }
+}
+
+/// The point of a tile that tile objects using it are positioned and rotated around, as set by
+/// the tileset the tile is from.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ObjectAlignment {
+    /// The bottom-left corner of the tile in orthogonal maps, and its bottom-center in isometric
+    /// ones.
+    #[default]
+    Unspecified,
+    /// The top-left corner of the tile.
+    TopLeft,
+    /// The center of the top edge of the tile.
+    Top,
+    /// The top-right corner of the tile.
+    TopRight,
+    /// The center of the left edge of the tile.
+    Left,
+    /// The center of the tile.
+    Center,
+    /// The center of the right edge of the tile.
+    Right,
+    /// The bottom-left corner of the tile.
+    BottomLeft,
+    /// The center of the bottom edge of the tile.
+    Bottom,
+    /// The bottom-right corner of the tile.
+    BottomRight,
+}
+
+impl ObjectAlignment {
+    /// Returns the position of the alignment point within a tile object of the size given, relative
+    /// to its top-left corner, in a map of the orientation given.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{ObjectAlignment, Orientation};
+    ///
+    /// assert_eq!(ObjectAlignment::Center.offset(32.0, 16.0, Orientation::Orthogonal), (16.0, 8.0));
+    /// assert_eq!(
+    ///     ObjectAlignment::Unspecified.offset(32.0, 16.0, Orientation::Isometric),
+    ///     (16.0, 16.0)
+    /// );
+    /// ```
+    pub fn offset(self, width: f32, height: f32, orientation: Orientation) -> (f32, f32) {
+        let alignment = match (self, orientation) {
+            (ObjectAlignment::Unspecified, Orientation::Isometric) => ObjectAlignment::Bottom,
+            (ObjectAlignment::Unspecified, _) => ObjectAlignment::BottomLeft,
+            (alignment, _) => alignment,
+        };
+        let (x, y) = match alignment {
+            ObjectAlignment::TopLeft => (0.0, 0.0),
+            ObjectAlignment::Top => (0.5, 0.0),
+            ObjectAlignment::TopRight => (1.0, 0.0),
+            ObjectAlignment::Left => (0.0, 0.5),
+            ObjectAlignment::Center => (0.5, 0.5),
+            ObjectAlignment::Right => (1.0, 0.5),
+            ObjectAlignment::Unspecified | ObjectAlignment::BottomLeft => (0.0, 1.0),
+            ObjectAlignment::Bottom => (0.5, 1.0),
+            ObjectAlignment::BottomRight => (1.0, 1.0),
+        };
+        (width * x, height * y)
+    }
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse an [`ObjectAlignment`] that is not valid.
+pub struct ObjectAlignmentError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for ObjectAlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse object alignment, valid options are `unspecified`, `topleft`, `top`, \
+            `topright`, `left`, `center`, `right`, `bottomleft`, `bottom`, `bottomright` but got \
+            `{}` instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for ObjectAlignmentError {}
+
+impl FromStr for ObjectAlignment {
+    type Err = ObjectAlignmentError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "unspecified" => Ok(ObjectAlignment::Unspecified),
+            "topleft" => Ok(ObjectAlignment::TopLeft),
+            "top" => Ok(ObjectAlignment::Top),
+            "topright" => Ok(ObjectAlignment::TopRight),
+            "left" => Ok(ObjectAlignment::Left),
+            "center" => Ok(ObjectAlignment::Center),
+            "right" => Ok(ObjectAlignment::Right),
+            "bottomleft" => Ok(ObjectAlignment::BottomLeft),
+            "bottom" => Ok(ObjectAlignment::Bottom),
+            "bottomright" => Ok(ObjectAlignment::BottomRight),
+            _ => Err(ObjectAlignmentError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ObjectAlignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectAlignment::Unspecified => write!(f, "unspecified"),
+            ObjectAlignment::TopLeft => write!(f, "topleft"),
+            ObjectAlignment::Top => write!(f, "top"),
+            ObjectAlignment::TopRight => write!(f, "topright"),
+            ObjectAlignment::Left => write!(f, "left"),
+            ObjectAlignment::Center => write!(f, "center"),
+            ObjectAlignment::Right => write!(f, "right"),
+            ObjectAlignment::BottomLeft => write!(f, "bottomleft"),
+            ObjectAlignment::Bottom => write!(f, "bottom"),
+            ObjectAlignment::BottomRight => write!(f, "bottomright"),
+        }
+    }
+}
+
+/// A rectangle of an image, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    /// The X coordinate of the left edge of the rectangle.
+    pub x: u32,
+    /// The Y coordinate of the top edge of the rectangle.
+    pub y: u32,
+    /// The width of the rectangle.
+    pub width: u32,
+    /// The height of the rectangle.
+    pub height: u32,
+}
+
+/// A rectangle of an image in normalized texture coordinates, ranging from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UvRect {
+    /// The horizontal coordinate of the left edge of the rectangle.
+    pub u_min: f32,
+    /// The vertical coordinate of the top edge of the rectangle.
+    pub v_min: f32,
+    /// The horizontal coordinate of the right edge of the rectangle.
+    pub u_max: f32,
+    /// The vertical coordinate of the bottom edge of the rectangle.
+    pub v_max: f32,
+}
+
 impl Tileset {
     /// Gets the tile with the specified ID from the tileset.
     #[inline]
@@ -110,9 +413,211 @@ impl Tileset {
             .iter()
             .map(move |(id, data)| (*id, Tile::new(self, data)))
     }
+
+    /// Sets the data of the tile with the specified ID, returning its previous data if it had any.
+    pub fn insert_tile(&mut self, id: TileId, data: TileData) -> Option<TileData> {
+        self.tiles.insert(id, data)
+    }
+
+    /// Makes this tileset a regular tileset cut out of the image given.
+    ///
+    /// The [columns](Self::columns) and [tile count](Self::tilecount) are calculated from the size
+    /// of the image, the [tile size](Self::tile_width), the [spacing](Self::spacing) and the
+    /// [margin](Self::margin), so these should be set beforehand. Every tile of the image is then
+    /// added to the tileset, with default data unless it already has some.
+    pub fn set_image(&mut self, image: Image) {
+        let (spacing, margin) = (self.spacing, self.margin);
+        let fit = |size: i32, tile_size: u32| {
+            (size.max(0) as u32 + spacing)
+                .saturating_sub(margin)
+                .checked_div(tile_size + spacing)
+                .unwrap_or(0)
+        };
+        self.columns = fit(image.width, self.tile_width);
+        self.tilecount = self.columns * fit(image.height, self.tile_height);
+        self.image = Some(image);
+        for id in 0..self.tilecount {
+            self.tiles.entry(id).or_default();
+        }
+    }
+
+    /// Returns the rectangle of the tileset's image the tile with the specified ID is cut out of,
+    /// in pixels, taking the [margin](Self::margin) and [spacing](Self::spacing) into account.
+    ///
+    /// Returns [`None`] if the tileset has no image, i.e. is an image collection tileset, or if the
+    /// ID is outside of the tileset.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, Rect};
+    ///
+    /// # fn main() {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    /// assert_eq!(
+    ///     tileset.tile_rect(15),
+    ///     Some(Rect { x: 32, y: 32, width: 32, height: 32 })
+    /// );
+    /// assert_eq!(tileset.tile_rect(84), None);
+    /// # }
+    /// ```
+    pub fn tile_rect(&self, id: TileId) -> Option<Rect> {
+        if self.image.is_none() || id >= self.tilecount || self.columns == 0 {
+            return None;
+        }
+        let (column, row) = (id % self.columns, id / self.columns);
+        Some(Rect {
+            x: self.margin + column * (self.tile_width + self.spacing),
+            y: self.margin + row * (self.tile_height + self.spacing),
+            width: self.tile_width,
+            height: self.tile_height,
+        })
+    }
+
+    /// Returns the texture coordinates of the tile with the specified ID, given the size of the
+    /// tileset's image in pixels. Coordinates are normalized between 0 and 1, with the origin at
+    /// the top left of the image.
+    ///
+    /// The image size is taken as a parameter since the one stored in the tileset's [`Image`] may
+    /// be missing or differ from the one of the texture actually used.
+    ///
+    /// Returns [`None`] in the same cases as [`Tileset::tile_rect()`], or if the image is empty.
+    pub fn tile_uv(&self, id: TileId, image_width: u32, image_height: u32) -> Option<UvRect> {
+        if image_width == 0 || image_height == 0 {
+            return None;
+        }
+        let rect = self.tile_rect(id)?;
+        let (width, height) = (image_width as f32, image_height as f32);
+        Some(UvRect {
+            u_min: rect.x as f32 / width,
+            v_min: rect.y as f32 / height,
+            u_max: (rect.x + rect.width) as f32 / width,
+            v_max: (rect.y + rect.height) as f32 / height,
+        })
+    }
+
+    /// Returns the rectangle the tile with the specified ID should be drawn in, given the one of
+    /// the map grid cell it is placed in, taking the [render size](Self::tile_render_size),
+    /// [fill mode](Self::fill_mode) and [offset](Self::offset_x) of the tileset into account.
+    ///
+    /// As in Tiled, tiles drawn at their own size are aligned to the bottom-left corner of their
+    /// cell, and tiles scaled while preserving their aspect ratio are centered in it.
+    ///
+    /// Returns [`None`] if the ID is outside of the tileset, or if the tile has no image or an
+    /// empty one.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{BoundingBox, Loader, TileRenderSize};
+    ///
+    /// # fn main() {
+    /// let mut tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    /// let cell = BoundingBox::new(64.0, 64.0, 16.0, 16.0);
+    /// assert_eq!(
+    ///     tileset.tile_draw_rect(0, cell),
+    ///     Some(BoundingBox::new(64.0, 48.0, 32.0, 32.0))
+    /// );
+    ///
+    /// tileset.tile_render_size = TileRenderSize::Grid;
+    /// assert_eq!(tileset.tile_draw_rect(0, cell), Some(cell));
+    /// # }
+    /// ```
+    pub fn tile_draw_rect(&self, id: TileId, cell: BoundingBox) -> Option<BoundingBox> {
+        let (tile_width, tile_height) = self.tile_size(id)?;
+        let mut rect = match (self.tile_render_size, self.fill_mode) {
+            (TileRenderSize::Tile, _) => BoundingBox::new(
+                cell.x,
+                cell.y + cell.height - tile_height,
+                tile_width,
+                tile_height,
+            ),
+            (TileRenderSize::Grid, FillMode::Stretch) => cell,
+            (TileRenderSize::Grid, FillMode::PreserveAspectFit) => {
+                let scale = (cell.width / tile_width).min(cell.height / tile_height);
+                let (width, height) = (tile_width * scale, tile_height * scale);
+                BoundingBox::new(
+                    cell.x + (cell.width - width) / 2.0,
+                    cell.y + (cell.height - height) / 2.0,
+                    width,
+                    height,
+                )
+            }
+        };
+        rect.x += self.offset_x as f32;
+        rect.y += self.offset_y as f32;
+        Some(rect)
+    }
+
+    /// Returns the size of the image of the tile with the specified ID, in pixels, or [`None`] if
+    /// the tile has no image or an empty one.
+    pub(crate) fn tile_size(&self, id: TileId) -> Option<(f32, f32)> {
+        let (width, height) = if self.image.is_some() {
+            if id >= self.tilecount {
+                return None;
+            }
+            (self.tile_width as f32, self.tile_height as f32)
+        } else {
+            let image = self.tiles.get(&id)?.image.as_ref()?;
+            (image.width as f32, image.height as f32)
+        };
+        (width > 0.0 && height > 0.0).then_some((width, height))
+    }
 }
 
 impl Tileset {
+    /// Creates a tileset without any image or tile, e.g. to build a map in code.
+    ///
+    /// Give it an image through [`Tileset::set_image()`] to make it a regular tileset, or add tiles
+    /// with images to it through [`Tileset::insert_tile()`] to make it an image collection
+    /// tileset.
+    ///
+    /// Also used in place of tilesets that failed to load in lenient mode.
+    pub fn new(name: impl Into<String>, tile_width: u32, tile_height: u32) -> Self {
+        Self {
+            name: name.into(),
+            tile_width,
+            tile_height,
+            spacing: 0,
+            margin: 0,
+            tilecount: 0,
+            columns: 0,
+            offset_x: 0,
+            offset_y: 0,
+            image: None,
+            tiles: HashMap::new(),
+            wang_sets: Vec::new(),
+            transformations: TilesetTransformations::default(),
+            tile_render_size: TileRenderSize::default(),
+            fill_mode: FillMode::default(),
+            object_alignment: ObjectAlignment::default(),
+            properties: Properties::new(),
+            user_type: None,
+        }
+    }
+
+    /// Loads the external tileset at the path given, or fetches it from the cache. Tilesets in the
+    /// JSON format are parsed as JSON if the `json` feature is enabled, and any other file as XML.
+    pub(crate) async fn load_cached(
+        path: &Path,
+        read_from: &mut impl ReadFrom,
+        cache: &mut impl ResourceCache,
+        options: &LoaderOptions,
+    ) -> Result<Arc<Tileset>> {
+        if let Some(tileset) = cache.get_tileset(path) {
+            debug_event!(path = %path.display(), "tileset cache hit");
+            return Ok(tileset);
+        }
+        debug_event!(path = %path.display(), "tileset cache miss");
+        #[cfg(feature = "json")]
+        let tileset = if is_json_resource(path) {
+            crate::parse::json::parse_tileset(path, read_from, cache, options).await?
+        } else {
+            crate::parse::xml::parse_tileset(path, read_from, cache, options).await?
+        };
+        #[cfg(not(feature = "json"))]
+        let tileset = crate::parse::xml::parse_tileset(path, read_from, cache, options).await?;
+        Ok(cache.get_or_insert_tileset(path, Arc::new(tileset)))
+    }
+
     pub(crate) async fn parse_xml_in_map<R: Reader>(
         parser: &mut Parser<R>,
         attrs: &[Attribute<'_>],
@@ -139,7 +644,17 @@ impl Tileset {
         cache: &mut impl ResourceCache,
     ) -> Result<EmbeddedParseResult> {
         let (
-            (spacing, margin, columns, name, user_type, user_class),
+            (
+                spacing,
+                margin,
+                columns,
+                name,
+                user_type,
+                user_class,
+                tile_render_size,
+                fill_mode,
+                object_alignment,
+            ),
             (tilecount, first_gid, tile_width, tile_height),
         ) = get_attrs!(
            for v in attrs {
@@ -147,16 +662,21 @@ impl Tileset {
             Some("margin") => margin ?= v.parse(),
             Some("columns") => columns ?= v.parse(),
             Some("name") => name = v,
-            Some("type") => user_type ?= v.parse(),
-            Some("class") => user_class ?= v.parse(),
+            Some("type") => user_type = v,
+            Some("class") => user_class = v,
+            Some("tilerendersize") => tile_render_size ?= v.parse(),
+            Some("fillmode") => fill_mode ?= v.parse(),
+            Some("objectalignment") => object_alignment ?= v.parse(),
 
             "tilecount" => tilecount ?= v.parse::<u32>(),
             "firstgid" => first_gid ?= v.parse::<u32>().map(Gid),
             "tilewidth" => tile_width ?= v.parse::<u32>(),
             "tileheight" => tile_height ?= v.parse::<u32>(),
            }
-           ((spacing, margin, columns, name, user_type, user_class), (tilecount, first_gid, tile_width, tile_height))
+           ((spacing, margin, columns, name, user_type, user_class, tile_render_size, fill_mode, object_alignment), (tilecount, first_gid, tile_width, tile_height))
         );
+        Self::warn_about_xml_attributes(parser, &user_type, &columns);
+        let user_type = user_type.or(user_class).map(|t| parser.interner.intern(t));
 
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
 
@@ -166,12 +686,15 @@ impl Tileset {
                 spacing,
                 margin,
                 name: name.unwrap_or_default().to_string(),
-                user_type: user_type.or(user_class),
+                user_type,
                 root_path,
                 columns,
                 tilecount,
                 tile_height,
                 tile_width,
+                tile_render_size: tile_render_size.unwrap_or_default(),
+                fill_mode: fill_mode.unwrap_or_default(),
+                object_alignment: object_alignment.unwrap_or_default(),
             },
             read_from,
             cache,
@@ -208,7 +731,17 @@ impl Tileset {
         cache: &mut impl ResourceCache,
     ) -> Result<Tileset> {
         let (
-            (spacing, margin, columns, name, user_type, user_class),
+            (
+                spacing,
+                margin,
+                columns,
+                name,
+                user_type,
+                user_class,
+                tile_render_size,
+                fill_mode,
+                object_alignment,
+            ),
             (tilecount, tile_width, tile_height),
         ) = get_attrs!(
             for v in attrs {
@@ -216,15 +749,20 @@ impl Tileset {
                 Some("margin") => margin ?= v.parse(),
                 Some("columns") => columns ?= v.parse(),
                 Some("name") => name = v,
-                Some("type") => user_type ?= v.parse(),
-                Some("class") => user_class ?= v.parse(),
+                Some("type") => user_type = v,
+                Some("class") => user_class = v,
+                Some("tilerendersize") => tile_render_size ?= v.parse(),
+                Some("fillmode") => fill_mode ?= v.parse(),
+                Some("objectalignment") => object_alignment ?= v.parse(),
 
                 "tilecount" => tilecount ?= v.parse::<u32>(),
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((spacing, margin, columns, name, user_type, user_class), (tilecount, tile_width, tile_height))
+            ((spacing, margin, columns, name, user_type, user_class, tile_render_size, fill_mode, object_alignment), (tilecount, tile_width, tile_height))
         );
+        Self::warn_about_xml_attributes(parser, &user_type, &columns);
+        let user_type = user_type.or(user_class).map(|t| parser.interner.intern(t));
 
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
 
@@ -234,12 +772,15 @@ impl Tileset {
                 spacing,
                 margin,
                 name: name.unwrap_or_default().to_string(),
-                user_type: user_type.or(user_class),
+                user_type,
                 root_path,
                 columns,
                 tilecount,
                 tile_height,
                 tile_width,
+                tile_render_size: tile_render_size.unwrap_or_default(),
+                fill_mode: fill_mode.unwrap_or_default(),
+                object_alignment: object_alignment.unwrap_or_default(),
             },
             reader,
             cache,
@@ -247,6 +788,28 @@ impl Tileset {
         .await
     }
 
+    /// Warns about the attributes of a `<tileset>` element that are deprecated or missing.
+    fn warn_about_xml_attributes<R>(
+        parser: &Parser<R>,
+        user_type: &Option<&str>,
+        columns: &Option<u32>,
+    ) {
+        if user_type.is_some() {
+            parser.warn(ParseWarningKind::DeprecatedAttribute {
+                element: "tileset".to_owned(),
+                name: "type".to_owned(),
+                replacement: "class".to_owned(),
+            });
+        }
+        if columns.is_none() {
+            parser.warn(ParseWarningKind::MissingAttribute {
+                element: "tileset".to_owned(),
+                name: "columns".to_owned(),
+                default: "the amount of columns that fit in the image".to_owned(),
+            });
+        }
+    }
+
     async fn finish_parsing_xml<R: Reader>(
         parser: &mut Parser<R>,
         prop: TilesetProperties,
@@ -257,6 +820,7 @@ impl Tileset {
         let mut tiles = HashMap::with_capacity(prop.tilecount as usize);
         let mut properties = HashMap::new();
         let mut wang_sets = Vec::new();
+        let mut transformations = TilesetTransformations::default();
         let mut offset = (0i32, 0i32);
 
         let mut buffer = Vec::new();
@@ -283,6 +847,10 @@ impl Tileset {
                 wang_sets.push(set);
                 Ok(())
             },
+            "transformations" => for attrs {
+                transformations = parse_transformations(attrs)?;
+                Ok(())
+            },
         });
 
         // A tileset is considered an image collection tileset if there is no image attribute (because its tiles do).
@@ -321,11 +889,15 @@ impl Tileset {
             image,
             tiles,
             wang_sets,
+            transformations,
+            tile_render_size: prop.tile_render_size,
+            fill_mode: prop.fill_mode,
+            object_alignment: prop.object_alignment,
             properties,
         })
     }
 
-    fn calculate_columns(
+    pub(crate) fn calculate_columns(
         image: &Option<Image>,
         tile_width: u32,
         margin: u32,
@@ -342,6 +914,25 @@ impl Tileset {
     }
 }
 
+/// Parse the optional <transformations hflip=... vflip=... rotate=.../> tag.
+fn parse_transformations(attrs: Vec<Attribute>) -> Result<TilesetTransformations> {
+    let (hflip, vflip, rotate, prefer_untransformed) = get_attrs!(
+        for v in attrs {
+            Some("hflip") => hflip = v == "1",
+            Some("vflip") => vflip = v == "1",
+            Some("rotate") => rotate = v == "1",
+            Some("preferuntransformed") => prefer_untransformed = v == "1",
+        }
+        (hflip, vflip, rotate, prefer_untransformed)
+    );
+    Ok(TilesetTransformations {
+        hflip: hflip.unwrap_or(false),
+        vflip: vflip.unwrap_or(false),
+        rotate: rotate.unwrap_or(false),
+        prefer_untransformed: prefer_untransformed.unwrap_or(false),
+    })
+}
+
 /// Parse the optional <tileoffset x=... y=.../> tag.
 fn parse_tileoffset(attrs: Vec<Attribute>) -> Result<(i32, i32)> {
     Ok(get_attrs!(
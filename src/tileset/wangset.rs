@@ -16,6 +16,7 @@ pub use wang_tile::*;
 
 /// Wang set's terrain brush connection type.
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum WangSetType {
     Corner,
@@ -26,6 +27,7 @@ pub enum WangSetType {
 
 /// Raw data belonging to a WangSet.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangSet {
     /// The name of the Wang set.
     pub name: String,
@@ -41,6 +43,50 @@ pub struct WangSet {
     pub properties: Properties,
 }
 
+impl WangSet {
+    /// Returns the color with the given index, as found in a [`WangId`]. Since index 0 means no
+    /// color, the first color has index 1.
+    #[inline]
+    pub fn get_wang_color(&self, index: u8) -> Option<&WangColor> {
+        self.wang_colors.get(usize::from(index).checked_sub(1)?)
+    }
+
+    /// Returns the IDs of the tiles whose Wang ID matches the one given, sorted. Positions with
+    /// no color (0) in the given Wang ID match any color, which allows searching for tiles
+    /// by their corners or edges alone.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, WangId};
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_csv_wangsets.tmx")
+    ///     .unwrap();
+    /// let wang_set = &map.tilesets()[0].wang_sets[1];
+    ///
+    /// let tiles = wang_set.tiles_matching(WangId([2, 2, 0, 2, 0, 2, 2, 2]));
+    /// assert!(tiles.contains(&10));
+    /// # }
+    /// ```
+    pub fn tiles_matching(&self, wang_id: WangId) -> Vec<TileId> {
+        let mut tiles: Vec<_> = self
+            .wang_tiles
+            .iter()
+            .filter(|(_, tile)| {
+                wang_id
+                    .0
+                    .iter()
+                    .zip(tile.wang_id.0.iter())
+                    .all(|(wanted, color)| *wanted == 0 || wanted == color)
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        tiles.sort_unstable();
+        tiles
+    }
+}
+
 impl WangSet {
     /// Reads data from XML parser to create a WangSet.
     // FIXME: was public before
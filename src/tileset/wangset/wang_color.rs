@@ -12,6 +12,7 @@ use crate::{
 
 /// Stores the data of the Wang color.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangColor {
     /// The name of this color.
     pub name: String,
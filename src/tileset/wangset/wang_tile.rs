@@ -5,9 +5,30 @@ use quick_xml::events::attributes::Attribute;
 use crate::{error::Error, util::get_attrs, Result, TileId};
 
 /// The Wang ID, stored as an array of 8 u8 values.
+///
+/// Each value is the index of a color of the [`WangSet`](crate::WangSet), starting from 1, with 0
+/// meaning no color. The values go clockwise around the tile, starting from the top edge: top,
+/// top-right corner, right, bottom-right corner, bottom, bottom-left corner, left and top-left
+/// corner.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangId(pub [u8; 8]);
 
+impl WangId {
+    /// Returns the colors of the edges of the tile, in order: top, right, bottom and left.
+    #[inline]
+    pub fn edges(&self) -> [u8; 4] {
+        [self.0[0], self.0[2], self.0[4], self.0[6]]
+    }
+
+    /// Returns the colors of the corners of the tile, in order: top-right, bottom-right,
+    /// bottom-left and top-left.
+    #[inline]
+    pub fn corners(&self) -> [u8; 4] {
+        [self.0[1], self.0[3], self.0[5], self.0[7]]
+    }
+}
+
 impl FromStr for WangId {
     type Err = Error;
 
@@ -33,6 +54,7 @@ impl FromStr for WangId {
 
 /// Stores the Wang ID.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangTile {
     #[allow(missing_docs)]
     pub wang_id: WangId,
@@ -0,0 +1,294 @@
+//! Structures related to placing layers in the world when rendering them.
+
+use crate::{coords::StaggerParams, Layer, LayerData, LayerDataType, LayerTile, Map, Orientation};
+
+/// A 2D affine transform, which maps a point `(x, y)` to
+/// `(a * x + c * y + tx, b * x + d * y + ty)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affine {
+    /// The horizontal scaling factor.
+    pub a: f32,
+    /// The vertical shearing factor.
+    pub b: f32,
+    /// The horizontal shearing factor.
+    pub c: f32,
+    /// The vertical scaling factor.
+    pub d: f32,
+    /// The horizontal translation.
+    pub tx: f32,
+    /// The vertical translation.
+    pub ty: f32,
+}
+
+impl Default for Affine {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Affine {
+    /// The transform that leaves every point unchanged.
+    pub const IDENTITY: Self = Self {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        tx: 0.0,
+        ty: 0.0,
+    };
+
+    /// Creates a transform that moves points by the given amount.
+    pub fn translation(x: f32, y: f32) -> Self {
+        Self {
+            tx: x,
+            ty: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Creates a transform that scales points by the given factors.
+    pub fn scale(x: f32, y: f32) -> Self {
+        Self {
+            a: x,
+            d: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Creates a transform that rotates points clockwise around the origin by the angle given,
+    /// in degrees. Clockwise is relative to the Y axis pointing down, as in Tiled.
+    pub fn rotation(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Returns the transform that applies `self` and then `other`.
+    pub fn then(&self, other: &Affine) -> Affine {
+        Affine {
+            a: other.a * self.a + other.c * self.b,
+            b: other.b * self.a + other.d * self.b,
+            c: other.a * self.c + other.c * self.d,
+            d: other.b * self.c + other.d * self.d,
+            tx: other.a * self.tx + other.c * self.ty + other.tx,
+            ty: other.b * self.tx + other.d * self.ty + other.ty,
+        }
+    }
+
+    /// Applies the transform to the point given.
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.tx,
+            self.b * x + self.d * y + self.ty,
+        )
+    }
+
+    /// Returns the transform as a column-major array, i.e. `[a, b, c, d, tx, ty]`, which is the
+    /// layout most math and rendering libraries use for 2D affine transforms.
+    pub fn to_cols_array(&self) -> [f32; 6] {
+        [self.a, self.b, self.c, self.d, self.tx, self.ty]
+    }
+}
+
+impl Map {
+    /// Returns the transform that maps tile coordinates to the pixel coordinates of the top-left
+    /// corner of their cell's bounding box, according to the map's orientation.
+    ///
+    /// Staggered and hexagonal maps shift every other row or column by half a tile, which can't
+    /// be expressed by an affine transform; The returned transform doesn't include that shift,
+    /// which [`Map::tile_to_world()`] does.
+    pub fn projection(&self) -> Affine {
+        let (tw, th) = (self.tile_width as f32, self.tile_height as f32);
+        match self.orientation {
+            Orientation::Orthogonal => Affine::scale(tw, th),
+            Orientation::Isometric => Affine {
+                a: tw / 2.0,
+                b: th / 2.0,
+                c: -tw / 2.0,
+                d: th / 2.0,
+                tx: (self.height as f32 - 1.0) * tw / 2.0,
+                ty: 0.0,
+            },
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let p = StaggerParams::new(self);
+                if p.stagger_x {
+                    Affine::scale(p.column_width, p.tile_height + p.side_length_y)
+                } else {
+                    Affine::scale(p.tile_width + p.side_length_x, p.row_height)
+                }
+            }
+        }
+    }
+}
+
+impl<'map> Layer<'map> {
+    /// Returns the transform that maps the tile coordinates of this layer to world pixel
+    /// coordinates, ready to be pushed onto a renderer's transform stack.
+    ///
+    /// The transform combines, in order:
+    /// - The [projection](Map::projection) of the map's orientation.
+    /// - The offsets of the layer and of all of its parent group layers.
+    /// - The parallax scrolling of the layer, whose factor is multiplied with the one of all of
+    ///   its parent group layers, relative to the camera given. The camera is the position of the
+    ///   center of the view, in world pixel coordinates, and layers are at their unscrolled
+    ///   position when it is at the [parallax origin](Map::parallax_origin_x) of the map.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_parallax.tmx")
+    ///     .unwrap();
+    /// let layer = map.layers().find(|layer| &*layer.name == "Background").unwrap();
+    ///
+    /// // The background scrolls at half the speed of the camera.
+    /// let transform = layer.to_affine((100.0, 0.0));
+    /// assert_eq!(transform.apply(0.0, 0.0), (50.0, 0.0));
+    /// # }
+    /// ```
+    pub fn to_affine(&self, camera: (f32, f32)) -> Affine {
+        let attributes = self.effective_attributes();
+        let offset = (attributes.offset_x, attributes.offset_y);
+        let parallax = (attributes.parallax_x, attributes.parallax_y);
+        let origin = (self.map.parallax_origin_x, self.map.parallax_origin_y);
+
+        self.map.projection().then(&Affine::translation(
+            offset.0 + (camera.0 - origin.0) * (1.0 - parallax.0),
+            offset.1 + (camera.1 - origin.1) * (1.0 - parallax.1),
+        ))
+    }
+}
+
+/// How the image of a [`LayerTile`] is flipped and rotated when drawn, as returned by
+/// [`LayerTile::transform()`].
+///
+/// The image is mirrored first, then rotated clockwise around its center, which is how Tiled
+/// draws it and how most renderers express sprite transforms.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TileTransform {
+    /// Whether the image is mirrored horizontally.
+    pub flip_x: bool,
+    /// Whether the image is mirrored vertically.
+    pub flip_y: bool,
+    /// The clockwise rotation of the image in degrees, applied after mirroring it. Either 0 or 90,
+    /// except on hexagonal maps where it is a multiple of 60 below 360.
+    pub rotation: f32,
+}
+
+impl TileTransform {
+    /// Returns the transform that maps the pixels of a tile's image of the size given, relative
+    /// to its top-left corner, to where they are drawn relative to the top-left corner of the
+    /// untransformed image.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::TileTransform;
+    ///
+    /// let transform = TileTransform {
+    ///     flip_x: true,
+    ///     flip_y: false,
+    ///     rotation: 0.0,
+    /// };
+    /// assert_eq!(transform.to_affine(32.0, 32.0).apply(0.0, 8.0), (32.0, 8.0));
+    /// ```
+    pub fn to_affine(&self, width: f32, height: f32) -> Affine {
+        let (center_x, center_y) = (width / 2.0, height / 2.0);
+        let scale = Affine::scale(
+            if self.flip_x { -1.0 } else { 1.0 },
+            if self.flip_y { -1.0 } else { 1.0 },
+        );
+        Affine::translation(-center_x, -center_y)
+            .then(&scale)
+            .then(&Affine::rotation(self.rotation))
+            .then(&Affine::translation(center_x, center_y))
+    }
+}
+
+impl<'map> LayerTile<'map> {
+    /// Returns how the tile's image is flipped and rotated, decomposed from its flip flags
+    /// according to the orientation of its map.
+    ///
+    /// On hexagonal maps, the [diagonal flip](crate::LayerTileData::flip_d) stands for a rotation
+    /// of 60 degrees and [`rotate_hex_120`](crate::LayerTileData::rotate_hex_120) for one of 120
+    /// degrees. On other maps, the diagonal flip swaps the axes of the image, which amounts to a
+    /// rotation of 90 degrees along with a vertical flip, and `rotate_hex_120` is ignored.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_flipped.tmx")
+    ///     .unwrap();
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// // Only flipped diagonally, which is a vertical flip followed by a clockwise rotation.
+    /// let transform = layer.get_tile(1, 1).unwrap().transform();
+    /// assert!(!transform.flip_x && transform.flip_y);
+    /// assert_eq!(transform.rotation, 90.0);
+    /// # }
+    /// ```
+    pub fn transform(&self) -> TileTransform {
+        let data = self.data;
+        if self.map.orientation == Orientation::Hexagonal {
+            let sixths = data.flip_d as u8 + 2 * data.rotate_hex_120 as u8;
+            TileTransform {
+                flip_x: data.flip_h,
+                flip_y: data.flip_v,
+                rotation: 60.0 * sixths as f32,
+            }
+        } else if data.flip_d {
+            TileTransform {
+                flip_x: data.flip_v,
+                flip_y: !data.flip_h,
+                rotation: 90.0,
+            }
+        } else {
+            TileTransform {
+                flip_x: data.flip_h,
+                flip_y: data.flip_v,
+                rotation: 0.0,
+            }
+        }
+    }
+}
+
+/// Pushes the group layers leading to `target` and `target` itself into `chain`. Falls back to
+/// `target` alone if it isn't part of `layers`.
+pub(crate) fn find_layer<'map>(
+    layers: &'map [LayerData],
+    target: &'map LayerData,
+    chain: &mut Vec<&'map LayerData>,
+) {
+    fn find<'map>(
+        layers: &'map [LayerData],
+        target: &'map LayerData,
+        chain: &mut Vec<&'map LayerData>,
+    ) -> bool {
+        for layer in layers {
+            chain.push(layer);
+            if std::ptr::eq(layer, target) {
+                return true;
+            }
+            if let LayerDataType::Group(group) = &layer.layer_type {
+                if find(group.layer_data(), target, chain) {
+                    return true;
+                }
+            }
+            chain.pop();
+        }
+        false
+    }
+
+    if !find(layers, target, chain) {
+        chain.push(target);
+    }
+}
@@ -0,0 +1,122 @@
+//! Structures related to analyzing how a map uses its tilesets.
+
+use std::collections::BTreeSet;
+
+use crate::{LayerData, LayerDataType, Map, TileId, TilesetLocation};
+
+/// How a map uses one of its tilesets, as reported by [`Map::usage_stats()`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TilesetUsage {
+    /// The amount of tiles placed in the tile layers of the map that reference the tileset.
+    pub placed_tiles: usize,
+    /// The amount of tile objects of the map that reference the tileset, not counting the ones
+    /// whose tileset comes from their template.
+    pub tile_objects: usize,
+    /// The IDs of the tiles of the tileset that are shown by the map, which are the tiles
+    /// referenced by tile layers and tile objects along with the frames of their animations.
+    pub used_tiles: BTreeSet<TileId>,
+}
+
+impl TilesetUsage {
+    /// Whether no tile layer or tile object of the map references the tileset.
+    pub fn is_unused(&self) -> bool {
+        self.placed_tiles == 0 && self.tile_objects == 0
+    }
+}
+
+/// How a map uses its tilesets, as returned by [`Map::usage_stats()`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct UsageStats {
+    /// The usage of each of the map's tilesets, in the same order as [`Map::tilesets()`].
+    pub tilesets: Vec<TilesetUsage>,
+}
+
+impl UsageStats {
+    /// Returns the indices of the tilesets that the map doesn't use at all, in ascending order.
+    pub fn unused_tilesets(&self) -> impl Iterator<Item = usize> + '_ {
+        self.tilesets
+            .iter()
+            .enumerate()
+            .filter(|(_, usage)| usage.is_unused())
+            .map(|(index, _)| index)
+    }
+
+    /// The total amount of tiles placed in the tile layers of the map.
+    pub fn placed_tiles(&self) -> usize {
+        self.tilesets.iter().map(|usage| usage.placed_tiles).sum()
+    }
+}
+
+impl Map {
+    /// Reports how many tiles of each tileset the map places, which of their tiles are used, and
+    /// which tilesets aren't used at all, e.g. to strip unused tilesets or trim atlases.
+    ///
+    /// Tiles are counted across every tile layer and tile object of the map, including those
+    /// in group layers.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_multiple_tilesets.tmx")
+    ///     .unwrap();
+    /// let stats = map.usage_stats();
+    ///
+    /// assert_eq!(stats.placed_tiles(), 3);
+    /// assert_eq!(stats.tilesets[0].used_tiles.iter().collect::<Vec<_>>(), vec![&0]);
+    /// assert_eq!(stats.unused_tilesets().count(), 0);
+    /// # }
+    /// ```
+    pub fn usage_stats(&self) -> UsageStats {
+        let mut tilesets = vec![TilesetUsage::default(); self.tilesets.len()];
+        count_usage(&self.layers, &mut tilesets);
+
+        // Animated tiles also need the tiles of their frames to be shown.
+        for (usage, tileset) in tilesets.iter_mut().zip(&self.tilesets) {
+            let mut pending: Vec<TileId> = usage.used_tiles.iter().copied().collect();
+            while let Some(id) = pending.pop() {
+                let Some(frames) = tileset.get_tile(id).and_then(|tile| tile.animation.clone())
+                else {
+                    continue;
+                };
+                for frame in frames {
+                    if usage.used_tiles.insert(frame.tile_id) {
+                        pending.push(frame.tile_id);
+                    }
+                }
+            }
+        }
+
+        UsageStats { tilesets }
+    }
+}
+
+fn count_usage(layers: &[LayerData], tilesets: &mut [TilesetUsage]) {
+    for layer in layers {
+        match &layer.layer_type {
+            LayerDataType::Tiles(tiles) => {
+                for tile in tiles.tile_data() {
+                    let usage = &mut tilesets[tile.tileset_index()];
+                    usage.placed_tiles += 1;
+                    usage.used_tiles.insert(tile.id());
+                }
+            }
+            LayerDataType::Objects(objects) => {
+                for object in objects.object_data() {
+                    let Some(tile) = &object.tile else {
+                        continue;
+                    };
+                    if let TilesetLocation::Map(index) = tile.tileset_location {
+                        let usage = &mut tilesets[index];
+                        usage.tile_objects += 1;
+                        usage.used_tiles.insert(tile.id());
+                    }
+                }
+            }
+            LayerDataType::Group(group) => count_usage(group.layer_data(), tilesets),
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
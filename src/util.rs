@@ -179,13 +179,27 @@ macro_rules! handle_attr_branches {
 
 pub(crate) use handle_attr_branches;
 
+/// Returns the value of an attribute, if present and valid UTF-8. Used for recovering from errors,
+/// where the attribute may be malformed and [`get_attrs`] can't be used.
+pub(crate) fn find_attr(
+    attrs: &[quick_xml::events::attributes::Attribute],
+    name: &str,
+) -> Option<String> {
+    attrs
+        .iter()
+        .find(|attr| attr.key.local_name().into_inner() == name.as_bytes())
+        .and_then(|attr| std::str::from_utf8(&attr.value).ok())
+        .map(ToOwned::to_owned)
+}
+
 /// Goes through the children of the tag and will call the correct function for
 /// that child. Closes the tag.
 macro_rules! parse_tag {
-    (@match_next $next:expr, $close_tag:expr, {$($open_tag:expr => $( for $attrs:ident )? $body:block),* $(,)*}) => {
+    (@match_next $next:expr, $parser:expr, $close_tag:expr, {$($open_tag:expr => $( for $attrs:ident )? $body:block),* $(,)*}) => {
         match $next {
             #[allow(unused_variables)]
             quick_xml::events::Event::Start(start) | quick_xml::events::Event::Empty(start) => {
+                $parser.check_element($close_tag, &start)?;
                 $(
                     if start.local_name().into_inner() == $open_tag.as_bytes() {
                         $(
@@ -212,19 +226,15 @@ macro_rules! parse_tag {
     };
 
     ($parser:expr, $close_tag:expr, {$($open_tag:expr => $( for $attrs:ident )? $body:block),* $(,)*}) => {
-        if !$parser.last_event_was_empty {
-            loop {
-                let next: quick_xml::events::Event = $parser.read_event().await.map_err(Error::XmlDecodingError)?;
-                parse_tag!(@match_next next, $close_tag, { $($open_tag => $( for $attrs )? $body, )? })
-            }
-        }
+        let mut buffer = Vec::new();
+        parse_tag!($parser => &mut buffer, $close_tag, { $($open_tag => $( for $attrs )? $body, )? })
     };
 
     ($parser:expr => $buf:expr, $close_tag:expr, {$($open_tag:expr => $( for $attrs:ident )? $body:block),* $(,)*}) => {
         if !$parser.last_event_was_empty {
             loop {
                 let next: quick_xml::events::Event = $parser.read_event_into($buf).await.map_err(Error::XmlDecodingError)?;
-                parse_tag!(@match_next next, $close_tag, { $($open_tag => $( for $attrs )? $body, )? })
+                parse_tag!(@match_next next, $parser, $close_tag, { $($open_tag => $( for $attrs )? $body, )? })
             }
         }
     }
@@ -264,6 +274,16 @@ macro_rules! map_wrapper {
     };
 }
 
+/// Emits a `debug` level [tracing](https://docs.rs/tracing) event if the `tracing` feature is
+/// enabled, and does nothing otherwise.
+macro_rules! debug_event {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($args)*);
+    };
+}
+
+pub(crate) use debug_event;
 pub(crate) use get_attrs;
 pub(crate) use map_wrapper;
 pub(crate) use parse_tag;
@@ -292,3 +312,22 @@ pub fn floor_div(a: i32, b: i32) -> i32 {
         d - ((a < 0) ^ (b < 0)) as i32
     }
 }
+
+/// Returns the extension telling the format of the file at the path given, which is the one
+/// before the `.gz` extension of gzip-compressed files, e.g. `tmx` for `level.tmx.gz`.
+pub(crate) fn format_extension(path: &std::path::Path) -> Option<&std::ffi::OsStr> {
+    match path.extension() {
+        Some(extension) if extension == "gz" => {
+            path.file_stem().map(std::path::Path::new)?.extension()
+        }
+        extension => extension,
+    }
+}
+
+/// Whether an external tileset or template is in the JSON format, judging by its extension.
+pub(crate) fn is_json_resource(path: &std::path::Path) -> bool {
+    matches!(
+        format_extension(path).and_then(std::ffi::OsStr::to_str),
+        Some("tsj" | "tj" | "json")
+    )
+}
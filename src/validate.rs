@@ -0,0 +1,280 @@
+//! Structures related to checking the consistency of maps, e.g. to validate game content in CI.
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    ChunkData, LayerData, LayerDataType, Map, TileId, TileLayerData, Tileset, TilesetLocation,
+};
+
+/// A problem found in a map by [`Map::validate()`].
+///
+/// These are references that Tiled would display as missing tiles or that break assumptions
+/// games usually make, but which don't prevent the map from loading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// A tile of a tile layer references a tile its tileset doesn't contain.
+    MissingLayerTile {
+        /// The ID of the tile layer.
+        layer_id: u32,
+        /// The X coordinate of the tile within the layer.
+        x: i32,
+        /// The Y coordinate of the tile within the layer.
+        y: i32,
+        /// The index of the tileset within the map.
+        tileset_index: usize,
+        /// The local ID of the missing tile.
+        tile_id: TileId,
+    },
+    /// A tile object references a tile its tileset doesn't contain, which includes tilesets that
+    /// failed to load in [lenient mode](crate::LoaderOptions::lenient).
+    MissingObjectTile {
+        /// The ID of the object layer the object is in.
+        layer_id: u32,
+        /// The ID of the object.
+        object_id: u32,
+        /// The local ID of the missing tile.
+        tile_id: TileId,
+    },
+    /// Several objects of the map share the same ID. Reported for every object after the first
+    /// one that has it.
+    DuplicateObjectId {
+        /// The ID of the object layer the object is in.
+        layer_id: u32,
+        /// The duplicated object ID.
+        object_id: u32,
+    },
+    /// Several layers of the map share the same ID. Reported for every layer after the first one
+    /// that has it.
+    DuplicateLayerId {
+        /// The duplicated layer ID.
+        layer_id: u32,
+    },
+    /// A frame of a tile's animation references a tile its tileset doesn't contain.
+    MissingAnimationFrame {
+        /// The index of the tileset within the map.
+        tileset_index: usize,
+        /// The local ID of the animated tile.
+        tile_id: TileId,
+        /// The local ID of the missing tile the frame references.
+        frame_tile_id: TileId,
+    },
+    /// A tile of an image collection tileset has no image.
+    MissingTileImage {
+        /// The index of the tileset within the map.
+        tileset_index: usize,
+        /// The local ID of the tile.
+        tile_id: TileId,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::MissingLayerTile {
+                layer_id,
+                x,
+                y,
+                tileset_index,
+                tile_id,
+            } => write!(
+                f,
+                "tile ({}, {}) of layer {} references missing tile {} of tileset {}",
+                x, y, layer_id, tile_id, tileset_index
+            ),
+            ValidationIssue::MissingObjectTile {
+                layer_id,
+                object_id,
+                tile_id,
+            } => write!(
+                f,
+                "object {} of layer {} references missing tile {}",
+                object_id, layer_id, tile_id
+            ),
+            ValidationIssue::DuplicateObjectId {
+                layer_id,
+                object_id,
+            } => write!(
+                f,
+                "object ID {} of layer {} is already used",
+                object_id, layer_id
+            ),
+            ValidationIssue::DuplicateLayerId { layer_id } => {
+                write!(f, "layer ID {} is already used", layer_id)
+            }
+            ValidationIssue::MissingAnimationFrame {
+                tileset_index,
+                tile_id,
+                frame_tile_id,
+            } => write!(
+                f,
+                "animation of tile {} of tileset {} references missing tile {}",
+                tile_id, tileset_index, frame_tile_id
+            ),
+            ValidationIssue::MissingTileImage {
+                tileset_index,
+                tile_id,
+            } => write!(
+                f,
+                "tile {} of image collection tileset {} has no image",
+                tile_id, tileset_index
+            ),
+        }
+    }
+}
+
+impl Map {
+    /// Checks the map for references to tiles that don't exist, duplicated IDs and other
+    /// inconsistencies, returning the problems found. See [`ValidationIssue`] for what is
+    /// checked.
+    ///
+    /// Issues are returned in a stable order: Tilesets first, then layers in display order.
+    /// IDs of 0, which mean the ID wasn't present in the file, aren't considered duplicates.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let map = Loader::new()
+    ///     .load_tmx_map("assets/tiled_validation.tmx")
+    ///     .unwrap();
+    ///
+    /// for issue in map.validate() {
+    ///     eprintln!("{}", issue);
+    /// }
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut validator = Validator {
+            map: self,
+            issues: Vec::new(),
+            layer_ids: HashSet::new(),
+            object_ids: HashSet::new(),
+        };
+        for (index, tileset) in self.tilesets().iter().enumerate() {
+            validator.check_tileset(index, tileset);
+        }
+        for layer in self.layers.iter() {
+            validator.check_layer(layer);
+        }
+        validator.issues
+    }
+}
+
+struct Validator<'map> {
+    map: &'map Map,
+    issues: Vec<ValidationIssue>,
+    layer_ids: HashSet<u32>,
+    object_ids: HashSet<u32>,
+}
+
+impl Validator<'_> {
+    fn check_tileset(&mut self, tileset_index: usize, tileset: &Tileset) {
+        let mut tiles: Vec<_> = tileset.tiles.iter().collect();
+        tiles.sort_unstable_by_key(|(id, _)| **id);
+        for (&tile_id, tile) in tiles {
+            if tileset.image.is_none() && tile.image.is_none() {
+                self.issues.push(ValidationIssue::MissingTileImage {
+                    tileset_index,
+                    tile_id,
+                });
+            }
+            for frame in tile.animation.iter().flatten() {
+                if !tileset.tiles.contains_key(&frame.tile_id) {
+                    self.issues.push(ValidationIssue::MissingAnimationFrame {
+                        tileset_index,
+                        tile_id,
+                        frame_tile_id: frame.tile_id,
+                    });
+                }
+            }
+        }
+    }
+
+    fn check_layer(&mut self, layer: &LayerData) {
+        let layer_id = layer.id();
+        if layer_id != 0 && !self.layer_ids.insert(layer_id) {
+            self.issues
+                .push(ValidationIssue::DuplicateLayerId { layer_id });
+        }
+        match &layer.layer_type {
+            LayerDataType::Tiles(tiles) => self.check_tiles(layer_id, tiles),
+            LayerDataType::Objects(objects) => {
+                for object in objects.object_data() {
+                    let object_id = object.id();
+                    if object_id != 0 && !self.object_ids.insert(object_id) {
+                        self.issues.push(ValidationIssue::DuplicateObjectId {
+                            layer_id,
+                            object_id,
+                        });
+                    }
+                    let Some(tile) = &object.tile else {
+                        continue;
+                    };
+                    let tileset = match tile.tileset_location() {
+                        TilesetLocation::Map(index) => &self.map.tilesets()[*index],
+                        TilesetLocation::Template(tileset) => tileset,
+                    };
+                    if !tileset.tiles.contains_key(&tile.id()) {
+                        self.issues.push(ValidationIssue::MissingObjectTile {
+                            layer_id,
+                            object_id,
+                            tile_id: tile.id(),
+                        });
+                    }
+                }
+            }
+            LayerDataType::Group(group) => {
+                for layer in &group.layers {
+                    self.check_layer(layer);
+                }
+            }
+            LayerDataType::Image(_) => {}
+        }
+    }
+
+    fn check_tiles(&mut self, layer_id: u32, tiles: &TileLayerData) {
+        let mut check = |x: i32, y: i32| {
+            let Some(tile) = tiles.get_tile_data(x, y) else {
+                return;
+            };
+            let tileset_index = tile.tileset_index();
+            if !self.map.tilesets()[tileset_index]
+                .tiles
+                .contains_key(&tile.id())
+            {
+                self.issues.push(ValidationIssue::MissingLayerTile {
+                    layer_id,
+                    x,
+                    y,
+                    tileset_index,
+                    tile_id: tile.id(),
+                });
+            }
+        };
+        match tiles {
+            TileLayerData::Finite(finite) => {
+                for y in 0..finite.height() as i32 {
+                    for x in 0..finite.width() as i32 {
+                        check(x, y);
+                    }
+                }
+            }
+            TileLayerData::Infinite(infinite) => {
+                let mut chunks: Vec<_> = infinite.chunk_data().map(|(pos, _)| pos).collect();
+                chunks.sort_unstable_by_key(|&(x, y)| (y, x));
+                for (chunk_x, chunk_y) in chunks {
+                    for y in 0..ChunkData::HEIGHT as i32 {
+                        for x in 0..ChunkData::WIDTH as i32 {
+                            check(
+                                chunk_x * ChunkData::WIDTH as i32 + x,
+                                chunk_y * ChunkData::HEIGHT as i32 + y,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Structures related to traversing the map tree.
+
+use crate::{
+    ChunkData, GroupLayer, Layer, LayerTile, LayerType, Map, Object, Properties, TileLayer, Tileset,
+};
+
+/// The element of the map tree a set of [`Properties`] belongs to, passed to
+/// [`MapVisitor::visit_properties()`].
+#[derive(Debug, Clone, Copy)]
+pub enum PropertiesOwner<'map> {
+    /// The properties of the map itself.
+    Map(&'map Map),
+    /// The properties of one of the map's tilesets.
+    Tileset(&'map Tileset),
+    /// The properties of a layer.
+    Layer(Layer<'map>),
+    /// The properties of an object inside of an object layer.
+    Object(Object<'map>),
+}
+
+/// A type that can be used to traverse the whole tree of a [`Map`] with [`Map::accept()`].
+///
+/// Every method has an empty default implementation, so implementors only need to override the
+/// callbacks they are interested in.
+///
+/// ## Example
+/// ```
+/// use tiled::{Layer, Loader, MapVisitor, Object};
+///
+/// # fn main() {
+/// #[derive(Default)]
+/// struct ObjectCounter {
+///     depth: usize,
+///     nested_objects: usize,
+/// }
+///
+/// impl<'map> MapVisitor<'map> for ObjectCounter {
+///     fn enter_group(&mut self, _layer: Layer<'map>) {
+///         self.depth += 1;
+///     }
+///
+///     fn leave_group(&mut self, _layer: Layer<'map>) {
+///         self.depth -= 1;
+///     }
+///
+///     fn visit_object(&mut self, _layer: Layer<'map>, _object: Object<'map>) {
+///         if self.depth > 0 {
+///             self.nested_objects += 1;
+///         }
+///     }
+/// }
+///
+/// let map = Loader::new()
+///     .load_tmx_map("assets/tiled_group_layers.tmx")
+///     .unwrap();
+///
+/// let mut counter = ObjectCounter::default();
+/// map.accept(&mut counter);
+/// assert_eq!(counter.depth, 0);
+/// # }
+/// ```
+#[allow(unused_variables)]
+pub trait MapVisitor<'map> {
+    /// Called once before anything else, with the map being traversed.
+    fn visit_map(&mut self, map: &'map Map) {}
+
+    /// Called for each of the map's tilesets, in order, before any layer is visited.
+    fn visit_tileset(&mut self, index: usize, tileset: &'map Tileset) {}
+
+    /// Called for every layer in the map, including nested ones, in display order.
+    ///
+    /// For group layers, this is called right before [`Self::enter_group()`].
+    fn visit_layer(&mut self, layer: Layer<'map>) {}
+
+    /// Called when the traversal descends into a group layer. Every call is paired with a later
+    /// call to [`Self::leave_group()`] once all of the group's children have been visited.
+    fn enter_group(&mut self, layer: Layer<'map>) {}
+
+    /// Called when the traversal has finished visiting all the children of a group layer.
+    fn leave_group(&mut self, layer: Layer<'map>) {}
+
+    /// Called for every non-empty tile in a tile layer, along with its position in tile
+    /// coordinates.
+    ///
+    /// Tiles of finite layers are visited in row-major order; Tiles of infinite layers are visited
+    /// chunk by chunk, with no particular chunk order.
+    fn visit_tile(&mut self, layer: Layer<'map>, x: i32, y: i32, tile: LayerTile<'map>) {}
+
+    /// Called for every object in an object layer, in the order they were declared.
+    fn visit_object(&mut self, layer: Layer<'map>, object: Object<'map>) {}
+
+    /// Called for every set of custom properties in the map, right after the element owning them
+    /// has been visited. Empty property sets are skipped.
+    fn visit_properties(&mut self, owner: PropertiesOwner<'map>, properties: &'map Properties) {}
+}
+
+impl Map {
+    /// Traverses the whole map tree, calling the corresponding [`MapVisitor`] callbacks on the
+    /// visitor given.
+    ///
+    /// The traversal order is: the map itself, its properties, its tilesets (along with their
+    /// properties) and then every layer in display order, descending into group layers as they
+    /// are found.
+    pub fn accept<'map>(&'map self, visitor: &mut impl MapVisitor<'map>) {
+        visitor.visit_map(self);
+        visit_properties(visitor, PropertiesOwner::Map(self), &self.properties);
+
+        for (index, tileset) in self.tilesets().iter().enumerate() {
+            visitor.visit_tileset(index, tileset);
+            visit_properties(
+                visitor,
+                PropertiesOwner::Tileset(tileset),
+                &tileset.properties,
+            );
+        }
+
+        for layer in self.layers() {
+            walk_layer(visitor, layer);
+        }
+    }
+}
+
+fn visit_properties<'map>(
+    visitor: &mut impl MapVisitor<'map>,
+    owner: PropertiesOwner<'map>,
+    properties: &'map Properties,
+) {
+    if !properties.is_empty() {
+        visitor.visit_properties(owner, properties);
+    }
+}
+
+fn walk_layer<'map>(visitor: &mut impl MapVisitor<'map>, layer: Layer<'map>) {
+    visitor.visit_layer(layer);
+    let data = layer.data;
+    match layer.layer_type() {
+        LayerType::Tiles(tiles) => {
+            visit_properties(visitor, PropertiesOwner::Layer(layer), &data.properties);
+            walk_tiles(visitor, layer, tiles);
+        }
+        LayerType::Objects(objects) => {
+            visit_properties(visitor, PropertiesOwner::Layer(layer), &data.properties);
+            for object in objects.objects() {
+                visitor.visit_object(layer, object);
+                visit_properties(
+                    visitor,
+                    PropertiesOwner::Object(object),
+                    &object.data.properties,
+                );
+            }
+        }
+        LayerType::Image(_) => {
+            visit_properties(visitor, PropertiesOwner::Layer(layer), &data.properties);
+        }
+        LayerType::Group(group) => {
+            visitor.enter_group(layer);
+            visit_properties(visitor, PropertiesOwner::Layer(layer), &data.properties);
+            walk_group(visitor, group);
+            visitor.leave_group(layer);
+        }
+    }
+}
+
+fn walk_group<'map>(visitor: &mut impl MapVisitor<'map>, group: GroupLayer<'map>) {
+    for layer in group.layers() {
+        walk_layer(visitor, layer);
+    }
+}
+
+fn walk_tiles<'map>(
+    visitor: &mut impl MapVisitor<'map>,
+    layer: Layer<'map>,
+    tiles: TileLayer<'map>,
+) {
+    match tiles {
+        TileLayer::Finite(finite) => {
+            for y in 0..finite.height() as i32 {
+                for x in 0..finite.width() as i32 {
+                    if let Some(tile) = finite.get_tile(x, y) {
+                        visitor.visit_tile(layer, x, y, tile);
+                    }
+                }
+            }
+        }
+        TileLayer::Infinite(infinite) => {
+            for ((chunk_x, chunk_y), chunk) in infinite.chunks() {
+                for y in 0..ChunkData::HEIGHT as i32 {
+                    for x in 0..ChunkData::WIDTH as i32 {
+                        if let Some(tile) = chunk.get_tile(x, y) {
+                            visitor.visit_tile(
+                                layer,
+                                chunk_x * ChunkData::WIDTH as i32 + x,
+                                chunk_y * ChunkData::HEIGHT as i32 + y,
+                                tile,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
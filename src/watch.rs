@@ -0,0 +1,128 @@
+//! Structures related to reloading maps when their files are edited.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{Error, Map, ResourcePathBuf, Result};
+
+/// Watches the files of a TMX map for changes: The map itself, along with its external tilesets,
+/// templates and images. Available under the `watch` feature.
+///
+/// Watchers are created through [`Loader::watch_tmx_map()`](crate::Loader::watch_tmx_map), and
+/// are polled through [`Loader::reload_if_changed()`](crate::Loader::reload_if_changed), which
+/// reloads the map once some of its files changed. Files are watched through the notification
+/// mechanism of the operating system, so they need to live on the local filesystem.
+///
+/// Directories are watched rather than files, so that edits made by saving a new file in place
+/// of the old one, as many editors do, are detected too.
+pub struct MapWatcher {
+    path: PathBuf,
+    /// The watched files as the loader names them, by their canonical path.
+    files: HashMap<PathBuf, PathBuf>,
+    watched_dirs: HashSet<PathBuf>,
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl std::fmt::Debug for MapWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapWatcher")
+            .field("path", &self.path)
+            .field("files", &self.files.values().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl MapWatcher {
+    pub(crate) fn new(path: &Path, dependencies: Vec<ResourcePathBuf>) -> Result<Self> {
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(sender).map_err(Error::WatchError)?;
+        let mut watcher = Self {
+            path: path.to_owned(),
+            files: HashMap::new(),
+            watched_dirs: HashSet::new(),
+            watcher,
+            events,
+        };
+        watcher.set_dependencies(dependencies)?;
+        Ok(watcher)
+    }
+
+    /// Returns the path of the watched map.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the watched files: The map and every file it depends on, in no particular order.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.values().map(PathBuf::as_path)
+    }
+
+    /// Replaces the watched files by the map and the dependencies given, which are listed by
+    /// [`Loader::scan_tmx_dependencies()`](crate::Loader::scan_tmx_dependencies).
+    pub(crate) fn set_dependencies(&mut self, dependencies: Vec<ResourcePathBuf>) -> Result<()> {
+        self.files.clear();
+        for path in std::iter::once(self.path.clone()).chain(dependencies) {
+            let Some((dir, file_name)) = path.parent().zip(path.file_name()) else {
+                continue;
+            };
+            // Files in directories that don't exist can't be watched, and would fail to load anyway
+            let Ok(dir) = dir_or_current(dir).canonicalize() else {
+                continue;
+            };
+            if self.watched_dirs.insert(dir.clone()) {
+                self.watcher
+                    .watch(&dir, RecursiveMode::NonRecursive)
+                    .map_err(Error::WatchError)?;
+            }
+            self.files.insert(dir.join(file_name), path);
+        }
+        Ok(())
+    }
+
+    /// Returns the watched files that were created, modified or removed since the last call, as
+    /// the loader names them.
+    pub(crate) fn changed_files(&mut self) -> Result<Vec<PathBuf>> {
+        let mut changed = Vec::new();
+        for event in self.events.try_iter() {
+            let event = event.map_err(Error::WatchError)?;
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+            ) {
+                continue;
+            }
+            for path in event.paths {
+                if let Some(file) = self.files.get(&path) {
+                    if !changed.contains(file) {
+                        changed.push(file.clone());
+                    }
+                }
+            }
+        }
+        Ok(changed)
+    }
+}
+
+/// A map reloaded by [`Loader::reload_if_changed()`](crate::Loader::reload_if_changed).
+#[derive(Debug, Clone)]
+pub struct MapReload {
+    /// The map, loaded again from its files.
+    pub map: Map,
+    /// The files that changed since the map was last loaded, as the loader names them. Images in
+    /// this list need to be loaded again by the application.
+    pub changed_files: Vec<PathBuf>,
+}
+
+fn dir_or_current(dir: &Path) -> &Path {
+    if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    }
+}
@@ -0,0 +1,128 @@
+//! Structures related to Tiled worlds, which lay out several maps next to each other.
+
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+/// A Tiled world, loaded from a `.world` file through
+/// [`Loader::load_world()`](crate::Loader::load_world).
+///
+/// Worlds only describe where maps are, so the maps themselves aren't loaded along with the world.
+/// Load them when needed through [`Loader::load_world_map()`](crate::Loader::load_world_map).
+///
+/// Also see the [Tiled docs](https://doc.mapeditor.org/en/stable/manual/worlds/).
+#[derive(Debug, Clone)]
+pub struct World {
+    /// The maps explicitly listed in the world.
+    pub maps: Vec<WorldMap>,
+    /// The patterns that place every map whose file name matches them.
+    pub patterns: Vec<WorldPattern>,
+    /// Whether only the maps next to the current one should be shown in the editor.
+    pub only_show_adjacent_maps: bool,
+}
+
+impl World {
+    /// Returns the maps placed in the world by its [patterns](Self::patterns), out of the files
+    /// given.
+    ///
+    /// Since the files matched by patterns can't be listed through a
+    /// [`ResourceReader`](crate::ResourceReader), the candidates have to be given, e.g. by listing
+    /// the world's directory. Paths are matched by file name, and the first matching pattern
+    /// places each map. Files that are already [listed](Self::maps) in the world are skipped.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() {
+    /// let world = Loader::new().load_world("assets/world/tiled.world").unwrap();
+    /// let maps = world.match_paths(["assets/world/chunk_x2_y1.tmx", "assets/world/notes.txt"]);
+    ///
+    /// assert_eq!(maps.len(), 1);
+    /// assert_eq!((maps[0].x, maps[0].y), (640, 320));
+    /// # }
+    /// ```
+    pub fn match_paths(&self, paths: impl IntoIterator<Item = impl AsRef<Path>>) -> Vec<WorldMap> {
+        paths
+            .into_iter()
+            .filter(|path| !self.maps.iter().any(|map| map.source == path.as_ref()))
+            .filter_map(|path| {
+                self.patterns
+                    .iter()
+                    .find_map(|pattern| pattern.match_path(path.as_ref()))
+            })
+            .collect()
+    }
+
+    /// Returns the listed maps whose bounds contain the given position, in pixels.
+    pub fn maps_at(&self, x: i32, y: i32) -> impl Iterator<Item = &WorldMap> {
+        self.maps.iter().filter(move |map| map.contains(x, y))
+    }
+}
+
+/// A map placed in a [`World`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WorldMap {
+    /// The path to the map file, relative to the working directory like the path the world was
+    /// loaded from.
+    pub source: PathBuf,
+    /// The horizontal offset of the map in the world, in pixels.
+    pub x: i32,
+    /// The vertical offset of the map in the world, in pixels.
+    pub y: i32,
+    /// The width of the map in pixels, which is 0 when the world doesn't specify it.
+    pub width: u32,
+    /// The height of the map in pixels, which is 0 when the world doesn't specify it.
+    pub height: u32,
+}
+
+impl WorldMap {
+    /// Returns `true` if the given position, in pixels, is inside of the map.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && ((x - self.x) as u32) < self.width
+            && ((y - self.y) as u32) < self.height
+    }
+}
+
+/// A pattern placing the maps of a [`World`] whose file name matches a regular expression.
+///
+/// The expression captures the horizontal and vertical index of the map, which are multiplied
+/// by the pattern's multipliers to obtain the position of the map.
+#[derive(Debug, Clone)]
+pub struct WorldPattern {
+    /// The regular expression matched against file names, with two capture groups for the
+    /// horizontal and vertical index of each map.
+    pub regexp: Regex,
+    /// The horizontal distance between consecutive maps, in pixels.
+    pub multiplier_x: i32,
+    /// The vertical distance between consecutive maps, in pixels.
+    pub multiplier_y: i32,
+    /// The horizontal offset added to the position of every map, in pixels.
+    pub offset_x: i32,
+    /// The vertical offset added to the position of every map, in pixels.
+    pub offset_y: i32,
+    /// The width of the matched maps in pixels.
+    pub map_width: u32,
+    /// The height of the matched maps in pixels.
+    pub map_height: u32,
+}
+
+impl WorldPattern {
+    /// Places the map at the given path if its file name matches the pattern.
+    pub fn match_path(&self, path: impl AsRef<Path>) -> Option<WorldMap> {
+        let path = path.as_ref();
+        let file_name = path.file_name()?.to_str()?;
+        let captures = self.regexp.captures(file_name)?;
+        let x: i32 = captures.get(1)?.as_str().parse().ok()?;
+        let y: i32 = captures.get(2)?.as_str().parse().ok()?;
+        Some(WorldMap {
+            source: path.to_owned(),
+            x: x * self.multiplier_x + self.offset_x,
+            y: y * self.multiplier_y + self.offset_y,
+            width: self.map_width,
+            height: self.map_height,
+        })
+    }
+}
@@ -0,0 +1,883 @@
+//! Structures related to writing maps and tilesets back to the TMX and TSX formats.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use base64::Engine;
+use flate2::{write::ZlibEncoder, Compression};
+use quick_xml::{
+    events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event},
+    Writer,
+};
+
+use crate::{
+    ChunkData, Color, EditorSettings, Error, FillMode, HorizontalAlignment, Image, ImageSource,
+    LayerData, LayerDataType, LayerTileData, Map, ObjectAlignment, ObjectData, ObjectLayerData,
+    ObjectShape, ObjectTileData, Orientation, Properties, PropertyValue, Result, StaggerAxis,
+    StaggerIndex, TileData, TileLayerData, TileRenderSize, Tileset, TilesetLocation,
+    TilesetTransformations, VerticalAlignment, WangSet, WangSetType,
+};
+
+/// The format version written to tileset files, and to maps that weren't loaded from a file.
+const FORMAT_VERSION: &str = "1.10";
+
+/// How the tile data of tile layers is encoded when writing a map.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#data).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileDataEncoding {
+    /// Global tile IDs separated by commas, one row per line.
+    #[default]
+    Csv,
+    /// Global tile IDs stored as little-endian 32-bit integers, encoded in base64.
+    Base64,
+    /// Same as [`TileDataEncoding::Base64`], but compressed with zlib before being encoded.
+    Base64Zlib,
+}
+
+/// Options that control how maps and tilesets are written by [`Map::to_writer()`] and
+/// [`Tileset::to_writer()`].
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// The encoding used for the tile data of tile layers.
+    pub encoding: TileDataEncoding,
+    /// The directory the file is written to. Image and tileset paths are written relative to it
+    /// when they are inside of it, and as is otherwise.
+    ///
+    /// Paths of loaded maps are relative to the working directory, so this usually needs to be set
+    /// to the directory of the written file for the paths to stay valid.
+    pub base_dir: PathBuf,
+    /// The tilesets of the map that are written as references to external tileset files, by index
+    /// in [`Map::tilesets()`]. Each index is mapped to the path of the tileset file, which must be
+    /// written separately, e.g. through [`Tileset::to_writer()`].
+    ///
+    /// The other tilesets are embedded in the written map.
+    pub tileset_sources: HashMap<usize, PathBuf>,
+    /// Whether global tile IDs are packed as tightly as possible, as described in
+    /// [`Map::first_gids()`]. Otherwise, maps keep the IDs of the file they were loaded from, as
+    /// described in [`Map::tileset_index_for_gid()`], so that the written map can be compared
+    /// with the original one. Defaults to `false`.
+    pub compact_gids: bool,
+}
+
+impl Map {
+    /// Writes the map in the TMX format.
+    ///
+    /// Global tile IDs are assigned as described in [`WriteOptions::compact_gids`]. Tile objects
+    /// instantiated from templates are written without their template, and the tilesets only used
+    /// by templates are embedded in the map after its own tilesets.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, TileDataEncoding, WriteOptions};
+    ///
+    /// # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    ///
+    /// let mut tmx = Vec::new();
+    /// map.to_writer(
+    ///     &mut tmx,
+    ///     &WriteOptions {
+    ///         encoding: TileDataEncoding::Base64Zlib,
+    ///         base_dir: "assets".into(),
+    ///         ..Default::default()
+    ///     },
+    /// )?;
+    ///
+    /// let tmx = String::from_utf8(tmx)?;
+    /// assert!(tmx.contains(r#"<image source="tilesheet.png""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_writer(&self, writer: impl Write, options: &WriteOptions) -> Result<()> {
+        let mut tmx = TmxWriter::new(writer, options);
+        tmx.declaration()?;
+        tmx.map(self)
+    }
+}
+
+impl Tileset {
+    /// Writes the tileset in the TSX format.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, WriteOptions};
+    ///
+    /// # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    ///
+    /// let mut tsx = Vec::new();
+    /// tileset.to_writer(&mut tsx, &WriteOptions::default())?;
+    ///
+    /// assert!(String::from_utf8(tsx)?.contains(r#"<tileset version="1.10" name="tilesheet""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_writer(&self, writer: impl Write, options: &WriteOptions) -> Result<()> {
+        let mut tsx = TmxWriter::new(writer, options);
+        tsx.declaration()?;
+        let mut tag = BytesStart::new("tileset");
+        attr(&mut tag, "version", FORMAT_VERSION);
+        tsx.tileset(self, tag)
+    }
+}
+
+/// The global tile IDs assigned to the tilesets of the map being written.
+struct Gids {
+    /// The first global tile ID of each of the map's tilesets.
+    map: Vec<u32>,
+    /// The tilesets only referenced by templates, which are written after the map's tilesets,
+    /// along with their first global tile ID.
+    templates: Vec<(Arc<Tileset>, u32)>,
+}
+
+impl Gids {
+    fn new(map: &Map, compact: bool) -> Self {
+        let first_gids = if compact {
+            map.first_gids()
+        } else {
+            map.numbered_first_gids().to_vec()
+        };
+        let mut next = match (first_gids.last(), map.tilesets.last()) {
+            (Some(first_gid), Some(tileset)) => first_gid + tileset.gid_span(),
+            _ => 1,
+        };
+        let mut templates: Vec<(Arc<Tileset>, u32)> = Vec::new();
+        for_each_object(&map.layers, &mut |object| {
+            let Some(TilesetLocation::Template(tileset)) =
+                object.tile.as_ref().map(ObjectTileData::tileset_location)
+            else {
+                return;
+            };
+            if !templates.iter().any(|(t, _)| Arc::ptr_eq(t, tileset)) {
+                templates.push((tileset.clone(), next));
+                next += tileset.gid_span();
+            }
+        });
+        Self {
+            map: first_gids,
+            templates,
+        }
+    }
+
+    fn layer_tile(&self, tile: Option<&LayerTileData>) -> u32 {
+        tile.map_or(0, |tile| {
+            let rotate_hex_120 = if tile.rotate_hex_120 {
+                LayerTileData::ROTATED_HEXAGONAL_120_FLAG
+            } else {
+                0
+            };
+            (self.map[tile.tileset_index()] + tile.id())
+                | flip_flags(tile.flip_h, tile.flip_v, tile.flip_d)
+                | rotate_hex_120
+        })
+    }
+
+    fn object_tile(&self, tile: &ObjectTileData) -> u32 {
+        let first_gid = match tile.tileset_location() {
+            TilesetLocation::Map(index) => self.map[*index],
+            TilesetLocation::Template(tileset) => self
+                .templates
+                .iter()
+                .find(|(t, _)| Arc::ptr_eq(t, tileset))
+                .map(|(_, first_gid)| *first_gid)
+                .unwrap_or(0),
+        };
+        (first_gid + tile.id()) | flip_flags(tile.flip_h, tile.flip_v, tile.flip_d)
+    }
+}
+
+fn flip_flags(flip_h: bool, flip_v: bool, flip_d: bool) -> u32 {
+    (flip_h as u32) << 31 | (flip_v as u32) << 30 | (flip_d as u32) << 29
+}
+
+fn for_each_object(layers: &[LayerData], f: &mut impl FnMut(&ObjectData)) {
+    for layer in layers {
+        match &layer.layer_type {
+            LayerDataType::Objects(objects) => objects.objects.iter().for_each(&mut *f),
+            LayerDataType::Group(group) => for_each_object(&group.layers, f),
+            _ => {}
+        }
+    }
+}
+
+/// Adds an attribute to a tag, formatting its value.
+fn attr(tag: &mut BytesStart, name: &str, value: impl Display) {
+    tag.push_attribute((name, value.to_string().as_str()));
+}
+
+/// Adds an attribute to a tag if its value differs from the default one.
+fn attr_unless<T: Display + PartialEq>(tag: &mut BytesStart, name: &str, value: T, default: T) {
+    if value != default {
+        attr(tag, name, value);
+    }
+}
+
+fn color(color: Color) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        color.alpha, color.red, color.green, color.blue
+    )
+}
+
+struct TmxWriter<'o, W: Write> {
+    writer: Writer<W>,
+    options: &'o WriteOptions,
+}
+
+impl<'o, W: Write> TmxWriter<'o, W> {
+    fn new(writer: W, options: &'o WriteOptions) -> Self {
+        Self {
+            writer: Writer::new_with_indent(writer, b' ', 1),
+            options,
+        }
+    }
+
+    fn event<'a>(&mut self, event: impl Into<Event<'a>>) -> Result<()> {
+        self.writer
+            .write_event(event)
+            .map_err(Error::XmlEncodingError)
+    }
+
+    fn declaration(&mut self) -> Result<()> {
+        self.event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))
+    }
+
+    fn start(&mut self, tag: BytesStart) -> Result<()> {
+        self.event(Event::Start(tag))
+    }
+
+    fn end(&mut self, name: &str) -> Result<()> {
+        self.event(Event::End(BytesEnd::new(name)))
+    }
+
+    fn empty(&mut self, tag: BytesStart) -> Result<()> {
+        self.event(Event::Empty(tag))
+    }
+
+    /// Writes an element that only contains text.
+    fn text_element(&mut self, tag: BytesStart, text: &str) -> Result<()> {
+        let name = String::from_utf8_lossy(tag.name().into_inner()).into_owned();
+        self.start(tag)?;
+        // The writer doesn't indent the end tag after text, so the text is kept as is
+        self.event(Event::Text(BytesText::new(text)))?;
+        self.end(&name)
+    }
+
+    /// Returns a path as it should be written, i.e. relative to the base directory if possible.
+    fn path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.options.base_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    fn map(&mut self, map: &Map) -> Result<()> {
+        let mut tag = BytesStart::new("map");
+        attr(
+            &mut tag,
+            "version",
+            match map.version.as_str() {
+                "" => FORMAT_VERSION,
+                version => version,
+            },
+        );
+        if let Some(user_type) = &map.user_type {
+            attr(&mut tag, "class", user_type);
+        }
+        attr(&mut tag, "orientation", map.orientation);
+        attr(&mut tag, "renderorder", "right-down");
+        attr(&mut tag, "width", map.width);
+        attr(&mut tag, "height", map.height);
+        attr(&mut tag, "tilewidth", map.tile_width);
+        attr(&mut tag, "tileheight", map.tile_height);
+        attr(&mut tag, "infinite", map.infinite as u8);
+        if map.orientation == Orientation::Hexagonal {
+            attr(&mut tag, "hexsidelength", map.hex_side_length);
+        }
+        if matches!(
+            map.orientation,
+            Orientation::Staggered | Orientation::Hexagonal
+        ) {
+            let stagger_axis = match map.stagger_axis {
+                StaggerAxis::X => "x",
+                StaggerAxis::Y => "y",
+            };
+            let stagger_index = match map.stagger_index {
+                StaggerIndex::Even => "even",
+                StaggerIndex::Odd => "odd",
+            };
+            attr(&mut tag, "staggeraxis", stagger_axis);
+            attr(&mut tag, "staggerindex", stagger_index);
+        }
+        attr_unless(&mut tag, "parallaxoriginx", map.parallax_origin_x, 0.0);
+        attr_unless(&mut tag, "parallaxoriginy", map.parallax_origin_y, 0.0);
+        if let Some(background_color) = map.background_color {
+            attr(&mut tag, "backgroundcolor", color(background_color));
+        }
+        attr(&mut tag, "nextobjectid", map.next_object_id);
+        self.start(tag)?;
+        if let Some(settings) = &map.editor_settings {
+            self.editor_settings(settings)?;
+        }
+        self.properties(&map.properties)?;
+
+        let gids = Gids::new(map, self.options.compact_gids);
+        for (index, tileset) in map.tilesets.iter().enumerate() {
+            let mut tag = BytesStart::new("tileset");
+            attr(&mut tag, "firstgid", gids.map[index]);
+            match self.options.tileset_sources.get(&index) {
+                Some(source) => {
+                    attr(&mut tag, "source", self.path(source));
+                    self.empty(tag)?;
+                }
+                None => self.tileset(tileset, tag)?,
+            }
+        }
+        for (tileset, first_gid) in &gids.templates {
+            let mut tag = BytesStart::new("tileset");
+            attr(&mut tag, "firstgid", first_gid);
+            self.tileset(tileset, tag)?;
+        }
+
+        for layer in &map.layers {
+            self.layer(layer, map, &gids)?;
+        }
+        self.end("map")
+    }
+
+    /// Writes a tileset, given its tag with the attributes specific to where it's written.
+    fn tileset(&mut self, tileset: &Tileset, mut tag: BytesStart) -> Result<()> {
+        attr(&mut tag, "name", &tileset.name);
+        if let Some(user_type) = &tileset.user_type {
+            attr(&mut tag, "class", user_type);
+        }
+        attr(&mut tag, "tilewidth", tileset.tile_width);
+        attr(&mut tag, "tileheight", tileset.tile_height);
+        attr_unless(&mut tag, "spacing", tileset.spacing, 0);
+        attr_unless(&mut tag, "margin", tileset.margin, 0);
+        attr(&mut tag, "tilecount", tileset.tilecount);
+        attr(&mut tag, "columns", tileset.columns);
+        attr_unless(
+            &mut tag,
+            "tilerendersize",
+            tileset.tile_render_size,
+            TileRenderSize::Tile,
+        );
+        attr_unless(&mut tag, "fillmode", tileset.fill_mode, FillMode::Stretch);
+        attr_unless(
+            &mut tag,
+            "objectalignment",
+            tileset.object_alignment,
+            ObjectAlignment::Unspecified,
+        );
+        self.start(tag)?;
+
+        if tileset.offset_x != 0 || tileset.offset_y != 0 {
+            let mut tag = BytesStart::new("tileoffset");
+            attr(&mut tag, "x", tileset.offset_x);
+            attr(&mut tag, "y", tileset.offset_y);
+            self.empty(tag)?;
+        }
+        self.properties(&tileset.properties)?;
+        if let Some(image) = &tileset.image {
+            self.image(image)?;
+        }
+        if tileset.transformations != TilesetTransformations::default() {
+            let transformations = &tileset.transformations;
+            let mut tag = BytesStart::new("transformations");
+            attr(&mut tag, "hflip", transformations.hflip as u8);
+            attr(&mut tag, "vflip", transformations.vflip as u8);
+            attr(&mut tag, "rotate", transformations.rotate as u8);
+            attr(
+                &mut tag,
+                "preferuntransformed",
+                transformations.prefer_untransformed as u8,
+            );
+            self.empty(tag)?;
+        }
+
+        let mut tiles: Vec<_> = tileset.tiles.iter().collect();
+        tiles.sort_unstable_by_key(|(id, _)| **id);
+        for (id, tile) in tiles {
+            let has_content = !tile.properties.is_empty()
+                || tile.image.is_some()
+                || tile.collision.is_some()
+                || tile.animation.is_some();
+            // Tiles of the tileset image that aren't in the file are filled in while loading
+            if tileset.image.is_some() && *tile == TileData::default() {
+                continue;
+            }
+            let mut tag = BytesStart::new("tile");
+            attr(&mut tag, "id", id);
+            if let Some(user_type) = &tile.user_type {
+                attr(&mut tag, "type", user_type);
+            }
+            attr_unless(&mut tag, "probability", tile.probability, 1.0);
+            if !has_content {
+                self.empty(tag)?;
+                continue;
+            }
+            self.start(tag)?;
+            self.properties(&tile.properties)?;
+            if let Some(image) = &tile.image {
+                self.image(image)?;
+            }
+            if let Some(collision) = &tile.collision {
+                let mut tag = BytesStart::new("objectgroup");
+                attr(&mut tag, "draworder", "index");
+                self.object_layer(collision, tag, None)?;
+            }
+            if let Some(animation) = &tile.animation {
+                self.start(BytesStart::new("animation"))?;
+                for frame in animation {
+                    let mut tag = BytesStart::new("frame");
+                    attr(&mut tag, "tileid", frame.tile_id);
+                    attr(&mut tag, "duration", frame.duration);
+                    self.empty(tag)?;
+                }
+                self.end("animation")?;
+            }
+            self.end("tile")?;
+        }
+
+        if !tileset.wang_sets.is_empty() {
+            self.start(BytesStart::new("wangsets"))?;
+            for wang_set in &tileset.wang_sets {
+                self.wang_set(wang_set)?;
+            }
+            self.end("wangsets")?;
+        }
+        self.end("tileset")
+    }
+
+    fn wang_set(&mut self, wang_set: &WangSet) -> Result<()> {
+        let mut tag = BytesStart::new("wangset");
+        attr(&mut tag, "name", &wang_set.name);
+        attr(
+            &mut tag,
+            "type",
+            match wang_set.wang_set_type {
+                WangSetType::Corner => "corner",
+                WangSetType::Edge => "edge",
+                WangSetType::Mixed => "mixed",
+            },
+        );
+        attr(&mut tag, "tile", wang_set.tile.map_or(-1, i64::from));
+        self.start(tag)?;
+        self.properties(&wang_set.properties)?;
+        for wang_color in &wang_set.wang_colors {
+            let mut tag = BytesStart::new("wangcolor");
+            attr(&mut tag, "name", &wang_color.name);
+            attr(&mut tag, "color", color(wang_color.color));
+            attr(&mut tag, "tile", wang_color.tile.map_or(-1, i64::from));
+            attr(&mut tag, "probability", wang_color.probability);
+            if wang_color.properties.is_empty() {
+                self.empty(tag)?;
+            } else {
+                self.start(tag)?;
+                self.properties(&wang_color.properties)?;
+                self.end("wangcolor")?;
+            }
+        }
+        let mut wang_tiles: Vec<_> = wang_set.wang_tiles.iter().collect();
+        wang_tiles.sort_unstable_by_key(|(id, _)| **id);
+        for (id, wang_tile) in wang_tiles {
+            let mut tag = BytesStart::new("wangtile");
+            attr(&mut tag, "tileid", id);
+            attr(
+                &mut tag,
+                "wangid",
+                wang_tile
+                    .wang_id
+                    .0
+                    .iter()
+                    .map(u8::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            self.empty(tag)?;
+        }
+        self.end("wangset")
+    }
+
+    fn image(&mut self, image: &Image) -> Result<()> {
+        let mut tag = BytesStart::new("image");
+        match &image.source {
+            ImageSource::Path(source) => attr(&mut tag, "source", self.path(source)),
+            ImageSource::Data(_, format) if !format.is_empty() => attr(&mut tag, "format", format),
+            ImageSource::Data(..) => {}
+        }
+        if let Some(transparent_colour) = image.transparent_colour {
+            attr(&mut tag, "trans", color(transparent_colour));
+        }
+        attr(&mut tag, "width", image.width);
+        attr(&mut tag, "height", image.height);
+        let Some((data, _)) = image.source.as_data() else {
+            return self.empty(tag);
+        };
+        self.start(tag)?;
+        let mut data_tag = BytesStart::new("data");
+        attr(&mut data_tag, "encoding", "base64");
+        self.text_element(
+            data_tag,
+            &base64::engine::general_purpose::STANDARD.encode(data),
+        )?;
+        self.end("image")
+    }
+
+    fn editor_settings(&mut self, settings: &EditorSettings) -> Result<()> {
+        self.start(BytesStart::new("editorsettings"))?;
+        if let Some((width, height)) = settings.chunk_size {
+            let mut tag = BytesStart::new("chunksize");
+            attr(&mut tag, "width", width);
+            attr(&mut tag, "height", height);
+            self.empty(tag)?;
+        }
+        if settings.export_target.is_some() || settings.export_format.is_some() {
+            let mut tag = BytesStart::new("export");
+            if let Some(target) = &settings.export_target {
+                attr(&mut tag, "target", target);
+            }
+            if let Some(format) = &settings.export_format {
+                attr(&mut tag, "format", format);
+            }
+            self.empty(tag)?;
+        }
+        self.end("editorsettings")
+    }
+
+    fn properties(&mut self, properties: &Properties) -> Result<()> {
+        if properties.is_empty() {
+            return Ok(());
+        }
+        let mut properties: Vec<_> = properties.iter().collect();
+        properties.sort_unstable_by_key(|(name, _)| *name);
+
+        self.start(BytesStart::new("properties"))?;
+        for (name, value) in properties {
+            let mut tag = BytesStart::new("property");
+            attr(&mut tag, "name", name);
+            // Enum values are stored by name, which Tiled also accepts for enums stored by index
+            if !matches!(
+                value,
+                PropertyValue::StringValue(_) | PropertyValue::Enum { .. }
+            ) {
+                attr(&mut tag, "type", value.type_name());
+            }
+            match value {
+                PropertyValue::Enum { name, value, .. } => {
+                    attr(&mut tag, "value", value);
+                    attr(&mut tag, "propertytype", name);
+                    self.empty(tag)?;
+                }
+                PropertyValue::ClassValue {
+                    property_type,
+                    properties,
+                } => {
+                    attr(&mut tag, "propertytype", property_type);
+                    if properties.is_empty() {
+                        self.empty(tag)?;
+                    } else {
+                        self.start(tag)?;
+                        self.properties(properties)?;
+                        self.end("property")?;
+                    }
+                }
+                // Like Tiled does, multiline strings are stored as text instead of in an attribute
+                PropertyValue::StringValue(value) if value.contains('\n') => {
+                    self.text_element(tag, value)?;
+                }
+                value => {
+                    let value = match value {
+                        PropertyValue::BoolValue(value) => value.to_string(),
+                        PropertyValue::FloatValue(value) => value.to_string(),
+                        PropertyValue::IntValue(value) => value.to_string(),
+                        PropertyValue::ColorValue(value) => color(*value),
+                        PropertyValue::StringValue(value) => value.clone(),
+                        PropertyValue::FileValue(path) => self.path(path),
+                        PropertyValue::ObjectValue(value) => value.to_string(),
+                        PropertyValue::ClassValue { .. } | PropertyValue::Enum { .. } => {
+                            unreachable!()
+                        }
+                    };
+                    attr(&mut tag, "value", value);
+                    self.empty(tag)?;
+                }
+            }
+        }
+        self.end("properties")
+    }
+
+    fn layer(&mut self, layer: &LayerData, map: &Map, gids: &Gids) -> Result<()> {
+        let name = match &layer.layer_type {
+            LayerDataType::Tiles(_) => "layer",
+            LayerDataType::Objects(_) => "objectgroup",
+            LayerDataType::Image(_) => "imagelayer",
+            LayerDataType::Group(_) => "group",
+        };
+        let mut tag = BytesStart::new(name);
+        attr_unless(&mut tag, "id", layer.id, 0);
+        attr(&mut tag, "name", &layer.name);
+        if let Some(user_type) = &layer.user_type {
+            attr(&mut tag, "class", user_type);
+        }
+        if let LayerDataType::Tiles(tiles) = &layer.layer_type {
+            let (width, height) = match tiles {
+                TileLayerData::Finite(data) => (data.width(), data.height()),
+                TileLayerData::Infinite(_) => (map.width, map.height),
+            };
+            attr(&mut tag, "width", width);
+            attr(&mut tag, "height", height);
+        }
+        if !layer.visible {
+            attr(&mut tag, "visible", 0);
+        }
+        attr_unless(&mut tag, "opacity", layer.opacity, 1.0);
+        if let Some(tint_color) = layer.tint_color {
+            attr(&mut tag, "tintcolor", color(tint_color));
+        }
+        attr_unless(&mut tag, "offsetx", layer.offset_x, 0.0);
+        attr_unless(&mut tag, "offsety", layer.offset_y, 0.0);
+        attr_unless(&mut tag, "parallaxx", layer.parallax_x, 1.0);
+        attr_unless(&mut tag, "parallaxy", layer.parallax_y, 1.0);
+
+        match &layer.layer_type {
+            LayerDataType::Objects(objects) => {
+                if let Some(colour) = objects.colour {
+                    attr(&mut tag, "color", color(colour));
+                }
+                self.start(tag)?;
+                self.properties(&layer.properties)?;
+                self.objects(objects, Some(gids))?;
+            }
+            LayerDataType::Tiles(tiles) => {
+                self.start(tag)?;
+                self.properties(&layer.properties)?;
+                self.tile_data(tiles, gids)?;
+            }
+            LayerDataType::Image(image) => {
+                if image.repeat_x {
+                    attr(&mut tag, "repeatx", 1);
+                }
+                if image.repeat_y {
+                    attr(&mut tag, "repeaty", 1);
+                }
+                self.start(tag)?;
+                self.properties(&layer.properties)?;
+                if let Some(image) = &image.image {
+                    self.image(image)?;
+                }
+            }
+            LayerDataType::Group(group) => {
+                self.start(tag)?;
+                self.properties(&layer.properties)?;
+                for layer in &group.layers {
+                    self.layer(layer, map, gids)?;
+                }
+            }
+        }
+        self.end(name)
+    }
+
+    fn tile_data(&mut self, tiles: &TileLayerData, gids: &Gids) -> Result<()> {
+        let mut tag = BytesStart::new("data");
+        match self.options.encoding {
+            TileDataEncoding::Csv => attr(&mut tag, "encoding", "csv"),
+            TileDataEncoding::Base64 => attr(&mut tag, "encoding", "base64"),
+            TileDataEncoding::Base64Zlib => {
+                attr(&mut tag, "encoding", "base64");
+                attr(&mut tag, "compression", "zlib");
+            }
+        }
+
+        match tiles {
+            TileLayerData::Finite(data) => {
+                let (width, height) = (data.width() as i32, data.height() as i32);
+                let gids: Vec<_> = (0..height)
+                    .flat_map(|y| (0..width).map(move |x| (x, y)))
+                    .map(|(x, y)| gids.layer_tile(data.get_tile_data(x, y)))
+                    .collect();
+                let text = self.encode(&gids, data.width())?;
+                self.text_element(tag, &text)
+            }
+            TileLayerData::Infinite(data) => {
+                self.start(tag)?;
+                let mut chunks: Vec<_> = data.chunk_data().collect();
+                chunks.sort_unstable_by_key(|((x, y), _)| (*y, *x));
+                for ((x, y), chunk) in chunks {
+                    let (width, height) = (ChunkData::WIDTH as i32, ChunkData::HEIGHT as i32);
+                    let gids: Vec<_> = (0..height)
+                        .flat_map(|y| (0..width).map(move |x| (x, y)))
+                        .map(|(x, y)| gids.layer_tile(chunk.get_tile_data(x, y)))
+                        .collect();
+                    let mut tag = BytesStart::new("chunk");
+                    attr(&mut tag, "x", x * width);
+                    attr(&mut tag, "y", y * height);
+                    attr(&mut tag, "width", width);
+                    attr(&mut tag, "height", height);
+                    let text = self.encode(&gids, ChunkData::WIDTH)?;
+                    self.text_element(tag, &text)?;
+                }
+                self.end("data")
+            }
+        }
+    }
+
+    /// Encodes the global tile IDs of a layer or chunk with rows of the width given.
+    fn encode(&self, gids: &[u32], width: u32) -> Result<String> {
+        let bytes = || gids.iter().flat_map(|gid| gid.to_le_bytes());
+        let base64 = base64::engine::general_purpose::STANDARD;
+        match self.options.encoding {
+            TileDataEncoding::Csv => {
+                let rows: Vec<_> = gids
+                    .chunks(width.max(1) as usize)
+                    .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+                    .collect();
+                Ok(format!("\n{}\n", rows.join(",\n")))
+            }
+            TileDataEncoding::Base64 => Ok(base64.encode(bytes().collect::<Vec<_>>())),
+            TileDataEncoding::Base64Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder
+                    .write_all(&bytes().collect::<Vec<_>>())
+                    .and_then(|()| encoder.finish())
+                    .map(|data| base64.encode(data))
+                    .map_err(|err| Error::XmlEncodingError(err.into()))
+            }
+        }
+    }
+
+    /// Writes the objects of an object layer, closing the layer's tag. Objects in tilesets have no
+    /// global tile IDs to refer to tiles with.
+    fn object_layer(
+        &mut self,
+        layer: &ObjectLayerData,
+        mut tag: BytesStart,
+        gids: Option<&Gids>,
+    ) -> Result<()> {
+        if let Some(colour) = layer.colour {
+            attr(&mut tag, "color", color(colour));
+        }
+        self.start(tag)?;
+        self.objects(layer, gids)?;
+        self.end("objectgroup")
+    }
+
+    fn objects(&mut self, layer: &ObjectLayerData, gids: Option<&Gids>) -> Result<()> {
+        for object in &layer.objects {
+            self.object(object, gids)?;
+        }
+        Ok(())
+    }
+
+    fn object(&mut self, object: &ObjectData, gids: Option<&Gids>) -> Result<()> {
+        let mut tag = BytesStart::new("object");
+        attr_unless(&mut tag, "id", object.id, 0);
+        if let (Some(tile), Some(gids)) = (&object.tile, gids) {
+            attr(&mut tag, "gid", gids.object_tile(tile));
+        }
+        if !object.name.is_empty() {
+            attr(&mut tag, "name", &object.name);
+        }
+        if !object.user_type.is_empty() {
+            attr(&mut tag, "type", &object.user_type);
+        }
+        attr(&mut tag, "x", object.x);
+        attr(&mut tag, "y", object.y);
+        match &object.shape {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => {
+                attr_unless(&mut tag, "width", *width, 0.0);
+                attr_unless(&mut tag, "height", *height, 0.0);
+            }
+            _ => {}
+        }
+        attr_unless(&mut tag, "rotation", object.rotation, 0.0);
+        if !object.visible {
+            attr(&mut tag, "visible", 0);
+        }
+
+        if object.properties.is_empty() && matches!(object.shape, ObjectShape::Rect { .. }) {
+            return self.empty(tag);
+        }
+        self.start(tag)?;
+        self.properties(&object.properties)?;
+        match &object.shape {
+            ObjectShape::Rect { .. } => {}
+            ObjectShape::Ellipse { .. } => self.empty(BytesStart::new("ellipse"))?,
+            ObjectShape::Point(..) => self.empty(BytesStart::new("point"))?,
+            ObjectShape::Polyline { points } => self.points("polyline", points)?,
+            ObjectShape::Polygon { points } => self.points("polygon", points)?,
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color: text_color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+                text,
+                ..
+            } => {
+                let mut tag = BytesStart::new("text");
+                attr_unless(&mut tag, "fontfamily", font_family.as_str(), "sans-serif");
+                attr_unless(&mut tag, "pixelsize", *pixel_size, 16);
+                attr_unless(&mut tag, "wrap", *wrap as u8, 0);
+                attr_unless(
+                    &mut tag,
+                    "color",
+                    color(*text_color),
+                    color(Color {
+                        red: 0,
+                        green: 0,
+                        blue: 0,
+                        alpha: 255,
+                    }),
+                );
+                attr_unless(&mut tag, "bold", *bold as u8, 0);
+                attr_unless(&mut tag, "italic", *italic as u8, 0);
+                attr_unless(&mut tag, "underline", *underline as u8, 0);
+                attr_unless(&mut tag, "strikeout", *strikeout as u8, 0);
+                attr_unless(&mut tag, "kerning", *kerning as u8, 1);
+                match halign {
+                    HorizontalAlignment::Left => {}
+                    HorizontalAlignment::Center => attr(&mut tag, "halign", "center"),
+                    HorizontalAlignment::Right => attr(&mut tag, "halign", "right"),
+                    HorizontalAlignment::Justify => attr(&mut tag, "halign", "justify"),
+                }
+                match valign {
+                    VerticalAlignment::Top => {}
+                    VerticalAlignment::Center => attr(&mut tag, "valign", "center"),
+                    VerticalAlignment::Bottom => attr(&mut tag, "valign", "bottom"),
+                }
+                self.text_element(tag, text)?;
+            }
+        }
+        self.end("object")
+    }
+
+    fn points(&mut self, name: &str, points: &[(f32, f32)]) -> Result<()> {
+        let mut tag = BytesStart::new(name);
+        attr(
+            &mut tag,
+            "points",
+            points
+                .iter()
+                .map(|(x, y)| format!("{},{}", x, y))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        self.empty(tag)
+    }
+}
@@ -1,8 +1,17 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use tiled::{
-    Color, FiniteTileLayer, HorizontalAlignment, LayerType, Loader, Map, ObjectShape,
-    PropertyValue, ResourceCache, TileLayer, TilesetLocation, VerticalAlignment, WangId,
+    Affine, AnimationState, BoundingBox, ChunkData, Color, FillMode, FiniteTileLayer,
+    FiniteTileLayerData, Frame, HorizontalAlignment, Image, ImageSource, InfiniteTileLayerData,
+    LayerAttributes, LayerData, LayerTileData, LayerType, Loader, Map, MapStreamVisitor, Object,
+    ObjectData, ObjectId, ObjectShape, ObjectTileData, Orientation, Properties, PropertyTypeError,
+    PropertyValue, Rect, ResourceCache, StreamedChunk, StreamedLayer, StreamedMap,
+    TileCollisionShape, TileDataEncoding, TileLayer, TileRect, TileRenderSize, Tileset,
+    TilesetLocation, TilesetTransformations, UvRect, ValidationIssue, VerticalAlignment, Viewport,
+    WangId, WriteOptions,
 };
 
 fn as_finite<'map>(data: TileLayer<'map>) -> FiniteTileLayer<'map> {
@@ -35,14 +44,22 @@ fn test_gzip_and_zlib_encoded_and_raw_are_the_same() {
     let z = loader.load_tmx_map("assets/tiled_base64_zlib.tmx").unwrap();
     let g = loader.load_tmx_map("assets/tiled_base64_gzip.tmx").unwrap();
     let r = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
-    let zstd = loader
-        .load_tmx_map("assets/tiled_base64_zstandard.tmx")
-        .unwrap();
     let c = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
     compare_everything_but_tileset_sources(&z, &g);
     compare_everything_but_tileset_sources(&z, &r);
     compare_everything_but_tileset_sources(&z, &c);
-    compare_everything_but_tileset_sources(&z, &zstd);
+
+    let zstd = loader.load_tmx_map("assets/tiled_base64_zstandard.tmx");
+    #[cfg(feature = "zstd")]
+    compare_everything_but_tileset_sources(&z, &zstd.unwrap());
+    #[cfg(not(feature = "zstd"))]
+    assert!(matches!(
+        zstd,
+        Err(tiled::Error::CompressionFeatureDisabled {
+            feature: "zstd",
+            ..
+        })
+    ));
 
     let layer = as_finite(c.get_layer(0).unwrap().as_tile_layer().unwrap());
     {
@@ -79,8 +96,8 @@ fn test_sources() {
         loader.cache().get_tileset("assets/tilesheet.tsx").unwrap()
     );
     assert_eq!(
-        e.tilesets()[0].image.as_ref().unwrap().source,
-        PathBuf::from("assets/tilesheet.png")
+        e.tilesets()[0].image.as_ref().unwrap().source.as_path(),
+        Some(Path::new("assets/tilesheet.png"))
     );
 }
 
@@ -149,7 +166,7 @@ fn test_image_layers() {
     });
     {
         let first = image_layers.next().unwrap();
-        assert_eq!(first.1.name, "Image Layer 1");
+        assert_eq!(&*first.1.name, "Image Layer 1");
         assert!(
             first.0.image.is_none(),
             "{}'s image should be None",
@@ -158,13 +175,16 @@ fn test_image_layers() {
     }
     {
         let second = image_layers.next().unwrap();
-        assert_eq!(second.1.name, "Image Layer 2");
+        assert_eq!(&*second.1.name, "Image Layer 2");
         let image = second
             .0
             .image
             .as_ref()
             .unwrap_or_else(|| panic!("{}'s image shouldn't be None", second.1.name));
-        assert_eq!(image.source, PathBuf::from("assets/tilesheet.png"));
+        assert_eq!(
+            image.source.as_path(),
+            Some(Path::new("assets/tilesheet.png"))
+        );
         assert_eq!(image.width, 448);
         assert_eq!(image.height, 192);
     }
@@ -286,17 +306,17 @@ fn test_parallax_layers() {
     for (i, layer) in r.layers().enumerate() {
         match i {
             0 => {
-                assert_eq!(layer.name, "Background");
+                assert_eq!(&*layer.name, "Background");
                 assert_eq!(layer.parallax_x, 0.5);
                 assert_eq!(layer.parallax_y, 0.75);
             }
             1 => {
-                assert_eq!(layer.name, "Middle");
+                assert_eq!(&*layer.name, "Middle");
                 assert_eq!(layer.parallax_x, 1.0);
                 assert_eq!(layer.parallax_y, 1.0);
             }
             2 => {
-                assert_eq!(layer.name, "Foreground");
+                assert_eq!(&*layer.name, "Foreground");
                 assert_eq!(layer.parallax_x, 2.0);
                 assert_eq!(layer.parallax_y, 2.0);
             }
@@ -353,6 +373,109 @@ fn test_class_property() {
     };
 }
 
+#[cfg(feature = "json")]
+#[test]
+fn test_project_class_defaults() {
+    fn class_members<'a>(properties: &'a tiled::Properties, name: &str) -> &'a tiled::Properties {
+        match properties.get(name) {
+            Some(PropertyValue::ClassValue { properties, .. }) => properties,
+            other => panic!("Expected class property, found {:?}", other),
+        }
+    }
+
+    let mut loader = Loader::new();
+    let project = loader
+        .load_project("assets/tiled_class_property.tiled-project")
+        .unwrap();
+    assert_eq!(project.folders, [PathBuf::from("assets/.")]);
+    assert_eq!(project.property_types.len(), 6);
+    let direction = project.enum_type("direction").unwrap();
+    assert_eq!(direction.storage_type, tiled::EnumStorageType::String);
+    assert_eq!(direction.values, ["north", "east", "south", "west"]);
+    assert!(project.class_type("direction").is_none());
+
+    loader.set_project(Some(project));
+    let map = loader
+        .load_tmx_map("assets/tiled_nested_class_property.tmx")
+        .unwrap();
+
+    // Members that were set keep their value, while the other ones get their default one
+    let object_layer = map.get_layer(1).unwrap();
+    let object = object_layer
+        .as_object_layer()
+        .unwrap()
+        .get_object(0)
+        .unwrap();
+    let members = class_members(&object.properties, "class property");
+    assert_eq!(members["test_property_1"], PropertyValue::IntValue(3));
+    assert_eq!(
+        members["test_property_2"],
+        PropertyValue::StringValue("default".to_owned())
+    );
+    let members = class_members(&map.properties, "map class property");
+    assert_eq!(
+        members["music"],
+        PropertyValue::FileValue(PathBuf::from("assets/theme.ogg"))
+    );
+    assert_eq!(members["volume"], PropertyValue::FloatValue(0.8));
+
+    // Nested classes are resolved as well, starting from the defaults of the outer class
+    let tile_layer = map.get_layer(0).unwrap();
+    let members = class_members(&tile_layer.properties, "layer class property");
+    assert_eq!(
+        members["facing"],
+        PropertyValue::Enum {
+            name: "direction".to_owned(),
+            value: "north".to_owned(),
+            flags: Vec::new(),
+        }
+    );
+    let nested = class_members(members, "nested");
+    assert_eq!(nested["radius"], PropertyValue::FloatValue(32.0));
+    assert_eq!(
+        nested["tint"],
+        PropertyValue::ColorValue(Color {
+            alpha: 0xff,
+            red: 0x10,
+            green: 0x20,
+            blue: 0x30
+        })
+    );
+
+    let tile = map.tilesets()[0].get_tile(0).unwrap();
+    let members = class_members(&tile.properties, "tile class property");
+    assert_eq!(members["walkable"], PropertyValue::BoolValue(false));
+    assert_eq!(members["cost"], PropertyValue::IntValue(1));
+
+    // Classes the project doesn't declare are left untouched
+    assert!(class_members(&object.properties, "empty property").is_empty());
+
+    // Tilesets that resolving doesn't change stay shared with the cache
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let cached = loader.cache().get_tileset("assets/tilesheet.tsx").unwrap();
+    assert!(std::sync::Arc::ptr_eq(&cached, &map.tilesets()[0]));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let mut loader = Loader::new();
+    for path in [
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_text_object.tmx",
+        "assets/tiled_nested_class_property.tmx",
+    ] {
+        let map = loader.load_tmx_map(path).unwrap();
+        let serialized = serde_json::to_string(&map).unwrap();
+        let deserialized: Map = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(map, deserialized, "{} changed through serde", path);
+    }
+}
+
 #[test]
 fn test_tint_color() {
     let r = Loader::new()
@@ -497,6 +620,8 @@ fn test_templates() {
         .as_ref()
         .unwrap()
         .source
+        .as_path()
+        .unwrap()
         .canonicalize()
         .unwrap(),
         PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/tilesheet.png"))
@@ -518,6 +643,18 @@ fn test_reading_wang_sets() {
     let wangset_2 = tileset.wang_sets.get(1).unwrap();
     let tile_10 = wangset_2.wang_tiles.get(&10).unwrap();
     assert_eq!(tile_10.wang_id, WangId([2u8, 2, 0, 2, 0, 2, 2, 2]));
+    assert_eq!(tile_10.wang_id.edges(), [2, 0, 0, 2]);
+    assert_eq!(tile_10.wang_id.corners(), [2, 2, 2, 2]);
+    let matching = wangset_2.tiles_matching(WangId([0, 2, 0, 2, 0, 2, 0, 2]));
+    assert!(matching.contains(&10));
+    assert!(matching
+        .iter()
+        .all(|id| wangset_2.wang_tiles[id].wang_id.corners() == [2, 2, 2, 2]));
+    assert_eq!(
+        wangset_2.get_wang_color(2).map(|color| &color.name),
+        wangset_2.wang_colors.get(1).map(|color| &color.name)
+    );
+    assert!(wangset_2.get_wang_color(0).is_none());
     let wangset_3 = tileset.wang_sets.get(2).unwrap();
     let color_2 = wangset_3.wang_colors.get(1).unwrap();
     let readed_damage = color_2.properties.get("Damage").unwrap();
@@ -574,3 +711,4383 @@ fn test_text_object() {
         _ => panic!(),
     };
 }
+
+#[test]
+fn test_map_visitor() {
+    use tiled::{Layer, LayerTile, MapVisitor, Properties, PropertiesOwner};
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        tiles: usize,
+        property_sets: usize,
+    }
+
+    impl<'map> MapVisitor<'map> for Recorder {
+        fn visit_layer(&mut self, layer: Layer<'map>) {
+            self.events.push(format!("layer {}", layer.name));
+        }
+
+        fn enter_group(&mut self, layer: Layer<'map>) {
+            self.events.push(format!("enter {}", layer.name));
+        }
+
+        fn leave_group(&mut self, layer: Layer<'map>) {
+            self.events.push(format!("leave {}", layer.name));
+        }
+
+        fn visit_tile(&mut self, _layer: Layer<'map>, _x: i32, _y: i32, _tile: LayerTile<'map>) {
+            self.tiles += 1;
+        }
+
+        fn visit_properties(&mut self, _owner: PropertiesOwner<'map>, _props: &'map Properties) {
+            self.property_sets += 1;
+        }
+    }
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let mut recorder = Recorder::default();
+    map.accept(&mut recorder);
+
+    assert_eq!(
+        recorder.events,
+        [
+            "layer tile-1",
+            "layer group-1",
+            "enter group-1",
+            "layer tile-2",
+            "leave group-1",
+            "layer group-2",
+            "enter group-2",
+            "layer group-3",
+            "enter group-3",
+            "layer tile-3",
+            "leave group-3",
+            "leave group-2",
+        ]
+    );
+    assert_eq!(recorder.tiles, 27);
+    // Six layers with properties plus the tileset's.
+    assert_eq!(recorder.property_sets, 7);
+}
+
+#[test]
+fn test_flat_map() {
+    use tiled::FlatEntity;
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let flat = map.to_flat();
+
+    let names: Vec<_> = flat.layers.iter().map(|l| &*l.name).collect();
+    assert_eq!(names, ["tile-1", "tile-2", "tile-3"]);
+    assert_eq!(flat.layers[2].parents, [6, 8]);
+    assert_eq!(flat.entities.len(), 27);
+
+    let tile = flat
+        .entities
+        .iter()
+        .find_map(|e| match e {
+            FlatEntity::Tile(tile) if tile.layer == 1 => Some(*tile),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(tile.tile_position, (3, 3));
+    assert_eq!(tile.position, (96.0, 96.0));
+    assert_eq!(tile.id, 5);
+}
+
+#[test]
+fn test_owned_handles() {
+    use std::sync::Arc;
+    use tiled::OwnedLayer;
+
+    let mut loader = Loader::new();
+    let groups = Arc::new(
+        loader
+            .load_tmx_map("assets/tiled_group_layers.tmx")
+            .unwrap(),
+    );
+    let nested = groups.get_owned_layer(2).unwrap().get_layer(0).unwrap();
+    let nested = nested.get_layer(0).unwrap();
+    assert_eq!(nested.path(), [2, 0, 0]);
+    assert_eq!(&*nested.name, "tile-3");
+    assert!(nested.as_tile_layer().is_some());
+    assert_eq!(nested.layers().len(), 0);
+    assert!(OwnedLayer::new(groups.clone(), vec![]).is_none());
+    assert!(OwnedLayer::new(groups.clone(), vec![0, 0]).is_none());
+
+    let map = Arc::new(
+        loader
+            .load_tmx_map("assets/tiled_object_property.tmx")
+            .unwrap(),
+    );
+    let object_layer = map.owned_layers().nth(1).unwrap();
+    let objects: Vec<_> = object_layer.objects().collect();
+    let borrowed = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(objects.len(), 2);
+    for (owned, borrowed) in objects.iter().zip(borrowed.objects()) {
+        assert_eq!(owned.id(), borrowed.id());
+        assert_eq!(owned.get(), borrowed);
+    }
+    assert!(object_layer.get_object(2).is_none());
+}
+
+#[test]
+fn test_map_changes() {
+    use tiled::TileRegion;
+
+    let mut loader = Loader::new();
+    let old = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let new = loader
+        .load_tmx_map("assets/tiled_group_layers_changed.tmx")
+        .unwrap();
+
+    assert!(old.changes_since(&old).is_empty());
+
+    let changes = new.changes_since(&old);
+    assert!(!changes.map_modified);
+    assert_eq!(changes.added_layers, [10]);
+    assert_eq!(changes.removed_layers, [9]);
+    assert_eq!(changes.modified_layers, [5]);
+    assert_eq!(changes.added_objects, [1]);
+    assert!(changes.removed_objects.is_empty());
+    assert_eq!(
+        changes.tile_regions,
+        [TileRegion {
+            layer_id: 1,
+            x: 0,
+            y: 0,
+            width: 8,
+            height: 8
+        }]
+    );
+
+    let reverse = old.changes_since(&new);
+    assert_eq!(reverse.added_layers, [9]);
+    assert_eq!(reverse.removed_objects, [1]);
+}
+
+#[test]
+fn test_automapping() {
+    let mut loader = Loader::new();
+    let rules = loader
+        .load_automapping_rules("assets/automap/rules.txt")
+        .unwrap();
+    assert_eq!(rules.rule_maps.len(), 1);
+    assert_eq!(rules.rule_maps[0].rule_count(), 1);
+    assert!(rules.rule_maps[0].options.delete_tiles);
+
+    let mut map = loader.load_tmx_map("assets/automap/target.tmx").unwrap();
+    // Looks the layers up by ID before the rules add one.
+    assert!(map.get_layer_by_id(2).is_none());
+    assert_eq!(rules.apply(&mut map), 2);
+    assert_eq!(&*map.get_layer_by_id(2).unwrap().name, "Shadows");
+
+    let shadows = map
+        .layers()
+        .find(|layer| &*layer.name == "Shadows")
+        .unwrap();
+    assert_eq!(shadows.id(), 2);
+    let shadows = shadows.as_tile_layer().unwrap();
+    let shaded: Vec<_> = (0..4)
+        .flat_map(|y| (0..4).map(move |x| (x, y)))
+        .filter(|&(x, y)| shadows.get_tile(x, y).is_some())
+        .collect();
+    assert_eq!(shaded, [(0, 1), (1, 2)]);
+    assert_eq!(shadows.get_tile(0, 1).unwrap().id(), 2);
+    assert_eq!(shadows.get_tile(0, 1).unwrap().tileset_index(), 0);
+    assert_eq!(map.tilesets().len(), 1);
+
+    // Applying the rules again doesn't change the result.
+    let before = map.clone();
+    rules.apply(&mut map);
+    assert!(map.changes_since(&before).is_empty());
+}
+
+#[test]
+fn test_export_options() {
+    let mut loader = Loader::new();
+    loader.set_export_options(tiled::ExportOptions::all());
+
+    let map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let object_layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let object = object_layer.get_object(0).unwrap();
+    // The template tileset was detached and added to the map.
+    assert_eq!(map.tilesets().len(), 2);
+    assert_eq!(
+        object.tile_data().unwrap().tileset_location(),
+        &TilesetLocation::Map(1)
+    );
+    assert_eq!(
+        object.get_tile().unwrap().get_tileset().name,
+        "tilesheet_template"
+    );
+    // Tilesets are owned by the map rather than shared through the cache.
+    let cached = loader.cache().get_tileset("assets/tilesheet.tsx").unwrap();
+    assert!(!std::sync::Arc::ptr_eq(&cached, &map.tilesets()[0]));
+    assert_eq!(*cached, *map.tilesets()[0]);
+
+    let map = loader
+        .load_tmx_map("assets/tiled_object_tile_types.tmx")
+        .unwrap();
+    let object_layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let inherited = object_layer.get_object(0).unwrap();
+    assert_eq!(&*inherited.user_type, "chest");
+    assert_eq!(
+        inherited.properties.get("gold"),
+        Some(&PropertyValue::IntValue(10))
+    );
+    assert_eq!(
+        inherited.properties.get("locked"),
+        Some(&PropertyValue::BoolValue(false))
+    );
+    let overridden = object_layer.get_object(1).unwrap();
+    assert_eq!(&*overridden.user_type, "mimic");
+    assert_eq!(
+        overridden.properties.get("gold"),
+        Some(&PropertyValue::IntValue(0))
+    );
+
+    // Without export options, nothing is resolved.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_tile_types.tmx")
+        .unwrap();
+    let object_layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let object = object_layer.get_object(0).unwrap();
+    assert_eq!(&*object.user_type, "");
+    assert!(object.properties.is_empty());
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_load_map_images() {
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    let images = loader.load_map_images(&map).unwrap();
+
+    // The tileset and the image layer share the same image, which is only decoded once.
+    assert_eq!(images.len(), 1);
+    let image = &images[&PathBuf::from("assets/tilesheet.png")];
+    assert_eq!((image.width(), image.height()), (448, 192));
+
+    let map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let images = loader.load_map_images(&map).unwrap();
+    assert_eq!(images.len(), 1);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_load_images() {
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_nested_class_property.tmx")
+        .unwrap();
+
+    // Image collection tilesets have no image of their own, but their tiles do
+    let tileset = &map.tilesets()[0];
+    assert!(tileset.load_image(loader.reader_mut()).unwrap().is_none());
+    let tile = tileset.get_tile(0).unwrap();
+    let image = tile.load_image(loader.reader_mut()).unwrap().unwrap();
+    assert_eq!(image.dimensions(), (448, 192));
+    assert_eq!(image.as_raw().len(), 448 * 192 * 4);
+
+    let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    let sheet = tileset.load_image(loader.reader_mut()).unwrap().unwrap();
+    assert_eq!(sheet, image);
+    assert!(tileset
+        .get_tile(0)
+        .unwrap()
+        .load_image(loader.reader_mut())
+        .unwrap()
+        .is_none());
+
+    // Pixels of the transparent color are made transparent
+    let image = tiled::Image {
+        source: PathBuf::from("assets/tilesheet.png").into(),
+        width: 448,
+        height: 192,
+        transparent_colour: Some(Color {
+            alpha: 0xff,
+            red: sheet.get_pixel(0, 0)[0],
+            green: sheet.get_pixel(0, 0)[1],
+            blue: sheet.get_pixel(0, 0)[2],
+        }),
+    };
+    let keyed = image.load(loader.reader_mut()).unwrap();
+    assert_eq!(keyed.get_pixel(0, 0)[3], 0);
+
+    let missing = tiled::Image {
+        source: PathBuf::from("assets/missing.png").into(),
+        transparent_colour: None,
+        ..image
+    };
+    assert!(matches!(
+        missing.load(loader.reader_mut()),
+        Err(tiled::Error::ResourceLoadingError { .. })
+    ));
+}
+
+#[test]
+fn test_raw_parse() {
+    let mut reader = tiled::FilesystemResourceReader::new();
+    let data = tiled::parse_map_data("assets/tiled_object_template.tmx", &mut reader).unwrap();
+    assert_eq!(data.tilesets.len(), 1);
+    assert_eq!(data.tilesets[0].name, "tilesheet");
+    assert_eq!(data.layers.len(), 2);
+    assert!(!data.infinite);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(Map::from(data), map);
+
+    let tileset = tiled::parse_tileset_data("assets/tilesheet.tsx", &mut reader).unwrap();
+    assert_eq!(tileset, *map.tilesets()[0]);
+}
+
+#[test]
+fn test_max_group_depth() {
+    fn nested_groups(depth: usize) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+{}{}
+</map>"#,
+            "<group>".repeat(depth),
+            "</group>".repeat(depth)
+        )
+    }
+    fn loader_for(depth: usize) -> Loader<impl tiled::ResourceReader, tiled::DefaultResourceCache> {
+        let contents = nested_groups(depth);
+        Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+            Ok(std::io::Cursor::new(contents.clone().into_bytes()))
+        })
+    }
+
+    let mut loader = loader_for(10);
+    loader.set_options(tiled::LoaderOptions {
+        max_group_depth: 10,
+        ..Default::default()
+    });
+    assert!(loader.load_tmx_map("/nested.tmx").is_ok());
+    loader.set_options(tiled::LoaderOptions {
+        max_group_depth: 5,
+        ..Default::default()
+    });
+    assert!(matches!(
+        loader.load_tmx_map("/nested.tmx"),
+        Err(tiled::Error::GroupNestingTooDeep { max_depth: 5 })
+    ));
+
+    let mut loader = loader_for(10_000);
+    assert!(matches!(
+        loader.load_tmx_map("/nested.tmx"),
+        Err(tiled::Error::GroupNestingTooDeep { max_depth: 32 })
+    ));
+}
+
+#[test]
+fn test_lenient_loading() {
+    let mut loader = Loader::new();
+    assert!(loader.load_tmx_map("assets/tiled_lenient.tmx").is_err());
+
+    loader.set_options(tiled::LoaderOptions {
+        lenient: true,
+        ..Default::default()
+    });
+    let map = loader.load_tmx_map("assets/tiled_lenient.tmx").unwrap();
+
+    let kinds: Vec<_> = map
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.kind.clone())
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            tiled::DiagnosticKind::ReplacedTileset { index: 0 },
+            tiled::DiagnosticKind::ReplacedLayer {
+                id: 1,
+                name: "broken data".to_owned()
+            },
+            tiled::DiagnosticKind::ReplacedLayer {
+                id: 4,
+                name: "broken attributes".to_owned()
+            },
+        ]
+    );
+
+    // The placeholder tileset keeps the GIDs of the following tilesets valid.
+    assert_eq!(map.tilesets().len(), 2);
+    assert_eq!(map.tilesets()[0].tilecount, 0);
+    let ok = as_finite(map.get_layer(1).unwrap().as_tile_layer().unwrap());
+    assert_eq!(ok.get_tile(0, 0).unwrap().tileset_index(), 1);
+    assert_eq!(ok.get_tile(1, 0).unwrap().id(), 1);
+    assert_eq!(ok.get_tile(0, 1).unwrap().tileset_index(), 0);
+
+    // Broken layers are replaced by empty layers of the same kind.
+    let broken = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!((broken.width(), broken.height()), (2, 2));
+    assert!(broken.get_tile(0, 0).is_none());
+
+    let group = map.get_layer(2).unwrap().as_group_layer().unwrap();
+    assert_eq!(group.layers().len(), 2);
+    let objects = group.get_layer(0).unwrap();
+    assert_eq!(objects.id(), 4);
+    assert_eq!(objects.as_object_layer().unwrap().objects().len(), 0);
+    assert_eq!(&*group.get_layer(1).unwrap().name, "image");
+}
+
+#[test]
+fn test_map_objects() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_nested_objects.tmx")
+        .unwrap();
+
+    let objects: Vec<_> = map
+        .objects()
+        .map(|(layer, object)| (layer.id(), object.id()))
+        .collect();
+    assert_eq!(objects, [(2, 1), (2, 2), (4, 3), (6, 4), (6, 5)]);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(map.objects().count(), 0);
+}
+
+#[test]
+fn test_tile_layer_bounds() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let bounds = layer.bounds();
+    assert_eq!(
+        bounds,
+        TileRect {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100
+        }
+    );
+    assert!(bounds.contains(99, 0));
+    assert!(!bounds.contains(100, 0));
+    assert!(!bounds.contains(-1, 0));
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(
+        layer.bounds(),
+        TileRect {
+            x: -16,
+            y: 0,
+            width: 48,
+            height: 48
+        }
+    );
+    for layer in map.layers().filter_map(|layer| layer.as_tile_layer()) {
+        let bounds = layer.bounds();
+        for y in bounds.y - 16..bounds.y + bounds.height as i32 + 16 {
+            for x in bounds.x - 16..bounds.x + bounds.width as i32 + 16 {
+                if layer.get_tile(x, y).is_some() {
+                    assert!(bounds.contains(x, y));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tile_layer_chunks() {
+    for path in [
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        for layer in map.layers().filter_map(|layer| layer.as_tile_layer()) {
+            let bounds = layer.bounds();
+            let chunks: Vec<_> = layer.chunks().collect();
+            assert!(!chunks.is_empty());
+
+            let mut chunked_tiles = 0;
+            for chunk in &chunks {
+                let rect = chunk.rect();
+                assert!(rect.width <= ChunkData::WIDTH && rect.height <= ChunkData::HEIGHT);
+                assert!(bounds.contains(rect.x, rect.y));
+                assert!(chunk.get_tile(-1, 0).is_none());
+                assert!(chunk.get_tile(rect.width as i32, 0).is_none());
+                for (x, y, tile) in chunk.tiles() {
+                    assert!(rect.contains(x, y));
+                    let expected = layer.get_tile(x, y).unwrap();
+                    assert_eq!(tile.id(), expected.id());
+                    assert_eq!(
+                        chunk.get_tile(x - rect.x, y - rect.y).unwrap().id(),
+                        tile.id()
+                    );
+                    chunked_tiles += 1;
+                }
+            }
+            assert!(chunks
+                .windows(2)
+                .all(|pair| (pair[0].rect().y, pair[0].rect().x)
+                    < (pair[1].rect().y, pair[1].rect().x)));
+
+            let mut layer_tiles = 0;
+            for y in bounds.y..bounds.y + bounds.height as i32 {
+                for x in bounds.x..bounds.x + bounds.width as i32 {
+                    if layer.get_tile(x, y).is_some() {
+                        layer_tiles += 1;
+                    }
+                }
+            }
+            assert_eq!(chunked_tiles, layer_tiles);
+        }
+    }
+
+    // 100 isn't a multiple of the chunk size, so the chunks at the edges are smaller.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert!(layer.chunks().all(|chunk| {
+        let rect = chunk.rect();
+        rect.x as u32 + rect.width <= 100 && rect.y as u32 + rect.height <= 100
+    }));
+}
+
+#[test]
+fn test_layer_to_affine() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_layer_transform.tmx")
+        .unwrap();
+    let group = map.get_layer(0).unwrap();
+    let layer = group.as_group_layer().unwrap().layers().next().unwrap();
+
+    // Isometric projection: the origin tile is at the horizontal center of the map.
+    assert_eq!(map.projection().apply(0.0, 0.0), (96.0, 0.0));
+    assert_eq!(map.projection().apply(1.0, 0.0), (128.0, 16.0));
+    assert_eq!(map.projection().apply(0.0, 1.0), (64.0, 16.0));
+
+    // Offsets are summed and parallax factors are multiplied with the parent's.
+    let transform = layer.to_affine((0.0, 0.0));
+    assert_eq!(transform.apply(0.0, 0.0), (111.0, 15.0));
+    let transform = layer.to_affine((100.0, 100.0));
+    assert_eq!(transform.apply(0.0, 0.0), (111.0 + 75.0, 15.0 + 50.0));
+    assert_eq!(transform.apply(1.0, 0.0), (143.0 + 75.0, 31.0 + 50.0));
+
+    let transform = group.to_affine((100.0, 100.0));
+    assert_eq!(transform.apply(0.0, 0.0), (106.0 + 50.0, 20.0 + 50.0));
+    assert_eq!(
+        Affine::translation(1.0, 2.0)
+            .then(&Affine::scale(2.0, 3.0))
+            .to_cols_array(),
+        [2.0, 0.0, 0.0, 3.0, 2.0, 6.0]
+    );
+}
+
+#[test]
+fn test_coordinate_conversions() {
+    use tiled::{StaggerAxis, StaggerIndex};
+
+    let orientations = [
+        (Orientation::Orthogonal, 0),
+        (Orientation::Isometric, 0),
+        (Orientation::Staggered, 0),
+        (Orientation::Hexagonal, 0),
+        (Orientation::Hexagonal, 12),
+    ];
+    for (orientation, hex_side_length) in orientations.iter().copied() {
+        for stagger_axis in [StaggerAxis::X, StaggerAxis::Y].iter().copied() {
+            for stagger_index in [StaggerIndex::Odd, StaggerIndex::Even].iter().copied() {
+                let mut map = Map::new(orientation, 10, 8, 32, 24, false);
+                map.stagger_axis = stagger_axis;
+                map.stagger_index = stagger_index;
+                map.hex_side_length = hex_side_length;
+                for y in -3..11 {
+                    for x in -3..13 {
+                        let (px, py) = map.tile_to_world(x, y);
+                        let (cx, cy) = (px + 16.0, py + 12.0);
+                        for (dx, dy) in
+                            [(0.0, 0.0), (-7.0, 0.0), (7.0, 0.0), (0.0, -5.0), (0.0, 5.0)]
+                                .iter()
+                                .copied()
+                        {
+                            assert_eq!(
+                                map.world_to_tile(cx + dx, cy + dy),
+                                (x, y),
+                                "{:?} {:?} {:?} map with {} side length",
+                                orientation,
+                                stagger_axis,
+                                stagger_index,
+                                hex_side_length
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let map = Map::new(Orientation::Orthogonal, 10, 8, 32, 24, false);
+    assert_eq!(map.tile_to_world(2, 3), (64.0, 72.0));
+    assert_eq!(map.world_to_tile(-0.5, 23.9), (-1, 0));
+
+    // Staggered maps shift every other row by half a tile
+    let mut map = Map::new(Orientation::Staggered, 10, 8, 32, 16, false);
+    map.stagger_axis = StaggerAxis::Y;
+    map.stagger_index = StaggerIndex::Odd;
+    assert_eq!(map.tile_to_world(1, 1), (48.0, 8.0));
+    assert_eq!(map.tile_to_world(1, 2), (32.0, 16.0));
+    map.stagger_index = StaggerIndex::Even;
+    assert_eq!(map.tile_to_world(1, 1), (32.0, 8.0));
+    assert_eq!(map.tile_to_world(1, 2), (48.0, 16.0));
+
+    // The side length of hexagonal maps is read and written
+    let tmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="hexagonal" width="4" height="4" tilewidth="28" tileheight="32" infinite="0" hexsidelength="14" staggeraxis="x" staggerindex="even">
+</map>"#;
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(tmx.as_bytes()))
+    });
+    let map = loader.load_tmx_map("/hex.tmx").unwrap();
+    assert_eq!(map.hex_side_length, 14);
+    assert_eq!(map.tile_to_world(0, 0), (0.0, 16.0));
+    assert_eq!(map.tile_to_world(1, 0), (21.0, 0.0));
+    assert_eq!(map.tile_to_world(2, 1), (42.0, 48.0));
+    let mut tmx = Vec::new();
+    map.to_writer(&mut tmx, &WriteOptions::default()).unwrap();
+    assert!(String::from_utf8(tmx)
+        .unwrap()
+        .contains(r#"hexsidelength="14""#));
+}
+
+#[test]
+fn test_property_value_conversions() {
+    use std::convert::TryFrom;
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let tileset = &map.tilesets()[0];
+    let properties = &tileset.get_tile(1).unwrap().properties;
+    assert_eq!(
+        String::try_from(&properties["a tile property"]).as_deref(),
+        Ok("123")
+    );
+    assert_eq!(
+        i32::try_from(&properties["a tile property"]),
+        Err(PropertyTypeError {
+            expected: "int",
+            found: "string"
+        })
+    );
+
+    assert_eq!(i32::try_from(&PropertyValue::from(-3)), Ok(-3));
+    assert_eq!(f32::try_from(&PropertyValue::from(0.5)), Ok(0.5));
+    assert_eq!(bool::try_from(&PropertyValue::from(true)), Ok(true));
+    assert_eq!(
+        PropertyValue::from("hello"),
+        PropertyValue::StringValue("hello".to_owned())
+    );
+    assert_eq!(
+        PathBuf::try_from(&PropertyValue::from(PathBuf::from("a/b.png"))),
+        Ok(PathBuf::from("a/b.png"))
+    );
+    assert_eq!(
+        ObjectId::try_from(&PropertyValue::ObjectValue(4)),
+        Ok(ObjectId(4))
+    );
+    assert_eq!(
+        PropertyValue::from(ObjectId(7)),
+        PropertyValue::ObjectValue(7)
+    );
+    let color = Color {
+        alpha: 255,
+        red: 1,
+        green: 2,
+        blue: 3,
+    };
+    assert_eq!(Color::try_from(&PropertyValue::from(color)), Ok(color));
+    assert!(Color::try_from(&PropertyValue::from(1)).is_err());
+}
+
+#[test]
+fn test_object_rotation_pivot() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_rotation.tmx")
+        .unwrap();
+    let find = |name: &str| {
+        map.objects()
+            .find(|(_, object)| object.name == name)
+            .unwrap()
+            .1
+    };
+    let approx = |(x0, y0): (f32, f32), (x1, y1): (f32, f32)| {
+        (x0 - x1).abs() < 1e-4 && (y0 - y1).abs() < 1e-4
+    };
+
+    let crate_object = find("crate");
+    assert_eq!(crate_object.pivot(), (0.0, 0.0));
+    assert!(approx(
+        crate_object.apply_rotation((32.0, 32.0)),
+        (32.0, 32.0)
+    ));
+    assert!(approx(
+        crate_object.apply_rotation((64.0, 48.0)),
+        (16.0, 64.0)
+    ));
+
+    // Tile objects rotate around the bottom-left corner of their image.
+    let chest = find("chest");
+    assert_eq!(chest.pivot(), (0.0, 32.0));
+    let top_left = (chest.x - chest.pivot().0, chest.y - chest.pivot().1);
+    assert_eq!(top_left, (64.0, 64.0));
+    assert!(approx(chest.apply_rotation(top_left), (96.0, 96.0)));
+
+    assert_eq!(find("marker").pivot(), (0.0, 0.0));
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_instrumentation() {
+    use std::sync::{Arc, Mutex};
+    use tracing::{field::Field, span, Event, Metadata, Subscriber};
+
+    /// Records the names of the spans created and the messages of the events emitted.
+    #[derive(Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct MessageVisitor<'a>(&'a mut String);
+
+    impl tracing::field::Visit for MessageVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                *self.0 = format!("{:?}", value);
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut records = self.0.lock().unwrap();
+            records.push(span.metadata().name().to_owned());
+            span::Id::from_u64(records.len() as u64)
+        }
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut message = String::new();
+            event.record(&mut MessageVisitor(&mut message));
+            self.0.lock().unwrap().push(message);
+        }
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut loader = Loader::new();
+        loader
+            .load_tmx_map("assets/tiled_base64_external.tmx")
+            .unwrap();
+        loader
+            .load_tmx_map("assets/tiled_base64_external.tmx")
+            .unwrap();
+    });
+
+    let records = recorder.0.lock().unwrap();
+    let count = |name: &str| records.iter().filter(|r| *r == name).count();
+    assert_eq!(count("parse_map"), 2);
+    assert_eq!(count("parse_tileset"), 1);
+    assert!(count("parse_layer") >= 2);
+    assert!(count("decode_tile_data") >= 2);
+    assert_eq!(count("tileset cache miss"), 1);
+    assert_eq!(count("tileset cache hit"), 1);
+}
+
+#[test]
+fn test_bom_and_utf16_encodings() {
+    let mut loader = Loader::new();
+    let reference = loader.load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    for path in [
+        "assets/tiled_csv_utf8_bom.tmx",
+        "assets/tiled_csv_utf16le.tmx",
+        "assets/tiled_csv_utf16be.tmx",
+    ] {
+        let map = loader.load_tmx_map(path).unwrap();
+        compare_everything_but_tileset_sources(&reference, &map);
+        let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+        assert_eq!(layer.get_tile(0, 0).unwrap().id(), 34);
+        assert_eq!(layer.get_tile(1, 2).unwrap().id(), 16);
+        assert_eq!(map.layers().len(), reference.layers().len());
+    }
+}
+
+#[test]
+fn test_stream_map() {
+    use std::sync::Arc;
+
+    /// Records the callbacks it receives, skipping the contents of the layers named "group-3".
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        tiles: Vec<(u32, i32, i32, u32)>,
+    }
+
+    impl MapStreamVisitor for Recorder {
+        fn visit_map(&mut self, map: &StreamedMap) {
+            self.events
+                .push(format!("map {}x{}", map.width, map.height));
+        }
+
+        fn visit_tileset(&mut self, index: usize, tileset: &Arc<Tileset>) {
+            self.events
+                .push(format!("tileset {} {}", index, tileset.name));
+        }
+
+        fn visit_properties(&mut self, layer_id: Option<u32>, properties: &Properties) {
+            self.events
+                .push(format!("properties {:?} {}", layer_id, properties.len()));
+        }
+
+        fn visit_layer(&mut self, layer: &StreamedLayer) -> bool {
+            self.events.push(format!("enter {}", layer.name));
+            layer.name != "group-3"
+        }
+
+        fn leave_layer(&mut self, layer: &StreamedLayer) {
+            self.events.push(format!("leave {}", layer.name));
+        }
+
+        fn visit_chunk(&mut self, chunk: StreamedChunk) {
+            for y in chunk.y..chunk.y + chunk.height as i32 {
+                for x in chunk.x..chunk.x + chunk.width as i32 {
+                    if let Some(tile) = chunk.get_tile_data(x, y) {
+                        self.tiles.push((chunk.layer_id, x, y, tile.id()));
+                    }
+                }
+            }
+        }
+
+        fn visit_object(&mut self, layer_id: u32, object: &ObjectData) {
+            self.events
+                .push(format!("object {} {}", layer_id, object.name));
+        }
+    }
+
+    let mut loader = Loader::new();
+    let mut recorder = Recorder::default();
+    loader
+        .stream_tmx_map("assets/tiled_group_layers.tmx", &mut recorder)
+        .unwrap();
+    assert_eq!(
+        recorder.events,
+        [
+            "map 8x8",
+            "tileset 0 tilesheet",
+            "enter tile-1",
+            "properties Some(1) 1",
+            "leave tile-1",
+            "enter group-1",
+            "properties Some(3) 1",
+            "enter tile-2",
+            "properties Some(5) 1",
+            "leave tile-2",
+            "leave group-1",
+            "enter group-2",
+            "properties Some(6) 1",
+            "enter group-3",
+            "leave group-3",
+            "leave group-2",
+        ]
+    );
+
+    // The streamed tiles are the ones of the loaded map
+    let map = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap();
+    let expected: Vec<_> = (0..8)
+        .flat_map(|y| (0..8).map(move |x| (x, y)))
+        .filter_map(|(x, y)| {
+            let tile = layer.as_tile_layer().unwrap().get_tile(x, y)?;
+            Some((layer.id(), x, y, tile.id()))
+        })
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(
+        recorder.tiles.iter().filter(|tile| tile.0 == 1).count(),
+        expected.len()
+    );
+    assert!(expected.iter().all(|tile| recorder.tiles.contains(tile)));
+    assert!(recorder.tiles.iter().all(|tile| tile.0 != 9));
+
+    let mut recorder = Recorder::default();
+    loader
+        .stream_tmx_map("assets/tiled_base64_zlib_infinite.tmx", &mut recorder)
+        .unwrap();
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let expected = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer
+            .chunks()
+            .map(|(_, chunk)| {
+                (0..ChunkData::HEIGHT as i32)
+                    .flat_map(|y| (0..ChunkData::WIDTH as i32).map(move |x| (x, y)))
+                    .filter(|&(x, y)| chunk.get_tile(x, y).is_some())
+                    .count()
+            })
+            .sum::<usize>(),
+        TileLayer::Finite(_) => panic!("Not an infinite tile layer"),
+    };
+    let layer_id = map.get_layer(0).unwrap().id();
+    assert_eq!(
+        recorder
+            .tiles
+            .iter()
+            .filter(|tile| tile.0 == layer_id)
+            .count(),
+        expected
+    );
+
+    let mut recorder = Recorder::default();
+    loader
+        .stream_tmx_map("assets/tiled_object_rotation.tmx", &mut recorder)
+        .unwrap();
+    assert_eq!(
+        recorder.events[2..],
+        [
+            "enter objects",
+            "object 1 crate",
+            "object 1 chest",
+            "object 1 marker",
+            "leave objects",
+        ]
+    );
+}
+
+#[test]
+fn test_object_spatial_queries() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_rotation.tmx")
+        .unwrap();
+    let names = |objects: &mut dyn Iterator<Item = Object>| -> Vec<String> {
+        objects.map(|object| object.name.clone()).collect()
+    };
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    assert_eq!(
+        names(&mut layer.objects_in_rect(BoundingBox::new(0.0, 0.0, 128.0, 128.0))),
+        ["crate", "chest", "marker"]
+    );
+    // The crate is rotated by 90 degrees around (32, 32), which makes it span (16, 32)-(32, 64)
+    assert_eq!(names(&mut layer.objects_at_point(20.0, 40.0)), ["crate"]);
+    assert_eq!(names(&mut layer.objects_at_point(16.0, 16.0)), ["marker"]);
+    assert_eq!(layer.objects_at_point(40.0, 40.0).count(), 0);
+    assert_eq!(
+        names(&mut layer.objects_in_rect(BoundingBox::new(60.0, 100.0, 10.0, 10.0))),
+        ["chest"]
+    );
+
+    // Objects added afterwards are found too
+    let mut layer_mut = map.get_object_layer_mut(1).unwrap();
+    for i in 0..1000 {
+        let shape = ObjectShape::Rect {
+            width: 8.0,
+            height: 8.0,
+        };
+        let x = (i % 40) as f32 * 10.0 - 100.0;
+        let y = (i / 40) as f32 * 10.0 - 100.0;
+        layer_mut.add_object(ObjectData::from_shape(x, y, shape));
+    }
+    layer_mut.add_object(ObjectData::from_shape(
+        -5000.0,
+        -5000.0,
+        ObjectShape::Rect {
+            width: 10000.0,
+            height: 10000.0,
+        },
+    ));
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    for rect in [
+        BoundingBox::new(20.0, 40.0, 0.0, 0.0),
+        BoundingBox::new(-45.0, 12.0, 33.0, 71.0),
+        BoundingBox::new(-1000.0, -1000.0, 2000.0, 2000.0),
+    ]
+    .iter()
+    {
+        // The objects added are unrotated rectangles, whose bounding box is easy to tell
+        let added = |object: &Object| object.id() >= 4;
+        let expected: Vec<_> = layer
+            .objects()
+            .filter(|object| added(object))
+            .filter(|object| match object.shape {
+                ObjectShape::Rect { width, height } => {
+                    BoundingBox::new(object.x, object.y, width, height).intersects(rect)
+                }
+                _ => false,
+            })
+            .map(|object| object.id())
+            .collect();
+        let found: Vec<_> = layer
+            .objects_in_rect(*rect)
+            .filter(|object| added(object))
+            .map(|o| o.id())
+            .collect();
+        assert_eq!(found, expected);
+    }
+    assert_eq!(layer.objects_at_point(-96.0, -96.0).count(), 2);
+}
+
+#[test]
+fn test_animation_state() {
+    let frame = |tile_id, duration| Frame { tile_id, duration };
+    let mut state = AnimationState::new(vec![frame(1, 100), frame(2, 0), frame(3, 50)]);
+    assert_eq!(state.total_duration(), Duration::from_millis(150));
+    assert_eq!(state.current_tile_id(), Some(1));
+
+    state.update(Duration::from_millis(99));
+    assert_eq!(state.current_tile_id(), Some(1));
+    // Zero-length frames are skipped
+    state.update(Duration::from_millis(1));
+    assert_eq!(state.current_tile_id(), Some(3));
+    assert_eq!(state.time_in_frame(), Duration::ZERO);
+    state.update(Duration::from_millis(60));
+    assert_eq!(state.current_tile_id(), Some(1));
+    assert_eq!(state.time_in_frame(), Duration::from_millis(10));
+
+    // Many loops at once
+    state.update(Duration::from_millis(150 * 1000 + 95));
+    assert_eq!(state.frame_index(), 2);
+    assert_eq!(state.time_in_frame(), Duration::from_millis(5));
+
+    state.reset();
+    assert_eq!(state.current_tile_id(), Some(1));
+
+    let mut state = AnimationState::new(vec![frame(7, 0), frame(8, 0)]);
+    state.update(Duration::from_secs(1));
+    assert_eq!(state.current_tile_id(), Some(7));
+    let mut state = AnimationState::new(Vec::new());
+    state.update(Duration::from_secs(1));
+    assert_eq!(state.current_tile_id(), None);
+}
+
+#[test]
+fn test_embedded_image() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_embedded_image.tmx")
+        .unwrap();
+    let image = map.tilesets()[0].image.as_ref().unwrap();
+    let (data, format) = image.source.as_data().unwrap();
+    assert_eq!(format, "png");
+    assert!(data.starts_with(b"\x89PNG"));
+    assert_eq!((image.width, image.height), (2, 2));
+
+    let image_layer = map.get_layer(1).unwrap().as_image_layer().unwrap();
+    assert_eq!(image_layer.image.as_ref(), Some(image));
+
+    let mut tmx = Vec::new();
+    map.to_writer(&mut tmx, &WriteOptions::default()).unwrap();
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(tmx.clone()))
+    });
+    let written = loader.load_tmx_map("/embedded.tmx").unwrap();
+    assert_eq!(written.tilesets()[0].image.as_ref(), Some(image));
+
+    #[cfg(feature = "image")]
+    {
+        let pixels = image
+            .load(&mut tiled::FilesystemResourceReader::new())
+            .unwrap();
+        assert_eq!(pixels.dimensions(), (2, 2));
+        assert_eq!(pixels.get_pixel(1, 0).0, [0, 255, 0, 255]);
+        assert_eq!(pixels.get_pixel(0, 1).0, [0, 0, 255, 255]);
+    }
+}
+
+#[test]
+fn test_image_path_rewriter() {
+    let mut loader = Loader::new();
+    loader.set_image_path_rewriter(|path| {
+        PathBuf::from("cooked")
+            .join(path.file_name().unwrap())
+            .with_extension("ktx2")
+    });
+    let expected = ImageSource::Path(PathBuf::from("cooked/tilesheet.ktx2"));
+
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].image.as_ref().unwrap().source, expected);
+
+    let map = loader
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    let image_layer = map.get_layer(1).unwrap().as_image_layer().unwrap();
+    assert_eq!(image_layer.image.as_ref().unwrap().source, expected);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    assert_eq!(
+        map.tilesets()[0].image.as_ref().unwrap().source,
+        ImageSource::Path(PathBuf::from("assets/tilesheet.png"))
+    );
+}
+
+#[test]
+fn test_load_with_dependencies() {
+    let mut loader = Loader::new();
+    let (map, dependencies) = loader
+        .load_tmx_map_with_dependencies("assets/tiled_object_template.tmx")
+        .unwrap();
+
+    assert_eq!(dependencies.tilesets.len(), 2);
+    assert!(std::sync::Arc::ptr_eq(
+        &dependencies.tilesets[0],
+        &map.tilesets()[0]
+    ));
+    assert_eq!(dependencies.templates.len(), 1);
+    assert!(std::sync::Arc::ptr_eq(
+        &dependencies.tilesets[1],
+        dependencies.templates[0].tileset.as_ref().unwrap()
+    ));
+    assert_eq!(dependencies.images, [PathBuf::from("assets/tilesheet.png")]);
+
+    // Resources coming from the cache are listed as well.
+    let (_, cached) = loader
+        .load_tmx_map_with_dependencies("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(cached.tilesets.len(), 2);
+    assert_eq!(cached.templates.len(), 1);
+    assert!(std::sync::Arc::ptr_eq(
+        &cached.templates[0],
+        &dependencies.templates[0]
+    ));
+
+    let (_, dependencies) = loader
+        .load_tmx_map_with_dependencies("assets/tiled_image_layers.tmx")
+        .unwrap();
+    assert!(dependencies.tilesets.is_empty());
+    assert!(dependencies.templates.is_empty());
+    assert_eq!(dependencies.images, [PathBuf::from("assets/tilesheet.png")]);
+}
+
+#[test]
+fn test_scan_dependencies() {
+    let mut loader = Loader::new();
+    let dependencies = loader
+        .scan_tmx_dependencies("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(
+        dependencies,
+        [
+            PathBuf::from("assets/tilesheet.tsx"),
+            PathBuf::from("assets/tiled_object_template.tx"),
+            PathBuf::from("assets/tilesheet.png"),
+            PathBuf::from("assets/tilesheet_template.tsx"),
+        ]
+    );
+    // Nothing is loaded while scanning
+    assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+    assert!(loader
+        .cache()
+        .get_template("assets/tiled_object_template.tx")
+        .is_none());
+
+    let dependencies = loader
+        .scan_tmx_dependencies("assets/tiled_image_layers.tmx")
+        .unwrap();
+    assert_eq!(dependencies, [PathBuf::from("assets/tilesheet.png")]);
+}
+
+#[test]
+fn test_concurrent_tileset_loading() {
+    use std::{
+        future::Future,
+        io::Cursor,
+        path::Path,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    };
+
+    /// Reads a file on its second poll, keeping track of how many reads are in flight meanwhile.
+    struct SlowRead {
+        path: PathBuf,
+        started: bool,
+        in_flight: Arc<AtomicUsize>,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl Future for SlowRead {
+        type Output = std::io::Result<Cursor<Vec<u8>>>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+            if !self.started {
+                self.started = true;
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Poll::Ready(std::fs::read(&self.path).map(Cursor::new))
+        }
+    }
+
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let reads = max_in_flight.clone();
+    let mut loader = Loader::with_reader(move |path: &Path| SlowRead {
+        path: path.to_owned(),
+        started: false,
+        in_flight: in_flight.clone(),
+        max_in_flight: reads.clone(),
+    });
+    let map = futures::executor::block_on(
+        loader.load_tmx_map_async("assets/tiled_multiple_tilesets.tmx"),
+    )
+    .unwrap();
+
+    // Both tilesets were read at once, and the one referenced twice only once
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    let tilesets = map.tilesets();
+    assert_eq!(tilesets.len(), 3);
+    assert_eq!(tilesets[0].name, "tilesheet");
+    assert_eq!(tilesets[1].name, "tilesheet_wangsets");
+    assert!(Arc::ptr_eq(&tilesets[0], &tilesets[2]));
+    assert!(Arc::ptr_eq(
+        &tilesets[0],
+        &loader.cache().get_tileset("assets/tilesheet.tsx").unwrap()
+    ));
+
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(layer.get_tile(0, 0).unwrap().tileset_index(), 0);
+    assert_eq!(layer.get_tile(1, 0).unwrap().tileset_index(), 1);
+    assert_eq!(layer.get_tile(0, 1).unwrap().tileset_index(), 2);
+}
+
+#[test]
+fn test_chunk_handler() {
+    use std::sync::{Arc, Mutex};
+
+    let streamed = Arc::new(Mutex::new(Vec::new()));
+    let sink = streamed.clone();
+    let mut loader = Loader::new();
+    loader.set_chunk_handler(true, move |chunk| {
+        let tiles: Vec<_> = (0..chunk.height as i32)
+            .flat_map(|y| (0..chunk.width as i32).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                chunk
+                    .get_tile_data(chunk.x + x, chunk.y + y)
+                    .map(|t| t.id())
+            })
+            .collect();
+        sink.lock()
+            .unwrap()
+            .push((chunk.layer_id, chunk.x, chunk.y, chunk.width, tiles));
+    });
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+
+    let streamed = std::mem::take(&mut *streamed.lock().unwrap());
+    assert!(!streamed.is_empty());
+    for (layer_id, x, y, width, tiles) in &streamed {
+        let layer = map
+            .layers()
+            .find(|layer| layer.id() == *layer_id)
+            .and_then(|layer| layer.as_tile_layer())
+            .unwrap();
+        for (i, tile) in tiles.iter().enumerate() {
+            let (tx, ty) = (x + (i as u32 % width) as i32, y + (i as u32 / width) as i32);
+            assert_eq!(*tile, layer.get_tile(tx, ty).map(|t| t.id()));
+        }
+    }
+
+    let count = Arc::new(Mutex::new(0));
+    let sink = count.clone();
+    let mut loader = Loader::new();
+    loader.set_chunk_handler(false, move |_| *sink.lock().unwrap() += 1);
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    assert_eq!(*count.lock().unwrap(), streamed.len());
+    for layer in map.layers().filter_map(|layer| layer.as_tile_layer()) {
+        assert!(layer.bounds().is_empty());
+    }
+}
+
+#[test]
+fn test_compact_gids() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_gid_gaps.tmx")
+        .unwrap();
+    assert_eq!(map.first_gids(), vec![1, 5, 89]);
+
+    let before = map.clone();
+    assert_eq!(map.compact_gids(), vec![1, 85]);
+    assert_eq!(map.tilesets().len(), 2);
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+    assert_eq!(map.tilesets()[1].gid_span(), 8);
+
+    let tiles = |map: &Map| {
+        let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+        (0..4)
+            .flat_map(|x| (0..2).map(move |y| (x, y)))
+            .map(|(x, y)| {
+                layer
+                    .get_tile(x, y)
+                    .map(|tile| (tile.get_tileset().name.clone(), tile.id()))
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(tiles(&map), tiles(&before));
+
+    let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    let objects = group.layers().next().unwrap().as_object_layer().unwrap();
+    let tile = objects.objects().next().unwrap().get_tile().unwrap();
+    assert_eq!(tile.get_tileset().name, "props");
+    assert_eq!(tile.id(), 7);
+}
+
+#[test]
+fn test_spawn_from_template() {
+    let mut loader = Loader::new();
+    let mut map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let template = loader
+        .cache()
+        .get_template("assets/tiled_object_template.tx")
+        .unwrap();
+    assert_eq!(map.next_object_id(), 3);
+
+    let object = template.instantiate_at(10.0, 20.0);
+    assert_eq!((object.id(), object.x, object.y), (0, 10.0, 20.0));
+    assert_eq!(object.shape, template.object.shape);
+    assert_eq!(object.properties, template.object.properties);
+
+    assert!(map.get_object_layer_mut(1).is_none());
+    let mut layer = map.get_object_layer_mut(2).unwrap();
+    assert_eq!(layer.spawn_from_template(&template, 10.0, 20.0), 3);
+    assert_eq!(layer.add_object(template.instantiate_at(0.0, 0.0)), 4);
+    assert_eq!(map.next_object_id(), 5);
+
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(layer.objects().len(), 4);
+    let spawned = layer.get_object(2).unwrap();
+    let original = layer.get_object(0).unwrap();
+    assert_eq!(spawned.id(), 3);
+    assert_eq!((spawned.x, spawned.y), (10.0, 20.0));
+    assert_eq!(
+        spawned.get_tile().map(|tile| tile.id()),
+        original.get_tile().map(|tile| tile.id())
+    );
+}
+
+#[test]
+fn test_object_graph() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_graph.tmx")
+        .unwrap();
+    let graph = map.object_graph();
+    assert_eq!(graph.nodes(), &[1, 2, 3, 4]);
+
+    let edges: Vec<_> = graph
+        .edges()
+        .iter()
+        .map(|edge| (edge.from, edge.to, edge.property.as_str()))
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            (1, 2, "opens"),
+            (2, 3, "link.next"),
+            (3, 1, "back"),
+            (3, 99, "key"),
+        ]
+    );
+
+    assert_eq!(graph.edges_to(1).count(), 1);
+    assert_eq!(graph.edges_from(4).count(), 0);
+    assert_eq!(graph.reachable_from(1), vec![2, 3, 1]);
+    assert!(graph.reachable_from(4).is_empty());
+    let dangling: Vec<_> = graph.dangling().map(|edge| edge.to).collect();
+    assert_eq!(dangling, vec![99]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_property_json_conversions() {
+    use std::convert::TryFrom;
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_nested_class_property.tmx")
+        .unwrap();
+    let mut values: Vec<PropertyValue> = map.properties.values().cloned().collect();
+    values.extend(vec![
+        PropertyValue::BoolValue(true),
+        PropertyValue::IntValue(-3),
+        PropertyValue::FloatValue(0.1),
+        PropertyValue::FloatValue(2.0),
+        PropertyValue::FloatValue(f32::INFINITY),
+        PropertyValue::ColorValue(Color {
+            alpha: 0x80,
+            red: 0xff,
+            green: 0,
+            blue: 0x10,
+        }),
+        PropertyValue::StringValue("plain".to_owned()),
+        PropertyValue::StringValue("color:not a color".to_owned()),
+        PropertyValue::FileValue(PathBuf::from("../sprites.png")),
+        PropertyValue::ObjectValue(12),
+    ]);
+    for value in values {
+        let json = serde_json::Value::from(&value);
+        let text = serde_json::to_string(&json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(PropertyValue::try_from(parsed).unwrap(), value);
+    }
+
+    assert_eq!(
+        serde_json::Value::from(PropertyValue::FloatValue(0.1)),
+        serde_json::json!(0.1)
+    );
+    assert_eq!(
+        serde_json::Value::from(PropertyValue::ObjectValue(12)),
+        serde_json::json!("object:12")
+    );
+    assert_eq!(
+        PropertyValue::try_from(serde_json::json!({ "speed": 3, "name": "slime" })).unwrap(),
+        PropertyValue::ClassValue {
+            property_type: String::new(),
+            properties: vec![
+                ("speed".into(), PropertyValue::IntValue(3)),
+                ("name".into(), "slime".into()),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    );
+    assert!(PropertyValue::try_from(serde_json::json!([1, 2])).is_err());
+    assert!(PropertyValue::try_from(serde_json::json!("color:#zz")).is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_formats() {
+    let mut loader = Loader::new();
+    let names = [
+        "tiled_base64_external",
+        "tiled_base64_zlib_infinite",
+        "tiled_csv_wangsets",
+        "tiled_group_layers",
+        "tiled_image_layers",
+        "tiled_object_groups",
+        "tiled_object_template",
+        "tiled_text_object",
+    ];
+    for name in names.iter() {
+        let json = loader.load_tmj_map(format!("assets/{}.tmj", name)).unwrap();
+        let xml = loader.load_tmx_map(format!("assets/{}.tmx", name)).unwrap();
+        assert_eq!(json, xml, "{} differs between formats", name);
+    }
+
+    let json = loader.load_tsj_tileset("assets/tilesheet.tsj").unwrap();
+    let xml = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    assert_eq!(json, xml);
+}
+
+#[test]
+fn test_writing_round_trip() {
+    fn reload(tmx: Vec<u8>) -> Map {
+        let mut loader = Loader::with_reader(move |path: &std::path::Path| {
+            if path == std::path::Path::new("assets/written.tmx") {
+                Ok(std::io::Cursor::new(tmx.clone()))
+            } else {
+                std::fs::read(path).map(std::io::Cursor::new)
+            }
+        });
+        loader.load_tmx_map("assets/written.tmx").unwrap()
+    }
+
+    let mut loader = Loader::new();
+    let names = [
+        "tiled_base64_external",
+        "tiled_base64_zlib_infinite",
+        "tiled_nested_class_property",
+        "tiled_csv_wangsets",
+        "tiled_flipped",
+        "tiled_gid_gaps",
+        "tiled_group_layers",
+        "tiled_image_layers",
+        "tiled_object_groups",
+        "tiled_object_property",
+        "tiled_parallax",
+        "tiled_text_object",
+    ];
+    let encodings = [
+        TileDataEncoding::Csv,
+        TileDataEncoding::Base64,
+        TileDataEncoding::Base64Zlib,
+    ];
+    for name in names.iter() {
+        let map = loader.load_tmx_map(format!("assets/{}.tmx", name)).unwrap();
+        for encoding in encodings.iter() {
+            let mut tmx = Vec::new();
+            let options = WriteOptions {
+                encoding: *encoding,
+                base_dir: "assets".into(),
+                ..Default::default()
+            };
+            map.to_writer(&mut tmx, &options).unwrap();
+            assert_eq!(reload(tmx), map, "{} differs once written", name);
+        }
+    }
+
+    // Tilesets can also be referenced instead of embedded
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let mut tmx = Vec::new();
+    let options = WriteOptions {
+        base_dir: "assets".into(),
+        tileset_sources: vec![(0, PathBuf::from("assets/tilesheet.tsx"))]
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+    map.to_writer(&mut tmx, &options).unwrap();
+    assert!(String::from_utf8_lossy(&tmx).contains(r#"source="tilesheet.tsx""#));
+    assert_eq!(reload(tmx), map);
+
+    let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    let mut tsx = Vec::new();
+    tileset.to_writer(&mut tsx, &options).unwrap();
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| {
+        Ok::<_, std::io::Error>(std::io::Cursor::new(tsx.clone()))
+    });
+    assert_eq!(
+        loader.load_tsx_tileset("assets/written.tsx").unwrap(),
+        tileset
+    );
+}
+
+#[test]
+fn test_building_maps() {
+    let mut map = Map::new(Orientation::Orthogonal, 20, 10, 32, 32, false);
+    let mut tileset = Tileset::new("tilesheet", 32, 32);
+    tileset.set_image(Image {
+        source: PathBuf::from("assets/tilesheet.png").into(),
+        width: 448,
+        height: 192,
+        transparent_colour: None,
+    });
+    assert_eq!((tileset.columns, tileset.tilecount), (14, 84));
+    assert!(tileset.get_tile(83).is_some());
+    let tileset = map.add_tileset(std::sync::Arc::new(tileset));
+
+    let mut tiles = FiniteTileLayerData::empty(20, 10);
+    for x in 0..20 {
+        tiles.set_tile_data(
+            x,
+            9,
+            Some(LayerTileData::new(tileset, 3, false, false, false)),
+        );
+    }
+    tiles.set_tile_data(
+        20,
+        9,
+        Some(LayerTileData::new(tileset, 3, false, false, false)),
+    );
+    let mut ground = LayerData::new_finite_tile_layer("ground", tiles);
+    ground.opacity = 0.5;
+    assert_eq!(map.add_layer(ground).unwrap(), 1);
+
+    let mut chest = ObjectData::from_shape(
+        64.0,
+        288.0,
+        ObjectShape::Rect {
+            width: 32.0,
+            height: 32.0,
+        },
+    );
+    chest.name = "chest".to_owned();
+    chest.set_tile(Some(ObjectTileData::new(
+        TilesetLocation::Map(tileset),
+        10,
+        false,
+        false,
+        false,
+    )));
+    let spawn = ObjectData::from_shape(32.0, 288.0, ObjectShape::Point(32.0, 288.0));
+    let group = LayerData::new_group_layer(
+        "entities",
+        vec![
+            LayerData::new_object_layer("items", vec![chest]),
+            LayerData::new_object_layer("spawns", vec![spawn]),
+        ],
+    );
+    assert_eq!(map.add_layer(group).unwrap(), 2);
+    assert_eq!(map.next_object_id(), 3);
+    let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    assert_eq!(group.get_layer(1).unwrap().id(), 4);
+    let spawns = group.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(spawns.get_object(0).unwrap().id(), 2);
+
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(layer.get_tile(19, 9).unwrap().id(), 3);
+    assert!(layer.get_tile(0, 0).is_none());
+
+    // Layers that don't fit the map are rejected
+    let infinite = LayerData::new_infinite_tile_layer("infinite", InfiniteTileLayerData::empty());
+    assert!(matches!(
+        map.add_layer(infinite),
+        Err(tiled::Error::InvalidLayer { .. })
+    ));
+    let mut tiles = FiniteTileLayerData::empty(20, 10);
+    tiles.set_tile_data(0, 0, Some(LayerTileData::new(1, 0, false, false, false)));
+    assert!(matches!(
+        map.add_layer(LayerData::new_finite_tile_layer("missing tileset", tiles)),
+        Err(tiled::Error::InvalidLayer { .. })
+    ));
+    assert_eq!(map.layers().len(), 2);
+
+    // Built maps can be written and read back
+    let mut tmx = Vec::new();
+    let options = WriteOptions {
+        base_dir: "assets".into(),
+        ..Default::default()
+    };
+    map.to_writer(&mut tmx, &options).unwrap();
+    let mut loader = Loader::with_reader(move |path: &std::path::Path| {
+        if path == std::path::Path::new("assets/written.tmx") {
+            Ok(std::io::Cursor::new(tmx.clone()))
+        } else {
+            std::fs::read(path).map(std::io::Cursor::new)
+        }
+    });
+    assert_eq!(loader.load_tmx_map("assets/written.tmx").unwrap(), map);
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world() {
+    let mut loader = Loader::new();
+    let world = loader.load_world("assets/world/tiled.world").unwrap();
+    assert!(!world.only_show_adjacent_maps);
+    assert_eq!(
+        world.maps[1],
+        tiled::WorldMap {
+            source: PathBuf::from("assets/world/../tiled_base64_external.tmj"),
+            x: 3200,
+            y: 0,
+            width: 3200,
+            height: 3200,
+        }
+    );
+
+    let maps: Vec<_> = world.maps_at(3300, 10).collect();
+    assert_eq!(maps, [&world.maps[1]]);
+    assert_eq!(world.maps_at(-1, 0).count(), 0);
+    let xml = loader.load_world_map(&world.maps[0]).unwrap();
+    let json = loader.load_world_map(&world.maps[1]).unwrap();
+    assert_eq!(xml, json);
+
+    let pattern = &world.patterns[0];
+    assert_eq!((pattern.map_width, pattern.map_height), (320, 320));
+    let matched = world.match_paths([
+        "assets/world/chunk_x0_y3.tmx",
+        "assets/world/chunk_xa_y3.tmx",
+        "assets/world/../tiled_base64_external.tmx",
+    ]);
+    assert_eq!(matched.len(), 1);
+    assert_eq!((matched[0].x, matched[0].y), (0, 960));
+    assert_eq!(
+        matched[0].source,
+        PathBuf::from("assets/world/chunk_x0_y3.tmx")
+    );
+}
+
+#[test]
+fn test_tile_rect() {
+    let mut tileset = Tileset::new("spaced", 16, 16);
+    tileset.margin = 2;
+    tileset.spacing = 1;
+    assert_eq!(tileset.tile_rect(0), None);
+    tileset.set_image(Image {
+        source: PathBuf::from("spaced.png").into(),
+        width: 100,
+        height: 50,
+        transparent_colour: None,
+    });
+    assert_eq!((tileset.columns, tileset.tilecount), (5, 10));
+
+    assert_eq!(
+        tileset.tile_rect(0),
+        Some(Rect {
+            x: 2,
+            y: 2,
+            width: 16,
+            height: 16
+        })
+    );
+    assert_eq!(
+        tileset.tile_rect(6),
+        Some(Rect {
+            x: 19,
+            y: 19,
+            width: 16,
+            height: 16
+        })
+    );
+    assert_eq!(tileset.tile_rect(10), None);
+
+    assert_eq!(
+        tileset.tile_uv(6, 100, 50),
+        Some(UvRect {
+            u_min: 0.19,
+            v_min: 0.38,
+            u_max: 0.35,
+            v_max: 0.7
+        })
+    );
+    assert_eq!(tileset.tile_uv(6, 0, 50), None);
+}
+
+#[test]
+fn test_effective_layer_attributes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_attributes.tmx")
+        .unwrap();
+    let group = map.get_layer(0).unwrap().as_group_layer().unwrap();
+    let inner = group.get_layer(0).unwrap();
+    assert_eq!(inner.effective_offset(), (14.0, 6.0));
+    assert_eq!(inner.effective_parallax(), (0.5, 1.0));
+    assert_eq!(inner.effective_opacity(), 0.25);
+    assert_eq!(
+        inner.effective_tint(),
+        Some(Color {
+            alpha: 255,
+            red: 128,
+            green: 64,
+            blue: 64
+        })
+    );
+    assert!(inner.effective_visibility());
+
+    let hidden = group.get_layer(1).unwrap().as_group_layer().unwrap();
+    let objects = hidden.get_layer(0).unwrap();
+    assert_eq!(objects.effective_offset(), (11.0, 7.0));
+    assert!(!objects.effective_visibility());
+    assert!(objects.visible);
+
+    let flattened: Vec<_> = map.layers_flattened().collect();
+    let names: Vec<_> = flattened.iter().map(|(layer, _)| &*layer.name).collect();
+    assert_eq!(names, ["inner-tile", "hidden-objects", "top"]);
+    assert_eq!(flattened[0].1, inner.effective_attributes());
+    assert_eq!(flattened[1].1, objects.effective_attributes());
+    assert_eq!(flattened[2].1, LayerAttributes::default());
+}
+
+#[test]
+fn test_validate() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_validation.tmx")
+        .unwrap();
+    assert_eq!(
+        map.validate(),
+        [
+            ValidationIssue::MissingAnimationFrame {
+                tileset_index: 0,
+                tile_id: 0,
+                frame_tile_id: 5
+            },
+            ValidationIssue::MissingTileImage {
+                tileset_index: 0,
+                tile_id: 1
+            },
+            ValidationIssue::MissingLayerTile {
+                layer_id: 1,
+                x: 0,
+                y: 1,
+                tileset_index: 0,
+                tile_id: 2
+            },
+            ValidationIssue::MissingObjectTile {
+                layer_id: 2,
+                object_id: 2,
+                tile_id: 3
+            },
+            ValidationIssue::DuplicateLayerId { layer_id: 2 },
+            ValidationIssue::DuplicateObjectId {
+                layer_id: 2,
+                object_id: 1
+            },
+        ]
+    );
+
+    for path in [
+        "assets/tiled_base64.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_image_layers.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        assert_eq!(map.validate(), [], "{}", path);
+    }
+}
+
+#[test]
+fn test_lookup_by_name() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(map.get_layer_by_name("tile-1").unwrap().id(), 1);
+    assert_eq!(map.get_layer_by_name("tile-2").unwrap().id(), 5);
+    assert_eq!(map.get_layer_by_name("group-3").unwrap().id(), 8);
+    assert!(map.get_layer_by_name("tile-4").is_none());
+
+    assert_eq!(map.layer_at_path("tile-1").unwrap().id(), 1);
+    assert_eq!(map.layer_at_path("group-1/tile-2").unwrap().id(), 5);
+    assert_eq!(map.layer_at_path("group-2/group-3/tile-3").unwrap().id(), 9);
+    assert!(map.layer_at_path("tile-2").is_none());
+    assert!(map.layer_at_path("tile-1/tile-2").is_none());
+    assert!(map.layer_at_path("group-2/group-3/").is_none());
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_graph.tmx")
+        .unwrap();
+    let loot = map
+        .layer_at_path("Group/Loot")
+        .unwrap()
+        .as_object_layer()
+        .unwrap();
+    assert_eq!(loot.get_object_by_name("rock").unwrap().id(), 4);
+    assert!(loot.get_object_by_name("lever").is_none());
+}
+
+#[test]
+fn test_lru_resource_cache() {
+    use std::sync::Arc;
+    use tiled::{CacheLimit, CachedResource, LruResourceCache};
+
+    let tileset = |name: &str| Arc::new(Tileset::new(name, 32, 32));
+    let mut cache = LruResourceCache::new(CacheLimit::Entries(2));
+    cache.insert_tileset("a.tsx", tileset("a"));
+    cache.insert_tileset("b.tsx", tileset("b"));
+    assert!(cache.get_tileset("a.tsx").is_some());
+    cache.insert_tileset("c.tsx", tileset("c"));
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get_tileset("a.tsx").is_some());
+    assert!(cache.get_tileset("b.tsx").is_none());
+    assert!(cache.get_tileset("c.tsx").is_some());
+
+    // Reinserting a resource doesn't count it twice
+    let size = cache.estimated_size();
+    cache.insert_tileset("c.tsx", tileset("c"));
+    assert_eq!(cache.estimated_size(), size);
+
+    // The newest resource is kept even if it doesn't fit
+    cache.set_limit(CacheLimit::Bytes(0));
+    assert_eq!(cache.len(), 0);
+    cache.insert_tileset("a.tsx", tileset("a"));
+    cache.insert_tileset("b.tsx", tileset("b"));
+    assert_eq!(cache.len(), 1);
+    assert!(cache.get_tileset("b.tsx").is_some());
+
+    cache.set_limit(CacheLimit::Entries(10));
+    cache.insert_tileset("a.tsx", tileset("a"));
+    let used = cache.get_tileset("a.tsx").unwrap();
+    cache.evict_unused();
+    assert_eq!(cache.len(), 1);
+    assert!(Arc::ptr_eq(&cache.remove_tileset("a.tsx").unwrap(), &used));
+    assert_eq!(cache.estimated_size(), 0);
+
+    let mut loader = Loader::new();
+    loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let cache = loader.cache_mut();
+    assert!(!cache.templates.is_empty());
+    cache.retain(|_, resource| matches!(resource, CachedResource::Tileset(_)));
+    assert!(cache.templates.is_empty());
+    assert!(!cache.tilesets.is_empty());
+    cache.clear();
+    assert!(cache.tilesets.is_empty());
+}
+
+#[test]
+fn test_shared_resource_cache() {
+    use std::sync::Arc;
+    use tiled::{CacheLimit, LruResourceCache, SharedResourceCache};
+
+    let loader = Loader::new_shared();
+    let maps: Vec<Map> = std::thread::scope(|scope| {
+        let handles: Vec<_> = [
+            "assets/tiled_base64_external.tmx",
+            "assets/tiled_flipped.tmx",
+            "assets/tiled_group_layers.tmx",
+        ]
+        .iter()
+        .map(|&path| {
+            let mut loader = loader.clone();
+            scope.spawn(move || loader.load_tmx_map(path).unwrap())
+        })
+        .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    let tileset = loader.cache().get_tileset("assets/tilesheet.tsx").unwrap();
+    assert!(maps
+        .iter()
+        .all(|map| Arc::ptr_eq(&map.tilesets()[0], &tileset)));
+
+    let mut cache = SharedResourceCache::with_cache(LruResourceCache::new(CacheLimit::Entries(1)));
+    let mut clone = cache.clone();
+    clone.insert_tileset("a.tsx", Arc::new(Tileset::new("a", 32, 32)));
+    assert!(cache.get_tileset("a.tsx").is_some());
+    cache.clear();
+    assert!(clone.read().is_empty());
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip_resource_reader() {
+    use std::io::Cursor;
+    use tiled::ZipResourceReader;
+
+    let reader = ZipResourceReader::open("assets/tiled_pack.zip").unwrap();
+    let mut loader = Loader::with_reader(reader);
+    let map = loader.load_tmx_map("maps/level.tmx").unwrap();
+    let expected = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(
+        &*map.get_layer(0).unwrap(),
+        &*expected.get_layer(0).unwrap()
+    );
+    assert_eq!(map.tilesets()[0].name, expected.tilesets()[0].name);
+    assert!(loader
+        .cache()
+        .get_tileset("maps/../tilesheet.tsx")
+        .is_some());
+    assert!(loader.load_tmx_map("maps/missing.tmx").is_err());
+    assert!(loader.load_tmx_map("../level.tmx").is_err());
+
+    let bytes = std::fs::read("assets/tiled_pack.zip").unwrap();
+    let mut loader = Loader::with_reader(ZipResourceReader::new(Cursor::new(bytes)).unwrap());
+    let map = futures::executor::block_on(loader.load_tmx_map_async("/maps/level.tmx")).unwrap();
+    assert_eq!(
+        &*map.get_layer(0).unwrap(),
+        &*expected.get_layer(0).unwrap()
+    );
+}
+
+#[cfg(feature = "fetch")]
+#[test]
+fn test_fetch_resource_reader_urls() {
+    use std::path::Path;
+    use tiled::FetchResourceReader;
+
+    let reader = FetchResourceReader::new("https://example.com/assets");
+    assert_eq!(
+        reader.url(Path::new("/maps/level.tmx")),
+        "https://example.com/assets/maps/level.tmx"
+    );
+    let reader = FetchResourceReader::new("");
+    assert_eq!(reader.url(Path::new("maps/level.tmx")), "maps/level.tmx");
+}
+
+#[test]
+fn test_compact_tile_layers() {
+    let full = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let mut loader = Loader::new();
+    loader.set_options(tiled::LoaderOptions {
+        compact_tile_layers: true,
+        ..Default::default()
+    });
+    let compact = loader.load_tmx_map("assets/tiled_base64_zlib.tmx").unwrap();
+
+    let full_layer = as_finite(full.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let compact_layer = as_finite(compact.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert!(!full_layer.is_compact());
+    assert!(compact_layer.is_compact());
+    assert_eq!(&*full_layer, &*compact_layer);
+    for y in 0..full_layer.height() as i32 {
+        for x in 0..full_layer.width() as i32 {
+            assert_eq!(
+                full_layer
+                    .get_tile(x, y)
+                    .map(|tile| (tile.id(), tile.tileset_index())),
+                compact_layer
+                    .get_tile(x, y)
+                    .map(|tile| (tile.id(), tile.tileset_index()))
+            );
+        }
+    }
+
+    let mut layer = FiniteTileLayerData::empty(4, 4);
+    layer.compact();
+    let tile = LayerTileData::new(0, 7, true, false, false);
+    layer.set_tile_data(1, 2, Some(tile));
+    layer.set_tile_data(3, 3, Some(tile));
+    assert_eq!(layer.get_tile_data(1, 2), Some(&tile));
+    assert_eq!(layer.get_tile_data(3, 3), Some(&tile));
+    layer.set_tile_data(1, 2, None);
+    assert_eq!(layer.get_tile_data(1, 2), None);
+    assert_eq!(layer.get_tile_data(0, 0), None);
+
+    #[cfg(feature = "json")]
+    {
+        let json = loader
+            .load_tmj_map("assets/tiled_base64_external.tmj")
+            .unwrap();
+        let layer = as_finite(json.get_layer(0).unwrap().as_tile_layer().unwrap());
+        assert!(layer.is_compact());
+    }
+}
+
+#[cfg(feature = "precompiled")]
+#[test]
+fn test_precompiled_map() {
+    for name in [
+        "tiled_base64_zlib",
+        "tiled_base64_zlib_infinite",
+        "tiled_object_template",
+        "tiled_group_layers",
+        "tiled_embedded_image",
+    ]
+    .iter()
+    {
+        let map = Loader::new()
+            .load_tmx_map(format!("assets/{}.tmx", name))
+            .unwrap();
+        let bytes = map.to_bytes().unwrap();
+        assert_eq!(Map::from_bytes(&bytes).unwrap(), map, "{}", name);
+    }
+
+    let mut loader = Loader::new();
+    loader.set_options(tiled::LoaderOptions {
+        compact_tile_layers: true,
+        ..Default::default()
+    });
+    let map = loader.load_tmx_map("assets/tiled_base64_zlib.tmx").unwrap();
+    let bytes = map.to_bytes().unwrap();
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(bytes.clone()))
+    });
+    assert_eq!(loader.load_precompiled_map("level.tmxb").unwrap(), map);
+
+    let bytes = map.to_bytes().unwrap();
+    assert!(matches!(
+        Map::from_bytes(&bytes[1..]),
+        Err(tiled::Error::InvalidPrecompiledMap { .. })
+    ));
+    assert!(matches!(
+        Map::from_bytes(&bytes[..bytes.len() / 2]),
+        Err(tiled::Error::InvalidPrecompiledMap { .. })
+    ));
+    let mut other_version = b"TILEDMAP0.0.1\0".to_vec();
+    other_version.extend_from_slice(&bytes[bytes.iter().position(|&b| b == 0).unwrap() + 1..]);
+    assert!(matches!(
+        Map::from_bytes(&other_version),
+        Err(tiled::Error::InvalidPrecompiledMap { .. })
+    ));
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn test_reload_if_changed() {
+    let dir = std::env::temp_dir().join(format!("tiled_watch_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("assets/tiled_base64_external.tmx", dir.join("level.tmx")).unwrap();
+    let tileset = std::fs::read_to_string("assets/tilesheet.tsx").unwrap();
+    std::fs::write(dir.join("tilesheet.tsx"), &tileset).unwrap();
+
+    let mut loader = Loader::new();
+    let map = loader.load_tmx_map(dir.join("level.tmx")).unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+    let mut watcher = loader.watch_tmx_map(dir.join("level.tmx")).unwrap();
+    assert!(watcher
+        .files()
+        .any(|file| file == dir.join("tilesheet.tsx")));
+    assert!(loader.reload_if_changed(&mut watcher).unwrap().is_none());
+
+    std::fs::write(
+        dir.join("tilesheet.tsx"),
+        tileset.replace("name=\"tilesheet\"", "name=\"renamed\""),
+    )
+    .unwrap();
+    let mut reload = None;
+    for _ in 0..100 {
+        reload = loader.reload_if_changed(&mut watcher).unwrap();
+        if reload.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let reload = reload.expect("the change should have been detected");
+    assert_eq!(reload.changed_files, [dir.join("tilesheet.tsx")]);
+    assert_eq!(reload.map.tilesets()[0].name, "renamed");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_typed_property_accessors() {
+    use tiled::PropertiesExt;
+
+    let mut properties = Properties::new();
+    properties.insert("name".into(), "door".into());
+    properties.insert("health".into(), 10.into());
+    properties.insert("speed".into(), 1.5.into());
+    properties.insert("locked".into(), true.into());
+    properties.insert("sprite".into(), PathBuf::from("door.png").into());
+    properties.insert("target".into(), ObjectId(7).into());
+
+    assert_eq!(properties.get_string("name"), Some("door"));
+    assert_eq!(properties.get_int("health"), Some(10));
+    assert_eq!(properties.get_float("speed"), Some(1.5));
+    assert_eq!(properties.get_bool("locked"), Some(true));
+    assert_eq!(properties.get_file("sprite"), Some(Path::new("door.png")));
+    assert_eq!(properties.get_object_ref("target"), Some(ObjectId(7)));
+    assert_eq!(properties.get_string("health"), None);
+    assert_eq!(properties.get_int("missing"), None);
+
+    assert_eq!(
+        properties.get_as::<String>("name"),
+        Ok(Some("door".to_owned()))
+    );
+    assert_eq!(properties.get_as::<bool>("missing"), Ok(None));
+    assert_eq!(
+        properties.get_as::<bool>("health"),
+        Err(PropertyTypeError {
+            expected: "bool",
+            found: "int"
+        })
+    );
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap();
+    assert_eq!(
+        layer.properties.get_color("key"),
+        Some(Color {
+            alpha: 0x12,
+            red: 0x34,
+            green: 0x56,
+            blue: 0x78
+        })
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_properties() {
+    use serde::Deserialize;
+    use tiled::PropertiesExt;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Facing {
+        North,
+        South,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Lighting {
+        tint: Color,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Door<'a> {
+        locked: bool,
+        key_id: u32,
+        speed: f64,
+        label: &'a str,
+        tint: String,
+        facing: Facing,
+        opens_to: Facing,
+        target: u32,
+        lighting: Lighting,
+        missing: Option<i32>,
+    }
+
+    let mut lighting = Properties::new();
+    lighting.insert(
+        "tint".into(),
+        PropertyValue::ColorValue(Color {
+            alpha: 0xff,
+            red: 0x10,
+            green: 0x20,
+            blue: 0x30,
+        }),
+    );
+    let mut properties = Properties::new();
+    properties.insert("locked".into(), true.into());
+    properties.insert("key_id".into(), 4.into());
+    properties.insert("speed".into(), 2.into());
+    properties.insert("label".into(), "front door".into());
+    properties.insert("tint".into(), lighting["tint"].clone());
+    properties.insert("facing".into(), "South".into());
+    properties.insert("opens_to".into(), 0.into());
+    properties.insert("target".into(), ObjectId(12).into());
+    properties.insert(
+        "lighting".into(),
+        PropertyValue::ClassValue {
+            property_type: "lighting".to_owned(),
+            properties: lighting,
+        },
+    );
+
+    let door: Door = properties.deserialize_as().unwrap();
+    assert_eq!(
+        door,
+        Door {
+            locked: true,
+            key_id: 4,
+            speed: 2.0,
+            label: "front door",
+            tint: "#ff102030".to_owned(),
+            facing: Facing::South,
+            opens_to: Facing::North,
+            target: 12,
+            lighting: Lighting {
+                tint: Color {
+                    alpha: 0xff,
+                    red: 0x10,
+                    green: 0x20,
+                    blue: 0x30,
+                },
+            },
+            missing: None,
+        }
+    );
+
+    properties.insert("key_id".into(), "four".into());
+    assert!(matches!(
+        properties.deserialize_as::<Door>(),
+        Err(tiled::Error::InvalidPropertyValue { .. })
+    ));
+    properties.remove("key_id");
+    assert!(matches!(
+        properties.deserialize_as::<Door>(),
+        Err(tiled::Error::InvalidPropertyValue { .. })
+    ));
+}
+
+#[test]
+fn test_object_lookup_by_id() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    assert_eq!(map.get_object_by_id(2).unwrap().x, 0.0);
+    assert_eq!(map.get_object_by_id(3).unwrap().x, 32.0);
+    assert!(map.get_object_by_id(0).is_none());
+    assert!(map.get_object_by_id(4).is_none());
+
+    let source = map.get_object_by_id(2).unwrap();
+    assert_eq!(
+        source
+            .resolve_object_property("object property")
+            .unwrap()
+            .id(),
+        3
+    );
+    assert!(source.resolve_object_property("missing").is_none());
+
+    let mut properties = Properties::new();
+    properties.insert("target".into(), ObjectId(2).into());
+    properties.insert("dangling".into(), ObjectId(99).into());
+    properties.insert("text".into(), "2".into());
+    assert_eq!(
+        map.resolve_object_property(&properties, "target")
+            .unwrap()
+            .id(),
+        2
+    );
+    assert!(map
+        .resolve_object_property(&properties, "dangling")
+        .is_none());
+    assert!(map.resolve_object_property(&properties, "text").is_none());
+
+    // Objects added after the index was built are found too
+    let id = map
+        .get_object_layer_mut(2)
+        .unwrap()
+        .add_object(ObjectData::from_shape(
+            64.0,
+            0.0,
+            ObjectShape::Point(64.0, 0.0),
+        ));
+    assert_eq!(map.get_object_by_id(id).unwrap().x, 64.0);
+
+    // And so are objects nested in group layers
+    map.add_layer(LayerData::new_group_layer(
+        "group",
+        vec![LayerData::new_object_layer(
+            "nested",
+            vec![ObjectData::from_shape(
+                96.0,
+                0.0,
+                ObjectShape::Point(96.0, 0.0),
+            )],
+        )],
+    ))
+    .unwrap();
+    let nested = map
+        .objects()
+        .find(|(layer, _)| &*layer.name == "nested")
+        .unwrap()
+        .1
+        .id();
+    assert_eq!(map.get_object_by_id(nested).unwrap().x, 96.0);
+}
+
+#[test]
+fn test_tileset_transformations() {
+    let mut loader = Loader::new();
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_transformations.tsx")
+        .unwrap();
+    assert_eq!(
+        tileset.transformations,
+        TilesetTransformations {
+            hflip: true,
+            vflip: true,
+            rotate: false,
+            prefer_untransformed: true,
+        }
+    );
+    let plain = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    assert_eq!(plain.transformations, TilesetTransformations::default());
+
+    let mut tsx = Vec::new();
+    tileset
+        .to_writer(&mut tsx, &WriteOptions::default())
+        .unwrap();
+    assert!(String::from_utf8_lossy(&tsx)
+        .contains(r#"<transformations hflip="1" vflip="1" rotate="0" preferuntransformed="1"/>"#));
+
+    #[cfg(feature = "json")]
+    {
+        let json = loader
+            .load_tsj_tileset("assets/tilesheet_transformations.tsj")
+            .unwrap();
+        assert_eq!(json.transformations, tileset.transformations);
+    }
+}
+
+#[test]
+fn test_tile_render_size() {
+    let mut loader = Loader::new();
+    let mut tileset = loader
+        .load_tsx_tileset("assets/tilesheet_render_size.tsx")
+        .unwrap();
+    assert_eq!(tileset.tile_render_size, TileRenderSize::Grid);
+    assert_eq!(tileset.fill_mode, FillMode::PreserveAspectFit);
+    #[cfg(feature = "json")]
+    {
+        let json = loader
+            .load_tsj_tileset("assets/tilesheet_render_size.tsj")
+            .unwrap();
+        assert_eq!(json.tile_render_size, TileRenderSize::Grid);
+        assert_eq!(json.fill_mode, FillMode::PreserveAspectFit);
+    }
+
+    let cell = BoundingBox::new(0.0, 0.0, 64.0, 32.0);
+    assert_eq!(
+        tileset.tile_draw_rect(0, cell),
+        Some(BoundingBox::new(16.0, 0.0, 32.0, 32.0))
+    );
+    tileset.fill_mode = FillMode::Stretch;
+    assert_eq!(tileset.tile_draw_rect(0, cell), Some(cell));
+    tileset.tile_render_size = TileRenderSize::Tile;
+    tileset.offset_x = 4;
+    assert_eq!(
+        tileset.tile_draw_rect(0, BoundingBox::new(0.0, 0.0, 16.0, 16.0)),
+        Some(BoundingBox::new(4.0, -16.0, 32.0, 32.0))
+    );
+    assert_eq!(tileset.tile_draw_rect(84, cell), None);
+
+    tileset.tile_render_size = TileRenderSize::Grid;
+    tileset.fill_mode = FillMode::PreserveAspectFit;
+    let mut tsx = Vec::new();
+    tileset
+        .to_writer(&mut tsx, &WriteOptions::default())
+        .unwrap();
+    assert!(String::from_utf8_lossy(&tsx)
+        .contains(r#"tilerendersize="grid" fillmode="preserve-aspect-fit""#));
+}
+
+#[test]
+fn test_object_geometry() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_rotation.tmx")
+        .unwrap();
+    let find = |name: &str| {
+        map.objects()
+            .find(|(_, object)| object.name == name)
+            .unwrap()
+            .1
+    };
+
+    let crate_object = find("crate");
+    assert_eq!(crate_object.world_vertices().len(), 4);
+    assert_eq!(
+        crate_object.shape.bounding_rect(),
+        BoundingBox::new(0.0, 0.0, 32.0, 16.0)
+    );
+    // Rotated by 90 degrees, the crate covers x in [16, 32] and y in [32, 64]
+    let bounds = crate_object.bounding_box();
+    assert!((bounds.x - 16.0).abs() < 1e-4 && (bounds.y - 32.0).abs() < 1e-4);
+    assert!((bounds.width - 16.0).abs() < 1e-4 && (bounds.height - 32.0).abs() < 1e-4);
+    assert!(crate_object.contains_point(20.0, 60.0));
+    assert!(!crate_object.contains_point(40.0, 36.0));
+
+    let chest = find("chest");
+    assert!(chest.contains_point(80.0, 100.0));
+    assert!(!chest.contains_point(80.0, 80.0));
+    assert!(!find("marker").contains_point(16.0, 16.0));
+
+    let ellipse = ObjectShape::Ellipse {
+        width: 20.0,
+        height: 10.0,
+    };
+    assert!(ellipse.contains_point(10.0, 5.0));
+    assert!(!ellipse.contains_point(1.0, 1.0));
+    assert_eq!(ellipse.vertices().len(), 32);
+
+    let triangle = ObjectShape::Polygon {
+        points: vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)],
+    };
+    assert!(triangle.contains_point(2.0, 2.0));
+    assert!(!triangle.contains_point(8.0, 8.0));
+    assert_eq!(
+        triangle.bounding_rect(),
+        BoundingBox::new(0.0, 0.0, 10.0, 10.0)
+    );
+    let polyline = ObjectShape::Polyline {
+        points: vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)],
+    };
+    assert!(!polyline.contains_point(2.0, 2.0));
+}
+
+#[test]
+fn test_tile_collision_shapes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_tile_collision.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let single = |x: i32, y: i32| TileRect {
+        x,
+        y,
+        width: 1,
+        height: 1,
+    };
+
+    let shapes: Vec<TileCollisionShape> = layer.collision_shapes().collect();
+    assert_eq!(shapes.len(), 9);
+    let slope = shapes
+        .iter()
+        .find(|shape| shape.tiles == single(3, 0))
+        .unwrap();
+    assert_eq!((slope.x, slope.y), (96.0, 32.0));
+    assert_eq!(slope.object.unwrap().name, "slope");
+    assert_eq!(
+        slope.shape,
+        ObjectShape::Polygon {
+            points: vec![(0.0, 0.0), (32.0, 0.0), (32.0, -32.0)]
+        }
+    );
+    // The spike tile is flipped horizontally, so its collision box is mirrored in its cell
+    let spike = shapes
+        .iter()
+        .find(|shape| shape.tiles == single(0, 2))
+        .unwrap();
+    assert_eq!((spike.x, spike.y), (20.0, 72.0));
+    assert_eq!(
+        spike.shape,
+        ObjectShape::Rect {
+            width: 8.0,
+            height: 16.0
+        }
+    );
+
+    let merged = layer.merged_collision_shapes();
+    assert_eq!(merged.len(), 4);
+    let block = merged.iter().find(|shape| shape.tiles.width == 3).unwrap();
+    assert_eq!(
+        block.tiles,
+        TileRect {
+            x: 0,
+            y: 0,
+            width: 3,
+            height: 2
+        }
+    );
+    assert_eq!(
+        block.shape,
+        ObjectShape::Rect {
+            width: 96.0,
+            height: 64.0
+        }
+    );
+    assert!(block.object.is_none());
+    assert!(merged.iter().any(|shape| shape.tiles == single(2, 2)));
+    assert!(merged.iter().any(|shape| shape.tiles == single(0, 2)));
+}
+
+#[test]
+fn test_layer_and_object_ids() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let layer = map.get_layer_by_id(9).unwrap();
+    assert_eq!(&*layer.name, "tile-3");
+    assert_eq!(&*map.get_layer_by_id(8).unwrap().name, "group-3");
+    assert_eq!(&*map.get_layer_by_id(1).unwrap().name, "tile-1");
+    assert!(map.get_layer_by_id(42).is_none());
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    let layer = map.get_layer_by_id(2).unwrap().as_object_layer().unwrap();
+    assert_eq!(layer.get_object_by_id(3).unwrap().x, 32.0);
+    assert!(layer.get_object_by_id(1).is_none());
+
+    // Objects added later are found too
+    let id = map
+        .get_object_layer_mut(2)
+        .unwrap()
+        .add_object(ObjectData::from_shape(
+            8.0,
+            8.0,
+            ObjectShape::Point(8.0, 8.0),
+        ));
+    let layer = map.get_layer_by_id(2).unwrap().as_object_layer().unwrap();
+    assert!(layer.get_object_by_id(id).is_some());
+    assert!(map.get_object_by_id(id).is_some());
+}
+
+#[test]
+fn test_user_classes() {
+    let check = |map: Map| {
+        assert_eq!(map.user_type.as_deref(), Some("dungeon"));
+        assert_eq!(map.tilesets()[0].user_type.as_deref(), Some("walls"));
+        assert_eq!(
+            map.get_layer_by_name("floor").unwrap().user_type.as_deref(),
+            Some("terrain")
+        );
+        assert_eq!(
+            map.get_layer_by_name("rooms").unwrap().user_type.as_deref(),
+            Some("rooms")
+        );
+        assert_eq!(
+            map.layer_at_path("rooms/spawns")
+                .unwrap()
+                .user_type
+                .as_deref(),
+            Some("spawns")
+        );
+
+        let mut tmx = Vec::new();
+        map.to_writer(&mut tmx, &WriteOptions::default()).unwrap();
+        let tmx = String::from_utf8(tmx).unwrap();
+        for class in ["dungeon", "walls", "terrain", "rooms", "spawns"].iter() {
+            assert!(tmx.contains(&format!(r#"class="{}""#, class)));
+        }
+    };
+
+    let mut loader = Loader::new();
+    check(
+        loader
+            .load_tmx_map("assets/tiled_user_classes.tmx")
+            .unwrap(),
+    );
+    #[cfg(feature = "json")]
+    check(
+        loader
+            .load_tmj_map("assets/tiled_user_classes.tmj")
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_editing_tile_layers() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let tile = LayerTileData::new(0, 7, true, false, false);
+    let mut layer = map.get_tile_layer_mut(1).unwrap();
+    layer.set_tile(99, 99, Some(tile)).unwrap();
+    layer.set_tile(0, 0, None).unwrap();
+    assert_eq!(layer.get_tile_data(99, 99), Some(&tile));
+    assert!(layer.set_tile(100, 0, Some(tile)).is_err());
+    assert!(layer
+        .set_tile(0, 0, Some(LayerTileData::new(1, 0, false, false, false)))
+        .is_err());
+    assert!(map.get_tile_layer_mut(42).is_none());
+
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert!(layer.get_tile(0, 0).is_none());
+    assert!(layer.get_tile(99, 99).unwrap().flip_h);
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let id = map.get_layer(0).unwrap().id();
+    let mut layer = map.get_tile_layer_mut(id).unwrap();
+    layer.set_tile(-1000, 1000, Some(tile)).unwrap();
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(layer.get_tile(-1000, 1000).unwrap().id(), 7);
+}
+
+#[test]
+fn test_gzip_compressed_files() {
+    let plain = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let compressed = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx.gz")
+        .unwrap();
+    compare_everything_but_tileset_sources(&plain, &compressed);
+    assert_eq!(compressed.tilesets()[0].name, plain.tilesets()[0].name);
+    assert_eq!(compressed.tilesets()[0].image, plain.tilesets()[0].image);
+    let layer = |map: &Map| {
+        let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+        (0..layer.width() as i32)
+            .flat_map(|x| (0..layer.height() as i32).map(move |y| (x, y)))
+            .map(|(x, y)| layer.get_tile(x, y).map(|tile| tile.id()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(layer(&plain), layer(&compressed));
+
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx.gz")
+        .unwrap();
+    assert_eq!(tileset.name, plain.tilesets()[0].name);
+
+    let mut loader = Loader::with_reader(|path: &Path| {
+        futures::future::ready(std::fs::read(path).map(std::io::Cursor::new))
+    });
+    let compressed = futures::executor::block_on(
+        loader.load_tmx_map_async("assets/tiled_base64_external.tmx.gz"),
+    )
+    .unwrap();
+    assert_eq!(layer(&plain), layer(&compressed));
+}
+
+#[test]
+fn test_load_map_from_reader() {
+    let expected = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let contents = std::fs::read("assets/tiled_base64_external.tmx").unwrap();
+
+    // The external tileset is read relative to the virtual path
+    let map = Loader::new()
+        .load_tmx_map_from(contents.as_slice(), "assets/tiled_base64_external.tmx")
+        .unwrap();
+    compare_everything_but_tileset_sources(&expected, &map);
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+    assert_eq!(map.tilesets()[0].image, expected.tilesets()[0].image);
+    assert!(Loader::new()
+        .load_tmx_map_from(contents.as_slice(), "elsewhere/map.tmx")
+        .is_err());
+
+    // Compressed contents are decompressed as with files
+    let compressed = std::fs::read("assets/tiled_base64_external.tmx.gz").unwrap();
+    let map = Loader::new()
+        .load_tmx_map_from(compressed.as_slice(), "assets/compressed.tmx.gz")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+
+    let mut loader = Loader::with_reader(|path: &Path| {
+        futures::future::ready(std::fs::read(path).map(std::io::Cursor::new))
+    });
+    let map = futures::executor::block_on(
+        loader.load_tmx_map_from_async(contents.as_slice(), "assets/tiled_base64_external.tmx"),
+    )
+    .unwrap();
+    compare_everything_but_tileset_sources(&expected, &map);
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+}
+
+#[test]
+fn test_load_progress_and_cancellation() {
+    use std::sync::{Arc, Mutex};
+    use tiled::{CancellationToken, LoadProgress, LoaderOptions};
+
+    let stages = Arc::new(Mutex::new(Vec::new()));
+    let mut loader = Loader::new();
+    let recorded = stages.clone();
+    loader.set_progress_handler(move |progress| {
+        recorded.lock().unwrap().push(match progress {
+            LoadProgress::MapStarted { .. } => "map started".to_owned(),
+            LoadProgress::TilesetLoaded { index, count, name } => {
+                format!("tileset {}/{} {}", index + 1, count, name)
+            }
+            LoadProgress::LayerLoaded { index, name } => format!("layer {} {}", index, name),
+            LoadProgress::MapLoaded { .. } => "map loaded".to_owned(),
+            _ => unreachable!(),
+        });
+    });
+    loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(
+        *stages.lock().unwrap(),
+        [
+            "map started",
+            "tileset 1/1 tilesheet",
+            "layer 0 tile-1",
+            "layer 1 group-1",
+            "layer 2 group-2",
+            "map loaded",
+        ]
+    );
+
+    // Cancelling from the handler stops loading at the next stage, even in lenient mode
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    let mut loader = Loader::with_reader(|path: &Path| {
+        futures::future::ready(std::fs::read(path).map(std::io::Cursor::new))
+    });
+    loader.set_options(LoaderOptions {
+        lenient: true,
+        cancellation_token: Some(token.clone()),
+        ..LoaderOptions::default()
+    });
+    let layers = Arc::new(Mutex::new(0));
+    let loaded = layers.clone();
+    loader.set_progress_handler(move |progress| {
+        if let LoadProgress::LayerLoaded { .. } = progress {
+            *loaded.lock().unwrap() += 1;
+            cancel.cancel();
+        }
+    });
+    let result =
+        futures::executor::block_on(loader.load_tmx_map_async("assets/tiled_group_layers.tmx"));
+    assert!(matches!(result, Err(tiled::Error::LoadingCancelled)));
+    assert_eq!(*layers.lock().unwrap(), 1);
+    assert!(token.is_cancelled());
+}
+
+#[test]
+fn test_load_template_and_preload_tilesets() {
+    use std::sync::{Arc, Mutex};
+
+    let reads = Arc::new(Mutex::new(Vec::new()));
+    let recorded = reads.clone();
+    let mut loader = Loader::with_reader(move |path: &Path| {
+        recorded.lock().unwrap().push(path.to_owned());
+        std::fs::File::open(path).map(std::io::BufReader::new)
+    });
+
+    let tilesets = loader.preload_tilesets(["assets/tilesheet.tsx"]).unwrap();
+    let template = loader
+        .load_template("assets/tiled_object_template.tx")
+        .unwrap();
+    assert_eq!(
+        template.object.properties["property"],
+        PropertyValue::IntValue(1)
+    );
+    assert_eq!(
+        template.tileset.as_ref().unwrap().name,
+        "tilesheet_template"
+    );
+    assert!(loader
+        .cache()
+        .get_template(Path::new("assets/tiled_object_template.tx"))
+        .is_some());
+
+    // The map reads neither the preloaded tileset nor the template again
+    reads.lock().unwrap().clear();
+    let map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(
+        *reads.lock().unwrap(),
+        [PathBuf::from("assets/tiled_object_template.tmx")]
+    );
+    assert!(Arc::ptr_eq(&map.tilesets()[0], &tilesets[0]));
+
+    // Cached tilesets are returned as is
+    let again = loader
+        .preload_tilesets(vec![PathBuf::from("assets/tilesheet.tsx")])
+        .unwrap();
+    assert!(Arc::ptr_eq(&again[0], &tilesets[0]));
+    assert!(loader.preload_tilesets(["assets/missing.tsx"]).is_err());
+
+    #[cfg(feature = "json")]
+    {
+        let mut loader = Loader::new();
+        let tilesets = loader.preload_tilesets(["assets/tilesheet.tsj"]).unwrap();
+        assert_eq!(tilesets[0].name, "tilesheet");
+    }
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parallel_tile_decoding() {
+    use tiled::LoaderOptions;
+
+    // Lenient mode decodes the layers one by one as they are parsed
+    let sequential_loader = |compact_tile_layers| {
+        let mut loader = Loader::new();
+        loader.set_options(LoaderOptions {
+            lenient: true,
+            compact_tile_layers,
+            ..LoaderOptions::default()
+        });
+        loader
+    };
+    for path in [
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_csv.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_multiple_tilesets.tmx",
+        "assets/tiled_parallax.tmx",
+    ]
+    .iter()
+    {
+        let parallel = Loader::new().load_tmx_map(path).unwrap();
+        let sequential = sequential_loader(false).load_tmx_map(path).unwrap();
+        assert_eq!(parallel, sequential, "{}", path);
+
+        let mut loader = Loader::new();
+        loader.set_options(LoaderOptions {
+            compact_tile_layers: true,
+            ..LoaderOptions::default()
+        });
+        let parallel = loader.load_tmx_map(path).unwrap();
+        let sequential = sequential_loader(true).load_tmx_map(path).unwrap();
+        assert_eq!(parallel, sequential, "{}", path);
+    }
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    let layer = group.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(layer.get_tile(3, 3).unwrap().id(), 5);
+}
+
+#[test]
+fn test_remap_gids_and_consolidate_tilesets() {
+    use std::collections::HashMap;
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_multiple_tilesets.tmx")
+        .unwrap();
+    assert_eq!(map.first_gids(), vec![1, 85, 169]);
+
+    let tile = |map: &Map, x: i32, y: i32| {
+        let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+        layer
+            .get_tile(x, y)
+            .map(|tile| (tile.tileset_index(), tile.id()))
+    };
+
+    // IDs outside of every tileset leave the map as is
+    let unchanged = map.clone();
+    assert!(matches!(
+        map.remap_gids(&HashMap::from([(1, 5), (85, 1000)])),
+        Err(tiled::Error::InvalidGid(1000))
+    ));
+    assert_eq!(map, unchanged);
+
+    map.remap_gids(&HashMap::from([(1, 90), (85, 170)]))
+        .unwrap();
+    assert_eq!(tile(&map, 0, 0), Some((1, 5)));
+    assert_eq!(tile(&map, 1, 0), Some((2, 1)));
+    assert_eq!(tile(&map, 0, 1), Some((2, 0)));
+    assert_eq!(tile(&map, 1, 1), None);
+
+    assert_eq!(map.consolidate_tilesets(), vec![1, 85]);
+    assert_eq!(map.tilesets().len(), 2);
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+    assert_eq!(map.tilesets()[1].name, "tilesheet_wangsets");
+    assert_eq!(tile(&map, 0, 0), Some((1, 5)));
+    assert_eq!(tile(&map, 1, 0), Some((0, 1)));
+    assert_eq!(tile(&map, 0, 1), Some((0, 0)));
+
+    // Nothing is left to merge
+    let consolidated = map.clone();
+    assert_eq!(map.consolidate_tilesets(), vec![1, 85]);
+    assert_eq!(map, consolidated);
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_pack_atlas() {
+    let mut loader = Loader::new();
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_collection.tsx")
+        .unwrap();
+
+    let atlas = tileset.pack_atlas(loader.reader_mut(), 1).unwrap().unwrap();
+    assert_eq!(atlas.image.dimensions(), (6, 6));
+    assert_eq!(atlas.rects.len(), 3);
+    let rect = |x, y, width, height| Rect {
+        x,
+        y,
+        width,
+        height,
+    };
+    assert_eq!(atlas.tile_rect(2), Some(rect(0, 0, 2, 3)));
+    assert_eq!(atlas.tile_rect(5), Some(rect(3, 0, 3, 3)));
+    assert_eq!(atlas.tile_rect(0), Some(rect(0, 4, 4, 2)));
+    assert_eq!(atlas.tile_rect(1), None);
+    assert_eq!(
+        atlas.tile_uv(5),
+        Some(UvRect {
+            u_min: 0.5,
+            v_min: 0.0,
+            u_max: 1.0,
+            v_max: 0.5,
+        })
+    );
+
+    // Each tile image is copied as is, and padding is left transparent
+    for (id, rect) in &atlas.rects {
+        let image = tileset
+            .get_tile(*id)
+            .unwrap()
+            .load_image(loader.reader_mut())
+            .unwrap()
+            .unwrap();
+        for (x, y, pixel) in image.enumerate_pixels() {
+            assert_eq!(atlas.image.get_pixel(rect.x + x, rect.y + y), pixel);
+        }
+    }
+    assert_eq!(atlas.image.get_pixel(2, 0).0, [0, 0, 0, 0]);
+    assert_eq!(atlas.image.get_pixel(0, 3).0, [0, 0, 0, 0]);
+    assert_eq!(atlas.image.get_pixel(0, 4).0, [255, 0, 0, 255]);
+
+    // Tilesets with an image of their own are already atlases
+    let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    assert!(tileset
+        .pack_atlas(loader.reader_mut(), 0)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_tile_layer_meshes() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_flipped.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+
+    let meshes = layer.meshes();
+    assert_eq!(meshes.len(), 1);
+    let mesh = &meshes[0];
+    assert_eq!(mesh.tileset_index, 0);
+    assert_eq!(mesh.tile_id, None);
+    assert_eq!(mesh.positions.len(), 16);
+    assert_eq!(mesh.uvs.len(), 16);
+    assert_eq!(mesh.indices.len(), 24);
+    assert_eq!(&mesh.indices[6..12], &[4, 5, 6, 4, 6, 7]);
+
+    // Tiles are listed row by row, each quad clockwise from its top-left corner
+    assert_eq!(
+        &mesh.positions[4..8],
+        &[[32.0, 0.0], [64.0, 0.0], [64.0, 32.0], [32.0, 32.0]]
+    );
+    let (u_min, u_max, v_min, v_max) = (64.0 / 448.0, 96.0 / 448.0, 0.0, 32.0 / 192.0);
+    // Flipped vertically
+    assert_eq!(
+        &mesh.uvs[4..8],
+        &[
+            [u_min, v_max],
+            [u_max, v_max],
+            [u_max, v_min],
+            [u_min, v_min]
+        ]
+    );
+    // Flipped horizontally
+    assert_eq!(
+        &mesh.uvs[8..12],
+        &[
+            [u_max, v_min],
+            [u_min, v_min],
+            [u_min, v_max],
+            [u_max, v_max]
+        ]
+    );
+    // Flipped diagonally
+    assert_eq!(
+        &mesh.uvs[12..16],
+        &[
+            [u_min, v_min],
+            [u_min, v_max],
+            [u_max, v_max],
+            [u_max, v_min]
+        ]
+    );
+    // Flipped in every direction, which is the same as being flipped diagonally and then rotated
+    // by 180 degrees
+    assert_eq!(
+        &mesh.uvs[0..4],
+        &[
+            [u_max, v_max],
+            [u_max, v_min],
+            [u_min, v_min],
+            [u_min, v_max]
+        ]
+    );
+
+    // Only the tiles within the viewport are included
+    let meshes = layer.meshes_in(TileRect {
+        x: 1,
+        y: 0,
+        width: 5,
+        height: 1,
+    });
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(meshes[0].positions.len(), 4);
+    assert_eq!(meshes[0].positions[0], [32.0, 0.0]);
+    assert!(layer
+        .meshes_in(TileRect {
+            x: 2,
+            y: 2,
+            width: 2,
+            height: 2,
+        })
+        .is_empty());
+
+    // On hexagonal maps, the diagonal flip rotates the quad by 60 degrees instead of swapping the
+    // texture coordinates
+    let mut hexagonal = map.clone();
+    hexagonal.orientation = Orientation::Hexagonal;
+    let layer = hexagonal.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let mesh = &layer.meshes()[0];
+    assert_eq!(
+        &mesh.uvs[12..16],
+        &[
+            [u_min, v_min],
+            [u_max, v_min],
+            [u_max, v_max],
+            [u_min, v_max]
+        ]
+    );
+    let quad = &mesh.positions[12..16];
+    let center_x = quad.iter().map(|p| p[0]).sum::<f32>() / 4.0;
+    let center_y = quad.iter().map(|p| p[1]).sum::<f32>() / 4.0;
+    for (position, &(x, y)) in quad
+        .iter()
+        .zip([(-16.0, -16.0), (16.0, -16.0), (16.0, 16.0), (-16.0, 16.0)].iter())
+    {
+        let (x, y) = Affine::rotation(60.0).apply(x, y);
+        assert!((position[0] - center_x - x).abs() < 1e-3);
+        assert!((position[1] - center_y - y).abs() < 1e-3);
+    }
+
+    // Tiles of image collection tilesets are drawn from their own image, aligned to the bottom of
+    // their cell
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_nested_class_property.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let meshes = layer.meshes();
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(meshes[0].tile_id, Some(0));
+    assert_eq!(
+        meshes[0].positions,
+        vec![[0.0, -160.0], [448.0, -160.0], [448.0, 32.0], [0.0, 32.0]]
+    );
+    assert_eq!(
+        meshes[0].uvs,
+        vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+    );
+}
+
+#[cfg(feature = "bevy")]
+#[test]
+fn test_bevy_asset_loader() {
+    use bevy_app::App;
+    use bevy_asset::{AssetApp, AssetPlugin, AssetServer, Assets, Handle, LoadState};
+    use bevy_tasks::{AsyncComputeTaskPool, ComputeTaskPool, IoTaskPool, TaskPool};
+    use tiled::{TiledMap, TiledPlugin};
+
+    IoTaskPool::get_or_init(TaskPool::new);
+    AsyncComputeTaskPool::get_or_init(TaskPool::new);
+    ComputeTaskPool::get_or_init(TaskPool::new);
+    let mut app = App::new();
+    app.add_plugins((AssetPlugin::default(), TiledPlugin))
+        .init_asset::<bevy_image::Image>();
+
+    let handle: Handle<TiledMap> = app
+        .world()
+        .resource::<AssetServer>()
+        .load("folder/tiled_relative_paths.tmx");
+    for _ in 0..1000 {
+        app.update();
+        match app.world().resource::<AssetServer>().load_state(&handle) {
+            LoadState::Loaded => break,
+            LoadState::Failed(err) => panic!("{}", err),
+            _ => std::thread::sleep(Duration::from_millis(5)),
+        }
+    }
+
+    let maps = app.world().resource::<Assets<TiledMap>>();
+    let map = maps.get(&handle).unwrap();
+    let expected = Loader::new()
+        .load_tmx_map("assets/folder/tiled_relative_paths.tmx")
+        .unwrap();
+    assert_eq!(map.layers().len(), expected.layers().len());
+    assert_eq!(map.tilesets().len(), 1);
+
+    // Tilesets are labeled assets of the map
+    assert_eq!(map.tilesets.len(), 1);
+    let tilesets = app.world().resource::<Assets<Tileset>>();
+    let tileset = tilesets.get(&map.tilesets[0]).unwrap();
+    assert_eq!(tileset.name, expected.tilesets()[0].name);
+    assert_eq!(tileset.tilecount, expected.tilesets()[0].tilecount);
+    let server = app.world().resource::<AssetServer>();
+    assert_eq!(
+        server.get_path(map.tilesets[0].id()).unwrap().to_string(),
+        "folder/tiled_relative_paths.tmx#Tileset0"
+    );
+
+    // Images are dependencies of the map, with paths relative to the root of the assets
+    let image = &map.images[Path::new("folder/../tilesheet.png")];
+    assert_eq!(
+        server.get_path(image.id()).unwrap().to_string(),
+        "tilesheet.png"
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_enum_properties() {
+    let enum_value = |name: &str, value: &str, flags: &[&str]| PropertyValue::Enum {
+        name: name.to_owned(),
+        value: value.to_owned(),
+        flags: flags.iter().map(|&flag| flag.to_owned()).collect(),
+    };
+
+    // Without a project, enum properties keep the type they are stored as
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_enum_property.tmx")
+        .unwrap();
+    assert_eq!(map.properties["element"], PropertyValue::IntValue(2));
+
+    let project = loader
+        .load_project("assets/tiled_enum_property.tiled-project")
+        .unwrap();
+    loader.set_project(Some(project));
+    for path in [
+        "assets/tiled_enum_property.tmx",
+        "assets/tiled_enum_property.tmj",
+    ] {
+        let map = if path.ends_with(".tmx") {
+            loader.load_tmx_map(path).unwrap()
+        } else {
+            loader.load_tmj_map(path).unwrap()
+        };
+
+        // Enums stored by index and by name, as single values and as flags
+        assert_eq!(
+            map.properties["element"],
+            enum_value("element", "earth", &[])
+        );
+        assert_eq!(
+            map.properties["resistances"],
+            enum_value("resistances", "fire,earth", &["fire", "earth"])
+        );
+        assert_eq!(
+            map.properties["layers"],
+            enum_value("layers", "ground,air", &["ground", "air"])
+        );
+        // Values the enum doesn't declare are left as they are stored
+        assert_eq!(
+            map.properties["unknown element"],
+            PropertyValue::IntValue(7)
+        );
+
+        // Class members are resolved too, including their default values
+        let Some(PropertyValue::ClassValue { properties, .. }) = map.properties.get("monster")
+        else {
+            panic!("expected a class property in {}", path);
+        };
+        assert_eq!(properties["element"], enum_value("element", "water", &[]));
+        assert_eq!(
+            properties["resistances"],
+            enum_value("resistances", "water", &["water"])
+        );
+    }
+}
+
+#[test]
+fn test_file_properties() {
+    use tiled::{PropertiesExt, ResourceReader};
+
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/folder/tiled_file_property.tmx")
+        .unwrap();
+
+    // Paths are joined with the directory of the file the property was declared in
+    let sprite = map.properties.get_file("sprite").unwrap().to_owned();
+    assert_eq!(sprite, Path::new("assets/folder/../tilesheet.png"));
+    // The tileset is in the parent directory of the map
+    assert_eq!(
+        map.tilesets()[0].properties.get_file("sheet"),
+        Some(Path::new("assets/folder/../tilesheet.png"))
+    );
+    assert_eq!(map.properties.get_file("unset"), Some(Path::new("")));
+
+    // Which is where the reader of the loader finds them
+    assert!(loader.reader_mut().read_from(&sprite).is_ok());
+}
+
+#[test]
+fn test_tile_neighbors() {
+    use std::collections::{HashMap, VecDeque};
+    use tiled::{StaggerAxis, StaggerIndex};
+
+    let orientations = [
+        (Orientation::Orthogonal, 0, 4),
+        (Orientation::Isometric, 0, 4),
+        (Orientation::Staggered, 0, 4),
+        (Orientation::Hexagonal, 0, 4),
+        (Orientation::Hexagonal, 12, 6),
+    ];
+    for (orientation, hex_side_length, count) in orientations.iter().copied() {
+        for stagger_axis in [StaggerAxis::X, StaggerAxis::Y].iter().copied() {
+            for stagger_index in [StaggerIndex::Odd, StaggerIndex::Even].iter().copied() {
+                let mut map = Map::new(orientation, 10, 8, 32, 24, false);
+                map.stagger_axis = stagger_axis;
+                map.stagger_index = stagger_index;
+                map.hex_side_length = hex_side_length;
+                let config = format!(
+                    "{:?} {:?} {:?} map with {} side length",
+                    orientation, stagger_axis, stagger_index, hex_side_length
+                );
+
+                // Distances match the ones found by walking from neighbour to neighbour
+                let start = (1, 2);
+                let mut distances = HashMap::new();
+                distances.insert(start, 0);
+                let mut queue = VecDeque::from(vec![start]);
+                while let Some((x, y)) = queue.pop_front() {
+                    let neighbors = map.neighbors(x, y);
+                    assert_eq!(neighbors.len(), count, "{}", config);
+                    for neighbor in neighbors {
+                        assert!(map.neighbors(neighbor.0, neighbor.1).contains(&(x, y)));
+                        if neighbor.0.abs() <= 12 && neighbor.1.abs() <= 12 {
+                            let distance = distances[&(x, y)] + 1;
+                            distances.entry(neighbor).or_insert_with(|| {
+                                queue.push_back(neighbor);
+                                distance
+                            });
+                        }
+                    }
+                }
+                for (&position, &distance) in &distances {
+                    if distance <= 6 {
+                        assert_eq!(
+                            map.tile_distance(start, position),
+                            distance,
+                            "{} from {:?} to {:?}",
+                            config,
+                            start,
+                            position
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_shared_chunks() {
+    fn infinite_layer(map: &Map, index: usize) -> tiled::InfiniteTileLayer<'_> {
+        match map.get_layer(index).unwrap().as_tile_layer() {
+            Some(TileLayer::Infinite(layer)) => layer,
+            _ => panic!("expected an infinite tile layer"),
+        }
+    }
+    fn first_tile(layer: &InfiniteTileLayerData, x: i32, y: i32) -> &LayerTileData {
+        layer
+            .get_chunk_data(x, y)
+            .unwrap()
+            .get_tile_data(0, 0)
+            .unwrap()
+    }
+
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_repeated_chunks.tmx")
+        .unwrap();
+    let (water, more_water) = (infinite_layer(&map, 0), infinite_layer(&map, 1));
+    let shared = first_tile(&water, 0, 0);
+
+    // Identical chunks are stored once, within layers and across them
+    assert!(std::ptr::eq(shared, first_tile(&water, 1, 0)));
+    assert!(std::ptr::eq(shared, first_tile(&more_water, 0, 1)));
+
+    // As well as across the maps loaded by the same loader
+    let other = loader
+        .load_tmx_map("assets/tiled_repeated_chunks.tmx")
+        .unwrap();
+    assert!(std::ptr::eq(
+        shared,
+        first_tile(&infinite_layer(&other, 0), 0, 0)
+    ));
+
+    // Editing a chunk copies it first
+    let mut edited = InfiniteTileLayerData::clone(&water);
+    edited.set_tile_data(0, 0, None);
+    assert_eq!(water.get_tile_data(0, 0), Some(shared));
+    assert_eq!(edited.get_tile_data(0, 0), None);
+    assert!(std::ptr::eq(shared, first_tile(&edited, 1, 0)));
+
+    // Unless chunks aren't pooled
+    loader.set_options(tiled::LoaderOptions {
+        chunk_pool: None,
+        ..Default::default()
+    });
+    let unpooled = loader
+        .load_tmx_map("assets/tiled_repeated_chunks.tmx")
+        .unwrap();
+    let unpooled_water = infinite_layer(&unpooled, 0);
+    assert!(!std::ptr::eq(
+        first_tile(&unpooled_water, 0, 0),
+        first_tile(&unpooled_water, 1, 0)
+    ));
+    assert_eq!(*unpooled_water, *water);
+}
+
+#[test]
+fn test_strict_loading() {
+    let mut loader = Loader::new();
+    assert!(loader.load_tmx_map("assets/tiled_strict.tmx").is_ok());
+
+    loader.set_options(tiled::LoaderOptions {
+        strict: true,
+        ..Default::default()
+    });
+    assert!(loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .is_ok());
+    assert!(loader.load_tsx_tileset("assets/tilesheet.tsx").is_ok());
+    match loader.load_tmx_map("assets/tiled_strict.tmx") {
+        Err(tiled::Error::UnknownAttribute { element, name }) => {
+            assert_eq!(element, "layer");
+            assert_eq!(name, "blendmode");
+        }
+        other => panic!("expected an unknown attribute, got {:?}", other.map(|_| ())),
+    }
+
+    let without_attribute = std::fs::read_to_string("assets/tiled_strict.tmx")
+        .unwrap()
+        .replace(" blendmode=\"multiply\"", "");
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(without_attribute.clone().into_bytes()))
+    });
+    loader.set_options(tiled::LoaderOptions {
+        strict: true,
+        ..Default::default()
+    });
+    match loader.load_tmx_map("strict.tmx") {
+        Err(tiled::Error::UnknownElement { name, parent }) => {
+            assert_eq!(name, "shadow");
+            assert_eq!(parent, "map");
+        }
+        other => panic!("expected an unknown element, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_parse_warnings() {
+    use std::sync::{Arc, Mutex};
+
+    let handled = Arc::new(Mutex::new(Vec::new()));
+    let mut loader = Loader::new();
+    loader.set_options(tiled::LoaderOptions {
+        warning_handler: Some(tiled::WarningHandler::new({
+            let handled = handled.clone();
+            move |warning| handled.lock().unwrap().push(warning.clone())
+        })),
+        ..Default::default()
+    });
+    loader.load_tmx_map("assets/tiled_warnings.tmx").unwrap();
+
+    let warnings = loader.take_warnings();
+    assert_eq!(*handled.lock().unwrap(), warnings);
+    assert_eq!(warnings.len(), 2);
+    let contents = std::fs::read_to_string("assets/tiled_warnings.tmx").unwrap();
+    assert_eq!(
+        warnings[0].kind,
+        tiled::ParseWarningKind::MissingAttribute {
+            element: "layer".to_owned(),
+            name: "id".to_owned(),
+            default: "0".to_owned(),
+        }
+    );
+    assert!(contents[warnings[0].position as usize..].starts_with("<layer "));
+    assert_eq!(
+        warnings[1].kind,
+        tiled::ParseWarningKind::DeprecatedAttribute {
+            element: "object".to_owned(),
+            name: "class".to_owned(),
+            replacement: "type".to_owned(),
+        }
+    );
+    assert!(contents[warnings[1].position as usize..].starts_with("<object "));
+    assert_eq!(
+        warnings[1].path,
+        std::path::Path::new("assets/tiled_warnings.tmx")
+    );
+    assert!(loader.take_warnings().is_empty());
+
+    loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    assert!(loader.take_warnings().is_empty());
+}
+
+#[test]
+fn test_usage_stats() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_usage.tmx")
+        .unwrap();
+    let stats = map.usage_stats();
+
+    assert_eq!(stats.tilesets.len(), 3);
+    assert_eq!(stats.tilesets[0].placed_tiles, 1);
+    assert_eq!(stats.tilesets[0].tile_objects, 0);
+    assert_eq!(
+        stats.tilesets[0]
+            .used_tiles
+            .iter()
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert_eq!(stats.tilesets[1].placed_tiles, 2);
+    assert_eq!(stats.tilesets[1].tile_objects, 1);
+    assert_eq!(
+        stats.tilesets[1]
+            .used_tiles
+            .iter()
+            .copied()
+            .collect::<Vec<_>>(),
+        vec![0, 6]
+    );
+    assert!(stats.tilesets[2].is_unused());
+    assert!(stats.tilesets[2].used_tiles.is_empty());
+    assert_eq!(stats.unused_tilesets().collect::<Vec<_>>(), vec![2]);
+    assert_eq!(stats.placed_tiles(), 3);
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn test_random_tiles() {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tiled_tile_probability.tsx")
+        .unwrap();
+    let mut rng = SmallRng::seed_from_u64(42);
+
+    let mut counts = [0; 4];
+    for _ in 0..8000 {
+        counts[tileset.random_tile(&mut rng).unwrap() as usize] += 1;
+    }
+    assert_eq!(counts[0], 0);
+    assert_eq!(counts[3], 0);
+    // Tile 1 has a probability of 3, and tile 2 the default of 1.
+    assert!((5700..6300).contains(&counts[1]), "{:?}", counts);
+
+    // Tile 1 is fully grass, while tile 2 has a corner of sand, whose probability is 0.5.
+    let mut counts = [0; 4];
+    for _ in 0..7000 {
+        let tile = tileset
+            .random_wang_tile(0, WangId([0, 1, 0, 0, 0, 1, 0, 1]), &mut rng)
+            .unwrap();
+        counts[tile as usize] += 1;
+    }
+    assert_eq!(counts[0] + counts[3], 0);
+    assert!((5700..6300).contains(&counts[1]), "{:?}", counts);
+
+    assert_eq!(
+        tileset.random_wang_tile(0, WangId([0, 1, 0, 1, 0, 1, 0, 1]), &mut rng),
+        Some(1)
+    );
+    // The only tile fully covered by sand has a probability of 0.
+    assert_eq!(
+        tileset.random_wang_tile(0, WangId([0, 2, 0, 2, 0, 2, 0, 2]), &mut rng),
+        None
+    );
+    assert_eq!(tileset.random_wang_tile(1, WangId([0; 8]), &mut rng), None);
+}
+
+#[test]
+fn test_editor_settings() {
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_editor_settings.tmx")
+        .unwrap();
+    let settings = tiled::EditorSettings {
+        chunk_size: Some((32, 32)),
+        export_target: Some("tiled_editor_settings.tmj".to_owned()),
+        export_format: Some("json".to_owned()),
+    };
+    assert_eq!(map.editor_settings, Some(settings.clone()));
+    #[cfg(feature = "json")]
+    {
+        let json = loader
+            .load_tmj_map("assets/tiled_editor_settings.tmj")
+            .unwrap();
+        assert_eq!(json.editor_settings, Some(settings));
+    }
+
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    assert_eq!(map.editor_settings, None);
+}
+
+#[test]
+fn test_xml_encoded_tile_data() {
+    let map = Loader::new().load_tmx_map("assets/tiled_xml.tmx").unwrap();
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(layer.width(), 100);
+    assert_eq!(layer.get_tile(0, 0).unwrap().id(), 29);
+    assert_eq!(layer.get_tile(9, 0).unwrap().id(), 29);
+    assert!(layer.get_tile(10, 0).is_none());
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_xml_infinite.tmx")
+        .unwrap();
+    let TileLayer::Infinite(layer) = map.get_layer(0).unwrap().as_tile_layer().unwrap() else {
+        panic!("It is wrongly recognised as a finite map");
+    };
+    assert_eq!(layer.get_tile(-2, 0).unwrap().id(), 4);
+    assert!(layer.get_tile(-1, 0).is_none());
+    assert_eq!(layer.get_tile(-1, 1).unwrap().id(), 6);
+    assert_eq!(layer.get_tile(1, 0).unwrap().id(), 11);
+    let flipped = layer.get_tile(0, 1).unwrap();
+    assert_eq!(flipped.id(), 2);
+    assert!(flipped.flip_h);
+    assert!(layer.get_tile(1, 1).is_none());
+}
+
+#[test]
+fn test_image_layer_visible_tiles() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_image_layer_repeat.tmx")
+        .unwrap();
+    let viewport = Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: 640.0,
+        height: 480.0,
+    };
+
+    let horizontal = map.get_layer(0).unwrap().as_image_layer().unwrap();
+    assert!(horizontal.repeat_x);
+    assert!(!horizontal.repeat_y);
+    assert_eq!(
+        horizontal.visible_tiles(viewport, (0.0, 0.0)),
+        vec![(0.0, 0.0), (448.0, 0.0)]
+    );
+    assert_eq!(
+        horizontal.visible_tiles(
+            Viewport {
+                x: -100.0,
+                y: 200.0,
+                ..viewport
+            },
+            (0.0, 0.0)
+        ),
+        vec![]
+    );
+
+    // The offset and parallax factor of the group apply to the layer inside of it.
+    let both = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    let both = both.get_layer(0).unwrap().as_image_layer().unwrap();
+    let tiles = both.visible_tiles(viewport, (100.0, 100.0));
+    // Placed at (10 + 100 * 0.5, -20 + 100 * 0.5) = (60, 30) and repeated in both directions.
+    assert_eq!(tiles.len(), 3 * 4);
+    assert_eq!(tiles[0], (60.0 - 448.0, 30.0 - 192.0));
+    assert_eq!(tiles[1], (60.0, 30.0 - 192.0));
+    assert_eq!(tiles[11], (60.0 + 448.0, 30.0 + 2.0 * 192.0));
+
+    let single = map.get_layer(2).unwrap().as_image_layer().unwrap();
+    assert_eq!(
+        single.visible_tiles(viewport, (1000.0, 1000.0)),
+        vec![(100.0, 50.0)]
+    );
+    assert_eq!(
+        single.visible_tiles(
+            Viewport {
+                x: 548.0,
+                ..viewport
+            },
+            (0.0, 0.0)
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn test_interned_strings() {
+    use std::sync::Arc;
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let (first, second) = (layer.get_object(0).unwrap(), layer.get_object(1).unwrap());
+
+    // Both objects share the allocations of their property name and type.
+    let key = |object: &Object| object.properties.keys().next().unwrap().clone();
+    assert_eq!(&*key(&first), "object property");
+    assert!(Arc::ptr_eq(&key(&first), &key(&second)));
+    assert!(Arc::ptr_eq(&first.user_type, &second.user_type));
+}
+
+#[test]
+fn test_layer_tile_transform() {
+    use tiled::TileTransform;
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_flipped.tmx")
+        .unwrap();
+    let transforms = |map: &Map| {
+        let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+        [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(x, y)| layer.get_tile(x, y).unwrap().transform())
+    };
+    let transform = |flip_x, flip_y, rotation| TileTransform {
+        flip_x,
+        flip_y,
+        rotation,
+    };
+
+    assert_eq!(
+        transforms(&map),
+        [
+            transform(true, false, 90.0),
+            transform(false, true, 0.0),
+            transform(true, false, 0.0),
+            transform(false, true, 90.0),
+        ]
+    );
+
+    // Diagonally flipping a tile swaps its axes, which moves the top-right corner to the bottom-left.
+    let (x, y) = transforms(&map)[3].to_affine(32.0, 32.0).apply(32.0, 0.0);
+    assert!(x.abs() < 1e-4 && (y - 32.0).abs() < 1e-4);
+
+    // On hexagonal maps, the diagonal flip is a rotation by 60 degrees instead.
+    map.orientation = Orientation::Hexagonal;
+    assert_eq!(
+        transforms(&map),
+        [
+            transform(true, true, 60.0),
+            transform(false, true, 0.0),
+            transform(true, false, 0.0),
+            transform(false, false, 60.0),
+        ]
+    );
+}
+
+#[test]
+fn test_tile_for_gid() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_gid_gaps.tmx")
+        .unwrap();
+    assert_eq!(map.first_gids(), vec![1, 5, 89]);
+
+    // IDs are looked up as they are in the file, whose first GIDs are 1, 100 and 200.
+    assert_eq!(map.tileset_index_for_gid(0), None);
+    assert_eq!(map.tileset_index_for_gid(4), Some(0));
+    assert_eq!(map.tileset_index_for_gid(99), Some(0));
+    assert_eq!(map.tileset_index_for_gid(100), Some(1));
+    assert_eq!(map.tileset_index_for_gid(207), Some(2));
+    assert_eq!(map.tileset_index_for_gid(208), None);
+    let (_, data) = map.tile_for_gid(100).unwrap();
+    assert_eq!((data.tileset_index(), data.id()), (1, 0));
+    let layer = match map.get_layer(0).unwrap().as_tile_layer() {
+        Some(TileLayer::Finite(layer)) => layer,
+        _ => panic!("expected a finite tile layer"),
+    };
+    for (x, y, gid) in [(0, 0, 100), (2, 0, 102), (2, 1, 183)] {
+        let (_, data) = map.tile_for_gid(gid).unwrap();
+        assert_eq!(Some(&data), layer.get_tile_data(x, y));
+    }
+
+    // Tile 1 was deleted from the first tileset.
+    assert!(map.tile_for_gid(2).is_none());
+    let (tile, data) = map.tile_for_gid(0x20000000 | 207).unwrap();
+    assert_eq!(tile.tileset().name, "props");
+    assert_eq!(data, LayerTileData::new(2, 7, false, false, true));
+
+    // Once the tilesets of the map change, IDs are numbered as given by `Map::first_gids()`.
+    map.compact_gids();
+    assert_eq!(map.tileset_index_for_gid(92), Some(1));
+    assert_eq!(map.tileset_index_for_gid(93), None);
+    let tileset = map.tilesets()[0].clone();
+    map.add_tileset(tileset);
+    assert_eq!(map.tile_for_gid(93).unwrap().1.tileset_index(), 2);
+}
+
+#[test]
+fn test_object_template_overrides() {
+    use std::sync::Arc;
+    use tiled::OverriddenFields;
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template_overrides.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let objects: Vec<_> = layer.objects().collect();
+
+    let template = objects[0].template().unwrap();
+    assert!(objects
+        .iter()
+        .all(|o| Arc::ptr_eq(o.template().unwrap(), template)));
+    assert_eq!(
+        objects[0].overridden_fields(),
+        Some(&OverriddenFields::default())
+    );
+
+    let overridden = objects[1].overridden_fields().unwrap();
+    assert!(overridden.name && overridden.rotation);
+    assert!(!overridden.visible && !overridden.tile && !overridden.shape);
+    assert_eq!(
+        overridden
+            .properties
+            .iter()
+            .map(|p| &**p)
+            .collect::<Vec<_>>(),
+        vec!["extra", "property"]
+    );
+    assert_eq!(
+        objects[1].properties["property"],
+        PropertyValue::IntValue(2)
+    );
+
+    let overridden = objects[2].overridden_fields().unwrap();
+    assert!(overridden.tile && overridden.shape && overridden.properties.is_empty());
+    assert_eq!(objects[2].tile_data().unwrap().id(), 0);
+
+    // Objects without a template don't have overridden fields.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let object = layer.get_object(1).unwrap();
+    assert!(object.template().is_none() && object.overridden_fields().is_none());
+}
+
+#[test]
+fn test_map_diff() {
+    use tiled::{MapChange, PropertyOwner};
+
+    let mut loader = Loader::new();
+    let before = loader.load_tmx_map("assets/tiled_diff_before.tmx").unwrap();
+    let after = loader.load_tmx_map("assets/tiled_diff_after.tmx").unwrap();
+
+    assert!(before.diff(&before).is_empty());
+    // The tilesets are reordered, which doesn't change the tiles that use them.
+    assert_eq!(
+        before.diff(&after).changes,
+        vec![
+            MapChange::PropertyChanged {
+                owner: PropertyOwner::Map,
+                name: "music".into(),
+                before: Some(PropertyValue::StringValue("calm".to_owned())),
+                after: Some(PropertyValue::StringValue("boss".to_owned())),
+            },
+            MapChange::TilesetAdded {
+                name: "tilesheet_template".to_owned(),
+            },
+            MapChange::TileChanged {
+                layer_id: 1,
+                x: 1,
+                y: 0,
+                before: Some(LayerTileData::new(0, 1, false, false, false)),
+                after: Some(LayerTileData::new(1, 6, false, false, false)),
+            },
+            MapChange::TileChanged {
+                layer_id: 1,
+                x: 0,
+                y: 1,
+                before: Some(LayerTileData::new(0, 3, false, false, false)),
+                after: None,
+            },
+            MapChange::ObjectMoved {
+                object_id: 1,
+                from: (0.0, 0.0),
+                to: (16.0, 0.0),
+            },
+            MapChange::PropertyChanged {
+                owner: PropertyOwner::Object(1),
+                name: "hp".into(),
+                before: Some(PropertyValue::IntValue(3)),
+                after: Some(PropertyValue::IntValue(5)),
+            },
+            MapChange::ObjectModified {
+                layer_id: 2,
+                object_id: 2,
+            },
+            MapChange::ObjectRemoved {
+                layer_id: 2,
+                object_id: 3,
+            },
+            MapChange::ObjectAdded {
+                layer_id: 2,
+                object_id: 4,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_tiles_in_rect() {
+    use tiled::BoundingBox;
+
+    // Infinite layers are queried like finite ones, including at negative coordinates.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap();
+    let tiles = layer.tiles_in_rect(BoundingBox::new(-560.0, -40.0, 64.0, 64.0), (0.0, 0.0));
+    assert_eq!(tiles.len(), 1);
+    assert_eq!((tiles[0].x, tiles[0].y), (-16, 0));
+    assert_eq!(tiles[0].tile.id(), 17);
+    assert_eq!(tiles[0].rect, BoundingBox::new(-512.0, 0.0, 32.0, 32.0));
+
+    // Group layers yield the tiles of the tile layers nested in them.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let group = map.get_layer(1).unwrap();
+    let tiles = group.tiles_in_rect(BoundingBox::new(96.0, 96.0, 96.0, 96.0), (0.0, 0.0));
+    assert_eq!(tiles.len(), 9);
+    assert!(tiles
+        .iter()
+        .all(|tile| tile.layer.name.as_ref() == "tile-2"));
+    assert_eq!((tiles[0].x, tiles[0].y), (3, 3));
+    assert_eq!((tiles[8].x, tiles[8].y), (5, 5));
+    let nested = map.get_layer(2).unwrap();
+    let tiles = nested.tiles_in_rect(BoundingBox::new(0.0, 0.0, 256.0, 32.0), (0.0, 0.0));
+    assert_eq!(tiles.len(), 3);
+    assert!(tiles
+        .iter()
+        .all(|tile| tile.layer.name.as_ref() == "tile-3"));
+    // Tiles that only touch the rectangle are left out.
+    assert!(group
+        .tiles_in_rect(BoundingBox::new(0.0, 0.0, 96.0, 96.0), (0.0, 0.0))
+        .is_empty());
+
+    // Parallax scrolling moves the tiles relative to the camera.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_parallax.tmx")
+        .unwrap();
+    let background = map.get_layer(0).unwrap();
+    let tiles = background.tiles_in_rect(BoundingBox::new(32.0, 144.0, 1.0, 1.0), (64.0, 64.0));
+    assert_eq!(tiles.len(), 1);
+    assert_eq!((tiles[0].x, tiles[0].y), (0, 4));
+    assert_eq!(tiles[0].rect, BoundingBox::new(32.0, 144.0, 32.0, 32.0));
+    let tiles = background.tiles_in_rect(BoundingBox::new(32.0, 144.0, 1.0, 1.0), (0.0, 0.0));
+    assert_eq!((tiles[0].x, tiles[0].y), (1, 4));
+}
+
+#[test]
+fn test_chunk_pool_frees_dropped_chunks() {
+    use tiled::{ChunkPool, LoaderOptions};
+
+    let pool = ChunkPool::new();
+    let mut loader = Loader::new();
+    loader.set_options(LoaderOptions {
+        chunk_pool: Some(pool.clone()),
+        ..Default::default()
+    });
+    let first = loader
+        .load_tmx_map("assets/tiled_repeated_chunks.tmx")
+        .unwrap();
+    let len = pool.len();
+    assert!(len > 0);
+
+    // Loading the same chunks again doesn't add to the pool
+    let second = loader
+        .load_tmx_map("assets/tiled_repeated_chunks.tmx")
+        .unwrap();
+    assert_eq!(pool.len(), len);
+
+    // Chunks stay in the pool as long as a map uses them
+    drop(first);
+    assert_eq!(pool.len(), len);
+    drop(second);
+    assert!(pool.is_empty());
+
+    // Maps loaded with other chunks since then don't keep the ones dropped either
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    assert!(!pool.is_empty());
+    drop(map);
+    assert!(pool.is_empty());
+}
+
+#[test]
+fn test_empty_tile_data() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_empty_data.tmx")
+        .unwrap();
+    let names: Vec<_> = map.layers().map(|layer| layer.name.clone()).collect();
+    assert_eq!(names, ["Empty".into(), "Empty CSV".into(), "Tiles".into()]);
+
+    for layer in map.layers().take(2) {
+        let tiles = layer.as_tile_layer().unwrap();
+        assert!(tiles.get_tile(0, 0).is_none());
+        assert!(tiles.get_tile(1, 1).is_none());
+    }
+    let tiles = map.get_layer(2).unwrap().as_tile_layer().unwrap();
+    assert_eq!(tiles.get_tile(0, 0).unwrap().id(), 0);
+    assert!(tiles.get_tile(1, 0).is_none());
+    assert_eq!(tiles.get_tile(1, 1).unwrap().id(), 1);
+}
+
+#[test]
+fn test_object_alignment() {
+    use tiled::ObjectAlignment;
+
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_object_alignment.tmx")
+        .unwrap();
+    let alignments: Vec<_> = map.tilesets().iter().map(|t| t.object_alignment).collect();
+    assert_eq!(
+        alignments,
+        [
+            ObjectAlignment::TopLeft,
+            ObjectAlignment::Center,
+            ObjectAlignment::BottomRight
+        ]
+    );
+    #[cfg(feature = "json")]
+    assert_eq!(
+        loader
+            .load_tsj_tileset("assets/tilesheet_object_alignment.tsj")
+            .unwrap()
+            .object_alignment,
+        ObjectAlignment::Center
+    );
+
+    let find = |name: &str| {
+        map.objects()
+            .find(|(_, object)| object.name == name)
+            .unwrap()
+            .1
+    };
+    assert_eq!(find("top-left").pivot(), (0.0, 0.0));
+    assert_eq!(find("bottom-right").pivot(), (32.0, 32.0));
+
+    // Objects rotate around the point of their tile given by the alignment.
+    let center = find("center");
+    assert_eq!(center.pivot(), (16.0, 8.0));
+    let (x, y) = center.apply_rotation((center.x - 16.0, center.y - 8.0));
+    assert!((x - 72.0).abs() < 1e-4 && (y - 48.0).abs() < 1e-4);
+
+    // The alignment is kept once the tileset is written.
+    let mut tsx = Vec::new();
+    map.tilesets()[1]
+        .to_writer(&mut tsx, &WriteOptions::default())
+        .unwrap();
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(tsx.clone()))
+    });
+    let written = loader.load_tsx_tileset("/center.tsx").unwrap();
+    assert_eq!(written.object_alignment, ObjectAlignment::Center);
+}
+
+#[test]
+fn test_nested_class_property() {
+    fn class_members<'a>(
+        properties: &'a tiled::Properties,
+        name: &str,
+        expected_type: &str,
+    ) -> &'a tiled::Properties {
+        match properties.get(name) {
+            Some(PropertyValue::ClassValue {
+                property_type,
+                properties,
+            }) => {
+                assert_eq!(property_type, expected_type);
+                properties
+            }
+            other => panic!("Expected class property, found {:?}", other),
+        }
+    }
+
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_nested_class_property.tmx")
+        .unwrap();
+    let layer = r.get_layer(1).unwrap().as_object_layer().unwrap();
+    let object = layer.get_object(0).unwrap();
+    let properties = class_members(&object.properties, "class property", "test_type");
+    assert_eq!(
+        properties.get("test_property_1").unwrap(),
+        &PropertyValue::IntValue(3)
+    );
+    assert!(class_members(&object.properties, "empty property", "empty_type").is_empty());
+
+    // Class properties are supported on maps, layers and tiles as well, and can be nested
+    let map_members = class_members(&r.properties, "map class property", "map_settings");
+    assert_eq!(
+        map_members.get("music"),
+        Some(&PropertyValue::FileValue(PathBuf::from("assets/theme.ogg")))
+    );
+    let tile_layer = r.get_layer(0).unwrap();
+    let layer_members = class_members(
+        &tile_layer.properties,
+        "layer class property",
+        "layer_settings",
+    );
+    let lighting = class_members(layer_members, "nested", "lighting");
+    assert_eq!(
+        lighting.get("tint"),
+        Some(&PropertyValue::ColorValue(Color {
+            alpha: 0xff,
+            red: 0x10,
+            green: 0x20,
+            blue: 0x30
+        }))
+    );
+    let tile = r.tilesets()[0].get_tile(0).unwrap();
+    let terrain = class_members(&tile.properties, "tile class property", "terrain");
+    assert_eq!(
+        terrain.get("walkable"),
+        Some(&PropertyValue::BoolValue(false))
+    );
+
+    // Members that an object doesn't override are taken from its template
+    let chest = layer.get_object(1).unwrap();
+    assert_eq!(chest.name, "chest");
+    let loot = class_members(&chest.properties, "loot", "loot_table");
+    assert_eq!(loot.get("gold"), Some(&PropertyValue::IntValue(50)));
+    assert_eq!(
+        loot.get("item"),
+        Some(&PropertyValue::StringValue("potion".to_owned()))
+    );
+}
+
+#[test]
+fn test_unknown_parts_warnings() {
+    let contents = std::fs::read_to_string("assets/tiled_strict.tmx")
+        .unwrap()
+        .replace(
+            "<shadow color=\"#000000\"/>",
+            "<shadow><color value=\"#000000\"/></shadow>",
+        );
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(contents.clone().into_bytes()))
+    });
+    loader.load_tmx_map("strict.tmx").unwrap();
+    assert!(loader.take_warnings().is_empty());
+
+    loader.set_options(tiled::LoaderOptions {
+        lenient: true,
+        ..Default::default()
+    });
+    loader.load_tmx_map("strict.tmx").unwrap();
+    let warnings: Vec<_> = loader
+        .take_warnings()
+        .into_iter()
+        .map(|warning| warning.kind)
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            tiled::ParseWarningKind::UnknownAttribute {
+                element: "layer".to_owned(),
+                name: "blendmode".to_owned(),
+            },
+            tiled::ParseWarningKind::UnknownElement {
+                name: "shadow".to_owned(),
+                parent: "map".to_owned(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_lenient_group_depth() {
+    let contents = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+ <group id="1" name="broken">
+  <properties>
+   <property name="count" type="int" value="many"/>
+  </properties>
+ </group>
+ <group id="2" name="outer">
+  <group id="3" name="inner"/>
+ </group>
+</map>"#;
+    let mut loader = Loader::with_reader(move |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(contents.as_bytes()))
+    });
+    loader.set_options(tiled::LoaderOptions {
+        lenient: true,
+        max_group_depth: 2,
+        ..Default::default()
+    });
+    let map = loader.load_tmx_map("/groups.tmx").unwrap();
+
+    let kinds: Vec<_> = map
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.kind.clone())
+        .collect();
+    assert_eq!(
+        kinds,
+        [tiled::DiagnosticKind::ReplacedLayer {
+            id: 1,
+            name: "broken".to_owned()
+        }]
+    );
+    let outer = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    assert_eq!(&*outer.get_layer(0).unwrap().name, "inner");
+}
+
+#[test]
+fn test_remap_gids_with_gaps() {
+    use std::collections::HashMap;
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_gid_gaps.tmx")
+        .unwrap();
+    assert_eq!(map.first_gids(), vec![1, 5, 89]);
+
+    // IDs are numbered as in the file, whose first GIDs are 1, 100 and 200, so 5 is past the
+    // first tileset and 190 past the second one.
+    for invalid in [5, 190] {
+        assert!(matches!(
+            map.remap_gids(&HashMap::from([(100, invalid)])),
+            Err(tiled::Error::InvalidGid(gid)) if gid == invalid
+        ));
+    }
+
+    map.remap_gids(&HashMap::from([(100, 207), (183, 1), (207, 101)]))
+        .unwrap();
+    let layer = match map.get_layer(0).unwrap().as_tile_layer() {
+        Some(TileLayer::Finite(layer)) => layer,
+        _ => panic!("expected a finite tile layer"),
+    };
+    let tile = |x, y| {
+        layer
+            .get_tile_data(x, y)
+            .map(|tile| (tile.tileset_index(), tile.id()))
+    };
+    assert_eq!(tile(0, 0), Some((2, 7)));
+    assert_eq!(tile(1, 0), Some((1, 1)));
+    assert_eq!(tile(2, 1), Some((0, 0)));
+
+    let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    let objects = group.get_layer(0).unwrap().as_object_layer().unwrap();
+    let object_tile = objects.get_object(0).unwrap().tile_data().unwrap();
+    assert_eq!(
+        object_tile.tileset_location(),
+        &tiled::TilesetLocation::Map(1)
+    );
+    assert_eq!(object_tile.id(), 1);
+}
+
+#[test]
+fn test_write_gid_numbering() {
+    let write = |map: &Map, compact_gids| {
+        let mut tmx = Vec::new();
+        let options = WriteOptions {
+            base_dir: "assets".into(),
+            compact_gids,
+            ..Default::default()
+        };
+        map.to_writer(&mut tmx, &options).unwrap();
+        String::from_utf8(tmx).unwrap()
+    };
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_gid_gaps.tmx")
+        .unwrap();
+    let tmx = write(&map, false);
+    assert!(tmx.contains(r#"<tileset firstgid="100""#));
+    assert!(tmx.contains(r#"<tileset firstgid="200""#));
+    assert!(tmx.contains("100,101,102,0,"));
+    assert!(tmx.contains(r#"gid="207""#));
+
+    let tmx = write(&map, true);
+    assert!(tmx.contains(r#"<tileset firstgid="5""#));
+    assert!(tmx.contains(r#"<tileset firstgid="89""#));
+    assert!(tmx.contains("5,6,7,0,"));
+    assert!(tmx.contains(r#"gid="96""#));
+
+    // Once the tilesets change, the loaded IDs are no longer used.
+    map.compact_gids();
+    let tmx = write(&map, false);
+    assert!(tmx.contains(r#"<tileset firstgid="85""#));
+    assert!(tmx.contains("1,2,3,0,"));
+}
+
+#[test]
+fn test_unsupported_automapping_rules() {
+    fn loader_for(rules: &'static str, rule_map: String) -> Loader<impl tiled::ResourceReader> {
+        Loader::with_reader(move |path: &std::path::Path| -> std::io::Result<_> {
+            let contents = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("txt") => rules.as_bytes().to_vec(),
+                Some("tmx") => rule_map.clone().into_bytes(),
+                _ => std::fs::read(path)?,
+            };
+            Ok(std::io::Cursor::new(contents))
+        })
+    }
+    let rule_map = std::fs::read_to_string("assets/automap/rule_shadows.tmx").unwrap();
+
+    let mut loader = loader_for("[*]\nrule_shadows.tmx\n", rule_map.clone());
+    let rules = loader
+        .load_automapping_rules("assets/automap/rules.txt")
+        .unwrap();
+    assert_eq!(rules.rule_maps.len(), 1);
+
+    let mut loader = loader_for("[dungeon*]\nrule_shadows.tmx\n", rule_map.clone());
+    assert!(matches!(
+        loader.load_automapping_rules("assets/automap/rules.txt"),
+        Err(tiled::Error::InvalidAutomappingRules { .. })
+    ));
+
+    let with_objects = rule_map.replace(
+        "</map>",
+        "<objectgroup id=\"4\" name=\"output_Props\"/>\n</map>",
+    );
+    let mut loader = loader_for("rule_shadows.tmx\n", with_objects);
+    assert!(matches!(
+        loader.load_automapping_rules("assets/automap/rules.txt"),
+        Err(tiled::Error::InvalidAutomappingRules { description }) if description.contains("output_Props")
+    ));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_compact_tile_layers() {
+    let mut layer = FiniteTileLayerData::empty(4, 4);
+    layer.set_tile_data(1, 2, Some(LayerTileData::new(0, 7, true, false, false)));
+    let full: FiniteTileLayerData =
+        serde_json::from_str(&serde_json::to_string(&layer).unwrap()).unwrap();
+    assert!(!full.is_compact());
+    assert_eq!(full, layer);
+
+    layer.compact();
+    let compact: FiniteTileLayerData =
+        serde_json::from_str(&serde_json::to_string(&layer).unwrap()).unwrap();
+    assert!(compact.is_compact());
+    assert_eq!(compact, layer);
+
+    // Cells referring to tiles past the palette are rejected instead of panicking once read.
+    let valid = r#"{"width":2,"height":1,"tiles":{"Compact":{"palette":[],"cells":[0,0]}}}"#;
+    assert!(serde_json::from_str::<FiniteTileLayerData>(valid).is_ok());
+    let invalid = valid.replace("[0,0]", "[0,1]");
+    assert!(serde_json::from_str::<FiniteTileLayerData>(&invalid).is_err());
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_load_map_images_transparent_colour() {
+    let mut loader = Loader::new();
+    let sheet = loader
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap()
+        .load_image(loader.reader_mut())
+        .unwrap()
+        .unwrap();
+    let [red, green, blue, _] = sheet.get_pixel(0, 0).0;
+    assert!(sheet.get_pixel(0, 0)[3] != 0);
+
+    let contents = std::fs::read_to_string("assets/tiled_image_layers.tmx")
+        .unwrap()
+        .replacen(
+            "<image source=\"tilesheet.png\"",
+            &format!(
+                "<image source=\"tilesheet.png\" trans=\"{:02x}{:02x}{:02x}\"",
+                red, green, blue
+            ),
+            1,
+        );
+    let mut loader = Loader::with_reader(move |path: &std::path::Path| -> std::io::Result<_> {
+        let contents = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tmx") => contents.clone().into_bytes(),
+            _ => std::fs::read(path)?,
+        };
+        Ok(std::io::Cursor::new(contents))
+    });
+    let map = loader
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    let images = loader.load_map_images(&map).unwrap();
+    let image = images[&PathBuf::from("assets/tilesheet.png")].to_rgba8();
+    assert_eq!(image.get_pixel(0, 0)[3], 0);
+}
+
+#[test]
+fn test_parallax_origin() {
+    let contents = std::fs::read_to_string("assets/tiled_parallax.tmx")
+        .unwrap()
+        .replacen(
+            "<map ",
+            "<map parallaxoriginx=\"40\" parallaxoriginy=\"20\" ",
+            1,
+        );
+    let mut loader = Loader::with_reader(move |path: &std::path::Path| -> std::io::Result<_> {
+        let contents = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tmx") => contents.clone().into_bytes(),
+            _ => std::fs::read(path)?,
+        };
+        Ok(std::io::Cursor::new(contents))
+    });
+    let map = loader.load_tmx_map("assets/tiled_parallax.tmx").unwrap();
+    assert_eq!((map.parallax_origin_x, map.parallax_origin_y), (40.0, 20.0));
+
+    // Layers are unscrolled when the camera is at the parallax origin.
+    let layer = map
+        .layers()
+        .find(|layer| &*layer.name == "Background")
+        .unwrap();
+    assert_eq!(layer.to_affine((40.0, 20.0)).apply(0.0, 0.0), (0.0, 0.0));
+    assert_eq!(layer.to_affine((140.0, 20.0)).apply(0.0, 0.0), (50.0, 0.0));
+
+    let mut tmx = Vec::new();
+    map.to_writer(&mut tmx, &WriteOptions::default()).unwrap();
+    let tmx = String::from_utf8(tmx).unwrap();
+    assert!(tmx.contains(r#"parallaxoriginx="40""#));
+    assert!(tmx.contains(r#"parallaxoriginy="20""#));
+}